@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::f64::consts::{PI, E};
+use std::fmt::Debug;
 use std::rc::Rc;
 use std::cell::RefCell;
 
@@ -13,6 +14,7 @@ pub type ContextHashMap = HashMap<String, Token>;
 #[derive(Clone)]
 #[derive(Debug)]
 #[derive(PartialEq)]
+#[allow(unpredictable_function_pointer_comparisons)]
 pub enum Token {
     LeftParenthesis,
     Comma,
@@ -24,6 +26,64 @@ pub enum Token {
     Num(f64),
     Var(Rc<RefCell<Variable>>),
     Func(usize, fn(&[f64]) -> f64),  
+    Closure(usize, ExternalFn),
+}
+
+/// A reference-counted Rust closure that can be called by name from
+/// within an expression. This allows callers to hook models that cannot
+/// be written as closed-form expressions into an equation or system.
+/// 
+/// Unlike the built-in functions in a `ContextHashMap`, the closure 
+/// receives its arguments in the same order that they are written in the 
+/// expression.
+#[derive(Clone)]
+pub struct ExternalFn(RcFnOfSliceToF64);
+
+/// Type alias for `ExternalFn`
+type RcFnOfSliceToF64 = Rc<dyn Fn(&[f64]) -> f64>;
+impl ExternalFn
+{
+    /// Wraps a closure so that it can be stored in a `ContextHashMap`.
+    pub fn new<F>(func: F) -> ExternalFn
+    where
+        F: Fn(&[f64]) -> f64 + 'static
+    {
+        ExternalFn(Rc::new(func))
+    }
+
+    /// Calls the wrapped closure with the given arguments.
+    pub fn call(&self, args: &[f64]) -> f64
+    {
+        (self.0)(args)
+    }
+}
+
+impl Debug for ExternalFn
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExternalFn")
+    }
+}
+
+impl PartialEq for ExternalFn
+{
+    fn eq(&self, other: &Self) -> bool 
+    {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A saved state of a `ContextHashMap`, produced by `ContextLike::snapshot`.
+/// 
+/// Cloning a `ContextHashMap` shares its variables with the clone, so a solve
+/// performed on the clone still changes the values seen by the original. A 
+/// snapshot additionally records the value and domain of every variable so 
+/// that `ContextLike::restore` can undo those changes too.
+#[derive(Clone, Debug)]
+pub struct ContextSnapshot
+{
+    entries: ContextHashMap,
+    variables: Vec<(Rc<RefCell<Variable>>, Variable)>,
 }
 
 fn sin(x:  &[f64]) -> f64 {
@@ -91,6 +151,29 @@ fn conditional(args: &[f64]) -> f64 {
     }
 }
 
+/// Like `conditional`, but blends the two branches with a sigmoid of the difference between 
+/// `a` and `b` instead of switching between them, so that the result is differentiable.
+/// `sharpness` scales the difference, so larger values switch more abruptly. Equality 
+/// comparisons cannot be blended and switch as `conditional` does.
+fn smooth_conditional(args: &[f64]) -> f64 {
+    let a              = args[5];
+    let op             = args[4];
+    let b              = args[3];
+    let if_true_return = args[2];
+    let else_return    = args[1];
+    let sharpness      = args[0];
+
+    let difference = match op.round() as usize {
+    2 | 4 => b - a,
+    3 | 5 => a - b,
+    _ => return conditional(&args[1..]),
+    };
+
+    // The logistic function, written with `tanh` so that it does not overflow
+    let weight = 0.5 + 0.5 * (0.5 * sharpness * difference).tanh();
+    weight * if_true_return + (1.0 - weight) * else_return
+}
+
 /// A module for sealing the `ContextLike` trait.
 pub (crate) mod private
 {
@@ -104,6 +187,10 @@ pub trait ContextLike: private::Sealed
 {
     fn add_func_to_ctx(&mut self, name: &str, func: fn(&[f64]) -> f64, num_args: usize);
 
+    fn add_closure_to_ctx<F>(&mut self, name: &str, func: F, num_args: usize)
+    where
+        F: Fn(&[f64]) -> f64 + 'static;
+
     fn add_const_to_ctx<T>(&mut self, name: &str, val: T)
     where
         T: Into<f64> + Copy;
@@ -115,6 +202,10 @@ pub trait ContextLike: private::Sealed
     fn add_var_with_domain_to_ctx<T>(&mut self, name: &str, val: T, min: T, max: T)
     where
         T: Into<f64> + Copy;
+
+    fn snapshot(&self) -> ContextSnapshot;
+
+    fn restore(&mut self, snapshot: ContextSnapshot);
 } 
 
 /// Provides extra methods for the `ContextHashMap` type.
@@ -124,6 +215,29 @@ impl ContextLike for ContextHashMap
     fn add_func_to_ctx(&mut self, name: &str, func: fn(&[f64]) -> f64, num_args: usize) {
         self.insert(name.to_owned(), Token::Func(num_args, func));
     }
+
+    /// Adds a named Rust closure to the `ContextHashMap`. The closure is 
+    /// given its arguments in the order they appear in the expression.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::shunting::{eval_str_with_context, new_context, ContextLike};
+    /// 
+    /// let offset = 2.0;
+    /// 
+    /// let mut ctx = new_context();
+    /// ctx.add_closure_to_ctx("shifted_ratio", move |args| args[0] / args[1] + offset, 2);
+    /// 
+    /// let ans = eval_str_with_context("shifted_ratio(9, 3)", &ctx).unwrap();
+    /// 
+    /// assert_eq!(ans, 5.0);
+    /// ```
+    fn add_closure_to_ctx<F>(&mut self, name: &str, func: F, num_args: usize)
+    where
+        F: Fn(&[f64]) -> f64 + 'static
+    {
+        self.insert(name.to_owned(), Token::Closure(num_args, ExternalFn::new(func)));
+    }
     
     /// Adds a named constant value to the `ContextHashMap`.
     fn add_const_to_ctx<T>(&mut self, name: &str, val: T) 
@@ -148,6 +262,58 @@ impl ContextLike for ContextHashMap
     {
         self.insert(name.to_owned(), Token::Var(Rc::new(RefCell::new(Variable::new(val, min, max)))));
     }
+
+    /// Saves the current state of the `ContextHashMap`, including the
+    /// values and domains of its variables.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::shunting::{eval_str_with_context, new_context, ContextLike, Token};
+    /// 
+    /// let mut ctx = new_context();
+    /// ctx.add_var_to_ctx("x", 1.0);
+    /// 
+    /// let snapshot = ctx.snapshot();
+    /// 
+    /// // Speculatively change the context...
+    /// ctx.add_const_to_ctx("y", 4.0);
+    /// if let Token::Var(x) = &ctx["x"]
+    /// {
+    ///     x.borrow_mut().set(3.0);
+    /// }
+    /// assert_eq!(eval_str_with_context("x + y", &ctx).unwrap(), 7.0);
+    /// 
+    /// // ...then roll it back
+    /// ctx.restore(snapshot);
+    /// assert!(!ctx.contains_key("y"));
+    /// assert_eq!(eval_str_with_context("x", &ctx).unwrap(), 1.0);
+    /// ```
+    fn snapshot(&self) -> ContextSnapshot
+    {
+        let variables = self.values()
+            .filter_map(|tok| match tok {
+                Token::Var(var) => Some((var.clone(), *var.borrow())),
+                _ => None,
+            })
+            .collect();
+
+        ContextSnapshot 
+        { 
+            entries: self.clone(), 
+            variables,
+        }
+    }
+
+    /// Returns the `ContextHashMap` to the state it was in when 
+    /// the given snapshot was taken. See `snapshot`.
+    fn restore(&mut self, snapshot: ContextSnapshot)
+    {
+        for (var, saved) in snapshot.variables
+        {
+            *var.borrow_mut() = saved;
+        }
+        *self = snapshot.entries;
+    }
 }
 
 /// Initializes a new `ContextHashMap` with basic trig, log, conditional, and absolute value
@@ -168,6 +334,7 @@ impl ContextLike for ContextHashMap
 pub fn new_context() -> ContextHashMap {
     let mut ctx = HashMap::new();
     ctx.add_func_to_ctx("if",     conditional, 5);
+    ctx.add_func_to_ctx("smooth_if", smooth_conditional, 6);
     
     ctx.add_func_to_ctx("sin",    sin,         1);
     ctx.add_func_to_ctx("cos",    cos,         1);
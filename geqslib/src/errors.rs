@@ -67,12 +67,35 @@ pub enum NewtonRaphsonSolverError {
     NegativeMargin,
     ReachedIterationLimit,
     ImproperlyConstrainedSystem,
+    EmptyDomain,
+    NoSignChange,
+    Cancelled,
+    InvalidDamping,
+    InvalidStep,
 }
 impl_err! {
     NewtonRaphsonSolverError,
     NewtonRaphsonSolverError::NegativeMargin, "given margin value must be greater than 0",
     NewtonRaphsonSolverError::ReachedIterationLimit, "reached the maximum number of iterations without finding a solution",
-    NewtonRaphsonSolverError::ImproperlyConstrainedSystem, "number of functions given did not match the number of variables"
+    NewtonRaphsonSolverError::ImproperlyConstrainedSystem, "number of functions given did not match the number of variables",
+    NewtonRaphsonSolverError::EmptyDomain, "found no points on the given domain where the function could be evaluated",
+    NewtonRaphsonSolverError::NoSignChange, "found no sign change on the given domain to bracket a root with",
+    NewtonRaphsonSolverError::Cancelled, "the solver was cancelled before finding a solution",
+    NewtonRaphsonSolverError::InvalidDamping, "given damping must be greater than 0 and no greater than 1",
+    NewtonRaphsonSolverError::InvalidStep, "given finite-difference step must be a finite number greater than 0"
+}
+
+#[derive(Debug)]
+pub enum FixedPointSolverError {
+    InvalidRelaxation,
+    Diverged,
+    NotFixedPointForm,
+}
+impl_err! {
+    FixedPointSolverError,
+    FixedPointSolverError::InvalidRelaxation, "the relaxation factor must be a positive, finite number",
+    FixedPointSolverError::Diverged, "successive substitution diverged. try a smaller relaxation factor",
+    FixedPointSolverError::NotFixedPointForm, "each equation must have a different unknown alone on its left hand side to be solved by successive substitution"
 }
 
 #[derive(Debug)]
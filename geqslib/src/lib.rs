@@ -15,14 +15,18 @@ pub mod shunting;
 /// Contains the `Variable` type for numbers that exist on a user-specified domain.
 pub mod variable;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use context::ContextLike;
 use errors::EquationSolverError;
-use newton::newton_raphson;
-use shunting::{ContextHashMap, compile_to_fn, compile_to_fn_of_hashmap, get_legal_variables_iter, new_context};
+use newton::{brent_root_find, hybrid_root_find, newton_raphson};
+use shunting::{ContextHashMap, compile_to_fn, compile_to_fn_of_hashmap, new_context};
 use system::get_equation_unknowns;
 
+/// The version of this crate, e.g. for recording which 
+/// version of the library produced a set of results.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// An internal function for formatting a single-unknown equation to an expression prior to tokenization 
 pub (in crate) fn compile_equation_to_fn(equation: &str, ctx: &ContextHashMap) -> anyhow::Result<impl Fn(f64) -> anyhow::Result<f64>>
 {
@@ -64,6 +68,18 @@ pub (in crate) fn compile_equation_to_fn_of_hashmap(equation: &str, ctx: &mut Co
     compile_to_fn_of_hashmap(&format!("{} - ({})", sides[0], sides[1]), ctx)
 }
 
+/// The root-finding method that produced the solution to a single-unknown equation.
+/// 
+/// # Variants:
+/// - `Newton` - the root was found by `newton::newton_raphson`
+/// - `Brent` - Newton-Raphson left the domain or failed to converge, so the root was bracketed on the domain and found by `newton::brent_root_find`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootMethod
+{
+    Newton,
+    Brent,
+}
+
 /// Solves an equation given as a string for the SINGLE
 /// unknown that is inferred based on the context and the given equation
 /// string. The given context must contain all known symbols in the 
@@ -71,6 +87,10 @@ pub (in crate) fn compile_equation_to_fn_of_hashmap(equation: &str, ctx: &mut Co
 /// E.g. the context for `"x + sin(y) = 9"` must define a value for `"y"` 
 /// and `"sin"`, but NO value for `"x"` if `"x"` is the variable to be solved for.
 /// 
+/// If the domain \[`min`, `max`\] is finite and Newton-Raphson leaves it or fails 
+/// to converge, the root is found with Brent's method on the domain instead. Use 
+/// `solve_equation_with_fallback` to find out which method was used.
+/// 
 /// # Example
 /// ```
 /// use geqslib::solve_equation_with_context;
@@ -85,13 +105,38 @@ pub (in crate) fn compile_equation_to_fn_of_hashmap(equation: &str, ctx: &mut Co
 /// assert!((soln - 8.0).abs() < 0.001);
 /// ```
 pub fn solve_equation_with_context(equation: &str, ctx: &mut ContextHashMap, guess: f64, min: f64, max: f64, margin: f64, limit: usize) -> anyhow::Result<(String, f64)>
+{
+    let (var, soln, _) = solve_equation_with_fallback(equation, ctx, guess, min, max, margin, limit)?;
+    Ok((var, soln))
+}
+
+/// Solves an equation given as a string for the SINGLE unknown inferred from the
+/// context, exactly like `solve_equation_with_context`, but also reports which 
+/// root-finding method produced the returned root.
+/// 
+/// Newton-Raphson is tried first. If the domain \[`min`, `max`\] is finite and 
+/// Newton-Raphson exits it or fails to converge (e.g. by oscillating), the root 
+/// is bracketed on the domain and found with `newton::brent_root_find`.
+/// 
+/// # Example
+/// ```
+/// use geqslib::{solve_equation_with_fallback, RootMethod};
+/// use geqslib::shunting::new_context;
+/// 
+/// let mut ctx = new_context();
+/// 
+/// // Newton-Raphson finds the positive root, which is outside of the domain
+/// let (var, soln, method) = solve_equation_with_fallback("x^2 = 4", &mut ctx, 1.0, -10.0, 0.0, 0.0001, 100)
+///     .expect("failed to find a solution");
+/// 
+/// assert_eq!(var, "x");
+/// assert!((soln + 2.0).abs() < 0.001);
+/// assert_eq!(method, RootMethod::Brent);
+/// ```
+pub fn solve_equation_with_fallback(equation: &str, ctx: &mut ContextHashMap, guess: f64, min: f64, max: f64, margin: f64, limit: usize) -> anyhow::Result<(String, f64, RootMethod)>
 {
     // Check constraints
-    let unknowns: Vec<&str> = get_legal_variables_iter(equation)
-        .filter(|&x| !ctx.contains_key(x))
-        .collect::<HashSet<&str>>()
-        .into_iter()
-        .collect();
+    let unknowns: Vec<&str> = get_equation_unknowns(equation, ctx).collect();
 
     // Exit early if equation is improperly constrained
     if unknowns.len() != 1
@@ -102,7 +147,54 @@ pub fn solve_equation_with_context(equation: &str, ctx: &mut ContextHashMap, gue
     ctx.add_var_with_domain_to_ctx(unknowns[0], guess, min, max);
     let f = compile_equation_to_fn(equation, ctx)?;
 
-    Ok((unknowns[0].to_owned(), newton_raphson(f, 1.0, margin, limit)?))
+    match newton_raphson(&f, 1.0, margin, limit)
+    {
+        Ok(x) if min <= x && x <= max => Ok((unknowns[0].to_owned(), x, RootMethod::Newton)),
+        _ if min.is_finite() && max.is_finite() => Ok((unknowns[0].to_owned(), brent_root_find(&f, min, max, margin, limit)?, RootMethod::Brent)),
+        newton => Ok((unknowns[0].to_owned(), newton?, RootMethod::Newton)),
+    }
+}
+
+/// Solves an equation given as a string for the SINGLE unknown inferred
+/// from the context, like `solve_equation_with_context`, but searches the 
+/// whole domain \[`min`, `max`\] for a root rather than starting from a 
+/// single guess. See `newton::hybrid_root_find` for details. 
+/// 
+/// This is slower, but works for equations whose derivative is near zero
+/// at typical initial guesses.
+/// 
+/// # Example
+/// ```
+/// use geqslib::solve_equation_robust;
+/// use geqslib::shunting::{new_context, ContextLike};
+/// 
+/// let mut ctx = new_context();
+/// ctx.add_const_to_ctx("rel_rough", 0.0001);
+/// ctx.add_const_to_ctx("re", 100_000.0);
+/// 
+/// // Colebrook equation for the Darcy friction factor
+/// let (var, soln) = solve_equation_robust(
+///     "1 / f^0.5 = -2 * log10(rel_rough / 3.7 + 2.51 / (re * f^0.5))", 
+///     &mut ctx, 0.001, 1.0, 0.000001, 100
+/// ).expect("failed to find a solution");
+/// 
+/// assert_eq!(var, "f");
+/// assert!((soln - 0.0185).abs() < 0.0001);
+/// ```
+pub fn solve_equation_robust(equation: &str, ctx: &mut ContextHashMap, min: f64, max: f64, margin: f64, limit: usize) -> anyhow::Result<(String, f64)>
+{
+    let unknowns: Vec<&str> = get_equation_unknowns(equation, ctx).collect();
+
+    // Exit early if equation is improperly constrained
+    if unknowns.len() != 1
+    {
+        return Err(EquationSolverError::SingleUnknownNotFound.into());
+    }
+    
+    ctx.add_var_with_domain_to_ctx(unknowns[0], min, min, max);
+    let f = compile_equation_to_fn(equation, ctx)?;
+
+    Ok((unknowns[0].to_owned(), hybrid_root_find(f, min, max, margin, limit)?))
 }
 
 /// Solves an equation given as a string for a SINGLE unknown variable.
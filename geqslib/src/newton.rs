@@ -1,9 +1,362 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
+use std::mem::swap;
+use std::ops::ControlFlow;
 use gmatlib::Matrix;
-use crate::errors::NewtonRaphsonSolverError;
+use gmatlib::sparse::SparseMatrix;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use crate::errors::{FixedPointSolverError, NewtonRaphsonSolverError};
 
 const _DX_: f64 = 0.001; 
+const _SCAN_POINTS_: usize = 64;
+#[cfg(feature = "parallel")]
+const _ROWS_PER_TASK_: usize = 32;
+const _ARMIJO_: f64 = 1E-4;
+
+/// How far each iteration of a multivariate Newton-Raphson solver moves along the step it finds.
+/// 
+/// # Variants:
+/// - `None` - takes the full Newton step, which converges fastest near a root but can overshoot 
+///   badly for strongly nonlinear systems
+/// - `Fixed` - takes the given fraction (greater than 0 and at most 1) of every Newton step
+/// - `Backtracking` - halves the step until it reduces the norm of the system vector enough 
+///   (the Armijo condition), but never below `min_step` (greater than 0 and at most 1) of the 
+///   full step. Trial points where the system cannot be evaluated are treated as overshoots
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Damping
+{
+    #[default]
+    None,
+    Fixed(f64),
+    Backtracking { min_step: f64 },
+}
+
+/// The settings of a multivariate Newton-Raphson solver.
+/// 
+/// # Fields:
+/// - `margin` - the largest squared norm of the system vector and norm of the final step that count as a solution
+/// - `limit` - the most iterations to run before giving up
+/// - `damping` - how far each iteration moves along the step it finds (see `Damping`)
+/// - `step` - how far each variable is stepped forward to approximate the jacobian with a forward difference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NewtonOptions
+{
+    pub margin: f64,
+    pub limit: usize,
+    pub damping: Damping,
+    pub step: f64,
+}
+impl NewtonOptions
+{
+    /// Creates the settings of a solver that takes full Newton steps and 
+    /// approximates the jacobian with the same step as the other solvers.
+    pub fn new(margin: f64, limit: usize) -> NewtonOptions
+    {
+        NewtonOptions { margin, limit, damping: Damping::None, step: _DX_ }
+    }
+
+    /// Sets how far each iteration moves along the step it finds.
+    pub fn with_damping(mut self, damping: Damping) -> NewtonOptions
+    {
+        self.damping = damping;
+        self
+    }
+
+    /// Sets how far each variable is stepped forward to approximate the jacobian. Smaller 
+    /// steps approximate it more closely, until rounding error in the system dominates.
+    pub fn with_step(mut self, step: f64) -> NewtonOptions
+    {
+        self.step = step;
+        self
+    }
+
+    /// Makes sure that the margin, damping and step can be used by a solver.
+    fn check(&self) -> anyhow::Result<()>
+    {
+        if self.margin <= 0.0
+        {
+            return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+        }
+
+        if !(self.step > 0.0 && self.step.is_finite())
+        {
+            return Err(NewtonRaphsonSolverError::InvalidStep.into());
+        }
+
+        match self.damping
+        {
+            Damping::Fixed(x) | Damping::Backtracking { min_step: x } if !(x > 0.0 && x <= 1.0) => Err(NewtonRaphsonSolverError::InvalidDamping.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Approximates the jacobian matrix of the system of functions `f` at `guess` 
+/// using a forward difference for each of the variables, in the order given by `vars`.
+fn approximate_jacobian<K, E>(f: &[impl Fn(&HashMap<K, f64>) -> Result<f64, E>], guess: &mut HashMap<K, f64>, vars: &[K]) -> anyhow::Result<Matrix<f64>>
+where 
+    K: Clone + Eq + Hash,
+    anyhow::Error: From<E>,
+{
+    let n = f.len();
+
+    // Build jacobian w/ F(X) values... we will mutate them to F'(X) later
+    let mut elements = vec![];
+    for func in f 
+    {
+        let row = &mut vec![func(guess)?; n];
+        elements.append(row);
+    }
+    let mut jacobian = Matrix::from_vec(n, elements)?; // <- should this be a panic on failure?
+
+    // Correct jacobian values
+    for (j, var) in vars.iter().enumerate()
+    {
+        if let Some(v) = guess.get_mut(var)
+        {
+            *v += _DX_;
+        } 
+        for i in 0..n
+        {
+            // mutate values to partial derivatives
+            jacobian[(i, j)] = (f[i](guess)? - jacobian[(i, j)]) / _DX_;
+        }
+        if let Some(v) = guess.get_mut(var)
+        {
+            *v -= _DX_;
+        } 
+    }
+
+    Ok(jacobian)
+}
+
+/// Approximates the jacobian matrix of the system of functions `f` at `guess` 
+/// using a forward difference for each of the variables, writing it into `jacobian`.
+fn approximate_jacobian_of_slice<E>(f: &[impl Fn(&[f64]) -> Result<f64, E>], guess: &mut [f64], jacobian: &mut Matrix<f64>) -> anyhow::Result<()>
+where 
+    anyhow::Error: From<E>,
+{
+    let n = f.len();
+    jacobian.inplace_reset(n, n);
+
+    // Fill jacobian w/ F(X) values... we will mutate them to F'(X) later
+    for (i, func) in f.iter().enumerate()
+    {
+        let fx = func(guess)?;
+        for j in 0..n
+        {
+            jacobian[(i, j)] = fx;
+        }
+    }
+
+    // Correct jacobian values
+    for j in 0..n
+    {
+        // Restore the variable exactly, rather than subtracting the step, so the guess doesn't drift
+        let x = guess[j];
+        guess[j] = x + _DX_;
+        for i in 0..n
+        {
+            // mutate values to partial derivatives
+            jacobian[(i, j)] = (f[i](guess)? - jacobian[(i, j)]) / _DX_;
+        }
+        guess[j] = x;
+    }
+
+    Ok(())
+}
+
+/// The state of a multivariate solver at the start of one of its iterations, 
+/// as recorded by `multivariate_newton_raphson_slice_traced`.
+/// 
+/// # Fields:
+/// - `iteration` - the (zero-based) iteration number
+/// - `guess` - the value of each variable at the start of the iteration
+/// - `residual` - the value of each function in the system at `guess`
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterationRecord
+{
+    pub iteration: usize,
+    pub guess: Vec<f64>,
+    pub residual: Vec<f64>,
+}
+
+/// Returns the state of a worker evaluating a system, creating it with `init` the first time it is needed.
+#[cfg(feature = "parallel")]
+fn worker_state<'a, S, E>(state: &'a mut Option<S>, init: &impl Fn() -> Result<S, E>) -> Result<&'a mut S, E>
+{
+    if state.is_none()
+    {
+        *state = Some(init()?);
+    }
+    Ok(state.as_mut().expect("the worker's state was just created"))
+}
+
+/// Evaluates every function in the system `f` at `guess`, writing the results into `y`.
+#[cfg(not(feature = "parallel"))]
+fn evaluate_system<S, E>(state: &mut S, _init: &(impl Fn() -> Result<S, E> + Sync), f: &(impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync), guess: &[f64], y: &mut [f64]) -> anyhow::Result<()>
+where
+    anyhow::Error: From<E>,
+{
+    f(state, guess, 0, y)?;
+    Ok(())
+}
+
+/// Evaluates every function in the system `f` at `guess`, writing the results into `y`.
+/// Each task evaluates a block of rows with its own state.
+#[cfg(feature = "parallel")]
+fn evaluate_system<S, E>(_state: &mut S, init: &(impl Fn() -> Result<S, E> + Sync), f: &(impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync), guess: &[f64], y: &mut [f64]) -> anyhow::Result<()>
+where
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    y.par_chunks_mut(_ROWS_PER_TASK_)
+        .enumerate()
+        .try_for_each_init(|| None, |state, (task, rows)| {
+            f(worker_state(state, init)?, guess, task * _ROWS_PER_TASK_, rows)?;
+            Ok(())
+        })
+}
+
+/// Evaluates the system `f` once for each group of variables in `groups`, with every variable 
+/// in the group stepped forward by `dx` from `guess`, writing each evaluation into `values`.
+#[cfg(not(feature = "parallel"))]
+fn evaluate_steps<S, E>(state: &mut S, _init: &(impl Fn() -> Result<S, E> + Sync), f: &(impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync), guess: &mut [f64], groups: &[Vec<usize>], dx: f64, values: &mut [Vec<f64>]) -> anyhow::Result<()>
+where
+    anyhow::Error: From<E>,
+{
+    for (group, vals) in groups.iter().zip(values.iter_mut())
+    {
+        // Restore each variable exactly, rather than subtracting the step, so the guess doesn't drift
+        let original: Vec<f64> = group.iter().map(|&j| guess[j]).collect();
+        for &j in group
+        {
+            guess[j] += dx;
+        }
+        let res = f(state, guess, 0, vals);
+        for (&j, &x) in group.iter().zip(&original)
+        {
+            guess[j] = x;
+        }
+        res?;
+    }
+
+    Ok(())
+}
+
+/// Evaluates the system `f` once for each group of variables in `groups`, with every variable 
+/// in the group stepped forward by `dx` from `guess`, writing each evaluation into `values`.
+/// Each group is evaluated as a separate task, with a state and copy of `guess` per worker.
+#[cfg(feature = "parallel")]
+fn evaluate_steps<S, E>(_state: &mut S, init: &(impl Fn() -> Result<S, E> + Sync), f: &(impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync), guess: &mut [f64], groups: &[Vec<usize>], dx: f64, values: &mut [Vec<f64>]) -> anyhow::Result<()>
+where
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    let guess: &[f64] = guess;
+    groups.par_iter()
+        .zip(values.par_iter_mut())
+        .try_for_each_init(|| (None, guess.to_vec()), |(state, x), (group, vals)| {
+            for &j in group
+            {
+                x[j] = guess[j] + dx;
+            }
+            let res = f(worker_state(state, init)?, x, 0, vals);
+            for &j in group
+            {
+                x[j] = guess[j];
+            }
+            res?;
+            Ok(())
+        })
+}
+
+/// Moves `guess` against the Newton step `deltas` by as much as `damping` allows, where `error` is 
+/// the squared norm of the system vector at `guess`. `trial` and `y` are scratch buffers for the 
+/// points tried by a line search and the system vector at them.
+#[allow(clippy::too_many_arguments)]
+fn take_step<S, E>(state: &mut S, init: &(impl Fn() -> Result<S, E> + Sync), f: &(impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync), damping: Damping, guess: &mut [f64], deltas: &[f64], error: f64, trial: &mut [f64], y: &mut [f64]) -> anyhow::Result<()>
+where
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    let t = match damping
+    {
+        Damping::None => 1.0,
+        Damping::Fixed(t) => t,
+        Damping::Backtracking { min_step } =>
+        {
+            let mut t = 1.0;
+            while t > min_step
+            {
+                for ((x, g), d) in trial.iter_mut().zip(guess.iter()).zip(deltas)
+                {
+                    *x = g - t * d;
+                }
+                
+                // NaN fails the comparison, so points that can't be evaluated are backtracked from too
+                let trial_error = match evaluate_system(state, init, f, trial, y)
+                {
+                    Ok(_) => y.iter().map(|v| v.powi(2)).sum::<f64>(),
+                    Err(_) => f64::NAN,
+                };
+                if trial_error <= (1.0 - 2.0 * _ARMIJO_ * t) * error
+                {
+                    break;
+                }
+                t *= 0.5;
+            }
+            t.max(min_step)
+        },
+    };
+
+    for (val, delta) in guess.iter_mut().zip(deltas)
+    {
+        *val -= t * delta;
+    }
+    Ok(())
+}
+
+/// Groups the variables of a system so that no two variables in a group appear in the same row, 
+/// given the rows that each variable appears in. Stepping every variable in a group at once changes 
+/// each row through at most one of them, so one evaluation per group is enough to find the jacobian.
+fn group_independent_variables(pattern: &[Vec<usize>], rows: usize) -> Vec<Vec<usize>>
+{
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut used_rows: Vec<Vec<bool>> = vec![];
+    for (j, col) in pattern.iter().enumerate()
+    {
+        let g = match used_rows.iter().position(|used| col.iter().all(|&i| !used[i]))
+        {
+            Some(g) => g,
+            None => 
+            {
+                groups.push(vec![]);
+                used_rows.push(vec![false; rows]);
+                groups.len() - 1
+            },
+        };
+
+        groups[g].push(j);
+        for &i in col
+        {
+            used_rows[g][i] = true;
+        }
+    }
+    groups
+}
+
+/// Checks that `vars` lists each of the `n` keys in `guess` exactly once.
+fn is_ordering_of<K>(vars: &[K], guess: &HashMap<K, f64>, n: usize) -> bool
+where
+    K: Eq + Hash,
+{
+    guess.len() == n && 
+    vars.len() == n && 
+    vars.iter().all(|v| guess.contains_key(v)) &&
+    vars.iter().collect::<HashSet<&K>>().len() == n
+}
 
 /// A basic implementation of the 1-D newton-raphson method.
 /// This function allows the caller to choose an initial guess value,
@@ -59,6 +412,299 @@ where anyhow::Error: From<E>
     newton_raphson(f, next_guess, margin, limit - 1)
 }
 
+/// Returns the points at which `hybrid_root_find` samples a function on 
+/// \[`min`, `max`\]. Finite domains are sampled evenly, while infinite 
+/// domains are sampled at logarithmically-spaced distances from their 
+/// finite bound (or from `0.0` if neither bound is finite).
+fn scan_points(min: f64, max: f64) -> Vec<f64>
+{
+    if min.is_finite() && max.is_finite()
+    {
+        let step = (max - min) / _SCAN_POINTS_ as f64;
+        return (0..=_SCAN_POINTS_).map(|i| min + step * i as f64).collect();
+    }
+
+    let anchor = if min.is_finite() { min } else if max.is_finite() { max } else { 0.0 };
+    let mut points = vec![anchor];
+    for k in -6..=9
+    {
+        let offset = 10f64.powi(k);
+        if max == f64::INFINITY
+        {
+            points.push(anchor + offset);
+        }
+        if min == f64::NEG_INFINITY
+        {
+            points.push(anchor - offset);
+        }
+    }
+
+    points.sort_by(f64::total_cmp);
+    points
+}
+
+/// A globally-convergent root finder for 1-D functions on the domain 
+/// \[`min`, `max`\]. This is useful for functions whose derivative is 
+/// close to zero near a typical initial guess, where `newton_raphson`
+/// may diverge or stall.
+/// 
+/// The function is first sampled across the domain. If it changes sign
+/// between two samples, the root in that interval is polished with 
+/// Newton steps, falling back to bisection whenever a Newton step would
+/// leave the interval. If no sign change is found, `newton_raphson` is 
+/// started from the sample closest to zero. Points where `f` fails or 
+/// is not finite are skipped while sampling.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use geqslib::newton::hybrid_root_find;
+/// 
+/// // Flat near the usual guess of 1.0, where newton_raphson would overshoot
+/// fn f(x: f64) -> Result<f64, Error>
+/// {
+///     Ok((x - 3.0).atan())
+/// }
+/// 
+/// let x = hybrid_root_find(f, -100.0, 100.0, 0.0001, 100).unwrap();
+/// 
+/// assert!((x - 3.0).abs() < 0.0001);
+/// ```
+pub fn hybrid_root_find<E>(f: impl Fn(f64) -> Result<f64, E>, min: f64, max: f64, margin: f64, limit: usize) -> anyhow::Result<f64>
+where anyhow::Error: From<E>
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
+        return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+    }
+
+    if min.is_nan() || max.is_nan() || min >= max
+    {
+        return Err(NewtonRaphsonSolverError::EmptyDomain.into());
+    }
+
+    let samples: Vec<(f64, f64)> = scan_points(min, max)
+        .into_iter()
+        .filter_map(|x| match f(x) {
+            Ok(y) if y.is_finite() => Some((x, y)),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(&(x, _)) = samples.iter().find(|(_, y)| *y == 0.0)
+    {
+        return Ok(x);
+    }
+
+    let bracket = samples.windows(2)
+        .find(|w| w[0].1.signum() != w[1].1.signum());
+
+    match bracket
+    {
+        Some(w) => safe_newton(f, w[0], w[1], margin, limit),
+        None => {
+            let closest = samples.iter()
+                .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .ok_or(NewtonRaphsonSolverError::EmptyDomain)?;
+
+            newton_raphson(f, closest.0, margin, limit)
+        }
+    }
+}
+
+/// Finds the root of `f` between `lo` and `hi`, which are given as
+/// `(x, f(x))` pairs where `f(x)` has opposite signs. Newton steps
+/// are taken when they stay within the bracket, and bisection 
+/// steps are taken otherwise.
+fn safe_newton<E>(f: impl Fn(f64) -> Result<f64, E>, lo: (f64, f64), hi: (f64, f64), margin: f64, limit: usize) -> anyhow::Result<f64>
+where anyhow::Error: From<E>
+{
+    let (mut a, mut fa) = lo;
+    let mut b = hi.0;
+    let mut x = 0.5 * (a + b);
+
+    for _ in 0..limit
+    {
+        let y = f(x)?;
+        // Use a step relative to x so that roots close to zero are still resolved
+        let h = _DX_ * _DX_ * x.abs().max(_DX_);
+        let y_prime = (f(x + h)? - y) / h;
+
+        // Shrink the bracket around the root
+        if y.signum() == fa.signum()
+        {
+            a = x;
+            fa = y;
+        }
+        else
+        {
+            b = x;
+        }
+
+        let newton_x = x - y / y_prime;
+        let next_x = if newton_x.is_finite() && newton_x > a.min(b) && newton_x < a.max(b)
+        {
+            newton_x
+        }
+        else
+        {
+            0.5 * (a + b)
+        };
+
+        if y.abs() <= margin && (next_x - x).abs() <= margin
+        {
+            return Ok(next_x);
+        }
+        x = next_x;
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+
+/// A bracketing root finder for 1-D functions on the domain \[`min`, `max`\],
+/// using Brent's method. Unlike `newton_raphson`, every step stays within the
+/// domain, so this cannot diverge or oscillate once a root is bracketed.
+/// 
+/// The function is first sampled across the domain like in `hybrid_root_find`, 
+/// and the first interval where it changes sign is narrowed with inverse quadratic 
+/// interpolation and secant steps, falling back to bisection whenever those would
+/// converge too slowly. If the function does not change sign on the domain, 
+/// `NewtonRaphsonSolverError::NoSignChange` is returned.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use geqslib::newton::brent_root_find;
+/// 
+/// fn f(x: f64) -> Result<f64, Error>
+/// {
+///     Ok(x * x - 4.0)
+/// }
+/// 
+/// // Only the negative root lies on the domain
+/// let x = brent_root_find(f, -10.0, 0.0, 0.0001, 100).unwrap();
+/// 
+/// assert!((x + 2.0).abs() < 0.0001);
+/// ```
+pub fn brent_root_find<E>(f: impl Fn(f64) -> Result<f64, E>, min: f64, max: f64, margin: f64, limit: usize) -> anyhow::Result<f64>
+where anyhow::Error: From<E>
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
+        return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+    }
+
+    if min.is_nan() || max.is_nan() || min >= max
+    {
+        return Err(NewtonRaphsonSolverError::EmptyDomain.into());
+    }
+
+    let samples: Vec<(f64, f64)> = scan_points(min, max)
+        .into_iter()
+        .filter_map(|x| match f(x) {
+            Ok(y) if y.is_finite() => Some((x, y)),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(&(x, _)) = samples.iter().find(|(_, y)| *y == 0.0)
+    {
+        return Ok(x);
+    }
+
+    let (mut a, mut fa, mut b, mut fb) = match samples.windows(2).find(|w| w[0].1.signum() != w[1].1.signum())
+    {
+        Some(w) => (w[0].0, w[0].1, w[1].0, w[1].1),
+        None => return Err(NewtonRaphsonSolverError::NoSignChange.into()),
+    };
+
+    // `b` is the best estimate of the root and `c` is on the other side of it
+    let (mut c, mut fc) = (a, fa);
+    let mut d = b - a;
+    let mut e = d;
+
+    for _ in 0..limit
+    {
+        if fb.signum() == fc.signum()
+        {
+            c = a;
+            fc = fa;
+            d = b - a;
+            e = d;
+        }
+
+        if fc.abs() < fb.abs()
+        {
+            a = b;
+            b = c;
+            c = a;
+            fa = fb;
+            fb = fc;
+            fc = fa;
+        }
+
+        let tol = 2.0 * f64::EPSILON * b.abs() + 0.5 * margin;
+        let m = 0.5 * (c - b);
+
+        // Check if we are sufficiently close to the solution in both the y AND x directions
+        if fb.abs() <= margin && (m.abs() <= tol || fb == 0.0)
+        {
+            return Ok(b);
+        }
+
+        if e.abs() >= tol && fa.abs() > fb.abs()
+        {
+            // Interpolate, using a secant step if only two points are known
+            let s = fb / fa;
+            let (mut p, mut q) = if a == c
+            {
+                (2.0 * m * s, 1.0 - s)
+            }
+            else
+            {
+                let q = fa / fc;
+                let r = fb / fc;
+                (s * (2.0 * m * q * (q - r) - (b - a) * (r - 1.0)), (q - 1.0) * (r - 1.0) * (s - 1.0))
+            };
+
+            if p > 0.0
+            {
+                q = -q;
+            }
+            else
+            {
+                p = -p;
+            }
+
+            // Only accept the interpolation if it stays in the bracket and converges quickly enough
+            if 2.0 * p < (3.0 * m * q - (tol * q).abs()).min((e * q).abs())
+            {
+                e = d;
+                d = p / q;
+            }
+            else
+            {
+                d = m;
+                e = m;
+            }
+        }
+        else
+        {
+            d = m;
+            e = m;
+        }
+
+        a = b;
+        fa = fb;
+        b += if d.abs() > tol { d } else { tol.copysign(m) };
+        fb = f(b)?;
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+
 /// A basic implementation of the Newton-Raphson method for multivariate
 /// systems. This function allows the caller to specify an initial guess 
 /// vector as a `HashMap<String, f64>`, a margin of error, and a maximum 
@@ -99,14 +745,65 @@ where anyhow::Error: From<E>
 /// assert!(soln["x"] - 6.5 < 0.0001);
 /// assert!(soln["y"] - 2.5 < 0.0001);
 /// ```
+/// 
+/// Note that the columns of the jacobian follow the iteration order of 
+/// `guess`, which varies between runs. Use `multivariate_newton_raphson_ordered`
+/// when repeated solves must be bit-for-bit reproducible.
 pub fn multivariate_newton_raphson<K, E>(f: Vec<impl Fn(&HashMap<K, f64>) -> Result<f64, E>>, guess: &mut HashMap<K, f64>, margin: f64, limit: usize) -> anyhow::Result<&mut HashMap<K, f64>>
 where 
     K: Clone + Eq + Hash,
     anyhow::Error: From<E>,
 {
-    // Catch illegal margin of error
-    if margin <= 0.0
-    {
+    // Copy keys to iterate over hashmap
+    let vars = Vec::from_iter(
+        guess.keys().map(|x| x.to_owned())
+    );
+
+    multivariate_newton_raphson_ordered(f, guess, &vars, margin, limit)
+}
+
+/// Identical to `multivariate_newton_raphson`, but the variables are 
+/// perturbed and updated in the order given by `vars` rather than the 
+/// iteration order of `guess`. Given the same inputs, this function 
+/// always produces exactly the same result.
+/// 
+/// `vars` must contain each key in `guess` exactly once.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use std::collections::HashMap;
+/// use geqslib::newton::multivariate_newton_raphson_ordered;
+/// 
+/// fn f1(x: &HashMap<&str, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["x"] * x["y"] - 6.0)
+/// }
+/// 
+/// fn f2(x: &HashMap<&str, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["x"] - x["y"] - 1.0)
+/// }
+/// 
+/// let solve = || {
+///     let mut guess = HashMap::from([("x", 2.0), ("y", 1.0)]);
+///     multivariate_newton_raphson_ordered(vec![f1, f2], &mut guess, &["x", "y"], 0.0001, 50)
+///         .unwrap()
+///         .clone()
+/// };
+/// 
+/// let soln = solve();
+/// assert!((soln["x"] - 3.0).abs() < 0.001);
+/// assert_eq!(soln, solve());
+/// ```
+pub fn multivariate_newton_raphson_ordered<'a, K, E>(f: Vec<impl Fn(&HashMap<K, f64>) -> Result<f64, E>>, guess: &'a mut HashMap<K, f64>, vars: &[K], margin: f64, limit: usize) -> anyhow::Result<&'a mut HashMap<K, f64>>
+where 
+    K: Clone + Eq + Hash,
+    anyhow::Error: From<E>,
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
         return Err(NewtonRaphsonSolverError::NegativeMargin.into());
     }
 
@@ -118,43 +815,12 @@ where
 
     // Establish system size and ensure number of functions == number of vars
     let n = f.len();
-    if guess.len() != n
+    if !is_ordering_of(vars, guess, n)
     {
         return Err(NewtonRaphsonSolverError::ImproperlyConstrainedSystem.into());
     }
 
-    // Build jacobian w/ F(X) values... we will mutate them to F'(X) later
-    let mut elements = vec![];
-    for func in &f 
-    {
-        let row = &mut vec![func(guess)?; n];
-        elements.append(row);
-    }
-    let mut jacobian = Matrix::from_vec(n, elements)?; // <- should this be a panic on failure?
-    
-    // Copy keys to iterate over hashmap
-    let vars = Vec::from_iter(
-        guess.keys().map(|x| x.to_owned())
-    );
-
-    // Correct jacobian values and invert
-    for (j, var) in vars.iter().enumerate()
-    {
-        if let Some(v) = guess.get_mut(var)
-        {
-            *v += _DX_;
-        } 
-        for i in 0..n
-        {
-            // mutate values to partial derivatives
-            jacobian[(i, j)] = (f[i](guess)? - jacobian[(i, j)]) / _DX_;
-        }
-        if let Some(v) = guess.get_mut(var)
-        {
-            *v -= _DX_;
-        } 
-    }
-
+    let mut jacobian = approximate_jacobian(&f, guess, vars)?;
     jacobian.try_inplace_invert()?;
 
     // Calculate current error
@@ -189,5 +855,753 @@ where
     }
 
     // COMPUTER, ENHANCE!
-    multivariate_newton_raphson(f, guess, margin, limit - 1)
-}
\ No newline at end of file
+    multivariate_newton_raphson_ordered(f, guess, vars, margin, limit - 1)
+}
+
+/// Identical to `multivariate_newton_raphson_ordered`, but each variable is identified by
+/// its index in `guess` rather than by a key. The functions in `f` are given the current
+/// guess as a slice, so evaluating them requires no hashing, cloning or allocation. 
+/// 
+/// This is intended for large systems whose functions are evaluated many times. Map 
+/// each variable to an index once, before building `f`, and read variables from the 
+/// slice by that index.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use geqslib::newton::multivariate_newton_raphson_slice;
+/// 
+/// // Variables: x = [0], y = [1]
+/// fn f1(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] * x[1] - 6.0)
+/// }
+/// 
+/// fn f2(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] - x[1] - 1.0)
+/// }
+/// 
+/// let mut guess = [2.0, 1.0];
+/// multivariate_newton_raphson_slice(vec![f1, f2], &mut guess, 0.0001, 50).unwrap();
+/// 
+/// assert!((guess[0] - 3.0).abs() < 0.001);
+/// assert!((guess[1] - 2.0).abs() < 0.001);
+/// ```
+pub fn multivariate_newton_raphson_slice<E>(f: Vec<impl Fn(&[f64]) -> Result<f64, E>>, guess: &mut [f64], margin: f64, limit: usize) -> anyhow::Result<&mut [f64]>
+where 
+    anyhow::Error: From<E>,
+{
+    multivariate_newton_raphson_slice_with_monitor(f, guess, margin, limit, |_, _| ControlFlow::Continue(()))
+}
+
+/// Identical to `multivariate_newton_raphson_slice`, but calls `monitor` at every iteration
+/// with the (zero-based) iteration number and the norm of the system vector at the start of
+/// that iteration. The last call is made with the residual that the solution converged to.
+/// 
+/// Returning `ControlFlow::Break` from `monitor` stops the solver with a `Cancelled` error, 
+/// e.g. when a user gives up on a slow solve.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use std::ops::ControlFlow;
+/// use geqslib::newton::multivariate_newton_raphson_slice_with_monitor;
+/// 
+/// fn f1(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] * x[1] - 6.0)
+/// }
+/// 
+/// fn f2(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] - x[1] - 1.0)
+/// }
+/// 
+/// let mut history = vec![];
+/// let mut guess = [2.0, 1.0];
+/// multivariate_newton_raphson_slice_with_monitor(vec![f1, f2], &mut guess, 0.0001, 50, |_, residual| {
+///     history.push(residual);
+///     ControlFlow::Continue(())
+/// }).unwrap();
+/// 
+/// assert!((history[0] - 4.0).abs() < 1E-9);
+/// assert!(history.last().unwrap() < &0.0001);
+/// 
+/// // Give up after the first iteration
+/// let mut guess = [2.0, 1.0];
+/// assert!(multivariate_newton_raphson_slice_with_monitor(vec![f1, f2], &mut guess, 0.0001, 50, |i, _| match i
+/// {
+///     0 => ControlFlow::Continue(()),
+///     _ => ControlFlow::Break(()),
+/// }).is_err());
+/// ```
+pub fn multivariate_newton_raphson_slice_with_monitor<E>(f: Vec<impl Fn(&[f64]) -> Result<f64, E>>, guess: &mut [f64], margin: f64, limit: usize, monitor: impl FnMut(usize, f64) -> ControlFlow<()>) -> anyhow::Result<&mut [f64]>
+where 
+    anyhow::Error: From<E>,
+{
+    slice_solver(f, guess, margin, limit, monitor, None)
+}
+
+/// Identical to `multivariate_newton_raphson_slice`, but records the guess and the value of
+/// every function in the system at the start of each iteration into `trace`, so that a solve
+/// can be inspected (e.g. plotted) after the fact. Any records already in `trace` are removed 
+/// first. The trace is kept whether or not the solver converges, so a failed solve can be 
+/// replayed without running it again.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use geqslib::newton::multivariate_newton_raphson_slice_traced;
+/// 
+/// fn f1(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] * x[1] - 6.0)
+/// }
+/// 
+/// fn f2(x: &[f64]) -> Result<f64, Error>
+/// {
+///     Ok(x[0] - x[1] - 1.0)
+/// }
+/// 
+/// let mut trace = vec![];
+/// let mut guess = [2.0, 1.0];
+/// multivariate_newton_raphson_slice_traced(vec![f1, f2], &mut guess, 0.0001, 50, &mut trace).unwrap();
+/// 
+/// assert_eq!(trace[0].guess, vec![2.0, 1.0]);
+/// assert_eq!(trace[0].residual, vec![-4.0, 0.0]);
+/// 
+/// // The last record is of the guess that the solver converged on
+/// assert_eq!(trace.last().unwrap().guess, guess.to_vec());
+/// ```
+pub fn multivariate_newton_raphson_slice_traced<'a, E>(f: Vec<impl Fn(&[f64]) -> Result<f64, E>>, guess: &'a mut [f64], margin: f64, limit: usize, trace: &mut Vec<IterationRecord>) -> anyhow::Result<&'a mut [f64]>
+where 
+    anyhow::Error: From<E>,
+{
+    trace.clear();
+    slice_solver(f, guess, margin, limit, |_, _| ControlFlow::Continue(()), Some(trace))
+}
+
+/// The newton-raphson loop behind the `multivariate_newton_raphson_slice` functions,
+/// which records each iteration into `trace` if one is given.
+fn slice_solver<'a, E>(f: Vec<impl Fn(&[f64]) -> Result<f64, E>>, guess: &'a mut [f64], margin: f64, limit: usize, mut monitor: impl FnMut(usize, f64) -> ControlFlow<()>, mut trace: Option<&mut Vec<IterationRecord>>) -> anyhow::Result<&'a mut [f64]>
+where 
+    anyhow::Error: From<E>,
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
+        return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+    }
+
+    // Ensure number of functions == number of vars
+    let n = f.len();
+    if guess.len() != n
+    {
+        return Err(NewtonRaphsonSolverError::ImproperlyConstrainedSystem.into());
+    }
+
+    // Buffers reused between iterations to avoid reallocating them
+    let mut jacobian = Matrix::new(n, n);
+    let mut y = Matrix::new(n, 1);
+    let mut deltas = Matrix::new(n, 1);
+
+    for iteration in 0..limit
+    {
+        approximate_jacobian_of_slice(&f, guess, &mut jacobian)?;
+        jacobian.try_inplace_invert()?;
+
+        // Calculate current error
+        for i in 0..n
+        {
+            y[(i, 0)] = f[i](guess)?;
+        }
+        let error = y.iter()
+            .map(|v| v.powi(2))
+            .sum::<f64>();
+        if let Some(trace) = trace.as_mut()
+        {
+            trace.push(IterationRecord { iteration, guess: guess.to_vec(), residual: y.iter().copied().collect() });
+        }
+        if monitor(iteration, error.sqrt()).is_break()
+        {
+            return Err(NewtonRaphsonSolverError::Cancelled.into());
+        }
+
+        // Calculate change vector and its magnitude
+        jacobian.multiply_matrix_into(&y, &mut deltas)?;
+        let change = deltas.iter()
+            .map(|d| d.powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        if error <= margin && change <= margin
+        {
+            return Ok(guess);
+        }
+
+        // Build next guess vector
+        for (i, val) in guess.iter_mut().enumerate()
+        {
+            *val -= deltas[(i, 0)];
+        }
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+
+/// Identical to `multivariate_newton_raphson_slice_with_monitor`, but the system is given as a 
+/// single function that evaluates a block of its rows at once, rather than one function per row.
+/// This suits systems whose rows share most of their work, e.g. a network model that must update
+/// every node before any one node's balance can be calculated.
+/// 
+/// `f(state, guess, first, rows)` writes the values of rows `first..first + rows.len()` of the 
+/// system at `guess` into `rows`. Any scratch data that `f` needs is kept in a `state` created 
+/// by `init`, so `f` itself is never mutated. The system has one row per variable in `guess`.
+/// 
+/// `monitor` is called at every iteration with the (zero-based) iteration number, the norm of
+/// the system vector at the start of that iteration and the guess it was evaluated at.
+/// 
+/// # Parallel evaluation
+/// With the `parallel` feature, the rows of the system and the columns of the jacobian are 
+/// evaluated on rayon's thread pool. Each worker creates its own state with `init`, so the 
+/// state never has to be shared between threads. The solution is identical either way.
+/// 
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+/// use geqslib::newton::multivariate_newton_raphson_system;
+/// 
+/// // Each row depends on its neighbours, e.g. the nodes of a chain of springs, with 
+/// // a scratch buffer for the displacement between each pair of neighbouring nodes
+/// let n = 50;
+/// let init = || Ok::<_, std::io::Error>(vec![0.0; n + 1]);
+/// let f = |stretch: &mut Vec<f64>, x: &[f64], first: usize, rows: &mut [f64]| {
+///     for i in 0..=n
+///     {
+///         let left = if i == 0 { 0.0 } else { x[i - 1] };
+///         let right = if i == n { 1.0 } else { x[i] };
+///         stretch[i] = right - left;
+///     }
+///     for (k, row) in rows.iter_mut().enumerate()
+///     {
+///         *row = stretch[first + k + 1] - stretch[first + k];
+///     }
+///     Ok(())
+/// };
+/// 
+/// let mut guess = vec![0.0; n];
+/// multivariate_newton_raphson_system(init, f, &mut guess, 1E-9, 10, |_, _, _| ControlFlow::Continue(())).unwrap();
+/// 
+/// // The springs stretch evenly from 0.0 to 1.0
+/// for (i, x) in guess.iter().enumerate()
+/// {
+///     assert!((x - (i + 1) as f64 / (n + 1) as f64).abs() < 1E-9);
+/// }
+/// ```
+pub fn multivariate_newton_raphson_system<S, E>(
+    init: impl Fn() -> Result<S, E> + Sync + Send, 
+    f: impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync + Send, 
+    guess: &mut [f64], 
+    margin: f64, 
+    limit: usize, 
+    monitor: impl FnMut(usize, f64, &[f64]) -> ControlFlow<()>,
+) -> anyhow::Result<&mut [f64]>
+where 
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    multivariate_newton_raphson_system_with_options(init, f, guess, &NewtonOptions::new(margin, limit), monitor)
+}
+
+/// Identical to `multivariate_newton_raphson_system`, but takes its margin, iteration limit 
+/// and damping from `options`. Damping trades the speed of full Newton steps for robustness 
+/// on systems whose steps overshoot, e.g. ones with exponential or fourth-power terms.
+/// 
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+/// use geqslib::newton::{multivariate_newton_raphson_system_with_options, Damping, NewtonOptions};
+/// 
+/// // Full Newton steps overshoot this root from far away, and never reach it
+/// let init = || Ok::<_, std::io::Error>(());
+/// let f = |_: &mut (), x: &[f64], _: usize, rows: &mut [f64]| {
+///     rows[0] = x[0].atan();
+///     Ok(())
+/// };
+/// 
+/// let mut guess = vec![3.0];
+/// let options = NewtonOptions::new(1E-9, 100);
+/// assert!(multivariate_newton_raphson_system_with_options(init, f, &mut guess, &options, |_, _, _| ControlFlow::Continue(())).is_err());
+/// 
+/// let mut guess = vec![3.0];
+/// let options = options.with_damping(Damping::Backtracking { min_step: 1E-3 });
+/// multivariate_newton_raphson_system_with_options(init, f, &mut guess, &options, |_, _, _| ControlFlow::Continue(())).unwrap();
+/// assert!(guess[0].abs() < 1E-6);
+/// ```
+pub fn multivariate_newton_raphson_system_with_options<'a, S, E>(
+    init: impl Fn() -> Result<S, E> + Sync + Send, 
+    f: impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync + Send, 
+    guess: &'a mut [f64], 
+    options: &NewtonOptions, 
+    mut monitor: impl FnMut(usize, f64, &[f64]) -> ControlFlow<()>,
+) -> anyhow::Result<&'a mut [f64]>
+where 
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    options.check()?;
+    let margin = options.margin;
+
+    // Buffers reused between iterations to avoid reallocating them
+    let n = guess.len();
+    let columns: Vec<Vec<usize>> = (0..n).map(|j| vec![j]).collect();
+    let mut state = init()?;
+    let mut jacobian = Matrix::new(n, n);
+    let mut steps = vec![vec![0.0; n]; n];
+    let mut y = vec![0.0; n];
+    let mut y_col = Matrix::new(n, 1);
+    let mut deltas = Matrix::new(n, 1);
+    let mut step = vec![0.0; n];
+    let mut trial = vec![0.0; n];
+
+    for iteration in 0..options.limit
+    {
+        // Calculate current error
+        evaluate_system(&mut state, &init, &f, guess, &mut y)?;
+        let error = y.iter()
+            .map(|v| v.powi(2))
+            .sum::<f64>();
+        if monitor(iteration, error.sqrt(), guess).is_break()
+        {
+            return Err(NewtonRaphsonSolverError::Cancelled.into());
+        }
+
+        // Approximate the jacobian with a forward difference for each variable
+        evaluate_steps(&mut state, &init, &f, guess, &columns, options.step, &mut steps)?;
+        for (j, step) in steps.iter().enumerate()
+        {
+            for i in 0..n
+            {
+                jacobian[(i, j)] = (step[i] - y[i]) / options.step;
+            }
+        }
+        jacobian.try_inplace_invert()?;
+
+        // Calculate change vector and its magnitude
+        for (i, &v) in y.iter().enumerate()
+        {
+            y_col[(i, 0)] = v;
+        }
+        jacobian.multiply_matrix_into(&y_col, &mut deltas)?;
+        let change = deltas.iter()
+            .map(|d| d.powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        if error <= margin && change <= margin
+        {
+            return Ok(guess);
+        }
+
+        // Build next guess vector
+        for (i, d) in step.iter_mut().enumerate()
+        {
+            *d = deltas[(i, 0)];
+        }
+        take_step(&mut state, &init, &f, options.damping, guess, &step, error, &mut trial, &mut y)?;
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+
+/// Identical to `multivariate_newton_raphson_system`, but for systems where each row only depends
+/// on a few of the variables, e.g. the flux balances of a network where each node is only coupled to
+/// its neighbours. `pattern[j]` lists the rows of the system that variable `j` appears in, and every 
+/// other element of the jacobian is taken to be zero.
+/// 
+/// Variables that never appear in the same row are stepped together when approximating the jacobian,
+/// so a network whose nodes have a handful of neighbours needs a handful of evaluations of the system 
+/// per iteration rather than one per variable. The jacobian is stored as a `SparseMatrix` and each 
+/// step is found by sparse elimination rather than by inverting it, so systems with thousands of 
+/// variables can be solved.
+/// 
+/// # Example
+/// ```
+/// use std::ops::ControlFlow;
+/// use geqslib::newton::multivariate_newton_raphson_sparse;
+/// 
+/// // A chain of 2000 springs, where each node only depends on its neighbours
+/// let n = 2000;
+/// let init = || Ok::<_, std::io::Error>(());
+/// let f = |_: &mut (), x: &[f64], first: usize, rows: &mut [f64]| {
+///     for (k, row) in rows.iter_mut().enumerate()
+///     {
+///         let i = first + k;
+///         let left = if i == 0 { 0.0 } else { x[i - 1] };
+///         let right = if i == n - 1 { 1.0 } else { x[i + 1] };
+///         *row = right - 2.0 * x[i] + left;
+///     }
+///     Ok(())
+/// };
+/// let pattern: Vec<Vec<usize>> = (0..n)
+///     .map(|j| (j.saturating_sub(1)..(j + 2).min(n)).collect())
+///     .collect();
+/// 
+/// let mut guess = vec![0.0; n];
+/// multivariate_newton_raphson_sparse(init, f, &pattern, &mut guess, 1E-9, 10, |_, _, _| ControlFlow::Continue(())).unwrap();
+/// 
+/// for (i, x) in guess.iter().enumerate()
+/// {
+///     assert!((x - (i + 1) as f64 / (n + 1) as f64).abs() < 1E-6);
+/// }
+/// ```
+pub fn multivariate_newton_raphson_sparse<'a, S, E>(
+    init: impl Fn() -> Result<S, E> + Sync + Send, 
+    f: impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync + Send, 
+    pattern: &[Vec<usize>],
+    guess: &'a mut [f64], 
+    margin: f64, 
+    limit: usize, 
+    monitor: impl FnMut(usize, f64, &[f64]) -> ControlFlow<()>,
+) -> anyhow::Result<&'a mut [f64]>
+where 
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    multivariate_newton_raphson_sparse_with_options(init, f, pattern, guess, &NewtonOptions::new(margin, limit), monitor)
+}
+
+/// Identical to `multivariate_newton_raphson_sparse`, but takes its margin, iteration limit 
+/// and damping from `options` (see `multivariate_newton_raphson_system_with_options`).
+pub fn multivariate_newton_raphson_sparse_with_options<'a, S, E>(
+    init: impl Fn() -> Result<S, E> + Sync + Send, 
+    f: impl Fn(&mut S, &[f64], usize, &mut [f64]) -> Result<(), E> + Sync + Send, 
+    pattern: &[Vec<usize>],
+    guess: &'a mut [f64], 
+    options: &NewtonOptions, 
+    mut monitor: impl FnMut(usize, f64, &[f64]) -> ControlFlow<()>,
+) -> anyhow::Result<&'a mut [f64]>
+where 
+    anyhow::Error: From<E>,
+    E: Send,
+{
+    options.check()?;
+    let margin = options.margin;
+
+    // Ensure that the pattern describes every variable in the system
+    let n = guess.len();
+    if pattern.len() != n || pattern.iter().flatten().any(|&i| i >= n)
+    {
+        return Err(NewtonRaphsonSolverError::ImproperlyConstrainedSystem.into());
+    }
+
+    // Buffers reused between iterations to avoid reallocating them
+    let groups = group_independent_variables(pattern, n);
+    let mut state = init()?;
+    let mut steps = vec![vec![0.0; n]; groups.len()];
+    let mut y = vec![0.0; n];
+    let mut trial = vec![0.0; n];
+
+    for iteration in 0..options.limit
+    {
+        // Calculate current error
+        evaluate_system(&mut state, &init, &f, guess, &mut y)?;
+        let error = y.iter()
+            .map(|v| v.powi(2))
+            .sum::<f64>();
+        if monitor(iteration, error.sqrt(), guess).is_break()
+        {
+            return Err(NewtonRaphsonSolverError::Cancelled.into());
+        }
+
+        // Each row changes through at most one variable in each group
+        evaluate_steps(&mut state, &init, &f, guess, &groups, options.step, &mut steps)?;
+        let mut jacobian = SparseMatrix::new(n, n);
+        for (group, step) in groups.iter().zip(&steps)
+        {
+            for &j in group
+            {
+                for &i in &pattern[j]
+                {
+                    jacobian.set(i, j, (step[i] - y[i]) / options.step);
+                }
+            }
+        }
+
+        // Calculate change vector and its magnitude
+        let deltas = jacobian.try_solve(&y)?;
+        let change = deltas.iter()
+            .map(|d| d.powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        if error <= margin && change <= margin
+        {
+            return Ok(guess);
+        }
+
+        // Build next guess vector
+        take_step(&mut state, &init, &f, options.damping, guess, &deltas, error, &mut trial, &mut y)?;
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+
+/// An implementation of Broyden's ("good") method for multivariate systems.
+/// This function takes the same arguments as `multivariate_newton_raphson`, 
+/// but only approximates the jacobian once. Each subsequent iteration updates
+/// the inverse jacobian with a rank-one correction instead, which requires
+/// far fewer function evaluations per iteration for large or expensive systems.
+/// 
+/// This function also guarantees that the root, if found, is within `margin` 
+/// of the actual root AND that F(`guess`) has a magnitude within `margin` of 
+/// `0.0` where 'F' is the "system vector" containing f1, f2, ..., fn.
+/// 
+/// # Example
+/// ```
+/// use std::io::Error;
+/// use std::collections::HashMap;
+/// use geqslib::newton::multivariate_broyden;
+/// 
+/// fn f1(x: &HashMap<String, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["x"] + x["y"] - 9.0)
+/// }
+/// 
+/// fn f2(x: &HashMap<String, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["x"] - x["y"] - 4.0)
+/// }
+/// 
+/// let mut guess = HashMap::from([
+///     ("x".to_string(), 7.0),
+///     ("y".to_string(), 2.0),
+/// ]);
+/// 
+/// let soln = multivariate_broyden(
+///     vec![f1, f2],
+///     &mut guess,
+///     0.0001,
+///     50,
+/// ).unwrap();
+/// 
+/// assert!((soln["x"] - 6.5).abs() < 0.0001);
+/// assert!((soln["y"] - 2.5).abs() < 0.0001);
+/// ```
+/// 
+/// As with `multivariate_newton_raphson`, use `multivariate_broyden_ordered`
+/// when repeated solves must be bit-for-bit reproducible.
+pub fn multivariate_broyden<K, E>(f: Vec<impl Fn(&HashMap<K, f64>) -> Result<f64, E>>, guess: &mut HashMap<K, f64>, margin: f64, limit: usize) -> anyhow::Result<&mut HashMap<K, f64>>
+where 
+    K: Clone + Eq + Hash,
+    anyhow::Error: From<E>,
+{
+    // Copy keys to iterate over hashmap
+    let vars = Vec::from_iter(
+        guess.keys().map(|x| x.to_owned())
+    );
+
+    multivariate_broyden_ordered(f, guess, &vars, margin, limit)
+}
+
+/// Identical to `multivariate_broyden`, but the variables are perturbed 
+/// and updated in the order given by `vars` rather than the iteration 
+/// order of `guess`. Given the same inputs, this function always 
+/// produces exactly the same result.
+/// 
+/// `vars` must contain each key in `guess` exactly once.
+pub fn multivariate_broyden_ordered<'a, K, E>(f: Vec<impl Fn(&HashMap<K, f64>) -> Result<f64, E>>, guess: &'a mut HashMap<K, f64>, vars: &[K], margin: f64, limit: usize) -> anyhow::Result<&'a mut HashMap<K, f64>>
+where 
+    K: Clone + Eq + Hash,
+    anyhow::Error: From<E>,
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
+        return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+    }
+
+    // Establish system size and ensure number of functions == number of vars
+    let n = f.len();
+    if !is_ordering_of(vars, guess, n)
+    {
+        return Err(NewtonRaphsonSolverError::ImproperlyConstrainedSystem.into());
+    }
+
+    let mut inv_jacobian = approximate_jacobian(&f, guess, vars)?;
+    inv_jacobian.try_inplace_invert()?;
+
+    let mut y = Matrix::new(n, 1);
+    for i in 0..n
+    {
+        y[(i, 0)] = f[i](guess)?;
+    }
+
+    // Buffers reused between iterations to avoid reallocating them
+    let mut deltas = Matrix::new(n, 1);
+    let mut next_y = Matrix::new(n, 1);
+    let mut dy = Matrix::new(n, 1);
+    let mut dx_t = Matrix::new(1, n);
+    let mut dx_t_h = Matrix::new(1, n);
+    let mut h_dy = Matrix::new(n, 1);
+    let mut numerator = Matrix::new(n, 1);
+    let mut correction = Matrix::new(n, n);
+
+    for _ in 0..limit
+    {
+        let error = y.iter()
+            .map(|v| v.powi(2))
+            .sum::<f64>();
+
+        // Calculate change vector and its magnitude
+        inv_jacobian.multiply_matrix_into(&y, &mut deltas)?;
+        deltas.inplace_scale(-1.0);
+        let change = deltas.iter()
+            .map(|d| d.powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        if error <= margin && change <= margin
+        {
+            return Ok(guess);
+        }
+
+        // Build next guess vector
+        for (i, var) in vars.iter().enumerate()
+        {
+            if let Some(guess_val) = guess.get_mut(var)
+            {
+                *guess_val += deltas[(i, 0)];
+            }
+        }
+
+        for i in 0..n
+        {
+            next_y[(i, 0)] = f[i](guess)?;
+        }
+        next_y.sub_into(&y, &mut dy)?;
+        swap(&mut y, &mut next_y);
+
+        // Apply the rank-one update to the inverse jacobian:
+        // H += (dx - H * dy) * (dx^T * H) / (dx^T * H * dy)
+        dx_t.clone_from(&deltas);
+        dx_t = dx_t.transpose_view();
+        dx_t.multiply_matrix_into(&inv_jacobian, &mut dx_t_h)?;
+
+        let denominator = dx_t_h.iter()
+            .zip(dy.iter())
+            .map(|(a, b)| a * b)
+            .sum::<f64>();
+        if denominator == 0.0
+        {
+            // The update is undefined, so start over with a fresh approximation
+            inv_jacobian = approximate_jacobian(&f, guess, vars)?;
+            inv_jacobian.try_inplace_invert()?;
+            continue;
+        }
+
+        inv_jacobian.multiply_matrix_into(&dy, &mut h_dy)?;
+        deltas.sub_into(&h_dy, &mut numerator)?;
+        numerator.multiply_matrix_into(&dx_t_h, &mut correction)?;
+        correction.inplace_scale(1.0 / denominator);
+        for i in 0..n
+        {
+            for j in 0..n
+            {
+                inv_jacobian[(i, j)] += correction[(i, j)];
+            }
+        }
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
+/// Solves a system of equations written in fixed-point form, i.e. `x_i = g_i(x)`, by successive
+/// substitution. Each function in `f` must give the residual `x_i - g_i(x)` of the equation for the
+/// variable at the same position in `vars`, which is then updated to `x_i - relaxation * f_i(x)`.
+/// Variables are updated one at a time in the order given by `vars`, so each update sees the 
+/// latest value of the variables before it.
+/// 
+/// A `relaxation` of `1.0` substitutes `g_i(x)` directly, while smaller values damp each update.
+/// This is well-suited to equations that are naturally written as `x = g(x)` and converge under
+/// substitution (e.g. the Colebrook equation for friction factors), and needs no jacobian.
+/// 
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use std::io::Error;
+/// use geqslib::newton::multivariate_fixed_point_ordered;
+/// 
+/// // x = cos(y), y = x / 2
+/// fn f1(x: &HashMap<String, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["x"] - x["y"].cos())
+/// }
+/// 
+/// fn f2(x: &HashMap<String, f64>) -> Result<f64, Error>
+/// {
+///     Ok(x["y"] - x["x"] / 2.0)
+/// }
+/// 
+/// let mut guess = HashMap::from([
+///     ("x".to_string(), 1.0),
+///     ("y".to_string(), 1.0),
+/// ]);
+/// let vars = ["x".to_string(), "y".to_string()];
+/// 
+/// let soln = multivariate_fixed_point_ordered(vec![f1, f2], &mut guess, &vars, 1.0, 1E-9, 100).unwrap();
+/// 
+/// assert!((soln["x"] - (soln["x"] / 2.0).cos()).abs() < 1E-6);
+/// ```
+pub fn multivariate_fixed_point_ordered<'a, K, E>(f: Vec<impl Fn(&HashMap<K, f64>) -> Result<f64, E>>, guess: &'a mut HashMap<K, f64>, vars: &[K], relaxation: f64, margin: f64, limit: usize) -> anyhow::Result<&'a mut HashMap<K, f64>>
+where 
+    K: Clone + Eq + Hash,
+    anyhow::Error: From<E>,
+{
+    // Catch illegal margin of error
+    if margin <= 0.0
+    {
+        return Err(NewtonRaphsonSolverError::NegativeMargin.into());
+    }
+
+    if relaxation <= 0.0 || !relaxation.is_finite()
+    {
+        return Err(FixedPointSolverError::InvalidRelaxation.into());
+    }
+
+    if !is_ordering_of(vars, guess, f.len())
+    {
+        return Err(NewtonRaphsonSolverError::ImproperlyConstrainedSystem.into());
+    }
+
+    for _ in 0..limit
+    {
+        let mut error = 0.0;
+        let mut change = 0.0;
+        for (func, var) in f.iter().zip(vars)
+        {
+            let residual = func(guess)?;
+            let delta = -relaxation * residual;
+
+            error += residual.powi(2);
+            change += delta.powi(2);
+
+            if let Some(guess_val) = guess.get_mut(var)
+            {
+                *guess_val += delta;
+            }
+        }
+
+        if !error.is_finite()
+        {
+            return Err(FixedPointSolverError::Diverged.into());
+        }
+
+        if error <= margin && change.sqrt() <= margin
+        {
+            return Ok(guess);
+        }
+    }
+
+    Err(NewtonRaphsonSolverError::ReachedIterationLimit.into())
+}
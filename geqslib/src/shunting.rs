@@ -30,9 +30,12 @@ pub fn get_legal_variables_iter(text: &str) -> impl Iterator<Item = &str>
 {
     lazy_static! 
     {
-        static ref RE: Regex = Regex::new(r"(?i)[a-z][a-z0-9_]*").unwrap();
+        // Number literals are matched too, so that the exponent of e.g. `2e-3` is not taken as a variable
+        static ref RE: Regex = Regex::new(r"(?i)[0-9.][0-9._]*(?:e[+-]?[0-9]+)?|[a-z][a-z0-9_]*").unwrap();
     }
-    RE.find_iter(text).map(|i| i.as_str())
+    RE.find_iter(text)
+        .map(|i| i.as_str())
+        .filter(|x| !x.starts_with(|c: char| c.is_ascii_digit() || c == '.'))
 }
 
 const _OPERATORS_: &str = "()^*/+-";
@@ -60,14 +63,174 @@ fn prec_check(o1: &str, o2: &str) -> bool
     check1 && (check2 || check3)
 }
 
+/// The kinds of word that `expand_shorthand` distinguishes between 
+/// when deciding whether two words are implicitly multiplied.
+#[derive(Clone, Copy, PartialEq)]
+enum WordKind
+{
+    Number,
+    Name,
+    Function,
+    Open,
+    Close,
+    Other,
+}
+
+/// Rewrites the shorthand commonly found in equations copied from 
+/// datasheets and spreadsheets into a form `rpnify` understands:
+/// 
+/// - Digit grouping with underscores (`1_000`) or thousands separators 
+///   (`1,000,000.5`) is removed. Commas inside a function call are 
+///   always treated as argument separators.
+/// - Implicit multiplication is made explicit, e.g. `2x`, `3(a + b)`, 
+///   `(a + b)(c + d)` and `x(a + b)` when `x` is not a function.
+fn expand_shorthand(expr: &str, context: &ContextHashMap) -> String
+{
+    let chars: Vec<char> = expr.chars().collect();
+    let mut output = String::new();
+    let mut prev = WordKind::Other;
+    let mut calls: Vec<bool> = vec![]; // whether each open parenthesis begins a function call
+    let mut i = 0;
+
+    while i < chars.len()
+    {
+        let c = chars[i];
+        let mut word = String::new();
+
+        let kind = if c.is_ascii_digit() || c == '.'
+        {
+            let in_call = calls.last() == Some(&true);
+            let mut group_len = 0;
+            while i < chars.len()
+            {
+                let ch = chars[i];
+                let next_is_digit = chars.get(i + 1).is_some_and(|x| x.is_ascii_digit());
+                let next_is_signed_digit = matches!(chars.get(i + 1), Some('+' | '-')) 
+                    && chars.get(i + 2).is_some_and(|x| x.is_ascii_digit());
+
+                if ch.is_ascii_digit() || ch == '.'
+                {
+                    group_len = if ch == '.' { 4 } else { group_len + 1 }; // no grouping after a decimal point
+                    word.push(ch);
+                }
+                else if ch == '_' && group_len > 0 && next_is_digit
+                {
+                    // underscore digit grouping
+                }
+                else if ch == ',' && !in_call && (1..=3).contains(&group_len) && is_thousands_group(&chars[i + 1..])
+                {
+                    group_len = 0;
+                }
+                else if (ch == 'e' || ch == 'E') && (next_is_digit || next_is_signed_digit)
+                {
+                    group_len = 4; // scientific notation
+                    word.push(ch);
+                    if next_is_signed_digit
+                    {
+                        word.push(chars[i + 1]);
+                        i += 1;
+                    }
+                }
+                else
+                {
+                    break;
+                }
+                i += 1;
+            }
+            WordKind::Number
+        }
+        else if c.is_ascii_alphabetic()
+        {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_')
+            {
+                word.push(chars[i]);
+                i += 1;
+            }
+
+            match context.get(&word)
+            {
+                Some(Token::Func(_, _)) | Some(Token::Closure(_, _)) => WordKind::Function,
+                _ => WordKind::Name,
+            }
+        }
+        else
+        {
+            word.push(c);
+            i += 1;
+
+            match c
+            {
+                '(' => 
+                {
+                    calls.push(prev == WordKind::Function);
+                    WordKind::Open
+                },
+                ')' => 
+                {
+                    calls.pop();
+                    WordKind::Close
+                },
+                _ => WordKind::Other,
+            }
+        };
+
+        if c.is_whitespace()
+        {
+            output.push(c);
+            continue;
+        }
+
+        let implicit_mul = match (prev, kind)
+        {
+            (WordKind::Number, WordKind::Number) => false,
+            (WordKind::Number | WordKind::Close, WordKind::Number | WordKind::Name | WordKind::Function | WordKind::Open) => true,
+            (WordKind::Name, WordKind::Open) => true,
+            _ => false,
+        };
+
+        if implicit_mul
+        {
+            output.push('*');
+        }
+
+        output += &word;
+        prev = kind;
+    }
+
+    output
+}
+
+/// Checks whether `rest` begins with exactly three digits, 
+/// as it would after a thousands separator.
+fn is_thousands_group(rest: &[char]) -> bool
+{
+    rest.len() >= 3 
+        && rest[..3].iter().all(|c| c.is_ascii_digit())
+        && !rest.get(3).is_some_and(|c| c.is_ascii_digit() || *c == '_')
+}
+
 /// Adds whitespace to help delimit tokens in an expression given as 
 /// a `&str`. 
 fn punctuate(expr: &str) -> String 
 {
+    let chars: Vec<char> = expr.chars().collect();
     let mut output = String::new();
-    for c in expr.chars() 
+    let mut in_number = false;
+    for (i, &c) in chars.iter().enumerate()
     {
-        if _OPERATORS_.contains(c) || c == ','
+        // The sign of an exponent, e.g. in `2e-3`, is part of the number rather than an operator
+        let is_exponent_sign = in_number 
+            && i > 0 && (chars[i - 1] == 'e' || chars[i - 1] == 'E')
+            && chars.get(i + 1).is_some_and(|x| x.is_ascii_digit());
+
+        let continues_word = i > 0 && (chars[i - 1].is_ascii_alphanumeric() || chars[i - 1] == '_' || chars[i - 1] == '.');
+        in_number = match continues_word || is_exponent_sign
+        {
+            true => in_number,
+            false => c.is_ascii_digit() || c == '.',
+        };
+
+        if (_OPERATORS_.contains(c) || c == ',') && !is_exponent_sign
         {
             output += &format!(" {c} ");
         }
@@ -110,6 +273,7 @@ fn tokenize_with_context(tok: &str, context: &ContextHashMap) -> anyhow::Result<
         let token = match cnst_var_or_fn 
         {
             Token::Func(args, func) => Token::Func(*args, *func),
+            Token::Closure(args, func) => Token::Closure(*args, func.clone()),
             Token::Var(val) => Token::Var(Rc::clone(val)),
             Token::Num(num) => Token::Num(*num),
             _ => return Err(ShuntingYardError::ContextMutation.into()),
@@ -126,7 +290,7 @@ fn tokenize_with_context(tok: &str, context: &ContextHashMap) -> anyhow::Result<
 /// https://en.wikipedia.org/wiki/Shunting_yard_algorithm
 fn rpnify(expr: &str, context: &ContextHashMap) -> anyhow::Result<Vec<Token>> 
 {
-    let punctuated = punctuate(expr);
+    let punctuated = punctuate(&expand_shorthand(expr, context));
     let words = punctuated.split(' ').filter(|c| !c.is_empty());
 
     let mut stack: Vec<&str> = Vec::new();
@@ -174,6 +338,16 @@ fn rpnify(expr: &str, context: &ContextHashMap) -> anyhow::Result<Vec<Token>>
                         return Err(ShuntingYardError::UnclosedParenthesis.into())
                     }
                 }
+
+                // A function name right before the parentheses applies to their contents
+                if let Some(&op) = stack.last()
+                {
+                    if matches!(context.get(op), Some(Token::Func(_, _)) | Some(Token::Closure(_, _)))
+                    {
+                        queue.push(tokenize_with_context(op, context)?);
+                        stack.pop();
+                    }
+                }
                 unary_minus = false;
             },
 
@@ -226,7 +400,7 @@ fn rpnify(expr: &str, context: &ContextHashMap) -> anyhow::Result<Vec<Token>>
                             queue.push(Token::Var(Rc::clone(val)));
                             unary_minus = false;
                         }
-                        Token::Func(_, _) => {
+                        Token::Func(_, _) | Token::Closure(_, _) => {
                             stack.push(word);
                             unary_minus = true;
                         },
@@ -308,9 +482,12 @@ pub fn compile_to_fn_of_hashmap(expr: &str, context: &ContextHashMap) -> anyhow:
     Ok(move |x: &HashMap<String, f64>| {
         for (var, value) in x 
         {
+            // Variables added to the context after compiling (e.g. by a
+            // growing system of equations) cannot affect this expression
             match arg_lookup_table.get(var)
             {
                 Some(Token::Var(r)) => (*r.borrow_mut()).set(*value),
+                None => continue,
                 _ => return Err(CompiledExpressionLookupError.into()),
             }
         }
@@ -419,6 +596,29 @@ fn eval_rpn_expression(expr: &Vec<Token>) -> anyhow::Result<f64>
                 );
             },
 
+            Token::Closure(args, func) => {
+
+                let mut arguments: Vec<f64> = Vec::new();
+                for _ in 0..*args 
+                {
+                    if let Some(num) = stack.pop() 
+                    {
+                        arguments.push(num);
+                    } 
+                    else 
+                    {
+                        return Err(ShuntingYardError::ExpectedArg.into())
+                    }
+                }
+                
+                // Arguments come off the stack backwards. Put them back in 
+                // the order the user wrote them in before calling.
+                arguments.reverse();
+                stack.push(
+                    func.call(&arguments)
+                );
+            },
+
             Token::Exp => {
                 if let (Some(arg2), Some(arg1)) = (stack.pop(), stack.pop()) 
                 {
@@ -555,6 +755,41 @@ fn test_rpnify()
     assert_eq!(rpn, vec![Token::Num(3.0), Token::Num(4.0), Token::Plus])
 }
 
+#[test]
+fn test_function_is_applied_before_following_operator() 
+{
+    let ctx: ContextHashMap = new_context();
+    let ans = eval_str_with_context("abs(-3) - 4", &ctx).unwrap();
+
+    assert_eq!(ans, -1.0);
+}
+
+#[test]
+fn test_expand_shorthand() 
+{
+    let ctx: ContextHashMap = new_context();
+
+    assert_eq!(expand_shorthand("2x + 3(a + b)", &ctx), "2*x + 3*(a + b)");
+    assert_eq!(expand_shorthand("(a)(b) - x(y)", &ctx), "(a)*(b) - x*(y)");
+    assert_eq!(expand_shorthand("2sin(x)", &ctx), "2*sin(x)");
+    assert_eq!(expand_shorthand("1,250.5 + 1_000 + 2e3", &ctx), "1250.5 + 1000 + 2e3");
+    assert_eq!(expand_shorthand("log(1,000) + 12,34", &ctx), "log(1,000) + 12,34");
+    assert_eq!(expand_shorthand("2e-3x + 1.5E+2(a)", &ctx), "2e-3*x + 1.5E+2*(a)");
+}
+
+#[test]
+fn test_signed_exponents_are_part_of_the_number()
+{
+    let ctx: ContextHashMap = new_context();
+
+    assert_eq!(eval_str_with_context("2e-3", &ctx).unwrap(), 2E-3);
+    assert_eq!(eval_str_with_context("1.5E+2", &ctx).unwrap(), 150.0);
+    assert_eq!(eval_str_with_context("3.0e-2", &ctx).unwrap(), 0.03);
+    assert!((eval_str_with_context("1.5e-3 * 1000", &ctx).unwrap() - 1.5).abs() < 1E-12);
+    assert!((eval_str_with_context("2 - 3e-1", &ctx).unwrap() - 1.7).abs() < 1E-12);
+    assert_eq!(get_legal_variables_iter("2e-3 * x + 1.5E+2").collect::<Vec<_>>(), vec!["x"]);
+}
+
 #[test]
 fn test_unary_minus() 
 {
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
-use crate::newton::multivariate_newton_raphson;
-use crate::shunting::{get_legal_variables_iter, ContextHashMap, Token};
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::errors::FixedPointSolverError;
+use crate::newton::{multivariate_broyden_ordered, multivariate_fixed_point_ordered, multivariate_newton_raphson_ordered};
+use crate::shunting::{get_legal_variables_iter, new_context, ContextHashMap, Token};
 use crate::compile_equation_to_fn_of_hashmap;
 
 /// An enum for indicating why an equation could or could not be added
@@ -21,6 +24,107 @@ pub enum ConstrainResult
     WillOverConstrain,
 }
 
+/// A rough classification of how difficult an equation is to solve, used by 
+/// `SystemBuilder` to prefer simpler equations when several could constrain
+/// a system. Variants are ordered from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EquationKind
+{
+    /// The equation contains only sums and scaled terms.
+    Linear,
+
+    /// The equation contains exponents or division, which may make it 
+    /// nonlinear in its unknowns.
+    Nonlinear,
+
+    /// The equation calls a function (e.g. `sin`, `exp`), making it
+    /// transcendental in any unknowns passed to that function.
+    Transcendental,
+}
+
+impl EquationKind
+{
+    /// Classifies an equation by looking for function calls, exponents, 
+    /// and division. This is a cheap syntactic check: products of two
+    /// unknowns (e.g. `x * y`) are still classified as `Linear`.
+    /// 
+    /// Only the functions in `new_context` are recognized as function calls, 
+    /// so a name followed by parentheses (e.g. `x(a + b)`) is otherwise read 
+    /// as implicit multiplication. See `EquationKind::of_with_context` to 
+    /// recognize functions from another context.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::system::EquationKind;
+    /// 
+    /// assert_eq!(EquationKind::of("x + 2 * y = 4"), EquationKind::Linear);
+    /// assert_eq!(EquationKind::of("x ^ 2 = y"), EquationKind::Nonlinear);
+    /// assert_eq!(EquationKind::of("sin(x) = y"), EquationKind::Transcendental);
+    /// assert_eq!(EquationKind::of("x(a + b) = y"), EquationKind::Linear);
+    /// ```
+    pub fn of(equation: &str) -> EquationKind
+    {
+        EquationKind::of_with_context(equation, &new_context())
+    }
+
+    /// Classifies an equation like `EquationKind::of`, recognizing the functions 
+    /// and closures in `ctx` as function calls.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::system::EquationKind;
+    /// use geqslib::shunting::{new_context, ContextLike};
+    /// 
+    /// let mut ctx = new_context();
+    /// assert_eq!(EquationKind::of_with_context("f(x) = y", &ctx), EquationKind::Linear);
+    /// 
+    /// ctx.add_closure_to_ctx("f", |args| args[0].exp(), 1);
+    /// assert_eq!(EquationKind::of_with_context("f(x) = y", &ctx), EquationKind::Transcendental);
+    /// ```
+    pub fn of_with_context(equation: &str, ctx: &ContextHashMap) -> EquationKind
+    {
+        lazy_static!
+        {
+            static ref FUNCTION_CALL: Regex = Regex::new(r"(?i)([a-z][a-z0-9_]*)\s*\(").unwrap();
+        }
+
+        let calls_function = FUNCTION_CALL.captures_iter(equation)
+            .any(|c| matches!(ctx.get(&c[1]), Some(Token::Func(_, _)) | Some(Token::Closure(_, _))));
+
+        if calls_function
+        {
+            EquationKind::Transcendental
+        }
+        else if equation.contains('^') || equation.contains('/')
+        {
+            EquationKind::Nonlinear
+        }
+        else
+        {
+            EquationKind::Linear
+        }
+    }
+}
+
+/// An enum for selecting the root-finding algorithm used to solve a `System`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SolverMethod
+{
+    /// Approximates the jacobian of the system at every iteration. 
+    /// This is the default method.
+    #[default]
+    NewtonRaphson,
+
+    /// Approximates the jacobian of the system once and then updates 
+    /// its inverse at every iteration. See `multivariate_broyden`.
+    Broyden,
+
+    /// Solves a system whose equations each have a different unknown alone on 
+    /// their left hand side (i.e. `x = g(x)`) by successive substitution, damping 
+    /// each update by `relaxation`. See `multivariate_fixed_point_ordered`.
+    FixedPoint { relaxation: f64 },
+}
+
 /// Type alias for `System` and `SystemBuilder`
 type BoxedFnOfHashMapToResultF64 = Box<dyn Fn(&HashMap<String, f64>) -> anyhow::Result<f64>>;
 
@@ -31,6 +135,7 @@ pub struct SystemBuilder
     context: ContextHashMap,
     system_vars: Vec<String>,
     system_equations: Vec<BoxedFnOfHashMapToResultF64>,
+    equation_text: Vec<String>,
 }
 impl SystemBuilder
 {
@@ -59,6 +164,7 @@ impl SystemBuilder
             context: ctx,
             system_vars,
             system_equations: vec![starting_eqn],
+            equation_text: vec![equation.to_owned()],
         })
     }
 
@@ -83,6 +189,25 @@ impl SystemBuilder
         &self.system_vars
     }
 
+    /// Gives a reference to the equations in the system in the order 
+    /// that they were added.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::system::SystemBuilder;
+    /// use geqslib::shunting::new_context;
+    /// 
+    /// let mut my_sys = SystemBuilder::new("x + y = 4", new_context())
+    ///     .expect("failed to build system!");
+    /// my_sys.try_constrain_with("x - y = 2").unwrap();
+    /// 
+    /// assert_eq!(my_sys.get_equations(), &vec!["x + y = 4", "x - y = 2"]);
+    /// ```
+    pub fn get_equations(&self) -> &Vec<String>
+    {
+        &self.equation_text
+    }
+
     /// Attempts to constrain the system of equations by adding an equation.
     /// If the equation adds at most 1 unknown variable, it will be added to
     /// the system and an `Ok(ConstrainResult::WillConstrain)` will be returned.
@@ -139,6 +264,7 @@ impl SystemBuilder
         self.system_equations.push(
             Box::new(compile_equation_to_fn_of_hashmap(equation, &mut self.context)?) 
         );
+        self.equation_text.push(equation.to_owned());
 
         // Add possible newly-found variable to the system
         if let Some(new_var) = unknowns.pop()
@@ -180,6 +306,17 @@ impl SystemBuilder
     /// Attempts to fully constrain a system using a given `Vec`
     /// of equations.
     /// 
+    /// When more than one of the given equations could constrain the 
+    /// system, the one that keeps the system smallest and simplest is 
+    /// added first. Candidates are ranked by:
+    /// 
+    /// 1. the number of new unknowns they add (fewer is better),
+    /// 2. their `EquationKind` (linear before nonlinear before transcendental),
+    /// 3. the number of variables they reference, and
+    /// 4. their position in `equations`.
+    /// 
+    /// The resulting order can be inspected with `get_equations`.
+    /// 
     /// # Example
     /// ```
     /// use geqslib::system::{ConstrainResult, SystemBuilder};
@@ -197,29 +334,47 @@ impl SystemBuilder
     ///     "(8 * x) + (9 * y) - (10 * z) = 11"])
     ///     .expect("failed to constrain system!");
     /// ```
+    /// 
+    /// The linear equation is preferred over the transcendental one even
+    /// though it is given last:
+    /// ```
+    /// use geqslib::system::SystemBuilder;
+    /// use geqslib::shunting::new_context;
+    /// 
+    /// let mut my_sys = SystemBuilder::new("x + y = 3", new_context()).unwrap();
+    /// 
+    /// my_sys.try_fully_constrain_with(vec!["ln(x) = y + 2", "x - y = 1"])
+    ///     .expect("failed to constrain system!");
+    /// 
+    /// assert_eq!(my_sys.get_equations(), &vec!["x + y = 3", "x - y = 1"]);
+    /// ```
     pub fn try_fully_constrain_with(&mut self, mut equations: Vec<&str>) -> anyhow::Result<bool>
     {
-        let mut still_learning = true;
-        while still_learning && !self.is_fully_constrained()
+        while !self.is_fully_constrained()
         {
-            still_learning = false;
-            for i in 0..equations.len()
-            {
-                match self.try_constrain_with(equations[i])
-                {
-                    Ok(ConstrainResult::WillNotConstrain) => {}, 
-                    Ok(ConstrainResult::WillConstrain) => {
-                        equations.remove(i);
-                        still_learning = true;
-                        break; // start loop over with equation removed
-                    },
-                    Ok(ConstrainResult::WillOverConstrain) => {
-                        break; // exit loop and abort
-                    },
-                    Err(e) => {
-                        return Err(e);
+            let best = equations.iter()
+                .enumerate()
+                .filter_map(|(i, &eqn)| {
+                    let new_unknowns = get_equation_unknowns(eqn, &self.context).count();
+                    if new_unknowns > 1
+                    {
+                        return None // will not constrain the system
                     }
-                }
+
+                    let num_vars = get_legal_variables_iter(eqn).collect::<HashSet<&str>>().len();
+                    Some((new_unknowns, EquationKind::of_with_context(eqn, &self.context), num_vars, i))
+                })
+                .min();
+
+            let i = match best
+            {
+                Some((_, _, _, i)) => i,
+                None => break,
+            };
+
+            if self.try_constrain_with(equations.remove(i))? != ConstrainResult::WillConstrain
+            {
+                break;
             }
         }
         Ok(self.is_fully_constrained())
@@ -235,6 +390,7 @@ impl SystemBuilder
                 context: self.context,
                 system_vars: self.system_vars,
                 system_equations: self.system_equations,
+                equation_text: self.equation_text,
             });
         }
         
@@ -258,6 +414,7 @@ pub struct System
     context: ContextHashMap,
     system_vars: Vec<String>,
     system_equations: Vec<BoxedFnOfHashMapToResultF64>,
+    equation_text: Vec<String>,
 }
 impl System
 {
@@ -335,6 +492,36 @@ impl System
     /// assert!((2.5 - soln["y"]).abs() < 0.001);
     /// ```
     pub fn solve(self, margin: f64, limit: usize) -> anyhow::Result<HashMap<String, f64>>
+    {
+        self.solve_with_method(SolverMethod::NewtonRaphson, margin, limit)
+    }
+
+    /// Tries to solve the system of equations to within the radius `margin` 
+    /// of the actual solution in `limit` iterations using the given `SolverMethod`.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::system::{SolverMethod, SystemBuilder};
+    /// use geqslib::shunting::new_context;
+    /// 
+    /// let mut ctx = new_context();
+    /// 
+    /// let mut builder = SystemBuilder::new("x + y = 9", ctx)
+    ///     .expect("Failed to create a system...");
+    /// builder.try_constrain_with("x - y = 4");
+    /// 
+    /// let mut sys = builder
+    ///     .build_system()
+    ///     .expect("Failed to constrain system...");
+    /// 
+    /// let soln = sys.solve_with_method(SolverMethod::Broyden, 0.0001, 10)
+    ///     .expect("Failed to find a solution...");
+    /// 
+    /// // Solution is x = 6.5, y = 2.5
+    /// assert!((6.5 - soln["x"]).abs() < 0.001);
+    /// assert!((2.5 - soln["y"]).abs() < 0.001);
+    /// ```
+    pub fn solve_with_method(self, method: SolverMethod, margin: f64, limit: usize) -> anyhow::Result<HashMap<String, f64>>
     {
         let mut guess = HashMap::new();
         for (key, var) in self.context
@@ -346,19 +533,53 @@ impl System
             };
         }
 
-        let res = multivariate_newton_raphson(
-            self.system_equations, 
-            &mut guess,
-            margin, 
-            limit
-        )?;
+        let res = match method
+        {
+            SolverMethod::NewtonRaphson => multivariate_newton_raphson_ordered(
+                self.system_equations, 
+                &mut guess,
+                &self.system_vars,
+                margin, 
+                limit
+            )?,
+            SolverMethod::Broyden => multivariate_broyden_ordered(
+                self.system_equations, 
+                &mut guess,
+                &self.system_vars,
+                margin, 
+                limit
+            )?,
+            SolverMethod::FixedPoint { relaxation } => 
+            {
+                // Each equation updates the unknown on its left hand side
+                let mut vars: Vec<String> = vec![];
+                for equation in &self.equation_text
+                {
+                    let lhs = equation.split('=').next().unwrap_or_default().trim();
+                    if !self.system_vars.iter().any(|x| x == lhs) || vars.iter().any(|x| x == lhs)
+                    {
+                        return Err(FixedPointSolverError::NotFixedPointForm.into());
+                    }
+                    vars.push(lhs.to_owned());
+                }
+
+                multivariate_fixed_point_ordered(
+                    self.system_equations, 
+                    &mut guess,
+                    &vars,
+                    relaxation,
+                    margin, 
+                    limit
+                )?
+            },
+        };
 
         Ok(res.clone())
     }
 }
 
-/// Returns an iterator with the unknown variables in a given equation or expression. 
-/// Note that the variables must exist in the given context in order to ensure that
+/// Returns an iterator with the unknown variables in a given equation or expression,
+/// each given once in the order that it first appears. Note that the variables must exist in the given context in order to ensure that
 /// they are variables and not constants or functions.
 /// 
 /// # Example
@@ -374,10 +595,10 @@ impl System
 ///     assert_ne!(unknown, "y"); // doesn't appear because it is not in ctx
 /// }
 /// ```
-pub fn get_equation_unknowns<'a>(equation: &'a str, ctx: &'a ContextHashMap) -> impl Iterator<Item = &'a str>
+pub fn get_equation_unknowns<'a, 'c>(equation: &'a str, ctx: &'c ContextHashMap) -> impl Iterator<Item = &'a str> + use<'a, 'c>
 {
+    // Unknowns are given in the order they first appear so that systems always solve them in the same order
+    let mut seen = HashSet::new();
     get_legal_variables_iter(equation)
-        .filter(|&x| !ctx.contains_key(x))
-        .collect::<HashSet<&str>>()
-        .into_iter()
+        .filter(move |&x| !ctx.contains_key(x) && seen.insert(x))
 }
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use geqslib::shunting::{new_context, ContextHashMap};
 use geqslib::shunting::{eval_str, eval_str_with_context, ContextLike};
-use geqslib::solve_equation_with_context;
+use geqslib::{solve_equation_with_context, solve_equation_with_fallback, RootMethod};
+use geqslib::system::{SolverMethod, SystemBuilder};
 
 #[test]
 fn test_eval_str() 
@@ -42,4 +43,173 @@ fn ensure_that_single_unknown_solver_can_solve_equation_with_if_statement()
 
     assert_eq!(soln.0, "i".to_owned());
     assert!(soln.1 - 1.0 < 0.001);
-}
\ No newline at end of file
+}
+
+#[test]
+fn ensure_that_smooth_if_statements_blend_their_branches()
+{
+    let ctx = new_context();
+
+    // Far from the switch, the branches are chosen as they would be by `if`
+    assert!((eval_str_with_context("smooth_if(5, 5.0, 2, 10, 20, 50)", &ctx).unwrap() - 10.0).abs() < 1E-9);
+    assert!((eval_str_with_context("smooth_if(5, 4.0, 2, 10, 20, 50)", &ctx).unwrap() - 20.0).abs() < 1E-9);
+
+    // At the switch, they are blended evenly
+    assert_eq!(eval_str_with_context("smooth_if(2, 4.0, 2, 10, 20, 50)", &ctx).unwrap(), 15.0);
+
+    // Equality has nothing to blend, so it switches
+    assert_eq!(eval_str_with_context("smooth_if(2, 1.0, 2, 10, 20, 50)", &ctx).unwrap(), 10.0);
+}
+
+#[test]
+fn ensure_that_single_unknown_solver_can_solve_equation_with_closure()
+{
+    let mut ctx = new_context();
+    ctx.add_closure_to_ctx("minus", |args| args[0] - args[1], 2);
+
+    let soln = solve_equation_with_context(
+        "minus(x, 3) = 4", 
+        &mut ctx, 
+        1.0, 
+        f64::NEG_INFINITY, 
+        f64::INFINITY, 
+        0.0001, 
+        100
+    ).unwrap();
+
+    assert_eq!(soln.0, "x".to_owned());
+    assert!((soln.1 - 7.0).abs() < 0.001);
+}
+
+#[test]
+fn ensure_that_system_builder_prefers_equations_that_add_no_unknowns()
+{
+    let mut builder = SystemBuilder::new("x + y = 3", new_context()).unwrap();
+
+    // The first candidate adds `z`, which would require yet another equation
+    builder.try_fully_constrain_with(vec!["x * z = 2", "x - y = 1"]).unwrap();
+
+    assert!(builder.is_fully_constrained());
+    assert_eq!(builder.get_equations(), &vec!["x + y = 3", "x - y = 1"]);
+}
+
+#[test]
+fn ensure_that_pasted_shorthand_evaluates_correctly()
+{
+    let mut ctx = new_context();
+    ctx.add_const_to_ctx("x", 3.0);
+
+    let ans = eval_str_with_context("2x + 0.5(1,500 + 1_500) - 2pi", &ctx).unwrap();
+
+    assert!((ans - (1506.0 - 2.0 * std::f64::consts::PI)).abs() < 1e-9);
+}
+
+#[test]
+fn ensure_that_fixed_point_method_solves_systems_in_fixed_point_form()
+{
+    let mut builder = SystemBuilder::new("x = cos(y)", new_context()).unwrap();
+    builder.try_constrain_with("y = x / 2").unwrap();
+    let sys = builder.build_system().unwrap();
+
+    let soln = sys.solve_with_method(SolverMethod::FixedPoint { relaxation: 0.8 }, 1E-9, 200).unwrap();
+
+    assert!((soln["x"] - (soln["y"]).cos()).abs() < 1E-6);
+    assert!((soln["y"] - soln["x"] / 2.0).abs() < 1E-6);
+
+    // Neither unknown is alone on the left hand side here
+    let mut builder = SystemBuilder::new("x + y = 3", new_context()).unwrap();
+    builder.try_constrain_with("x - y = 1").unwrap();
+    let sys = builder.build_system().unwrap();
+
+    assert!(sys.solve_with_method(SolverMethod::FixedPoint { relaxation: 1.0 }, 1E-9, 200).is_err());
+}
+
+#[test]
+fn ensure_that_iteration_traces_are_kept_when_the_solver_fails()
+{
+    use geqslib::newton::multivariate_newton_raphson_slice_traced;
+
+    // x^2 + 1 has no real root, so the solver never converges
+    let f = |x: &[f64]| Ok::<f64, anyhow::Error>(x[0] * x[0] + 1.0);
+
+    let mut trace = vec![];
+    let mut guess = [0.5];
+    assert!(multivariate_newton_raphson_slice_traced(vec![f], &mut guess, 1E-6, 5, &mut trace).is_err());
+
+    assert_eq!(trace.len(), 5);
+    for (i, record) in trace.iter().enumerate()
+    {
+        assert_eq!(record.iteration, i);
+        assert_eq!(record.residual, vec![record.guess[0].powi(2) + 1.0]);
+    }
+    assert_eq!(trace[0].guess, vec![0.5]);
+    assert!(trace.windows(2).all(|w| w[0].guess != w[1].guess));
+
+    // Records from a previous solve are replaced
+    let mut guess = [2.0];
+    let g = |x: &[f64]| Ok::<f64, anyhow::Error>(x[0] - 3.0);
+    multivariate_newton_raphson_slice_traced(vec![g], &mut guess, 1E-6, 5, &mut trace).unwrap();
+    assert_eq!(trace[0].guess, vec![2.0]);
+    assert!(trace.len() < 5);
+}
+
+#[test]
+fn ensure_that_single_unknown_solver_falls_back_to_brent_on_finite_domains()
+{
+    let mut ctx = new_context();
+
+    // Newton-Raphson overshoots further every step from a guess of 1.0
+    let (_, soln, method) = solve_equation_with_fallback("arctan(x - 3) = 0", &mut ctx, 1.0, -100.0, 100.0, 0.0001, 100).unwrap();
+    assert_eq!(method, RootMethod::Brent);
+    assert!((soln - 3.0).abs() < 0.0001);
+
+    // Without a finite domain there is nothing to bracket the root with
+    let mut ctx = new_context();
+    assert!(solve_equation_with_fallback("arctan(x - 3) = 0", &mut ctx, 1.0, f64::NEG_INFINITY, f64::INFINITY, 0.0001, 100).is_err());
+
+    // Well-behaved equations are still solved by Newton-Raphson
+    let mut ctx = new_context();
+    let (_, soln, method) = solve_equation_with_fallback("2 * x = 5", &mut ctx, 1.0, 0.0, 10.0, 0.0001, 100).unwrap();
+    assert_eq!(method, RootMethod::Newton);
+    assert!((soln - 2.5).abs() < 0.0001);
+}
+
+#[test]
+fn ensure_that_system_unknowns_are_ordered_by_first_appearance()
+{
+    // Each builder (and each hash set it could have used) must agree on the same order
+    for _ in 0..32
+    {
+        let mut builder = SystemBuilder::new("zeta + a * y - m = 4 + a", new_context()).unwrap();
+        assert_eq!(builder.get_vars(), &vec!["zeta", "a", "y", "m"]);
+
+        builder.try_constrain_with("q = zeta").unwrap();
+        assert_eq!(builder.get_vars(), &vec!["zeta", "a", "y", "m", "q"]);
+    }
+}
+
+#[test]
+fn ensure_that_functions_apply_before_the_operators_that_follow_them()
+{
+    let ctx = new_context();
+
+    assert_eq!(eval_str_with_context("abs(-3) - 4", &ctx).unwrap(), -1.0);
+    assert_eq!(eval_str_with_context("cos(0) - 1 + abs(-2) * 3", &ctx).unwrap(), 6.0);
+    assert_eq!(eval_str_with_context("abs(cos(0) - 3) ^ 2", &ctx).unwrap(), 4.0);
+}
+
+#[test]
+fn ensure_that_implicit_multiplication_is_not_classified_as_a_function_call()
+{
+    use geqslib::system::EquationKind;
+
+    assert_eq!(EquationKind::of("x(a + b) = 3"), EquationKind::Linear);
+    assert_eq!(EquationKind::of("x (a + b) ^ 2 = 3"), EquationKind::Nonlinear);
+    assert_eq!(EquationKind::of("x(a + b) = sin(3)"), EquationKind::Transcendental);
+
+    // The builder should prefer the implicit product over the genuinely transcendental equation
+    let mut builder = SystemBuilder::new("x + y = 3", new_context()).unwrap();
+    builder.try_fully_constrain_with(vec!["sin(x) = y + 2", "x(2 + 1) - y = 1"]).unwrap();
+
+    assert_eq!(builder.get_equations(), &vec!["x + y = 3", "x(2 + 1) - y = 1"]);
+}
@@ -2,6 +2,7 @@
 #define _GMATLIB_H_
 // includes
 #include <limits.h>
+#include <stdint.h>
 #ifdef __cplusplus
 #include <exception>
 #include <sstream>
@@ -39,109 +40,169 @@ namespace _ngffi_
             ZeroDuringInversion,
             /// @brief indicates that one of any other number of internal gmatlib errors occurred. 
             UnknownGmatlibError,
+            /// @brief indicates that the matrix was inverted, but its condition number exceeded the given limit, so the inverse may be dominated by round-off error.
+            IllConditioned,
             
             /// @brief this should be the last value and should have the maximum value a 32-bit integer can have. This value indicates that the process inverted the matrix successfully
             Ok = UINT_MAX,
         };
 
+        /// @brief an opaque handle to a matrix owned by gmatlib. Handles are never reused (storage that has
+        ///        been reused too often to issue a new handle is retired), so passing a handle to a matrix 
+        ///        that was already freed is always detected and reported as a failure. 
+        ///        A handle of 0 is never valid and indicates that a function failed to produce a matrix.
+        typedef uint64_t MatrixHandle;
+
         /// @brief creates a new matrix of `double` values with the specified number of rows and columns
         /// @param rows the number of rows that the matrix should have
         /// @param cols the number of columns that the matrix should have
-        /// @return a raw pointer to the new matrix. 
-        void *new_double_matrix(unsigned rows, unsigned cols);
+        /// @return a handle to the new matrix. 
+        MatrixHandle new_double_matrix(unsigned rows, unsigned cols);
 
         /// @brief creates a new identity matrix of `double` values with the specified edge length
         /// @param n the edge length of the matrix
-        /// @return a raw pointer to the new matrix.
-        void *new_double_identity_matrix(unsigned n);
+        /// @return a handle to the new matrix.
+        MatrixHandle new_double_identity_matrix(unsigned n);
+
+        /// @brief creates a new matrix of `double` values by copying them, in row-major order, from a caller-owned buffer
+        /// @param data a pointer to at least `rows * cols` values
+        /// @param rows the number of rows that the matrix should have
+        /// @param cols the number of columns that the matrix should have
+        /// @return a handle to the new matrix (0 if `data` is NULL)
+        MatrixHandle new_double_matrix_from_buffer(const double *data, unsigned rows, unsigned cols);
+
+        /// @brief overwrites every element of the matrix by copying them, in row-major order, from a caller-owned buffer
+        /// @param handle a handle to the matrix to mutate
+        /// @param data a pointer to at least `len` values
+        /// @param len the number of values to copy, which must equal the number of elements in the matrix
+        /// @return a boolean integer value indicating success (0 on failure)
+        unsigned copy_buffer_into_double_matrix(MatrixHandle handle, const double *data, unsigned len);
 
         /// @brief scales the given row of the matrix
-        /// @param ptr a raw pointer to the matrix to mutate
+        /// @param handle a handle to the matrix to mutate
         /// @param row the 0-indexed row to scale 
         /// @param scalar the scalar value to multiply the row values by
         /// @return a boolean integer value indicating success (0 on failure)
-        unsigned inplace_row_scale(void *ptr, unsigned row, double scalar);
+        unsigned inplace_row_scale(MatrixHandle handle, unsigned row, double scalar);
 
         /// @brief scales all elements of the matrix by the given scalar quantity
-        /// @param ptr a raw pointer to the matrix to scale
+        /// @param handle a handle to the matrix to scale
         /// @param scalar the value to scale the matrix by
         /// @return a boolean integer value indicating success (0 on failure) 
-        unsigned inplace_scale(void *ptr, double scalar);
+        unsigned inplace_scale(MatrixHandle handle, double scalar);
 
         /// @brief adds the row r1 to the row r2 in an element-wise manner
-        /// @param ptr a raw pointer to the matrix to mutate
+        /// @param handle a handle to the matrix to mutate
         /// @param r1 the row to add without mutating
         /// @param r2 the row to mutably add to
         /// @return a boolean integer value indicating success (0 on failure)
-        unsigned inplace_row_add(void *ptr, unsigned r1, unsigned r2);
+        unsigned inplace_row_add(MatrixHandle handle, unsigned r1, unsigned r2);
 
         /// @brief adds the row r1 to the row r2 in an element-wise manner AFTER scaling row r1 by a given value
-        /// @param ptr a raw pointer to the matrix to mutate
+        /// @param handle a handle to the matrix to mutate
         /// @param r1 the row to add without mutating
         /// @param r2 the row to mutably add to
         /// @param scalar the quantity to scale row r1 by
         /// @return a boolean integer value indicating success (0 on failure)
-        unsigned inplace_scaled_row_add(void *ptr, unsigned r1, unsigned r2, double scalar);
+        unsigned inplace_scaled_row_add(MatrixHandle handle, unsigned r1, unsigned r2, double scalar);
 
         /// @brief returns the matrix product of the two given matrices
-        /// @param ptr_a the left operand
-        /// @param ptr_b the right operand
-        /// @return the matrix product of the matrices at ptr_a and ptr_b
-        void *multiply_matrix(void *ptr_a, void *ptr_b);
+        /// @param handle_a the left operand
+        /// @param handle_b the right operand
+        /// @return the matrix product of the matrices at handle_a and handle_b
+        MatrixHandle multiply_matrix(MatrixHandle handle_a, MatrixHandle handle_b);
 
         /// @brief returns the augment matrix created by the two given matrices
-        /// @param ptr_a the left operand
-        /// @param ptr_b the right operand (the matrix whose rows should be appended to the one at ptr_a)
-        /// @return a raw pointer to the augment matrix of the two given matrices
-        void *augment_with(void *ptr_a, void *ptr_b);
+        /// @param handle_a the left operand
+        /// @param handle_b the right operand (the matrix whose rows should be appended to the one at handle_a)
+        /// @return a handle to the augment matrix of the two given matrices
+        MatrixHandle augment_with(MatrixHandle handle_a, MatrixHandle handle_b);
 
         /// @brief returns a pointer to a new matrix that contains the specified 2D slice of elements from the given matrix
-        /// @param ptr a raw pointer to the matrix to slice 
+        /// @param handle a handle to the matrix to slice 
         /// @param r1 the first row to slice
         /// @param c1 the first column to slice
         /// @param r2 the last row in the slice
         /// @param c2 the last column in the slice
-        /// @return a pointer to a new matrix containing the specified slice
-        void *subset(void *ptr, unsigned r1, unsigned c1, unsigned r2, unsigned c2);
+        /// @return a handle to a new matrix containing the specified slice
+        MatrixHandle subset(MatrixHandle handle, unsigned r1, unsigned c1, unsigned r2, unsigned c2);
 
         /// @brief returns the trace of the given matrix
-        /// @param ptr a raw pointer to the matrix to compute the trace of
+        /// @param handle a handle to the matrix to compute the trace of
         /// @return the trace of the matrix
-        double trace(void *ptr);
+        double trace(MatrixHandle handle);
 
         /// @brief creates a new matrix whose elements are the same as the given matrix mirrored about it's diagonal
-        /// @param ptr a raw pointer to the matrix to transpose
-        /// @return a pointer to a new transposed matrix 
-        void *transpose(void *ptr);
+        /// @param handle a handle to the matrix to transpose
+        /// @return a handle to a new transposed matrix 
+        MatrixHandle transpose(MatrixHandle handle);
+
+        /// @brief transposes the matrix in-place by switching its memory layout without moving any elements
+        /// @param handle a handle to the matrix to transpose
+        /// @return a boolean integer value indicating success (0 on failure)
+        unsigned inplace_transpose_view(MatrixHandle handle);
+
+        /// @brief indicates whether the matrix stores its elements in column-major (Fortran) order
+        /// @param handle a handle to the matrix to inspect
+        /// @return 1 if the matrix is column-major, 0 if it is row-major, and UINT_MAX on failure
+        unsigned is_column_major_double_matrix(MatrixHandle handle);
+
+        /// @brief gets the number of elements between the starts of consecutive rows in the matrix's storage
+        /// @param handle a handle to the matrix to inspect
+        /// @return the row stride of the matrix (0 on failure)
+        unsigned row_stride_double_matrix(MatrixHandle handle);
+
+        /// @brief gets the number of elements between the starts of consecutive columns in the matrix's storage
+        /// @param handle a handle to the matrix to inspect
+        /// @return the column stride of the matrix (0 on failure)
+        unsigned col_stride_double_matrix(MatrixHandle handle);
+
+        /// @brief gets a pointer to the matrix's underlying storage for zero-copy interop with other libraries
+        /// @param handle a handle to the matrix to inspect
+        /// @return a pointer to the first element, valid until the matrix is freed (NULL on failure)
+        const double *data_double_matrix(MatrixHandle handle);
 
         /// @brief attempts to invert the matrix by mutating it in-place
-        /// @param ptr a raw pointer to the matrix to invert
+        /// @param handle a handle to the matrix to invert
         /// @return a boolean integer value indicating success (0 on failure)
-        unsigned try_inplace_invert(void *ptr);
+        unsigned try_inplace_invert(MatrixHandle handle);
+
+        /// @brief attempts to invert the matrix in-place, then checks that its 1-norm condition number does not exceed `max_condition`
+        /// @param handle a handle to the matrix to invert
+        /// @param max_condition the largest condition number for which the inverse is trusted
+        /// @return a `MatrixInversionError` code. the matrix still holds its inverse when `IllConditioned` is returned
+        unsigned try_inplace_invert_conditioned(MatrixHandle handle, double max_condition);
+
+        /// @brief solves the linear system Ax = b without inverting A
+        /// @param handle_a a handle to the square coefficient matrix A
+        /// @param handle_b a handle to the right hand side b, which may have several columns
+        /// @return a handle to a new matrix containing x (0 if A is singular or the sizes do not match)
+        MatrixHandle solve_linear_system(MatrixHandle handle_a, MatrixHandle handle_b);
 
         /// @brief mutates the individual `double` value at the `i`th row and `j`th column
-        /// @param ptr a raw pointer to the matrix to index 
+        /// @param handle a handle to the matrix to index 
         /// @param i the row of the element to mutate
         /// @param j the column of the element to mutate
         /// @param value the value that the matrix should have the given index set to
         /// @return a boolean integer value indicating success (0 on failure)
-        unsigned index_mut_double_matrix(void *ptr, unsigned i, unsigned j, double value);
+        unsigned index_mut_double_matrix(MatrixHandle handle, unsigned i, unsigned j, double value);
 
         /// @brief returns the `double` element at the `i`th row and the `j`th column
-        /// @param ptr a raw pointer to the matrix to index
+        /// @param handle a handle to the matrix to index
         /// @param i the row of the element to mutate
         /// @param j the column of the element to mutate
         /// @return the double value at the given index
-        double index_double_matrix(void *ptr, unsigned i, unsigned j);
+        double index_double_matrix(MatrixHandle handle, unsigned i, unsigned j);
 
         /// @brief clones the matrix, allocating space sufficient for a copy of the given matrix
-        /// @param ptr a raw pointer to the matrix to clone
-        /// @return a raw pointer to the new matrix
-        void *clone_double_matrix(void *ptr);
+        /// @param handle a handle to the matrix to clone
+        /// @return a handle to the new matrix
+        MatrixHandle clone_double_matrix(MatrixHandle handle);
 
         /// @brief frees the memory tied up in the given matrix
-        /// @param ptr a raw pointer to the matrix to free
-        void free_double_matrix(void *ptr);
+        /// @param handle a handle to the matrix to free
+        /// @return a boolean integer value indicating success (0 if the handle was invalid or already freed)
+        unsigned free_double_matrix(MatrixHandle handle);
 #ifdef __cplusplus
     }
 }
@@ -157,17 +218,17 @@ namespace ngineer
         unsigned rows;
         /// @brief the number of columns in the given matrix
         unsigned cols;
-        /// @brief the raw pointer to the matrix elements
-        void *ptr;
+        /// @brief the handle to the matrix elements
+        _ngffi_::MatrixHandle handle;
 
         /// @brief a private constructor for building a `Matrix` from raw parts
         /// @param rows the number of rows in the matrix
         /// @param cols the number of columns in the matrix 
-        /// @param ptr a raw pointer to the matrix to instantiate
-        Matrix(unsigned rows, unsigned cols, void *ptr):
+        /// @param handle a handle to the matrix to instantiate
+        Matrix(unsigned rows, unsigned cols, _ngffi_::MatrixHandle handle):
             rows(rows),
             cols(cols),
-            ptr(ptr)
+            handle(handle)
         {
         }
 
@@ -178,7 +239,7 @@ namespace ngineer
         Matrix(unsigned rows, unsigned cols): 
             rows(rows), 
             cols(cols), 
-            ptr(_ngffi_::new_double_matrix(rows, cols))
+            handle(_ngffi_::new_double_matrix(rows, cols))
         {
         }
 
@@ -190,6 +251,32 @@ namespace ngineer
             return Matrix { n, n, _ngffi_::new_double_identity_matrix(n) };
         }
 
+        /// @brief creates a new matrix by copying its elements, in row-major order, from a buffer that the caller keeps ownership of
+        /// @param data a pointer to at least `rows * cols` values
+        /// @param rows the number of rows that the matrix should have
+        /// @param cols the number of columns that the matrix should have
+        /// @return a new matrix holding a copy of the buffer
+        inline static Matrix FromBuffer(const double *data, unsigned rows, unsigned cols)
+        {
+            _ngffi_::MatrixHandle copied = _ngffi_::new_double_matrix_from_buffer(data, rows, cols);
+            if (copied == 0)
+            {
+                throw runtime_error { "failed to create matrix from buffer.\n" };
+            }
+            return Matrix { rows, cols, copied };
+        }
+
+        /// @brief overwrites every element of the matrix by copying them, in row-major order, from a buffer that the caller keeps ownership of
+        /// @param data a pointer to at least `rows * cols` values
+        inline void copy_from_buffer(const double *data)
+        {
+            unsigned result = _ngffi_::copy_buffer_into_double_matrix(handle, data, rows * cols);
+            if (!result)
+            {
+                throw runtime_error { "failed to copy buffer into matrix.\n" };
+            }
+        }
+
         /// @brief returns a boolean value indicating whether the given matrix is square
         /// @return whether the matrix is square
         inline bool is_square() const noexcept
@@ -202,7 +289,7 @@ namespace ngineer
         /// @param scalar the scalar value to multiply the row values by
         inline void inplace_row_scale(unsigned row, double scalar)
         {
-            unsigned result = _ngffi_::inplace_row_scale(ptr, row, scalar);
+            unsigned result = _ngffi_::inplace_row_scale(handle, row, scalar);
             if (!result)
             {
                 string errorText;
@@ -217,7 +304,7 @@ namespace ngineer
         /// @param scalar the quantity to scale the matrix elements by
         inline void inplace_scale(double scalar)
         {
-            unsigned result = _ngffi_::inplace_scale(ptr, scalar);
+            unsigned result = _ngffi_::inplace_scale(handle, scalar);
             if (!result)
             {
                 string errorText;
@@ -233,7 +320,7 @@ namespace ngineer
         /// @param r2 the row to mutate by the addition operation
         inline void inplace_row_add(unsigned r1, unsigned r2)
         {
-            unsigned result = _ngffi_::inplace_row_add(ptr, r1, r2);
+            unsigned result = _ngffi_::inplace_row_add(handle, r1, r2);
             if (!result)
             {
                 string errorText;
@@ -250,7 +337,7 @@ namespace ngineer
         /// @param scalar the value to scaled 
         inline void inplace_scaled_row_add(unsigned r1, unsigned r2, double scalar)
         {
-            unsigned result = _ngffi_::inplace_scaled_row_add(ptr, r1, r2, scalar);
+            unsigned result = _ngffi_::inplace_scaled_row_add(handle, r1, r2, scalar);
             if (!result)
             {
                 string errorText;
@@ -266,8 +353,8 @@ namespace ngineer
         /// @return the matrix product of `this` matrix and `rhs`
         inline Matrix operator*(const Matrix rhs) const
         {
-            void* matrixProduct = _ngffi_::multiply_matrix(ptr, const_cast<Matrix *>(&rhs));
-            if (matrixProduct == nullptr)
+            _ngffi_::MatrixHandle matrixProduct = _ngffi_::multiply_matrix(handle, rhs.handle);
+            if (matrixProduct == 0)
             {
                 string errorText;
                 stringstream ss;
@@ -283,8 +370,8 @@ namespace ngineer
         /// @return a new matrix produced by appending the rows of the right-hand matrix to the rows of the left 
         inline Matrix operator|(const Matrix rhs) const
         {
-            void *augmentMatrix = _ngffi_::augment_with(ptr, const_cast<Matrix *>(&rhs));
-            if (augmentMatrix == nullptr)
+            _ngffi_::MatrixHandle augmentMatrix = _ngffi_::augment_with(handle, rhs.handle);
+            if (augmentMatrix == 0)
             {
                 string errorText;
                 stringstream ss;
@@ -305,7 +392,7 @@ namespace ngineer
         {
             string errorText;
             stringstream ss;
-            void *matrixSlice = _ngffi_::subset(ptr, r1, c1, r2, c2);
+            _ngffi_::MatrixHandle matrixSlice = _ngffi_::subset(handle, r1, c1, r2, c2);
 
             if (r1 >= rows)
             {
@@ -323,7 +410,7 @@ namespace ngineer
             {
                 ss << "failed to slice matrix ending at column " << c2 << "because it only has " << cols << " columns.\n";
             }
-            else if (matrixSlice == nullptr)
+            else if (matrixSlice == 0)
             {
                 ss << "failed to slice matrix: "  ".\n";
             }
@@ -342,22 +429,22 @@ namespace ngineer
         /// @return either the trace value of the matrix or NaN if the matrix is not square.
         inline double trace() const noexcept
         {
-            return _ngffi_::trace(ptr);
+            return _ngffi_::trace(handle);
         }
 
         /// @brief produces a copy of the matrix containing the same elements but transposed 
         /// @return the transpose of the given matrix as a new matrix object
         inline Matrix transpose() const noexcept
         {
-            void *transposed = _ngffi_::transpose(ptr);
-            return Matrix { cols, rows, ptr };
+            _ngffi_::MatrixHandle transposed = _ngffi_::transpose(handle);
+            return Matrix { cols, rows, transposed };
         }
 
         /// @brief inverts the given matrix by mutating its values through gaussian elimination
         inline void inplace_invert()
         {
             constexpr char *ERROR_MESSAGE_PREFIX = "failed to invert matrix because ";
-            unsigned result = _ngffi_::try_inplace_invert(ptr);
+            unsigned result = _ngffi_::try_inplace_invert(handle);
             string errorText;
             stringstream ss;
 
@@ -388,13 +475,30 @@ namespace ngineer
             throw runtime_error { errorText };
         }
 
+        /// @brief solves the linear system `this * x = rhs` for `x` without inverting this matrix
+        /// @param rhs the right-hand side of the system, with one column per system to solve
+        /// @return the solution `x`, which has the same size as `rhs`
+        inline Matrix solve(const Matrix rhs) const
+        {
+            _ngffi_::MatrixHandle solution = _ngffi_::solve_linear_system(handle, rhs.handle);
+            if (solution == 0)
+            {
+                string errorText;
+                stringstream ss;
+                ss << "failed to solve linear system with matrix: " << this << " and right-hand side: " << &rhs << ".\n";
+                ss >> errorText;
+                throw runtime_error { errorText };
+            }
+            return Matrix { rhs.rows, rhs.cols, solution };
+        }
+
         /// @brief mutates the value at the given index in the matrix to a desired value.
         /// @param indices the row and column of the element to modify
         /// @param value the value that the chosen element should have
         /// @return a boolean value indicating success. (`false` on failure)
         inline bool set(pair<unsigned, unsigned> indices, double value) noexcept
         {
-            return _ngffi_::index_mut_double_matrix(ptr, indices.first, indices.second, value);
+            return _ngffi_::index_mut_double_matrix(handle, indices.first, indices.second, value);
         }
 
         /// @brief returns a copy of the value at the given index in the matrix
@@ -402,7 +506,7 @@ namespace ngineer
         /// @return the `double` value located at that index
         inline const double operator[](pair<unsigned, unsigned> indices)
         {
-            return _ngffi_::index_double_matrix(ptr, indices.first, indices.second);
+            return _ngffi_::index_double_matrix(handle, indices.first, indices.second);
         }
 
         /// @brief formats this matrix reference as part of a `std::stringstream`, allowing users to view it's elements all at once. 
@@ -444,7 +548,7 @@ namespace ngineer
         /// @brief deallocates the memory tied up in this matrix
         ~Matrix()
         {
-            _ngffi_::free_double_matrix(ptr);
+            _ngffi_::free_double_matrix(handle);
         }
     };
 }
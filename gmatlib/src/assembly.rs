@@ -0,0 +1,81 @@
+use anyhow::Result;
+use crate::{Element, Matrix};
+
+/// Builds a global `Matrix<T>` by scattering many small element
+/// matrices into it, summing contributions that land on the same
+/// index. This is the assembly step of nodal and finite-element
+/// analysis, where each element only couples a handful of the
+/// global unknowns.
+///
+/// # Example
+/// ```
+/// use gmatlib::Matrix;
+/// use gmatlib::assembly::Assembler;
+///
+/// // Two springs in series: ground - node 0 - node 1
+/// let k1: Matrix<i32> = Matrix::from_vec(1, vec![2]).unwrap();
+/// let k2: Matrix<i32> = Matrix::from_vec(
+///     2,
+///     vec![ 3, -3,
+///          -3,  3]
+/// ).unwrap();
+///
+/// let mut asm = Assembler::new(2);
+/// asm.add_element(&k1, &[0]).unwrap();
+/// asm.add_element(&k2, &[0, 1]).unwrap();
+///
+/// let k: Vec<i32> = asm.finish().into();
+/// assert_eq!(
+///     k,
+///     vec![ 5, -3,
+///          -3,  3]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Assembler<T>
+where T: Element<T>
+{
+    global: Matrix<T>,
+}
+
+impl <T> Assembler<T>
+where T: Element<T>
+{
+    /// Creates an assembler for a square `n` x `n` global matrix.
+    pub fn new(n: usize) -> Assembler<T>
+    {
+        Assembler::new_rect(n, n)
+    }
+
+    /// Creates an assembler for a `rows` x `cols` global matrix.
+    pub fn new_rect(rows: usize, cols: usize) -> Assembler<T>
+    {
+        Assembler { global: Matrix::new(rows, cols) }
+    }
+
+    /// Adds a square element matrix whose rows and columns both
+    /// correspond to the global indices given in `dofs`.
+    pub fn add_element(&mut self, local: &Matrix<T>, dofs: &[usize]) -> Result<()>
+    {
+        self.global.inplace_scatter_add(local, dofs, dofs)
+    }
+
+    /// Adds an element matrix whose rows and columns correspond
+    /// to the global indices in `row_map` and `col_map`.
+    pub fn add_block(&mut self, local: &Matrix<T>, row_map: &[usize], col_map: &[usize]) -> Result<()>
+    {
+        self.global.inplace_scatter_add(local, row_map, col_map)
+    }
+
+    /// Returns a reference to the global matrix assembled so far.
+    pub fn get_matrix(&self) -> &Matrix<T>
+    {
+        &self.global
+    }
+
+    /// Consumes the assembler, returning the global matrix.
+    pub fn finish(self) -> Matrix<T>
+    {
+        self.global
+    }
+}
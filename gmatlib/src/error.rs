@@ -35,7 +35,8 @@ pub enum MatrixInversionError
 {
     DeterminantWasZero,
     SingularValueWasZero,
-    ZeroDuringInversion
+    ZeroDuringInversion,
+    IllConditioned { kappa: f64 },
 }
 impl Display for MatrixInversionError
 {
@@ -46,6 +47,7 @@ impl Display for MatrixInversionError
             MatrixInversionError::DeterminantWasZero   => write!(f, "calculated a determinant of 0 while trying to invert matrix."),
             MatrixInversionError::SingularValueWasZero => write!(f, "matrix was zero matrix of size 1 x 1 and cannot be inverted."),
             MatrixInversionError::ZeroDuringInversion  => write!(f, "found a 0 value during the nxn matrix inversion process."),
+            MatrixInversionError::IllConditioned { kappa } => write!(f, "matrix was inverted, but its condition number ({kappa:e}) was too large for the inverse to be trusted."),
         }
     }
 }
@@ -80,4 +82,40 @@ impl Display for MatrixMultiplicationError
         write!(f, "failed to multiply matrices because columns of left operand and rows of right operand were not equal.")
     }
 }
-impl Error for MatrixMultiplicationError {}
\ No newline at end of file
+impl Error for MatrixMultiplicationError {}
+#[derive(Debug)]
+pub enum MatrixAssemblyError
+{
+    IndexMapLengthMismatch,
+    IndexOutOfBounds,
+}
+impl Display for MatrixAssemblyError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            MatrixAssemblyError::IndexMapLengthMismatch => write!(f, "failed to assemble matrix because an index map did not have one entry per row or column of the local matrix."),
+            MatrixAssemblyError::IndexOutOfBounds       => write!(f, "failed to assemble matrix because an index map pointed outside of the global matrix."),
+        }
+    }
+}
+impl Error for MatrixAssemblyError {}
+
+#[derive(Debug)]
+pub struct MatrixDimensionError
+{
+    pub expected: (usize, usize),
+    pub found: (usize, usize),
+}
+impl Display for MatrixDimensionError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(
+            f, "failed to convert matrix because it was not of the expected size. (expected: {} x {}, found: {} x {})",
+            self.expected.0, self.expected.1, self.found.0, self.found.1
+        )
+    }
+}
+impl Error for MatrixDimensionError {}
@@ -1,332 +1,427 @@
-use std::ffi::{c_double, c_uint, c_void};
-use std::mem;
-use std::panic::catch_unwind;
-use std::ptr::null_mut;
-use crate::{Matrix, MatrixInversionError};
+use std::ffi::{c_double, c_uint};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Mutex, MutexGuard};
+use crate::{Layout, Matrix, MatrixInversionError};
+
+/// An opaque identifier for a `Matrix<f64>` owned by the FFI registry.
+///
+/// The low 32 bits hold the index of the slot in the registry and the
+/// high 32 bits hold the generation of that slot when the handle was
+/// issued. A handle of `0` is never valid and is used to signal failure.
+pub type MatrixHandle = u64;
+
+/// A single storage location in the registry.
+struct Slot
+{
+    generation: u32,
+    matrix: Option<Matrix<c_double>>,
+}
 
-#[no_mangle]
-pub extern "C" fn new_double_matrix(rows: c_uint, cols: c_uint) -> *mut c_void
-{
-    // We need to use catch_unwind to prevent UB if caller exceeds isize::MAX bytes
-    let res = catch_unwind(|| {
-        let a = Box::new(Matrix::<c_double>::new(rows as usize, cols as usize));
-        Box::into_raw(a) as *mut c_void
-    });
-    
-    match res
+/// Owns every matrix handed out across the FFI boundary.
+///
+/// Freeing a matrix bumps the generation of its slot, so stale
+/// handles (i.e. use-after-free or double-free) no longer match
+/// and are rejected instead of touching freed memory. A slot whose
+/// generation cannot be bumped again is retired rather than wrapped,
+/// so no handle is ever issued twice.
+struct Registry
+{
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+impl Registry
+{
+    const fn new() -> Registry
     {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
+        Registry { slots: Vec::new(), free: Vec::new() }
     }
-}
 
-#[no_mangle]
-pub extern "C" fn new_double_identity_matrix(n: c_uint) -> *mut c_void
-{
-    let res = catch_unwind(|| {
-        let a = Box::new(Matrix::<c_double>::new_identity(n as usize));
-        Box::into_raw(a) as *mut c_void
-    });
-    
-    match res
+    fn insert(&mut self, matrix: Matrix<c_double>) -> MatrixHandle
     {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
+        let index = match self.free.pop()
+        {
+            Some(i) => i,
+            None =>
+            {
+                if self.slots.len() >= u32::MAX as usize
+                {
+                    return 0
+                }
+                self.slots.push(Slot { generation: 1, matrix: None });
+                self.slots.len() - 1
+            }
+        };
+
+        let slot = &mut self.slots[index];
+        slot.matrix = Some(matrix);
+
+        ((slot.generation as u64) << 32) | index as u64
     }
-}
 
-#[no_mangle]
-pub extern "C" fn inplace_row_swap(ptr: *mut c_void, r1: c_uint, r2: c_uint) -> c_uint
-{
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a.inplace_row_swap(r1 as usize, r2 as usize);
-        
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
-    });
+    fn slot_index(&self, handle: MatrixHandle) -> Option<usize>
+    {
+        let index = (handle & u32::MAX as u64) as usize;
+        let generation = (handle >> 32) as u32;
+
+        match self.slots.get(index)
+        {
+            Some(slot) if slot.generation == generation && slot.matrix.is_some() => Some(index),
+            _ => None,
+        }
+    }
 
-    match res
+    fn get(&self, handle: MatrixHandle) -> Option<&Matrix<c_double>>
     {
-        Ok(_)  => 1,
-        Err(_) => 0,
+        let index = self.slot_index(handle)?;
+        self.slots[index].matrix.as_ref()
+    }
+
+    fn get_mut(&mut self, handle: MatrixHandle) -> Option<&mut Matrix<c_double>>
+    {
+        let index = self.slot_index(handle)?;
+        self.slots[index].matrix.as_mut()
+    }
+
+    fn remove(&mut self, handle: MatrixHandle) -> Option<Matrix<c_double>>
+    {
+        let index = self.slot_index(handle)?;
+        let slot = &mut self.slots[index];
+
+        // Retire the slot instead of wrapping its generation, which would reissue old handles
+        if let Some(generation) = slot.generation.checked_add(1)
+        {
+            slot.generation = generation;
+            self.free.push(index);
+        }
+
+        slot.matrix.take()
     }
 }
 
-#[no_mangle]
-pub extern "C" fn inplace_row_scale(ptr: *mut c_void, row: c_uint, scalar: c_double) -> c_uint
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry::new());
+
+/// Locks the registry. A panic while the lock was held cannot leave
+/// the registry itself in an inconsistent state, so poisoning is ignored.
+fn registry() -> MutexGuard<'static, Registry>
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a.inplace_row_scale(row as usize, scalar);
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
-    });
+/// Runs `f` with exclusive access to the registry, converting
+/// any panic into `None` so it never unwinds into foreign code.
+fn with_registry<R>(f: impl FnOnce(&mut Registry) -> Option<R>) -> Option<R>
+{
+    catch_unwind(AssertUnwindSafe(|| f(&mut registry()))).ok().flatten()
+}
 
-    match res
+/// Converts the result of inverting a matrix into the status code returned
+/// across the FFI boundary, or `c_uint::MAX` if the inversion succeeded.
+fn inversion_status(result: anyhow::Result<()>) -> c_uint
+{
+    match result
     {
-        Ok(_)  => 1,
-        Err(_) => 0,
+        Ok(_)  => c_uint::MAX,
+        Err(e) =>
+        {
+            match e.downcast()
+            {
+                Ok(MatrixInversionError::DeterminantWasZero)    => 0,
+                Ok(MatrixInversionError::SingularValueWasZero)  => 1,
+                Ok(MatrixInversionError::ZeroDuringInversion)   => 2,
+                Ok(MatrixInversionError::IllConditioned { .. }) => 4,
+                Err(_) => 3,
+            }
+        }
     }
 }
 
 #[no_mangle]
-pub extern "C" fn inplace_scale(ptr: *mut c_void, scalar: c_double) -> c_uint
+pub extern "C" fn new_double_matrix(rows: c_uint, cols: c_uint) -> MatrixHandle
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a.inplace_scale(scalar);
-
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
-    });
+    // catch_unwind prevents UB if caller exceeds isize::MAX bytes
+    with_registry(|reg| {
+        Some(reg.insert(Matrix::new(rows as usize, cols as usize)))
+    })
+    .unwrap_or(0)
+}
 
-    match res
+/// Creates a matrix by copying `rows * cols` elements, in row-major order, from `data`.
+/// Returns `0` if `data` is null.
+///
+/// # Safety
+/// `data` must point to at least `rows * cols` readable `c_double`s.
+#[no_mangle]
+pub unsafe extern "C" fn new_double_matrix_from_buffer(data: *const c_double, rows: c_uint, cols: c_uint) -> MatrixHandle
+{
+    if data.is_null()
     {
-        Ok(_)  => 1,
-        Err(_) => 0,
+        return 0
     }
+
+    let slice = std::slice::from_raw_parts(data, rows as usize * cols as usize);
+    with_registry(|reg| {
+        let a = Matrix::from_slice(rows as usize, cols as usize, slice).ok()?;
+        Some(reg.insert(a))
+    })
+    .unwrap_or(0)
 }
 
+/// Overwrites every element of a matrix with `len` elements, in row-major order, 
+/// copied from `data`. Returns `0` if `data` is null or `len` is not the number of
+/// elements in the matrix.
+///
+/// # Safety
+/// `data` must point to at least `len` readable `c_double`s.
 #[no_mangle]
-pub extern "C" fn inplace_row_add(ptr: *mut c_void, r1: c_uint, r2: c_uint) -> c_uint
+pub unsafe extern "C" fn copy_buffer_into_double_matrix(handle: MatrixHandle, data: *const c_double, len: c_uint) -> c_uint
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a.inplace_row_add(r1 as usize, r2 as usize);
-
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
-    });
-
-    match res
+    if data.is_null()
     {
-        Ok(_)  => 1,
-        Err(_) => 0,
+        return 0
     }
+
+    let slice = std::slice::from_raw_parts(data, len as usize);
+    with_registry(|reg| {
+        reg.get_mut(handle)?.copy_from_slice(slice).ok()?;
+        Some(1)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn inplace_scaled_row_add(ptr: *mut c_void, r1: c_uint, r2: c_uint, scalar: c_double) -> c_uint
+pub extern "C" fn new_double_identity_matrix(n: c_uint) -> MatrixHandle
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a.inplace_scaled_row_add(r1 as usize, r2 as usize, scalar);
+    with_registry(|reg| {
+        Some(reg.insert(Matrix::new_identity(n as usize)))
+    })
+    .unwrap_or(0)
+}
 
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
-    });
+#[no_mangle]
+pub extern "C" fn inplace_row_swap(handle: MatrixHandle, r1: c_uint, r2: c_uint) -> c_uint
+{
+    with_registry(|reg| {
+        reg.get_mut(handle)?.inplace_row_swap(r1 as usize, r2 as usize);
+        Some(1)
+    })
+    .unwrap_or(0)
+}
 
-    match res
-    {
-        Ok(_)  => 1,
-        Err(_) => 0,
-    }
+#[no_mangle]
+pub extern "C" fn inplace_row_scale(handle: MatrixHandle, row: c_uint, scalar: c_double) -> c_uint
+{
+    with_registry(|reg| {
+        reg.get_mut(handle)?.inplace_row_scale(row as usize, scalar);
+        Some(1)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn multiply_matrix(ptr_a: *mut c_void, ptr_b: *mut c_void) -> *mut c_void
-{
-    let res = catch_unwind(|| {
-        let (a, b) = unsafe 
-        {(
-            Box::from_raw(ptr_a as *mut Matrix<c_double>),
-            Box::from_raw(ptr_b as *mut Matrix<c_double>),
-        )};
-    
-        let ab = match a.multiply_matrix(&b) 
-        {
-            Ok(x)  => Box::new(x),
-            Err(_) => return null_mut(), // return early and indicate failure via NULL
-        };
-    
-        mem::forget(a); // Prevent drop that would deallocate matrix data. We don't inform the
-        mem::forget(b); // caller that a or b will be deallocated, so we shouldn't do it here.
-    
-        Box::into_raw(ab) as *mut c_void
-    });
-
-    match res
-    {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
-    }
+pub extern "C" fn inplace_scale(handle: MatrixHandle, scalar: c_double) -> c_uint
+{
+    with_registry(|reg| {
+        reg.get_mut(handle)?.inplace_scale(scalar);
+        Some(1)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn augment_with(ptr_a: *mut c_void, ptr_b: *mut c_void) -> *mut c_void
-{
-    let res = catch_unwind(|| {
-        let (a, b) = unsafe 
-        {(
-            Box::from_raw(ptr_a as *mut Matrix<c_double>),
-            Box::from_raw(ptr_b as *mut Matrix<c_double>),
-        )};
-    
-        let ab = match a.augment_with(&b) 
-        {
-            Ok(x)  => Box::new(x),
-            Err(_) => return null_mut(), // return early and indicate failure via NULL
-        };
-    
-        mem::forget(a); // Prevent drop that would deallocate matrix data. We don't inform the
-        mem::forget(b); // caller that a or b will be deallocated, so we shouldn't do it here.
-    
-        Box::into_raw(ab) as *mut c_void
-    });
-    
-    match res
-    {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
-    }
+pub extern "C" fn inplace_row_add(handle: MatrixHandle, r1: c_uint, r2: c_uint) -> c_uint
+{
+    with_registry(|reg| {
+        reg.get_mut(handle)?.inplace_row_add(r1 as usize, r2 as usize);
+        Some(1)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn subset(ptr: *mut c_void, r1: c_uint, c1: c_uint, r2: c_uint, c2: c_uint) -> *mut c_void
+pub extern "C" fn inplace_scaled_row_add(handle: MatrixHandle, r1: c_uint, r2: c_uint, scalar: c_double) -> c_uint
 {
-    let res = catch_unwind(|| {
-        let a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        let b = Box::new(a.subset(r1 as usize, c1 as usize, r2 as usize, c2 as usize));
-        mem::forget(a); // Prevent drop that would deallocate matrix data.
-        Box::into_raw(b) as *mut c_void
-    });
+    with_registry(|reg| {
+        reg.get_mut(handle)?.inplace_scaled_row_add(r1 as usize, r2 as usize, scalar);
+        Some(1)
+    })
+    .unwrap_or(0)
+}
 
-    match res
-    {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
-    }
+#[no_mangle]
+pub extern "C" fn multiply_matrix(handle_a: MatrixHandle, handle_b: MatrixHandle) -> MatrixHandle
+{
+    with_registry(|reg| {
+        let ab = reg.get(handle_a)?
+            .multiply_matrix(reg.get(handle_b)?)
+            .ok()?;
+
+        Some(reg.insert(ab))
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn trace(ptr: *mut c_void) -> c_double
+pub extern "C" fn augment_with(handle_a: MatrixHandle, handle_b: MatrixHandle) -> MatrixHandle
 {
-    let res = catch_unwind(|| {
-        let a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        let trace = match a.trace()
-        {
-            Ok(t) => t,
-            Err(_) => c_double::NAN
-        };
+    with_registry(|reg| {
+        let ab = reg.get(handle_a)?
+            .augment_with(reg.get(handle_b)?)
+            .ok()?;
+
+        Some(reg.insert(ab))
+    })
+    .unwrap_or(0)
+}
 
-        mem::forget(a); // Prevent drop that would deallocate the matrix data
+#[no_mangle]
+pub extern "C" fn subset(handle: MatrixHandle, r1: c_uint, c1: c_uint, r2: c_uint, c2: c_uint) -> MatrixHandle
+{
+    with_registry(|reg| {
+        let b = reg.get(handle)?.subset(r1 as usize, c1 as usize, r2 as usize, c2 as usize);
+        Some(reg.insert(b))
+    })
+    .unwrap_or(0)
+}
 
-        trace
-    });
+#[no_mangle]
+pub extern "C" fn trace(handle: MatrixHandle) -> c_double
+{
+    with_registry(|reg| {
+        match reg.get(handle)?.trace()
+        {
+            Ok(t)  => Some(t),
+            Err(_) => Some(c_double::NAN),
+        }
+    })
+    .unwrap_or(c_double::MIN)
+}
 
-    match res 
-    {
-        Ok(t)  => t as c_double,
-        Err(_) => c_double::MIN,
-    }
+#[no_mangle]
+pub extern "C" fn transpose(handle: MatrixHandle) -> MatrixHandle
+{
+    with_registry(|reg| {
+        let b = reg.get(handle)?.transpose();
+        Some(reg.insert(b))
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn transpose(ptr: *mut c_void) -> *mut c_void
+pub extern "C" fn inplace_transpose_view(handle: MatrixHandle) -> c_uint
 {
-    let res = catch_unwind(|| {
-        let a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        let b = Box::new(a.transpose());
-        mem::forget(a);
+    with_registry(|reg| {
+        // Move the matrix out and back in so that its elements are never copied
+        let index = reg.slot_index(handle)?;
+        let slot = &mut reg.slots[index];
+        slot.matrix = Some(slot.matrix.take()?.transpose_view());
+        Some(1)
+    })
+    .unwrap_or(0)
+}
 
-        Box::into_raw(b) as *mut c_void
-    });
+#[no_mangle]
+pub extern "C" fn is_column_major_double_matrix(handle: MatrixHandle) -> c_uint
+{
+    with_registry(|reg| {
+        match reg.get(handle)?.get_layout()
+        {
+            Layout::ColumnMajor => Some(1),
+            Layout::RowMajor    => Some(0),
+        }
+    })
+    .unwrap_or(c_uint::MAX)
+}
 
-    match res
-    {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
-    }
+#[no_mangle]
+pub extern "C" fn row_stride_double_matrix(handle: MatrixHandle) -> c_uint
+{
+    with_registry(|reg| Some(reg.get(handle)?.get_row_stride() as c_uint))
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn try_inplace_invert(ptr: *mut c_void) -> c_uint
+pub extern "C" fn col_stride_double_matrix(handle: MatrixHandle) -> c_uint
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        let status = match a.try_inplace_invert()
-        {
-            Ok(_)  => c_uint::MAX,
-            Err(e) => 
-            {
-                match e.downcast()
-                {
-                    Ok(MatrixInversionError::DeterminantWasZero)    => 0,
-                    Ok(MatrixInversionError::SingularValueWasZero)  => 1,
-                    Ok(MatrixInversionError::ZeroDuringInversion)   => 2,
-                    Err(_) => 3,
-                }
-            }
-        };
-    
-        mem::forget(a);
-        status
-    });
-    
-    res.unwrap_or(0)
+    with_registry(|reg| Some(reg.get(handle)?.get_col_stride() as c_uint))
+        .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn index_mut_double_matrix(ptr: *mut c_void, i: c_uint, j: c_uint, value: c_double) -> c_uint
+pub extern "C" fn data_double_matrix(handle: MatrixHandle) -> *const c_double
 {
-    let res = catch_unwind(|| {
-        let mut a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        a[(i as usize, j as usize)] = value;
+    // The returned pointer borrows the matrix's storage, so it is only
+    // valid until the matrix is freed or replaced.
+    with_registry(|reg| Some(reg.get(handle)?.as_slice().as_ptr()))
+        .unwrap_or(std::ptr::null())
+}
 
-        mem::forget(a);
-    });
+#[no_mangle]
+pub extern "C" fn try_inplace_invert(handle: MatrixHandle) -> c_uint
+{
+    with_registry(|reg| {
+        Some(inversion_status(reg.get_mut(handle)?.try_inplace_invert()))
+    })
+    .unwrap_or(3)
+}
 
-    match res
-    {
-        Ok(_)  => 1,
-        Err(_) => 0,
-    }
+#[no_mangle]
+pub extern "C" fn try_inplace_invert_conditioned(handle: MatrixHandle, max_condition: c_double) -> c_uint
+{
+    with_registry(|reg| {
+        Some(inversion_status(reg.get_mut(handle)?.try_inplace_invert_conditioned(max_condition)))
+    })
+    .unwrap_or(3)
 }
 
 #[no_mangle]
-pub extern "C" fn index_double_matrix(ptr: *mut c_void, i: c_uint, j: c_uint) -> c_double
+pub extern "C" fn solve_linear_system(handle_a: MatrixHandle, handle_b: MatrixHandle) -> MatrixHandle
 {
-    let res = catch_unwind(|| {
-        let a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-        let value = a[(i as usize, j as usize)];
-        mem::forget(a);
-        value
-    });
+    with_registry(|reg| {
+        let x = reg.get(handle_a)?
+            .try_solve(reg.get(handle_b)?)
+            .ok()?;
+
+        Some(reg.insert(x))
+    })
+    .unwrap_or(0)
+}
 
-    match res
-    {
-        Ok(o)  => o,
-        Err(_) =>
-        {
-            c_double::MIN
-        }
-    }
+#[no_mangle]
+pub extern "C" fn index_mut_double_matrix(handle: MatrixHandle, i: c_uint, j: c_uint, value: c_double) -> c_uint
+{
+    with_registry(|reg| {
+        reg.get_mut(handle)?[(i as usize, j as usize)] = value;
+        Some(1)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn clone_double_matrix(ptr: *mut c_void) -> *mut c_void
-{
-    let res = catch_unwind(|| {
-        // Get the actual matrix instance
-        let a = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-
-        // Use clone to allocate a new instance and mem::forget it AND the old instance
-        let b = a.clone();
-        mem::forget(a);
-    
-        Box::into_raw(b) as *mut c_void
-    });
-    
-    match res
-    {
-        Ok(ptr) => ptr,
-        Err(_)  => null_mut(),
-    }
+pub extern "C" fn index_double_matrix(handle: MatrixHandle, i: c_uint, j: c_uint) -> c_double
+{
+    with_registry(|reg| Some(reg.get(handle)?[(i as usize, j as usize)]))
+        .unwrap_or(c_double::MIN)
+}
+
+#[no_mangle]
+pub extern "C" fn clone_double_matrix(handle: MatrixHandle) -> MatrixHandle
+{
+    with_registry(|reg| {
+        let b = reg.get(handle)?.clone();
+        Some(reg.insert(b))
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
-pub extern "C" fn free_double_matrix(ptr: *mut c_void)
+pub extern "C" fn free_double_matrix(handle: MatrixHandle) -> c_uint
 {
-    // Try to dealloc. if a panic occurs, abort and leak mem 
-    // to avoid UB in the name of Ferris.
-    let _ = catch_unwind(|| {
-        let _drop_this = unsafe { Box::from_raw(ptr as *mut Matrix<c_double>) };
-    });
+    // Drop the matrix after releasing the lock, and report
+    // stale or unknown handles rather than freeing anything.
+    match with_registry(|reg| reg.remove(handle))
+    {
+        Some(_) => 1,
+        None    => 0,
+    }
 }
@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use crate::{Element, Matrix};
+
+/// A helper trait for float types, whose values can be hashed and
+/// totally ordered by their exact bits even though they are not `Hash` or `Ord`.
+pub trait ExactBits
+{
+    /// Returns the exact bits of the value, e.g. from `f64::to_bits`.
+    fn exact_bits(&self) -> u64;
+
+    /// Compares two values by IEEE 754's total order, e.g. with `f64::total_cmp`,
+    /// which only treats two values as equal if their bits are equal.
+    fn total_order(&self, other: &Self) -> Ordering;
+}
+
+impl ExactBits for f32
+{
+    fn exact_bits(&self) -> u64
+    {
+        self.to_bits() as u64
+    }
+
+    fn total_order(&self, other: &Self) -> Ordering
+    {
+        self.total_cmp(other)
+    }
+}
+
+impl ExactBits for f64
+{
+    fn exact_bits(&self) -> u64
+    {
+        self.to_bits()
+    }
+
+    fn total_order(&self, other: &Self) -> Ordering
+    {
+        self.total_cmp(other)
+    }
+}
+
+/// Wraps a `Matrix<T>` of floats so that it can key a `HashMap` or `BTreeMap`, e.g. to
+/// cache the results of solving a system for a given vector of inputs.
+///
+/// Keys are hashed, compared and ordered by the exact bits of their elements, with no
+/// tolerance, so keys are only equal if every element is bit-for-bit identical. This means
+/// that `0.1 + 0.2` and `0.3` are different keys, as are `0.0` and `-0.0`, but a `NaN` is
+/// equal to itself. Like a `Matrix<T>`, keys are ordered by their shape (rows, then columns)
+/// and then by their elements in row-major order, and do not depend on the matrix's layout.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeMap;
+/// use gmatlib::Matrix;
+/// use gmatlib::key::MatrixKey;
+///
+/// let mut cache = BTreeMap::new();
+/// cache.insert(MatrixKey(Matrix::from_col_vec(vec![0.3, 1.0])), "exact");
+/// cache.insert(MatrixKey(Matrix::from_col_vec(vec![0.1 + 0.2, 1.0])), "rounded");
+///
+/// assert_eq!(cache.len(), 2);
+/// assert_eq!(cache[&MatrixKey(Matrix::from_col_vec(vec![0.3, 1.0]))], "exact");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MatrixKey<T>(pub Matrix<T>)
+where T: Element<T> + ExactBits;
+
+impl <T> PartialEq for MatrixKey<T>
+where T: Element<T> + ExactBits
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl <T> Eq for MatrixKey<T>
+where T: Element<T> + ExactBits
+{}
+
+impl <T> Hash for MatrixKey<T>
+where T: Element<T> + ExactBits
+{
+    fn hash<H: Hasher>(&self, state: &mut H)
+    {
+        self.0.get_rows().hash(state);
+        self.0.get_cols().hash(state);
+        for val in self.0.iter()
+        {
+            val.exact_bits().hash(state);
+        }
+    }
+}
+
+impl <T> PartialOrd for MatrixKey<T>
+where T: Element<T> + ExactBits
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        Some(self.cmp(other))
+    }
+}
+
+impl <T> Ord for MatrixKey<T>
+where T: Element<T> + ExactBits
+{
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        (self.0.get_rows(), self.0.get_cols()).cmp(&(other.0.get_rows(), other.0.get_cols()))
+            .then_with(|| self.0.iter().zip(other.0.iter())
+                .map(|(l, r)| l.total_order(r))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal))
+    }
+}
@@ -3,21 +3,40 @@ pub mod error;
 /// Contains the source for the traits implemented for and 
 /// operators invoving `Matrix<T>`.
 mod trait_impls;
+/// Contains helpers for assembling a global `Matrix<T>` from
+/// many smaller element matrices, as in nodal or FEA analysis.
+pub mod assembly;
+/// Contains `SMatrix<T, R, C>`, a fixed-size matrix stored on the stack
+/// for small matrices that are created and discarded very frequently.
+pub mod small;
+/// Contains `SparseMatrix<T>`, which only stores nonzero elements, for
+/// large matrices where each row only has a handful of them.
+pub mod sparse;
+/// Contains `MatrixKey<T>`, which hashes and orders a `Matrix<T>` of floats
+/// by the exact bits of its elements so that it can key a cache.
+pub mod key;
 /// Contains `extern "C"` function definitions for linking this library
 /// against projects in different languages. Not intended for use in 
 /// other Rust projects.
 pub mod ffi;
 
-use core::slice;
 use std::{fmt::Debug, fmt::Display};
 use std::ops::{AddAssign, MulAssign, Neg, SubAssign};
 use std::mem::swap;
 use anyhow::{Error, Result};
 use error::*;
-use num_traits::Num;
+use num_traits::{Num, ToPrimitive};
+
+/// The version of this crate, e.g. for recording which 
+/// version of the library produced a set of results.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// A helper trait to constrain the type of the elements of a `Matrix<T>`.
-pub trait Element<T>: Num + Copy + Debug + Display + AddAssign + MulAssign + SubAssign + Neg<Output = T> {}
+/// 
+/// Elements only need to be `Clone`, so heap-allocated or extended-precision
+/// number types may be used in addition to the primitive numeric types. 
+/// Implement this trait for your own number type to use it in a `Matrix<T>`.
+pub trait Element<T>: Num + Clone + Debug + Display + AddAssign + MulAssign + SubAssign + Neg<Output = T> {}
 
 impl Element<f32> for f32 {}
 impl Element<f64> for f64 {}
@@ -26,14 +45,29 @@ impl Element<i16> for i16 {}
 impl Element<i32> for i32 {}
 impl Element<i64> for i64 {}
 
+#[cfg(feature = "decimal")]
+impl Element<rust_decimal::Decimal> for rust_decimal::Decimal {}
+
+/// The order in which the elements of a `Matrix<T>` are stored in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout
+{
+    /// Elements in the same row are adjacent in memory (C/NumPy ordering).
+    #[default]
+    RowMajor,
+    /// Elements in the same column are adjacent in memory (Fortran ordering).
+    ColumnMajor,
+}
+
 /// An MxN matrix stored as a single contiguous piece of memory.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug)]
 pub struct Matrix<T>
 where T: Element<T>
 {
     rows: usize,
     cols: usize,
     vals: Vec<T>,
+    layout: Layout,
 }
 
 impl <T> Matrix<T>
@@ -60,6 +94,7 @@ where T: Element<T>
             rows, 
             cols, 
             vals: Vec::with_capacity(rows * cols),
+            layout: Layout::RowMajor,
         };
         
         for _ in 0..a.vals.capacity()
@@ -123,9 +158,115 @@ where T: Element<T>
             rows: vec.len() / cols,
             cols,
             vals: vec,
+            layout: Layout::RowMajor,
+        })
+    }
+
+    /// Constructs a `Matrix<T>` from a `Vec<T>` holding its 
+    /// elements in column-major (Fortran) order, without copying 
+    /// them. Fails if the number of elements is not evenly 
+    /// divisible by the number of rows specified.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Layout, Matrix};
+    /// 
+    /// let a = Matrix::from_col_major_vec(
+    ///     2,
+    ///     vec![1, 3,  // first column
+    ///          2, 4]  // second column
+    /// ).expect("Failed to create matrix");
+    /// 
+    /// assert_eq!(a.get_layout(), Layout::ColumnMajor);
+    /// assert_eq!(a[(0, 1)], 2);
+    /// assert_eq!(
+    ///     a, 
+    ///     Matrix::from_vec(2, vec![1, 2, 
+    ///                              3, 4]).unwrap()
+    /// );
+    /// ```
+    pub fn from_col_major_vec(rows: usize, vec: Vec<T>) -> Result<Matrix<T>>
+    {
+        if rows == 0 || !vec.len().is_multiple_of(rows)
+        {
+            return Err(MatrixFromVecError.into())
+        }
+
+        Ok(Matrix {
+            rows,
+            cols: vec.len() / rows,
+            vals: vec,
+            layout: Layout::ColumnMajor,
+        })
+    }
+
+    /// Constructs a `Matrix<T>` with the given number of rows and 
+    /// columns by copying its elements, in row-major order, from a 
+    /// borrowed slice. This suits data that the caller does not own 
+    /// (e.g. an FFI buffer or a memory-mapped file), as the elements 
+    /// are copied straight into the matrix's storage. Fails if the 
+    /// slice does not hold exactly `rows * cols` elements.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let buffer = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    /// let a = Matrix::from_slice(2, 3, &buffer).expect("Failed to create matrix");
+    /// 
+    /// assert_eq!(a[(1, 0)], 4.0);
+    /// assert!(Matrix::from_slice(4, 2, &buffer).is_err());
+    /// ```
+    pub fn from_slice(rows: usize, cols: usize, slice: &[T]) -> Result<Matrix<T>>
+    {
+        if slice.len() != rows * cols
+        {
+            return Err(MatrixDimensionError { expected: (rows, cols), found: (slice.len(), 1) }.into())
+        }
+
+        Ok(Matrix {
+            rows,
+            cols,
+            vals: slice.to_vec(),
+            layout: Layout::RowMajor,
         })
     }
 
+    /// Overwrites every element of the matrix with those of a borrowed
+    /// slice, given in row-major order, keeping the matrix's shape, layout
+    /// and allocation. Fails if the slice does not hold exactly as many 
+    /// elements as the matrix.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let mut a: Matrix<i32> = Matrix::new(2, 2);
+    /// a.copy_from_slice(&[1, 2, 3, 4]).unwrap();
+    /// 
+    /// assert_eq!(a, Matrix::from_vec(2, vec![1, 2, 3, 4]).unwrap());
+    /// assert!(a.copy_from_slice(&[1, 2, 3]).is_err());
+    /// ```
+    pub fn copy_from_slice(&mut self, slice: &[T]) -> Result<()>
+    {
+        if slice.len() != self.rows * self.cols
+        {
+            return Err(MatrixDimensionError { expected: (self.rows, self.cols), found: (slice.len(), 1) }.into())
+        }
+
+        match self.layout
+        {
+            Layout::RowMajor => self.vals.clone_from_slice(slice),
+            Layout::ColumnMajor => for (k, val) in slice.iter().enumerate()
+            {
+                let idx = self.offset(k / self.cols, k % self.cols);
+                self.vals[idx] = val.clone();
+            },
+        }
+
+        Ok(())
+    }
+
     /// Constructs a single-row `Matrix<T>` from
     /// a given `Vec<T>`.
     /// 
@@ -146,6 +287,7 @@ where T: Element<T>
             rows: 1,
             cols: vec.len(),
             vals: vec,
+            layout: Layout::RowMajor,
         }
     }
 
@@ -171,6 +313,7 @@ where T: Element<T>
             rows: vec.len(),
             cols: 1,
             vals: vec,
+            layout: Layout::RowMajor,
         }
     }
 
@@ -204,9 +347,97 @@ where T: Element<T>
         self.cols
     }
 
+    /// Returns the memory layout of the `Matrix<T>`.
+    /// 
+    /// # Example 
+    /// ```
+    /// use gmatlib::{Layout, Matrix};
+    /// 
+    /// let a: Matrix<i32> = Matrix::new(4, 5);
+    /// 
+    /// assert_eq!(a.get_layout(), Layout::RowMajor);
+    /// assert_eq!(a.transpose_view().get_layout(), Layout::ColumnMajor);
+    /// ```
+    pub fn get_layout(&self) -> Layout
+    {
+        self.layout
+    }
+
+    /// Returns the number of elements between the starts of two 
+    /// consecutive rows in the underlying storage.
+    /// 
+    /// # Example 
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a: Matrix<i32> = Matrix::new(4, 5);
+    /// assert_eq!(a.get_row_stride(), 5);
+    /// 
+    /// let b = a.transpose_view();
+    /// assert_eq!(b.get_row_stride(), 1);
+    /// ```
+    pub fn get_row_stride(&self) -> usize
+    {
+        match self.layout
+        {
+            Layout::RowMajor    => self.cols,
+            Layout::ColumnMajor => 1,
+        }
+    }
+
+    /// Returns the number of elements between the starts of two 
+    /// consecutive columns in the underlying storage.
+    /// 
+    /// # Example 
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a: Matrix<i32> = Matrix::new(4, 5);
+    /// assert_eq!(a.get_col_stride(), 1);
+    /// 
+    /// let b = a.transpose_view();
+    /// assert_eq!(b.get_col_stride(), 5);
+    /// ```
+    pub fn get_col_stride(&self) -> usize
+    {
+        match self.layout
+        {
+            Layout::RowMajor    => 1,
+            Layout::ColumnMajor => self.rows,
+        }
+    }
+
+    /// Returns the elements of the matrix in the order they
+    /// are stored in memory, as given by `get_layout`.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a: Matrix<i32> = Matrix::from_vec(
+    ///     2,
+    ///     vec![1, 2,
+    ///          3, 4]
+    /// ).expect("Failed to create matrix");
+    /// 
+    /// assert_eq!(a.as_slice(), &[1, 2, 3, 4]);
+    /// assert_eq!(a.transpose_view().as_slice(), &[1, 2, 3, 4]);
+    /// ```
+    pub fn as_slice(&self) -> &[T]
+    {
+        &self.vals
+    }
+
+    /// Returns the offset into the underlying storage
+    /// of the element at the given row and column.
+    fn offset(&self, row: usize, col: usize) -> usize
+    {
+        row * self.get_row_stride() + col * self.get_col_stride()
+    }
+
     /// Returns an iterator over the elements of the matrix.
     /// The order of the elements is left-to-right, 
-    /// top-to-bottom.
+    /// top-to-bottom, regardless of the memory layout.
     /// 
     /// # Example
     /// ```
@@ -225,9 +456,9 @@ where T: Element<T>
     ///     assert_eq!(&check[idx], elem)
     /// }
     /// ```
-    pub fn iter(&self) -> slice::Iter<'_, T>
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_
     {
-        self.vals.iter()
+        (0..self.rows * self.cols).map(|k| &self.vals[self.offset(k / self.cols, k % self.cols)])
     }
 
     /// Swaps the locations of two rows in the matrix.
@@ -252,8 +483,8 @@ where T: Element<T>
         let mut storage: T;
         for i in 0..self.cols
         {
-            storage       = self[(i, r1)];
-            self[(i, r1)] = self[(i, r2)];
+            storage       = self[(i, r1)].clone();
+            self[(i, r1)] = self[(i, r2)].clone();
             self[(i, r2)] = storage;
         } 
     }
@@ -274,7 +505,7 @@ where T: Element<T>
     {
         for i in 0..self.cols
         {
-            self[(row, i)] *= scalar;
+            self[(row, i)] *= scalar.clone();
         }
     }
 
@@ -301,7 +532,7 @@ where T: Element<T>
     {
         for i in 0..self.rows
         {
-            self.inplace_row_scale(i, scalar);
+            self.inplace_row_scale(i, scalar.clone());
         }
     }
 
@@ -327,7 +558,7 @@ where T: Element<T>
     {
         for i in 0..self.cols
         {
-            let addend = self[(r2, i)];
+            let addend = self[(r2, i)].clone();
             self[(r1, i)] += addend;
         }
     }
@@ -355,7 +586,7 @@ where T: Element<T>
     {
         for i in 0..self.cols
         {
-            let addend = self[(r2, i)] * scalar;
+            let addend = self[(r2, i)].clone() * scalar.clone();
             self[(r1, i)] += addend;
         }
     }
@@ -383,6 +614,54 @@ where T: Element<T>
     /// );
     /// ```
     pub fn multiply_matrix(&self, a: &Matrix<T>) -> Result<Matrix<T>>
+    {
+        let mut result = Matrix::new(0, 0);
+        self.multiply_matrix_into(a, &mut result)?;
+        Ok(result)
+    }
+
+    /// Resizes the matrix to `rows` x `cols` and sets every element to `0`,
+    /// reusing the existing allocation whenever it is large enough.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let mut a: Matrix<i32> = Matrix::new_identity(3);
+    /// a.inplace_reset(2, 1);
+    /// 
+    /// assert_eq!(a, Matrix::new(2, 1));
+    /// ```
+    pub fn inplace_reset(&mut self, rows: usize, cols: usize)
+    {
+        self.rows = rows;
+        self.cols = cols;
+        self.layout = Layout::RowMajor;
+        self.vals.clear();
+        self.vals.resize(rows * cols, T::zero());
+    }
+
+    /// Writes the matrix multiplication product of this `Matrix<T>` and another 
+    /// `Matrix<T>` into `out`, reusing its allocation. This is otherwise identical
+    /// to `multiply_matrix`, and is intended for hot loops that would otherwise 
+    /// allocate a new matrix for every product.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Matrix, col_vec};
+    /// 
+    /// let a: Matrix<i32> = Matrix::new_identity(2) * 2;
+    /// let b: Matrix<i32> = col_vec![2, 3];
+    /// 
+    /// let mut c = Matrix::new(2, 1);
+    /// for _ in 0..3
+    /// {
+    ///     a.multiply_matrix_into(&b, &mut c).unwrap();
+    /// }
+    /// 
+    /// assert_eq!(c, col_vec![4, 6]);
+    /// ```
+    pub fn multiply_matrix_into(&self, a: &Matrix<T>, out: &mut Matrix<T>) -> Result<()>
     {
         if self.cols != a.rows
         {
@@ -390,7 +669,7 @@ where T: Element<T>
         }
 
         let n = self.cols;
-        let mut result = Matrix::new(self.rows, a.cols);
+        out.inplace_reset(self.rows, a.cols);
 
         for i in 0..self.rows
         {
@@ -398,12 +677,75 @@ where T: Element<T>
             {
                 for x in 0..n
                 {
-                    result[(i, j)] += self[(i, x)] * a[(x, j)]
+                    out[(i, j)] += self[(i, x)].clone() * a[(x, j)].clone()
                 }
             }
         }
 
-        Ok(result)
+        Ok(())
+    }
+
+    /// Writes the element-wise sum of this `Matrix<T>` and another `Matrix<T>`
+    /// into `out`, reusing its allocation. Unlike the `+` operator, this returns 
+    /// a `MatrixDimensionError` instead of panicking if the sizes do not match.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Matrix, col_vec};
+    /// 
+    /// let a: Matrix<i32> = col_vec![1, 2];
+    /// let b: Matrix<i32> = col_vec![3, 4];
+    /// 
+    /// let mut c = Matrix::new(2, 1);
+    /// a.add_into(&b, &mut c).unwrap();
+    /// 
+    /// assert_eq!(c, col_vec![4, 6]);
+    /// assert!(a.add_into(&Matrix::new(1, 2), &mut c).is_err());
+    /// ```
+    pub fn add_into(&self, a: &Matrix<T>, out: &mut Matrix<T>) -> Result<()>
+    {
+        self.elementwise_into(a, out, |l, r| l + r)
+    }
+
+    /// Writes the element-wise difference of this `Matrix<T>` and another `Matrix<T>`
+    /// into `out`, reusing its allocation. Unlike the `-` operator, this returns 
+    /// a `MatrixDimensionError` instead of panicking if the sizes do not match.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Matrix, col_vec};
+    /// 
+    /// let a: Matrix<i32> = col_vec![1, 2];
+    /// let b: Matrix<i32> = col_vec![3, 4];
+    /// 
+    /// let mut c = Matrix::new(2, 1);
+    /// a.sub_into(&b, &mut c).unwrap();
+    /// 
+    /// assert_eq!(c, col_vec![-2, -2]);
+    /// ```
+    pub fn sub_into(&self, a: &Matrix<T>, out: &mut Matrix<T>) -> Result<()>
+    {
+        self.elementwise_into(a, out, |l, r| l - r)
+    }
+
+    /// Applies `op` to each pair of corresponding elements in `self` and `a`, 
+    /// writing the results into `out`.
+    fn elementwise_into(&self, a: &Matrix<T>, out: &mut Matrix<T>, op: impl Fn(T, T) -> T) -> Result<()>
+    {
+        if self.rows != a.rows || self.cols != a.cols
+        {
+            return Err(MatrixDimensionError { expected: (self.rows, self.cols), found: (a.rows, a.cols) }.into())
+        }
+
+        out.rows = self.rows;
+        out.cols = self.cols;
+        out.layout = Layout::RowMajor;
+        out.vals.clear();
+        out.vals.extend(
+            self.iter().zip(a.iter()).map(|(l, r)| op(l.clone(), r.clone()))
+        );
+
+        Ok(())
     }
 
     /// Creates a new `Matrix<T>` with the columns of `a` appended to
@@ -441,11 +783,11 @@ where T: Element<T>
             {
                 if j < self.cols
                 {
-                    b[(i, j)] = self[(i, j)];
+                    b[(i, j)] = self[(i, j)].clone();
                 }
                 else
                 {
-                    b[(i, j)] = a[(i, j - self.cols)];
+                    b[(i, j)] = a[(i, j - self.cols)].clone();
                 }
             }
         }
@@ -453,6 +795,101 @@ where T: Element<T>
         Ok(b)
     }
 
+    /// Creates a new block-diagonal `Matrix<T>` with the given
+    /// matrices placed along its diagonal and zeros elsewhere.
+    /// The blocks do not need to be square.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a: Matrix<i32> = Matrix::new_identity(2);
+    /// let b: Matrix<i32> = Matrix::from_vec(1, vec![3]).unwrap();
+    /// 
+    /// let c: Vec<i32> = Matrix::block_diag(&[a, b]).into();
+    /// 
+    /// assert_eq!(
+    ///     c,
+    ///     vec![1, 0, 0,
+    ///          0, 1, 0,
+    ///          0, 0, 3]
+    /// );
+    /// ```
+    pub fn block_diag(blocks: &[Matrix<T>]) -> Matrix<T>
+    {
+        let rows = blocks.iter().map(|b| b.rows).sum();
+        let cols = blocks.iter().map(|b| b.cols).sum();
+        let mut result = Matrix::new(rows, cols);
+
+        let (mut r0, mut c0) = (0, 0);
+        for block in blocks
+        {
+            for i in 0..block.rows
+            {
+                for j in 0..block.cols
+                {
+                    result[(r0 + i, c0 + j)] = block[(i, j)].clone();
+                }
+            }
+            r0 += block.rows;
+            c0 += block.cols;
+        }
+
+        result
+    }
+
+    /// Adds each element of `local` into this matrix at the row 
+    /// and column given by `row_map` and `col_map`, respectively.
+    /// Element `(i, j)` of `local` is added to element 
+    /// `(row_map[i], col_map[j])` of `self`.
+    /// 
+    /// This operation will fail without modifying `self` if the 
+    /// maps do not have one entry per row/column of `local` or if
+    /// any entry is out of bounds.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let mut global: Matrix<i32> = Matrix::new(3, 3);
+    /// let local: Matrix<i32> = Matrix::from_vec(
+    ///     2,
+    ///     vec![ 1, -1,
+    ///          -1,  1]
+    /// ).unwrap();
+    /// 
+    /// global.inplace_scatter_add(&local, &[0, 2], &[0, 2]).unwrap();
+    /// 
+    /// assert_eq!(
+    ///     Into::<Vec<i32>>::into(global),
+    ///     vec![ 1, 0, -1,
+    ///           0, 0,  0,
+    ///          -1, 0,  1]
+    /// );
+    /// ```
+    pub fn inplace_scatter_add(&mut self, local: &Matrix<T>, row_map: &[usize], col_map: &[usize]) -> Result<()>
+    {
+        if row_map.len() != local.rows || col_map.len() != local.cols
+        {
+            return Err(MatrixAssemblyError::IndexMapLengthMismatch.into())
+        }
+
+        if row_map.iter().any(|&i| i >= self.rows) || col_map.iter().any(|&j| j >= self.cols)
+        {
+            return Err(MatrixAssemblyError::IndexOutOfBounds.into())
+        }
+
+        for (i, &gi) in row_map.iter().enumerate()
+        {
+            for (j, &gj) in col_map.iter().enumerate()
+            {
+                self[(gi, gj)] += local[(i, j)].clone();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Creates a new `Matrix<T>` containing the rows in a range from `r1` to
     /// `r2` and columns in a range from `c1` to `c2`. 
     /// 
@@ -484,7 +921,7 @@ where T: Element<T>
         {
             for j in c1..c2+1
             {
-                b[(i-r1, j-c1)] = self[(i, j)];
+                b[(i-r1, j-c1)] = self[(i, j)].clone();
             }
         }
 
@@ -515,7 +952,7 @@ where T: Element<T>
         let mut total: T = T::zero();
         for i in 0..self.rows
         {
-            total += self[(i, i)];
+            total += self[(i, i)].clone();
         }
 
         Ok(total)
@@ -559,32 +996,104 @@ where T: Element<T>
         {    
             for j in 0..self.cols
             {
-                tspose[(j, i)] = self[(i, j)];
+                tspose[(j, i)] = self[(i, j)].clone();
             }
         }
 
         tspose
     }
 
+    /// Transposes the `Matrix<T>` without moving any of its 
+    /// elements by switching its memory layout between row-major
+    /// and column-major.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Layout, Matrix};
+    /// 
+    /// let a: Matrix<i32> = Matrix::from_vec(
+    ///     3, 
+    ///     vec![1, 2, 3, 
+    ///          4, 5, 6]
+    /// ).expect("Failed to create matrix");
+    /// 
+    /// let b = a.clone().transpose_view();
+    /// 
+    /// assert_eq!(b.get_layout(), Layout::ColumnMajor);
+    /// assert_eq!(b.as_slice(), a.as_slice());
+    /// assert_eq!(b, a.transpose());
+    /// ```
+    pub fn transpose_view(mut self) -> Matrix<T>
+    {
+        swap(&mut self.rows, &mut self.cols);
+        self.layout = match self.layout
+        {
+            Layout::RowMajor    => Layout::ColumnMajor,
+            Layout::ColumnMajor => Layout::RowMajor,
+        };
+
+        self
+    }
+
+    /// Returns the `Matrix<T>` with its elements stored in the given 
+    /// layout, rearranging them in memory only if the layout differs.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Layout, Matrix};
+    /// 
+    /// let a: Matrix<i32> = Matrix::from_vec(
+    ///     2, 
+    ///     vec![1, 2, 
+    ///          3, 4]
+    /// ).expect("Failed to create matrix");
+    /// 
+    /// let b = a.clone().into_layout(Layout::ColumnMajor);
+    /// 
+    /// assert_eq!(b.as_slice(), &[1, 3, 2, 4]);
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn into_layout(self, layout: Layout) -> Matrix<T>
+    {
+        if self.layout == layout
+        {
+            return self
+        }
+
+        let vals = match layout
+        {
+            Layout::RowMajor    => self.iter().cloned().collect(),
+            Layout::ColumnMajor => (0..self.rows * self.cols)
+                .map(|k| self[(k % self.rows, k / self.rows)].clone())
+                .collect(),
+        };
+
+        Matrix
+        {
+            rows: self.rows,
+            cols: self.cols,
+            vals,
+            layout,
+        }
+    }
+
     /// Attempts to invert a 2x2 `Matrix<T>` in-place.
     fn try_inplace_invert_2(&mut self) -> Result<()>
     {
-        let a11 = self[(0, 0)];
-        let a12 = self[(0, 1)];
-        let a21 = self[(1, 0)];
-        let a22 = self[(1, 1)];
+        let m = self.clone();
+        let a = |i: usize, j: usize| m[(i - 1, j - 1)].clone();
 
-        let det = a11*a22 - a12*a21;
+        let det = a(1,1)*a(2,2) - a(1,2)*a(2,1);
 
         if det == T::zero()
         {
             return Err(MatrixInversionError::DeterminantWasZero.into())
         }
         
-        self[(0, 0)] =   a22 / det;
-        self[(1, 0)] = - a21 / det;
-        self[(0, 1)] = - a12 / det;
-        self[(1, 1)] =   a11 / det;
+        self[(0, 0)] =   a(2,2) / det.clone();
+        self[(1, 0)] = - a(2,1) / det.clone();
+        self[(0, 1)] = - a(1,2) / det.clone();
+        self[(1, 1)] =   a(1,1) / det.clone();
 
         Ok(())
     }
@@ -592,33 +1101,26 @@ where T: Element<T>
     /// Attempts to invert a 3x3 `Matrix<T>` in-place.
     fn try_inplace_invert_3(&mut self) -> Result<()>
     {
-        let a11 = self[(0, 0)];
-        let a12 = self[(1, 0)];
-        let a13 = self[(2, 0)];
-        let a21 = self[(0, 1)];
-        let a22 = self[(1, 1)];
-        let a23 = self[(2, 1)];
-        let a31 = self[(0, 2)];
-        let a32 = self[(1, 2)];
-        let a33 = self[(2, 2)];
+        let m = self.clone();
+        let a = |i: usize, j: usize| m[(i - 1, j - 1)].clone();
 
-        let det  = a11*a22*a33 + a21*a32*a13 + a31*a12*a23 
-                 - a11*a32*a23 - a31*a22*a13 - a21*a12*a33;
+        let det  = a(1,1)*a(2,2)*a(3,3) + a(2,1)*a(3,2)*a(1,3) + a(3,1)*a(1,2)*a(2,3) 
+                 - a(1,1)*a(3,2)*a(2,3) - a(3,1)*a(2,2)*a(1,3) - a(2,1)*a(1,2)*a(3,3);
 
         if det == T::zero()
         {
             return Err(MatrixInversionError::DeterminantWasZero.into())
         }
 
-        self[(0, 0)] = (a22 * a33 - a23 * a32) / det;
-        self[(1, 0)] = (a23 * a31 - a21 * a33) / det;
-        self[(2, 0)] = (a21 * a32 - a22 * a31) / det;
-        self[(0, 1)] = (a13 * a32 - a12 * a33) / det;
-        self[(1, 1)] = (a11 * a33 - a13 * a31) / det;
-        self[(2, 1)] = (a12 * a31 - a11 * a32) / det;
-        self[(0, 2)] = (a12 * a23 - a13 * a22) / det;
-        self[(1, 2)] = (a13 * a21 - a11 * a23) / det;
-        self[(2, 2)] = (a11 * a22 - a12 * a21) / det;
+        self[(0, 0)] = (a(2,2) * a(3,3) - a(2,3) * a(3,2)) / det.clone();
+        self[(1, 0)] = (a(2,3) * a(3,1) - a(2,1) * a(3,3)) / det.clone();
+        self[(2, 0)] = (a(2,1) * a(3,2) - a(2,2) * a(3,1)) / det.clone();
+        self[(0, 1)] = (a(1,3) * a(3,2) - a(1,2) * a(3,3)) / det.clone();
+        self[(1, 1)] = (a(1,1) * a(3,3) - a(1,3) * a(3,1)) / det.clone();
+        self[(2, 1)] = (a(1,2) * a(3,1) - a(1,1) * a(3,2)) / det.clone();
+        self[(0, 2)] = (a(1,2) * a(2,3) - a(1,3) * a(2,2)) / det.clone();
+        self[(1, 2)] = (a(1,3) * a(2,1) - a(1,1) * a(2,3)) / det.clone();
+        self[(2, 2)] = (a(1,1) * a(2,2) - a(1,2) * a(2,1)) / det.clone();
 
         Ok(())
     }
@@ -626,53 +1128,39 @@ where T: Element<T>
     /// Attempts to invert a 4x4 `Matrix<T>` in-place.
     fn try_inplace_invert_4(&mut self) -> Result<()>
     {
-        let a11 = self[(0, 0)];
-        let a12 = self[(1, 0)];
-        let a13 = self[(2, 0)];
-        let a14 = self[(3, 0)];
-        let a21 = self[(0, 1)];
-        let a22 = self[(1, 1)];
-        let a23 = self[(2, 1)];
-        let a24 = self[(3, 1)];
-        let a31 = self[(0, 2)];
-        let a32 = self[(1, 2)];
-        let a33 = self[(2, 2)];
-        let a34 = self[(3, 2)];
-        let a41 = self[(0, 3)];
-        let a42 = self[(1, 3)];
-        let a43 = self[(2, 3)];
-        let a44 = self[(3, 3)];
-
-        let det  = a11*a22*a33*a44 + a11*a23*a34*a42 + a11*a24*a32*a43 +
-                   a12*a21*a34*a43 + a12*a23*a31*a44 + a12*a24*a33*a41 + 
-                   a13*a21*a32*a44 + a13*a22*a34*a41 + a13*a24*a31*a42 + 
-                   a14*a21*a33*a42 + a14*a22*a34*a43 + a14*a23*a32*a41 -
-                   a11*a22*a34*a43 - a11*a23*a32*a44 - a11*a24*a33*a42 -
-                   a12*a21*a33*a44 - a12*a23*a34*a41 - a12*a24*a31*a43 -
-                   a13*a21*a34*a42 - a13*a22*a31*a44 - a13*a24*a32*a41 -
-                   a14*a21*a32*a43 - a14*a22*a33*a41 - a14*a23*a31*a42;
+        let m = self.clone();
+        let a = |i: usize, j: usize| m[(j - 1, i - 1)].clone();
+
+        let det  = a(1,1)*a(2,2)*a(3,3)*a(4,4) + a(1,1)*a(2,3)*a(3,4)*a(4,2) + a(1,1)*a(2,4)*a(3,2)*a(4,3) +
+                   a(1,2)*a(2,1)*a(3,4)*a(4,3) + a(1,2)*a(2,3)*a(3,1)*a(4,4) + a(1,2)*a(2,4)*a(3,3)*a(4,1) + 
+                   a(1,3)*a(2,1)*a(3,2)*a(4,4) + a(1,3)*a(2,2)*a(3,4)*a(4,1) + a(1,3)*a(2,4)*a(3,1)*a(4,2) + 
+                   a(1,4)*a(2,1)*a(3,3)*a(4,2) + a(1,4)*a(2,2)*a(3,4)*a(4,3) + a(1,4)*a(2,3)*a(3,2)*a(4,1) -
+                   a(1,1)*a(2,2)*a(3,4)*a(4,3) - a(1,1)*a(2,3)*a(3,2)*a(4,4) - a(1,1)*a(2,4)*a(3,3)*a(4,2) -
+                   a(1,2)*a(2,1)*a(3,3)*a(4,4) - a(1,2)*a(2,3)*a(3,4)*a(4,1) - a(1,2)*a(2,4)*a(3,1)*a(4,3) -
+                   a(1,3)*a(2,1)*a(3,4)*a(4,2) - a(1,3)*a(2,2)*a(3,1)*a(4,4) - a(1,3)*a(2,4)*a(3,2)*a(4,1) -
+                   a(1,4)*a(2,1)*a(3,2)*a(4,3) - a(1,4)*a(2,2)*a(3,3)*a(4,1) - a(1,4)*a(2,3)*a(3,1)*a(4,2);
 
         if det == T::zero()
         {
             return Err(MatrixInversionError::DeterminantWasZero.into());
         }
 
-        self[(0, 0)] = (a22*a33*a44 + a23*a34*a42 + a24*a32*a43 - a22*a34*a43 - a23*a32*a44 - a24*a33*a42) / det;
-        self[(1, 0)] = (a12*a34*a43 + a13*a32*a44 + a14*a33*a42 - a12*a33*a44 - a13*a34*a42 - a14*a32*a43) / det;
-        self[(2, 0)] = (a12*a23*a44 + a13*a24*a42 + a14*a22*a43 - a12*a24*a43 - a13*a22*a44 - a14*a23*a42) / det;
-        self[(3, 0)] = (a12*a24*a33 + a13*a22*a34 + a14*a23*a32 - a12*a23*a34 - a13*a24*a32 - a14*a22*a33) / det;
-        self[(0, 1)] = (a21*a34*a43 + a23*a31*a44 + a24*a33*a41 - a21*a33*a44 - a23*a34*a41 - a24*a31*a43) / det;
-        self[(1, 1)] = (a11*a33*a44 + a13*a34*a41 + a14*a31*a43 - a11*a34*a43 - a13*a31*a44 - a14*a33*a41) / det;
-        self[(2, 1)] = (a11*a24*a43 + a13*a21*a44 + a14*a23*a41 - a11*a23*a44 - a13*a24*a41 - a14*a21*a43) / det;
-        self[(3, 1)] = (a11*a23*a34 + a13*a24*a31 + a14*a21*a33 - a11*a24*a33 - a13*a21*a34 - a14*a23*a31) / det;
-        self[(0, 2)] = (a21*a32*a44 + a22*a34*a41 + a24*a31*a42 - a21*a34*a42 - a22*a31*a44 - a24*a32*a41) / det;
-        self[(1, 2)] = (a11*a34*a42 + a12*a31*a44 + a14*a32*a41 - a11*a32*a44 - a12*a34*a41 - a14*a31*a42) / det;
-        self[(2, 2)] = (a11*a22*a44 + a12*a24*a41 + a14*a21*a42 - a11*a24*a42 - a12*a21*a44 - a14*a22*a41) / det;
-        self[(3, 2)] = (a11*a24*a32 + a12*a21*a34 + a14*a22*a31 - a11*a22*a34 - a12*a24*a31 - a14*a21*a32) / det;
-        self[(0, 3)] = (a21*a33*a42 + a22*a31*a43 + a23*a32*a41 - a21*a32*a43 - a22*a33*a41 - a23*a31*a42) / det;
-        self[(1, 3)] = (a11*a32*a43 + a12*a33*a41 + a13*a31*a42 - a11*a33*a42 - a12*a31*a43 - a13*a32*a41) / det;
-        self[(2, 3)] = (a11*a23*a42 + a12*a21*a43 + a13*a22*a41 - a11*a22*a43 - a12*a23*a41 - a13*a21*a42) / det;
-        self[(3, 3)] = (a11*a22*a33 + a12*a23*a31 + a13*a21*a32 - a11*a23*a32 - a12*a21*a33 - a13*a22*a31) / det;
+        self[(0, 0)] = (a(2,2)*a(3,3)*a(4,4) + a(2,3)*a(3,4)*a(4,2) + a(2,4)*a(3,2)*a(4,3) - a(2,2)*a(3,4)*a(4,3) - a(2,3)*a(3,2)*a(4,4) - a(2,4)*a(3,3)*a(4,2)) / det.clone();
+        self[(1, 0)] = (a(1,2)*a(3,4)*a(4,3) + a(1,3)*a(3,2)*a(4,4) + a(1,4)*a(3,3)*a(4,2) - a(1,2)*a(3,3)*a(4,4) - a(1,3)*a(3,4)*a(4,2) - a(1,4)*a(3,2)*a(4,3)) / det.clone();
+        self[(2, 0)] = (a(1,2)*a(2,3)*a(4,4) + a(1,3)*a(2,4)*a(4,2) + a(1,4)*a(2,2)*a(4,3) - a(1,2)*a(2,4)*a(4,3) - a(1,3)*a(2,2)*a(4,4) - a(1,4)*a(2,3)*a(4,2)) / det.clone();
+        self[(3, 0)] = (a(1,2)*a(2,4)*a(3,3) + a(1,3)*a(2,2)*a(3,4) + a(1,4)*a(2,3)*a(3,2) - a(1,2)*a(2,3)*a(3,4) - a(1,3)*a(2,4)*a(3,2) - a(1,4)*a(2,2)*a(3,3)) / det.clone();
+        self[(0, 1)] = (a(2,1)*a(3,4)*a(4,3) + a(2,3)*a(3,1)*a(4,4) + a(2,4)*a(3,3)*a(4,1) - a(2,1)*a(3,3)*a(4,4) - a(2,3)*a(3,4)*a(4,1) - a(2,4)*a(3,1)*a(4,3)) / det.clone();
+        self[(1, 1)] = (a(1,1)*a(3,3)*a(4,4) + a(1,3)*a(3,4)*a(4,1) + a(1,4)*a(3,1)*a(4,3) - a(1,1)*a(3,4)*a(4,3) - a(1,3)*a(3,1)*a(4,4) - a(1,4)*a(3,3)*a(4,1)) / det.clone();
+        self[(2, 1)] = (a(1,1)*a(2,4)*a(4,3) + a(1,3)*a(2,1)*a(4,4) + a(1,4)*a(2,3)*a(4,1) - a(1,1)*a(2,3)*a(4,4) - a(1,3)*a(2,4)*a(4,1) - a(1,4)*a(2,1)*a(4,3)) / det.clone();
+        self[(3, 1)] = (a(1,1)*a(2,3)*a(3,4) + a(1,3)*a(2,4)*a(3,1) + a(1,4)*a(2,1)*a(3,3) - a(1,1)*a(2,4)*a(3,3) - a(1,3)*a(2,1)*a(3,4) - a(1,4)*a(2,3)*a(3,1)) / det.clone();
+        self[(0, 2)] = (a(2,1)*a(3,2)*a(4,4) + a(2,2)*a(3,4)*a(4,1) + a(2,4)*a(3,1)*a(4,2) - a(2,1)*a(3,4)*a(4,2) - a(2,2)*a(3,1)*a(4,4) - a(2,4)*a(3,2)*a(4,1)) / det.clone();
+        self[(1, 2)] = (a(1,1)*a(3,4)*a(4,2) + a(1,2)*a(3,1)*a(4,4) + a(1,4)*a(3,2)*a(4,1) - a(1,1)*a(3,2)*a(4,4) - a(1,2)*a(3,4)*a(4,1) - a(1,4)*a(3,1)*a(4,2)) / det.clone();
+        self[(2, 2)] = (a(1,1)*a(2,2)*a(4,4) + a(1,2)*a(2,4)*a(4,1) + a(1,4)*a(2,1)*a(4,2) - a(1,1)*a(2,4)*a(4,2) - a(1,2)*a(2,1)*a(4,4) - a(1,4)*a(2,2)*a(4,1)) / det.clone();
+        self[(3, 2)] = (a(1,1)*a(2,4)*a(3,2) + a(1,2)*a(2,1)*a(3,4) + a(1,4)*a(2,2)*a(3,1) - a(1,1)*a(2,2)*a(3,4) - a(1,2)*a(2,4)*a(3,1) - a(1,4)*a(2,1)*a(3,2)) / det.clone();
+        self[(0, 3)] = (a(2,1)*a(3,3)*a(4,2) + a(2,2)*a(3,1)*a(4,3) + a(2,3)*a(3,2)*a(4,1) - a(2,1)*a(3,2)*a(4,3) - a(2,2)*a(3,3)*a(4,1) - a(2,3)*a(3,1)*a(4,2)) / det.clone();
+        self[(1, 3)] = (a(1,1)*a(3,2)*a(4,3) + a(1,2)*a(3,3)*a(4,1) + a(1,3)*a(3,1)*a(4,2) - a(1,1)*a(3,3)*a(4,2) - a(1,2)*a(3,1)*a(4,3) - a(1,3)*a(3,2)*a(4,1)) / det.clone();
+        self[(2, 3)] = (a(1,1)*a(2,3)*a(4,2) + a(1,2)*a(2,1)*a(4,3) + a(1,3)*a(2,2)*a(4,1) - a(1,1)*a(2,2)*a(4,3) - a(1,2)*a(2,3)*a(4,1) - a(1,3)*a(2,1)*a(4,2)) / det.clone();
+        self[(3, 3)] = (a(1,1)*a(2,2)*a(3,3) + a(1,2)*a(2,3)*a(3,1) + a(1,3)*a(2,1)*a(3,2) - a(1,1)*a(2,3)*a(3,2) - a(1,2)*a(2,1)*a(3,3) - a(1,3)*a(2,2)*a(3,1)) / det.clone();
 
         Ok(())
     }
@@ -698,8 +1186,8 @@ where T: Element<T>
                     {
                         return Err(MatrixInversionError::ZeroDuringInversion.into())
                     }
-                    let scalar = self[(i, j)] / self[(j, j)];
-                    self.inplace_scaled_row_add(i, j, -scalar);
+                    let scalar = self[(i, j)].clone() / self[(j, j)].clone();
+                    self.inplace_scaled_row_add(i, j, -scalar.clone());
                     inv.inplace_scaled_row_add(i, j, -scalar);
                 }
             }
@@ -707,8 +1195,8 @@ where T: Element<T>
 
         for i in 0..n
         {
-            let scalar: T = T::one() / self[(i, i)];
-            self.inplace_row_scale(i, scalar);
+            let scalar: T = T::one() / self[(i, i)].clone();
+            self.inplace_row_scale(i, scalar.clone());
             inv.inplace_row_scale(i, scalar);
         }
 
@@ -749,7 +1237,7 @@ where T: Element<T>
         }
 
         match self.rows {
-            1 => self.vals[0] = T::one() / self.vals[0],
+            1 => self.vals[0] = T::one() / self.vals[0].clone(),
             2 => self.try_inplace_invert_2()?,
             3 => self.try_inplace_invert_3()?,
             4 => self.try_inplace_invert_4()?,
@@ -759,6 +1247,141 @@ where T: Element<T>
         Ok(())
     }
 
+    /// Attempts to solve the linear system `Ax = b` for `x`, where `A` is this 
+    /// `Matrix<T>` and `b` has one column for each right hand side to solve for.
+    /// This is done by gaussian elimination of the augmented matrix `[A | b]`, 
+    /// so `A` is never inverted explicitly.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::{Matrix, col_vec};
+    /// 
+    /// let a = Matrix::from_vec(2, vec![0.0, 2.0, 
+    ///                                  1.0, 1.0]).unwrap();
+    /// 
+    /// let x = a.try_solve(&col_vec![4.0, 
+    ///                               3.0]).unwrap();
+    /// 
+    /// assert_eq!(Into::<Vec<f64>>::into(x), vec![1.0, 2.0]);
+    /// ```
+    pub fn try_solve(&self, b: &Matrix<T>) -> Result<Matrix<T>>
+    {
+        if self.rows != self.cols
+        {
+            return Err(NonSquareMatrixError.into())
+        }
+
+        let n = self.rows;
+        let mut ab = self.augment_with(b)?;
+
+        for j in 0..n
+        {
+            // Borrow a nonzero pivot from a later row if this row has none. Its earlier 
+            // columns are already eliminated, so adding it leaves them unchanged
+            if ab[(j, j)] == T::zero()
+            {
+                match (j + 1..n).find(|&i| ab[(i, j)] != T::zero())
+                {
+                    Some(i) => ab.inplace_row_add(j, i),
+                    None => return Err(MatrixInversionError::ZeroDuringInversion.into()),
+                }
+            }
+
+            for i in 0..n
+            {
+                if i == j
+                {
+                    continue;
+                }
+                let scalar = ab[(i, j)].clone() / ab[(j, j)].clone();
+                ab.inplace_scaled_row_add(i, j, -scalar);
+            }
+        }
+
+        for i in 0..n
+        {
+            let scalar: T = T::one() / ab[(i, i)].clone();
+            ab.inplace_row_scale(i, scalar);
+        }
+
+        Ok(ab.subset(0, n, n - 1, ab.cols - 1))
+    }
+
+}
+
+impl <T> Matrix<T>
+where T: Element<T> + PartialOrd + ToPrimitive
+{
+    /// Returns the 1-norm of a `Matrix<T>`, i.e. the largest sum 
+    /// of the absolute values of the elements in any of its columns.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a = Matrix::from_vec(2, vec![1, -4, 
+    ///                                  2,  3]).unwrap();
+    /// 
+    /// assert_eq!(a.norm_1(), 7);
+    /// ```
+    pub fn norm_1(&self) -> T
+    {
+        let mut norm = T::zero();
+        for j in 0..self.cols
+        {
+            let mut sum = T::zero();
+            for i in 0..self.rows
+            {
+                let x = self[(i, j)].clone();
+                sum += if x < T::zero() { -x } else { x };
+            }
+
+            if sum > norm
+            {
+                norm = sum;
+            }
+        }
+
+        norm
+    }
+
+    /// Attempts to invert a `Matrix<T>` in-place like `try_inplace_invert`, but also
+    /// checks the 1-norm condition number of the matrix, `kappa = ||A|| * ||A^-1||`.
+    /// 
+    /// If `kappa` is larger than `max_condition`, an `IllConditioned` error is returned 
+    /// to signal that the inverse is dominated by round-off (e.g. the jacobian of a 
+    /// nearly singular system). The matrix still holds the inverse in this case, so 
+    /// callers may treat the error as a warning.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// use gmatlib::error::MatrixInversionError;
+    /// 
+    /// let mut a = Matrix::from_vec(2, vec![1.0, 1.0, 
+    ///                                      1.0, 1.0 + 1E-12]).unwrap();
+    /// 
+    /// let err = a.try_inplace_invert_conditioned(1E8).unwrap_err();
+    /// 
+    /// match err.downcast_ref::<MatrixInversionError>()
+    /// {
+    ///     Some(MatrixInversionError::IllConditioned { kappa }) => assert!(*kappa > 1E12),
+    ///     _ => panic!("expected the matrix to be ill-conditioned"),
+    /// }
+    /// ```
+    pub fn try_inplace_invert_conditioned(&mut self, max_condition: f64) -> Result<()>
+    {
+        let norm = self.norm_1();
+        self.try_inplace_invert()?;
+
+        let kappa = (norm * self.norm_1()).to_f64().unwrap_or(f64::INFINITY);
+        if kappa.is_nan() || kappa > max_condition
+        {
+            return Err(MatrixInversionError::IllConditioned { kappa }.into())
+        }
+
+        Ok(())
+    }
 }
 
 /// Creates a new row vector `Matrix<T>`
@@ -0,0 +1,389 @@
+use std::array::from_fn;
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Sub, SubAssign};
+use anyhow::{Error, Result};
+use crate::{Element, Matrix};
+use crate::error::{MatrixDimensionError, MatrixInversionError};
+
+/// An `R` x `C` matrix whose size is known at compile time.
+///
+/// Unlike `Matrix<T>`, the elements are stored inline (i.e. on the
+/// stack when used as a local variable) so creating one never allocates.
+/// This makes `SMatrix` a better fit for the many tiny matrices (e.g.
+/// 1 x 1 gains or 2 x 1 potential differences) created while evaluating
+/// an element's flux. Use `Matrix::from` and `SMatrix::try_from` to move
+/// between the two types.
+///
+/// # Example
+/// ```
+/// use gmatlib::small::SMatrix;
+///
+/// let a: SMatrix<i32, 2, 3> = SMatrix::from_array([[1, 2, 3],
+///                                                  [4, 5, 6]]);
+/// let b: SMatrix<i32, 3, 1> = SMatrix::from_array([[1],
+///                                                  [0],
+///                                                  [1]]);
+///
+/// let c = a * b;
+/// assert_eq!(c, SMatrix::from_array([[4], [10]]));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SMatrix<T, const R: usize, const C: usize>
+where T: Element<T>
+{
+    vals: [[T; C]; R],
+}
+
+/// A 2 x 2 `SMatrix<T>`.
+pub type Matrix2<T> = SMatrix<T, 2, 2>;
+
+/// A 3 x 3 `SMatrix<T>`.
+pub type Matrix3<T> = SMatrix<T, 3, 3>;
+
+/// A 4 x 4 `SMatrix<T>`.
+pub type Matrix4<T> = SMatrix<T, 4, 4>;
+
+/// An `N` x 1 `SMatrix<T>`.
+pub type SColVec<T, const N: usize> = SMatrix<T, N, 1>;
+
+impl <T, const R: usize, const C: usize> SMatrix<T, R, C>
+where T: Element<T>
+{
+    /// Constructs a new `SMatrix<T, R, C>` with all indices initialized to `0`.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::SMatrix;
+    ///
+    /// let a: SMatrix<i32, 2, 2> = SMatrix::new();
+    /// assert_eq!(a.as_slice(), &[0, 0, 0, 0]);
+    /// ```
+    pub fn new() -> SMatrix<T, R, C>
+    {
+        SMatrix { vals: from_fn(|_| from_fn(|_| T::zero())) }
+    }
+
+    /// Constructs a new `SMatrix<T, R, C>` from an array of rows.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::SMatrix;
+    ///
+    /// let a = SMatrix::from_array([[1, 2],
+    ///                              [3, 4]]);
+    /// assert_eq!(a[(1, 0)], 3);
+    /// ```
+    pub fn from_array(vals: [[T; C]; R]) -> SMatrix<T, R, C>
+    {
+        SMatrix { vals }
+    }
+
+    /// Returns the number of rows in the matrix.
+    pub fn get_rows(&self) -> usize
+    {
+        R
+    }
+
+    /// Returns the number of columns in the matrix.
+    pub fn get_cols(&self) -> usize
+    {
+        C
+    }
+
+    /// Returns the elements of the matrix in row-major order.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::SMatrix;
+    ///
+    /// let a = SMatrix::from_array([[1, 2],
+    ///                              [3, 4]]);
+    /// assert_eq!(a.as_slice(), &[1, 2, 3, 4]);
+    /// ```
+    pub fn as_slice(&self) -> &[T]
+    {
+        self.vals.as_flattened()
+    }
+
+    /// Returns an iterator over the elements of the matrix in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_
+    {
+        self.as_slice().iter()
+    }
+
+    /// Returns the transpose of the matrix.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::SMatrix;
+    ///
+    /// let a = SMatrix::from_array([[1, 2, 3]]);
+    /// assert_eq!(a.transpose(), SMatrix::from_array([[1], [2], [3]]));
+    /// ```
+    pub fn transpose(&self) -> SMatrix<T, C, R>
+    {
+        SMatrix { vals: from_fn(|i| from_fn(|j| self.vals[j][i].clone())) }
+    }
+
+    /// Multiplies every element of the matrix by `scalar` in place.
+    pub fn inplace_scale(&mut self, scalar: T)
+    {
+        for x in self.vals.iter_mut().flatten()
+        {
+            *x *= scalar.clone();
+        }
+    }
+}
+
+impl <T, const N: usize> SMatrix<T, N, N>
+where T: Element<T>
+{
+    /// Constructs a new `N` x `N` identity matrix.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::Matrix2;
+    ///
+    /// let a: Matrix2<i32> = Matrix2::new_identity();
+    /// assert_eq!(a.as_slice(), &[1, 0, 0, 1]);
+    /// ```
+    pub fn new_identity() -> SMatrix<T, N, N>
+    {
+        SMatrix { vals: from_fn(|i| from_fn(|j| if i == j { T::one() } else { T::zero() })) }
+    }
+
+    /// Returns the sum of the elements on the main diagonal.
+    pub fn trace(&self) -> T
+    {
+        let mut trace = T::zero();
+        for i in 0..N
+        {
+            trace += self.vals[i][i].clone();
+        }
+        trace
+    }
+}
+
+impl <T, const R: usize, const C: usize> Default for SMatrix<T, R, C>
+where T: Element<T>
+{
+    fn default() -> Self
+    {
+        SMatrix::new()
+    }
+}
+
+impl <T, const R: usize, const C: usize> Display for SMatrix<T, R, C>
+where T: Element<T>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{}", Matrix::from(self.clone()))
+    }
+}
+
+impl <T, const R: usize, const C: usize> Add for SMatrix<T, R, C>
+where T: Element<T>
+{
+    type Output = SMatrix<T, R, C>;
+
+    /// Performs element-wise addition between all
+    /// elements of two matrices.
+    fn add(mut self, rhs: Self) -> Self::Output
+    {
+        self += rhs;
+        self
+    }
+}
+
+impl <T, const R: usize, const C: usize> AddAssign for SMatrix<T, R, C>
+where T: Element<T>
+{
+    fn add_assign(&mut self, rhs: Self)
+    {
+        for (l, r) in self.vals.iter_mut().flatten().zip(rhs.vals.into_iter().flatten())
+        {
+            *l += r;
+        }
+    }
+}
+
+impl <T, const R: usize, const C: usize> Sub for SMatrix<T, R, C>
+where T: Element<T>
+{
+    type Output = SMatrix<T, R, C>;
+
+    /// Performs element-wise subtraction between all
+    /// elements of two matrices.
+    fn sub(mut self, rhs: Self) -> Self::Output
+    {
+        self -= rhs;
+        self
+    }
+}
+
+impl <T, const R: usize, const C: usize> SubAssign for SMatrix<T, R, C>
+where T: Element<T>
+{
+    fn sub_assign(&mut self, rhs: Self)
+    {
+        for (l, r) in self.vals.iter_mut().flatten().zip(rhs.vals.into_iter().flatten())
+        {
+            *l -= r;
+        }
+    }
+}
+
+impl <T, const R: usize, const K: usize, const C: usize> Mul<SMatrix<T, K, C>> for SMatrix<T, R, K>
+where T: Element<T>
+{
+    type Output = SMatrix<T, R, C>;
+
+    /// Performs matrix multiplication. Unlike `Matrix<T>`, operands
+    /// of incompatible sizes are rejected at compile time.
+    fn mul(self, rhs: SMatrix<T, K, C>) -> Self::Output
+    {
+        SMatrix {
+            vals: from_fn(|i| from_fn(|j| {
+                let mut sum = T::zero();
+                for k in 0..K
+                {
+                    sum += self.vals[i][k].clone() * rhs.vals[k][j].clone();
+                }
+                sum
+            }))
+        }
+    }
+}
+
+impl <T, const R: usize, const C: usize> Mul<T> for SMatrix<T, R, C>
+where T: Element<T>
+{
+    type Output = SMatrix<T, R, C>;
+
+    /// Multiplies every element of the matrix by a scalar.
+    fn mul(mut self, rhs: T) -> Self::Output
+    {
+        self.inplace_scale(rhs);
+        self
+    }
+}
+
+impl <T, const R: usize, const C: usize> Index<(usize, usize)> for SMatrix<T, R, C>
+where T: Element<T>
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: (usize, usize)) -> &T
+    {
+        &self.vals[index.0][index.1]
+    }
+}
+
+impl <T, const R: usize, const C: usize> IndexMut<(usize, usize)> for SMatrix<T, R, C>
+where T: Element<T>
+{
+    #[inline]
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut T
+    {
+        &mut self.vals[index.0][index.1]
+    }
+}
+
+impl <T, const R: usize, const C: usize> From<SMatrix<T, R, C>> for Matrix<T>
+where T: Element<T>
+{
+    /// Moves the elements of an `SMatrix` onto the heap.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// use gmatlib::small::SMatrix;
+    ///
+    /// let a = Matrix::from(SMatrix::from_array([[1, 2], [3, 4]]));
+    /// assert_eq!(a, Matrix::from_vec(2, vec![1, 2, 3, 4]).unwrap());
+    /// ```
+    fn from(value: SMatrix<T, R, C>) -> Self
+    {
+        if C == 0
+        {
+            return Matrix::new(R, 0);
+        }
+
+        let vals: Vec<T> = value.vals.into_iter().flatten().collect();
+        Matrix::from_vec(C, vals).expect("an R x C array always has a multiple of C elements")
+    }
+}
+
+impl <T, const R: usize, const C: usize> TryFrom<&Matrix<T>> for SMatrix<T, R, C>
+where T: Element<T>
+{
+    type Error = Error;
+
+    /// Copies the elements of a `Matrix` into an `SMatrix`,
+    /// failing if the matrix is not exactly `R` x `C`.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::{Matrix, col_vec};
+    /// use gmatlib::small::SColVec;
+    ///
+    /// let a: Matrix<i32> = col_vec![1, 2];
+    ///
+    /// let b: SColVec<i32, 2> = SColVec::try_from(&a).unwrap();
+    /// assert_eq!(b[(1, 0)], 2);
+    ///
+    /// assert!(SColVec::<i32, 3>::try_from(&a).is_err());
+    /// ```
+    fn try_from(value: &Matrix<T>) -> Result<Self>
+    {
+        if value.get_rows() != R || value.get_cols() != C
+        {
+            return Err(MatrixDimensionError {
+                expected: (R, C),
+                found: (value.get_rows(), value.get_cols())
+            }.into())
+        }
+
+        Ok(SMatrix { vals: from_fn(|i| from_fn(|j| value[(i, j)].clone())) })
+    }
+}
+
+impl <T> Matrix2<T>
+where T: Element<T>
+{
+    /// Returns the determinant of the matrix.
+    pub fn determinant(&self) -> T
+    {
+        let [[a, b], [c, d]] = self.vals.clone();
+        a * d - b * c
+    }
+
+    /// Returns the inverse of the matrix, calculated directly
+    /// from its adjugate without any heap allocation.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::small::Matrix2;
+    ///
+    /// let a = Matrix2::from_array([[4.0, 7.0],
+    ///                              [2.0, 6.0]]);
+    ///
+    /// let i = a * a.try_inverse().unwrap();
+    /// assert!((i[(0, 0)] - 1.0_f64).abs() < 1E-12);
+    /// assert!(i[(0, 1)].abs() < 1E-12);
+    /// ```
+    pub fn try_inverse(&self) -> Result<Matrix2<T>>
+    {
+        let det = self.determinant();
+        if det == T::zero()
+        {
+            return Err(MatrixInversionError::DeterminantWasZero.into())
+        }
+
+        let [[a, b], [c, d]] = self.vals.clone();
+        Ok(SMatrix::from_array([[d / det.clone(), -b / det.clone()],
+                                [-c / det.clone(), a / det]]))
+    }
+}
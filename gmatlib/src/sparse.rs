@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+use anyhow::Result;
+use crate::{Element, Matrix};
+use crate::error::{MatrixDimensionError, MatrixInversionError, NonSquareMatrixError};
+
+/// An MxN matrix that only stores its nonzero elements, row by row.
+///
+/// This suits matrices where each row only has a handful of nonzero elements, e.g. the
+/// jacobian of a network where each node is only coupled to its neighbours. Linear systems
+/// are solved by elimination directly on the stored elements (see `try_solve`), so a
+/// `SparseMatrix<T>` with thousands of rows never needs a dense copy.
+///
+/// # Example
+/// ```
+/// use gmatlib::sparse::SparseMatrix;
+///
+/// let mut a = SparseMatrix::new(1000, 1000);
+/// a.set(0, 0, 2.0);
+/// a.set(999, 0, 1.0);
+///
+/// assert_eq!(a.get(999, 0), 1.0);
+/// assert_eq!(a.get(500, 500), 0.0);
+/// assert_eq!(a.nonzero_count(), 2);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMatrix<T>
+where T: Element<T>
+{
+    rows: usize,
+    cols: usize,
+    vals: Vec<BTreeMap<usize, T>>,
+}
+
+impl <T> SparseMatrix<T>
+where T: Element<T> + PartialOrd
+{
+    /// Constructs a new `SparseMatrix<T>` with every element equal to `0`.
+    pub fn new(rows: usize, cols: usize) -> SparseMatrix<T>
+    {
+        SparseMatrix { rows, cols, vals: vec![BTreeMap::new(); rows] }
+    }
+
+    /// Returns the number of rows in the matrix.
+    pub fn get_rows(&self) -> usize
+    {
+        self.rows
+    }
+
+    /// Returns the number of columns in the matrix.
+    pub fn get_cols(&self) -> usize
+    {
+        self.cols
+    }
+
+    /// Returns the number of elements that are stored, i.e. that are not `0`.
+    pub fn nonzero_count(&self) -> usize
+    {
+        self.vals.iter().map(BTreeMap::len).sum()
+    }
+
+    /// Returns the element at the given row and column.
+    ///
+    /// # Panics
+    /// If the index is outside of the matrix, like indexing a `Matrix<T>`.
+    pub fn get(&self, row: usize, col: usize) -> T
+    {
+        assert!(col < self.cols, "column index {col} is out of bounds for a matrix with {} columns", self.cols);
+        self.vals[row].get(&col).cloned().unwrap_or_else(T::zero)
+    }
+
+    /// Sets the element at the given row and column. Setting an element to `0` removes it.
+    ///
+    /// # Panics
+    /// If the index is outside of the matrix, like indexing a `Matrix<T>`.
+    pub fn set(&mut self, row: usize, col: usize, val: T)
+    {
+        assert!(col < self.cols, "column index {col} is out of bounds for a matrix with {} columns", self.cols);
+        match val == T::zero()
+        {
+            true => self.vals[row].remove(&col),
+            false => self.vals[row].insert(col, val),
+        };
+    }
+
+    /// Returns the nonzero elements of a row as `(column, value)` pairs, in column order.
+    pub fn row_iter(&self, row: usize) -> impl Iterator<Item = (usize, &T)> + '_
+    {
+        self.vals[row].iter().map(|(&col, val)| (col, val))
+    }
+
+    /// Converts the matrix into a dense `Matrix<T>`.
+    pub fn to_dense(&self) -> Matrix<T>
+    {
+        let mut dense = Matrix::new(self.rows, self.cols);
+        for (i, row) in self.vals.iter().enumerate()
+        {
+            for (&j, val) in row
+            {
+                dense[(i, j)] = val.clone();
+            }
+        }
+        dense
+    }
+
+    /// Attempts to solve the linear system `Ax = b` for `x`, where `A` is this
+    /// `SparseMatrix<T>`. This is done by gaussian elimination with partial pivoting,
+    /// which only ever visits the rows that have an element in the column being
+    /// eliminated, so banded or mostly-local systems are solved in close to linear time.
+    ///
+    /// # Example
+    /// ```
+    /// use gmatlib::sparse::SparseMatrix;
+    ///
+    /// // A tridiagonal system, e.g. a chain of springs
+    /// let n = 500;
+    /// let mut a = SparseMatrix::new(n, n);
+    /// for i in 0..n
+    /// {
+    ///     a.set(i, i, 2.0);
+    ///     if i > 0     { a.set(i, i - 1, -1.0); }
+    ///     if i < n - 1 { a.set(i, i + 1, -1.0); }
+    /// }
+    ///
+    /// let mut b = vec![0.0; n];
+    /// b[n - 1] = 1.0;
+    ///
+    /// let x = a.try_solve(&b).unwrap();
+    /// for (i, x) in x.iter().enumerate()
+    /// {
+    ///     assert!((x - (i + 1) as f64 / (n + 1) as f64).abs() < 1E-9);
+    /// }
+    /// ```
+    pub fn try_solve(&self, b: &[T]) -> Result<Vec<T>>
+    {
+        if self.rows != self.cols
+        {
+            return Err(NonSquareMatrixError.into())
+        }
+
+        if b.len() != self.rows
+        {
+            return Err(MatrixDimensionError { expected: (self.rows, 1), found: (b.len(), 1) }.into())
+        }
+
+        let n = self.rows;
+        let mut vals = self.vals.clone();
+        let mut b = b.to_vec();
+
+        // The rows that have an element in each column, kept up to date as elements fill in
+        let mut col_rows = vec![BTreeSet::new(); n];
+        for (i, row) in vals.iter().enumerate()
+        {
+            for &j in row.keys()
+            {
+                col_rows[j].insert(i);
+            }
+        }
+
+        let abs = |x: &T| if *x < T::zero() { -x.clone() } else { x.clone() };
+        let mut pivots = vec![0; n];
+        let mut is_pivot = vec![false; n];
+
+        for j in 0..n
+        {
+            // Pivot on the largest element in this column from a row that has not been used yet
+            let pivot = col_rows[j].iter()
+                .filter(|&&i| !is_pivot[i])
+                .map(|&i| (i, abs(&vals[i][&j])))
+                .fold(None, |best: Option<(usize, T)>, (i, x)| match best
+                {
+                    Some((_, ref y)) if *y >= x => best,
+                    _ => Some((i, x)),
+                });
+
+            let p = match pivot
+            {
+                Some((p, _)) => p,
+                None => return Err(MatrixInversionError::ZeroDuringInversion.into()),
+            };
+            pivots[j] = p;
+            is_pivot[p] = true;
+
+            let pivot_row: Vec<(usize, T)> = vals[p].iter()
+                .filter(|(&k, _)| k != j)
+                .map(|(&k, x)| (k, x.clone()))
+                .collect();
+            let pivot_val = vals[p][&j].clone();
+            let pivot_b = b[p].clone();
+
+            let eliminated: Vec<usize> = col_rows[j].iter().copied().filter(|&i| !is_pivot[i]).collect();
+            for i in eliminated
+            {
+                let scalar = match vals[i].remove(&j)
+                {
+                    Some(x) => x / pivot_val.clone(),
+                    None => continue,
+                };
+                col_rows[j].remove(&i);
+
+                for (k, x) in &pivot_row
+                {
+                    let updated = vals[i].get(k).cloned().unwrap_or_else(T::zero) - scalar.clone() * x.clone();
+                    match updated == T::zero()
+                    {
+                        true =>
+                        {
+                            vals[i].remove(k);
+                            col_rows[*k].remove(&i);
+                        },
+                        false =>
+                        {
+                            vals[i].insert(*k, updated);
+                            col_rows[*k].insert(i);
+                        },
+                    }
+                }
+
+                b[i] -= scalar * pivot_b.clone();
+            }
+        }
+
+        // Each pivot row now only has elements in its own and later columns
+        let mut x = vec![T::zero(); n];
+        for j in (0..n).rev()
+        {
+            let p = pivots[j];
+            let mut sum = b[p].clone();
+            for (&k, val) in vals[p].range(j + 1..)
+            {
+                sum -= val.clone() * x[k].clone();
+            }
+            x[j] = sum / vals[p][&j].clone();
+        }
+
+        Ok(x)
+    }
+}
+
+impl <T> From<&Matrix<T>> for SparseMatrix<T>
+where T: Element<T> + PartialOrd
+{
+    /// Stores the nonzero elements of a dense `Matrix<T>`.
+    fn from(value: &Matrix<T>) -> SparseMatrix<T>
+    {
+        let mut sparse = SparseMatrix::new(value.get_rows(), value.get_cols());
+        for i in 0..value.get_rows()
+        {
+            for j in 0..value.get_cols()
+            {
+                sparse.set(i, j, value[(i, j)].clone());
+            }
+        }
+        sparse
+    }
+}
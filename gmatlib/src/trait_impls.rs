@@ -1,7 +1,9 @@
+use std::cmp::Ordering;
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 use std::iter::zip;
 use std::ops::{Add, AddAssign, BitOr, Index, IndexMut, Mul, Sub, SubAssign};
-use crate::{Matrix, Element};
+use crate::{Element, Layout, Matrix};
 
 impl <T> Display for Matrix<T>
 where T: Element<T>
@@ -31,6 +33,134 @@ where T: Element<T>
     }
 }
 
+impl <T> Clone for Matrix<T>
+where T: Element<T>
+{
+    fn clone(&self) -> Self
+    {
+        Matrix
+        {
+            rows: self.rows,
+            cols: self.cols,
+            vals: self.vals.clone(),
+            layout: self.layout,
+        }
+    }
+
+    /// Copies `source` into this matrix, reusing its allocation
+    /// whenever it is large enough. This is also what
+    /// `source.clone_into(&mut self)` calls.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let a: Matrix<i32> = Matrix::new_identity(2);
+    /// let mut b: Matrix<i32> = Matrix::new(3, 3);
+    /// 
+    /// a.clone_into(&mut b);
+    /// assert_eq!(a, b);
+    /// ```
+    fn clone_from(&mut self, source: &Self)
+    {
+        self.rows = source.rows;
+        self.cols = source.cols;
+        self.vals.clone_from(&source.vals);
+        self.layout = source.layout;
+    }
+}
+
+impl <T> PartialEq for Matrix<T>
+where T: Element<T>
+{
+    /// Compares two matrices element-by-element, regardless 
+    /// of how each one is laid out in memory.
+    fn eq(&self, other: &Self) -> bool 
+    {
+        self.rows == other.rows &&
+        self.cols == other.cols &&
+        zip(self.iter(), other.iter()).all(|(l, r)| l == r)
+    }
+}
+
+impl <T> Eq for Matrix<T>
+where T: Element<T> + Eq
+{}
+
+impl <T> Hash for Matrix<T>
+where T: Element<T> + Hash
+{
+    /// Hashes the shape of the matrix and then its elements in row-major 
+    /// order, so that equal matrices hash equally regardless of their layout.
+    /// 
+    /// Elements are hashed exactly, with no tolerance. To key a cache with a
+    /// matrix of floats, which are not `Hash`, see `key::MatrixKey`.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::collections::HashSet;
+    /// use gmatlib::{Layout, Matrix};
+    /// 
+    /// let a: Matrix<i32> = Matrix::from_vec(2, vec![1, 2, 3, 4]).unwrap();
+    /// let b = a.clone().into_layout(Layout::ColumnMajor);
+    /// 
+    /// let set: HashSet<Matrix<i32>> = [a, b].into();
+    /// assert_eq!(set.len(), 1);
+    /// ```
+    fn hash<H: Hasher>(&self, state: &mut H)
+    {
+        self.rows.hash(state);
+        self.cols.hash(state);
+        for val in self.iter()
+        {
+            val.hash(state);
+        }
+    }
+}
+
+impl <T> PartialOrd for Matrix<T>
+where T: Element<T> + PartialOrd
+{
+    /// Orders matrices by their shape (rows, then columns) and then by their 
+    /// elements in row-major order, regardless of their layout. Matrices of the 
+    /// same shape whose elements cannot be compared (e.g. `NaN`s) are unordered.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        match (self.rows, self.cols).cmp(&(other.rows, other.cols))
+        {
+            Ordering::Equal => self.iter().partial_cmp(other.iter()),
+            ord => Some(ord),
+        }
+    }
+}
+
+impl <T> Ord for Matrix<T>
+where T: Element<T> + Ord
+{
+    /// Orders matrices by their shape (rows, then columns) and then by 
+    /// their elements in row-major order, regardless of their layout.
+    /// 
+    /// # Example
+    /// ```
+    /// use gmatlib::Matrix;
+    /// 
+    /// let mut matrices: Vec<Matrix<i32>> = vec![
+    ///     Matrix::from_vec(2, vec![1, 2, 3, 4]).unwrap(),
+    ///     Matrix::from_col_vec(vec![9, 9]),
+    ///     Matrix::from_vec(2, vec![1, 2, 0, 5]).unwrap(),
+    /// ];
+    /// matrices.sort();
+    /// 
+    /// assert_eq!(matrices[0], Matrix::from_col_vec(vec![9, 9]));
+    /// assert_eq!(matrices[1], Matrix::from_vec(2, vec![1, 2, 0, 5]).unwrap());
+    /// ```
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        (self.rows, self.cols).cmp(&(other.rows, other.cols))
+            .then_with(|| self.iter().cmp(other.iter()))
+    }
+}
+
 impl <T> BitOr for &Matrix<T>
 where T: Element<T>
 {
@@ -225,8 +355,8 @@ where T: Element<T>
 
         let mut ret_val = Matrix::new(self.rows, self.cols);
         ret_val.vals = Vec::from_iter(
-            zip(&self.vals, &rhs.vals)
-                .map(|(l, r)| *l + *r)
+            zip(self.iter(), rhs.iter())
+                .map(|(l, r)| l.clone() + r.clone())
         );
         ret_val
     }
@@ -245,7 +375,7 @@ where T: Element<T>
 
         for (i, elem) in rhs.iter().enumerate()
         {
-            self.vals[i] += *elem;
+            self[(i / rhs.cols, i % rhs.cols)] += elem.clone();
         }
     }
 }
@@ -408,8 +538,8 @@ where T: Element<T>
 
         let mut ret_val = Matrix::new(self.rows, self.cols);
         ret_val.vals = Vec::from_iter(
-            zip(&self.vals, &rhs.vals)
-                .map(|(l, r)| *l - *r)
+            zip(self.iter(), rhs.iter())
+                .map(|(l, r)| l.clone() - r.clone())
         );
         ret_val
     }
@@ -428,7 +558,7 @@ where T: Element<T>
 
         for (i, elem) in rhs.iter().enumerate()
         {
-            self.vals[i] -= *elem;
+            self[(i / rhs.cols, i % rhs.cols)] -= elem.clone();
         }
     }
 }
@@ -729,7 +859,7 @@ where T: Element<T>
     /// assert_eq!(five, a_vec[4]);
     /// ```
     fn from(value: Matrix<T>) -> Self {
-        value.vals
+        value.into_layout(Layout::RowMajor).vals
     }
 }
 
@@ -770,7 +900,7 @@ where T: Element<T>
         {
             panic!("index out of bounds: the matrix has {} rows and {} cols but the index was [({}, {})]", self.rows, self.cols, index.0, index.1)
         }
-        &(self.vals[index.0 * self.cols + index.1])
+        &(self.vals[self.offset(index.0, index.1)])
     }
 }
 
@@ -805,6 +935,7 @@ where T: Element<T>
     #[inline]
     fn index_mut(&mut self, index: (usize, usize)) -> &mut T 
     {
-        &mut (self.vals[index.0 * self.cols + index.1])
+        let offset = self.offset(index.0, index.1);
+        &mut (self.vals[offset])
     }
 }
@@ -136,4 +136,282 @@ fn ensure_inplace_transpose_method_works_on_case_not_in_doctest()
              2, 5,
              3, 6]
     );
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn ensure_that_matrix_of_decimals_inverts_exactly()
+{
+    use rust_decimal::Decimal;
+
+    let a: Matrix<Decimal> = Matrix::from_vec(
+        2,
+        vec![Decimal::from(4), Decimal::from(7),
+             Decimal::from(2), Decimal::from(6)]
+    ).unwrap();
+
+    let mut inv = a.clone();
+    inv.try_inplace_invert().unwrap();
+    let prod: Vec<Decimal> = (a * inv).into();
+
+    assert_eq!(
+        prod,
+        vec![Decimal::ONE, Decimal::ZERO,
+             Decimal::ZERO, Decimal::ONE]
+    );
+}
+
+#[test]
+fn ensure_that_column_major_matrices_behave_like_row_major_ones()
+{
+    use gmatlib::Layout;
+
+    let a: Matrix<i32> = Matrix::from_vec(
+        3,
+        vec![1, 2, 3,
+             4, 5, 6]
+    ).unwrap();
+    let b = a.clone().into_layout(Layout::ColumnMajor);
+
+    assert_eq!(b.as_slice(), &[1, 4, 2, 5, 3, 6]);
+    assert_eq!(b.get_row_stride(), 1);
+    assert_eq!(b.get_col_stride(), 2);
+
+    let sum: Vec<i32> = (&a + &b).into();
+    assert_eq!(sum, vec![2, 4, 6, 8, 10, 12]);
+
+    let prod: Vec<i32> = (b.transpose_view() * a).into();
+    assert_eq!(
+        prod,
+        vec![17, 22, 27,
+             22, 29, 36,
+             27, 36, 45]
+    );
+}
+
+#[test]
+fn ensure_that_ffi_rejects_stale_matrix_handles()
+{
+    use gmatlib::ffi::*;
+
+    let a = new_double_identity_matrix(2);
+    assert_ne!(a, 0);
+    assert_eq!(index_double_matrix(a, 1, 1), 1.0);
+
+    assert_eq!(free_double_matrix(a), 1);
+    assert_eq!(free_double_matrix(a), 0); // double-free is reported
+    assert_eq!(index_mut_double_matrix(a, 0, 0, 2.0), 0); // so is use-after-free
+
+    // Reusing the freed slot must not revive the old handle
+    let b = new_double_matrix(2, 2);
+    assert_ne!(a, b);
+    assert_eq!(index_double_matrix(a, 0, 0), f64::MIN);
+    assert_eq!(free_double_matrix(b), 1);
+}
+
+#[test]
+fn ensure_that_scatter_add_rejects_bad_index_maps_without_mutating()
+{
+    let mut a: Matrix<i32> = Matrix::new_identity(2);
+    let local: Matrix<i32> = Matrix::new_identity(2);
+
+    assert!(a.inplace_scatter_add(&local, &[0], &[0, 1]).is_err());
+    assert!(a.inplace_scatter_add(&local, &[0, 2], &[0, 1]).is_err());
+    assert_eq!(a, Matrix::new_identity(2));
+}
+
+#[test]
+fn ensure_small_matrix_round_trips_and_matches_matrix()
+{
+    use gmatlib::small::{Matrix3, SColVec};
+
+    let a = Matrix::from_vec(
+        3,
+        vec![ 1.0,  2.0, -1.0,
+              2.0,  1.0,  2.0,
+             -1.0,  2.0,  1.0]
+    ).unwrap();
+    let x = Matrix::from_col_vec(vec![1.0, 2.0, 3.0]);
+
+    let sa = Matrix3::try_from(&a).unwrap();
+    let sx = SColVec::<f64, 3>::try_from(&x).unwrap();
+
+    assert_eq!(Matrix::from(sa * sx), a.multiply_matrix(&x).unwrap());
+    assert_eq!(Matrix::from(sa), a);
+    assert!(Matrix3::try_from(&x).is_err());
+}
+
+#[test]
+fn ensure_that_ffi_solves_linear_systems_without_inverting()
+{
+    use gmatlib::ffi::*;
+
+    // A zero in the first pivot requires a row swap
+    let a = new_double_matrix(3, 3);
+    for (i, j, v) in [(0, 1, 1.0), (0, 2, 1.0), (1, 0, 2.0), (1, 2, 1.0), (2, 0, 1.0), (2, 1, 1.0)]
+    {
+        assert_eq!(index_mut_double_matrix(a, i, j, v), 1);
+    }
+    let b = new_double_matrix(3, 1);
+    for (i, v) in [(0, 5.0), (1, 5.0), (2, 3.0)]
+    {
+        assert_eq!(index_mut_double_matrix(b, i, 0, v), 1);
+    }
+
+    let x = solve_linear_system(a, b);
+    assert_ne!(x, 0);
+    for (i, v) in [(0, 1.0), (1, 2.0), (2, 3.0)]
+    {
+        assert!((index_double_matrix(x, i, 0) - v).abs() < 1E-12);
+    }
+
+    // Singular systems and mismatched sizes are reported as failures
+    let singular = new_double_matrix(3, 3);
+    let short = new_double_matrix(2, 1);
+    assert_eq!(solve_linear_system(singular, b), 0);
+    assert_eq!(solve_linear_system(a, short), 0);
+
+    for handle in [a, b, x, singular, short]
+    {
+        assert_eq!(free_double_matrix(handle), 1);
+    }
+}
+
+#[test]
+fn ensure_that_conditioned_inversion_flags_nearly_singular_matrices()
+{
+    use gmatlib::ffi::*;
+
+    // A well-conditioned matrix inverts as usual
+    let a = new_double_identity_matrix(3);
+    assert_eq!(index_mut_double_matrix(a, 0, 0, 2.0), 1);
+    assert_eq!(try_inplace_invert_conditioned(a, 10.0), u32::MAX);
+    assert_eq!(index_double_matrix(a, 0, 0), 0.5);
+
+    // The columns of this one are nearly parallel
+    let b = new_double_matrix(5, 5);
+    for i in 0..5
+    {
+        for j in 0..5
+        {
+            let v = if i == j { 1.0 + 1E-10 } else { 1.0 };
+            assert_eq!(index_mut_double_matrix(b, i, j, v), 1);
+        }
+    }
+    assert_eq!(try_inplace_invert_conditioned(b, 1E8), 4);
+
+    // ...but it is still inverted, so the error can be treated as a warning
+    assert!(index_double_matrix(b, 0, 0) > 1E9);
+
+    for handle in [a, b]
+    {
+        assert_eq!(free_double_matrix(handle), 1);
+    }
+}
+
+#[test]
+fn ensure_that_sparse_solve_matches_dense_solve_when_pivoting_is_needed()
+{
+    use gmatlib::sparse::SparseMatrix;
+
+    // The first diagonal element is zero, so a row must be swapped in
+    let a = Matrix::from_vec(4, vec![0.0, 2.0, 0.0, 1.0,
+                                     3.0, 0.0, 1.0, 0.0,
+                                     0.0, 1.0, 4.0, 0.0,
+                                     1.0, 0.0, 0.0, 5.0]).unwrap();
+    let b = vec![1.0, 2.0, 3.0, 4.0];
+
+    let sparse = SparseMatrix::from(&a);
+    assert_eq!(sparse.nonzero_count(), 8);
+    assert_eq!(sparse.to_dense(), a);
+
+    let x = sparse.try_solve(&b).unwrap();
+    let expected: Vec<f64> = a.try_solve(&Matrix::from_col_vec(b)).unwrap().into();
+    for (x, expected) in x.iter().zip(expected)
+    {
+        assert!((x - expected).abs() < 1E-12);
+    }
+
+    // A column with no elements can't be solved for
+    let mut singular = SparseMatrix::new(2, 2);
+    singular.set(0, 0, 1.0);
+    singular.set(1, 0, 1.0);
+    assert!(singular.try_solve(&[1.0, 2.0]).is_err());
+}
+
+#[test]
+fn ensure_that_matrices_key_maps_regardless_of_layout()
+{
+    use std::collections::{BTreeSet, HashMap};
+    use gmatlib::Layout;
+    use gmatlib::key::MatrixKey;
+
+    let a: Matrix<i64> = Matrix::from_vec(3, vec![1, 2, 3, 4, 5, 6]).unwrap();
+    let b = a.clone().into_layout(Layout::ColumnMajor);
+    let c: Matrix<i64> = Matrix::from_vec(2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+
+    let mut counts = HashMap::new();
+    for m in [&a, &b, &c]
+    {
+        *counts.entry(m.clone()).or_insert(0) += 1;
+    }
+    assert_eq!(counts[&a], 2);
+    assert_eq!(counts[&c], 1);
+
+    // Shape is compared before data, so the 2x3 matrix sorts before the 3x2 one
+    let sorted: Vec<Matrix<i64>> = BTreeSet::from([c.clone(), a.clone()]).into_iter().collect();
+    assert_eq!(sorted, vec![a, c]);
+
+    // Float keys are exact, and stay consistent across layouts
+    let x = Matrix::from_vec(2, vec![0.0, f64::NAN, 1.5, -2.0]).unwrap();
+    let y = x.clone().into_layout(Layout::ColumnMajor);
+    let z = Matrix::from_vec(2, vec![-0.0, f64::NAN, 1.5, -2.0]).unwrap();
+
+    let keys = BTreeSet::from([MatrixKey(x), MatrixKey(y), MatrixKey(z)]);
+    assert_eq!(keys.len(), 2);
+    assert!(keys.iter().next().unwrap().0[(0, 0)].is_sign_negative());
+}
+
+#[test]
+fn ensure_that_matrices_copy_borrowed_buffers_in_row_major_order()
+{
+    use gmatlib::ffi::*;
+
+    // Column-major matrices are still filled in row-major order
+    let mut a = Matrix::from_col_major_vec(2, vec![0.0; 4]).unwrap();
+    a.copy_from_slice(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+    assert_eq!(a, Matrix::from_slice(2, 2, &[1.0, 2.0, 3.0, 4.0]).unwrap());
+    assert_eq!(a.as_slice(), &[1.0, 3.0, 2.0, 4.0]);
+
+    let buffer = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let b = unsafe { new_double_matrix_from_buffer(buffer.as_ptr(), 3, 2) };
+    assert_ne!(b, 0);
+    assert_eq!(index_double_matrix(b, 2, 1), 6.0);
+
+    unsafe
+    {
+        assert_eq!(copy_buffer_into_double_matrix(b, buffer.as_ptr(), 5), 0);
+        assert_eq!(copy_buffer_into_double_matrix(b, std::ptr::null(), 6), 0);
+        assert_eq!(copy_buffer_into_double_matrix(b, [6.0, 5.0, 4.0, 3.0, 2.0, 1.0].as_ptr(), 6), 1);
+    }
+    assert_eq!(index_double_matrix(b, 0, 0), 6.0);
+    assert_eq!(free_double_matrix(b), 1);
+}
+
+#[test]
+fn ensure_that_non_symmetric_3x3_matrices_invert_exactly()
+{
+    let a = Matrix::from_vec(
+        3,
+        vec![2.0, 1.0, 0.0,
+             0.0, 4.0, 0.0,
+             2.0, 0.0, 1.0]
+    ).unwrap();
+
+    let mut inv = a.clone();
+    inv.try_inplace_invert().unwrap();
+
+    assert_eq!(&a * &inv, Matrix::new_identity(3));
+    assert_eq!(inv[(0, 1)], -0.125);
+    assert_eq!(inv[(1, 0)], 0.0);
+}
@@ -0,0 +1,241 @@
+// Std modules
+use std::collections::{BTreeMap, HashMap};
+
+// 3rd party modules
+use serde::{Deserialize, Serialize};
+
+// Local modules
+use crate::errors::StudyAuditError;
+use crate::modelling::NodalAnalysisModel;
+use crate::options::SolverOptions;
+use crate::{NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// The name of the solver used by `run_study`.
+const _SOLVER_: &str = "multivariate_newton_raphson";
+
+/// Calculates a 64-bit FNV-1a hash of a model.
+///
+/// The model is first converted into a `serde_json::Value`, which stores
+/// objects with sorted keys, so the hash does not depend on the order
+/// that nodes were configured in.
+fn hash_model(model: &NodalAnalysisModel) -> anyhow::Result<String>
+{
+    let canonical = serde_json::to_value(model)?.to_string();
+
+    let hash = canonical.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    Ok(format!("{hash:016x}"))
+}
+
+/// A record of every input that affects the result of a study.
+/// 
+/// Every solver setting is recorded except `verbose`, which only changes what is logged.
+/// The damping is recorded as it is written in Rust, e.g. `Backtracking { min_step: 0.001 }`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct StudyAudit
+{
+    model_hash: String,
+    margin: f64,
+    limit: usize,
+    damping: String,
+    initial_guess: f64,
+    step: f64,
+    solver: String,
+    jacobian: String,
+    versions: BTreeMap<String, String>,
+}
+impl StudyAudit
+{
+    /// Records the inputs to a study of `model` solved with the given `margin` and `limit`,
+    /// and the default for every other solver setting, with a dense jacobian.
+    pub fn new(model: &NodalAnalysisModel, margin: f64, limit: usize) -> anyhow::Result<StudyAudit>
+    {
+        StudyAudit::with_options(model, &SolverOptions::new(margin, limit), false)
+    }
+
+    /// Records the inputs to a study of `model` solved with the settings in `options`, 
+    /// approximating a sparse jacobian if `sparse_jacobian` is set (see 
+    /// `NodalAnalysisStudyBuilder::with_sparse_jacobian`).
+    pub fn with_options(model: &NodalAnalysisModel, options: &SolverOptions, sparse_jacobian: bool) -> anyhow::Result<StudyAudit>
+    {
+        Ok(StudyAudit
+        {
+            model_hash: hash_model(model)?,
+            margin: options.margin,
+            limit: options.limit,
+            damping: format!("{:?}", options.damping),
+            initial_guess: options.initial_guess,
+            step: options.step,
+            solver: _SOLVER_.to_string(),
+            jacobian: match sparse_jacobian
+            {
+                true => "sparse".to_string(),
+                false => "dense".to_string(),
+            },
+            versions: BTreeMap::from([
+                ("neapolitan".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+                ("geqslib".to_string(),    geqslib::VERSION.to_string()),
+                ("gmatlib".to_string(),    gmatlib::VERSION.to_string()),
+            ]),
+        })
+    }
+
+    /// Returns the hash of the model that was studied.
+    pub fn get_model_hash(&self) -> &str
+    {
+        &self.model_hash
+    }
+
+    /// Checks that `other` records exactly the same inputs as `self`, returning
+    /// a `StudyAuditError::InputMismatch` naming the first input that differs.
+    pub fn verify_inputs(&self, other: &StudyAudit) -> anyhow::Result<()>
+    {
+        let mismatch = if self.model_hash != other.model_hash { Some("model_hash") }
+            else if self.margin != other.margin { Some("margin") }
+            else if self.limit != other.limit { Some("limit") }
+            else if self.damping != other.damping { Some("damping") }
+            else if self.initial_guess != other.initial_guess { Some("initial_guess") }
+            else if self.step != other.step { Some("step") }
+            else if self.solver != other.solver { Some("solver") }
+            else if self.jacobian != other.jacobian { Some("jacobian") }
+            else if self.versions != other.versions { Some("versions") }
+            else { None };
+
+        match mismatch
+        {
+            Some(input) => Err(StudyAuditError::InputMismatch(input.to_string()).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The result of a study along with a record of the inputs that produced it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditedStudyResult
+{
+    audit: StudyAudit,
+    result: NodalAnalysisStudyResult,
+}
+impl AuditedStudyResult
+{
+    /// Returns the record of the study's inputs.
+    pub fn get_audit(&self) -> &StudyAudit
+    {
+        &self.audit
+    }
+
+    /// Returns the result of the study.
+    pub fn get_result(&self) -> &NodalAnalysisStudyResult
+    {
+        &self.result
+    }
+
+    /// Checks that this result was produced by the same inputs as `previous` and that
+    /// every nodal potential and elemental flux matches it to within the study's margin.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    ///
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![2.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap();
+    ///
+    /// let first = builder.clone().run_audited_study(1E-6, 100).unwrap();
+    /// let second = builder.clone().run_audited_study(1E-6, 100).unwrap();
+    /// assert!(second.verify_against(&first).is_ok());
+    ///
+    /// // Changing a tolerance is caught as a change of inputs
+    /// let looser = builder.run_audited_study(1E-3, 100).unwrap();
+    /// assert!(looser.verify_against(&first).is_err());
+    /// ```
+    pub fn verify_against(&self, previous: &AuditedStudyResult) -> anyhow::Result<()>
+    {
+        self.audit.verify_inputs(&previous.audit)?;
+
+        let tolerance = self.audit.margin;
+        let nodes = compare(&self.result.nodes, &previous.result.nodes, tolerance);
+        let elements = compare(&self.result.elements, &previous.result.elements, tolerance);
+
+        match nodes.or(elements)
+        {
+            Some(key) => Err(StudyAuditError::ResultMismatch(key).into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the first key (in sorted order) whose values are missing or
+/// differ by more than `tolerance` between `current` and `previous`.
+fn compare<K>(current: &HashMap<K, Vec<f64>>, previous: &HashMap<K, Vec<f64>>, tolerance: f64) -> Option<String>
+where K: Ord + ToString + std::hash::Hash
+{
+    let mut keys: Vec<&K> = current.keys().chain(previous.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .find(|&key| {
+            match (current.get(key), previous.get(key))
+            {
+                (Some(a), Some(b)) =>
+                {
+                    a.len() != b.len() || a.iter().zip(b).any(|(x, y)| (x - y).abs() > tolerance)
+                },
+                _ => true,
+            }
+        })
+        .map(|key| key.to_string())
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Runs the study exactly as `run_study` does, but also records every input
+    /// that affects the result so that a re-run can later be checked against it
+    /// with `AuditedStudyResult::verify_against`.
+    pub fn run_audited_study(self, margin: f64, limit: usize) -> anyhow::Result<AuditedStudyResult>
+    {
+        self.run_audited_study_with_options(&SolverOptions::new(margin, limit))
+    }
+
+    /// Runs the study exactly as `run_study_with_options` does, recording every solver 
+    /// setting in `options` and whether the jacobian is sparse along with the model.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::options::{Damping, SolverOptions};
+    ///
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 0, vec![2.0]).unwrap();
+    ///
+    /// let options = SolverOptions::new(1E-9, 100);
+    /// let first = builder.clone().run_audited_study_with_options(&options).unwrap();
+    ///
+    /// // Damping the solver is caught as a change of inputs, as is a sparse jacobian
+    /// let damped = options.with_damping(Damping::Fixed(0.5));
+    /// assert!(builder.clone().run_audited_study_with_options(&damped).unwrap().verify_against(&first).is_err());
+    /// 
+    /// let sparse = builder.with_sparse_jacobian(true).run_audited_study_with_options(&options).unwrap();
+    /// assert!(sparse.verify_against(&first).is_err());
+    /// ```
+    pub fn run_audited_study_with_options(self, options: &SolverOptions) -> anyhow::Result<AuditedStudyResult>
+    {
+        let audit = StudyAudit::with_options(&self.model, options, self.sparse_jacobian)?;
+        let result = self.run_study_with_options(options)?;
+
+        Ok(AuditedStudyResult { audit, result })
+    }
+}
@@ -0,0 +1,101 @@
+// Std modules
+use std::sync::Mutex;
+use std::thread;
+
+// Local modules
+use crate::modelling::NodalAnalysisModel;
+use crate::options::SolverOptions;
+use crate::{CompiledStudy, NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// Runs `solve` on every job across as many threads as the machine has cores,
+/// returning each job's result in the order that the jobs were given.
+fn solve_in_parallel<T: Send>(jobs: Vec<T>, solve: impl Fn(T) -> anyhow::Result<NodalAnalysisStudyResult> + Sync) -> Vec<anyhow::Result<NodalAnalysisStudyResult>>
+{
+    let count = jobs.len();
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(count);
+
+    // Each worker takes the next job until there are none left, so that slow jobs don't hold up the rest
+    let queue = Mutex::new(jobs.into_iter().enumerate());
+    let next_job = || queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+
+    let solved: Vec<Vec<(usize, anyhow::Result<NodalAnalysisStudyResult>)>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| scope.spawn(|| {
+                let mut solved = vec![];
+                while let Some((idx, job)) = next_job()
+                {
+                    solved.push((idx, solve(job)));
+                }
+                solved
+            }))
+            .collect();
+
+        workers.into_iter()
+            .map(|worker| worker.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+
+    let mut results: Vec<Option<anyhow::Result<NodalAnalysisStudyResult>>> = (0..count).map(|_| None).collect();
+    for (idx, result) in solved.into_iter().flatten()
+    {
+        results[idx] = Some(result);
+    }
+    results.into_iter()
+        .map(|result| result.expect("every job was solved by a worker"))
+        .collect()
+}
+
+/// Solves independent models on multiple threads, e.g. the corners of a design or the runs of
+/// a design-of-experiments, returning the result of each model in the order they were given.
+/// Each model is built with the default configurators (see 
+/// `NodalAnalysisStudyBuilder::from_model_with_default_config`) and solved with the settings in
+/// `options`. A model that fails to build or solve gives an error in its place without stopping
+/// the others.
+///
+/// Models are shared out between as many threads as the machine has cores. Each model is built
+/// and solved entirely on the thread that takes it, so no model's nodes or elements are shared.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::batch::solve_models_parallel;
+/// use neapolitan::dc_circuits::*;
+/// use neapolitan::options::SolverOptions;
+///
+/// // The same voltage divider with a different bottom resistor in each model
+/// let models = (1..=8)
+///     .map(|r| NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///         .unwrap()
+///         .add_nodes(3)
+///         .configure_node(0, vec![0.0], true, None)
+///         .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+///         .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+///         .add_element(RESISTOR,       2, 0, vec![r as f64]).unwrap()
+///         .get_model()
+///         .clone())
+///     .collect();
+///
+/// let results = solve_models_parallel(models, &SolverOptions::new(1E-9, 100));
+///
+/// for (r, soln) in (1..=8).zip(results)
+/// {
+///     let r = r as f64;
+///     let v2 = soln.unwrap().get_node_potential(2).unwrap()[0];
+///     assert!((v2 - 10.0 * r / (1.0 + r)).abs() < 1E-6);
+/// }
+/// ```
+pub fn solve_models_parallel(models: Vec<NodalAnalysisModel>, options: &SolverOptions) -> Vec<anyhow::Result<NodalAnalysisStudyResult>>
+{
+    solve_in_parallel(models, |model| {
+        NodalAnalysisStudyBuilder::from_model_with_default_config(model).run_study_with_options(options)
+    })
+}
+
+/// Solves independent compiled studies on multiple threads exactly as `solve_models_parallel`
+/// does, returning the result of each study in the order they were given. Unlike models,
+/// compiled studies may use element types from custom configurators, and may have had their
+/// gains changed since they were compiled (see `CompiledStudy::set_gain`).
+pub fn solve_studies_parallel(studies: Vec<CompiledStudy>, options: &SolverOptions) -> Vec<anyhow::Result<NodalAnalysisStudyResult>>
+{
+    solve_in_parallel(studies, |mut study| study.solve_with_options(options))
+}
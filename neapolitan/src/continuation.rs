@@ -0,0 +1,211 @@
+// 3rd party modules
+use serde::Serialize;
+
+// Local modules
+use crate::dc_circuits::{CURRENT_SOURCE, VOLTAGE_SOURCE};
+use crate::errors::{ContinuationError, SweepError};
+use crate::harmonic_balance::{PERIODIC_CURRENT_SOURCE, PERIODIC_VOLTAGE_SOURCE};
+use crate::heat_transfer::{HEAT_FLUX, HEAT_SOURCE, TEMPERATURE_DELTA};
+use crate::magnetic_circuit::MMF_SOURCE;
+use crate::mass_diffusion::{CONCENTRATION_SOURCE, SPECIES_SOURCE};
+use crate::options::SolverOptions;
+use crate::sweep::SweepParameter;
+use crate::{CompiledStudy, NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// The element types whose gains are scaled by `Continuation::SourceStepping`.
+pub const SOURCE_ELEMENTS: [&str; 10] = [
+    VOLTAGE_SOURCE,
+    CURRENT_SOURCE,
+    TEMPERATURE_DELTA,
+    HEAT_FLUX,
+    HEAT_SOURCE,
+    PERIODIC_VOLTAGE_SOURCE,
+    PERIODIC_CURRENT_SOURCE,
+    MMF_SOURCE,
+    CONCENTRATION_SOURCE,
+    SPECIES_SOURCE,
+];
+
+/// How a model is ramped up to its full value over several solves, each starting from the
+/// solution of the one before it. Nonlinear models (e.g. diodes or radiation) that diverge
+/// from a cold start can often be solved this way, as each step only moves the solution a little.
+///
+/// # Variants:
+/// - `SourceStepping` - scales the gain of every source element (see `SOURCE_ELEMENTS`) and the
+///   potential of every locked node from zero up to its value in the model, in `steps` equal steps
+/// - `Parameter` - moves a single `parameter` from `start` up to its value in the model, in `steps` equal steps
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum Continuation
+{
+    SourceStepping { steps: usize },
+    Parameter { parameter: SweepParameter, start: f64, steps: usize },
+}
+impl Continuation
+{
+    /// Returns the number of solves that the model is ramped up over.
+    pub fn get_steps(&self) -> usize
+    {
+        match *self
+        {
+            Continuation::SourceStepping { steps } | Continuation::Parameter { steps, .. } => steps,
+        }
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Sets how `run_study` ramps the model up to its full value rather than solving it from
+    /// a cold start (see `Continuation`). The result is the model's solution at its full value,
+    /// and its statistics describe the final step.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::continuation::Continuation;
+    /// use neapolitan::heat_transfer::*;
+    ///
+    /// // A plate held at 2000 K radiates to a sink through a thin conductor
+    /// let soln = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![2000.0], true, None)
+    ///     .configure_node(2, vec![300.0], true, None)
+    ///     .add_element(RADIATION_INTERFACE, 0, 1, vec![1.0, 0.9]).unwrap()
+    ///     .add_element(CONDUCTOR,           1, 2, vec![0.5, 50.0]).unwrap()
+    ///     .with_continuation(Continuation::SourceStepping { steps: 10 })
+    ///     .run_study(1E-6, 100)
+    ///     .unwrap();
+    ///
+    /// let t1 = serde_json::to_value(soln).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+    /// assert!(t1 > 300.0 && t1 < 2000.0);
+    /// ```
+    pub fn with_continuation(mut self, continuation: Continuation) -> NodalAnalysisStudyBuilder
+    {
+        self.continuation = Some(continuation);
+        self
+    }
+}
+
+impl CompiledStudy
+{
+    /// Solves the study by ramping it up to its current gains and locked potentials over
+    /// several solves, each starting from the solution of the one before it (see `Continuation`).
+    /// If any step fails, the study's gains and locked potentials are restored and the error
+    /// says which step failed.
+    pub fn solve_with_continuation(&mut self, margin: f64, limit: usize, continuation: Continuation) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.continue_with(&SolverOptions::new(margin, limit), continuation)
+    }
+
+    /// Solves the study exactly as `solve_with_continuation` does, solving each step
+    /// with the solver settings in `options`.
+    pub (in crate) fn continue_with(&mut self, options: &SolverOptions, continuation: Continuation) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        let steps = continuation.get_steps();
+        if steps == 0
+        {
+            return Err(ContinuationError::NoSteps.into());
+        }
+
+        if let Continuation::Parameter { parameter, .. } = continuation
+        {
+            self.check_parameter(parameter)?;
+        }
+
+        // The values that the study is ramped up to, which it is left with however the ramp ends
+        let gains: Vec<Vec<f64>> = self.model.elements.iter().map(|elem| elem.gain.clone()).collect();
+        let mut locked: Vec<(usize, Vec<f64>)> = self.model.configuration.iter()
+            .filter(|(_, node_data)| node_data.is_locked)
+            .map(|(&node, node_data)| (node, node_data.potential.clone()))
+            .collect();
+        locked.sort_by_key(|&(node, _)| node);
+
+        let mut soln = None;
+        for step in 1..=steps
+        {
+            let res = self.set_continuation_step(continuation, step, steps, &gains, &locked)
+                .and_then(|_| self.solve_with_options(options));
+
+            match res
+            {
+                Ok(result) =>
+                {
+                    self.set_initial_state(&result);
+                    soln = Some(result);
+                },
+                Err(e) =>
+                {
+                    self.set_continuation_step(continuation, steps, steps, &gains, &locked)?;
+                    return Err(e.context(ContinuationError::StepFailed(step, steps)));
+                },
+            }
+        }
+
+        soln.ok_or(ContinuationError::NoSteps.into())
+    }
+
+    /// Makes sure that a ramped parameter exists in the study.
+    fn check_parameter(&self, parameter: SweepParameter) -> anyhow::Result<()>
+    {
+        match parameter
+        {
+            SweepParameter::Gain { element, component } => match self.model.elements.get(element)
+            {
+                None => Err(SweepError::ElementDoesNotExist.into()),
+                Some(element) if component >= element.gain.len() => Err(SweepError::GainComponentDoesNotExist.into()),
+                Some(_) => Ok(()),
+            },
+            SweepParameter::Potential { node, component } if component >= self.get_node_dimension(node) => Err(SweepError::PotentialComponentDoesNotExist.into()),
+            SweepParameter::Potential { .. } => Ok(()),
+        }
+    }
+
+    /// Sets the gains and locked potentials that the study has at `step` of `steps`, given
+    /// their full values. The final step sets the full values exactly.
+    fn set_continuation_step(&mut self, continuation: Continuation, step: usize, steps: usize, gains: &[Vec<f64>], locked: &[(usize, Vec<f64>)]) -> anyhow::Result<()>
+    {
+        let ramp = |start: f64, full: f64| match step == steps
+        {
+            true => full,
+            false => start + (full - start) * step as f64 / steps as f64,
+        };
+
+        match continuation
+        {
+            Continuation::SourceStepping { .. } =>
+            {
+                for (element, gain) in gains.iter().enumerate()
+                {
+                    if SOURCE_ELEMENTS.contains(&self.model.elements[element].element_type.as_str())
+                    {
+                        self.set_gain(element, gain.iter().map(|&x| ramp(0.0, x)).collect())?;
+                    }
+                }
+
+                for (node, potential) in locked
+                {
+                    self.set_locked_potential(*node, potential.iter().map(|&x| ramp(0.0, x)).collect())?;
+                }
+            },
+            Continuation::Parameter { parameter: SweepParameter::Gain { element, component }, start, .. } =>
+            {
+                let mut gain = gains[element].clone();
+                gain[component] = ramp(start, gain[component]);
+                self.set_gain(element, gain)?;
+            },
+            Continuation::Parameter { parameter: SweepParameter::Potential { node, component }, start, .. } =>
+            {
+                let mut potential = match locked.iter().find(|(locked_node, _)| *locked_node == node)
+                {
+                    Some((_, potential)) => potential.clone(),
+                    None => vec![],
+                };
+                potential.resize(self.get_node_dimension(node), 0.0);
+                potential[component] = ramp(start, potential[component]);
+                self.set_locked_potential(node, potential)?;
+            },
+        }
+
+        Ok(())
+    }
+}
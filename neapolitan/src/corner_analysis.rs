@@ -0,0 +1,266 @@
+// Std modules
+use std::collections::{BTreeMap, HashMap};
+
+// 3rd party modules
+use serde::Serialize;
+
+// Local modules
+use crate::errors::CornerAnalysisError;
+use crate::{NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// The most elements that may be given gain bounds when
+/// every corner combination is enumerated automatically.
+const _MAX_ENUMERATED_ELEMENTS_: usize = 20;
+
+/// Indicates which of its gain bounds an element takes on at a given corner.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum Corner
+{
+    Min,
+    Typ,
+    Max,
+}
+
+/// The lower and upper bounds on an element's gain. The element's
+/// typical gain is the one it was added to the model with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GainBounds
+{
+    pub min: Vec<f64>,
+    pub max: Vec<f64>,
+}
+
+/// A single corner combination, mapping element indices to the bound
+/// they take on. Elements that are not present take on their typical gain.
+pub type CornerCombination = BTreeMap<usize, Corner>;
+
+/// The extreme values of a single component of a potential or flux
+/// across every corner solved, along with the index of the corner
+/// (in `CornerAnalysisResult::get_corners`) that produced each.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CornerExtremes
+{
+    pub min: f64,
+    pub min_corner: usize,
+    pub max: f64,
+    pub max_corner: usize,
+}
+impl CornerExtremes
+{
+    fn new(value: f64, corner: usize) -> CornerExtremes
+    {
+        CornerExtremes { min: value, min_corner: corner, max: value, max_corner: corner }
+    }
+
+    fn update(&mut self, value: f64, corner: usize)
+    {
+        if value < self.min
+        {
+            self.min = value;
+            self.min_corner = corner;
+        }
+
+        if value > self.max
+        {
+            self.max = value;
+            self.max_corner = corner;
+        }
+    }
+}
+
+/// The results of a worst-case corner analysis.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CornerAnalysisResult
+{
+    corners: Vec<CornerCombination>,
+    nodes: HashMap<String, Vec<CornerExtremes>>,
+    elements: HashMap<String, Vec<CornerExtremes>>,
+}
+impl CornerAnalysisResult
+{
+    /// Returns every corner combination that was solved, in the order
+    /// referred to by the `min_corner` and `max_corner` indices.
+    pub fn get_corners(&self) -> &Vec<CornerCombination>
+    {
+        &self.corners
+    }
+
+    /// Returns the extreme values of each component of the given node's potential. Nodes are
+    /// named the same way as in a study's results, i.e. by their name or else their index.
+    pub fn get_node_extremes(&self, node: &str) -> Option<&Vec<CornerExtremes>>
+    {
+        self.nodes.get(node)
+    }
+
+    /// Returns the extreme values of each component of the given element's
+    /// flux. Elements are named the same way as in a study's results, i.e.
+    /// by their label or else `"<element_type>.<element_index>"`.
+    pub fn get_element_extremes(&self, element: &str) -> Option<&Vec<CornerExtremes>>
+    {
+        self.elements.get(element)
+    }
+
+    fn record(extremes: &mut Vec<CornerExtremes>, values: &[f64], corner: usize)
+    {
+        if extremes.is_empty()
+        {
+            extremes.extend(values.iter().map(|&x| CornerExtremes::new(x, corner)));
+        }
+        else
+        {
+            for (ext, &x) in extremes.iter_mut().zip(values)
+            {
+                ext.update(x, corner);
+            }
+        }
+    }
+
+    fn add_study(&mut self, study: &NodalAnalysisStudyResult, corner: usize)
+    {
+        for (node, potential) in &study.nodes
+        {
+            CornerAnalysisResult::record(self.nodes.entry(node.clone()).or_default(), potential, corner);
+        }
+
+        for (name, flux) in &study.elements
+        {
+            CornerAnalysisResult::record(self.elements.entry(name.clone()).or_default(), flux, corner);
+        }
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Solves the model at corners of its elements' gain tolerances, reporting the
+    /// extreme value of every nodal potential and elemental flux along with the corner
+    /// that produced it. `bounds` maps the index of an element (in the order that it
+    /// was added to the model) to its gain bounds.
+    ///
+    /// If `corners` is `None`, every combination of minimum and maximum gains for the
+    /// bounded elements is solved. Otherwise, only the given corners are solved.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::corner_analysis::GainBounds;
+    /// use neapolitan::dc_circuits::*;
+    ///
+    /// let result = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .run_corner_analysis(
+    ///         HashMap::from([
+    ///             (1, GainBounds { min: vec![0.9], max: vec![1.1] }),
+    ///             (2, GainBounds { min: vec![0.9], max: vec![1.1] }),
+    ///         ]),
+    ///         None,
+    ///         1E-9, 100
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // The divider output is highest when the top resistor is smallest
+    /// let v2 = result.get_node_extremes("2").unwrap()[0];
+    /// assert!((v2.max - 5.5).abs() < 1E-6);
+    /// assert!((v2.min - 4.5).abs() < 1E-6);
+    /// ```
+    pub fn run_corner_analysis(self, bounds: HashMap<usize, GainBounds>, corners: Option<Vec<CornerCombination>>, margin: f64, limit: usize) -> anyhow::Result<CornerAnalysisResult>
+    {
+        for (&idx, GainBounds { min, max }) in &bounds
+        {
+            let element = match self.model.elements.get(idx)
+            {
+                Some(element) => element,
+                None => return Err(CornerAnalysisError::ElementDoesNotExist.into()),
+            };
+
+            if min.len() != element.gain.len() || max.len() != element.gain.len()
+            {
+                return Err(CornerAnalysisError::BoundsLengthMismatch.into());
+            }
+        }
+
+        let corners = match corners
+        {
+            Some(corners) => corners,
+            None => enumerate_corners(&bounds)?,
+        };
+
+        if corners.is_empty()
+        {
+            return Err(CornerAnalysisError::NoCorners.into());
+        }
+
+        let mut result = CornerAnalysisResult
+        {
+            corners: vec![],
+            nodes: HashMap::new(),
+            elements: HashMap::new(),
+        };
+
+        // Every element that any corner changes, along with its typical gain
+        let mut typical = HashMap::new();
+        for &elem_idx in corners.iter().flat_map(|corner| corner.keys())
+        {
+            match self.model.elements.get(elem_idx)
+            {
+                Some(element) => typical.insert(elem_idx, element.gain.clone()),
+                None => return Err(CornerAnalysisError::ElementDoesNotExist.into()),
+            };
+        }
+
+        // Only the gains change between corners, so the model is only built once
+        let mut study = self.compile()?;
+
+        for (corner_idx, corner) in corners.iter().enumerate()
+        {
+            for (&elem_idx, typical_gain) in &typical
+            {
+                let gain = match (corner.get(&elem_idx), bounds.get(&elem_idx))
+                {
+                    (None | Some(Corner::Typ), _) => typical_gain,
+                    (Some(Corner::Min), Some(b)) => &b.min,
+                    (Some(Corner::Max), Some(b)) => &b.max,
+                    (_, None) => return Err(CornerAnalysisError::ElementDoesNotExist.into()),
+                };
+
+                study.set_gain(elem_idx, gain.clone())?;
+            }
+
+            result.add_study(&study.solve(margin, limit)?, corner_idx);
+        }
+
+        result.corners = corners;
+        Ok(result)
+    }
+}
+
+/// Lists every combination of minimum and maximum gains for the bounded elements.
+fn enumerate_corners(bounds: &HashMap<usize, GainBounds>) -> anyhow::Result<Vec<CornerCombination>>
+{
+    let mut elements: Vec<usize> = bounds.keys().copied().collect();
+    elements.sort();
+
+    if elements.len() > _MAX_ENUMERATED_ELEMENTS_
+    {
+        return Err(CornerAnalysisError::TooManyCorners.into());
+    }
+
+    let corners = (0..1usize << elements.len())
+        .map(|bits| {
+            elements.iter()
+                .enumerate()
+                .map(|(i, &elem)| {
+                    let bound = if bits & (1 << i) == 0 { Corner::Min } else { Corner::Max };
+                    (elem, bound)
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(corners)
+}
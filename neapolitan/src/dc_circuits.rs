@@ -4,16 +4,51 @@ use std::cell::RefCell;
 
 // 3rd party modules
 use gmatlib::{col_vec, Matrix};
+use thiserror::Error;
 
 // Local modules
 use crate::errors::ElementCreationError;
 use crate::{flux_formulas::*, get_node_potential, is_locked, lock_node, set_node_potential};
 use crate::{GenericElement, GenericNode};
 
+#[derive(Debug, Error)]
+#[error("you must specify a positive saturation current, a positive thermal voltage and optionally a positive maximum current (in that order) to create a diode element")]
+pub struct DiodeCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must specify a positive forward resistance, a non-negative smoothing voltage and optionally a positive reverse resistance (in that order) to create a check_valve element")]
+pub struct CheckValveCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must specify an open-circuit voltage and a positive internal resistance, or a positive internal resistance, a state of charge and at least two (state, open-circuit voltage) points in order of increasing state (in that order), to create a battery element")]
+pub struct BatteryCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must specify at least two (flow, rise) points of a performance curve in order of increasing flow and decreasing rise to create a pump element")]
+pub struct PumpCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must connect an npn transistor to its collector, base and emitter nodes (in that order) and specify a positive saturation current, thermal voltage, forward current gain and optionally reverse current gain (in that order)")]
+pub struct TransistorCreationError;
+
+/// The exponent beyond which a `diode`'s current grows linearly rather than
+/// exponentially when no maximum current is given. For a silicon diode this is
+/// about 1 V, which passes thousands of amps.
+pub const DEFAULT_DIODE_EXPONENT_LIMIT: f64 = 40.0;
+
 pub const DC_CIRCUIT: &str = "dc_circuit";
 pub const RESISTOR: &str = "resistor";
 pub const VOLTAGE_SOURCE: &str = "voltage_source";
 pub const CURRENT_SOURCE: &str = "current_source";
+pub const DIODE: &str = "diode";
+pub const VOLTAGE_CONTROLLED_VOLTAGE_SOURCE: &str = "voltage_controlled_voltage_source";
+pub const VOLTAGE_CONTROLLED_CURRENT_SOURCE: &str = "voltage_controlled_current_source";
+pub const CURRENT_CONTROLLED_VOLTAGE_SOURCE: &str = "current_controlled_voltage_source";
+pub const CURRENT_CONTROLLED_CURRENT_SOURCE: &str = "current_controlled_current_source";
+pub const NPN_TRANSISTOR: &str = "npn_transistor";
+pub const CHECK_VALVE: &str = "check_valve";
+pub const BATTERY: &str = "battery";
+pub const PUMP: &str = "pump";
 
 pub fn resistor(
     input_node: Weak<RefCell<GenericNode>>, 
@@ -21,56 +56,73 @@ pub fn resistor(
     resistance: Vec<f64>,
 ) -> anyhow::Result<Rc<GenericElement>>
 {
-    GenericElement::try_new(
+    let elem = GenericElement::try_new(
         vec![1.0 / resistance[0]],  // Conductance (gain) is reciprocal of resistance in ohms
         input_node, output_node,    // Input and output_node nodes
         normal_flux,                // Flux calculation
         false,                      // Does not drive a nodal potential
         true, true,                 // Connect to both nodes
-    )
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
 }
 
-pub fn voltage_source(
-    input_node: Weak<RefCell<GenericNode>>, 
-    output_node: Weak<RefCell<GenericNode>>, 
-    voltage: Vec<f64>,
-) -> anyhow::Result<Rc<GenericElement>>
+/// Locks whichever of the given nodes a voltage source should drive and sets its 
+/// potential `voltage` above the other node, returning `true` if it is the output.
+pub (in crate) fn drive_node(
+    input_node: &Weak<RefCell<GenericNode>>, 
+    output_node: &Weak<RefCell<GenericNode>>, 
+    voltage: f64,
+) -> anyhow::Result<bool>
 {
     // Abort if we cannot remove a DOF from the problem
-    if is_locked(&output_node)? && is_locked(&input_node)?
+    if is_locked(output_node)? && is_locked(input_node)?
     {
         return Err(ElementCreationError.into())
     }
 
     // Determine if we're driving the input or output node
-    let drives_output = !is_locked(&output_node)?;
+    let drives_output = !is_locked(output_node)?;
 
     // Remove the appropriate DOF
     if drives_output
     {
-        lock_node(&output_node)?;
-        set_node_potential(&output_node, (get_node_potential(&input_node)? + col_vec![voltage[0]]).into())?;
+        lock_node(output_node)?;
+        set_node_potential(output_node, (get_node_potential(input_node)? + col_vec![voltage]).into())?;
     }
     else // driving input_node node:
     {
-        lock_node(&input_node)?;
-        set_node_potential(&input_node, (get_node_potential(&output_node)? + col_vec![voltage[0]]).into())?;
+        lock_node(input_node)?;
+        set_node_potential(input_node, (get_node_potential(output_node)? - col_vec![voltage]).into())?;
     }
 
+    Ok(drives_output)
+}
+
+pub fn voltage_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    voltage: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let drives_output = drive_node(&input_node, &output_node, voltage[0])?;
+
     // If we're driving the output node, we need to make the input node aware of this element.
     let connect_input_node = drives_output;
 
     // If we're not going to make the input aware of this element, make the output node aware.
     let connect_output_node = !connect_input_node;
     
-    GenericElement::try_new(
+    let elem = GenericElement::try_new(
         voltage,
         input_node, output_node,
         observe_flux,
         drives_output,
         connect_input_node,
         connect_output_node,
-    )
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
 }
 
 pub fn current_source(
@@ -79,11 +131,417 @@ pub fn current_source(
     current: Vec<f64>,
 ) -> anyhow::Result<Rc<GenericElement>>
 {
-    GenericElement::try_new(
+    let elem = GenericElement::try_new(
         current,
         input_node, output_node,
         constant_flux,
         false,
         true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents a diode obeying the shockley diode equation, where the current from
+/// input to output is `i_s * (exp((v_in - v_out) / v_t) - 1)`. The gain is given as 
+/// the saturation current `i_s`, the thermal voltage `v_t` (including the ideality
+/// factor) and optionally a maximum current.
+/// 
+/// Beyond the maximum current (or an exponent of `DEFAULT_DIODE_EXPONENT_LIMIT`), the
+/// current grows linearly with voltage instead. This keeps the element's flux finite
+/// for any potentials the solver tries, so the maximum should be set well above the
+/// currents expected in the solved circuit.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// 
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+///     .add_element(DIODE,          2, 0, vec![1E-14, 0.025]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+/// 
+/// let json = serde_json::to_value(soln).unwrap();
+/// let forward_voltage = json["nodes"]["2"][0].as_f64().unwrap();
+/// assert!(0.6 < forward_voltage && forward_voltage < 0.7);
+/// ```
+pub fn diode(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    saturation_thermal_and_max: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let (saturation, thermal, limit) = match *saturation_thermal_and_max.as_slice()
+    {
+        [i_s, v_t] => (i_s, v_t, DEFAULT_DIODE_EXPONENT_LIMIT),
+        [i_s, v_t, i_max] => (i_s, v_t, (i_max / i_s + 1.0).ln()),
+        _ => return Err(DiodeCreationError.into()),
+    };
+
+    if !(saturation > 0.0 && thermal > 0.0 && limit > 0.0)
+    {
+        return Err(DiodeCreationError.into());
+    }
+
+    let elem = GenericElement::try_new(
+        vec![saturation, thermal, limit],
+        input_node, output_node,
+        limited_exponential_flux,
+        false,
+        true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents an element that conducts in only one direction, e.g. an idealized rectifier or (in
+/// a hydraulic model) a check valve. The gain is given as the forward resistance, the smoothing
+/// voltage and optionally the reverse resistance (in that order). Without a reverse resistance,
+/// no current flows from output to input.
+/// 
+/// The element's resistance changes from forward to reverse over a width of about the smoothing
+/// voltage around zero (see `flux_formulas::one_way_flux`), which lets the solver's jacobian see 
+/// the corner between them. A smoothing voltage of zero gives an ideal corner, but the solver may
+/// then fail to converge when the voltage across the element is near zero.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// 
+/// // The same source feeds one load through a forward valve and another through a reversed valve
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(4)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+///     .add_element(CHECK_VALVE,    1, 2, vec![1.0, 1E-6]).unwrap()
+///     .add_element(RESISTOR,       2, 0, vec![9.0]).unwrap()
+///     .add_element(CHECK_VALVE,    3, 1, vec![1.0, 1E-6]).unwrap()
+///     .add_element(RESISTOR,       3, 0, vec![9.0]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+/// 
+/// assert!((soln.get_node_potential(2).unwrap()[0] - 4.5).abs() < 1E-6);
+/// assert!(soln.get_node_potential(3).unwrap()[0].abs() < 1E-6);
+/// ```
+pub fn check_valve(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    resistances_and_smoothing: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let (forward, smoothing, reverse) = match *resistances_and_smoothing.as_slice()
+    {
+        [r_f, smoothing] if r_f > 0.0 && smoothing >= 0.0 => (1.0 / r_f, smoothing, 0.0),
+        [r_f, smoothing, r_r] if r_f > 0.0 && smoothing >= 0.0 && r_r > 0.0 => (1.0 / r_f, smoothing, 1.0 / r_r),
+        _ => return Err(CheckValveCreationError.into()),
+    };
+
+    let elem = GenericElement::try_new(
+        vec![forward, reverse, smoothing],
+        input_node, output_node,
+        one_way_flux,
+        false,
+        true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Returns the open-circuit voltage of a battery at a state of charge of `state`, linearly
+/// interpolated from a table of (state, voltage) points in order of increasing state. States
+/// outside of the table take the voltage of the nearest point.
+fn open_circuit_voltage(table: &[f64], state: f64) -> f64
+{
+    let points: Vec<&[f64]> = table.chunks(2).collect();
+    match points.iter().position(|point| point[0] > state)
+    {
+        Some(0) => points[0][1],
+        Some(k) => 
+        {
+            let (s_0, v_0, s_1, v_1) = (points[k - 1][0], points[k - 1][1], points[k][0], points[k][1]);
+            v_0 + (v_1 - v_0) * (state - s_0) / (s_1 - s_0)
+        },
+        None => points[points.len() - 1][1],
+    }
+}
+
+/// Represents a battery with an internal resistance, connected from its negative terminal 
+/// (the input) to its positive terminal (the output). Its flux is the current that it delivers, 
+/// so the output is the open-circuit voltage above the input less the voltage dropped across the
+/// internal resistance, and no internal node is needed between them.
+/// 
+/// The gain is given as the open-circuit voltage and the internal resistance (in that order), or
+/// as the internal resistance, the state of charge and a table of (state of charge, open-circuit 
+/// voltage) points (in that order). The open-circuit voltage is linearly interpolated from the 
+/// table, whose points must be in order of increasing state of charge.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// 
+/// // A half-charged battery whose open-circuit voltage falls from 12.8 V to 11.8 V as it discharges
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(BATTERY,  0, 1, vec![0.05, 0.5, 0.0, 11.8, 1.0, 12.8]).unwrap()
+///     .add_element(RESISTOR, 1, 0, vec![1.0]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+/// 
+/// let current = 12.3 / 1.05;
+/// assert!((soln.get_element_flux(0).unwrap()[0] - current).abs() < 1E-9);
+/// assert!((soln.get_node_potential(1).unwrap()[0] - (12.3 - 0.05 * current)).abs() < 1E-9);
+/// ```
+pub fn battery(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    voltage_and_resistance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let (voltage, resistance) = match *voltage_and_resistance.as_slice()
+    {
+        [voltage, r] if r > 0.0 => (voltage, r),
+        [r, state, ref table @ ..] if r > 0.0 && table.len() >= 4 && table.len().is_multiple_of(2) 
+            && table.chunks(2).zip(table.chunks(2).skip(1)).all(|(a, b)| b[0] > a[0]) => 
+        {
+            (open_circuit_voltage(table, state), r)
+        },
+        _ => return Err(BatteryCreationError.into()),
+    };
+
+    let elem = GenericElement::try_new(
+        vec![1.0 / resistance, voltage],
+        input_node, output_node,
+        thevenin_flux,
+        false,
+        true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents (in a hydraulic or air-flow model) a pump or fan that raises the potential from
+/// its input to its output along its performance curve, e.g. head or pressure rise against flow 
+/// rate. The gain is given as the curve's `(flow, rise)` points, i.e. `[flow_0, rise_0, flow_1, 
+/// rise_1, ...]`, in order of increasing flow and decreasing rise. The flow is interpolated 
+/// linearly from the curve (see `flux_formulas::performance_curve_flux`), so the curve should
+/// span the flows that the pump reaches, from its shutoff rise at zero flow onwards.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// 
+/// // A pump lifting water through a pipe whose pressure drop is proportional to its flow
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(PUMP,     0, 1, vec![0.0, 10.0, 2.0, 8.0, 4.0, 0.0]).unwrap()
+///     .add_element(RESISTOR, 1, 0, vec![2.0]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+/// 
+/// // The pipe's curve crosses the pump's between its last two points
+/// assert!((soln.get_element_flux(0).unwrap()[0] - 8.0 / 3.0).abs() < 1E-9);
+/// assert!((soln.get_node_potential(1).unwrap()[0] - 16.0 / 3.0).abs() < 1E-9);
+/// ```
+pub fn pump(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    curve: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let is_curve = curve.len() >= 4 && curve.len().is_multiple_of(2)
+        && curve.chunks(2).zip(curve.chunks(2).skip(1)).all(|(a, b)| b[0] > a[0] && b[1] < a[1]);
+    if !is_curve
+    {
+        return Err(PumpCreationError.into());
+    }
+
+    let elem = GenericElement::try_new(
+        curve,
+        input_node, output_node,
+        performance_curve_flux,
+        false,
+        true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents an npn bipolar junction transistor following the Ebers-Moll transport model,
+/// connected to its collector, base and emitter nodes (in that order) with 
+/// `NodalAnalysisStudyBuilder::add_terminal_element`. The gain is given as the saturation 
+/// current, the thermal voltage, the forward current gain and optionally the reverse current
+/// gain (which defaults to 1). Each junction is limited in the same way as a `diode` with no
+/// maximum current.
+/// 
+/// Its flux is the current into the collector, base and emitter, in that order.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// 
+/// // A common emitter stage biased through a 100k base resistor
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(4)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+///     .add_element(RESISTOR,       1, 2, vec![100E3]).unwrap()
+///     .add_element(RESISTOR,       1, 3, vec![1E3]).unwrap()
+///     .add_terminal_element(NPN_TRANSISTOR, vec![3, 2, 0], vec![1E-14, 0.025, 100.0]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+/// 
+/// let json = serde_json::to_value(soln).unwrap();
+/// let currents = &json["elements"]["npn_transistor.3"];
+/// let (i_c, i_b) = (currents[0].as_f64().unwrap(), currents[1].as_f64().unwrap());
+/// assert!((i_c / i_b - 100.0).abs() < 1E-3);
+/// ```
+pub fn npn_transistor(
+    nodes: Vec<Weak<RefCell<GenericNode>>>,
+    gains: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let (saturation, thermal, beta_f, beta_r) = match *gains.as_slice()
+    {
+        [i_s, v_t, beta_f] => (i_s, v_t, beta_f, 1.0),
+        [i_s, v_t, beta_f, beta_r] => (i_s, v_t, beta_f, beta_r),
+        _ => return Err(TransistorCreationError.into()),
+    };
+
+    if nodes.len() != 3 || !(saturation > 0.0 && thermal > 0.0 && beta_f > 0.0 && beta_r > 0.0)
+    {
+        return Err(TransistorCreationError.into());
+    }
+
+    let elem = GenericElement::try_new_with_terminals(
+        vec![saturation, thermal, beta_f, beta_r, DEFAULT_DIODE_EXPONENT_LIMIT],
+        nodes,
+        ebers_moll_flux,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents a voltage source whose voltage is `gain * sensed`, where the sensed quantity
+/// is the potential difference across another pair of nodes. The gain is given as the
+/// voltage gain, and the sensed nodes are set with `NodalAnalysisStudyBuilder::add_controlled_element`.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// use neapolitan::modelling::ElementControl;
+/// 
+/// // An amplifier with a voltage gain of 5 driving a voltage divider
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(4)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![0.2]).unwrap()
+///     .add_controlled_element(VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, 0, 2, vec![5.0], ElementControl::Potential { input: 1, output: 0 }).unwrap()
+///     .add_element(RESISTOR, 2, 3, vec![10.0]).unwrap()
+///     .add_element(RESISTOR, 3, 0, vec![10.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+/// 
+/// let json = serde_json::to_value(soln).unwrap();
+/// assert!((json["nodes"]["2"][0].as_f64().unwrap() - 1.0).abs() < 1E-9);
+/// assert!((json["nodes"]["3"][0].as_f64().unwrap() - 0.5).abs() < 1E-9);
+/// ```
+pub fn voltage_controlled_voltage_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    gain: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    // The sensed voltage is not known yet, so start from no voltage across the source
+    let drives_output = drive_node(&input_node, &output_node, 0.0)?;
+
+    GenericElement::try_new(
+        gain,
+        input_node, output_node,
+        controlled_observe_flux,
+        drives_output,
+        drives_output,
+        !drives_output,
+    )
+}
+
+/// Represents a current source whose current is `gain * sensed`, where the sensed quantity
+/// is the potential difference across another pair of nodes. The gain is given as the
+/// transconductance, and the sensed nodes are set with `NodalAnalysisStudyBuilder::add_controlled_element`.
+pub fn voltage_controlled_current_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    gain: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    GenericElement::try_new(
+        gain,
+        input_node, output_node,
+        controlled_flux,
+        false,
+        true, true,
     )
 }
+
+/// Represents a voltage source whose voltage is `gain * sensed`, where the sensed quantity
+/// is the current through another element. The gain is given as the transresistance, and 
+/// the sensed element is set with `NodalAnalysisStudyBuilder::add_controlled_element`.
+pub fn current_controlled_voltage_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    gain: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    voltage_controlled_voltage_source(input_node, output_node, gain)
+}
+
+/// Represents a current source whose current is `gain * sensed`, where the sensed quantity
+/// is the current through another element. The gain is given as the current gain, and 
+/// the sensed element is set with `NodalAnalysisStudyBuilder::add_controlled_element`.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// use neapolitan::modelling::ElementControl;
+/// 
+/// // A current mirror with a gain of 3
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(CURRENT_SOURCE, 0, 1, vec![0.5]).unwrap()
+///     .add_element(RESISTOR, 1, 0, vec![2.0]).unwrap()
+///     .add_controlled_element(CURRENT_CONTROLLED_CURRENT_SOURCE, 0, 2, vec![3.0], ElementControl::Flux { element: 1 }).unwrap()
+///     .add_element(RESISTOR, 2, 0, vec![4.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+/// 
+/// let json = serde_json::to_value(soln).unwrap();
+/// assert!((json["nodes"]["2"][0].as_f64().unwrap() - 6.0).abs() < 1E-9);
+/// ```
+pub fn current_controlled_current_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    gain: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    voltage_controlled_current_source(input_node, output_node, gain)
+}
@@ -1,4 +1,6 @@
 
+use std::collections::HashMap;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -19,6 +21,14 @@ pub enum FluxCalculationError
 {
     #[error("failed to access nodes during flux calculation because they were already dropped.")]
     NodeRefsAlreadyDropped,
+    #[error("failed to read the quantity sensed by a controlled element because its nodes or element were already dropped.")]
+    SensorRefsAlreadyDropped,
+    #[error("controlled elements must be added to a model with `add_controlled_element` so that they have a quantity to sense")]
+    MissingControl,
+    #[error("a performance curve must have at least two (flux, rise) points")]
+    InvalidPerformanceCurve,
+    #[error("the element's flux calculation did not give a flux for every one of its terminals")]
+    MissingTerminalFlux,
 }
 
 #[derive(Debug, Error)]
@@ -32,6 +42,16 @@ pub enum NodalAnalysisConfigurationError
     ElementTypeNameCollision,
     #[error("a configuration with this name was already added to this model builder")]
     ConfigurationNameCollision,
+    #[error("a configuration with dimension {0} cannot inherit the elements of a configuration with dimension {1}")]
+    DimensionMismatch(usize, usize),
+    #[error("cannot alias '{0}' because there is no element type with that name in this configurator object")]
+    AliasTargetNotFound(String),
+    #[error("a flux formula named '{0}' was already registered with this configurator object")]
+    FluxFormulaNameCollision(String),
+    #[error("no flux formula named '{0}' was registered with this configurator object")]
+    FluxFormulaNotFound(String),
+    #[error("no element type named '{0}' was registered with the configurator object that this configuration was read with")]
+    UnregisteredElementType(String),
 }
 
 #[derive(Debug, Error)]
@@ -41,4 +61,206 @@ pub enum NodalAnalysisModellingError
     NodeDoesNotExist,
     #[error("could not find desired model type in the given or default configurators")]
     ModelTypeNotFound,
-}
\ No newline at end of file
+    #[error("elements of type '{0}' are not available in this model type")]
+    ElementTypeNotFound(String),
+    #[error("a controlled element must sense the flux of another element that exists in the model")]
+    ControlElementDoesNotExist,
+    #[error("a node cannot be named '{0}' because that name is a number or is already used by another node")]
+    NodeNameCollision(String),
+    #[error("an element cannot be labelled '{0}' because that label ends in '.<number>' or is already used by another element")]
+    ElementLabelCollision(String),
+    #[error("the element does not exist in the model")]
+    ElementDoesNotExist,
+    #[error("element {0} cannot be removed because a controlled element senses its flux")]
+    ElementIsSensed(usize),
+    #[error("node {0} cannot be removed because an element is connected to it or senses its potential")]
+    NodeIsConnected(usize),
+    #[error("the model has no locked node to reference its potentials to. lock a node with `configure_node` or enable automatic grounding")]
+    NoReferenceNode,
+    #[error("no node of the '{0}' domain is locked to reference its potentials to. lock one of its nodes with `configure_node` or enable automatic grounding")]
+    FloatingDomain(String),
+    #[error("node {0} is locked, so its potential cannot be given an initial guess")]
+    NodeIsLocked(usize),
+    #[error("element '{element}' cannot drive the potential between nodes '{input}' and '{output}' because both are already locked ('{input}' by {input_locker} and '{output}' by {output_locker})")]
+    LockedNodeConflict { element: String, input: String, input_locker: String, output: String, output_locker: String },
+    #[error("a '{0}' element cannot connect nodes from different domains. couple domains with controlled elements that sense the other domain instead")]
+    DomainMismatch(String),
+}
+#[derive(Debug, Error)]
+pub enum CornerAnalysisError
+{
+    #[error("gain bounds were given for an element that does not exist in the model")]
+    ElementDoesNotExist,
+    #[error("the minimum and maximum gain bounds of an element must have the same length as its gain")]
+    BoundsLengthMismatch,
+    #[error("there were no corners to solve. at least one element must have gain bounds or a corner must be selected")]
+    NoCorners,
+    #[error("too many elements were given gain bounds to enumerate every corner. select a subset of corners instead")]
+    TooManyCorners,
+}
+
+#[derive(Debug, Error)]
+pub enum MonteCarloError
+{
+    #[error("a gain distribution was given for an element that does not exist in the model")]
+    ElementDoesNotExist,
+    #[error("the tolerance or standard deviation of a gain distribution must be a finite, non-negative fraction")]
+    InvalidDistribution,
+    #[error("a monte carlo analysis must solve at least one sample")]
+    NoSamples,
+}
+
+#[derive(Debug, Error)]
+pub enum EquationExportError
+{
+    #[error("models of type '{0}' cannot be exported as an equation system")]
+    UnsupportedModelType(String),
+    #[error("elements of type '{0}' cannot be exported as an equation system")]
+    UnsupportedElementType(String),
+    #[error("node '{0}' has no coordinates to find the length of a geometric conductor from")]
+    MissingCoordinates(usize),
+}
+
+#[derive(Debug, Error)]
+pub enum GoalSeekError
+{
+    #[error("the parameter to solve for belongs to an element that does not exist in the model")]
+    ElementDoesNotExist,
+    #[error("the parameter to solve for is not a component of the element's gain")]
+    GainComponentDoesNotExist,
+    #[error("the target node does not exist in the model")]
+    NodeDoesNotExist,
+}
+
+#[derive(Debug, Error)]
+pub enum StudyAuditError
+{
+    #[error("the study inputs do not match the audited study: '{0}' differs")]
+    InputMismatch(String),
+    #[error("the study results do not match the audited study within tolerance: '{0}' differs")]
+    ResultMismatch(String),
+}
+
+#[derive(Debug, Error)]
+pub enum StudyServerError
+{
+    #[error("the server could not listen on the given address: {0}")]
+    BindFailed(String),
+}
+
+#[derive(Debug, Error)]
+pub enum TransientStudyError
+{
+    #[error("the time step must be positive and no longer than the duration of the study")]
+    InvalidTimeStep,
+}
+
+#[derive(Debug, Error)]
+pub enum SubcircuitError
+{
+    #[error("a subcircuit named '{0}' was already defined for this model builder")]
+    NameCollision(String),
+    #[error("no subcircuit named '{0}' was defined for this model builder")]
+    NotFound(String),
+    #[error("the subcircuit '{0}' must be connected to exactly {1} port node(s)")]
+    PortCountMismatch(String, usize),
+    #[error("could not connect the subcircuit to one or more of the given port nodes because the node(s) did not exist in the model")]
+    PortDoesNotExist,
+}
+
+#[derive(Debug, Error)]
+pub enum ModelValidationError
+{
+    #[error("{0}: expected {1}")]
+    Mismatch(String, String),
+    #[error("{0}: missing required property")]
+    MissingProperty(String),
+    #[error("{0}: unknown property")]
+    UnknownProperty(String),
+    #[error("{0}: has too few or too many properties")]
+    PropertyCount(String),
+}
+
+#[derive(Debug, Error)]
+pub enum SweepError
+{
+    #[error("every swept parameter must be given a list of values, and every list must have the same length")]
+    ValueCountMismatch,
+    #[error("a swept gain belongs to an element that does not exist in the model")]
+    ElementDoesNotExist,
+    #[error("a swept gain is not a component of its element's gain")]
+    GainComponentDoesNotExist,
+    #[error("a swept potential is not a component of its node's potential")]
+    PotentialComponentDoesNotExist,
+}
+
+#[derive(Debug, Error)]
+pub enum ContinuationError
+{
+    #[error("a continuation must ramp the model up over at least one step")]
+    NoSteps,
+    #[error("failed to solve step {0} of {1} of the continuation")]
+    StepFailed(usize, usize),
+}
+
+#[derive(Debug, Error)]
+pub enum VtkExportError
+{
+    #[error("node '{0}' has no coordinates. place every node with `place_node` to export the model")]
+    MissingCoordinates(String),
+    #[error("the results have no potential for node '{0}'. they may be the results of another model")]
+    MissingPotential(String),
+    #[error("the results have no flux for element '{0}'. they may be the results of another model")]
+    MissingFlux(String),
+}
+
+#[derive(Debug, Error)]
+pub enum FileFormatError
+{
+    #[error("'{0}' is not a supported file format. use json, yaml or toml")]
+    UnknownFormat(String),
+    #[error("toml files cannot hold the null value at '{0}'")]
+    NullInToml(String),
+}
+
+#[derive(Debug, Error)]
+pub enum SpiceImportError
+{
+    #[error("line {0}: '{1}' cards are not supported. only resistors (R), voltage sources (V), current sources (I) and .end are")]
+    UnsupportedCard(usize, String),
+    #[error("line {0}: the element '{1}' must be given two nodes and a value")]
+    MissingField(usize, String),
+    #[error("line {0}: could not read '{1}' as a value")]
+    UnreadableValue(usize, String),
+    #[error("the netlist has no ground node (named '0' or 'gnd')")]
+    NoGroundNode,
+}
+
+#[derive(Debug, Error)]
+pub enum CompiledStudyError
+{
+    #[error("cannot change the gain of an element that does not exist in the study")]
+    ElementDoesNotExist,
+    #[error("cannot set the potential of node {0} because it was not locked with `configure_node`")]
+    NodeIsNotLocked(usize),
+}
+
+/// The state that a study was left in when its solver failed, so that the node whose flux
+/// balance is blowing up can be found. Nodes are named as they are in a study's results.
+/// 
+/// # Fields:
+/// - `reason` - why the solver failed, e.g. that it reached its iteration limit
+/// - `guess` - the solver's last guess at each unknown, in the order that the study solves them
+/// - `potentials` - the potential of every node at the solver's last guess
+/// - `discrepancies` - the flux discrepancy of every unlocked node at the solver's last guess
+/// - `worst_node` - the unlocked node with the largest flux discrepancy, if there are any
+#[derive(Debug, Error)]
+#[error("{reason}. the largest flux discrepancy at the last guess was at node '{}'", worst_node.as_deref().unwrap_or("none"))]
+pub struct SolveFailedError
+{
+    pub reason: String,
+    pub guess: Vec<f64>,
+    pub potentials: HashMap<String, Vec<f64>>,
+    pub discrepancies: HashMap<String, Vec<f64>>,
+    pub worst_node: Option<String>,
+}
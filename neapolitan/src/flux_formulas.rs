@@ -1,7 +1,10 @@
 use gmatlib::Matrix;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use crate::errors::FluxCalculationError;
+use crate::modelling::element::FluxCalculation;
 use crate::modelling::node::GenericNode;
 
 pub fn normal_flux(
@@ -19,6 +22,242 @@ pub fn normal_flux(
     Ok(deltas)
 }
 
+/// A flux calculation for elements whose flux is proportional to the difference of 
+/// the fourth powers of their nodal potentials, i.e. `gain[0] * (i^4 - o^4)`, as in 
+/// thermal radiation between two surfaces.
+pub fn fourth_power_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        flux[(i, 0)] = gain[(0, 0)] * (inode.potential[(i, 0)].powi(4) - onode.potential[(i, 0)].powi(4));
+    }
+    Ok(flux)
+}
+
+/// A flux calculation for elements that combine a linear and a fourth power flux
+/// in parallel, i.e. `gain[0] * (i - o) + gain[1] * (i^4 - o^4)`, as in a surface
+/// that loses heat by both convection and radiation.
+pub fn linear_and_fourth_power_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        let (ti, to) = (inode.potential[(i, 0)], onode.potential[(i, 0)]);
+        flux[(i, 0)] = gain[(0, 0)] * (ti - to) + gain[(1, 0)] * (ti.powi(4) - to.powi(4));
+    }
+    Ok(flux)
+}
+
+/// Returns `exp(x)` for `x <= limit`, continuing it as a straight line with the
+/// same slope beyond `limit`. The result and its derivative stay continuous and
+/// finite however far a solver overshoots, unlike `exp(x)`, which quickly overflows.
+pub fn limited_exp(x: f64, limit: f64) -> f64
+{
+    if x <= limit
+    {
+        x.exp()
+    }
+    else
+    {
+        limit.exp() * (1.0 + x - limit)
+    }
+}
+
+/// A flux calculation for elements whose flux follows the shockley diode equation,
+/// i.e. `gain[0] * (exp((i - o) / gain[1]) - 1)`. To help the solver converge, the
+/// exponential is continued linearly once its exponent exceeds `gain[2]` (see `limited_exp`).
+pub fn limited_exponential_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        let exponent = (inode.potential[(i, 0)] - onode.potential[(i, 0)]) / gain[(1, 0)];
+        flux[(i, 0)] = gain[(0, 0)] * (limited_exp(exponent, gain[(2, 0)]) - 1.0);
+    }
+    Ok(flux)
+}
+
+/// Returns `max(x, 0)` smoothed over a width of about `smoothing` around zero, i.e.
+/// `(x + sqrt(x^2 + smoothing^2)) / 2`. The result is `max(x, 0)` exactly when `smoothing`
+/// is zero, and otherwise has a continuous derivative, which the solver's jacobian needs
+/// to converge near the corner.
+pub fn smooth_ramp(x: f64, smoothing: f64) -> f64
+{
+    (x + x.hypot(smoothing)) / 2.0
+}
+
+/// A flux calculation for elements that conduct more easily in one direction than the other,
+/// e.g. check valves and idealized rectifiers, i.e. `gain[1] * v + (gain[0] - gain[1]) * smooth_ramp(v, gain[2])`,
+/// where `v` is `i - o`. The element's conductance is `gain[0]` when `v` is positive and `gain[1]` when 
+/// it is negative, changing over a width of about `gain[2]` around zero (see `smooth_ramp`).
+pub fn one_way_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let (forward, reverse, smoothing) = (gain[(0, 0)], gain[(1, 0)], gain[(2, 0)]);
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        let v = inode.potential[(i, 0)] - onode.potential[(i, 0)];
+        flux[(i, 0)] = reverse * v + (forward - reverse) * smooth_ramp(v, smoothing);
+    }
+    Ok(flux)
+}
+
+/// A flux calculation for elements that behave like a potential difference in series with a
+/// conductance, e.g. a battery with internal resistance, i.e. `gain[0] * (i - o + gain[1])`.
+/// Its flux is zero when the output is `gain[1]` above the input.
+pub fn thevenin_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        flux[(i, 0)] = gain[(0, 0)] * (inode.potential[(i, 0)] - onode.potential[(i, 0)] + gain[(1, 0)]);
+    }
+    Ok(flux)
+}
+
+/// A flux calculation for elements that raise the potential from their input to their output
+/// along a performance curve, e.g. a pump's head or a fan's pressure rise against its flow rate.
+/// The gain is the curve as a table of `(flux, rise)` points in order of increasing flux and
+/// decreasing rise, i.e. `[flux_0, rise_0, flux_1, rise_1, ...]`. The flux is interpolated 
+/// linearly from the curve at a rise of `o - i`, and the curve's first and last segments are
+/// extended past its ends so that the flux keeps changing with the rise.
+pub fn performance_curve_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let points: Vec<&[f64]> = gain.as_slice().chunks(2).collect();
+    if points.len() < 2 || points.iter().any(|point| point.len() < 2)
+    {
+        return Err(FluxCalculationError::InvalidPerformanceCurve.into());
+    }
+
+    let mut flux = Matrix::new(inode.potential.get_rows(), 1);
+    for i in 0..flux.get_rows()
+    {
+        let rise = onode.potential[(i, 0)] - inode.potential[(i, 0)];
+
+        // The segment whose rises span this one, or the nearest segment past either end
+        let k = points[1..points.len() - 1].iter().take_while(|point| point[1] > rise).count();
+        let (q_0, h_0, q_1, h_1) = (points[k][0], points[k][1], points[k + 1][0], points[k + 1][1]);
+        flux[(i, 0)] = q_0 + (q_1 - q_0) * (rise - h_0) / (h_1 - h_0);
+    }
+    Ok(flux)
+}
+
+/// A flux calculation for three-terminal bipolar junction transistors following the
+/// Ebers-Moll transport model, with terminals ordered as `[collector, base, emitter]`. The
+/// gain holds the saturation current, the thermal voltage, the forward and reverse current 
+/// gains, and the exponent beyond which each junction's exponential is continued linearly 
+/// (see `limited_exp`), in that order. Each flux is the current into the transistor at that terminal.
+pub fn ebers_moll_flux(nodes: &[Rc<RefCell<GenericNode>>], gain: &Matrix<f64>) -> anyhow::Result<Vec<Matrix<f64>>>
+{
+    let (collector, base, emitter) = match nodes
+    {
+        [c, b, e] => (c.try_borrow()?, b.try_borrow()?, e.try_borrow()?),
+        _ => return Err(FluxCalculationError::MissingTerminalFlux.into()),
+    };
+    let (i_s, v_t, beta_f, beta_r, limit) = (gain[(0, 0)], gain[(1, 0)], gain[(2, 0)], gain[(3, 0)], gain[(4, 0)]);
+
+    let rows = base.potential.get_rows();
+    let (mut i_c, mut i_b, mut i_e) = (Matrix::new(rows, 1), Matrix::new(rows, 1), Matrix::new(rows, 1));
+    for i in 0..rows
+    {
+        let forward = limited_exp((base.potential[(i, 0)] - emitter.potential[(i, 0)]) / v_t, limit) - 1.0;
+        let reverse = limited_exp((base.potential[(i, 0)] - collector.potential[(i, 0)]) / v_t, limit) - 1.0;
+
+        i_c[(i, 0)] = i_s * (forward - reverse) - i_s / beta_r * reverse;
+        i_b[(i, 0)] = i_s / beta_f * forward + i_s / beta_r * reverse;
+        i_e[(i, 0)] = -(i_c[(i, 0)] + i_b[(i, 0)]);
+    }
+    Ok(vec![i_c, i_b, i_e])
+}
+
+/// Returns the quantity sensed by a controlled element scaled by its gain, i.e.
+/// `gain[0] * gain[1..]`, where the sensed quantity was appended to the gain
+/// by `GenericElement::get_flux`.
+fn scaled_sensed_quantity(gain: &Matrix<f64>) -> anyhow::Result<Matrix<f64>>
+{
+    if gain.get_rows() < 2
+    {
+        return Err(FluxCalculationError::MissingControl.into());
+    }
+
+    let scale = gain[(0, 0)];
+    Ok(Matrix::from_col_vec(gain.as_slice()[1..].iter().map(|x| scale * x).collect()))
+}
+
+/// A flux calculation for controlled elements whose flux is proportional to the quantity
+/// they sense, e.g. a current-controlled current source, i.e. `gain[0] * sensed`.
+pub fn controlled_flux(
+    _inode_ref: Rc<RefCell<GenericNode>>, 
+    _onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    scaled_sensed_quantity(gain)
+}
+
+/// A flux calculation for controlled elements that drive a potential difference proportional 
+/// to the quantity they sense, e.g. a voltage-controlled voltage source. This behaves like 
+/// `observe_flux` with a potential difference of `gain[0] * sensed`.
+pub fn controlled_observe_flux(
+    inode_ref: Rc<RefCell<GenericNode>>, 
+    onode_ref: Rc<RefCell<GenericNode>>, 
+    gain: &Matrix<f64>, 
+    drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    observe_flux(inode_ref, onode_ref, &scaled_sensed_quantity(gain)?, drives_output)
+}
+
 pub fn observe_flux(
     inode_ref: Rc<RefCell<GenericNode>>, 
     onode_ref: Rc<RefCell<GenericNode>>, 
@@ -55,7 +294,12 @@ pub fn observe_flux(
     let mut discrepancy = sub_ref.try_borrow()?
         .get_flux_discrepancy()?;
 
-    discrepancy.inplace_scale(-1.0);
+    // The element carries the flux that the driven node is missing. This flows 
+    // out of the element into the output node, or into the element from the input node.
+    if drives_output
+    {
+        discrepancy.inplace_scale(-1.0);
+    }
 
     Ok(discrepancy)
 }
@@ -68,4 +312,56 @@ pub fn constant_flux(
 ) -> anyhow::Result<Matrix<f64>>
 {
     Ok(flux.clone())
-}
\ No newline at end of file
+}
+
+/// A `PostProcessor` that reports the power an element absorbs as `"power"`, i.e. the 
+/// potential at each terminal times the flux into the element there, summed over every 
+/// terminal and component. For an element with an input and output, this is 
+/// `(i - o) * flux`, e.g. the power dissipated by a resistor or (when negative) 
+/// the power delivered by a source.
+pub fn absorbed_power(flux: &Matrix<f64>, potentials: &[Matrix<f64>], _gain: &Matrix<f64>) -> HashMap<String, f64>
+{
+    let flux = Vec::from(flux.clone());
+    let power = match potentials
+    {
+        [input, output] if flux.len() == input.get_rows() => flux.iter()
+            .enumerate()
+            .map(|(k, i)| (input[(k, 0)] - output[(k, 0)]) * i)
+            .sum(),
+        terminals => 
+        {
+            let dimension = flux.len() / terminals.len().max(1);
+            terminals.iter()
+                .zip(flux.chunks(dimension.max(1)))
+                .map(|(v, i)| i.iter().enumerate().map(|(k, i)| v[(k, 0)] * i).sum::<f64>())
+                .sum()
+        },
+    };
+
+    HashMap::from([("power".to_string(), power)])
+}
+
+/// A `PostProcessor` that reports an element's flux as `"heat_rate"`, for heat transfer 
+/// elements whose flux is the rate that heat flows from their input to their output.
+pub fn heat_rate(flux: &Matrix<f64>, _potentials: &[Matrix<f64>], _gain: &Matrix<f64>) -> HashMap<String, f64>
+{
+    HashMap::from([("heat_rate".to_string(), flux[(0, 0)])])
+}
+
+/// Returns the flux formulas that every `NodalAnalysisStudyConfigurator` registers by default, 
+/// keyed by their function names (see `NodalAnalysisStudyConfigurator::add_flux_formula`). 
+/// Only the formulas that depend on nothing but an element's nodes and gain are included, 
+/// so that any of them can be used by an element type read from a file.
+pub fn builtin_flux_formulas() -> HashMap<&'static str, FluxCalculation>
+{
+    HashMap::from([
+        ("normal_flux",                  normal_flux                  as FluxCalculation),
+        ("fourth_power_flux",            fourth_power_flux            as FluxCalculation),
+        ("linear_and_fourth_power_flux", linear_and_fourth_power_flux as FluxCalculation),
+        ("limited_exponential_flux",     limited_exponential_flux     as FluxCalculation),
+        ("one_way_flux",                 one_way_flux                 as FluxCalculation),
+        ("thevenin_flux",                thevenin_flux                as FluxCalculation),
+        ("performance_curve_flux",       performance_curve_flux       as FluxCalculation),
+        ("constant_flux",                constant_flux                as FluxCalculation),
+    ])
+}
@@ -0,0 +1,172 @@
+// 3rd party modules
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+// Local modules
+use crate::errors::FileFormatError;
+use crate::modelling::NodalAnalysisModel;
+use crate::schema::validate_model;
+
+/// The formats that models and results can be read from and written to.
+/// 
+/// Every format is read through the same JSON data model, so a model reads the same way
+/// in any format and is checked against the same schema (see `schema::validate_model`).
+/// Map keys are always written as strings, e.g. `"0"` for the configuration of node 0.
+/// 
+/// # Variants:
+/// - `Json` - JSON, e.g. `model.json`
+/// - `Yaml` - YAML, e.g. `model.yaml` or `model.yml`
+/// - `Toml` - TOML, e.g. `model.toml`. TOML has no null value, so nulls inside of tables are
+///   left out and nulls inside of arrays (e.g. `NaN` results) cannot be written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat
+{
+    Json,
+    Yaml,
+    Toml,
+}
+impl FileFormat
+{
+    /// Looks up a format by name, e.g. `"yaml"`, ignoring case.
+    pub fn from_name(name: &str) -> anyhow::Result<FileFormat>
+    {
+        match name.to_lowercase().as_str()
+        {
+            "json" => Ok(FileFormat::Json),
+            "yaml" | "yml" => Ok(FileFormat::Yaml),
+            "toml" => Ok(FileFormat::Toml),
+            _ => Err(FileFormatError::UnknownFormat(name.to_owned()).into()),
+        }
+    }
+
+    /// Looks up the format of a file from its extension, if it is one of the supported formats.
+    pub fn from_path(path: &str) -> Option<FileFormat>
+    {
+        path.rsplit_once('.').and_then(|(_, ext)| FileFormat::from_name(ext).ok())
+    }
+
+    /// Returns the extension of files in this format, including the leading `.`
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            FileFormat::Json => ".json",
+            FileFormat::Yaml => ".yaml",
+            FileFormat::Toml => ".toml",
+        }
+    }
+
+    /// Reads a document in this format as a JSON value.
+    pub fn to_value(&self, text: &str) -> anyhow::Result<Value>
+    {
+        // Maps in yaml and toml may have non-string keys, which become strings in json
+        match self
+        {
+            FileFormat::Json => Ok(serde_json::from_str(text)?),
+            FileFormat::Yaml => Ok(serde_json::to_value(serde_yaml::from_str::<serde_yaml::Value>(text)?)?),
+            FileFormat::Toml => Ok(serde_json::to_value(toml::from_str::<toml::Value>(text)?)?),
+        }
+    }
+
+    /// Reads a value (e.g. a `NodalAnalysisStudyResult`) from a document in this format.
+    pub fn read<T: DeserializeOwned>(&self, text: &str) -> anyhow::Result<T>
+    {
+        Ok(serde_json::from_value(self.to_value(text)?)?)
+    }
+
+    /// Writes a value (e.g. a `NodalAnalysisStudyResult`) as a document in this format.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::formats::FileFormat;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 0, vec![2.0]).unwrap();
+    /// 
+    /// let toml = FileFormat::Toml.write(builder.get_model()).unwrap();
+    /// assert!(toml.contains("model_type = \"dc_circuit\""));
+    /// 
+    /// let soln = builder.run_study(1E-9, 100).unwrap();
+    /// let yaml = FileFormat::Yaml.write(&soln).unwrap();
+    /// let soln: serde_json::Value = FileFormat::Yaml.read(&yaml).unwrap();
+    /// assert_eq!(soln["nodes"]["1"][0], 3.0);
+    /// ```
+    pub fn write<T: Serialize>(&self, value: &T) -> anyhow::Result<String>
+    {
+        let value = serde_json::to_value(value)?;
+        match self
+        {
+            FileFormat::Json => Ok(serde_json::to_string_pretty(&value)?),
+            FileFormat::Yaml => Ok(serde_yaml::to_string(&value)?),
+            FileFormat::Toml => Ok(toml::to_string_pretty(&without_nulls(value, "")?)?),
+        }
+    }
+}
+
+/// Removes nulls from the tables of a JSON value so that it can be written as TOML, 
+/// which reads the same since every optional field in a model or result can be left out.
+fn without_nulls(value: Value, path: &str) -> anyhow::Result<Value>
+{
+    match value
+    {
+        Value::Object(map) => map.into_iter()
+            .filter(|(_, x)| !x.is_null())
+            .map(|(key, x)| Ok((key.clone(), without_nulls(x, &format!("{path}/{key}"))?)))
+            .collect::<anyhow::Result<_>>()
+            .map(Value::Object),
+        Value::Array(xs) => xs.into_iter()
+            .enumerate()
+            .map(|(idx, x)| match x
+            {
+                Value::Null => Err(FileFormatError::NullInToml(format!("{path}/{idx}")).into()),
+                x => without_nulls(x, &format!("{path}/{idx}")),
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(Value::Array),
+        x => Ok(x),
+    }
+}
+
+impl NodalAnalysisModel
+{
+    /// Reads a model from a document in the given format, first validating it against the 
+    /// model schema exactly as `from_json_strict` does.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::formats::FileFormat;
+    /// use neapolitan::modelling::NodalAnalysisModel;
+    /// 
+    /// let yaml = "
+    /// model_type: dc_circuit
+    /// nodes: 2
+    /// configuration:
+    ///   0: { potential: [0.0], is_locked: true, metadata: null }
+    /// elements:
+    ///   - { element_type: voltage_source, input: 0, output: 1, gain: [3.0] }
+    ///   - { element_type: resistor, input: 1, output: 0, gain: [2.0] }
+    /// ";
+    /// 
+    /// let model = NodalAnalysisModel::from_str_strict(yaml, FileFormat::Yaml).unwrap();
+    /// let soln = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["elements"]["resistor.1"][0].as_f64().unwrap() - 1.5).abs() < 1E-9);
+    /// ```
+    pub fn from_str_strict(text: &str, format: FileFormat) -> anyhow::Result<NodalAnalysisModel>
+    {
+        let value = format.to_value(text)?;
+        validate_model(&value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
@@ -0,0 +1,82 @@
+// 3rd party modules
+use geqslib::newton::newton_raphson;
+
+// Local modules
+use crate::errors::GoalSeekError;
+use crate::{NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// The factor by which each inner study's margin of error is tightened 
+/// relative to the goal-seek's so that solver noise does not pollute 
+/// the finite-difference derivative of the outer root find.
+const _INNER_MARGIN_SCALE_: f64 = 1E-6;
+
+/// Identifies a single component of an element's gain in a model.
+/// 
+/// # Fields:
+/// - `element` - the index of the element, in the order it was added to the model
+/// - `component` - the index of the component in the element's gain
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ModelParameter
+{
+    pub element: usize,
+    pub component: usize,
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Finds the value of `parameter` that makes the potential of `target_node` equal to
+    /// `target_value`, starting from `guess`. The study is re-solved for every trial value of
+    /// the parameter, and the found value is returned alongside the study's final result.
+    /// 
+    /// For models with multi-component potentials, the first component is targeted.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::goal_seek::ModelParameter;
+    /// 
+    /// // What bottom resistance makes a divider of a 1 ohm resistor sit at 2.5 V from 10 V?
+    /// let (r, _soln) = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .solve_for_parameter(2, 2.5, ModelParameter { element: 2, component: 0 }, 1.0, 1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// assert!((r - 1.0 / 3.0).abs() < 1E-6);
+    /// ```
+    pub fn solve_for_parameter(self, target_node: usize, target_value: f64, parameter: ModelParameter, guess: f64, margin: f64, limit: usize) -> anyhow::Result<(f64, NodalAnalysisStudyResult)>
+    {
+        if target_node >= self.model.nodes
+        {
+            return Err(GoalSeekError::NodeDoesNotExist.into());
+        }
+
+        let ModelParameter { element, component } = parameter;
+        match self.model.elements.get(element)
+        {
+            Some(elem) if component < elem.gain.len() => {},
+            Some(_) => return Err(GoalSeekError::GainComponentDoesNotExist.into()),
+            None => return Err(GoalSeekError::ElementDoesNotExist.into()),
+        }
+
+        let inner_margin = margin * _INNER_MARGIN_SCALE_;
+        let study_with = |value: f64| {
+            let mut study = self.clone();
+            study.model.elements[element].gain[component] = value;
+            study.run_study(inner_margin, limit)
+        };
+
+        let target_node = self.model.get_node_name(target_node);
+        let value = newton_raphson(
+            |x| Ok::<f64, anyhow::Error>(study_with(x)?.nodes[&target_node][0] - target_value),
+            guess, margin, limit,
+        )?;
+
+        Ok((value, study_with(value)?))
+    }
+}
@@ -0,0 +1,520 @@
+// Std modules
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use gmatlib::Matrix;
+use thiserror::Error;
+
+// Local modules
+use crate::errors::ElementCreationError;
+use crate::dc_circuits::{resistor, RESISTOR};
+use crate::modelling::element::ElementConstructor;
+use crate::{flux_formulas::*, get_node_potential, is_locked, lock_node, set_node_potential};
+use crate::modelling::ElementControl;
+use crate::{GenericElement, GenericNode, NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator};
+
+#[derive(Debug, Error)]
+pub enum HarmonicBalanceElementError
+{
+    #[error("a periodic source was given more fourier coefficients than the study has components")]
+    TooManyCoefficients,
+    #[error("you must specify a capacitance and the fundamental angular frequency (in that order) to create a capacitor element")]
+    CapacitorParameters,
+    #[error("you must specify a saturation flux and a thermal/emission scale (in that order) to create a diode element")]
+    DiodeParameters,
+    #[error("you must specify a single, nonzero turns ratio to create a transformer winding")]
+    TurnsRatio,
+}
+
+pub const HARMONIC_BALANCE: &str = "harmonic_balance";
+pub const CAPACITOR: &str = "capacitor";
+pub const NONLINEAR_CONDUCTOR: &str = "nonlinear_conductor";
+pub const DIODE: &str = "diode";
+pub const PERIODIC_VOLTAGE_SOURCE: &str = "periodic_voltage_source";
+pub const PERIODIC_CURRENT_SOURCE: &str = "periodic_current_source";
+pub const TRANSFORMER_PRIMARY: &str = "transformer_primary";
+pub const TRANSFORMER_SECONDARY: &str = "transformer_secondary";
+
+/// The number of harmonics (excluding the DC term) solved for
+/// by the harmonic balance study in the default configuration.
+pub const DEFAULT_HARMONICS: usize = 3;
+
+/// Returns a configurator for a harmonic balance study that solves for the
+/// DC term, the fundamental and every harmonic up to and including `harmonics`.
+///
+/// Each nodal potential and elemental flux in this study is a vector of fourier
+/// coefficients laid out as `[dc, cos(wt), sin(wt), cos(2wt), sin(2wt), ...]`,
+/// so the study has a dimension of `2 * harmonics + 1`.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::RESISTOR;
+/// use neapolitan::harmonic_balance::*;
+///
+/// let config = HashMap::from([
+///     (HARMONIC_BALANCE.to_string(), harmonic_balance_config(5))
+/// ]);
+///
+/// let soln = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), Some(config))
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(PERIODIC_VOLTAGE_SOURCE, 0, 1, vec![0.0, 2.0]).unwrap()
+///     .add_element(RESISTOR,                1, 2, vec![1.0]).unwrap()
+///     .add_element(NONLINEAR_CONDUCTOR,     2, 0, vec![1.0, 0.0, 0.5]).unwrap()
+///     .run_study(1E-9, 100);
+///
+/// assert!(soln.is_ok())
+/// ```
+pub fn harmonic_balance_config(harmonics: usize) -> NodalAnalysisStudyConfigurator
+{
+    NodalAnalysisStudyConfigurator
+    {
+        dimension: 2 * harmonics + 1,
+        elements: HashMap::from([
+            (RESISTOR,                resistor                as ElementConstructor),
+            (CAPACITOR,               capacitor               as ElementConstructor),
+            (NONLINEAR_CONDUCTOR,     nonlinear_conductor     as ElementConstructor),
+            (DIODE,                   diode                   as ElementConstructor),
+            (PERIODIC_VOLTAGE_SOURCE, periodic_voltage_source as ElementConstructor),
+            (PERIODIC_CURRENT_SOURCE, periodic_current_source as ElementConstructor),
+            (TRANSFORMER_PRIMARY,     transformer_primary     as ElementConstructor),
+            (TRANSFORMER_SECONDARY,   transformer_secondary   as ElementConstructor),
+        ]),
+        terminal_elements: HashMap::new(),
+        aliases: HashMap::new(),
+        flux_formulas: builtin_flux_formulas(),
+        flux_elements: HashMap::new(),
+    }
+}
+
+/// Evaluates the periodic waveform described by a vector of fourier
+/// coefficients (in the layout used by harmonic balance studies) at
+/// `samples` evenly-spaced points over a single period.
+///
+/// # Example
+/// ```
+/// use neapolitan::harmonic_balance::sample_waveform;
+///
+/// // 1 + cos(wt), sampled at wt = 0, pi/2, pi, 3pi/2
+/// let v = sample_waveform(&[1.0, 1.0, 0.0], 4);
+///
+/// assert!((v[0] - 2.0).abs() < 1E-12);
+/// assert!((v[1] - 1.0).abs() < 1E-12);
+/// assert!((v[2] - 0.0).abs() < 1E-12);
+/// ```
+pub fn sample_waveform(coefficients: &[f64], samples: usize) -> Vec<f64>
+{
+    (0..samples).map(|j| {
+        let theta = TAU * j as f64 / samples as f64;
+        let harmonics = coefficients[1..].chunks(2);
+
+        harmonics.enumerate().fold(coefficients[0], |acc, (k, ab)| {
+            let k = (k + 1) as f64;
+            let a = ab[0];
+            let b = ab.get(1).copied().unwrap_or(0.0);
+            acc + a * (k * theta).cos() + b * (k * theta).sin()
+        })
+    })
+    .collect()
+}
+
+/// Calculates the fourier coefficients of a periodic waveform from `samples`
+/// taken at evenly-spaced points over a single period, keeping the DC term and
+/// the first `harmonics` harmonics. This is the inverse of `sample_waveform`
+/// provided that enough samples are taken.
+///
+/// # Example
+/// ```
+/// use neapolitan::harmonic_balance::{fourier_coefficients, sample_waveform};
+///
+/// let coefs = vec![0.5, 0.0, 1.0, -2.0, 0.0];
+/// let recovered = fourier_coefficients(&sample_waveform(&coefs, 16), 2);
+///
+/// for (x, y) in coefs.iter().zip(recovered)
+/// {
+///     assert!((x - y).abs() < 1E-12);
+/// }
+/// ```
+pub fn fourier_coefficients(samples: &[f64], harmonics: usize) -> Vec<f64>
+{
+    let n = samples.len() as f64;
+    let mut coefficients = vec![samples.iter().sum::<f64>() / n];
+
+    for k in 1..=harmonics
+    {
+        let (mut a, mut b) = (0.0, 0.0);
+        for (j, x) in samples.iter().enumerate()
+        {
+            let theta = TAU * (k * j) as f64 / n;
+            a += x * theta.cos();
+            b += x * theta.sin();
+        }
+        coefficients.push(2.0 * a / n);
+        coefficients.push(2.0 * b / n);
+    }
+
+    coefficients
+}
+
+/// Calculates the flux of an element whose instantaneous flux is some nonlinear
+/// function of the instantaneous potential difference across it. The potential
+/// difference is sampled in the time domain, passed through `law`, and transformed
+/// back into fourier coefficients.
+fn time_domain_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    law: impl Fn(f64) -> f64,
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let deltas: Vec<f64> = (&(inode.potential) - &(onode.potential)).into();
+    let harmonics = deltas.len() / 2;
+
+    // Oversample so that products of harmonics up to a cubic do not alias
+    let samples = 4 * deltas.len();
+    let fluxes: Vec<f64> = sample_waveform(&deltas, samples)
+        .into_iter()
+        .map(law)
+        .collect();
+
+    Ok(Matrix::from_col_vec(fourier_coefficients(&fluxes, harmonics)))
+}
+
+/// A flux calculation for elements whose instantaneous flux is a polynomial
+/// in the potential difference, i.e. `gain[0] * v + gain[1] * v^2 + ...`.
+pub fn polynomial_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    time_domain_flux(inode_ref, onode_ref, |v| {
+        gain.as_slice().iter().rev().fold(0.0, |acc, g| (acc + g) * v)
+    })
+}
+
+/// A flux calculation for elements whose instantaneous flux follows the
+/// shockley diode equation, i.e. `gain[0] * (exp(v / gain[1]) - 1)`.
+pub fn exponential_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let saturation = gain[(0, 0)];
+    let scale = gain[(1, 0)];
+
+    time_domain_flux(inode_ref, onode_ref, |v| saturation * ((v / scale).exp() - 1.0))
+}
+
+/// A flux calculation for elements whose flux is proportional to the
+/// time derivative of the potential difference, i.e. `gain[0] * dv/dt`
+/// at a fundamental angular frequency of `gain[1]`.
+pub fn derivative_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let deltas = &(inode.potential) - &(onode.potential);
+    let mut flux = Matrix::new(deltas.get_rows(), 1);
+
+    // d/dt (a cos(kwt) + b sin(kwt)) = kwb cos(kwt) - kwa sin(kwt)
+    for k in 1..=deltas.get_rows() / 2
+    {
+        let scale = gain[(0, 0)] * gain[(1, 0)] * k as f64;
+        flux[(2 * k - 1, 0)] = scale * deltas[(2 * k, 0)];
+        flux[(2 * k, 0)] = -scale * deltas[(2 * k - 1, 0)];
+    }
+
+    Ok(flux)
+}
+
+/// Pads a list of fourier coefficients with zeros so
+/// that it matches the dimension of the given node.
+fn pad_to_node(node: &Weak<RefCell<GenericNode>>, mut coefficients: Vec<f64>) -> anyhow::Result<Vec<f64>>
+{
+    let dimension = get_node_potential(node)?.get_rows();
+
+    if coefficients.len() > dimension
+    {
+        return Err(HarmonicBalanceElementError::TooManyCoefficients.into());
+    }
+
+    coefficients.resize(dimension, 0.0);
+    Ok(coefficients)
+}
+
+/// Represents a linear capacitor. The gain must be given as the
+/// capacitance followed by the study's fundamental angular frequency.
+pub fn capacitor(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    capacitance_and_frequency: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if capacitance_and_frequency.len() != 2
+    {
+        return Err(HarmonicBalanceElementError::CapacitorParameters.into());
+    }
+
+    GenericElement::try_new(
+        capacitance_and_frequency,
+        input_node, output_node,
+        derivative_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents an element whose instantaneous flux is a polynomial in the potential
+/// difference across it. The gain gives the coefficients of `v`, `v^2`, `v^3`, etc.
+pub fn nonlinear_conductor(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    coefficients: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    GenericElement::try_new(
+        coefficients,
+        input_node, output_node,
+        polynomial_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents a diode obeying the shockley diode equation. The gain must be
+/// given as the saturation current followed by the product of the ideality
+/// factor and the thermal voltage.
+pub fn diode(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    saturation_and_scale: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if saturation_and_scale.len() != 2
+    {
+        return Err(HarmonicBalanceElementError::DiodeParameters.into());
+    }
+
+    GenericElement::try_new(
+        saturation_and_scale,
+        input_node, output_node,
+        exponential_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents a periodic voltage source. The gain gives the fourier coefficients
+/// of the source voltage as `[dc, cos(wt), sin(wt), cos(2wt), ...]`. Any
+/// coefficients that are omitted are assumed to be zero.
+pub fn periodic_voltage_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    voltage: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let voltage = pad_to_node(&input_node, voltage)?;
+    let drives_output = drive_node(&input_node, &output_node, Matrix::from_col_vec(voltage.clone()))?;
+
+    // If we're driving the output node, we need to make the input node aware of this element.
+    let connect_input_node = drives_output;
+
+    // If we're not going to make the input aware of this element, make the output node aware.
+    let connect_output_node = !connect_input_node;
+
+    GenericElement::try_new(
+        voltage,
+        input_node, output_node,
+        observe_flux,
+        drives_output,
+        connect_input_node,
+        connect_output_node,
+    )
+}
+
+/// Locks whichever of the two nodes is not already locked and sets its potential so that
+/// the output is `delta` above the input. Returns `true` if the output node is driven.
+fn drive_node(
+    input_node: &Weak<RefCell<GenericNode>>,
+    output_node: &Weak<RefCell<GenericNode>>,
+    delta: Matrix<f64>,
+) -> anyhow::Result<bool>
+{
+    // Abort if we cannot remove a DOF from the problem
+    if is_locked(output_node)? && is_locked(input_node)?
+    {
+        return Err(ElementCreationError.into())
+    }
+
+    // Determine if we're driving the input or output node
+    let drives_output = !is_locked(output_node)?;
+
+    // Remove the appropriate DOF
+    if drives_output
+    {
+        lock_node(output_node)?;
+        set_node_potential(output_node, (get_node_potential(input_node)? + delta).into())?;
+    }
+    else // driving input_node node:
+    {
+        lock_node(input_node)?;
+        set_node_potential(input_node, (get_node_potential(output_node)? - delta).into())?;
+    }
+
+    Ok(drives_output)
+}
+
+/// Represents a periodic current source. The gain gives the fourier coefficients
+/// of the source current as `[dc, cos(wt), sin(wt), cos(2wt), ...]`. Any
+/// coefficients that are omitted are assumed to be zero.
+pub fn periodic_current_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    current: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let current = pad_to_node(&input_node, current)?;
+
+    GenericElement::try_new(
+        current,
+        input_node, output_node,
+        constant_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Converts the turns ratio given to a transformer winding into the gain applied to the
+/// quantity it senses. Both windings use `-1 / turns_ratio`: the secondary drives a potential 
+/// opposite to the one it is given (see `observe_flux`), and the current entering the primary
+/// is the current leaving the secondary.
+fn winding_gain(turns_ratio: Vec<f64>) -> anyhow::Result<Vec<f64>>
+{
+    match *turns_ratio.as_slice()
+    {
+        [n] if n != 0.0 && n.is_finite() => Ok(vec![-1.0 / n]),
+        _ => Err(HarmonicBalanceElementError::TurnsRatio.into()),
+    }
+}
+
+/// Represents the primary winding of an ideal transformer. The gain is given as the
+/// turns ratio `primary turns / secondary turns`, and the primary's current is
+/// `-secondary current / turns ratio`, where the secondary winding is the element
+/// sensed through its control (see `NodalAnalysisStudyBuilder::add_transformer`).
+pub fn transformer_primary(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    turns_ratio: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    GenericElement::try_new(
+        winding_gain(turns_ratio)?,
+        input_node, output_node,
+        controlled_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents the secondary winding of an ideal transformer. The gain is given as the
+/// turns ratio `primary turns / secondary turns`, and the secondary's voltage is
+/// `primary voltage / turns ratio`, where the primary's voltage is the potential
+/// difference sensed through its control (see `NodalAnalysisStudyBuilder::add_transformer`).
+pub fn transformer_secondary(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    turns_ratio: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let gain = winding_gain(turns_ratio)?;
+
+    // The primary's voltage is not known yet, so start from no voltage across the winding
+    let zeros = pad_to_node(&input_node, vec![])?;
+    let drives_output = drive_node(&input_node, &output_node, Matrix::from_col_vec(zeros))?;
+
+    GenericElement::try_new(
+        gain,
+        input_node, output_node,
+        controlled_observe_flux,
+        drives_output,
+        drives_output,
+        !drives_output,
+    )
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Adds an ideal transformer with a single primary winding and any number of
+    /// secondary windings, each given as `(input, output, turns ratio)`, where the
+    /// turns ratio is `primary turns / secondary turns`. The voltage across each 
+    /// secondary is the voltage across the primary divided by its turns ratio, and 
+    /// no power is lost between the windings.
+    /// 
+    /// Each secondary is modelled as a `TRANSFORMER_SECONDARY` element that senses the 
+    /// voltage across the primary, followed by a `TRANSFORMER_PRIMARY` element across the
+    /// primary's nodes that senses the current through that secondary. The current drawn 
+    /// by the primary is the sum of the fluxes of these `TRANSFORMER_PRIMARY` elements.
+    /// 
+    /// Every secondary drives one of its nodes, so each one must have a node that is
+    /// not locked and is not shared with the primary.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::RESISTOR;
+    /// use neapolitan::harmonic_balance::*;
+    /// 
+    /// // A 2:1 step-down transformer feeding a 5 ohm load from a 
+    /// // 10.5 V (peak) source with an internal resistance of 1 ohm
+    /// let soln = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(4)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(PERIODIC_VOLTAGE_SOURCE, 0, 1, vec![0.0, 10.5]).unwrap()
+    ///     .add_element(RESISTOR, 1, 2, vec![1.0]).unwrap()
+    ///     .add_transformer(2, 0, &[(3, 0, 2.0)]).unwrap()
+    ///     .add_element(RESISTOR, 3, 0, vec![5.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// // The load is seen by the source as 5 * 2^2 = 20 ohms
+    /// let json = serde_json::to_value(soln).unwrap();
+    /// assert!((json["nodes"]["2"][1].as_f64().unwrap() - 10.0).abs() < 1E-9);
+    /// assert!((json["nodes"]["3"][1].as_f64().unwrap() - 5.0).abs() < 1E-9);
+    /// ```
+    pub fn add_transformer(self, primary_input: usize, primary_output: usize, secondaries: &[(usize, usize, f64)]) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        let mut builder = self;
+        for &(input, output, turns_ratio) in secondaries
+        {
+            builder = builder.add_controlled_element(
+                TRANSFORMER_SECONDARY, input, output, vec![turns_ratio],
+                ElementControl::Potential { input: primary_input, output: primary_output }
+            )?;
+
+            let element = builder.get_model().elements.len() - 1;
+            builder = builder.add_controlled_element(
+                TRANSFORMER_PRIMARY, primary_input, primary_output, vec![turns_ratio],
+                ElementControl::Flux { element }
+            )?;
+        }
+        Ok(builder)
+    }
+}
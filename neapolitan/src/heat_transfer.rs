@@ -1,6 +1,7 @@
 // Std modules
 use std::rc::{Rc, Weak};
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 // 3rd party modules
 use gmatlib::{col_vec, Matrix};
@@ -8,22 +9,51 @@ use thiserror::Error;
 
 use crate::errors::ElementCreationError;
 // Local modules
-use crate::{flux_formulas::*, get_node_potential, is_locked, lock_node, set_node_potential};
+use crate::{flux_formulas::*, get_node_coordinates, get_node_potential, is_locked, lock_node, set_node_potential};
 use crate::{GenericElement, GenericNode};
 
 #[derive(Debug, Error)]
 #[error("you must specify a conductivity coefficient 'k' and a length (in that order), or calculate the ratio youself to create a conductor element")]
 pub struct ConductorCreationError;
 
+#[derive(Debug, Error)]
+pub enum GeometricConductorCreationError
+{
+    #[error("you must specify only a conductivity coefficient 'k' to create a geometric_conductor element, as its length is the distance between its nodes")]
+    Conductivity,
+    #[error("both nodes of a geometric_conductor element must be placed with `place_node`, at different points")]
+    NotPlaced,
+}
+
 #[derive(Debug, Error)]
 #[error("you must specify only a convection coefficient 'h' to create a convection_interface element")]
 pub struct ConvectionInterFaceCreationError;
 
+#[derive(Debug, Error)]
+#[error("you must specify an emissivity and optionally a surface area (in that order) to create a radiation_interface element")]
+pub struct RadiationInterfaceCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must specify a convection coefficient 'h', an emissivity and optionally a surface area (in that order) to create a convection_radiation_interface element")]
+pub struct ConvectionRadiationInterfaceCreationError;
+
+#[derive(Debug, Error)]
+#[error("you must specify an area followed by a thickness, conductivity and contact resistance for each layer (in that order) to create a thermal_interface element")]
+pub struct ThermalInterfaceCreationError;
+
+/// The Stefan-Boltzmann constant in W/m^2-K^4.
+pub const STEFAN_BOLTZMANN: f64 = 5.670374419E-8;
+
 pub const HEAT_TRANSFER: &str = "heat_transfer";
 pub const CONDUCTOR: &str = "conductor";
+pub const GEOMETRIC_CONDUCTOR: &str = "geometric_conductor";
 pub const CONVECTION_INTERFACE: &str = "convection_interface";
+pub const RADIATION_INTERFACE: &str = "radiation_interface";
+pub const CONVECTION_RADIATION_INTERFACE: &str = "convection_radiation_interface";
 pub const TEMPERATURE_DELTA: &str = "temperature_delta";
 pub const HEAT_FLUX: &str = "heat_flux";
+pub const HEAT_SOURCE: &str = "heat_source";
+pub const THERMAL_INTERFACE: &str = "thermal_interface";
 
 /// Represents a simple 1-dimensional piece of conductive material with a
 /// different temperature at each end and known thermal conductivity (often
@@ -62,13 +92,64 @@ pub fn conductor(
         },
     };
 
-    GenericElement::try_new(
+    let elem = GenericElement::try_new(
         conductivity, 
         input_node, output_node,
         normal_flux, 
         false, 
         true, true
-    )
+    )?;
+    elem.set_post_process(heat_rate)?;
+    Ok(elem)
+}
+
+/// Represents a `conductor` whose length is the straight-line distance between its nodes,
+/// so that models built from a geometry (e.g. a mesh of a heat sink or a piping layout) 
+/// don't need each length worked out by hand. Both nodes must be placed with 
+/// `NodalAnalysisStudyBuilder::place_node`, and the gain is only the conductivity 'K'.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::heat_transfer::*;
+/// 
+/// // A 5 m bar between two nodes placed at opposite corners of a 3 m x 4 m plate
+/// let soln = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+///     .place_node(1, [3.0, 4.0, 0.0]).unwrap()
+///     .configure_node(0, vec![100.0], true, None)
+///     .configure_node(1, vec![0.0], true, None)
+///     .add_element(GEOMETRIC_CONDUCTOR, 0, 1, vec![2.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+/// 
+/// assert!((soln.get_element_flux(0).unwrap()[0] - 2.0 * 100.0 / 5.0).abs() < 1E-9);
+/// ```
+pub fn geometric_conductor(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    conductivity: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let k = match *conductivity.as_slice()
+    {
+        [k] => k,
+        _ => return Err(GeometricConductorCreationError::Conductivity.into()),
+    };
+
+    let length = match (get_node_coordinates(&input_node)?, get_node_coordinates(&output_node)?)
+    {
+        (Some(a), Some(b)) => a.iter().zip(&b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt(),
+        _ => return Err(GeometricConductorCreationError::NotPlaced.into()),
+    };
+    if length == 0.0
+    {
+        return Err(GeometricConductorCreationError::NotPlaced.into());
+    }
+
+    conductor(input_node, output_node, vec![length, k])
 }
 
 pub fn convection_interface(
@@ -82,13 +163,184 @@ pub fn convection_interface(
         return Err(ConvectionInterFaceCreationError.into());
     }
 
-    GenericElement::try_new(
+    let elem = GenericElement::try_new(
         convection_coef, 
         input_node, output_node, 
         normal_flux, 
         false, 
         true, true
-    )
+    )?;
+    elem.set_post_process(heat_rate)?;
+    Ok(elem)
+}
+
+/// Represents net thermal radiation between two surfaces, where the heat flux is 
+/// `emissivity * STEFAN_BOLTZMANN * area * (T_in^4 - T_out^4)`. If the area is not 
+/// given, the flux is per unit area.
+/// 
+/// Because the flux depends on the fourth power of each temperature, nodal 
+/// potentials in models using this element must be absolute temperatures 
+/// (i.e. in Kelvin).
+/// 
+/// # Example
+/// ```
+/// use std::rc::Rc;
+/// use neapolitan::set_node_potential;
+/// use neapolitan::modelling::GenericNode;
+/// use neapolitan::heat_transfer::{radiation_interface, STEFAN_BOLTZMANN};
+/// 
+/// let hot = GenericNode::new();
+/// let cold = GenericNode::new();
+/// 
+/// set_node_potential(&Rc::downgrade(&hot), vec![400.0]).unwrap();
+/// set_node_potential(&Rc::downgrade(&cold), vec![300.0]).unwrap();
+/// 
+/// let elem = radiation_interface(Rc::downgrade(&hot), Rc::downgrade(&cold), vec![0.5]).unwrap();
+/// let flux: Vec<f64> = elem.get_flux().unwrap().into();
+/// 
+/// let expected = 0.5 * STEFAN_BOLTZMANN * (400.0_f64.powi(4) - 300.0_f64.powi(4));
+/// assert!((flux[0] - expected).abs() < 1E-9);
+/// ```
+pub fn radiation_interface(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    emissivity_and_area: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let coefficient = match emissivity_and_area.as_slice()
+    {
+        [e] => e * STEFAN_BOLTZMANN,
+        [e, a] => e * STEFAN_BOLTZMANN * a,
+        _ => return Err(RadiationInterfaceCreationError.into()),
+    };
+
+    let elem = GenericElement::try_new(
+        vec![coefficient], 
+        input_node, output_node, 
+        fourth_power_flux, 
+        false, 
+        true, true
+    )?;
+    elem.set_post_process(heat_rate)?;
+    Ok(elem)
+}
+
+/// Represents a surface that loses heat by convection and radiation at once, where 
+/// the heat flux is `area * (h * (T_in - T_out) + emissivity * STEFAN_BOLTZMANN * (T_in^4 - T_out^4))`. 
+/// If the area is not given, the flux is per unit area. This is equivalent to a 
+/// `convection_interface` and a `radiation_interface` in parallel.
+/// 
+/// As with `radiation_interface`, nodal potentials in models using this 
+/// element must be absolute temperatures (i.e. in Kelvin).
+pub fn convection_radiation_interface(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    convection_emissivity_and_area: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let coefficients = match convection_emissivity_and_area.as_slice()
+    {
+        [h, e] => vec![*h, e * STEFAN_BOLTZMANN],
+        [h, e, a] => vec![h * a, e * STEFAN_BOLTZMANN * a],
+        _ => return Err(ConvectionRadiationInterfaceCreationError.into()),
+    };
+
+    let elem = GenericElement::try_new(
+        coefficients, 
+        input_node, output_node, 
+        linear_and_fourth_power_flux, 
+        false, 
+        true, true
+    )?;
+    elem.set_post_process(heat_rate)?;
+    Ok(elem)
+}
+
+/// Represents a thermal interface material (TIM) stack, e.g. between a chip and a heat sink,
+/// where heat flows through several layers of material in series, each followed by a contact
+/// resistance at its boundary with the next layer (or the output surface).
+/// 
+/// The gain is the area of the stack followed by the thickness, thermal conductivity and
+/// area-specific contact resistance (in m^2-K/W or an equivalent unit) of each layer, i.e.
+/// `[area, t_1, k_1, rc_1, t_2, k_2, rc_2, ...]`. The heat flux is 
+/// `area * (T_in - T_out) / sum(t_i / k_i + rc_i)`.
+/// 
+/// Besides the `"heat_rate"` through the stack, the element reports the temperature drop 
+/// across each layer as `"layer_<n>_drop"` and across each contact as `"contact_<n>_drop"`,
+/// numbered from 1 starting at the input node.
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::heat_transfer::*;
+/// 
+/// // A 10 W chip on a 1 cm^2 pad of paste and a copper spreader, over a 300 K sink
+/// let soln = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .configure_node(0, vec![300.0], true, None)
+///     .add_element(HEAT_SOURCE,       1, 1, vec![10.0]).unwrap()
+///     .add_element(THERMAL_INTERFACE, 1, 0, vec![
+///         1E-4,                   // area
+///         50E-6, 5.0,   1E-5,     // paste
+///         1E-3,  400.0, 2E-5,     // copper
+///     ]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+/// 
+/// let derived = soln.get_derived_quantities("thermal_interface.1").unwrap();
+/// 
+/// assert!((derived["layer_1_drop"] - 1.0).abs() < 1E-9);
+/// assert!((derived["contact_1_drop"] - 1.0).abs() < 1E-9);
+/// assert!((derived["layer_2_drop"] - 0.25).abs() < 1E-9);
+/// assert!((derived["contact_2_drop"] - 2.0).abs() < 1E-9);
+/// ```
+pub fn thermal_interface(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    area_and_layers: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let (area, layers) = match area_and_layers.split_first()
+    {
+        Some((area, layers)) if !layers.is_empty() && layers.len() % 3 == 0 => (*area, layers),
+        _ => return Err(ThermalInterfaceCreationError.into()),
+    };
+
+    let resistance: f64 = layers.chunks(3)
+        .map(|layer| layer[0] / layer[1] + layer[2])
+        .sum();
+
+    // The overall conductance comes first so that the flux can be found with `normal_flux`
+    let mut gain = vec![area / resistance];
+    gain.extend(area_and_layers);
+
+    let elem = GenericElement::try_new(
+        gain, 
+        input_node, output_node, 
+        normal_flux, 
+        false, 
+        true, true
+    )?;
+    elem.set_post_process(layer_temperature_drops)?;
+    Ok(elem)
+}
+
+/// A `PostProcessor` for `thermal_interface` elements, which reports the heat rate through 
+/// the stack and the temperature drop across each of its layers and contacts.
+fn layer_temperature_drops(flux: &Matrix<f64>, potentials: &[Matrix<f64>], gain: &Matrix<f64>) -> HashMap<String, f64>
+{
+    let mut derived = heat_rate(flux, potentials, gain);
+    let heat_flux = flux[(0, 0)] / gain[(1, 0)];
+
+    let layers: Vec<f64> = gain.iter().skip(2).copied().collect();
+    for (n, layer) in layers.chunks(3).enumerate()
+    {
+        derived.insert(format!("layer_{}_drop", n + 1), heat_flux * layer[0] / layer[1]);
+        derived.insert(format!("contact_{}_drop", n + 1), heat_flux * layer[2]);
+    }
+
+    derived
 }
 
 pub fn temperature_delta(
@@ -147,4 +399,41 @@ pub fn heat_flux(
         false,
         true, true
     )
-}
\ No newline at end of file
+}
+
+/// Represents heat generated at a node, e.g. by electrical resistance or a chemical 
+/// reaction, which adds a fixed wattage to the output node. Unlike a `heat_flux`, 
+/// the heat is not drawn from the input node, which is left unaffected. 
+/// 
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::heat_transfer::*;
+/// 
+/// // A 50 W heater on a block with a 10 W/K path to a 300 K ambient
+/// let soln = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .configure_node(0, vec![300.0], true, None)
+///     .add_element(HEAT_SOURCE,          1, 1, vec![50.0]).unwrap()
+///     .add_element(CONVECTION_INTERFACE, 1, 0, vec![10.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+/// 
+/// let soln = serde_json::to_value(soln).unwrap();
+/// assert!((soln["nodes"]["1"][0].as_f64().unwrap() - 305.0).abs() < 1E-6);
+/// ```
+pub fn heat_source(
+    input_node: Weak<RefCell<GenericNode>>, 
+    output_node: Weak<RefCell<GenericNode>>, 
+    power: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    GenericElement::try_new(
+        power, 
+        input_node, output_node, 
+        constant_flux, 
+        false,
+        false, true // Only the output node receives the heat
+    )
+}
@@ -11,18 +11,82 @@ pub mod dc_circuits;
 /// Contains constructor functions for elements usefule in
 /// modelling steady-state heat transfer problems.
 pub mod heat_transfer;
+/// Contains constructor functions for elements useful in finding the
+/// steady periodic response of nonlinear models driven by periodic sources.
+pub mod harmonic_balance;
+/// Contains constructor functions for elements useful in modelling
+/// three-phase electrical networks one phase at a time.
+pub mod three_phase;
+/// Contains constructor functions for elements useful in modelling
+/// magnetic circuits, e.g. to size the cores of solenoids and transformers.
+pub mod magnetic_circuit;
+/// Contains constructor functions for elements useful in modelling
+/// steady-state diffusion of a species through media and membranes.
+pub mod mass_diffusion;
+/// Contains constructor functions for elements that couple the domains
+/// of a multi-physics model, e.g. electrical and thermal nodes.
+pub mod multiphysics;
+/// Contains constructor functions for stateful elements and
+/// tools for stepping a model through time.
+pub mod transient;
+/// Contains types for solving a model at the corners of
+/// its elements' gain tolerances to find worst-case results.
+pub mod corner_analysis;
+/// Contains types for solving a model many times with randomly drawn
+/// element gains to find the statistics of its results.
+pub mod monte_carlo;
+/// Contains tools for solving many independent models or
+/// compiled studies at once on multiple threads.
+pub mod batch;
+/// Contains tools for finding the model parameter 
+/// that produces a desired nodal potential.
+pub mod goal_seek;
+/// Contains tools for recording the inputs to a study and 
+/// checking that re-running it reproduces the same results.
+pub mod audit;
+/// Contains tools for defining reusable subnetworks and
+/// adding any number of instances of them to a model.
+pub mod subcircuit;
+/// Contains the JSON Schema for model files and tools for
+/// validating models against it before they are read.
+pub mod schema;
+/// Contains tools for solving a model at many values 
+/// of its gains and locked potentials.
+pub mod sweep;
+/// Contains tools for reading models from SPICE netlists.
+pub mod spice;
+/// Contains tools for reading and writing models and 
+/// results as JSON, YAML or TOML files.
+pub mod formats;
+/// Contains tools for writing solved models as VTK files, 
+/// to visualize spatial networks in e.g. ParaView.
+pub mod vtk;
+/// Contains tools for writing configurators by the names that their element types 
+/// and flux formulas are registered under, and reading them back from those names.
+pub mod registry;
+/// Contains tools for ramping a model up to its full sources over several 
+/// solves, to converge models that diverge from a cold start.
+pub mod continuation;
+/// Contains the settings used by the solver, such as how it damps its steps.
+pub mod options;
+/// Contains an HTTP service for solving models on a remote machine.
+#[cfg(feature = "server")]
+pub mod server;
 
 // Standard modules
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
-use std::rc::{Rc, Weak};
+use std::ops::ControlFlow;
+use std::rc::Weak;
 use std::cell::RefCell;
+use std::time::Instant;
 
 use anyhow::Ok;
-use modelling::{NodalAnalysisElement, NodalAnalysisModel, NodalMetadata};
+use modelling::{ElementControl, NodalAnalysisElement, NodalAnalysisModel, NodalMetadata};
 // 3rd party modules
-use serde::Serialize;
-use geqslib::newton::multivariate_newton_raphson;
+use serde::{Deserialize, Serialize};
+use geqslib::newton::{multivariate_newton_raphson_sparse_with_options, multivariate_newton_raphson_system_with_options};
+use tracing::{debug, info, info_span, trace};
 
 /// This is a re-export of a `gmatlib::Matrix<T>`, a type for representing numerical 
 /// matrices and vectors and operating on them in a more math-oriented way.
@@ -37,12 +101,23 @@ use geqslib::newton::multivariate_newton_raphson;
 pub type Matrix<T> = gmatlib::Matrix<T>;
 
 // Local modules
-use errors::{DroppedNodeError, NodalAnalysisConfigurationError, NodalAnalysisModellingError};
-use modelling::element::{ElementConstructor, GenericElement};
+use errors::{CompiledStudyError, DroppedNodeError, ElementCreationError, NodalAnalysisConfigurationError, NodalAnalysisModellingError, SolveFailedError};
+use modelling::arena::{ArenaSensor, Constructor, ElementId, NodalArena, NodalGraph, NodeId};
+use modelling::element::{ElementConstructor, FluxCalculation, GenericElement, TerminalElementConstructor};
 use modelling::node::GenericNode;
 use serde_json::to_string_pretty;
 use heat_transfer::*;
 use dc_circuits::*;
+use harmonic_balance::{harmonic_balance_config, DEFAULT_HARMONICS, HARMONIC_BALANCE};
+use transient::{transient_circuit_config, TRANSIENT_CIRCUIT};
+use three_phase::{three_phase_config, THREE_PHASE};
+use magnetic_circuit::{magnetic_circuit_config, MAGNETIC_CIRCUIT};
+use mass_diffusion::{mass_diffusion_config, MASS_DIFFUSION};
+use multiphysics::*;
+use subcircuit::SubcircuitDefinition;
+use continuation::Continuation;
+use options::SolverOptions;
+use flux_formulas::builtin_flux_formulas;
 
 /// The default settings used by the neapolitan solver to build models
 #[inline]
@@ -57,7 +132,26 @@ pub fn default_study_builder_config() -> HashMap<String, NodalAnalysisStudyConfi
                 (RESISTOR,                resistor                as ElementConstructor),
                 (VOLTAGE_SOURCE,          voltage_source          as ElementConstructor),
                 (CURRENT_SOURCE,          current_source          as ElementConstructor),
+                (DIODE,                   diode                   as ElementConstructor),
+                (CHECK_VALVE,             check_valve             as ElementConstructor),
+                (BATTERY,                 battery                 as ElementConstructor),
+                (PUMP,                    pump                    as ElementConstructor),
+                (TEMPERATURE_DEPENDENT_RESISTOR, temperature_dependent_resistor as ElementConstructor),
+                (VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, voltage_controlled_voltage_source as ElementConstructor),
+                (VOLTAGE_CONTROLLED_CURRENT_SOURCE, voltage_controlled_current_source as ElementConstructor),
+                (CURRENT_CONTROLLED_VOLTAGE_SOURCE, current_controlled_voltage_source as ElementConstructor),
+                (CURRENT_CONTROLLED_CURRENT_SOURCE, current_controlled_current_source as ElementConstructor),
             ]),
+            terminal_elements: HashMap::from([
+                (NPN_TRANSISTOR,          npn_transistor          as TerminalElementConstructor),
+            ]),
+            aliases: HashMap::from([
+                ("res",                   RESISTOR),
+                ("vsrc",                  VOLTAGE_SOURCE),
+                ("isrc",                  CURRENT_SOURCE),
+            ]),
+            flux_formulas: builtin_flux_formulas(),
+            flux_elements: HashMap::new(),
         }),
         (HEAT_TRANSFER.to_string(), 
         NodalAnalysisStudyConfigurator
@@ -65,11 +159,26 @@ pub fn default_study_builder_config() -> HashMap<String, NodalAnalysisStudyConfi
             dimension: 1,
             elements: HashMap::from([
                 (CONDUCTOR,               conductor               as ElementConstructor),
+                (GEOMETRIC_CONDUCTOR,     geometric_conductor     as ElementConstructor),
                 (CONVECTION_INTERFACE,    convection_interface    as ElementConstructor),
+                (RADIATION_INTERFACE,     radiation_interface     as ElementConstructor),
+                (CONVECTION_RADIATION_INTERFACE, convection_radiation_interface as ElementConstructor),
                 (TEMPERATURE_DELTA,       temperature_delta       as ElementConstructor),
                 (HEAT_FLUX,               heat_flux               as ElementConstructor),
+                (HEAT_SOURCE,             heat_source             as ElementConstructor),
+                (THERMAL_INTERFACE,       thermal_interface       as ElementConstructor),
+                (JOULE_HEAT_SOURCE,       joule_heat_source       as ElementConstructor),
             ]),
+            terminal_elements: HashMap::new(),
+            aliases: HashMap::new(),
+            flux_formulas: builtin_flux_formulas(),
+            flux_elements: HashMap::new(),
         }),
+        (HARMONIC_BALANCE.to_string(), harmonic_balance_config(DEFAULT_HARMONICS)),
+        (TRANSIENT_CIRCUIT.to_string(), transient_circuit_config()),
+        (THREE_PHASE.to_string(), three_phase_config()),
+        (MAGNETIC_CIRCUIT.to_string(), magnetic_circuit_config()),
+        (MASS_DIFFUSION.to_string(), mass_diffusion_config()),
     ])
 }
 
@@ -80,11 +189,291 @@ struct ComponentIndex
     component: u32,
 }
 
-#[derive(Clone, Debug, PartialEq, Serialize)]
+/// Refers to a node or element in a study's results, either by its index in the model 
+/// or by its name in the results. Nodes are named by their name or else their index, and 
+/// elements by their label or else `"<element_type>.<element_index>"`.
+/// 
+/// Indices can only be resolved in results that were solved in this process, as results 
+/// that are read back from a file do not say which node or element each name belongs to. 
+/// The index of an unnamed node is its name, so it is always resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResultKey<'a>
+{
+    Index(usize),
+    Name(&'a str),
+}
+impl From<usize> for ResultKey<'_>
+{
+    fn from(value: usize) -> Self
+    {
+        ResultKey::Index(value)
+    }
+}
+impl <'a> From<&'a str> for ResultKey<'a>
+{
+    fn from(value: &'a str) -> Self
+    {
+        ResultKey::Name(value)
+    }
+}
+impl <'a> From<&'a String> for ResultKey<'a>
+{
+    fn from(value: &'a String) -> Self
+    {
+        ResultKey::Name(value)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NodalAnalysisStudyResult
 {
-    nodes: HashMap<u32, Vec<f64>>,
+    nodes: HashMap<String, Vec<f64>>,
     elements: HashMap<String, Vec<f64>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    derived: HashMap<String, HashMap<String, f64>>,
+    #[serde(default)]
+    statistics: SolverStatistics,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(skip)]
+    node_names: Vec<String>,
+    #[serde(skip)]
+    element_names: Vec<String>,
+}
+impl NodalAnalysisStudyResult
+{
+    /// Returns the potential of the given node, referred to by its index or name (see `ResultKey`).
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(1)
+    ///     .add_named_node("vcc").unwrap()
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR, 1, 0, vec![5.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// assert_eq!(soln.get_node_potential("vcc"), soln.get_node_potential(1));
+    /// assert!((soln.get_node_potential("vcc").unwrap()[0] - 10.0).abs() < 1E-9);
+    /// 
+    /// // Elements are found by label, by their name in the results or by their index
+    /// assert_eq!(soln.get_element_flux("resistor.1"), soln.get_element_flux(1));
+    /// assert!((soln.get_element_flux(1).unwrap()[0] - 2.0).abs() < 1E-9);
+    /// assert!(soln.get_element_flux("V1").is_some());
+    /// assert!(soln.get_element_flux(2).is_none());
+    /// ```
+    pub fn get_node_potential<'a>(&self, node: impl Into<ResultKey<'a>>) -> Option<&[f64]>
+    {
+        let potential = match node.into()
+        {
+            ResultKey::Name(name) => self.nodes.get(name),
+            ResultKey::Index(idx) => match self.node_names.get(idx)
+            {
+                Some(name) => self.nodes.get(name),
+                None => self.nodes.get(&idx.to_string()),
+            },
+        };
+        potential.map(|x| x.as_slice())
+    }
+
+    /// Returns the flux through the given element, referred to by its index or name (see `ResultKey`).
+    pub fn get_element_flux<'a>(&self, element: impl Into<ResultKey<'a>>) -> Option<&[f64]>
+    {
+        let flux = match element.into()
+        {
+            ResultKey::Name(name) => self.elements.get(name),
+            ResultKey::Index(idx) => self.element_names.get(idx).and_then(|name| self.elements.get(name)),
+        };
+        flux.map(|x| x.as_slice())
+    }
+
+    /// Iterates over the name and potential of every node, in the same order as the 
+    /// columns of `to_csv`, i.e. indices in numerical order followed by names in alphabetical order.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let names: Vec<&str> = soln.iter_nodes().map(|(name, _)| name).collect();
+    /// assert_eq!(names, vec!["0", "1", "2"]);
+    /// 
+    /// // Kirchhoff's current law holds for every resistor
+    /// let currents: Vec<f64> = soln.iter_elements()
+    ///     .filter(|(name, _)| name.starts_with(RESISTOR))
+    ///     .map(|(_, flux)| flux[0])
+    ///     .collect();
+    /// assert!((currents[0] - currents[1]).abs() < 1E-9);
+    /// ```
+    pub fn iter_nodes(&self) -> impl Iterator<Item = (&str, &[f64])>
+    {
+        sort_names(&self.nodes).into_iter().map(|name| (name.as_str(), self.nodes[name].as_slice()))
+    }
+
+    /// Iterates over the name and flux of every element, in the same order as the columns of `to_csv`.
+    pub fn iter_elements(&self) -> impl Iterator<Item = (&str, &[f64])>
+    {
+        sort_names(&self.elements).into_iter().map(|name| (name.as_str(), self.elements[name].as_slice()))
+    }
+
+    /// Returns how the solver reached this result.
+    pub fn get_statistics(&self) -> &SolverStatistics
+    {
+        &self.statistics
+    }
+
+    /// Returns the quantities derived from an element's solved state by its `PostProcessor` 
+    /// (e.g. the `"power"` dissipated by a resistor or the `"heat_rate"` through a conductor), 
+    /// keyed by element name as in the study's results. Elements that derive nothing are not included.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_labeled_element("R1", RESISTOR,       1, 0, vec![5.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// // The source delivers the 20 W that the resistor dissipates
+    /// assert!((soln.get_derived_quantities("R1").unwrap()["power"] - 20.0).abs() < 1E-6);
+    /// assert!((soln.get_derived_quantities("V1").unwrap()["power"] + 20.0).abs() < 1E-6);
+    /// ```
+    pub fn get_derived_quantities(&self, element: &str) -> Option<&HashMap<String, f64>>
+    {
+        self.derived.get(element)
+    }
+
+    /// Returns any changes that were made to the model in order to solve it, 
+    /// e.g. grounding a model that had no locked node (see `with_auto_ground`).
+    pub fn get_warnings(&self) -> &Vec<String>
+    {
+        &self.warnings
+    }
+
+    /// Formats the result as a single row of csv with a header, so that it can be opened in a 
+    /// spreadsheet or read by a dataframe library. Each component of each nodal potential is a 
+    /// `node:<name>` column, followed by `element:<name>` columns for each elemental flux and 
+    /// `element:<name>:<quantity>` columns for each derived quantity (see `get_derived_quantities`).
+    /// Components after the first are suffixed with their index, e.g. `node:out[1]`.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_labeled_element("R1", RESISTOR,       1, 0, vec![5.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let csv = soln.to_csv();
+    /// let mut lines = csv.lines();
+    /// assert_eq!(lines.next().unwrap(), "node:0,node:1,element:R1,element:V1,element:R1:power,element:V1:power");
+    /// assert!(lines.next().unwrap().starts_with("0,10,2,"));
+    /// ```
+    pub fn to_csv(&self) -> String
+    {
+        let mut columns = csv_columns("node", &self.nodes);
+        columns.extend(csv_columns("element", &self.elements));
+
+        let derived: HashMap<String, Vec<f64>> = self.derived.iter()
+            .flat_map(|(element, quantities)| quantities.iter()
+                .map(move |(quantity, &value)| (format!("{element}:{quantity}"), vec![value])))
+            .collect();
+        columns.extend(csv_columns("element", &derived));
+
+        let (header, row): (Vec<String>, Vec<Vec<f64>>) = columns.into_iter().unzip();
+        write_csv(&header, &[row])
+    }
+}
+impl PartialEq for NodalAnalysisStudyResult
+{
+    /// Results are equal if their potentials and fluxes are, regardless of how they were solved.
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.nodes == other.nodes && self.elements == other.elements
+    }
+}
+
+/// Describes how the solver converged on a study's result, so that a solve
+/// that barely converged can be told apart from one that converged robustly.
+/// 
+/// # Fields:
+/// - `iterations` - the number of newton-raphson iterations taken
+/// - `residual_norm` - the norm of the nodal flux discrepancies at the solution
+/// - `residual_history` - the norm of the nodal flux discrepancies at the start of each iteration
+/// - `wall_time` - the time taken to solve the model, in seconds
+/// - `element_convergence` - how each element behaved as the solver converged, keyed by 
+///   element name as in the study's results
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct SolverStatistics
+{
+    pub iterations: usize,
+    pub residual_norm: f64,
+    pub residual_history: Vec<f64>,
+    pub wall_time: f64,
+    #[serde(default)]
+    pub element_convergence: HashMap<String, ElementConvergence>,
+}
+
+/// The number of final solver iterations that `ElementConvergence` describes.
+pub const FINAL_ITERATIONS: usize = 3;
+
+/// Describes how a single element behaved as the solver converged. Elements whose flux
+/// was still changing the most over the final iterations are the ones driving the residual,
+/// which often points to an element with an extreme gain that dominates the conditioning
+/// of the model.
+/// 
+/// # Fields:
+/// - `flux_magnitude` - the norm of the element's flux at the solution
+/// - `residual_contribution` - the element's share (from 0 to 1) of the total change in 
+///   elemental flux over the final `FINAL_ITERATIONS` iterations, or 0 if no flux changed
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ElementConvergence
+{
+    pub flux_magnitude: f64,
+    pub residual_contribution: f64,
+}
+
+/// Decides which element type is kept when a configurator inherits an element type
+/// with the same name as one it already has (see `NodalAnalysisStudyConfigurator::inherit`).
+/// 
+/// # Variants:
+/// - `Reject` - inheriting fails with an `ElementTypeNameCollision` error
+/// - `Override` - the inheriting configurator keeps its own element type
+/// - `Inherit` - the inherited element type replaces the inheriting configurator's
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CollisionRule
+{
+    Reject,
+    Override,
+    Inherit,
 }
 
 /// A builder struct for building a customized instance of 
@@ -92,6 +481,10 @@ pub struct NodalAnalysisStudyResult
 /// user to extend it's functionality by adding custom elements
 /// and flux calculations to the engine's vocabulary.
 /// 
+/// Element types and flux formulas are registered under names, so a configurator can be
+/// written to a file by those names and read back against another configurator that has 
+/// registered the same names (see the `registry` module).
+/// 
 /// # Example
 /// ```
 /// 
@@ -101,11 +494,17 @@ pub struct NodalAnalysisStudyConfigurator
 {
     dimension: usize,
     elements: HashMap<&'static str, ElementConstructor>,
+    terminal_elements: HashMap<&'static str, TerminalElementConstructor>,
+    aliases: HashMap<&'static str, &'static str>,
+    flux_formulas: HashMap<&'static str, FluxCalculation>,
+    flux_elements: HashMap<&'static str, &'static str>,
 }
 impl NodalAnalysisStudyConfigurator
 {
     /// Creates a new `NodalAnalysisStudyConfigurator` instance, allowing 
     /// a user to create a customized instance of the Neapolitan solver engine.
+    /// It starts with no element types, and with the flux formulas given by 
+    /// `flux_formulas::builtin_flux_formulas` registered.
     /// 
     /// # Example
     /// ```
@@ -117,6 +516,10 @@ impl NodalAnalysisStudyConfigurator
         {
             dimension,
             elements: HashMap::new(),
+            terminal_elements: HashMap::new(),
+            aliases: HashMap::new(),
+            flux_formulas: builtin_flux_formulas(),
+            flux_elements: HashMap::new(),
         }
     }
 
@@ -129,7 +532,242 @@ impl NodalAnalysisStudyConfigurator
     /// ```
     pub fn add_element_type(mut self, name: &'static str, element_type: ElementConstructor) -> anyhow::Result<NodalAnalysisStudyConfigurator>
     {
-        if self.elements.insert(name, element_type).is_none()
+        if !self.has_element_type(name) && self.elements.insert(name, element_type).is_none()
+        {
+            Ok(self)
+        }
+        else
+        {
+            Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into())
+        }
+    }
+
+    /// Returns `true` if elements named `name` can be built with this configuration.
+    pub fn has_element_type(&self, name: &str) -> bool
+    {
+        self.elements.contains_key(name) || self.terminal_elements.contains_key(name) || self.flux_elements.contains_key(name)
+    }
+
+    /// Registers a flux calculation under `name`, so that element types can be defined by 
+    /// the name of their flux formula alone (see `add_flux_element_type`), e.g. in a 
+    /// configuration read from a file.
+    pub fn add_flux_formula(mut self, name: &'static str, flux_calc: FluxCalculation) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        if self.flux_formulas.insert(name, flux_calc).is_some()
+        {
+            return Err(NodalAnalysisConfigurationError::FluxFormulaNameCollision(name.to_owned()).into());
+        }
+        Ok(self)
+    }
+
+    /// Adds an element type whose elements connect an input and an output node with the 
+    /// flux formula registered as `flux_formula` (see `add_flux_formula`), passing the gain 
+    /// given in the model to it unchanged. Unlike an `ElementConstructor`, this element type
+    /// is described entirely by names, so it can be written to and read from a file.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::{NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator};
+    /// use neapolitan::dc_circuits::*;
+    /// use std::collections::HashMap;
+    /// 
+    /// // A resistor given by its conductance rather than its resistance
+    /// let config = NodalAnalysisStudyConfigurator::new(1)
+    ///     .add_element_type(VOLTAGE_SOURCE, voltage_source).unwrap()
+    ///     .add_flux_element_type("conductance", "normal_flux").unwrap();
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new("circuit".to_string(), Some(HashMap::from([("circuit".to_string(), config)])))
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V", VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_labeled_element("G", "conductance",  1, 0, vec![0.5]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["elements"]["G"][0].as_f64().unwrap() - 2.5).abs() < 1E-9);
+    /// ```
+    pub fn add_flux_element_type(mut self, name: &'static str, flux_formula: &str) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        let formula = match self.flux_formulas.get_key_value(flux_formula)
+        {
+            Some((&formula, _)) => formula,
+            None => return Err(NodalAnalysisConfigurationError::FluxFormulaNotFound(flux_formula.to_owned()).into()),
+        };
+
+        if self.has_element_type(name)
+        {
+            return Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into());
+        }
+
+        self.flux_elements.insert(name, formula);
+        Ok(self)
+    }
+
+    /// Removes the element type `name`, however it was added.
+    fn remove_element_type(&mut self, name: &str)
+    {
+        self.elements.remove(name);
+        self.terminal_elements.remove(name);
+        self.flux_elements.remove(name);
+    }
+
+    /// Registers `alias` as another name for the element type `name`, e.g. `"res"` for 
+    /// `"resistor"`, so that models can refer to elements by either name without the element 
+    /// type being added twice. Aliases must not collide with an element type or another alias.
+    /// 
+    /// Element types given to a `NodalAnalysisStudyBuilder` are resolved to the names that they
+    /// alias (see `resolve_element_type`) as they are added, so study results and exported 
+    /// models only ever use the element type's own name.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::{NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator};
+    /// use neapolitan::dc_circuits::*;
+    /// use std::collections::HashMap;
+    /// 
+    /// let config = NodalAnalysisStudyConfigurator::new(1)
+    ///     .add_element_type(RESISTOR, resistor).unwrap()
+    ///     .add_element_type(VOLTAGE_SOURCE, voltage_source).unwrap()
+    ///     .add_alias("r", RESISTOR).unwrap()
+    ///     .add_alias("v", VOLTAGE_SOURCE).unwrap();
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new("circuit".to_string(), Some(HashMap::from([("circuit".to_string(), config)])))
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element("V", 0, 1, vec![5.0]).unwrap()
+    ///     .add_element("r", 1, 0, vec![2.0]).unwrap();
+    /// 
+    /// let soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    /// assert!((soln["elements"]["resistor.1"][0].as_f64().unwrap() - 2.5).abs() < 1E-9);
+    /// ```
+    pub fn add_alias(mut self, alias: &'static str, name: &'static str) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        if !self.has_element_type(name)
+        {
+            return Err(NodalAnalysisConfigurationError::AliasTargetNotFound(name.to_owned()).into());
+        }
+
+        if self.has_element_type(alias) || self.aliases.insert(alias, name).is_some()
+        {
+            return Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into());
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the name of the element type that `name` refers to in this configuration, if any. 
+    /// Element type names are matched first, then aliases (see `add_alias`), and then both 
+    /// again ignoring case, so that e.g. `"Resistor"` and `"RES"` refer to a `"resistor"`.
+    pub fn resolve_element_type(&self, name: &str) -> Option<&'static str>
+    {
+        let names = self.elements.keys().chain(self.terminal_elements.keys()).chain(self.flux_elements.keys()).map(|&x| (x, x));
+        let aliases = self.aliases.iter().map(|(&alias, &x)| (alias, x));
+        let candidates: Vec<(&'static str, &'static str)> = names.chain(aliases).collect();
+
+        candidates.iter()
+            .find(|(alias, _)| *alias == name)
+            .or_else(|| candidates.iter().find(|(alias, _)| alias.eq_ignore_ascii_case(name)))
+            .map(|&(_, x)| x)
+    }
+
+    /// Adds every element type of `base` to this configuration, so that custom element packs
+    /// can be layered on top of another configuration (e.g. the built-in `dc_circuit` elements)
+    /// instead of redeclaring it. Element types defined by both are resolved by `rule`, and both 
+    /// configurations must have the same dimension.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::{default_study_builder_config, CollisionRule, NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator};
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let dc = &default_study_builder_config()[DC_CIRCUIT];
+    /// let config = NodalAnalysisStudyConfigurator::new(1)
+    ///     .add_element_type("fuse", resistor).unwrap()
+    ///     .inherit(dc, CollisionRule::Reject).unwrap();
+    /// 
+    /// assert!(config.has_element_type("fuse"));
+    /// assert!(config.has_element_type(VOLTAGE_SOURCE));
+    /// 
+    /// // Redefining a built-in element is only allowed if the collision rule says which to keep
+    /// let custom = NodalAnalysisStudyConfigurator::new(1).add_element_type(RESISTOR, resistor).unwrap();
+    /// assert!(custom.clone().inherit(dc, CollisionRule::Reject).is_err());
+    /// assert!(custom.inherit(dc, CollisionRule::Override).is_ok());
+    /// ```
+    pub fn inherit(mut self, base: &NodalAnalysisStudyConfigurator, rule: CollisionRule) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        if self.dimension != base.dimension
+        {
+            return Err(NodalAnalysisConfigurationError::DimensionMismatch(self.dimension, base.dimension).into());
+        }
+
+        for (&name, &element_type) in &base.elements
+        {
+            if self.has_element_type(name)
+            {
+                match rule
+                {
+                    CollisionRule::Reject => return Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into()),
+                    CollisionRule::Override => continue,
+                    CollisionRule::Inherit => self.remove_element_type(name),
+                };
+            }
+            self.elements.insert(name, element_type);
+        }
+
+        for (&name, &element_type) in &base.terminal_elements
+        {
+            if self.has_element_type(name)
+            {
+                match rule
+                {
+                    CollisionRule::Reject => return Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into()),
+                    CollisionRule::Override => continue,
+                    CollisionRule::Inherit => self.remove_element_type(name),
+                };
+            }
+            self.terminal_elements.insert(name, element_type);
+        }
+
+        // Flux formulas are needed by the flux element types that refer to them
+        for (&name, &flux_calc) in &base.flux_formulas
+        {
+            self.flux_formulas.entry(name).or_insert(flux_calc);
+        }
+
+        for (&name, &formula) in &base.flux_elements
+        {
+            if self.has_element_type(name)
+            {
+                match rule
+                {
+                    CollisionRule::Reject => return Err(NodalAnalysisConfigurationError::ElementTypeNameCollision.into()),
+                    CollisionRule::Override => continue,
+                    CollisionRule::Inherit => self.remove_element_type(name),
+                };
+            }
+            self.flux_elements.insert(name, formula);
+        }
+
+        // Aliases are kept unless they would hide an element type or another alias
+        for (&alias, &name) in &base.aliases
+        {
+            if !self.has_element_type(alias) && !self.aliases.contains_key(alias) && self.has_element_type(name)
+            {
+                self.aliases.insert(alias, name);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Adds a custom element with any number of terminals to the study configuration.
+    /// These elements are added to a model with `NodalAnalysisStudyBuilder::add_terminal_element`.
+    pub fn add_terminal_element_type(mut self, name: &'static str, element_type: TerminalElementConstructor) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        if !self.has_element_type(name) && self.terminal_elements.insert(name, element_type).is_none()
         {
             Ok(self)
         }
@@ -145,6 +783,11 @@ pub struct NodalAnalysisStudyBuilder
 {
     pub (in crate) configurator: HashMap<String, NodalAnalysisStudyConfigurator>,
     pub (in crate) model: NodalAnalysisModel, 
+    pub (in crate) subcircuits: HashMap<String, SubcircuitDefinition>,
+    pub (in crate) auto_ground: bool,
+    pub (in crate) sparse_jacobian: bool,
+    pub (in crate) continuation: Option<Continuation>,
+    pub (in crate) conservation_tolerance: Option<f64>,
 }
 impl NodalAnalysisStudyBuilder
 {
@@ -170,178 +813,1364 @@ impl NodalAnalysisStudyBuilder
                 nodes: 0,
                 configuration: HashMap::new(),
                 elements: vec![],
+                node_names: HashMap::new(),
+                element_labels: HashMap::new(),
+                node_domains: HashMap::new(),
             },
+            subcircuits: HashMap::new(),
+            auto_ground: false,
+            sparse_jacobian: false,
+            continuation: None,
+            conservation_tolerance: None,
         })
     }
 
     pub fn from_model_with_default_config(model: NodalAnalysisModel) -> NodalAnalysisStudyBuilder
     {
-        NodalAnalysisStudyBuilder
+        let mut builder = NodalAnalysisStudyBuilder
         {
             configurator: default_study_builder_config(),
             model,
+            subcircuits: HashMap::new(),
+            auto_ground: false,
+            sparse_jacobian: false,
+            continuation: None,
+            conservation_tolerance: None,
+        };
+
+        // Hand-written models may use aliases or a different case for element types
+        for idx in 0..builder.model.elements.len()
+        {
+            let NodalAnalysisElement { element_type, input, .. } = &builder.model.elements[idx];
+            builder.model.elements[idx].element_type = builder.resolve_element_type(element_type, *input);
         }
+        builder
     }
 
-    fn get_element_constructor(&self, elem: &str) -> ElementConstructor
+    /// Returns the model that this builder will solve.
+    pub fn get_model(&self) -> &NodalAnalysisModel
     {
-        let configurator = &self.configurator[&self.model.model_type];
-        configurator.elements[elem]
+        &self.model
     }
 
-    fn get_dimension(&self) -> usize
+    /// Returns the name of the element type that `elem` refers to in the model type of the domain
+    /// that `node` belongs to (see `NodalAnalysisStudyConfigurator::resolve_element_type`), or 
+    /// `elem` if there is none.
+    fn resolve_element_type(&self, elem: &str, node: usize) -> String
     {
-        let configurator = &self.configurator[&self.model.model_type];
-        configurator.dimension
+        match self.configurator.get(self.model.get_node_domain(node)).and_then(|x| x.resolve_element_type(elem))
+        {
+            Some(name) => name.to_owned(),
+            None => elem.to_owned(),
+        }
     }
 
-    pub fn add_nodes(mut self, n: usize) -> NodalAnalysisStudyBuilder
+    fn get_element_constructor(&self, elem: &str, node: usize) -> anyhow::Result<Constructor>
     {
-        self.model.nodes += n;
-        self
+        let configurator = &self.configurator[self.model.get_node_domain(node)];
+        let elem = configurator.resolve_element_type(elem).unwrap_or(elem);
+        let flux_calc = configurator.flux_elements.get(elem).and_then(|&formula| configurator.flux_formulas.get(formula));
+        match (configurator.elements.get(elem), configurator.terminal_elements.get(elem), flux_calc)
+        {
+            (Some(&constructor), _, _) => Ok(Constructor::Two(constructor)),
+            (None, Some(&constructor), _) => Ok(Constructor::Many(constructor)),
+            (None, None, Some(&flux_calc)) => Ok(Constructor::Flux(flux_calc)),
+            (None, None, None) => Err(NodalAnalysisModellingError::ElementTypeNotFound(elem.to_owned()).into()),
+        }
     }
 
-    pub fn configure_node(mut self, node: usize, potential: Vec<f64>, is_locked: bool, metadata: Option<HashMap<String, f64>>) -> NodalAnalysisStudyBuilder
+    /// Returns the number of components in the potential of the node at index `node`,
+    /// which is the dimension of the domain that it belongs to.
+    fn get_node_dimension(&self, node: usize) -> usize
     {
-        self.model.configuration.insert(node, NodalMetadata { potential, is_locked, metadata });
-        self
+        let configurator = &self.configurator[self.model.get_node_domain(node)];
+        configurator.dimension
     }
 
-    pub fn add_element(mut self, element: &str, input: usize, output: usize, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
-    {
-        if input >= self.model.nodes || output >= self.model.nodes
-        {
-            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
-        };
-        self.model.elements.push(
-            NodalAnalysisElement { element_type: element.to_string(), input, output, gain, }
-        );
-        Ok(self)
-    }    
-
-    pub fn save_model(self, model_rep: &mut String) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    /// Makes sure that every node an element connects belongs to the same domain, and
+    /// returns the name of the element type that `elem` refers to in that domain.
+    fn resolve_connected_element_type(&self, elem: &str, nodes: &[usize]) -> anyhow::Result<String>
     {
-        let res = to_string_pretty(&self.model);
-        if let Err(e) = res
+        let domain = self.model.get_node_domain(nodes[0]);
+        if nodes.iter().any(|&node| self.model.get_node_domain(node) != domain)
         {
-            return Err(e.into());
+            return Err(NodalAnalysisModellingError::DomainMismatch(elem.to_owned()).into());
         }
-        *model_rep = res.unwrap();
-        Ok(self)
+        Ok(self.resolve_element_type(elem, nodes[0]))
     }
 
-    pub fn run_study(self, margin: f64, limit: usize) -> anyhow::Result<NodalAnalysisStudyResult>
+    pub fn add_nodes(mut self, n: usize) -> NodalAnalysisStudyBuilder
     {
-        let n = self.get_dimension();
-        let mut nodes = vec![];
-        let mut elements = vec![];
+        self.model.nodes += n;
+        self
+    }
 
-        // Step 1 - create/initialize nodes for model
-        for _ in 0..self.model.nodes
+    /// Adds `n` nodes to the model that belong to the domain of another model type, e.g. the 
+    /// thermal nodes of an electrical model, so that several physical domains are solved together
+    /// in a single study. Each node's potential has as many components as its domain's dimension, 
+    /// and elements that connect it are looked up in its domain's configurator.
+    /// 
+    /// An element can only connect nodes of one domain. Domains are coupled by controlled elements 
+    /// (see `add_controlled_element`) that sense the potentials or fluxes of another domain, e.g. a
+    /// `multiphysics::temperature_dependent_resistor` that senses the temperature of a thermal node. 
+    /// Since the whole model is solved at once, the coupling is self-consistent without iterating 
+    /// between separate studies. Each domain needs a locked node to reference its potentials to.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::heat_transfer::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .add_domain_nodes(HEAT_TRANSFER, 2).unwrap()
+    ///     .add_element(CONDUCTOR, 2, 3, vec![0.5]).unwrap();
+    /// 
+    /// assert_eq!(builder.get_model().get_node_domain(3), HEAT_TRANSFER);
+    /// assert!(builder.add_element(RESISTOR, 1, 2, vec![1.0]).is_err());
+    /// ```
+    pub fn add_domain_nodes(mut self, domain: &str, n: usize) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if !self.configurator.contains_key(domain)
         {
-            nodes.push(
-                Rc::new(RefCell::new(GenericNode 
-                {
-                    potential: Matrix::from_col_vec(vec![1.0; n]),
-                    inputs: vec![],
-                    outputs: vec![],
-                    is_locked: false,
-                    _metadata: None,
-                }))
-            );
+            return Err(NodalAnalysisModellingError::ModelTypeNotFound.into());
         }
 
-        // Step 2 - set nodal metadata if it is given
-        for (&i, node_data) in &self.model.configuration
+        if domain != self.model.model_type
         {
-            let mut node = nodes[i].borrow_mut();
-            node.potential = Matrix::from_col_vec(node_data.potential.to_vec());
-            node.is_locked = node_data.is_locked;
-            node._metadata = node_data.metadata.clone();
+            for node in self.model.nodes..self.model.nodes + n
+            {
+                self.model.node_domains.insert(node, domain.to_owned());
+            }
         }
+        self.model.nodes += n;
+        Ok(self)
+    }
 
-        // Step 3 - build model 
-        for element_data in &self.model.elements
-        {
-            let NodalAnalysisElement { element_type, input, output, gain } = element_data;
-            let constructor = self.get_element_constructor(element_type);
-            elements.push(constructor(
-                Rc::downgrade(&nodes[*input]), 
-                Rc::downgrade(&nodes[*output]), 
-                gain.to_vec(),
-            )?);
+    /// Adds a single node to the model with the given name, which identifies it in study
+    /// results in place of its index. Its index is the number of nodes added before it, and
+    /// can be looked up with `NodalAnalysisModel::get_node_index`.
+    /// 
+    /// Names must be unique and must not be a number, so that they cannot be mistaken for 
+    /// the index of an unnamed node.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_named_node("gnd").unwrap()
+    ///     .add_named_node("vcc").unwrap()
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_labeled_element("R1", RESISTOR,       1, 0, vec![2.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["nodes"]["vcc"][0].as_f64().unwrap() - 5.0).abs() < 1E-9);
+    /// assert!((soln["elements"]["R1"][0].as_f64().unwrap() - 2.5).abs() < 1E-9);
+    /// ```
+    pub fn add_named_node(mut self, name: &str) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if name.parse::<usize>().is_ok() || self.model.get_node_index(name).is_some()
+        {
+            return Err(NodalAnalysisModellingError::NodeNameCollision(name.to_owned()).into());
+        }
+
+        self.model.node_names.insert(self.model.nodes, name.to_owned());
+        self.model.nodes += 1;
+        Ok(self)
+    }
+
+    /// Sets whether a model with no locked node is grounded by locking node 0 at zero potential
+    /// when it is built. Otherwise, such models fail to build with a `NoReferenceNode` error, as
+    /// every potential in them could be offset by the same amount without changing any flux.
+    /// The same goes for each domain added with `add_domain_nodes`, whose first node is locked 
+    /// if none of its nodes are, or which fails with a `FloatingDomain` error otherwise.
+    /// Results of a model that was grounded this way say so in their warnings.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    ///
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 0, vec![3.0]).unwrap();
+    ///
+    /// assert!(builder.clone().run_study(1E-9, 100).is_err());
+    ///
+    /// let soln = builder.with_auto_ground(true).run_study(1E-9, 100).unwrap();
+    /// assert_eq!(soln.get_warnings().len(), 1);
+    ///
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["nodes"]["1"][0].as_f64().unwrap() - 6.0).abs() < 1E-6);
+    /// ```
+    pub fn with_auto_ground(mut self, enabled: bool) -> NodalAnalysisStudyBuilder
+    {
+        self.auto_ground = enabled;
+        self
+    }
+
+    /// Whether the solver should only approximate the parts of the jacobian that the model's 
+    /// topology says can be nonzero, i.e. how each flux balance changes with the potentials of 
+    /// the nodes that share an element with it (or that its elements sense), and solve for each 
+    /// step with a sparse linear solve. This is disabled by default.
+    /// 
+    /// A dense jacobian costs one evaluation of every flux balance per unknown and a matrix 
+    /// inversion on each iteration, which grows too slow for models with more than a few hundred 
+    /// nodes. With a sparse jacobian, models with thousands of nodes can be solved.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// // A ladder of 1000 resistors, driven at one end and grounded at the other
+    /// let n = 1000;
+    /// let mut builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(n + 1)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap();
+    /// 
+    /// for i in 1..n
+    /// {
+    ///     builder = builder.add_element(RESISTOR, i, i + 1, vec![1.0]).unwrap();
+    /// }
+    /// builder = builder.add_element(RESISTOR, n, 0, vec![1.0]).unwrap();
+    /// 
+    /// let soln = builder.with_sparse_jacobian(true).run_study(1E-9, 100).unwrap();
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// 
+    /// // Every resistor drops the same potential
+    /// let v = soln["nodes"]["500"][0].as_f64().unwrap();
+    /// assert!((v - 10.0 * 501.0 / 1000.0).abs() < 1E-6);
+    /// ```
+    pub fn with_sparse_jacobian(mut self, enabled: bool) -> NodalAnalysisStudyBuilder
+    {
+        self.sparse_jacobian = enabled;
+        self
+    }
+
+    /// Sets the largest flux discrepancy that a node may be left with in a solution before it is 
+    /// reported. This does not change how the study is solved: the solver still stops once its 
+    /// margin is met, but any unlocked node whose flux discrepancy is larger than `tolerance` is 
+    /// listed in the result's warnings. This catches solutions that meet the solver's margin but 
+    /// still visibly violate conservation, e.g. at nodes that only carry very large fluxes.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+    ///     .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+    /// 
+    /// let soln = builder.clone().with_conservation_tolerance(1E-6).run_study(1E-9, 100).unwrap();
+    /// assert!(soln.get_warnings().is_empty());
+    /// 
+    /// // A loose margin lets the solver stop with microamps still unaccounted for at node 2
+    /// let soln = builder.with_conservation_tolerance(1E-6).run_study(1E-3, 100).unwrap();
+    /// assert!(soln.get_warnings()[0].contains("node '2'"));
+    /// ```
+    pub fn with_conservation_tolerance(mut self, tolerance: f64) -> NodalAnalysisStudyBuilder
+    {
+        self.conservation_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Sets the potential that the solver starts from at an unlocked node, in place of the 
+    /// default of 1.0 for every component. Components that are not given start from zero.
+    /// 
+    /// Good guesses help nonlinear elements (e.g. diodes or radiation) converge, and 
+    /// are equivalent to configuring the node with `is_locked` set to `false`.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .set_initial_guess(2, vec![0.6]).unwrap()
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+    ///     .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let v_diode = serde_json::to_value(soln).unwrap()["nodes"]["2"][0].as_f64().unwrap();
+    /// assert!(v_diode > 0.5 && v_diode < 0.7);
+    /// ```
+    pub fn set_initial_guess(mut self, node: usize, potential: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if node >= self.model.nodes
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        }
+
+        match self.model.configuration.get_mut(&node)
+        {
+            Some(node_data) if node_data.is_locked => return Err(NodalAnalysisModellingError::NodeIsLocked(node).into()),
+            Some(node_data) => node_data.potential = potential,
+            None => 
+            {
+                self.model.configuration.insert(node, NodalMetadata { potential, is_locked: false, metadata: None, coordinates: None });
+            },
+        }
+        Ok(self)
+    }
+
+    /// Guesses the potential of every unlocked node from a previous result (e.g. of the same 
+    /// model before a gain was tweaked), so that the solver starts from that solution. 
+    /// Nodes are matched by name, and those missing from `result` keep their current guess.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+    ///     .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+    /// 
+    /// let first = builder.clone().run_study(1E-9, 100).unwrap();
+    /// 
+    /// // Nudging the supply only moves the diode a little, so the re-solve needs few iterations
+    /// let soln = builder
+    ///     .update_gain(0, vec![5.1]).unwrap()
+    ///     .with_initial_state(&first)
+    ///     .run_study(1E-9, 5)
+    ///     .unwrap();
+    /// 
+    /// let v_diode = serde_json::to_value(soln).unwrap()["nodes"]["2"][0].as_f64().unwrap();
+    /// assert!(v_diode > 0.5 && v_diode < 0.7);
+    /// ```
+    pub fn with_initial_state(mut self, result: &NodalAnalysisStudyResult) -> NodalAnalysisStudyBuilder
+    {
+        for node in 0..self.model.nodes
+        {
+            let potential = match result.nodes.get(&self.model.get_node_name(node))
+            {
+                Some(potential) => potential.clone(),
+                None => continue,
+            };
+
+            match self.model.configuration.get_mut(&node)
+            {
+                Some(node_data) if node_data.is_locked => (),
+                Some(node_data) => node_data.potential = potential,
+                None => 
+                {
+                    self.model.configuration.insert(node, NodalMetadata { potential, is_locked: false, metadata: None, coordinates: None });
+                },
+            }
+        }
+        self
+    }
+
+    pub fn configure_node(mut self, node: usize, potential: Vec<f64>, is_locked: bool, metadata: Option<HashMap<String, f64>>) -> NodalAnalysisStudyBuilder
+    {
+        // Nodes keep their place in space when they are reconfigured
+        let coordinates = self.model.get_node_coordinates(node);
+        self.model.configuration.insert(node, NodalMetadata { potential, is_locked, metadata, coordinates });
+        self
+    }
+
+    /// Places the node at index `node` at `[x, y, z]` in space, so that exporters 
+    /// (e.g. `NodalAnalysisStudyResult::to_vtk`) can draw the network and elements such as
+    /// `GEOMETRIC_CONDUCTOR` can find their length from the distance between their nodes.
+    /// Placing a node does not change its potential or whether it is locked.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::heat_transfer::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+    ///     .place_node(1, [3.0, 4.0, 0.0]).unwrap()
+    ///     .configure_node(0, vec![100.0], true, None)
+    ///     .configure_node(1, vec![0.0], true, None);
+    /// 
+    /// assert_eq!(builder.get_model().get_node_coordinates(1), Some([3.0, 4.0, 0.0]));
+    /// assert!(builder.place_node(2, [1.0, 1.0, 1.0]).is_err());
+    /// ```
+    pub fn place_node(mut self, node: usize, coordinates: [f64; 3]) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if node >= self.model.nodes
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        }
+
+        match self.model.configuration.get_mut(&node)
+        {
+            Some(node_data) => node_data.coordinates = Some(coordinates),
+            None => 
+            {
+                self.model.configuration.insert(node, NodalMetadata { potential: vec![], is_locked: false, metadata: None, coordinates: Some(coordinates) });
+            },
+        }
+        Ok(self)
+    }
+
+    pub fn add_element(mut self, element: &str, input: usize, output: usize, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if input >= self.model.nodes || output >= self.model.nodes
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        };
+        self.model.elements.push(
+            NodalAnalysisElement { element_type: self.resolve_connected_element_type(element, &[input, output])?, input, output, gain, control: None, terminals: vec![] }
+        );
+        Ok(self)
+    }    
+
+    /// Adds an element to the model exactly as `add_element` does, labelling it so that it is
+    /// identified by `label` in study results in place of `"<element_type>.<element_index>"`.
+    /// Its index can be looked up with `NodalAnalysisModel::get_element_index`.
+    /// 
+    /// Labels must be unique and must not end in `.<number>`, so that they cannot be mistaken
+    /// for the name of an unlabelled element. See `add_named_node` for an example.
+    pub fn add_labeled_element(self, label: &str, element: &str, input: usize, output: usize, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        let looks_like_name = match label.rsplit_once('.')
+        {
+            Some((_, idx)) => idx.parse::<usize>().is_ok(),
+            None => false,
+        };
+
+        if looks_like_name || self.model.get_element_index(label).is_some()
+        {
+            return Err(NodalAnalysisModellingError::ElementLabelCollision(label.to_owned()).into());
+        }
+
+        let mut builder = self.add_element(element, input, output, gain)?;
+        let idx = builder.model.elements.len() - 1;
+        builder.model.element_labels.insert(idx, label.to_owned());
+        Ok(builder)
+    }
+
+    /// Adds an element with any number of terminals to the model, e.g. a transistor, connecting
+    /// its terminals to `terminals` in order. The element's `input` and `output` are recorded as
+    /// its first and last terminals.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .add_terminal_element(NPN_TRANSISTOR, vec![0, 1, 2], vec![1E-14, 0.025, 100.0]).unwrap();
+    /// 
+    /// assert!(builder.add_terminal_element(NPN_TRANSISTOR, vec![0, 1, 3], vec![1E-14, 0.025, 100.0]).is_err());
+    /// ```
+    pub fn add_terminal_element(mut self, element: &str, terminals: Vec<usize>, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        let (input, output) = match (terminals.first(), terminals.last())
+        {
+            (Some(&input), Some(&output)) => (input, output),
+            _ => return Err(NodalAnalysisModellingError::NodeDoesNotExist.into()),
+        };
+
+        if terminals.iter().any(|&node| node >= self.model.nodes)
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        }
+        self.model.elements.push(
+            NodalAnalysisElement { element_type: self.resolve_connected_element_type(element, &terminals)?, input, output, gain, control: None, terminals }
+        );
+        Ok(self)
+    }
+
+    /// Adds a controlled element to the model, whose flux also depends on the quantity
+    /// identified by `control`, e.g. a voltage-controlled voltage source. Elements sensed 
+    /// by flux may be added to the model after the controlled element.
+    /// 
+    /// A controlled element must not sense its own flux, or the flux of an element whose 
+    /// flux in turn depends on it (e.g. another controlled element sensing it).
+    /// 
+    /// See `dc_circuits::voltage_controlled_voltage_source` for an example.
+    pub fn add_controlled_element(self, element: &str, input: usize, output: usize, gain: Vec<f64>, control: ElementControl) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if let ElementControl::Potential { input, output } = control
+        {
+            if input >= self.model.nodes || output >= self.model.nodes
+            {
+                return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+            }
+        }
+
+        let mut builder = self.add_element(element, input, output, gain)?;
+        if let Some(added) = builder.model.elements.last_mut()
+        {
+            added.control = Some(control);
+        }
+        Ok(builder)
+    }
+
+    /// Changes the gain of the element at index `element` (in the order that it was added to the model).
+    pub fn update_gain(mut self, element: usize, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        match self.model.elements.get_mut(element)
+        {
+            Some(added) => added.gain = gain,
+            None => return Err(NodalAnalysisModellingError::ElementDoesNotExist.into()),
+        }
+        Ok(self)
+    }
+
+    /// Replaces the element at index `element` with a new element, exactly as if the new element
+    /// had been added with `add_element` in its place. The element keeps its index and label,
+    /// so controlled elements that sense its flux now sense the flux of the new element.
+    pub fn replace_element(mut self, element: usize, element_type: &str, input: usize, output: usize, gain: Vec<f64>) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if element >= self.model.elements.len()
+        {
+            return Err(NodalAnalysisModellingError::ElementDoesNotExist.into());
+        }
+        if input >= self.model.nodes || output >= self.model.nodes
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        }
+
+        self.model.elements[element] = NodalAnalysisElement 
+        { 
+            element_type: self.resolve_connected_element_type(element_type, &[input, output])?, input, output, gain, control: None, terminals: vec![] 
+        };
+        Ok(self)
+    }
+
+    /// Removes the element at index `element` from the model. Every element added after it
+    /// moves down by one index, and keeps its label if it has one.
+    /// 
+    /// Elements that are sensed by a controlled element cannot be removed.
+    pub fn remove_element(mut self, element: usize) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if element >= self.model.elements.len()
+        {
+            return Err(NodalAnalysisModellingError::ElementDoesNotExist.into());
+        }
+        if self.model.elements.iter().any(|x| x.control == Some(ElementControl::Flux { element }))
+        {
+            return Err(NodalAnalysisModellingError::ElementIsSensed(element).into());
+        }
+
+        self.model.elements.remove(element);
+        for added in &mut self.model.elements
+        {
+            if let Some(ElementControl::Flux { element: sensed }) = &mut added.control
+            {
+                if *sensed > element
+                {
+                    *sensed -= 1;
+                }
+            }
+        }
+
+        self.model.element_labels = self.model.element_labels
+            .drain()
+            .filter(|(idx, _)| *idx != element)
+            .map(|(idx, label)| (if idx > element { idx - 1 } else { idx }, label))
+            .collect();
+
+        Ok(self)
+    }
+
+    /// Removes the node at index `node` from the model. Every node after it moves down by one 
+    /// index, and keeps its configuration and name if it has them.
+    /// 
+    /// Nodes that are connected to an element or sensed by a controlled element cannot be removed.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// // Remove the short circuit around the bottom resistor, leaving node 2 unused
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(4)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 3, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       3, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1E-9]).unwrap()
+    ///     .replace_element(2, RESISTOR, 3, 0, vec![1.0]).unwrap()
+    ///     .remove_element(3).unwrap()
+    ///     .remove_node(2).unwrap();
+    /// 
+    /// assert_eq!(builder.get_model().get_element_name(1), "resistor.1");
+    /// 
+    /// let soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    /// assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 5.0).abs() < 1E-6);
+    /// ```
+    pub fn remove_node(mut self, node: usize) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if node >= self.model.nodes
+        {
+            return Err(NodalAnalysisModellingError::NodeDoesNotExist.into());
+        }
+
+        let is_connected = |x: &NodalAnalysisElement| {
+            x.input == node || x.output == node || x.terminals.contains(&node) 
+                || matches!(x.control, Some(ElementControl::Potential { input, output }) if input == node || output == node)
+        };
+        if self.model.elements.iter().any(is_connected)
+        {
+            return Err(NodalAnalysisModellingError::NodeIsConnected(node).into());
+        }
+
+        let reindex = |idx: &mut usize| if *idx > node { *idx -= 1 };
+        for added in &mut self.model.elements
+        {
+            reindex(&mut added.input);
+            reindex(&mut added.output);
+            added.terminals.iter_mut().for_each(reindex);
+            if let Some(ElementControl::Potential { input, output }) = &mut added.control
+            {
+                reindex(input);
+                reindex(output);
+            }
+        }
+
+        self.model.configuration = self.model.configuration
+            .drain()
+            .filter(|(idx, _)| *idx != node)
+            .map(|(mut idx, node_data)| { reindex(&mut idx); (idx, node_data) })
+            .collect();
+        self.model.node_names = self.model.node_names
+            .drain()
+            .filter(|(idx, _)| *idx != node)
+            .map(|(mut idx, name)| { reindex(&mut idx); (idx, name) })
+            .collect();
+        self.model.node_domains = self.model.node_domains
+            .drain()
+            .filter(|(idx, _)| *idx != node)
+            .map(|(mut idx, domain)| { reindex(&mut idx); (idx, domain) })
+            .collect();
+        self.model.nodes -= 1;
+
+        Ok(self)
+    }
+
+    pub fn save_model(self, model_rep: &mut String) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        let res = to_string_pretty(&self.model);
+        if let Err(e) = res
+        {
+            return Err(e.into());
+        }
+        *model_rep = res.unwrap();
+        Ok(self)
+    }
+
+    /// Solves the study with the given margin and iteration limit, and the default 
+    /// for every other solver setting (see `options::SolverOptions`).
+    pub fn run_study(self, margin: f64, limit: usize) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.run_study_with_options(&SolverOptions::new(margin, limit))
+    }
+
+    /// Runs the study exactly as `run_study` does, but calls `progress` at every iteration
+    /// of the solver with the (zero-based) iteration number and the norm of the nodal flux 
+    /// discrepancies, so that long solves can report their progress. Returning 
+    /// `ControlFlow::Break` from `progress` cancels the solve with an error.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+    ///     .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+    /// 
+    /// let mut residuals = vec![];
+    /// builder.clone().run_study_with_progress(1E-9, 100, |_, residual| {
+    ///     residuals.push(residual);
+    ///     ControlFlow::Continue(())
+    /// }).unwrap();
+    /// assert!(residuals.len() > 1);
+    /// 
+    /// // Cancel the solve if it takes more than a few iterations
+    /// let cancelled = builder.run_study_with_progress(1E-9, 100, |i, _| match i < 3
+    /// {
+    ///     true => ControlFlow::Continue(()),
+    ///     false => ControlFlow::Break(()),
+    /// });
+    /// assert!(cancelled.is_err());
+    /// ```
+    pub fn run_study_with_progress(self, margin: f64, limit: usize, progress: impl FnMut(usize, f64) -> ControlFlow<()>) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.compile()?.solve_with_progress(margin, limit, progress)
+    }
+
+    /// Builds the nodes and elements of the model once, returning a `CompiledStudy`
+    /// that can be solved repeatedly. This is useful for sweeps and batches that 
+    /// solve the same topology many times with different element gains, as only the 
+    /// changed gains need to be rebuilt between solves.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let mut study = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .compile()
+    ///     .unwrap();
+    /// 
+    /// for r in [1.0, 3.0, 9.0]
+    /// {
+    ///     study.set_gain(2, vec![r]).unwrap();
+    ///     
+    ///     let soln = serde_json::to_value(study.solve(1E-9, 100).unwrap()).unwrap();
+    ///     let v2 = soln["nodes"]["2"][0].as_f64().unwrap();
+    ///     assert!((v2 - 10.0 * r / (1.0 + r)).abs() < 1E-6);
+    /// }
+    /// ```
+    pub fn compile(mut self) -> anyhow::Result<CompiledStudy>
+    {
+        let _span = info_span!("compile", model_type = %self.model.model_type, nodes = self.model.nodes, elements = self.model.elements.len()).entered();
+        let n = self.get_node_dimension(0);
+
+        // Step 0 - make sure that every domain of the model has a reference for its potentials
+        let mut warnings = vec![];
+        let locked_domains: HashSet<&str> = self.model.configuration.iter()
+            .filter(|(_, node)| node.is_locked)
+            .map(|(&i, _)| self.model.get_node_domain(i))
+            .collect();
+        let mut floating: Vec<(String, usize)> = vec![]; // each domain without a locked node, and its first node
+        for i in 0..self.model.nodes
+        {
+            let domain = self.model.get_node_domain(i);
+            if !locked_domains.contains(domain) && floating.iter().all(|(d, _)| d != domain)
+            {
+                floating.push((domain.to_string(), i));
+            }
         }
 
-        // Step 4 - solve model
-        let mut partials = vec![];
-        let mut guess = HashMap::new();
-        for (node_idx, _) in nodes.iter().enumerate().filter(|(_, x)| !x.borrow().is_locked)
+        for (domain, i) in floating
         {
-            for comp_idx in 0..self.get_dimension()
+            let is_own_domain = domain == self.model.model_type;
+            match (self.auto_ground, is_own_domain)
+            {
+                (false, true) => return Err(NodalAnalysisModellingError::NoReferenceNode.into()),
+                (false, false) => return Err(NodalAnalysisModellingError::FloatingDomain(domain).into()),
+                (true, _) => (),
+            }
+
+            let dimension = self.get_node_dimension(i);
+            let node = self.model.configuration.entry(i).or_insert(NodalMetadata 
+            { 
+                potential: vec![], 
+                is_locked: true, 
+                metadata: None,
+                coordinates: None,
+            });
+            node.potential = vec![0.0; dimension];
+            node.is_locked = true;
+
+            match is_own_domain
+            {
+                true => warnings.push(format!("no node was locked, so node '{}' was locked at zero potential", self.model.get_node_name(i))),
+                false => warnings.push(format!("no node of the '{domain}' domain was locked, so node '{}' was locked at zero potential", self.model.get_node_name(i))),
+            }
+        }
+        // Step 1 - create/initialize nodes for model
+        let mut arena = NodalArena::new(n, self.model.nodes);
+        for &i in self.model.node_domains.keys()
+        {
+            arena.configure_node(NodeId(i), Matrix::from_col_vec(vec![1.0; self.get_node_dimension(i)]), false, None);
+        }
+
+        // Step 2 - set nodal metadata if it is given
+        for (&i, node_data) in &self.model.configuration
+        {
+            let mut potential = node_data.potential.to_vec();
+            if potential.len() < self.get_node_dimension(i)
+            {
+                // Unspecified components (e.g. higher harmonics) default to zero
+                potential.resize(self.get_node_dimension(i), 0.0);
+            }
+            arena.configure_node(NodeId(i), Matrix::from_col_vec(potential), node_data.is_locked, node_data.metadata.clone());
+            arena.place_node(NodeId(i), node_data.coordinates);
+        }
+
+        // Step 2.5 - add each element and the quantity it senses to the arena
+        for element_data in &self.model.elements
+        {
+            let NodalAnalysisElement { element_type, input, output, gain, terminals, .. } = element_data;
+            let constructor = self.get_element_constructor(element_type, *input)?;
+            let nodes = match constructor
+            {
+                Constructor::Two(_) | Constructor::Flux(_) => vec![NodeId(*input), NodeId(*output)],
+                Constructor::Many(_) => terminals.iter().map(|&node| NodeId(node)).collect(),
+            };
+            arena.add_element(constructor, nodes, gain.to_vec());
+        }
+
+        for (idx, element_data) in self.model.elements.iter().enumerate()
+        {
+            let sensor = match element_data.control
+            {
+                None => continue,
+                Some(ElementControl::Potential { input, output }) => ArenaSensor::Potential(NodeId(input), NodeId(output)),
+                Some(ElementControl::Flux { element }) if element < self.model.elements.len() && element != idx => 
+                    ArenaSensor::Flux(ElementId(element)),
+                Some(ElementControl::Flux { .. }) => return Err(NodalAnalysisModellingError::ControlElementDoesNotExist.into()),
+            };
+            arena.set_sensor(ElementId(idx), sensor);
+        }
+
+        // Step 3 - build model, keeping track of what locked each node so 
+        // that sources which would lock an already-locked node can be reported
+        let mut lockers: HashMap<usize, String> = self.model.configuration.iter()
+            .filter(|(_, node_data)| node_data.is_locked)
+            .map(|(&node, _)| (node, "configure_node".to_owned()))
+            .collect();
+
+        let graph = arena.view_with(|ElementId(idx), nodes| {
+            trace!(element = %self.model.get_element_name(idx), "built element");
+            for &NodeId(node) in arena.get_element_nodes(ElementId(idx))
             {
-                let idx = ComponentIndex 
+                if nodes[node].borrow().is_locked && !lockers.contains_key(&node)
+                {
+                    lockers.insert(node, format!("element '{}'", self.model.get_element_name(idx)));
+                }
+            }
+        });
+
+        let graph = match graph
+        {
+            std::result::Result::Ok(graph) => graph,
+            Err((ElementId(idx), e)) if e.is::<ElementCreationError>() => 
+            {
+                let NodalAnalysisElement { input, output, .. } = &self.model.elements[idx];
+                let locker = |node: &usize| lockers.get(node).cloned().unwrap_or_default();
+                return Err(NodalAnalysisModellingError::LockedNodeConflict 
+                { 
+                    element: self.model.get_element_name(idx), 
+                    input: self.model.get_node_name(*input),
+                    input_locker: locker(input),
+                    output: self.model.get_node_name(*output),
+                    output_locker: locker(output),
+                }.into());
+            },
+            Err((_, e)) => return Err(e),
+        };
+
+        // Step 4 - find the unknowns of the model and guess them from the nodes' initial potentials
+        // Each unknown is identified by its position in `unknowns`
+        let mut unknowns = vec![];
+        let mut guess = vec![];
+        let mut defaulted = vec![];
+        for (node_idx, node) in graph.nodes.iter().enumerate().filter(|(_, x)| !x.borrow().is_locked)
+        {
+            for comp_idx in 0..node.borrow().potential.get_rows()
+            {
+                unknowns.push(ComponentIndex 
                 { 
                     node: node_idx as u32, 
                     component: comp_idx as u32 
-                };
+                });
+                guess.push(node.borrow().potential[(comp_idx, 0)]);
+                // Nodes that were only placed were not given a potential either
+                let has_guess = self.model.configuration.get(&node_idx).is_some_and(|node_data| !node_data.potential.is_empty());
+                defaulted.push(!has_guess);
+            }
+        }
 
-                let local_nodes = nodes.to_vec();
+        // Step 4.5 - find which unknowns can change which flux balances from the model's topology
+        let jacobian_pattern = match self.sparse_jacobian
+        {
+            false => None,
+            true => 
+            {
+                let is_unknown: Vec<bool> = graph.nodes.iter().map(|node| !node.borrow().is_locked).collect();
+                let dependencies = arena.get_flux_balance_dependencies(&is_unknown);
 
-                guess.insert(idx, 1.0);
-                partials.push(move |x: &HashMap<ComponentIndex, f64>| {
-                    for (&ComponentIndex { node, component }, &val) in x
+                // Each unknown's rows are the components of the flux balances that depend on its node
+                let mut pattern = vec![vec![]; unknowns.len()];
+                for (row, idx) in unknowns.iter().enumerate()
+                {
+                    for &NodeId(node) in &dependencies[idx.node as usize]
                     {
-                        local_nodes[node as usize]
-                            .try_borrow_mut()?
-                            .potential[(component as usize, 0)] = val;
+                        let first = unknowns.partition_point(|col| (col.node as usize) < node);
+                        let last = unknowns.partition_point(|col| (col.node as usize) <= node);
+                        for rows in &mut pattern[first..last]
+                        {
+                            rows.push(row);
+                        }
                     }
+                }
+                Some(pattern)
+            },
+        };
 
-                    let flux_discrepancy = local_nodes[node_idx]
-                        .try_borrow()?
-                        .get_flux_discrepancy()?;
+        debug!(unknowns = unknowns.len(), sparse = jacobian_pattern.is_some(), "compiled study");
+        Ok(CompiledStudy
+        {
+            dimension: n,
+            model: self.model,
+            arena,
+            unknowns,
+            jacobian_pattern,
+            guess,
+            defaulted,
+            warnings,
+            conservation_tolerance: self.conservation_tolerance,
+        })
+    }
+}
 
-                    Ok(flux_discrepancy[(comp_idx, 0)])
-                });
+/// A model whose nodes and elements have already been built, ready to be solved
+/// any number of times. Created with `NodalAnalysisStudyBuilder::compile`.
+/// 
+/// The nodes and elements are stored in a `NodalArena`, so a compiled study owns no shared 
+/// references and can be sent to another thread to be solved. Each solve builds the network 
+/// that runs the elements' flux calculations from the arena on the thread that solves it.
+#[derive(Clone, Debug)]
+pub struct CompiledStudy
+{
+    dimension: usize,
+    model: NodalAnalysisModel,
+    arena: NodalArena,
+    unknowns: Vec<ComponentIndex>,
+    jacobian_pattern: Option<Vec<Vec<usize>>>,
+    guess: Vec<f64>,
+    defaulted: Vec<bool>,
+    warnings: Vec<String>,
+    conservation_tolerance: Option<f64>,
+}
+impl CompiledStudy
+{
+    /// Returns the model that this study solves, including any gains that have been changed.
+    pub fn get_model(&self) -> &NodalAnalysisModel
+    {
+        &self.model
+    }
+
+    /// Returns the arena that holds the study's nodes and elements, which 
+    /// holds the potentials of the last solution once the study is solved.
+    pub fn get_arena(&self) -> &NodalArena
+    {
+        &self.arena
+    }
+
+    /// Changes the gain of the element at index `element` (in the order that it was added 
+    /// to the model), exactly as if the model had been built with that gain. The element's 
+    /// constructor is re-run on a set of scratch nodes to check that it accepts the gain, 
+    /// which is then stored in the study's arena, so no other part of the study is rebuilt.
+    /// 
+    /// Elements that drive a nodal potential keep driving the same node.
+    pub fn set_gain(&mut self, element: usize, gain: Vec<f64>) -> anyhow::Result<()>
+    {
+        if element >= self.arena.element_count()
+        {
+            return Err(CompiledStudyError::ElementDoesNotExist.into());
+        }
+
+        // Make sure that the constructor accepts the gain before the arena is changed
+        let id = ElementId(element);
+        let scratch: Vec<_> = self.arena.get_element_nodes(id).iter().map(|_| GenericNode::new()).collect();
+        for (node, &id) in scratch.iter().zip(self.arena.get_element_nodes(id))
+        {
+            let mut node = node.try_borrow_mut()?;
+            node.potential = Matrix::new(self.arena.get_node_dimension(id), 1);
+            node.coordinates = self.arena.get_node_coordinates(id);
+        }
+        self.arena.build_element(id, &scratch, gain.clone())?;
+
+        self.arena.set_gain(id, gain.clone());
+        self.model.elements[element].gain = gain;
+
+        Ok(())
+    }
+
+    /// Returns the number of components in the potential of the node at index `node`, or in 
+    /// the potentials of the model's own domain if there is no such node.
+    pub (in crate) fn get_node_dimension(&self, node: usize) -> usize
+    {
+        match node < self.arena.node_count()
+        {
+            true => self.arena.get_node_dimension(NodeId(node)),
+            false => self.dimension,
+        }
+    }
+
+    /// Changes the potential of a node that was locked with `configure_node`, exactly as if 
+    /// the model had been built with that potential. Components that are not given are zero.
+    pub fn set_locked_potential(&mut self, node: usize, potential: Vec<f64>) -> anyhow::Result<()>
+    {
+        match self.model.configuration.get_mut(&node)
+        {
+            Some(node_data) if node_data.is_locked => node_data.potential = potential.clone(),
+            _ => return Err(CompiledStudyError::NodeIsNotLocked(node).into()),
+        }
+
+        // Elements that drive a nodal potential from this one update it when the study is solved
+        let mut potential = potential;
+        potential.resize(self.get_node_dimension(node), 0.0);
+        self.arena.set_potential(NodeId(node), Matrix::from_col_vec(potential));
+
+        Ok(())
+    }
+
+    /// Starts the next solve from the potentials in a previous result (see 
+    /// `NodalAnalysisStudyBuilder::with_initial_state`). Unknowns at nodes 
+    /// that are missing from `result` keep their current guess.
+    pub fn set_initial_state(&mut self, result: &NodalAnalysisStudyResult)
+    {
+        for ((guess, defaulted), idx) in self.guess.iter_mut().zip(self.defaulted.iter_mut()).zip(self.unknowns.iter())
+        {
+            let potential = result.nodes.get(&self.model.get_node_name(idx.node as usize));
+            if let Some(&component) = potential.and_then(|p| p.get(idx.component as usize))
+            {
+                *guess = component;
+                *defaulted = false;
             }
         }
+    }
 
-        let soln = multivariate_newton_raphson(partials, &mut guess, margin, limit)?;
+    /// Changes the largest flux discrepancy that a node may be left with before it is listed in 
+    /// a result's warnings (see `NodalAnalysisStudyBuilder::with_conservation_tolerance`).
+    pub fn set_conservation_tolerance(&mut self, tolerance: Option<f64>)
+    {
+        self.conservation_tolerance = tolerance;
+    }
 
-        // Step 5 - Set model state to solution
-        for (idx, component) in soln
+    /// Captures the potentials and flux discrepancies of the study at the solver's last guess.
+    fn describe_failure(&self, graph: &NodalGraph, reason: anyhow::Error, guess: &[f64]) -> anyhow::Result<SolveFailedError>
+    {
+        load_guess(graph, &self.unknowns, guess)?;
+
+        let mut potentials = HashMap::new();
+        let mut discrepancies = HashMap::new();
+        let mut worst: Option<(String, f64)> = None;
+        for (idx, node) in graph.nodes.iter().enumerate()
         {
-            let mut node = nodes[idx.node as usize].try_borrow_mut()?;
-            node.potential[(idx.component as usize, 0)] = *component;
+            let node = node.try_borrow()?;
+            let name = self.model.get_node_name(idx);
+            potentials.insert(name.clone(), node.potential.clone().into());
+
+            if node.is_locked
+            {
+                continue;
+            }
+
+            let discrepancy: Vec<f64> = node.get_flux_discrepancy()?.into();
+            let magnitude = discrepancy.iter().map(|x| x.powi(2)).sum::<f64>().sqrt();
+
+            // A discrepancy that is no longer a number is the worst there is
+            if worst.as_ref().is_none_or(|(_, w)| !w.is_nan() && (magnitude.is_nan() || magnitude > *w))
+            {
+                worst = Some((name.clone(), magnitude));
+            }
+            discrepancies.insert(name, discrepancy);
         }
 
+        Ok(SolveFailedError 
+        { 
+            reason: reason.to_string(), 
+            guess: guess.to_vec(), 
+            potentials, 
+            discrepancies, 
+            worst_node: worst.map(|(name, _)| name),
+        })
+    }
+
+    /// Solves the study with its current gains.
+    pub fn solve(&mut self, margin: f64, limit: usize) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.solve_with_progress(margin, limit, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Solves the study with its current gains, reporting the solver's progress to 
+    /// `progress` (see `NodalAnalysisStudyBuilder::run_study_with_progress`).
+    pub fn solve_with_progress(&mut self, margin: f64, limit: usize, progress: impl FnMut(usize, f64) -> ControlFlow<()>) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.solve_with(&SolverOptions::new(margin, limit), progress)
+    }
+
+    /// Solves the study with its current gains and the solver settings in `options`, 
+    /// reporting the solver's progress to `progress`.
+    pub (in crate) fn solve_with(&mut self, options: &SolverOptions, mut progress: impl FnMut(usize, f64) -> ControlFlow<()>) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        let _span = info_span!("solve", unknowns = self.unknowns.len()).entered();
+
+        // The network is built from the arena on the thread that solves it. With the `parallel` 
+        // feature, each of the solver's workers builds its own network to evaluate the model on
+        let graph = self.arena.view()?;
+        let unknowns = &self.unknowns;
+        let flux_balances = |graph: &mut NodalGraph, x: &[f64], first: usize, rows: &mut [f64]| {
+            load_guess(graph, unknowns, x)?;
+            for (row, &ComponentIndex { node, component }) in rows.iter_mut().zip(&unknowns[first..])
+            {
+                let flux_discrepancy = graph.nodes[node as usize]
+                    .try_borrow()?
+                    .get_flux_discrepancy()?;
+
+                *row = flux_discrepancy[(component as usize, 0)];
+            }
+            Ok(())
+        };
+
+        let start = Instant::now();
+        let mut residual_history = vec![];
+        let mut flux_history = VecDeque::new();
+        // Unknowns at nodes that were never given a potential start from the default guess
+        let mut guess = self.guess.clone();
+        for (x, &defaulted) in guess.iter_mut().zip(&self.defaulted)
+        {
+            if defaulted
+            {
+                *x = options.initial_guess;
+            }
+        }
+
+        let monitor = |iteration, residual, x: &[f64]| {
+            residual_history.push(residual);
+            // Verbose solves log each iteration where a default subscriber shows it
+            match options.verbose
+            {
+                true => info!(iteration, residual, "solver iteration"),
+                false => debug!(iteration, residual, "solver iteration"),
+            }
+
+            let fluxes: anyhow::Result<Vec<Vec<f64>>> = load_guess(&graph, unknowns, x)
+                .and_then(|_| graph.elements.iter().map(|elem| Ok(elem.get_flux()?.into())).collect());
+            if let std::result::Result::Ok(fluxes) = fluxes
+            {
+                flux_history.push_back(fluxes);
+                if flux_history.len() > FINAL_ITERATIONS + 1
+                {
+                    flux_history.pop_front();
+                }
+            }
+
+            progress(iteration, residual)
+        };
+
+        let res = match &self.jacobian_pattern
+        {
+            Some(pattern) => multivariate_newton_raphson_sparse_with_options(
+                || self.arena.view(), flux_balances, pattern, &mut guess, &options.to_newton_options(), monitor
+            ),
+            None => multivariate_newton_raphson_system_with_options(
+                || self.arena.view(), flux_balances, &mut guess, &options.to_newton_options(), monitor
+            ),
+        };
+        let soln = match res
+        {
+            std::result::Result::Ok(soln) => soln.to_vec(),
+            Err(e) => return Err(self.describe_failure(&graph, e, &guess)?.into()),
+        };
+
+        debug!(iterations = residual_history.len(), "solver converged");
+
+        // Step 5 - Set model state to solution
+        load_guess(&graph, &self.unknowns, &soln)?;
+        self.arena.update_from(&graph)?;
+
         // Step 6 - gather results
         let mut result = NodalAnalysisStudyResult 
         { 
             nodes: HashMap::new(), 
-            elements: HashMap::new() 
+            elements: HashMap::new(),
+            derived: HashMap::new(),
+            statistics: SolverStatistics
+            {
+                iterations: residual_history.len(),
+                residual_norm: residual_history.last().copied().unwrap_or_default(),
+                residual_history,
+                wall_time: 0.0,
+                element_convergence: HashMap::new(),
+            },
+            warnings: self.warnings.clone(),
+            node_names: (0..graph.nodes.len()).map(|idx| self.model.get_node_name(idx)).collect(),
+            element_names: (0..graph.elements.len()).map(|idx| self.model.get_element_name(idx)).collect(),
         };
+
+        // How far each element's flux moved over the final iterations
+        let norm = |x: &[f64]| x.iter().map(|v| v.powi(2)).sum::<f64>().sqrt();
+        let mut changes = vec![0.0; graph.elements.len()];
+        for (prev, next) in flux_history.iter().zip(flux_history.iter().skip(1))
+        {
+            for (change, (a, b)) in changes.iter_mut().zip(prev.iter().zip(next))
+            {
+                let delta: Vec<f64> = a.iter().zip(b).map(|(x, y)| y - x).collect();
+                *change += norm(&delta);
+            }
+        }
+        let total_change: f64 = changes.iter().sum();
         
-        for (idx, elem) in elements.iter().enumerate()
+        for (idx, elem) in graph.elements.iter().enumerate()
         {
-            result.elements.insert(
-                format!("{}.{idx}", self.model.elements[idx].element_type),
-                elem.get_flux()?.into()
+            let flux: Vec<f64> = elem.get_flux()?.into();
+            let residual_contribution = match total_change > 0.0
+            {
+                true => changes[idx] / total_change,
+                false => 0.0,
+            };
+
+            result.statistics.element_convergence.insert(
+                self.model.get_element_name(idx),
+                ElementConvergence { flux_magnitude: norm(&flux), residual_contribution }
             );
+            result.elements.insert(self.model.get_element_name(idx), flux);
+
+            let derived = elem.get_derived_quantities()?;
+            if !derived.is_empty()
+            {
+                result.derived.insert(self.model.get_element_name(idx), derived);
+            }
         }
 
         // Get all nodal potential values for solution
-        for (idx, node) in nodes.iter().enumerate()
+        for (idx, node) in graph.nodes.iter().enumerate()
         {
             result.nodes.insert(
-                idx as u32, 
+                self.model.get_node_name(idx), 
                 node.try_borrow()?.potential.clone().into(),
             );
         }
 
+        // Report nodes that the solver's margin let through with a visible flux discrepancy
+        if let Some(tolerance) = self.conservation_tolerance
+        {
+            for (idx, node) in graph.nodes.iter().enumerate()
+            {
+                let node = node.try_borrow()?;
+                if node.is_locked
+                {
+                    continue;
+                }
+
+                let discrepancy: Vec<f64> = node.get_flux_discrepancy()?.into();
+                let magnitude = norm(&discrepancy);
+                if magnitude > tolerance
+                {
+                    result.warnings.push(format!(
+                        "node '{}' has a flux discrepancy of {:e}, which is more than the conservation tolerance of {:e}", 
+                        self.model.get_node_name(idx), magnitude, tolerance
+                    ));
+                }
+            }
+        }
+
+        result.statistics.wall_time = start.elapsed().as_secs_f64();
         Ok(result)
     }
 }
 
+/// Sorts named values (e.g. a result's nodal potentials) into csv columns, each named 
+/// `<kind>:<name>`, with components after the first suffixed with their index. Names that 
+/// are indices are sorted numerically and come before any other names, which are sorted 
+/// alphabetically. Each column is returned with the values that it holds in each row.
+pub (in crate) fn csv_columns<'a, T>(kind: &str, values: &'a HashMap<String, T>) -> Vec<(String, T)>
+where T: Clone + 'a
+{
+    sort_names(values).into_iter()
+        .map(|name| (format!("{kind}:{name}"), values[name].clone()))
+        .collect()
+}
+
+/// Sorts the names of named values, with names that are indices sorted numerically 
+/// before any other names, which are sorted alphabetically.
+pub (in crate) fn sort_names<T>(values: &HashMap<String, T>) -> Vec<&String>
+{
+    let mut names: Vec<&String> = values.keys().collect();
+    names.sort_by_key(|name| (name.parse::<usize>().map_err(|_| name.to_string()), name.to_string()));
+    names
+}
+
+/// Writes a header and rows of numbers as csv. Columns whose values have more than one 
+/// component are split into one column per component, suffixed with the component's index.
+pub (in crate) fn write_csv(header: &[String], rows: &[Vec<Vec<f64>>]) -> String
+{
+    let escape = |field: &str| match field.contains([',', '"', '\n', '\r'])
+    {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    };
+
+    // Each column is as wide as the most components it has in any row
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| rows.iter().map(|row| row[col].len()).max().unwrap_or(1).max(1))
+        .collect();
+
+    let mut csv = header.iter()
+        .zip(&widths)
+        .flat_map(|(name, &width)| (0..width).map(move |k| match k
+        {
+            0 => escape(name),
+            k => escape(&format!("{name}[{k}]")),
+        }))
+        .collect::<Vec<String>>()
+        .join(",");
+    csv.push('\n');
+
+    for row in rows
+    {
+        let fields: Vec<String> = row.iter()
+            .zip(&widths)
+            .flat_map(|(values, &width)| (0..width).map(|k| values.get(k).map(f64::to_string).unwrap_or_default()))
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Sets the potential of each unknown in a graph to its value in `guess`, then 
+/// updates the potentials that controlled sources drive from the unknowns.
+fn load_guess(graph: &NodalGraph, unknowns: &[ComponentIndex], guess: &[f64]) -> anyhow::Result<()>
+{
+    for (&ComponentIndex { node, component }, &val) in unknowns.iter().zip(guess)
+    {
+        graph.nodes[node as usize]
+            .try_borrow_mut()?
+            .potential[(component as usize, 0)] = val;
+    }
+    graph.update_controlled()
+}
+
 /// Returns a boolean indicating whether the `GenericNode` at the given pointer 
 /// is locked or not. This function will return a `DroppedNodeError` if the 
 /// node was dropped for some reason prior to checking the state of `is_locked`.
@@ -387,7 +2216,22 @@ pub fn get_node_potential(node_ref: &Weak<RefCell<GenericNode>>) -> anyhow::Resu
 {
     if let Some(node) = node_ref.upgrade()
     {
-        Ok(node.try_borrow_mut()?.potential.clone())
+        Ok(node.try_borrow()?.potential.clone())
+    }
+    else
+    {
+        Err(DroppedNodeError.into())
+    }
+}
+
+/// Returns where the `GenericNode` at the given pointer is in space as `[x, y, z]`,
+/// if it was placed (see `NodalAnalysisStudyBuilder::place_node`). Element constructors
+/// can use this to find their dimensions from the geometry of the model.
+pub fn get_node_coordinates(node_ref: &Weak<RefCell<GenericNode>>) -> anyhow::Result<Option<[f64; 3]>>
+{
+    if let Some(node) = node_ref.upgrade()
+    {
+        Ok(node.try_borrow()?.coordinates)
     }
     else
     {
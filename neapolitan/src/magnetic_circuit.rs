@@ -0,0 +1,175 @@
+// Std modules
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use gmatlib::Matrix;
+use thiserror::Error;
+
+// Local modules
+use crate::dc_circuits::drive_node;
+use crate::flux_formulas::*;
+use crate::modelling::element::ElementConstructor;
+use crate::{GenericElement, GenericNode, NodalAnalysisStudyConfigurator};
+
+#[derive(Debug, Error)]
+pub enum MagneticElementError
+{
+    #[error("you must specify a magnetomotive force, or a number of turns and the current through them (in that order), to create an mmf_source element")]
+    MmfParameters,
+    #[error("you must specify a positive reluctance, or a positive length, cross-sectional area and relative permeability (in that order), to create a reluctance element")]
+    ReluctanceParameters,
+    #[error("you must specify a positive length and cross-sectional area (in that order) to create an air_gap element")]
+    AirGapParameters,
+}
+
+/// The permeability of free space in H/m.
+pub const VACUUM_PERMEABILITY: f64 = 1.25663706212E-6;
+
+pub const MAGNETIC_CIRCUIT: &str = "magnetic_circuit";
+pub const MMF_SOURCE: &str = "mmf_source";
+pub const RELUCTANCE: &str = "reluctance";
+pub const AIR_GAP: &str = "air_gap";
+
+/// Returns the configurator for a magnetic circuit study, in which each nodal potential
+/// is a magnetic scalar potential (in ampere-turns) and each elemental flux is the magnetic
+/// flux (in webers) through a section of the circuit.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::magnetic_circuit::*;
+///
+/// // A 500 turn coil carrying 2 A around a steel core with a 1 mm air gap
+/// let soln = NodalAnalysisStudyBuilder::new(MAGNETIC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(MMF_SOURCE, 0, 1, vec![500.0, 2.0]).unwrap()
+///     .add_element(RELUCTANCE, 1, 2, vec![0.3, 1E-4, 2000.0]).unwrap()
+///     .add_element(AIR_GAP,    2, 0, vec![1E-3, 1E-4]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+///
+/// // Most of the coil's mmf is dropped across the air gap
+/// let core = 0.3 / (VACUUM_PERMEABILITY * 2000.0 * 1E-4);
+/// let gap = 1E-3 / (VACUUM_PERMEABILITY * 1E-4);
+/// let flux = soln.get_element_flux(2).unwrap()[0];
+/// assert!((flux - 1000.0 / (core + gap)).abs() < 1E-12);
+/// assert!(soln.get_node_potential(2).unwrap()[0] > 800.0);
+/// ```
+pub fn magnetic_circuit_config() -> NodalAnalysisStudyConfigurator
+{
+    NodalAnalysisStudyConfigurator
+    {
+        dimension: 1,
+        elements: HashMap::from([
+            (MMF_SOURCE,              mmf_source              as ElementConstructor),
+            (RELUCTANCE,              reluctance              as ElementConstructor),
+            (AIR_GAP,                 air_gap                 as ElementConstructor),
+        ]),
+        terminal_elements: HashMap::new(),
+        aliases: HashMap::new(),
+        flux_formulas: builtin_flux_formulas(),
+        flux_elements: HashMap::new(),
+    }
+}
+
+/// A `PostProcessor` that reports the flux density through an element as `"flux_density"`
+/// (in teslas), for elements whose gain is their permeance followed by their cross-sectional area.
+pub fn flux_density(flux: &Matrix<f64>, _potentials: &[Matrix<f64>], gain: &Matrix<f64>) -> HashMap<String, f64>
+{
+    HashMap::from([("flux_density".to_string(), flux[(0, 0)] / gain[(1, 0)])])
+}
+
+/// Represents a coil that drives a magnetomotive force around the circuit, analogous to a
+/// `voltage_source` in DC circuits. The gain is given as the mmf in ampere-turns, or as the
+/// number of turns followed by the current through them.
+pub fn mmf_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    mmf: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let mmf = match *mmf.as_slice()
+    {
+        [mmf] => mmf,
+        [turns, current] => turns * current,
+        _ => return Err(MagneticElementError::MmfParameters.into()),
+    };
+
+    let drives_output = drive_node(&input_node, &output_node, mmf)?;
+
+    GenericElement::try_new(
+        vec![mmf],
+        input_node, output_node,
+        observe_flux,
+        drives_output,
+        drives_output,
+        !drives_output,
+    )
+}
+
+/// Builds an element whose flux is its permeance times the mmf across it, reporting
+/// its flux density if its cross-sectional area is given after its permeance.
+fn permeance(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    permeance_and_area: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let has_area = permeance_and_area.len() > 1;
+    let elem = GenericElement::try_new(
+        permeance_and_area,
+        input_node, output_node,
+        normal_flux,
+        false,
+        true, true,
+    )?;
+
+    if has_area
+    {
+        elem.set_post_process(flux_density)?;
+    }
+    Ok(elem)
+}
+
+/// Represents a section of the circuit with a known reluctance, analogous to a `resistor`
+/// in DC circuits. The gain is given as the reluctance in ampere-turns per weber, or as the
+/// section's length, cross-sectional area and relative permeability, in which case its flux
+/// density is also reported.
+pub fn reluctance(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    reluctance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let permeance_and_area = match *reluctance.as_slice()
+    {
+        [r] if r > 0.0 => vec![1.0 / r],
+        [length, area, mu_r] if length > 0.0 && area > 0.0 && mu_r > 0.0 => vec![VACUUM_PERMEABILITY * mu_r * area / length, area],
+        _ => return Err(MagneticElementError::ReluctanceParameters.into()),
+    };
+
+    permeance(input_node, output_node, permeance_and_area)
+}
+
+/// Represents an air gap in a core, i.e. a section with the permeability of free space.
+/// The gain is given as the length of the gap and its cross-sectional area, and fringing
+/// around the gap is neglected.
+pub fn air_gap(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    length_and_area: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    match *length_and_area.as_slice()
+    {
+        [length, area] if length > 0.0 && area > 0.0 => permeance(
+            input_node, output_node,
+            vec![VACUUM_PERMEABILITY * area / length, area],
+        ),
+        _ => Err(MagneticElementError::AirGapParameters.into()),
+    }
+}
@@ -1,118 +1,700 @@
-use std::env::args;
-use std::fs::{read_to_string, write};
+use std::fmt::Display;
+use std::fs::{read_dir, read_to_string, write};
+use std::io::{stderr, stdin, stdout, Read, Write};
 use std::process;
-use serde_json::{from_str, to_string_pretty};
+use std::sync::atomic::{AtomicBool, Ordering};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::audit::AuditedStudyResult;
+use neapolitan::batch::solve_studies_parallel;
+use neapolitan::formats::FileFormat;
+use neapolitan::modelling::NodalAnalysisModel;
+use neapolitan::options::{Damping, SolverOptions};
+use neapolitan::schema::MODEL_SCHEMA;
+use neapolitan::sweep::{linspace, SweepParameter};
+use tracing::{error, info, warn, Level};
 
-fn main()
+/// A file could not be read or written. Usage errors exit with 2.
+const EXIT_IO: i32 = 1;
+/// The model could not be read, validated or built.
+const EXIT_INVALID_MODEL: i32 = 3;
+/// The solver did not converge on a solution.
+const EXIT_SOLVE_FAILED: i32 = 4;
+/// The results of a deterministic run did not match the previous audited run.
+const EXIT_AUDIT_MISMATCH: i32 = 5;
+
+const EXIT_CODES: &str = "Exit codes:
+  0  success
+  1  a file could not be read or written
+  2  the command line was invalid
+  3  the model could not be read, validated or built
+  4  the solver did not converge
+  5  results did not match the previous audited run";
+
+/// The model path that reads a model from stdin.
+const STDIN: &str = "-";
+
+/// Set when the model is read from stdin, so that results are written to stdout
+/// and logs are kept out of them on stderr.
+static PIPED: AtomicBool = AtomicBool::new(false);
+
+/// Returns where log lines are written: stdout, or stderr when results are written to stdout.
+fn log_writer() -> Box<dyn Write>
+{
+    match PIPED.load(Ordering::Relaxed)
+    {
+        true => Box::new(stderr()),
+        false => Box::new(stdout()),
+    }
+}
+
+/// Models and solves circuit-like problems.
+///
+/// Models are read in the format of their extension (JSON, YAML or TOML, and JSON by default),
+/// and SPICE netlists (`.cir`) are read in place of model files. A model given as `-` is read
+/// from stdin, and its results are written to stdout with logs on stderr.
+#[derive(Parser)]
+#[command(name = "neapolitan", version, after_help = EXIT_CODES)]
+struct Cli
+{
+    #[command(subcommand)]
+    command: Command,
+    /// The format of a model read from stdin [default: JSON]
+    #[arg(long, global = true, value_parser = FileFormat::from_name)]
+    input_format: Option<FileFormat>,
+    /// Logs the solver's residual at each iteration, and each element as it is built with `-vv`
+    #[arg(short, long, global = true, action = ArgAction::Count, alias = "progress")]
+    verbose: u8,
+    /// Only logs warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+#[derive(Subcommand)]
+enum Command
+{
+    /// Solves a model, writing its solution to `<model>.soln.<ext>`
+    Solve(SolveArgs),
+    /// Checks that a model can be read and built, without solving it
+    Validate(ValidateArgs),
+    /// Writes a model in another format, anonymized for bug reports or as a nexsys equation deck
+    Export(ExportArgs),
+    /// Solves a model at evenly spaced values of one gain or locked potential, writing
+    /// every solution to `<model>.sweep.<ext>`
+    Sweep(SweepArgs),
+    /// Prints the JSON Schema that model files must satisfy
+    Schema,
+    /// Serves studies over HTTP on the given address
+    #[cfg(feature = "server")]
+    Serve
+    {
+        /// The address to listen on, e.g. `127.0.0.1:8080`
+        address: String,
+    },
+}
+
+/// The settings used to build and solve a model.
+#[derive(Args)]
+struct StudyArgs
+{
+    /// The largest residual that the solver accepts [default: 1E-4]
+    #[arg(short, long)]
+    precision: Option<f64>,
+    /// The most iterations that the solver takes [default: 100]
+    #[arg(short, long)]
+    iterations: Option<usize>,
+    /// Damps each newton step by a fraction, or with a backtracking line search ('line-search')
+    #[arg(long, value_parser = parse_damping)]
+    damping: Option<Damping>,
+    /// The initial guess of nodes that were not given one [default: 1]
+    #[arg(long)]
+    guess: Option<f64>,
+    /// The finite-difference step of the solver's jacobian
+    #[arg(long)]
+    step: Option<f64>,
+    /// Locks node 0 at zero potential if no node is locked
+    #[arg(short = 'g', long)]
+    auto_ground: bool,
+    /// Estimates the jacobian from the model's topology
+    #[arg(short, long)]
+    sparse: bool,
+    /// Warns of nodes whose final flux discrepancy exceeds this tolerance
+    #[arg(short, long)]
+    tolerance: Option<f64>,
+    /// The format of the files written [default: the model's format]
+    #[arg(short, long, value_parser = FileFormat::from_name)]
+    format: Option<FileFormat>,
+}
+impl StudyArgs
+{
+    /// Returns the solver settings given, printing each one that differs from the default.
+    fn to_options(&self) -> SolverOptions
+    {
+        let mut options = SolverOptions::default();
+        if let Some(precision) = self.precision
+        {
+            info!("solver precision is: {precision}");
+            options.margin = precision;
+        }
+        if let Some(iterations) = self.iterations
+        {
+            info!("solver iteration limit is: {iterations}");
+            options.limit = iterations;
+        }
+        if let Some(damping) = self.damping
+        {
+            info!("solver damping is: {damping:?}");
+            options.damping = damping;
+        }
+        if let Some(guess) = self.guess
+        {
+            info!("default initial guess is: {guess}");
+            options.initial_guess = guess;
+        }
+        if let Some(step) = self.step
+        {
+            info!("jacobian step is: {step}");
+            options.step = step;
+        }
+        options
+    }
+
+    /// Builds a study of `model` with the settings given.
+    fn to_builder(&self, model: NodalAnalysisModel) -> NodalAnalysisStudyBuilder
+    {
+        let builder = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+            .with_auto_ground(self.auto_ground)
+            .with_sparse_jacobian(self.sparse);
+        match self.tolerance
+        {
+            Some(tolerance) =>
+            {
+                info!("flux conservation tolerance is: {tolerance}");
+                builder.with_conservation_tolerance(tolerance)
+            },
+            None => builder,
+        }
+    }
+}
+
+#[derive(Args)]
+struct SolveArgs
+{
+    /// The model file to solve, or the directory of models to solve with `--all`
+    model: String,
+    #[command(flatten)]
+    study: StudyArgs,
+    /// Records an audited run, or checks the results against the last one
+    #[arg(short, long, conflicts_with = "all")]
+    deterministic: bool,
+    /// Solves every model file in the directory, printing a summary of which failed and why.
+    /// Exits with the code of the first model that failed, if any did
+    #[arg(long)]
+    all: bool,
+    /// Solves the models of `--all` on multiple threads
+    #[arg(long, requires = "all")]
+    parallel: bool,
+    /// Also writes the results to `<model>.soln.csv`, or only the results to stdout if piped
+    #[arg(short, long)]
+    csv: bool,
+    /// Also writes the network and its results to `<model>.soln.vtk` for ParaView, or only
+    /// those to stdout if piped. Every node needs `coordinates` (or `x`/`y`/`z` metadata) to place it
+    #[arg(long)]
+    vtk: bool,
+}
+
+#[derive(Args)]
+struct ValidateArgs
+{
+    /// The model file to validate
+    model: String,
+    /// Locks node 0 at zero potential if no node is locked
+    #[arg(short = 'g', long)]
+    auto_ground: bool,
+}
+
+#[derive(Args)]
+struct ExportArgs
+{
+    /// The model file to export
+    model: String,
+    /// The format to write the model in [default: the model's format]
+    #[arg(short, long, value_parser = FileFormat::from_name, conflicts_with = "equations")]
+    format: Option<FileFormat>,
+    /// Removes names, labels and metadata, writing `<model>.anon.<ext>` by default
+    #[arg(short, long)]
+    anonymize: bool,
+    /// Writes the model's balance equations as a nexsys deck, `<model>.nxs` by default
+    #[arg(short, long)]
+    equations: bool,
+    /// The file to write to, or `-` for stdout
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct SweepArgs
+{
+    /// The model file to sweep
+    model: String,
+    #[command(flatten)]
+    study: StudyArgs,
+    /// Sweeps the gain of the element at this index
+    #[arg(long, required_unless_present = "node", conflicts_with = "node")]
+    element: Option<usize>,
+    /// Sweeps the potential of this locked node
+    #[arg(long)]
+    node: Option<usize>,
+    /// The component of the gain or potential to sweep
+    #[arg(long, default_value_t = 0)]
+    component: usize,
+    /// The first value of the sweep
+    #[arg(long)]
+    from: f64,
+    /// The last value of the sweep
+    #[arg(long)]
+    to: f64,
+    /// The number of evenly spaced values to solve at, including the first and last
+    #[arg(long, default_value_t = 11)]
+    points: usize,
+}
+
+/// Parses a damping argument: a fraction of each step, or 'line-search'.
+fn parse_damping(arg: &str) -> Result<Damping, String>
+{
+    match arg
+    {
+        "line-search" => Ok(Damping::Backtracking { min_step: 1E-3 }),
+        x => match x.parse()
+        {
+            Ok(o) => Ok(Damping::Fixed(o)),
+            Err(e) => Err(format!("use a fraction or 'line-search' ({e})")),
+        },
+    }
+}
+
+/// Reports an error and exits with `code`.
+fn fail(code: i32, message: &str, e: impl Display) -> !
 {
-    let args: Vec<String> = args().collect();
-    let mut precision: Option<f64> = None;
-    let mut iteration_limit: Option<usize> = None;
+    error!(error = %e, "{message}");
+    process::exit(code);
+}
 
-    let model_json = match read_to_string(&args[1]) 
+/// Writes `text` to `path`, or to stdout if results are being piped,
+/// exiting if it cannot be written.
+fn write_file(path: &str, text: anyhow::Result<String>)
+{
+    let text = match text
     {
         Ok(o) => o,
-        Err(e) => 
+        Err(e) => fail(EXIT_IO, &format!("failed to format {path}!"), e),
+    };
+
+    if PIPED.load(Ordering::Relaxed)
+    {
+        print!("{text}");
+    }
+    else if let Err(e) = write(path, text)
+    {
+        fail(EXIT_IO, &format!("neapolitan could not write to {path}!"), e);
+    }
+}
+
+/// Returns where `write_file` writes to `path`, for logging.
+fn destination(path: &str) -> &str
+{
+    match PIPED.load(Ordering::Relaxed)
+    {
+        true => "stdout",
+        false => path,
+    }
+}
+
+/// Reads the model at `path`, returning it with its format and
+/// its path without the extension, or exits if it cannot be read.
+/// A path of `-` reads the model from stdin in `stdin_format`.
+fn read_model(path: &str, stdin_format: Option<FileFormat>) -> (NodalAnalysisModel, FileFormat, String)
+{
+    if path == STDIN
+    {
+        let mut model_text = String::new();
+        if let Err(e) = stdin().read_to_string(&mut model_text)
         {
-            println!("[neapolitan].....ERR: could not find the specified filepath!");
-            println!("[neapolitan].....ERR: {e}");
-            process::exit(1);
+            fail(EXIT_IO, "could not read a model from stdin!", e);
         }
+
+        let format = stdin_format.unwrap_or(FileFormat::Json);
+        return match NodalAnalysisModel::from_str_strict(&model_text, format)
+        {
+            Ok(o) => (o, format, String::from("stdin")),
+            Err(e) => fail(EXIT_INVALID_MODEL, "failed to read model from stdin!", e),
+        };
+    }
+
+    let model_text = match read_to_string(path)
+    {
+        Ok(o) => o,
+        Err(e) => fail(EXIT_IO, "could not find the specified filepath!", e),
     };
 
-    let mut i = 1;
-    while i < args.len()
+    match parse_model(path, &model_text)
+    {
+        Ok(o) => (o, model_format(path), split_extension(path).0.to_string()),
+        Err(e) => fail(EXIT_INVALID_MODEL, &format!("failed to read model from {path}!"), e),
+    }
+}
+
+/// Splits `path` into the path without its extension and the extension, including the leading `.`
+fn split_extension(path: &str) -> (&str, &str)
+{
+    match path.rfind('.')
+    {
+        Some(idx) if !path[idx..].contains(['/', '\\']) => path.split_at(idx),
+        _ => (path, ""),
+    }
+}
+
+/// Returns the format of the model file at `path`, which is JSON unless its extension says otherwise.
+fn model_format(path: &str) -> FileFormat
+{
+    FileFormat::from_path(path).unwrap_or(FileFormat::Json)
+}
+
+/// Reads a model from the text of the file at `path`, in the format of its extension.
+fn parse_model(path: &str, text: &str) -> anyhow::Result<NodalAnalysisModel>
+{
+    match split_extension(path).1
+    {
+        ".cir" => NodalAnalysisModel::from_spice(text),
+        _ => NodalAnalysisModel::from_str_strict(text, model_format(path)),
+    }
+}
+
+fn solve(args: SolveArgs, stdin_format: Option<FileFormat>)
+{
+    if args.all
     {
-        let arg = &args[i];
+        return solve_all(args);
+    }
 
-        if arg == "--precision" ||
-           arg == "-p"
+    PIPED.store(args.model == STDIN, Ordering::Relaxed);
+    let options = args.study.to_options();
+    if args.deterministic
+    {
+        info!("running in deterministic audit mode");
+    }
+
+    let (model, input_format, stem) = read_model(&args.model, stdin_format);
+    let output_format = args.study.format.unwrap_or(input_format);
+    let solution_file = format!("{stem}.soln{}", output_format.extension());
+    let vtk_model = args.vtk.then(|| model.clone());
+    let builder = args.study.to_builder(model);
+
+    let (solution, solution_text) = if args.deterministic
+    {
+        let solution = match builder.run_audited_study_with_options(&options)
         {
-            precision = match args[i + 1].parse()
+            Ok(o) => o,
+            Err(e) => fail(EXIT_SOLVE_FAILED, "failed to solve the given model!", e),
+        };
+
+        // Check the new results against the last audited run, if there was one
+        let previous = match PIPED.load(Ordering::Relaxed)
+        {
+            true => None,
+            false => read_to_string(&solution_file).ok(),
+        };
+        let previous = previous
+            .and_then(|x| output_format.read::<AuditedStudyResult>(&x).ok());
+
+        match previous
+        {
+            Some(previous) => match solution.verify_against(&previous)
             {
-                Ok(o) => 
-                {
-                    println!("[neapolitan]......... solver precision is: {o}");
-                    Some(o)
-                },
-                Err(e) =>
-                {
-                    println!("[neapolitan].....ERR: failed to parse precision argument!");
-                    println!("[neapolitan].....ERR: {e}");
-                    process::exit(1);
-                }
-            };
-
-            i += 1;
+                Ok(_) => info!("results match the previous audited run"),
+                Err(e) => fail(EXIT_AUDIT_MISMATCH, "results could not be verified against the previous audited run!", e),
+            },
+            None => info!("no previous audited run found, recording a new audit"),
+        }
+
+        let solution_text = output_format.write(&solution);
+        (solution.get_result().clone(), solution_text)
+    }
+    else
+    {
+        let solution = match builder.run_study_with_options(&options)
+        {
+            Ok(o) => o,
+            Err(e) => fail(EXIT_SOLVE_FAILED, "failed to solve the given model!", e),
+        };
+
+        let solution_text = output_format.write(&solution);
+        (solution, solution_text)
+    };
+
+    for warning in solution.get_warnings()
+    {
+        warn!("{warning}");
+    }
+
+    // Spreadsheet-friendly and VTK results are written alongside the solution file,
+    // or in its place when there is only stdout to write to
+    if args.csv
+    {
+        let csv_file = format!("{stem}.soln.csv");
+        write_file(&csv_file, Ok(solution.to_csv()));
+        info!("wrote results to {}", destination(&csv_file));
+    }
+    if let Some(model) = &vtk_model
+    {
+        let vtk_file = format!("{stem}.soln.vtk");
+        match solution.to_vtk(model)
+        {
+            Ok(o) => write_file(&vtk_file, Ok(o)),
+            Err(e) => fail(EXIT_INVALID_MODEL, "failed to place the model's nodes for VTK!", e),
         }
+        info!("wrote network to {}", destination(&vtk_file));
+    }
+    if PIPED.load(Ordering::Relaxed) && (args.csv || args.vtk)
+    {
+        return;
+    }
 
-        else if arg == "--iterations" ||
-                arg == "-i"
+    write_file(&solution_file, solution_text);
+    info!("wrote solution to {}", destination(&solution_file));
+}
+
+/// Returns whether `path` is a model file that `solve --all` should solve,
+/// rather than another kind of file or the results of a previous run.
+fn is_model_file(path: &str) -> bool
+{
+    let (stem, extension) = split_extension(path);
+    let is_model = extension == ".cir" || FileFormat::from_path(path).is_some();
+    is_model && !stem.ends_with(".soln") && !stem.ends_with(".sweep")
+}
+
+/// Solves every model file in the directory `args.model`, writing each solution next to its
+/// model and printing a summary of which models converged and which failed.
+fn solve_all(args: SolveArgs)
+{
+    let options = args.study.to_options();
+    let mut paths: Vec<String> = match read_dir(&args.model)
+    {
+        Ok(o) => o.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .filter(|path| is_model_file(path))
+            .collect(),
+        Err(e) => fail(EXIT_IO, &format!("could not read the directory {}!", args.model), e),
+    };
+    paths.sort();
+    info!("solving {} models in {}", paths.len(), args.model);
+
+    // Each model's exit code (0 once solved) and the reason that it failed, if it did
+    let mut outcomes: Vec<(i32, String)> = vec![(0, String::new()); paths.len()];
+    let mut studies = vec![];
+    for (idx, path) in paths.iter().enumerate()
+    {
+        let model = match read_to_string(path)
         {
-            iteration_limit = match args[i + 1].parse()
+            Ok(o) => parse_model(path, &o),
+            Err(e) =>
             {
-                Ok(o) => 
-                {
-                    println!("[neapolitan]......... solver iteration limit is: {o}");
-                    Some(o)
-                },
-                Err(e) =>
-                {
-                    println!("[neapolitan].....ERR: failed to parse iteration limit argument!");
-                    println!("[neapolitan].....ERR: {e}");
-                    process::exit(1);
-                }
-            };
-            
-            i += 1;
+                outcomes[idx] = (EXIT_IO, e.to_string());
+                continue;
+            },
+        };
+        match model.and_then(|model| args.study.to_builder(model).compile())
+        {
+            Ok(o) => studies.push((idx, o)),
+            Err(e) => outcomes[idx] = (EXIT_INVALID_MODEL, e.to_string()),
         }
+    }
+
+    let vtk_models: Vec<_> = studies.iter().map(|(_, study)| args.vtk.then(|| study.get_model().clone())).collect();
+    let (solved, studies): (Vec<usize>, Vec<_>) = studies.into_iter().unzip();
+    let solutions = match args.parallel
+    {
+        true => solve_studies_parallel(studies, &options),
+        false => studies.into_iter().map(|mut study| study.solve_with_options(&options)).collect(),
+    };
+
+    for ((idx, solution), vtk_model) in solved.into_iter().zip(solutions).zip(vtk_models)
+    {
+        let solution = match solution
+        {
+            Ok(o) => o,
+            Err(e) =>
+            {
+                outcomes[idx] = (EXIT_SOLVE_FAILED, e.to_string());
+                continue;
+            },
+        };
 
-        i += 1;
+        let path = &paths[idx];
+        let stem = split_extension(path).0;
+        let output_format = args.study.format.unwrap_or(model_format(path));
+        write_file(&format!("{stem}.soln{}", output_format.extension()), output_format.write(&solution));
+        if args.csv
+        {
+            write_file(&format!("{stem}.soln.csv"), Ok(solution.to_csv()));
+        }
+        if let Some(model) = vtk_model
+        {
+            // A model that cannot be placed still has its solution written
+            match solution.to_vtk(&model)
+            {
+                Ok(o) => write_file(&format!("{stem}.soln.vtk"), Ok(o)),
+                Err(e) => warn!("{path}: {e}"),
+            }
+        }
+
+        let statistics = solution.get_statistics();
+        outcomes[idx].1 = format!("{} iterations, residual {:.2e}", statistics.iterations, statistics.residual_norm);
     }
 
-    let model = match from_str(&model_json)
+    // Summarize the batch as a table, one row per model
+    let width = paths.iter().map(|path| path.len()).max().unwrap_or(0);
+    for (path, (code, detail)) in paths.iter().zip(&outcomes)
     {
-        Ok(o) => o,
-        Err(e) =>
+        match *code
         {
-            println!("[neapolitan].....ERR: failed to read model from json file!");
-            println!("[neapolitan].....ERR: {e}");
-            process::exit(1);
+            0 => info!("{path:width$}  {:10}  {detail}", "converged"),
+            EXIT_IO => warn!("{path:width$}  {:10}  {detail}", "unreadable"),
+            EXIT_INVALID_MODEL => warn!("{path:width$}  {:10}  {detail}", "invalid"),
+            _ => warn!("{path:width$}  {:10}  {detail}", "failed"),
         }
+    }
+
+    let failures: Vec<i32> = outcomes.iter().map(|(code, _)| *code).filter(|code| *code != 0).collect();
+    info!("{} of {} models converged", paths.len() - failures.len(), paths.len());
+    if let Some(code) = failures.first()
+    {
+        process::exit(*code);
+    }
+}
+
+fn validate(args: ValidateArgs, stdin_format: Option<FileFormat>)
+{
+    let (model, _, _) = read_model(&args.model, stdin_format);
+    let name = match args.model.as_str()
+    {
+        STDIN => "stdin",
+        path => path,
     };
 
-    let solution = match NodalAnalysisStudyBuilder::from_model_with_default_config(model)
-        .run_study(precision.unwrap_or(0.0001), iteration_limit.unwrap_or(100))
+    let builder = NodalAnalysisStudyBuilder::from_model_with_default_config(model).with_auto_ground(args.auto_ground);
+    let study = match builder.compile()
     {
         Ok(o) => o,
-        Err(e) => 
+        Err(e) => fail(EXIT_INVALID_MODEL, &format!("failed to build model from {name}!"), e),
+    };
+
+    let arena = study.get_arena();
+    info!("{name} is valid: {} nodes and {} elements", arena.node_count(), arena.element_count());
+}
+
+fn export(args: ExportArgs, stdin_format: Option<FileFormat>)
+{
+    let to_stdout = match args.output.as_deref()
+    {
+        Some(output) => output == STDIN,
+        None => args.model == STDIN,
+    };
+    PIPED.store(to_stdout, Ordering::Relaxed);
+
+    let (model, input_format, stem) = read_model(&args.model, stdin_format);
+    let model = match args.anonymize
+    {
+        true => model.anonymize(),
+        false => model,
+    };
+
+    let (path, text) = if args.equations
+    {
+        let text = match model.to_equation_system()
         {
-            println!("[neapolitan].....ERR: failed to solve the given model!");
-            println!("[neapolitan].....ERR: {e}");
-            process::exit(1);
-        }
+            Ok(o) => o,
+            Err(e) => fail(EXIT_INVALID_MODEL, "failed to write the model's equations!", e),
+        };
+        (args.output.unwrap_or(format!("{stem}.nxs")), Ok(text))
+    }
+    else
+    {
+        let format = args.format.unwrap_or(input_format);
+        let default_path = match args.anonymize
+        {
+            true => format!("{stem}.anon{}", format.extension()),
+            false => format!("{stem}{}", format.extension()),
+        };
+        (args.output.unwrap_or(default_path), format.write(&model))
     };
 
-    let solution_json = match to_string_pretty(&solution)
+    if path == args.model && !to_stdout
+    {
+        fail(EXIT_IO, "refusing to overwrite the model being exported!", "give another file with --output");
+    }
+    write_file(&path, text);
+    info!("wrote model to {}", destination(&path));
+}
+
+fn sweep(args: SweepArgs, stdin_format: Option<FileFormat>)
+{
+    PIPED.store(args.model == STDIN, Ordering::Relaxed);
+    let options = args.study.to_options();
+    let (model, input_format, stem) = read_model(&args.model, stdin_format);
+    let output_format = args.study.format.unwrap_or(input_format);
+
+    let parameter = match (args.element, args.node)
+    {
+        (Some(element), _) => SweepParameter::Gain { element, component: args.component },
+        (None, Some(node)) => SweepParameter::Potential { node, component: args.component },
+        (None, None) => unreachable!("clap requires an element or node to sweep"),
+    };
+
+    let values = linspace(args.from, args.to, args.points);
+    let sweep = match args.study.to_builder(model).run_sweep_with_options(&[parameter], &[values], &options)
     {
         Ok(o) => o,
-        Err(e) => 
-        {
-            println!("[neapolitan].....ERR: failed to format solution file!");
-            println!("[neapolitan].....ERR: {e}");
-            process::exit(1);
-        }
+        Err(e) => fail(EXIT_SOLVE_FAILED, "failed to sweep the given model!", e),
     };
 
-    let solution_file = args[1].replace(".json", ".soln.json");
-    match write(solution_file, solution_json) 
+    let sweep_file = format!("{stem}.sweep{}", output_format.extension());
+    write_file(&sweep_file, output_format.write(&sweep));
+    info!("wrote {} solutions to {}", sweep.get_results().len(), destination(&sweep_file));
+}
+
+fn main()
+{
+    let cli = Cli::parse();
+    let level = match (cli.quiet, cli.verbose)
     {
-        Ok(_) => process::exit(0),
-        Err(e) => {
-            println!("[neapolitan].....ERR: neapolitan could not write to the output file!");
-            println!("[neapolitan].....ERR: {e}");
-            process::exit(1);
-        }
+        (true, _) => Level::WARN,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_target(false)
+        .without_time()
+        .with_writer(log_writer)
+        .init();
+
+    match cli.command
+    {
+        Command::Solve(args) => solve(args, cli.input_format),
+        Command::Validate(args) => validate(args, cli.input_format),
+        Command::Export(args) => export(args, cli.input_format),
+        Command::Sweep(args) => sweep(args, cli.input_format),
+        Command::Schema => println!("{MODEL_SCHEMA}"),
+        #[cfg(feature = "server")]
+        Command::Serve { address } =>
+        {
+            info!("serving studies on {address}");
+            if let Err(e) = neapolitan::server::serve(&address)
+            {
+                fail(EXIT_IO, "failed to serve studies!", e);
+            }
+        },
     }
-}
\ No newline at end of file
+}
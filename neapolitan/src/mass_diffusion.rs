@@ -0,0 +1,185 @@
+// Std modules
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use thiserror::Error;
+
+// Local modules
+use crate::dc_circuits::drive_node;
+use crate::flux_formulas::*;
+use crate::modelling::element::ElementConstructor;
+use crate::{GenericElement, GenericNode, NodalAnalysisStudyConfigurator};
+
+#[derive(Debug, Error)]
+pub enum MassDiffusionElementError
+{
+    #[error("you must specify a positive diffusance, or a positive diffusivity, area and length (in that order), to create a diffusion element")]
+    DiffusionParameters,
+    #[error("you must specify a positive diffusivity, partition coefficient, thickness and area (in that order) to create a membrane_interface element")]
+    MembraneParameters,
+    #[error("you must specify only a concentration difference to create a concentration_source element")]
+    ConcentrationParameters,
+    #[error("you must specify only a molar flow rate to create a species_source element")]
+    SpeciesParameters,
+}
+
+pub const MASS_DIFFUSION: &str = "mass_diffusion";
+pub const DIFFUSION: &str = "diffusion";
+pub const MEMBRANE_INTERFACE: &str = "membrane_interface";
+pub const CONCENTRATION_SOURCE: &str = "concentration_source";
+pub const SPECIES_SOURCE: &str = "species_source";
+
+/// Returns the configurator for a steady-state mass diffusion study, in which each nodal
+/// potential is the concentration of a species (e.g. in mol/m^3) and each elemental flux
+/// is the rate at which the species is transported (e.g. in mol/s).
+///
+/// Like any other configurator, it can be extended with new element types, flux element
+/// types and aliases, and solved under a name of its own.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::mass_diffusion::*;
+///
+/// // A first-order reaction consumes the species at a rate proportional to its
+/// // concentration, i.e. it is a flux element to a node held at zero concentration
+/// let config = mass_diffusion_config()
+///     .add_flux_element_type("first_order_reaction", "normal_flux").unwrap()
+///     .add_alias("membrane", MEMBRANE_INTERFACE).unwrap();
+///
+/// // A feed held at 10 mol/m^3 above the reactor's sink, separated from it by a membrane
+/// let soln = NodalAnalysisStudyBuilder::new("reactor".to_string(), Some(HashMap::from([("reactor".to_string(), config)])))
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(CONCENTRATION_SOURCE,   0, 1, vec![10.0]).unwrap()
+///     .add_element("membrane",             1, 2, vec![1E-9, 0.5, 1E-4, 2.0]).unwrap()
+///     .add_element("first_order_reaction", 2, 0, vec![1E-5]).unwrap()
+///     .run_study(1E-15, 100)
+///     .unwrap();
+///
+/// // The membrane's permeance and the reaction rate constant are equal,
+/// // so the reactor settles halfway between the feed and the sink
+/// assert!((soln.get_node_potential(2).unwrap()[0] - 5.0).abs() < 1E-9);
+/// ```
+pub fn mass_diffusion_config() -> NodalAnalysisStudyConfigurator
+{
+    NodalAnalysisStudyConfigurator
+    {
+        dimension: 1,
+        elements: HashMap::from([
+            (DIFFUSION,               diffusion               as ElementConstructor),
+            (MEMBRANE_INTERFACE,      membrane_interface      as ElementConstructor),
+            (CONCENTRATION_SOURCE,    concentration_source    as ElementConstructor),
+            (SPECIES_SOURCE,          species_source          as ElementConstructor),
+        ]),
+        terminal_elements: HashMap::new(),
+        aliases: HashMap::new(),
+        flux_formulas: builtin_flux_formulas(),
+        flux_elements: HashMap::new(),
+    }
+}
+
+/// Represents diffusion through a uniform medium following Fick's first law, where the flux
+/// is `diffusivity * area / length * (c_in - c_out)`. The gain is given as the diffusivity,
+/// the cross-sectional area and the length of the path (in that order), or as their product
+/// `diffusivity * area / length` (the diffusance) if it was calculated in advance.
+pub fn diffusion(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    diffusivity_area_and_length: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let diffusance = match *diffusivity_area_and_length.as_slice()
+    {
+        [g] if g > 0.0 => g,
+        [d, area, length] if d > 0.0 && area > 0.0 && length > 0.0 => d * area / length,
+        _ => return Err(MassDiffusionElementError::DiffusionParameters.into()),
+    };
+
+    GenericElement::try_new(
+        vec![diffusance],
+        input_node, output_node,
+        normal_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents a membrane separating two regions of the same phase, where the flux is
+/// `diffusivity * partition * area / thickness * (c_in - c_out)`. The partition coefficient
+/// is the ratio of the concentration just inside the membrane to the concentration beside it.
+/// The gain is given as the diffusivity of the species in the membrane, the partition
+/// coefficient, the membrane's thickness and its area (in that order).
+pub fn membrane_interface(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    membrane: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let permeance = match *membrane.as_slice()
+    {
+        [d, k, thickness, area] if d > 0.0 && k > 0.0 && thickness > 0.0 && area > 0.0 => d * k * area / thickness,
+        _ => return Err(MassDiffusionElementError::MembraneParameters.into()),
+    };
+
+    GenericElement::try_new(
+        vec![permeance],
+        input_node, output_node,
+        normal_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents a fixed difference in concentration between two nodes, e.g. a reservoir held
+/// at a known concentration, analogous to a `voltage_source` in DC circuits. The gain is given
+/// as the concentration of the output above the input.
+pub fn concentration_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    concentration: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if concentration.len() != 1
+    {
+        return Err(MassDiffusionElementError::ConcentrationParameters.into());
+    }
+
+    let drives_output = drive_node(&input_node, &output_node, concentration[0])?;
+
+    GenericElement::try_new(
+        concentration,
+        input_node, output_node,
+        observe_flux,
+        drives_output,
+        drives_output,
+        !drives_output,
+    )
+}
+
+/// Represents a fixed rate of transport from the input to the output, e.g. a species
+/// generated at a known rate, analogous to a `current_source` in DC circuits. The gain
+/// is given as the molar (or mass) flow rate.
+pub fn species_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    rate: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if rate.len() != 1
+    {
+        return Err(MassDiffusionElementError::SpeciesParameters.into());
+    }
+
+    GenericElement::try_new(
+        rate,
+        input_node, output_node,
+        constant_flux,
+        false,
+        true, true,
+    )
+}
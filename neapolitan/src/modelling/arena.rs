@@ -0,0 +1,431 @@
+/// Std modules
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+/// Local modules
+use crate::Matrix;
+use crate::modelling::element::{ElementConstructor, ElementSensor, FluxCalculation, GenericElement, TerminalElementConstructor};
+use crate::modelling::node::GenericNode;
+
+/// Identifies a node in a `NodalArena` by its index, i.e. the order it was added to the model.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId(pub usize);
+
+/// Identifies an element in a `NodalArena` by its index, i.e. the order it was added to the model.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ElementId(pub usize);
+
+/// The constructor used to build an element, which depends on how many terminals it has.
+#[derive(Clone, Copy, Debug)]
+pub (in crate) enum Constructor
+{
+    Two(ElementConstructor),
+    Many(TerminalElementConstructor),
+    Flux(FluxCalculation),
+}
+
+/// The quantity sensed by a controlled element in a `NodalArena`.
+///
+/// # Variants:
+/// - `Potential` - the potential of the first node minus that of the second
+/// - `Flux` - the flux through another element
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ArenaSensor
+{
+    Potential(NodeId, NodeId),
+    Flux(ElementId),
+}
+
+/// A node's state, stored by value in a `NodalArena`.
+#[derive(Clone, Debug)]
+struct ArenaNode
+{
+    potential: Matrix<f64>,
+    is_locked: bool,
+    metadata: Option<HashMap<String, f64>>,
+    coordinates: Option<[f64; 3]>,
+    elements: Vec<ElementId>,
+}
+
+/// An element's connections and model gain, stored by value in a `NodalArena`.
+#[derive(Clone, Debug)]
+struct ArenaElement
+{
+    constructor: Constructor,
+    nodes: Vec<NodeId>,
+    gain: Vec<f64>,
+    sensor: Option<ArenaSensor>,
+}
+
+/// The nodes and elements of a model, stored by value and connected by index rather than
+/// by reference. Unlike a network of `GenericNode`s and `GenericElement`s, an arena has no
+/// shared ownership or interior mutability, so it can be sent to (and solved on) other threads.
+///
+/// # Compatibility with element constructors:
+/// Elements are stored as the constructor that builds them and their model gain, so every
+/// `ElementConstructor`, `TerminalElementConstructor` and `FluxCalculation` works with an arena
+/// unchanged. Flux calculations are run on a `NodalGraph` (see `NodalArena::view`), which builds
+/// the familiar `Rc<RefCell<GenericNode>>` network from the arena on the thread that needs it.
+#[derive(Clone, Debug)]
+pub struct NodalArena
+{
+    dimension: usize,
+    nodes: Vec<ArenaNode>,
+    elements: Vec<ArenaElement>,
+}
+impl NodalArena
+{
+    /// Creates an arena of `nodes` unlocked nodes, each with a potential of `dimension`
+    /// components that are all 1.0, and no elements.
+    pub (in crate) fn new(dimension: usize, nodes: usize) -> NodalArena
+    {
+        NodalArena
+        {
+            dimension,
+            nodes: (0..nodes)
+                .map(|_| ArenaNode
+                {
+                    potential: Matrix::from_col_vec(vec![1.0; dimension]),
+                    is_locked: false,
+                    metadata: None,
+                    coordinates: None,
+                    elements: vec![],
+                })
+                .collect(),
+            elements: vec![],
+        }
+    }
+
+    /// Sets the configured state of a node, as given by `configure_node`.
+    pub (in crate) fn configure_node(&mut self, node: NodeId, potential: Matrix<f64>, is_locked: bool, metadata: Option<HashMap<String, f64>>)
+    {
+        let node = &mut self.nodes[node.0];
+        node.potential = potential;
+        node.is_locked = is_locked;
+        node.metadata = metadata;
+    }
+
+    /// Sets where a node is in space, as given by `place_node`.
+    pub (in crate) fn place_node(&mut self, node: NodeId, coordinates: Option<[f64; 3]>)
+    {
+        self.nodes[node.0].coordinates = coordinates;
+    }
+
+    /// Returns where a node is in space as `[x, y, z]`, if it was placed.
+    pub fn get_node_coordinates(&self, node: NodeId) -> Option<[f64; 3]>
+    {
+        self.nodes[node.0].coordinates
+    }
+
+    /// Adds an element connecting `nodes` (the input and output, or every terminal in order).
+    pub (in crate) fn add_element(&mut self, constructor: Constructor, nodes: Vec<NodeId>, gain: Vec<f64>) -> ElementId
+    {
+        let id = ElementId(self.elements.len());
+        for node in &nodes
+        {
+            let elements = &mut self.nodes[node.0].elements;
+            if !elements.contains(&id)
+            {
+                elements.push(id);
+            }
+        }
+
+        self.elements.push(ArenaElement { constructor, nodes, gain, sensor: None });
+        id
+    }
+
+    /// Makes an element a controlled element that senses `sensor`.
+    pub (in crate) fn set_sensor(&mut self, element: ElementId, sensor: ArenaSensor)
+    {
+        self.elements[element.0].sensor = Some(sensor);
+    }
+
+    /// Returns the number of components in each nodal potential of the model's own domain.
+    pub fn get_dimension(&self) -> usize
+    {
+        self.dimension
+    }
+
+    /// Returns the number of components in the potential of a node, which differs from
+    /// `get_dimension` for nodes added to another domain (see `NodalAnalysisStudyBuilder::add_domain_nodes`).
+    pub fn get_node_dimension(&self, node: NodeId) -> usize
+    {
+        self.nodes[node.0].potential.get_rows()
+    }
+
+    /// Returns the number of nodes in the arena.
+    pub fn node_count(&self) -> usize
+    {
+        self.nodes.len()
+    }
+
+    /// Returns the number of elements in the arena.
+    pub fn element_count(&self) -> usize
+    {
+        self.elements.len()
+    }
+
+    /// Returns the potential of a node.
+    pub fn get_potential(&self, node: NodeId) -> &Matrix<f64>
+    {
+        &self.nodes[node.0].potential
+    }
+
+    /// Sets the potential of a node.
+    pub fn set_potential(&mut self, node: NodeId, potential: Matrix<f64>)
+    {
+        self.nodes[node.0].potential = potential;
+    }
+
+    /// Returns whether a node was configured as locked. Nodes that are locked
+    /// by an element (e.g. the node driven by a voltage source) are not included.
+    pub fn is_locked(&self, node: NodeId) -> bool
+    {
+        self.nodes[node.0].is_locked
+    }
+
+    /// Returns every element connected to a node, in the order they were added.
+    pub fn get_node_elements(&self, node: NodeId) -> &[ElementId]
+    {
+        &self.nodes[node.0].elements
+    }
+
+    /// Returns the nodes an element connects: its input and output, or each of its terminals in order.
+    pub fn get_element_nodes(&self, element: ElementId) -> &[NodeId]
+    {
+        &self.elements[element.0].nodes
+    }
+
+    /// Returns the model gain of an element.
+    pub fn get_gain(&self, element: ElementId) -> &[f64]
+    {
+        &self.elements[element.0].gain
+    }
+
+    /// Sets the model gain of an element.
+    pub (in crate) fn set_gain(&mut self, element: ElementId, gain: Vec<f64>)
+    {
+        self.elements[element.0].gain = gain;
+    }
+
+    /// Returns the quantity sensed by an element, if it is a controlled element.
+    pub fn get_sensor(&self, element: ElementId) -> Option<ArenaSensor>
+    {
+        self.elements[element.0].sensor
+    }
+
+    /// Returns the nodes that share an element with `node` (not including `node` itself),
+    /// i.e. the nodes whose potentials its flux balance depends on directly.
+    pub fn get_neighbours(&self, node: NodeId) -> Vec<NodeId>
+    {
+        let mut neighbours: Vec<NodeId> = self.get_node_elements(node).iter()
+            .flat_map(|&element| self.get_element_nodes(element).iter().copied())
+            .filter(|&other| other != node)
+            .collect();
+        neighbours.sort();
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Returns, for each node, the unknown nodes (those where `is_unknown` is `true`) whose
+    /// potentials its flux balance depends on, in order.
+    ///
+    /// A flux balance depends on the potentials of every node that its elements connect, and
+    /// on whatever those elements sense. Nodes that are locked by an element rather than by
+    /// `configure_node` (e.g. the node driven by a voltage source) have potentials that depend
+    /// on the elements connected to them, so their dependencies are followed through as well.
+    /// The result is conservative: a dependency is never missed, but one may be included that
+    /// an element's flux calculation does not actually use.
+    pub fn get_flux_balance_dependencies(&self, is_unknown: &[bool]) -> Vec<Vec<NodeId>>
+    {
+        let mut node_deps: Vec<BTreeSet<usize>> = (0..self.nodes.len())
+            .map(|idx| match is_unknown[idx]
+            {
+                true => BTreeSet::from([idx]),
+                false => BTreeSet::new(),
+            })
+            .collect();
+        let mut element_deps = vec![BTreeSet::new(); self.elements.len()];
+
+        // Dependencies only ever grow, so this stops once none of them change
+        let mut changed = true;
+        while changed
+        {
+            changed = false;
+            for (idx, element) in self.elements.iter().enumerate()
+            {
+                let mut deps: BTreeSet<usize> = element.nodes.iter()
+                    .flat_map(|node| node_deps[node.0].iter().copied())
+                    .collect();
+                match element.sensor
+                {
+                    Some(ArenaSensor::Potential(a, b)) => deps.extend(node_deps[a.0].iter().chain(&node_deps[b.0])),
+                    Some(ArenaSensor::Flux(sensed)) => deps.extend(element_deps[sensed.0].iter()),
+                    None => (),
+                }
+
+                if deps.len() > element_deps[idx].len()
+                {
+                    element_deps[idx] = deps;
+                    changed = true;
+                }
+            }
+
+            for (idx, node) in self.nodes.iter().enumerate()
+            {
+                if is_unknown[idx] || node.is_locked
+                {
+                    continue;
+                }
+
+                let deps: BTreeSet<usize> = node.elements.iter()
+                    .flat_map(|element| element_deps[element.0].iter().copied())
+                    .collect();
+                if deps.len() > node_deps[idx].len()
+                {
+                    node_deps[idx] = deps;
+                    changed = true;
+                }
+            }
+        }
+
+        self.nodes.iter()
+            .map(|node| node.elements.iter()
+                .flat_map(|element| element_deps[element.0].iter().copied())
+                .collect::<BTreeSet<usize>>()
+                .into_iter()
+                .map(NodeId)
+                .collect())
+            .collect()
+    }
+
+    /// Runs an element's constructor with the given gain, connecting it to `terminals`
+    /// (its input and output, or each of its terminals in order).
+    pub (in crate) fn build_element(&self, element: ElementId, terminals: &[Rc<RefCell<GenericNode>>], gain: Vec<f64>) -> anyhow::Result<Rc<GenericElement>>
+    {
+        match self.elements[element.0].constructor
+        {
+            Constructor::Two(constructor) => constructor(
+                Rc::downgrade(&terminals[0]),
+                Rc::downgrade(&terminals[1]),
+                gain,
+            ),
+            Constructor::Many(constructor) => constructor(terminals.iter().map(Rc::downgrade).collect(), gain),
+            Constructor::Flux(flux_calc) => GenericElement::try_new(
+                gain,
+                Rc::downgrade(&terminals[0]),
+                Rc::downgrade(&terminals[1]),
+                flux_calc,
+                false,
+                true, true,
+            ),
+        }
+    }
+
+    /// Builds the network of `GenericNode`s and `GenericElement`s that the arena describes,
+    /// calling `built` with the network's nodes after each element is built. If an element
+    /// fails to build, its id is returned alongside the error.
+    pub (in crate) fn view_with(&self, mut built: impl FnMut(ElementId, &[Rc<RefCell<GenericNode>>])) -> Result<NodalGraph, (ElementId, anyhow::Error)>
+    {
+        let nodes: Vec<Rc<RefCell<GenericNode>>> = self.nodes.iter()
+            .map(|node| Rc::new(RefCell::new(GenericNode
+            {
+                potential: node.potential.clone(),
+                inputs: vec![],
+                outputs: vec![],
+                terminals: vec![],
+                is_locked: node.is_locked,
+                _metadata: node.metadata.clone(),
+                coordinates: node.coordinates,
+            })))
+            .collect();
+
+        let mut elements = vec![];
+        for id in (0..self.elements.len()).map(ElementId)
+        {
+            let element = &self.elements[id.0];
+            let terminals: Vec<_> = element.nodes.iter().map(|node| Rc::clone(&nodes[node.0])).collect();
+            match self.build_element(id, &terminals, element.gain.clone())
+            {
+                Ok(element) => elements.push(element),
+                Err(e) => return Err((id, e)),
+            }
+            built(id, &nodes);
+        }
+
+        // Sensors are connected after every element exists so that any may be sensed
+        let mut controlled = vec![];
+        for (idx, element) in self.elements.iter().enumerate()
+        {
+            let sensor = match element.sensor
+            {
+                Some(ArenaSensor::Potential(a, b)) => ElementSensor::Potential(Rc::downgrade(&nodes[a.0]), Rc::downgrade(&nodes[b.0])),
+                Some(ArenaSensor::Flux(sensed)) => ElementSensor::Flux(Rc::downgrade(&elements[sensed.0])),
+                None => continue,
+            };
+
+            if let Err(e) = elements[idx].set_sensor(sensor)
+            {
+                return Err((ElementId(idx), e));
+            }
+            controlled.push(Rc::clone(&elements[idx]));
+        }
+
+        Ok(NodalGraph { nodes: Rc::new(nodes), elements, controlled: Rc::new(controlled) })
+    }
+
+    /// Builds the network of `GenericNode`s and `GenericElement`s that the arena describes,
+    /// which is used to run the elements' flux calculations. The network shares nothing with
+    /// the arena, so each thread that solves a model can build its own.
+    pub fn view(&self) -> anyhow::Result<NodalGraph>
+    {
+        self.view_with(|_, _| ()).map_err(|(_, e)| e)
+    }
+
+    /// Copies the potential of every node in a graph built with `view` back into the arena.
+    pub fn update_from(&mut self, graph: &NodalGraph) -> anyhow::Result<()>
+    {
+        for (node, graph_node) in self.nodes.iter_mut().zip(graph.nodes.iter())
+        {
+            node.potential = graph_node.try_borrow()?.potential.clone();
+        }
+        Ok(())
+    }
+}
+
+/// The network of `GenericNode`s and `GenericElement`s built from a `NodalArena`, indexed
+/// in the same order as the arena. This is a view for running flux calculations on the
+/// current thread, and does not change the arena it was built from.
+#[derive(Clone, Debug)]
+pub struct NodalGraph
+{
+    pub (in crate) nodes: Rc<Vec<Rc<RefCell<GenericNode>>>>,
+    pub (in crate) elements: Vec<Rc<GenericElement>>,
+    pub (in crate) controlled: Rc<Vec<Rc<GenericElement>>>,
+}
+impl NodalGraph
+{
+    /// Returns the node with the given id.
+    pub fn get_node(&self, node: NodeId) -> &Rc<RefCell<GenericNode>>
+    {
+        &self.nodes[node.0]
+    }
+
+    /// Returns the element with the given id.
+    pub fn get_element(&self, element: ElementId) -> &Rc<GenericElement>
+    {
+        &self.elements[element.0]
+    }
+
+    /// Calculates the flux of every controlled element, which updates any
+    /// potentials that they drive from the quantities that they sense.
+    pub fn update_controlled(&self) -> anyhow::Result<()>
+    {
+        for element in self.controlled.iter()
+        {
+            element.get_flux()?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::{Rc, Weak};
 
 use crate::Matrix;
 use crate::errors::{DroppedNodeError, FluxCalculationError};
+use crate::get_node_potential;
 use crate::modelling::node::GenericNode;
 
 /// The function signature for a `neapolitan`-compatible element constructor function.
@@ -29,6 +31,11 @@ use crate::modelling::node::GenericNode;
 /// potential difference specified prior to connecting the element to the network. 
 pub type ElementConstructor = fn (Weak<RefCell<GenericNode>>, Weak<RefCell<GenericNode>>, Vec<f64>) -> anyhow::Result<Rc<GenericElement>>;
 
+/// The function signature for a constructor of elements with any number of terminals, 
+/// e.g. transistors or 3-port valves. These are given every node that the element connects, 
+/// in the order of its terminals, and are otherwise identical to an `ElementConstructor`.
+pub type TerminalElementConstructor = fn (Vec<Weak<RefCell<GenericNode>>>, Vec<f64>) -> anyhow::Result<Rc<GenericElement>>;
+
 /// The function signature used to calculate flux between nodes.
 /// 
 /// # Design Philosophy
@@ -52,8 +59,79 @@ pub type ElementConstructor = fn (Weak<RefCell<GenericNode>>, Weak<RefCell<Gener
 /// that require calculating a node's flux discrepancy value in order to determine a flux must **not**
 /// mutably borrow that node or any other node used in the flux discrepancy calculation to avoid
 /// raising an `AlreadyMutablyBorrowed` error.  
+/// 
+/// # Note on Nonlinear Fluxes:
+/// The solver may try nodal potentials far from the solution before it converges, so flux
+/// calculations should return a finite flux for any potentials. Fluxes that grow very quickly
+/// (e.g. exponentially) should be limited, for example by continuing them linearly beyond some
+/// point as `flux_formulas::limited_exponential_flux` does. Storing that point in the element's
+/// gain lets each element choose its own limit.
 pub type FluxCalculation = fn (Rc<RefCell<GenericNode>>, Rc<RefCell<GenericNode>>, &Matrix<f64>, bool) -> anyhow::Result<Matrix<f64>>;
 
+/// The function signature used to calculate the fluxes of an element with any number of terminals.
+/// 
+/// These are given the nodes connected to each terminal (in order) and the element's gain, and
+/// must return one flux per terminal: the flux flowing *into* the element from that terminal's node.
+/// Unlike a `FluxCalculation`, the fluxes do not need to balance, e.g. a transistor's base, collector
+/// and emitter currents sum to zero, but an element that stores or sources flux need not.
+pub type TerminalFluxCalculation = fn (&[Rc<RefCell<GenericNode>>], &Matrix<f64>) -> anyhow::Result<Vec<Matrix<f64>>>;
+
+/// The function signature used to derive further quantities from a solved element, 
+/// e.g. the power dissipated by a resistor.
+/// 
+/// These are given the element's flux (as returned by `GenericElement::get_flux`), the potential
+/// of each node it connects (its input and output, or each terminal in order) and its gain, and 
+/// return each derived quantity by name. They are only run once a model is solved, so unlike a 
+/// `FluxCalculation` they do not need to be cheap or well-behaved far from the solution.
+pub type PostProcessor = fn (&Matrix<f64>, &[Matrix<f64>], &Matrix<f64>) -> HashMap<String, f64>;
+
+/// A quantity elsewhere in a model that a controlled element's flux depends on, 
+/// e.g. the voltage across a pair of nodes sensed by a voltage-controlled source.
+/// 
+/// # Variants:
+/// - `Potential` - senses the potential of the first node minus that of the second
+/// - `Flux` - senses the flux through another element
+#[derive(Clone, Debug)]
+pub enum ElementSensor
+{
+    Potential(Weak<RefCell<GenericNode>>, Weak<RefCell<GenericNode>>),
+    Flux(Weak<GenericElement>),
+}
+impl ElementSensor
+{
+    /// Returns the sensed quantity.
+    fn read(&self) -> anyhow::Result<Matrix<f64>>
+    {
+        match self
+        {
+            ElementSensor::Potential(a, b) => Ok(&get_node_potential(a)? - &get_node_potential(b)?),
+            ElementSensor::Flux(element) => match element.upgrade()
+            {
+                Some(element) => element.get_flux(),
+                None => Err(FluxCalculationError::SensorRefsAlreadyDropped.into()),
+            },
+        }
+    }
+}
+
+/// The nodes an element connects and the flux calculation it uses for them.
+#[derive(Clone, Debug)]
+enum Terminals
+{
+    Two
+    {
+        input_node: Weak<RefCell<GenericNode>>,
+        output_node: Weak<RefCell<GenericNode>>,
+        flux_calc: FluxCalculation,
+        drives_output: bool,
+    },
+    Many
+    {
+        nodes: Vec<Weak<RefCell<GenericNode>>>,
+        flux_calc: TerminalFluxCalculation,
+    },
+}
+
 /// A struct representing an element in a nodal analysis problem.
 /// 
 /// # Concept:
@@ -65,14 +143,16 @@ pub type FluxCalculation = fn (Rc<RefCell<GenericNode>>, Rc<RefCell<GenericNode>
 /// but **must** operate only on knowing which nodes they connect, their
 /// own gain value (e.g. resistance, voltage, and current, respectively in this case), 
 /// and which node's potential they drive, if they drive one at all.
+/// 
+/// Most elements have an input and an output terminal, but elements may also have 
+/// any number of terminals (see `GenericElement::try_new_with_terminals`).
 #[derive(Clone, Debug)]
 pub struct GenericElement
 {
-    gain: Matrix<f64>,
-    input_node: Weak<RefCell<GenericNode>>,
-    output_node: Weak<RefCell<GenericNode>>,
-    flux_calc: FluxCalculation,
-    drives_output: bool,
+    gain: RefCell<Matrix<f64>>,
+    terminals: Terminals,
+    sensor: RefCell<Option<ElementSensor>>,
+    post_process: RefCell<Option<PostProcessor>>,
 }
 impl GenericElement
 {
@@ -129,11 +209,16 @@ impl GenericElement
         let elem = Rc::new(
             GenericElement 
             {
-                gain: Matrix::from_col_vec(gain), 
-                input_node: Weak::clone(&input_node), 
-                output_node: Weak::clone(&output_node), 
-                flux_calc, 
-                drives_output 
+                gain: RefCell::new(Matrix::from_col_vec(gain)), 
+                terminals: Terminals::Two
+                {
+                    input_node: Weak::clone(&input_node), 
+                    output_node: Weak::clone(&output_node), 
+                    flux_calc, 
+                    drives_output,
+                },
+                sensor: RefCell::new(None),
+                post_process: RefCell::new(None),
             }
         );
 
@@ -160,15 +245,132 @@ impl GenericElement
         }
     }
 
+    /// Attempts to construct a new `GenericElement` with a terminal connected to each of 
+    /// `nodes`, possibly returning a `DroppedNodeError` if any of them were previously dropped.
+    /// This method is intended for use in a `TerminalElementConstructor`-compatible function.
+    /// 
+    /// Every node is made aware of the element, and each node's flux balance includes
+    /// the flux that `flux_calc` gives for that node's terminal.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::rc::Rc;
+    /// use neapolitan::{set_node_potential, Matrix};
+    /// use neapolitan::modelling::{GenericElement, GenericNode};
+    /// 
+    /// // Each terminal conducts towards the average potential of all three nodes
+    /// let nodes = [GenericNode::new(), GenericNode::new(), GenericNode::new()];
+    /// for (node, v) in nodes.iter().zip([3.0, 0.0, 0.0])
+    /// {
+    ///     set_node_potential(&Rc::downgrade(node), vec![v]).unwrap();
+    /// }
+    /// 
+    /// let elem = GenericElement::try_new_with_terminals(
+    ///     vec![1.0],
+    ///     nodes.iter().map(Rc::downgrade).collect(),
+    ///     |nodes, gain| {
+    ///         let v: Vec<f64> = nodes.iter().map(|n| n.borrow().get_potential()[(0, 0)]).collect();
+    ///         let mean = v.iter().sum::<f64>() / v.len() as f64;
+    ///         Ok(v.iter().map(|x| Matrix::from_col_vec(vec![gain[(0, 0)] * (x - mean)])).collect())
+    ///     },
+    /// ).unwrap();
+    /// 
+    /// assert_eq!(Vec::from(elem.get_terminal_flux(0).unwrap()), vec![2.0]);
+    /// assert_eq!(Vec::from(elem.get_flux().unwrap()), vec![2.0, -1.0, -1.0]);
+    /// ```
+    pub fn try_new_with_terminals(gain: Vec<f64>,
+        nodes: Vec<Weak<RefCell<GenericNode>>>,
+        flux_calc: TerminalFluxCalculation,
+    ) -> anyhow::Result<Rc<GenericElement>>
+    {
+        let elem = Rc::new(
+            GenericElement 
+            {
+                gain: RefCell::new(Matrix::from_col_vec(gain)), 
+                terminals: Terminals::Many { nodes: nodes.clone(), flux_calc },
+                sensor: RefCell::new(None),
+                post_process: RefCell::new(None),
+            }
+        );
+
+        // Make nodes aware of element
+        for (terminal, node) in nodes.iter().enumerate()
+        {
+            match node.upgrade()
+            {
+                Some(node) => node.try_borrow_mut()?.terminals.push((Rc::clone(&elem), terminal)),
+                None => return Err(DroppedNodeError.into()),
+            }
+        }
+
+        Ok(elem)
+    }
+
+    /// Returns the gain given to this element's flux calculation, 
+    /// with the sensed quantity appended if it has a sensor.
+    fn get_calculation_gain(&self) -> anyhow::Result<Matrix<f64>>
+    {
+        let gain = self.gain.try_borrow()?.clone();
+        match &*self.sensor.try_borrow()?
+        {
+            Some(sensor) => 
+            {
+                let mut gain = Vec::from(gain);
+                gain.extend(Vec::from(sensor.read()?));
+                Ok(Matrix::from_col_vec(gain))
+            },
+            None => Ok(gain),
+        }
+    }
+
+    /// Calculates the flux flowing into this element from each of its terminals. Elements with an
+    /// input and output terminal have their flux flow in through the input and out through the output.
+    pub fn get_terminal_fluxes(&self) -> anyhow::Result<Vec<Matrix<f64>>>
+    {
+        match &self.terminals
+        {
+            Terminals::Two { .. } => 
+            {
+                let flux = self.get_flux()?;
+                let mut out = flux.clone();
+                out.inplace_scale(-1.0);
+                Ok(vec![flux, out])
+            },
+            Terminals::Many { nodes, flux_calc } => 
+            {
+                let nodes = match nodes.iter().map(Weak::upgrade).collect::<Option<Vec<_>>>()
+                {
+                    Some(nodes) => nodes,
+                    None => return Err(FluxCalculationError::NodeRefsAlreadyDropped.into()),
+                };
+                flux_calc(&nodes, &self.get_calculation_gain()?)
+            },
+        }
+    }
+
+    /// Calculates the flux flowing into this element from its `terminal`th terminal.
+    pub fn get_terminal_flux(&self, terminal: usize) -> anyhow::Result<Matrix<f64>>
+    {
+        match self.get_terminal_fluxes()?.into_iter().nth(terminal)
+        {
+            Some(flux) => Ok(flux),
+            None => Err(FluxCalculationError::MissingTerminalFlux.into()),
+        }
+    }
+
     /// Calculates the flux for this element by calling the `FluxCalculation` function pointer
-    /// it owns.
+    /// it owns. If the element has a sensor (see `set_sensor`), the sensed quantity is 
+    /// appended to the gain given to the `FluxCalculation`.
+    /// 
+    /// For elements with any number of terminals, this is the flux into each terminal 
+    /// (see `get_terminal_fluxes`), stacked in the order of the terminals.
     /// 
     /// # Example
     /// ```
     /// use std::rc::Rc;
     /// use neapolitan::set_node_potential;
     /// use neapolitan::modelling::{GenericElement, GenericNode};
-    /// use neapolitan::ssdc_circuits::resistor;
+    /// use neapolitan::dc_circuits::resistor;
     /// 
     /// let a = GenericNode::new();
     /// let b = GenericNode::new();
@@ -184,13 +386,117 @@ impl GenericElement
     /// ```
     pub fn get_flux(&self) -> anyhow::Result<Matrix<f64>>
     {
-        if let (Some(inode), Some(onode)) = (self.input_node.upgrade(), self.output_node.upgrade())
+        let (input_node, output_node, flux_calc, drives_output) = match &self.terminals
         {
-            Ok((self.flux_calc)(inode, onode, &self.gain, self.drives_output)?)
+            Terminals::Two { input_node, output_node, flux_calc, drives_output } => (input_node, output_node, flux_calc, *drives_output),
+            Terminals::Many { .. } => 
+            {
+                let fluxes: Vec<f64> = self.get_terminal_fluxes()?.into_iter().flat_map(Vec::from).collect();
+                return Ok(Matrix::from_col_vec(fluxes));
+            },
+        };
+
+        if let (Some(inode), Some(onode)) = (input_node.upgrade(), output_node.upgrade())
+        {
+            if self.sensor.try_borrow()?.is_none()
+            {
+                return flux_calc(inode, onode, &*self.gain.try_borrow()?, drives_output);
+            }
+
+            flux_calc(inode, onode, &self.get_calculation_gain()?, drives_output)
         }
         else
         {
             Err(FluxCalculationError::NodeRefsAlreadyDropped.into())
         }
     }
-}
\ No newline at end of file
+
+    /// Makes this element a controlled element, whose flux depends on the quantity read 
+    /// by `sensor` as well as its own gain. This is done after the element is constructed,
+    /// as the sensed nodes or element may not exist when it is created.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::rc::Rc;
+    /// use neapolitan::set_node_potential;
+    /// use neapolitan::modelling::GenericNode;
+    /// use neapolitan::modelling::element::ElementSensor;
+    /// use neapolitan::dc_circuits::voltage_controlled_current_source;
+    /// 
+    /// let (a, b, c) = (GenericNode::new(), GenericNode::new(), GenericNode::new());
+    /// set_node_potential(&Rc::downgrade(&c), vec![3.0]).unwrap();
+    /// 
+    /// // Sources 2 A per volt of potential at `c` above `a`
+    /// let elem = voltage_controlled_current_source(Rc::downgrade(&a), Rc::downgrade(&b), vec![2.0]).unwrap();
+    /// elem.set_sensor(ElementSensor::Potential(Rc::downgrade(&c), Rc::downgrade(&a))).unwrap();
+    /// 
+    /// assert_eq!(vec![4.0], Vec::from(elem.get_flux().unwrap()));
+    /// ```
+    pub fn set_sensor(&self, sensor: ElementSensor) -> anyhow::Result<()>
+    {
+        *self.sensor.try_borrow_mut()? = Some(sensor);
+        Ok(())
+    }
+
+    /// Gives this element a `PostProcessor` that derives further quantities from its 
+    /// solved state, which are reported alongside its flux in a study's results.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::rc::Rc;
+    /// use neapolitan::set_node_potential;
+    /// use neapolitan::modelling::GenericNode;
+    /// use neapolitan::dc_circuits::current_source;
+    /// 
+    /// let (a, b) = (GenericNode::new(), GenericNode::new());
+    /// set_node_potential(&Rc::downgrade(&a), vec![3.0]).unwrap();
+    /// set_node_potential(&Rc::downgrade(&b), vec![0.0]).unwrap();
+    /// 
+    /// // Report the flux in milliamps as well as amps
+    /// let elem = current_source(Rc::downgrade(&a), Rc::downgrade(&b), vec![2.0]).unwrap();
+    /// elem.set_post_process(|flux, _, _| [("milliamps".to_string(), 1000.0 * flux[(0, 0)])].into()).unwrap();
+    /// 
+    /// let derived = elem.get_derived_quantities().unwrap();
+    /// assert_eq!(derived["milliamps"], 2000.0);
+    /// ```
+    pub fn set_post_process(&self, post_process: PostProcessor) -> anyhow::Result<()>
+    {
+        *self.post_process.try_borrow_mut()? = Some(post_process);
+        Ok(())
+    }
+
+    /// Runs this element's `PostProcessor` (see `set_post_process`) on its current state, 
+    /// returning each derived quantity by name. Elements without one derive nothing.
+    pub fn get_derived_quantities(&self) -> anyhow::Result<HashMap<String, f64>>
+    {
+        let post_process = match *self.post_process.try_borrow()?
+        {
+            Some(post_process) => post_process,
+            None => return Ok(HashMap::new()),
+        };
+
+        let potentials = match &self.terminals
+        {
+            Terminals::Two { input_node, output_node, .. } => vec![get_node_potential(input_node)?, get_node_potential(output_node)?],
+            Terminals::Many { nodes, .. } => nodes.iter().map(get_node_potential).collect::<anyhow::Result<_>>()?,
+        };
+
+        Ok(post_process(&self.get_flux()?, &potentials, &*self.gain.try_borrow()?))
+    }
+
+    /// Returns the number of terminals this element has.
+    pub fn get_terminal_count(&self) -> usize
+    {
+        match &self.terminals
+        {
+            Terminals::Two { .. } => 2,
+            Terminals::Many { nodes, .. } => nodes.len(),
+        }
+    }
+
+    /// Returns `true` if this element has a sensor.
+    pub fn is_controlled(&self) -> anyhow::Result<bool>
+    {
+        Ok(self.sensor.try_borrow()?.is_some())
+    }
+}
@@ -1,22 +1,44 @@
+pub mod arena;
 pub mod element;
 pub mod node;
 
 /// Std modules
 use std::{collections::HashMap, str::FromStr, usize};
+use std::fmt::Write;
 
 /// 3rd party modules
 use serde::de::{Deserialize, IntoDeserializer, value};
 
 /// Local modules
+use crate::default_study_builder_config;
+use crate::errors::EquationExportError;
+use crate::dc_circuits::*;
+use crate::heat_transfer::*;
 pub use element::GenericElement;
 pub use node::GenericNode;
 
+/// Identifies the quantity sensed by a controlled element, e.g. a voltage-controlled voltage source.
+/// 
+/// # Variants:
+/// - `Potential` - the potential of the `input` node minus that of the `output` node
+/// - `Flux` - the flux through the element at index `element` (in the order elements were added)
+#[derive(Clone, Copy, Debug, serde::Deserialize, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ElementControl
+{
+    Potential { input: usize, output: usize },
+    Flux { element: usize },
+}
+
 /// Represents an element in a nodal analysis problem.
 /// 
 /// # Fields:
 /// - `element_type` - the kind of element that should be added in the model
 /// - `input`, `output` - the nodes to connect to the element's input and output ports, respectively
 /// - `gain` - the element's gain value expressed as a list of values
+/// - `control` - the quantity sensed by a controlled element, if it is one
+/// - `terminals` - the nodes connected to each terminal of an element with any number of 
+///   terminals, in order. This is empty for elements with only an input and output.
 #[derive(Clone, Debug, serde::Deserialize, PartialEq, serde::Serialize)]
 pub struct NodalAnalysisElement
 {
@@ -24,22 +46,75 @@ pub struct NodalAnalysisElement
     pub (in crate) input: usize,
     pub (in crate) output: usize,
     pub (in crate) gain: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub (in crate) control: Option<ElementControl>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub (in crate) terminals: Vec<usize>,
 }
 
 /// Represents nodal metadata that should be set during the model's configuration stage
 /// 
 /// # Fields:
-/// - `node` - the node to set the metadata value for
+/// - `potential` - the fixed potential of a locked node, or the initial guess for an unlocked one
+/// - `is_locked` - whether the node's potential is fixed rather than solved for
 /// - `metadata` - the serde-friendly metadata to set for the node
+/// - `coordinates` - where the node is in space as `[x, y, z]`, if it was placed with `place_node`
+/// 
+/// Models saved before `coordinates` existed placed nodes with `x`, `y` and `z` metadata 
+/// instead, so those are read as the node's coordinates (with any missing axis at zero) 
+/// when `coordinates` is absent.
 #[derive(Clone, Debug, serde::Deserialize, PartialEq, serde::Serialize)]
+#[serde(from = "StoredNodalMetadata")]
 pub struct NodalMetadata
 {
     pub (in crate) potential: Vec<f64>,
     pub (in crate) is_locked: bool,
     pub (in crate) metadata: Option<HashMap<String, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub (in crate) coordinates: Option<[f64; 3]>,
+}
+
+/// `NodalMetadata` as it is stored, before any legacy coordinates are read from its metadata
+#[derive(serde::Deserialize)]
+struct StoredNodalMetadata
+{
+    potential: Vec<f64>,
+    is_locked: bool,
+    metadata: Option<HashMap<String, f64>>,
+    #[serde(default)]
+    coordinates: Option<[f64; 3]>,
+}
+
+impl From<StoredNodalMetadata> for NodalMetadata
+{
+    fn from(stored: StoredNodalMetadata) -> Self
+    {
+        let StoredNodalMetadata { potential, is_locked, metadata, coordinates } = stored;
+
+        let legacy_coordinates = metadata.as_ref().and_then(|m| 
+        {
+            let axes = ["x", "y", "z"].map(|axis| m.get(axis).copied());
+            match axes.iter().any(Option::is_some)
+            {
+                true => Some(axes.map(|a| a.unwrap_or(0.0))),
+                false => None,
+            }
+        });
+
+        NodalMetadata { potential, is_locked, metadata, coordinates: coordinates.or(legacy_coordinates) }
+    }
 }
 
 /// Represents an entire nodal analysis problem
+/// 
+/// # Fields:
+/// - `model_type` - the configuration used to build the model's elements
+/// - `nodes` - the number of nodes in the model
+/// - `configuration` - the metadata of each node that is not left at its default state
+/// - `elements` - the elements of the model, in the order they were added
+/// - `node_names` - the names given to nodes, e.g. the internal nodes of a subcircuit
+/// - `element_labels` - the labels given to elements, e.g. `"R1"`
+/// - `node_domains` - the model type of each node added to another domain than `model_type`
 #[derive(Clone, Debug, serde::Deserialize, PartialEq, serde::Serialize)]
 pub struct NodalAnalysisModel 
 {
@@ -47,8 +122,269 @@ pub struct NodalAnalysisModel
     pub (in crate) nodes: usize,
     pub (in crate) configuration: HashMap<usize, NodalMetadata>,
     pub (in crate) elements: Vec<NodalAnalysisElement>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub (in crate) node_names: HashMap<usize, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub (in crate) element_labels: HashMap<usize, String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub (in crate) node_domains: HashMap<usize, String>,
 }
-impl NodalAnalysisModel {}
+impl NodalAnalysisModel 
+{
+    /// Returns the name of the node at index `node` in study results, 
+    /// which is its name if it was given one or its index otherwise.
+    pub fn get_node_name(&self, node: usize) -> String
+    {
+        match self.node_names.get(&node)
+        {
+            Some(name) => name.clone(),
+            None => node.to_string(),
+        }
+    }
+
+    /// Returns the name of the element at index `element` in study results, which is its 
+    /// label if it was given one or `"<element_type>.<element_index>"` otherwise.
+    pub fn get_element_name(&self, element: usize) -> String
+    {
+        match self.element_labels.get(&element)
+        {
+            Some(label) => label.clone(),
+            None => format!("{}.{element}", self.elements[element].element_type),
+        }
+    }
+
+    /// Returns the model type of the domain that the node at index `node` was added to,
+    /// which is the model's own `model_type` unless it was added with `add_domain_nodes`.
+    pub fn get_node_domain(&self, node: usize) -> &str
+    {
+        match self.node_domains.get(&node)
+        {
+            Some(domain) => domain,
+            None => &self.model_type,
+        }
+    }
+
+    /// Returns where the node at index `node` is in space as `[x, y, z]`, 
+    /// if it was placed with `NodalAnalysisStudyBuilder::place_node`.
+    pub fn get_node_coordinates(&self, node: usize) -> Option<[f64; 3]>
+    {
+        self.configuration.get(&node).and_then(|node_data| node_data.coordinates)
+    }
+
+    /// Returns the index of the node with the given name, if there is one.
+    pub fn get_node_index(&self, name: &str) -> Option<usize>
+    {
+        self.node_names.iter().find(|(_, x)| *x == name).map(|(&idx, _)| idx)
+    }
+
+    /// Returns the index of the element with the given label, if there is one.
+    pub fn get_element_index(&self, label: &str) -> Option<usize>
+    {
+        self.element_labels.iter().find(|(_, x)| *x == label).map(|(&idx, _)| idx)
+    }
+
+    /// Writes out the balance equations that a study of this model solves as a
+    /// nexsys equation deck. The potential of node `i` is named `node_i`, and the 
+    /// flux through element `j` is named `<element_type>_j`. Each element contributes 
+    /// the equation relating its flux to its nodal potentials, each locked node is 
+    /// pinned to its configured potential, and every other node gets a flux balance.
+    /// 
+    /// Only the single-component `dc_circuit` and `heat_transfer` models are supported.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 0, vec![2.0]).unwrap();
+    /// 
+    /// let deck = builder.get_model().to_equation_system().unwrap();
+    /// 
+    /// assert!(deck.contains("node_1 - node_0 = 3"));
+    /// assert!(deck.contains("resistor_1 = (node_1 - node_0) / 2"));
+    /// ```
+    pub fn to_equation_system(&self) -> anyhow::Result<String>
+    {
+        if self.model_type != DC_CIRCUIT && self.model_type != HEAT_TRANSFER
+        {
+            return Err(EquationExportError::UnsupportedModelType(self.model_type.clone()).into());
+        }
+
+        let mut deck = format!("// {} model exported from neapolitan\n", self.model_type);
+        let mut inflows = vec![vec![]; self.nodes];
+        let mut outflows = vec![vec![]; self.nodes];
+
+        deck.push_str("\n// Element equations\n");
+        for (idx, NodalAnalysisElement { element_type, input, output, gain, control, .. }) in self.elements.iter().enumerate()
+        {
+            let flux = format!("{element_type}_{idx}");
+            let (i, o) = (format!("node_{input}"), format!("node_{output}"));
+
+            // The name of the quantity sensed by a controlled element
+            let sensed = match control
+            {
+                Some(ElementControl::Potential { input, output }) => format!("(node_{input} - node_{output})"),
+                Some(ElementControl::Flux { element }) => match self.elements.get(*element)
+                {
+                    Some(sensed) => format!("{}_{element}", sensed.element_type),
+                    None => return Err(EquationExportError::UnsupportedElementType(element_type.clone()).into()),
+                },
+                None => String::new(),
+            };
+
+            let equation = match (element_type.as_str(), gain.as_slice())
+            {
+                (VOLTAGE_CONTROLLED_VOLTAGE_SOURCE | CURRENT_CONTROLLED_VOLTAGE_SOURCE, [k]) if control.is_some() => format!("{o} - {i} = {k} * {sensed}"),
+                (VOLTAGE_CONTROLLED_CURRENT_SOURCE | CURRENT_CONTROLLED_CURRENT_SOURCE, [k]) if control.is_some() => format!("{flux} = {k} * {sensed}"),
+                (RESISTOR, [r]) => format!("{flux} = ({i} - {o}) / {r}"),
+                (CONDUCTOR, [l, k]) => format!("{flux} = {k} / {l} * ({i} - {o})"),
+                (GEOMETRIC_CONDUCTOR, [k]) =>
+                {
+                    let l = match (self.get_node_coordinates(*input), self.get_node_coordinates(*output))
+                    {
+                        (Some(a), Some(b)) => a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt(),
+                        (None, _) => return Err(EquationExportError::MissingCoordinates(*input).into()),
+                        (_, None) => return Err(EquationExportError::MissingCoordinates(*output).into()),
+                    };
+                    format!("{flux} = {k} / {l} * ({i} - {o})")
+                },
+                (CONDUCTOR | CONVECTION_INTERFACE, [g]) => format!("{flux} = {g} * ({i} - {o})"),
+                (RADIATION_INTERFACE, [e]) => format!("{flux} = {e} * {STEFAN_BOLTZMANN} * ({i}^4 - {o}^4)"),
+                (RADIATION_INTERFACE, [e, a]) => format!("{flux} = {e} * {STEFAN_BOLTZMANN} * {a} * ({i}^4 - {o}^4)"),
+                (CONVECTION_RADIATION_INTERFACE, [h, e]) => format!("{flux} = {h} * ({i} - {o}) + {e} * {STEFAN_BOLTZMANN} * ({i}^4 - {o}^4)"),
+                (CONVECTION_RADIATION_INTERFACE, [h, e, a]) => format!("{flux} = {a} * ({h} * ({i} - {o}) + {e} * {STEFAN_BOLTZMANN} * ({i}^4 - {o}^4))"),
+                (THERMAL_INTERFACE, [a, layers @ ..]) if !layers.is_empty() && layers.len() % 3 == 0 =>
+                {
+                    let resistances: Vec<String> = layers.chunks(3).map(|l| format!("{} / {} + {}", l[0], l[1], l[2])).collect();
+                    format!("{flux} = {a} / ({}) * ({i} - {o})", resistances.join(" + "))
+                },
+                (DIODE, [i_s, v_t]) => format!("{flux} = {i_s} * (e^(({i} - {o}) / {v_t}) - 1)"),
+                (VOLTAGE_SOURCE | TEMPERATURE_DELTA, [dv]) => format!("{o} - {i} = {dv}"),
+                (CURRENT_SOURCE | HEAT_FLUX | HEAT_SOURCE, [q]) => format!("{flux} = {q}"),
+                _ => return Err(EquationExportError::UnsupportedElementType(element_type.clone()).into()),
+            };
+            writeln!(deck, "{equation}")?;
+
+            // Generated heat is not drawn from the input node
+            if element_type != HEAT_SOURCE
+            {
+                outflows[*input].push(flux.clone());
+            }
+            inflows[*output].push(flux);
+        }
+
+        deck.push_str("\n// Node equations\n");
+        for node in 0..self.nodes
+        {
+            match self.configuration.get(&node)
+            {
+                Some(NodalMetadata { potential, is_locked: true, .. }) =>
+                {
+                    writeln!(deck, "node_{node} = {}", potential.first().copied().unwrap_or(0.0))?;
+                },
+                _ =>
+                {
+                    if inflows[node].is_empty() && outflows[node].is_empty()
+                    {
+                        continue;
+                    }
+
+                    writeln!(deck, "{} = {}", sum_of(&inflows[node]), sum_of(&outflows[node]))?;
+                },
+            }
+        }
+
+        Ok(deck)
+    }
+
+    /// Returns a copy of the model that can be shared (e.g. attached to a bug report) without
+    /// revealing anything beyond what is needed to reproduce a study of it. Nodal metadata, node
+    /// names and element labels are removed, and any element type that is not built into 
+    /// neapolitan for this model type is renamed to `custom_element_<n>`, numbered in the order 
+    /// each type first appears. Model types that are not built into neapolitan are renamed to 
+    /// `custom_model`.
+    /// 
+    /// The model's topology, gains, controls, nodal potentials and node coordinates are unchanged, so a model
+    /// using only built-in element types solves exactly as the original does, although its
+    /// results are keyed by node index and element type rather than by name.
+    /// 
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// 
+    /// let model = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, Some(HashMap::from([("secret_rail".to_string(), 1.0)])))
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 0, vec![2.0]).unwrap()
+    ///     .get_model()
+    ///     .anonymize();
+    /// 
+    /// let json = serde_json::to_string(&model).unwrap();
+    /// assert!(!json.contains("secret_rail"));
+    /// assert!(json.contains(RESISTOR));
+    /// ```
+    pub fn anonymize(&self) -> NodalAnalysisModel
+    {
+        let defaults = default_study_builder_config();
+        let is_built_in = |element_type: &str| match defaults.get(&self.model_type)
+        {
+            Some(config) => config.has_element_type(element_type),
+            None => false,
+        };
+
+        let mut model = self.clone();
+        if !defaults.contains_key(&model.model_type)
+        {
+            model.model_type = "custom_model".to_string();
+        }
+
+        model.node_names.clear();
+        model.element_labels.clear();
+        for node_data in model.configuration.values_mut()
+        {
+            node_data.metadata = None;
+        }
+
+        let mut renamed: Vec<String> = vec![];
+        for element in model.elements.iter_mut().filter(|x| !is_built_in(&x.element_type))
+        {
+            let n = match renamed.iter().position(|x| *x == element.element_type)
+            {
+                Some(n) => n,
+                None => 
+                {
+                    renamed.push(element.element_type.clone());
+                    renamed.len() - 1
+                },
+            };
+            element.element_type = format!("custom_element_{n}");
+        }
+
+        model
+    }
+}
+/// Joins flux variable names into a sum, or `0` if there are none.
+fn sum_of(fluxes: &[String]) -> String
+{
+    if fluxes.is_empty()
+    {
+        "0".to_string()
+    }
+    else
+    {
+        fluxes.join(" + ")
+    }
+}
+
 impl FromStr for NodalAnalysisModel
 {
     type Err = value::Error;
@@ -33,8 +33,10 @@ pub struct GenericNode
     pub (in crate) potential: Matrix<f64>,
     pub (in crate) inputs: Vec<Rc<GenericElement>>,
     pub (in crate) outputs: Vec<Rc<GenericElement>>,
+    pub (in crate) terminals: Vec<(Rc<GenericElement>, usize)>,
     pub (in crate) is_locked: bool,
     pub (in crate) _metadata: Option<HashMap<String, f64>>, 
+    pub (in crate) coordinates: Option<[f64; 3]>,
 }
 impl GenericNode
 {
@@ -46,11 +48,25 @@ impl GenericNode
             potential: col_vec![1f64],
             inputs: vec![],
             outputs: vec![],
+            terminals: vec![],
             is_locked: false,
             _metadata: None,
+            coordinates: None,
         }))
     }
 
+    /// Returns the node's potential.
+    pub fn get_potential(&self) -> &Matrix<f64>
+    {
+        &self.potential
+    }
+
+    /// Returns where the node is in space as `[x, y, z]`, if it was placed.
+    pub fn get_coordinates(&self) -> Option<[f64; 3]>
+    {
+        self.coordinates
+    }
+
     pub fn get_flux_discrepancy(&self) -> anyhow::Result<Matrix<f64>>
     {
         let mut inputs = Matrix::new(
@@ -70,6 +86,12 @@ impl GenericNode
             outputs += elem.get_flux()?;
         }
 
+        // Elements with many terminals give the flux into each of them
+        for (elem, terminal) in &self.terminals
+        {
+            outputs += elem.get_terminal_flux(*terminal)?;
+        }
+
         let discrepancy = inputs - outputs;
         Ok(discrepancy)
     } 
@@ -0,0 +1,260 @@
+// Std modules
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+
+// 3rd party modules
+use serde::Serialize;
+
+// Local modules
+use crate::errors::MonteCarloError;
+use crate::{NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// How an element's gain varies from sample to sample, relative to the gain
+/// it was added to the model with. Every component of the gain varies independently.
+///
+/// # Variants:
+/// - `Uniform` - each component is drawn uniformly from within `tolerance` (a fraction,
+///   e.g. `0.05` for a 5% resistor) of its nominal value
+/// - `Normal` - each component is drawn from a normal distribution about its nominal
+///   value with a standard deviation of `sigma` (a fraction of the nominal value)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum GainDistribution
+{
+    Uniform { tolerance: f64 },
+    Normal { sigma: f64 },
+}
+
+/// The statistics of a single component of a potential or flux across every sample solved.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct SampleStatistics
+{
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Running statistics of a single component, updated one sample at a time with welford's method.
+#[derive(Clone, Copy, Debug, Default)]
+struct Accumulator
+{
+    count: usize,
+    mean: f64,
+    sum_sq: f64,
+    min: f64,
+    max: f64,
+}
+impl Accumulator
+{
+    fn add(&mut self, x: f64)
+    {
+        if self.count == 0
+        {
+            self.min = x;
+            self.max = x;
+        }
+        self.count += 1;
+
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.sum_sq += delta * (x - self.mean);
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    fn statistics(&self) -> SampleStatistics
+    {
+        let std_dev = match self.count
+        {
+            0 | 1 => 0.0,
+            n => (self.sum_sq / (n - 1) as f64).sqrt(),
+        };
+        SampleStatistics { mean: self.mean, std_dev, min: self.min, max: self.max }
+    }
+}
+
+/// The results of a monte carlo tolerance analysis.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MonteCarloResult
+{
+    samples: usize,
+    nodes: HashMap<String, Vec<SampleStatistics>>,
+    elements: HashMap<String, Vec<SampleStatistics>>,
+}
+impl MonteCarloResult
+{
+    /// Returns the number of samples that were solved.
+    pub fn get_samples(&self) -> usize
+    {
+        self.samples
+    }
+
+    /// Returns the statistics of each component of the given node's potential. Nodes are
+    /// named the same way as in a study's results, i.e. by their name or else their index.
+    pub fn get_node_statistics(&self, node: &str) -> Option<&Vec<SampleStatistics>>
+    {
+        self.nodes.get(node)
+    }
+
+    /// Returns the statistics of each component of the given element's flux. Elements are named
+    /// the same way as in a study's results, i.e. by their label or else `"<element_type>.<element_index>"`.
+    pub fn get_element_statistics(&self, element: &str) -> Option<&Vec<SampleStatistics>>
+    {
+        self.elements.get(element)
+    }
+}
+
+/// A small, seedable random number generator (splitmix64), so
+/// that an analysis can be reproduced exactly from its seed.
+struct SampleGenerator
+{
+    state: u64,
+}
+impl SampleGenerator
+{
+    fn next_u64(&mut self) -> u64
+    {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value uniformly distributed on `[0, 1)`.
+    fn uniform(&mut self) -> f64
+    {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a value from the standard normal distribution (by the box-muller transform).
+    fn normal(&mut self) -> f64
+    {
+        let u = 1.0 - self.uniform();
+        let v = self.uniform();
+        (-2.0 * u.ln()).sqrt() * (TAU * v).cos()
+    }
+
+    /// Draws a value about `nominal` from the given distribution.
+    fn sample(&mut self, nominal: f64, distribution: GainDistribution) -> f64
+    {
+        match distribution
+        {
+            GainDistribution::Uniform { tolerance } => nominal * (1.0 + tolerance * (2.0 * self.uniform() - 1.0)),
+            GainDistribution::Normal { sigma } => nominal * (1.0 + sigma * self.normal()),
+        }
+    }
+}
+
+fn accumulate(stats: &mut HashMap<String, Vec<Accumulator>>, values: &HashMap<String, Vec<f64>>)
+{
+    for (name, values) in values
+    {
+        let acc = stats.entry(name.clone()).or_default();
+        acc.resize(values.len(), Accumulator::default());
+        for (acc, &x) in acc.iter_mut().zip(values)
+        {
+            acc.add(x);
+        }
+    }
+}
+
+fn summarize(stats: HashMap<String, Vec<Accumulator>>) -> HashMap<String, Vec<SampleStatistics>>
+{
+    stats.into_iter()
+        .map(|(name, acc)| (name, acc.iter().map(Accumulator::statistics).collect()))
+        .collect()
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Solves the model `samples` times, each time drawing the gains of the elements in
+    /// `distributions` (keyed by element index, in the order elements were added) about
+    /// their nominal values, and reports the mean, standard deviation, minimum and maximum
+    /// of every nodal potential and elemental flux. Elements that are not given a
+    /// distribution keep their nominal gain.
+    ///
+    /// The samples are drawn from a generator seeded with `seed`, so running the same
+    /// analysis with the same seed reproduces the same results.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::monte_carlo::GainDistribution;
+    ///
+    /// // A divider made of 5% resistors
+    /// let result = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .run_monte_carlo(
+    ///         HashMap::from([
+    ///             (1, GainDistribution::Uniform { tolerance: 0.05 }),
+    ///             (2, GainDistribution::Uniform { tolerance: 0.05 }),
+    ///         ]),
+    ///         500, 42,
+    ///         1E-9, 100
+    ///     )
+    ///     .unwrap();
+    ///
+    /// let v2 = result.get_node_statistics("2").unwrap()[0];
+    /// assert!((v2.mean - 5.0).abs() < 0.05);
+    /// assert!(v2.min >= 4.75 && v2.max <= 5.25);
+    /// ```
+    pub fn run_monte_carlo(self, distributions: HashMap<usize, GainDistribution>, samples: usize, seed: u64, margin: f64, limit: usize) -> anyhow::Result<MonteCarloResult>
+    {
+        if samples == 0
+        {
+            return Err(MonteCarloError::NoSamples.into());
+        }
+
+        // Visit elements in a fixed order so that a seed always gives the same samples
+        let mut nominal = vec![];
+        for (&idx, &distribution) in &distributions
+        {
+            let spread = match distribution
+            {
+                GainDistribution::Uniform { tolerance } => tolerance,
+                GainDistribution::Normal { sigma } => sigma,
+            };
+            if !(spread.is_finite() && spread >= 0.0)
+            {
+                return Err(MonteCarloError::InvalidDistribution.into());
+            }
+
+            match self.model.elements.get(idx)
+            {
+                Some(element) => nominal.push((idx, element.gain.clone(), distribution)),
+                None => return Err(MonteCarloError::ElementDoesNotExist.into()),
+            }
+        }
+        nominal.sort_by_key(|&(idx, _, _)| idx);
+
+        let mut generator = SampleGenerator { state: seed };
+        let mut nodes = HashMap::new();
+        let mut elements = HashMap::new();
+
+        // Only the gains change between samples, so the model is only built once
+        let mut study = self.compile()?;
+
+        for _ in 0..samples
+        {
+            for (idx, gain, distribution) in &nominal
+            {
+                let sample = gain.iter().map(|&x| generator.sample(x, *distribution)).collect();
+                study.set_gain(*idx, sample)?;
+            }
+
+            let NodalAnalysisStudyResult { nodes: potentials, elements: fluxes, .. } = study.solve(margin, limit)?;
+            accumulate(&mut nodes, &potentials);
+            accumulate(&mut elements, &fluxes);
+        }
+
+        Ok(MonteCarloResult { samples, nodes: summarize(nodes), elements: summarize(elements) })
+    }
+}
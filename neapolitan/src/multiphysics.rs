@@ -0,0 +1,207 @@
+// Std modules
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use gmatlib::Matrix;
+use thiserror::Error;
+
+// Local modules
+use crate::errors::FluxCalculationError;
+use crate::flux_formulas::absorbed_power;
+use crate::{GenericElement, GenericNode};
+
+#[derive(Debug, Error)]
+pub enum MultiphysicsElementError
+{
+    #[error("you must specify a positive reference resistance, a temperature coefficient and a reference temperature (in that order) to create a {0} element")]
+    ResistanceParameters(&'static str),
+    #[error("the resistance of a temperature-dependent element fell to {0} at a temperature of {1}. its temperature coefficient may be too large for the temperatures it reaches")]
+    ResistanceNotPositive(f64, f64),
+}
+
+pub const TEMPERATURE_DEPENDENT_RESISTOR: &str = "temperature_dependent_resistor";
+pub const JOULE_HEAT_SOURCE: &str = "joule_heat_source";
+
+/// Makes sure that a gain is a positive reference resistance, a temperature coefficient
+/// and a reference temperature (in that order).
+fn check_resistance(element: &'static str, gain: &[f64]) -> anyhow::Result<()>
+{
+    match *gain
+    {
+        [r_0, _, _] if r_0 > 0.0 => Ok(()),
+        _ => Err(MultiphysicsElementError::ResistanceParameters(element).into()),
+    }
+}
+
+/// Returns the resistance `r_0 * (1 + alpha * (t - t_0))` at a temperature of `t`,
+/// where the gain is `[r_0, alpha, t_0]`.
+fn resistance_at(gain: &Matrix<f64>, t: f64) -> anyhow::Result<f64>
+{
+    let r = gain[(0, 0)] * (1.0 + gain[(1, 0)] * (t - gain[(2, 0)]));
+    match r > 0.0
+    {
+        true => Ok(r),
+        false => Err(MultiphysicsElementError::ResistanceNotPositive(r, t).into()),
+    }
+}
+
+/// A flux calculation for resistors whose resistance depends on the temperature that they
+/// sense, i.e. `(i - o) / resistance_at(sensed)`.
+fn temperature_dependent_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    if gain.get_rows() < 4
+    {
+        return Err(FluxCalculationError::MissingControl.into());
+    }
+
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let mut deltas = &(inode.potential) - &(onode.potential);
+    deltas.inplace_scale(1.0 / resistance_at(gain, gain[(3, 0)])?);
+    Ok(deltas)
+}
+
+/// A flux calculation for the heat dissipated by a temperature-dependent resistor that carries
+/// the current it senses, i.e. `sensed^2 * resistance_at(o - i)`. The resistor's temperature
+/// is that of the output node, measured from the input node.
+fn joule_heat_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    if gain.get_rows() < 4
+    {
+        return Err(FluxCalculationError::MissingControl.into());
+    }
+
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let t = onode.potential[(0, 0)] - inode.potential[(0, 0)];
+    let current = gain[(3, 0)];
+    Ok(Matrix::from_col_vec(vec![current.powi(2) * resistance_at(gain, t)?]))
+}
+
+/// Represents a resistor in an electrical domain whose resistance changes linearly with its
+/// temperature, i.e. `r_0 * (1 + alpha * (t - t_0))`. The gain is given as the reference
+/// resistance `r_0`, the temperature coefficient `alpha` and the reference temperature `t_0`
+/// (in that order).
+///
+/// The temperature is the potential difference that the element senses, which is set with
+/// `NodalAnalysisStudyBuilder::add_controlled_element`. This is usually the potential of a
+/// node in a thermal domain (see `NodalAnalysisStudyBuilder::add_domain_nodes`) above a node
+/// locked at the temperature that `t_0` is measured from.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// use neapolitan::heat_transfer::*;
+/// use neapolitan::modelling::ElementControl;
+/// use neapolitan::multiphysics::*;
+///
+/// // A copper resistor held at 75 degrees C by a thermal node
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .add_domain_nodes(HEAT_TRANSFER, 2).unwrap()
+///     .configure_node(0, vec![0.0], true, None)
+///     .configure_node(2, vec![0.0], true, None)
+///     .configure_node(3, vec![75.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0]).unwrap()
+///     .add_controlled_element(
+///         TEMPERATURE_DEPENDENT_RESISTOR, 1, 0,
+///         vec![10.0, 0.00393, 25.0],
+///         ElementControl::Potential { input: 3, output: 2 }
+///     ).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+///
+/// let r = 10.0 * (1.0 + 0.00393 * 50.0);
+/// assert!((soln.get_element_flux(1).unwrap()[0] - 1.0 / r).abs() < 1E-9);
+/// ```
+pub fn temperature_dependent_resistor(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    resistance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    check_resistance(TEMPERATURE_DEPENDENT_RESISTOR, &resistance)?;
+
+    let elem = GenericElement::try_new(
+        resistance,
+        input_node, output_node,
+        temperature_dependent_flux,
+        false,
+        true, true,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents the heat dissipated in a thermal domain by the current through a resistor in
+/// an electrical domain, i.e. `current^2 * r_0 * (1 + alpha * (t - t_0))`. The gain is given
+/// as the resistor's reference resistance, temperature coefficient and reference temperature,
+/// exactly as for a `temperature_dependent_resistor` (a temperature coefficient of zero gives
+/// a fixed resistance).
+///
+/// The current is the flux of the resistor, which the element senses once it is added with
+/// `NodalAnalysisStudyBuilder::add_controlled_element`. Like a `heat_source`, the heat flows
+/// into the output node, which is usually the node whose temperature the resistor senses.
+/// The resistor's temperature is the output node's potential above the input node's.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::dc_circuits::*;
+/// use neapolitan::heat_transfer::*;
+/// use neapolitan::modelling::ElementControl;
+/// use neapolitan::multiphysics::*;
+///
+/// // A 12 V supply heats a resistor that is cooled to a 20 degree C ambient, while
+/// // its resistance rises with the temperature that the heating settles at
+/// let r = vec![4.0, 0.004, 20.0];
+/// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+///     .unwrap()
+///     .add_nodes(2)
+///     .add_domain_nodes(HEAT_TRANSFER, 3).unwrap()
+///     .configure_node(0, vec![0.0], true, None)
+///     .configure_node(2, vec![0.0], true, None)
+///     .configure_node(3, vec![20.0], true, None)
+///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![12.0]).unwrap()
+///     .add_controlled_element(TEMPERATURE_DEPENDENT_RESISTOR, 1, 0, r.clone(), ElementControl::Potential { input: 4, output: 2 }).unwrap()
+///     .add_controlled_element(JOULE_HEAT_SOURCE,              2, 4, r.clone(), ElementControl::Flux { element: 1 }).unwrap()
+///     .add_element(CONDUCTOR,                                 4, 3, vec![0.5]).unwrap()
+///     .run_study(1E-12, 100)
+///     .unwrap();
+///
+/// // The resistor's temperature rise is exactly what it dissipates at that temperature
+/// let t = soln.get_node_potential(4).unwrap()[0];
+/// let power = 144.0 / (4.0 * (1.0 + 0.004 * (t - 20.0)));
+/// assert!((t - (20.0 + power / 0.5)).abs() < 1E-6);
+/// ```
+pub fn joule_heat_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    resistance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    check_resistance(JOULE_HEAT_SOURCE, &resistance)?;
+
+    GenericElement::try_new(
+        resistance,
+        input_node, output_node,
+        joule_heat_flux,
+        false,
+        false, true // Only the output node receives the heat
+    )
+}
@@ -0,0 +1,170 @@
+// 3rd party modules
+use geqslib::newton::NewtonOptions;
+pub use geqslib::newton::Damping;
+
+// Local modules
+use crate::{CompiledStudy, NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// The settings used to solve a study. Every study API that solves a model has a variant that 
+/// takes these settings (e.g. `run_study_with_options`), while the variants that only take a margin 
+/// and an iteration limit use the defaults for every other setting.
+///
+/// # Fields:
+/// - `margin` - the tolerance of the solver, i.e. the largest squared norm of the nodal flux 
+///   discrepancies (and norm of the final newton step) that counts as a solution
+/// - `limit` - the most newton-raphson iterations to run before giving up
+/// - `damping` - how far each iteration moves along the newton step it finds (see `Damping`)
+/// - `initial_guess` - the potential that the solver starts from at every node that was not 
+///   given one, e.g. with `set_initial_guess` or `with_initial_state`
+/// - `step` - how far each unknown is stepped to approximate the jacobian with a forward difference
+/// - `verbose` - whether the norm of the nodal flux discrepancies at every iteration is logged as an
+///   `info` event through `tracing`, rather than a `debug` event
+///
+/// # Example
+/// ```
+/// use neapolitan::options::{Damping, SolverOptions};
+///
+/// let options = SolverOptions::default()
+///     .with_margin(1E-9)
+///     .with_damping(Damping::Backtracking { min_step: 1E-3 });
+///
+/// assert_eq!(options.limit, 100);
+/// assert_eq!(options, SolverOptions::new(1E-9, 100).with_damping(Damping::Backtracking { min_step: 1E-3 }));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolverOptions
+{
+    pub margin: f64,
+    pub limit: usize,
+    pub damping: Damping,
+    pub initial_guess: f64,
+    pub step: f64,
+    pub verbose: bool,
+}
+impl Default for SolverOptions
+{
+    /// The settings used by the command line: a margin of `1E-4`, 100 iterations of full newton 
+    /// steps from a guess of one, and the same jacobian step as geqslib's other solvers.
+    fn default() -> SolverOptions
+    {
+        let newton = NewtonOptions::new(1E-4, 100);
+        SolverOptions
+        {
+            margin: newton.margin,
+            limit: newton.limit,
+            damping: newton.damping,
+            initial_guess: 1.0,
+            step: newton.step,
+            verbose: false,
+        }
+    }
+}
+impl SolverOptions
+{
+    /// Creates the settings that `run_study` solves with, which are the defaults 
+    /// with the given margin and iteration limit.
+    pub fn new(margin: f64, limit: usize) -> SolverOptions
+    {
+        SolverOptions { margin, limit, ..Default::default() }
+    }
+
+    /// Sets the tolerance of the solver (see `margin`).
+    pub fn with_margin(mut self, margin: f64) -> SolverOptions
+    {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets the most newton-raphson iterations to run before giving up.
+    pub fn with_limit(mut self, limit: usize) -> SolverOptions
+    {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets how far each iteration moves along the newton step it finds. Nonlinear flux
+    /// formulas (e.g. diodes or radiation) often make full steps overshoot the solution,
+    /// which a fixed fraction of each step or a backtracking line search avoids.
+    pub fn with_damping(mut self, damping: Damping) -> SolverOptions
+    {
+        self.damping = damping;
+        self
+    }
+
+    /// Sets the potential that the solver starts from at every node that was not given one.
+    /// Models whose solution is far from zero (e.g. temperatures in kelvin) converge faster 
+    /// from a guess near their solution.
+    pub fn with_initial_guess(mut self, initial_guess: f64) -> SolverOptions
+    {
+        self.initial_guess = initial_guess;
+        self
+    }
+
+    /// Sets how far each unknown is stepped to approximate the jacobian. Models whose 
+    /// potentials are very large or very small may need a step on the same scale.
+    pub fn with_step(mut self, step: f64) -> SolverOptions
+    {
+        self.step = step;
+        self
+    }
+
+    /// Sets whether the norm of the nodal flux discrepancies at every iteration is logged at the
+    /// `info` level through `tracing`, rather than at the `debug` level.
+    pub fn with_verbose(mut self, verbose: bool) -> SolverOptions
+    {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Returns the settings of the newton-raphson solver that solves the study.
+    pub (in crate) fn to_newton_options(self) -> NewtonOptions
+    {
+        NewtonOptions::new(self.margin, self.limit)
+            .with_damping(self.damping)
+            .with_step(self.step)
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Runs the study exactly as `run_study` does, but with the solver settings in `options`.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::options::{Damping, SolverOptions};
+    ///
+    /// let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+    ///     .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+    ///
+    /// let options = SolverOptions::new(1E-9, 100)
+    ///     .with_damping(Damping::Backtracking { min_step: 1E-3 });
+    /// let soln = builder.run_study_with_options(&options).unwrap();
+    ///
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 0.55).abs() < 0.05);
+    /// ```
+    pub fn run_study_with_options(mut self, options: &SolverOptions) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        match self.continuation.take()
+        {
+            Some(continuation) => self.compile()?.continue_with(options, continuation),
+            None => self.compile()?.solve_with_options(options),
+        }
+    }
+}
+
+impl CompiledStudy
+{
+    /// Solves the study with its current gains and the solver settings in `options`.
+    pub fn solve_with_options(&mut self, options: &SolverOptions) -> anyhow::Result<NodalAnalysisStudyResult>
+    {
+        self.solve_with(options, |_, _| std::ops::ControlFlow::Continue(()))
+    }
+}
@@ -0,0 +1,178 @@
+// Std modules
+use std::collections::{BTreeMap, BTreeSet};
+
+// 3rd party modules
+use serde::de::{DeserializeSeed, Error};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Local modules
+use crate::errors::NodalAnalysisConfigurationError;
+use crate::NodalAnalysisStudyConfigurator;
+
+/// A `NodalAnalysisStudyConfigurator` described by the names that its element types and
+/// flux formulas were registered under, as it is written to and read from a file.
+///
+/// # Fields:
+/// - `dimension` - the number of components in each nodal potential
+/// - `elements` - the names of the element types built by an `ElementConstructor`
+/// - `terminal_elements` - the names of the element types built by a `TerminalElementConstructor`
+/// - `flux_elements` - the name of the flux formula used by each element type that is defined by
+///   its flux formula alone (see `NodalAnalysisStudyConfigurator::add_flux_element_type`)
+/// - `aliases` - the element type that each alias refers to
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct ConfiguratorSpec
+{
+    pub dimension: usize,
+    #[serde(default)]
+    pub elements: BTreeSet<String>,
+    #[serde(default)]
+    pub terminal_elements: BTreeSet<String>,
+    #[serde(default)]
+    pub flux_elements: BTreeMap<String, String>,
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Returns a name that lives as long as the configurators that use it. Names read from a
+/// file are only leaked when they are not already registered with the configurator that
+/// the file is read with, i.e. for the flux element types and aliases that the file defines.
+fn static_name(name: &str) -> &'static str
+{
+    Box::leak(name.to_owned().into_boxed_str())
+}
+
+impl NodalAnalysisStudyConfigurator
+{
+    /// Describes this configuration by the names of its element types and flux formulas.
+    pub fn to_spec(&self) -> ConfiguratorSpec
+    {
+        ConfiguratorSpec
+        {
+            dimension: self.dimension,
+            elements: self.elements.keys().map(|x| x.to_string()).collect(),
+            terminal_elements: self.terminal_elements.keys().map(|x| x.to_string()).collect(),
+            flux_elements: self.flux_elements.iter().map(|(x, y)| (x.to_string(), y.to_string())).collect(),
+            aliases: self.aliases.iter().map(|(x, y)| (x.to_string(), y.to_string())).collect(),
+        }
+    }
+
+    /// Builds the configuration described by `spec`, looking up each of its element types
+    /// and flux formulas by name in `registry`. The registry is any configurator that has
+    /// every constructor and flux formula that `spec` refers to registered, e.g. one of the
+    /// configurators from `default_study_builder_config` extended with custom element types.
+    ///
+    /// The returned configuration also has every flux formula of `registry` registered,
+    /// so that more flux element types can be added to it.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::{default_study_builder_config, NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator};
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::registry::ConfiguratorSpec;
+    /// use std::collections::HashMap;
+    ///
+    /// // Custom element types are registered under names in code...
+    /// let registry = default_study_builder_config()[DC_CIRCUIT].clone()
+    ///     .add_element_type("fuse", resistor).unwrap();
+    ///
+    /// // ...so that a configuration written in a file can refer to them by name
+    /// let spec: ConfiguratorSpec = serde_json::from_str(r#"{
+    ///     "dimension": 1,
+    ///     "elements": ["voltage_source", "fuse"],
+    ///     "flux_elements": {"conductance": "normal_flux"},
+    ///     "aliases": {"g": "conductance"}
+    /// }"#).unwrap();
+    /// let config = NodalAnalysisStudyConfigurator::from_spec(&spec, &registry).unwrap();
+    /// assert_eq!(config.to_spec(), spec);
+    ///
+    /// let soln = NodalAnalysisStudyBuilder::new("custom".to_string(), Some(HashMap::from([("custom".to_string(), config)])))
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_labeled_element("V", VOLTAGE_SOURCE, 0, 1, vec![6.0]).unwrap()
+    ///     .add_labeled_element("F", "fuse",         1, 2, vec![1.0]).unwrap()
+    ///     .add_labeled_element("G", "g",            2, 0, vec![0.5]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    ///
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["elements"]["G"][0].as_f64().unwrap() - 2.0).abs() < 1E-9);
+    /// ```
+    pub fn from_spec(spec: &ConfiguratorSpec, registry: &NodalAnalysisStudyConfigurator) -> anyhow::Result<NodalAnalysisStudyConfigurator>
+    {
+        let mut config = NodalAnalysisStudyConfigurator::new(spec.dimension);
+        for (&name, &flux_calc) in &registry.flux_formulas
+        {
+            config.flux_formulas.insert(name, flux_calc);
+        }
+
+        for name in &spec.elements
+        {
+            config = match registry.elements.get_key_value(name.as_str())
+            {
+                Some((&name, &constructor)) => config.add_element_type(name, constructor)?,
+                None => return Err(NodalAnalysisConfigurationError::UnregisteredElementType(name.to_owned()).into()),
+            };
+        }
+
+        for name in &spec.terminal_elements
+        {
+            config = match registry.terminal_elements.get_key_value(name.as_str())
+            {
+                Some((&name, &constructor)) => config.add_terminal_element_type(name, constructor)?,
+                None => return Err(NodalAnalysisConfigurationError::UnregisteredElementType(name.to_owned()).into()),
+            };
+        }
+
+        for (name, formula) in &spec.flux_elements
+        {
+            let name = match registry.flux_elements.get_key_value(name.as_str())
+            {
+                Some((&name, _)) => name,
+                None => static_name(name),
+            };
+            config = config.add_flux_element_type(name, formula)?;
+        }
+
+        for (alias, name) in &spec.aliases
+        {
+            let name = match config.resolve_element_type(name)
+            {
+                Some(name) => name,
+                None => return Err(NodalAnalysisConfigurationError::AliasTargetNotFound(name.to_owned()).into()),
+            };
+            let alias = match registry.aliases.get_key_value(alias.as_str())
+            {
+                Some((&alias, _)) => alias,
+                None => static_name(alias),
+            };
+            config = config.add_alias(alias, name)?;
+        }
+
+        Ok(config)
+    }
+}
+
+impl Serialize for NodalAnalysisStudyConfigurator
+{
+    /// Writes the configuration by the names of its element types and flux formulas (see `to_spec`).
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+    {
+        self.to_spec().serialize(serializer)
+    }
+}
+
+impl <'de> DeserializeSeed<'de> for &NodalAnalysisStudyConfigurator
+{
+    type Value = NodalAnalysisStudyConfigurator;
+
+    /// Reads a configuration written by name, looking up its element types
+    /// and flux formulas in this configurator (see `from_spec`).
+    fn deserialize<D>(self, deserializer: D) -> Result<NodalAnalysisStudyConfigurator, D::Error>
+    where D: Deserializer<'de>
+    {
+        let spec = ConfiguratorSpec::deserialize(deserializer)?;
+        NodalAnalysisStudyConfigurator::from_spec(&spec, self).map_err(D::Error::custom)
+    }
+}
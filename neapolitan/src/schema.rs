@@ -0,0 +1,235 @@
+// 3rd party modules
+use regex::Regex;
+use serde_json::{Map, Value};
+
+// Local modules
+use crate::errors::ModelValidationError;
+use crate::modelling::NodalAnalysisModel;
+
+/// The JSON Schema (draft 7) that every model file must satisfy.
+pub const MODEL_SCHEMA: &str = include_str!("../neapolitan_json/neapolitan_model_schema.json");
+
+/// Returns the JSON Schema for `NodalAnalysisModel` files (see `MODEL_SCHEMA`).
+pub fn model_schema() -> Value
+{
+    serde_json::from_str(MODEL_SCHEMA).expect("the embedded model schema is valid json")
+}
+
+/// Checks a JSON document against the model schema, returning a `ModelValidationError`
+/// that gives the path to the first part of the document that does not match,
+/// e.g. `elements[3].gain: expected array of numbers`.
+///
+/// Only the parts of JSON Schema that the model schema uses are supported.
+///
+/// # Example
+/// ```
+/// use neapolitan::schema::validate_model;
+/// use serde_json::json;
+///
+/// let model = json!({
+///     "model_type": "ssdc_circuit",
+///     "nodes": 2,
+///     "configuration": {},
+///     "elements": [
+///         { "element_type": "resistor", "input": 0, "output": 1, "gain": ["1k"] }
+///     ]
+/// });
+///
+/// let err = validate_model(&model).unwrap_err();
+/// assert_eq!(err.to_string(), "elements[0].gain: expected array of numbers");
+/// ```
+pub fn validate_model(model: &Value) -> anyhow::Result<()>
+{
+    let schema = model_schema();
+    Validator { root: &schema }.validate(&schema, model, "")?;
+    Ok(())
+}
+
+impl NodalAnalysisModel
+{
+    /// Reads a model from JSON, first validating it against the model schema so that
+    /// mistakes are reported with the path to the offending value (see `validate_model`).
+    /// Unlike deserializing with `serde_json` directly, unknown fields are rejected.
+    pub fn from_json_strict(json: &str) -> anyhow::Result<NodalAnalysisModel>
+    {
+        let value: Value = serde_json::from_str(json)?;
+        validate_model(&value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Walks a document alongside the schema that it should satisfy.
+struct Validator<'a>
+{
+    root: &'a Value,
+}
+impl<'a> Validator<'a>
+{
+    /// Follows a local `$ref` (e.g. `#/definitions/element`) to the schema it names.
+    fn resolve(&self, schema: &'a Value) -> &'a Value
+    {
+        match schema.get("$ref").and_then(Value::as_str)
+        {
+            Some(path) => self.root.pointer(path.trim_start_matches('#')).unwrap_or(schema),
+            None => schema,
+        }
+    }
+
+    /// Describes the values that a schema accepts, e.g. `array of numbers`.
+    fn describe(&self, schema: &'a Value) -> String
+    {
+        let schema = self.resolve(schema);
+        let types: Vec<&str> = match schema.get("type")
+        {
+            Some(Value::String(t)) => vec![t],
+            Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => return "value".to_owned(),
+        };
+
+        types.iter()
+            .map(|&t| match (t, schema.get("items").map(|items| self.describe(items)))
+            {
+                ("array", Some(items)) if items.starts_with("array") => format!("array of {items}"),
+                ("array", Some(items)) if items != "object" => format!("array of {items}s"),
+                ("integer", _) if schema.get("minimum").and_then(Value::as_f64) == Some(0.0) => "non-negative integer".to_owned(),
+                (t, _) => t.to_owned(),
+            })
+            .collect::<Vec<String>>()
+            .join(" or ")
+    }
+
+    /// Whether a value is one of the JSON Schema type `t`.
+    fn is_type(value: &Value, t: &str) -> bool
+    {
+        match t
+        {
+            "object"  => value.is_object(),
+            "array"   => value.is_array(),
+            "string"  => value.is_string(),
+            "number"  => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null"    => value.is_null(),
+            _ => true,
+        }
+    }
+
+    fn validate(&self, schema: &'a Value, value: &Value, path: &str) -> Result<(), ModelValidationError>
+    {
+        let schema = self.resolve(schema);
+        let mismatch = || ModelValidationError::Mismatch(display(path), self.describe(schema));
+
+        let type_ok = match schema.get("type")
+        {
+            Some(Value::String(t)) => Self::is_type(value, t),
+            Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).any(|t| Self::is_type(value, t)),
+            _ => true,
+        };
+        if !type_ok
+        {
+            return Err(mismatch());
+        }
+
+        if let (Some(minimum), Some(x)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64())
+        {
+            if x < minimum
+            {
+                return Err(mismatch());
+            }
+        }
+
+        match value
+        {
+            Value::Array(items) => self.validate_items(schema, items, path),
+            Value::Object(fields) => self.validate_fields(schema, fields, path),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_items(&self, schema: &'a Value, items: &[Value], path: &str) -> Result<(), ModelValidationError>
+    {
+        let item_schema = match schema.get("items")
+        {
+            Some(item_schema) => item_schema,
+            None => return Ok(()),
+        };
+
+        // Arrays of plain values are reported as a whole, since that is how they are written
+        let is_plain = !matches!(self.resolve(item_schema).get("type").and_then(Value::as_str), Some("object" | "array"));
+
+        for (i, item) in items.iter().enumerate()
+        {
+            let result = self.validate(item_schema, item, &format!("{path}[{i}]"));
+            if result.is_err() && is_plain
+            {
+                return Err(ModelValidationError::Mismatch(display(path), self.describe(schema)));
+            }
+            result?;
+        }
+        Ok(())
+    }
+
+    fn validate_fields(&self, schema: &'a Value, fields: &Map<String, Value>, path: &str) -> Result<(), ModelValidationError>
+    {
+        let field_path = |name: &str| match path.is_empty()
+        {
+            true => name.to_owned(),
+            false => format!("{path}.{name}"),
+        };
+
+        for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str)
+        {
+            if !fields.contains_key(name)
+            {
+                return Err(ModelValidationError::MissingProperty(field_path(name)));
+            }
+        }
+
+        let count_ok = schema.get("minProperties").and_then(Value::as_u64).is_none_or(|min| fields.len() as u64 >= min)
+            && schema.get("maxProperties").and_then(Value::as_u64).is_none_or(|max| fields.len() as u64 <= max);
+        if !count_ok
+        {
+            return Err(ModelValidationError::PropertyCount(display(path)));
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns = schema.get("patternProperties").and_then(Value::as_object);
+
+        for (name, field) in fields
+        {
+            if let Some(field_schema) = properties.and_then(|p| p.get(name))
+            {
+                self.validate(field_schema, field, &field_path(name))?;
+                continue;
+            }
+
+            let pattern_schema = patterns.into_iter()
+                .flatten()
+                .find(|(pattern, _)| Regex::new(pattern).is_ok_and(|re| re.is_match(name)))
+                .map(|(_, field_schema)| field_schema);
+            if let Some(field_schema) = pattern_schema
+            {
+                self.validate(field_schema, field, &field_path(name))?;
+                continue;
+            }
+
+            match schema.get("additionalProperties")
+            {
+                Some(Value::Bool(false)) => return Err(ModelValidationError::UnknownProperty(field_path(name))),
+                Some(field_schema @ Value::Object(_)) => self.validate(field_schema, field, &field_path(name))?,
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names the document itself when a path is empty.
+fn display(path: &str) -> String
+{
+    match path.is_empty()
+    {
+        true => "model".to_owned(),
+        false => path.to_owned(),
+    }
+}
@@ -0,0 +1,164 @@
+// Std modules
+use std::panic::catch_unwind;
+use std::thread;
+
+// 3rd party modules
+use serde_json::{json, to_string_pretty};
+use tiny_http::{Header, Request, Response, Server};
+
+// Local modules
+use crate::errors::StudyServerError;
+use crate::modelling::NodalAnalysisModel;
+use crate::NodalAnalysisStudyBuilder;
+
+/// The margin of error used when a request does not specify a `precision`.
+pub const DEFAULT_PRECISION: f64 = 0.0001;
+
+/// The iteration limit used when a request does not specify `iterations`.
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+/// Formats an error message as a JSON response body.
+fn error_body(message: impl std::fmt::Display) -> String
+{
+    json!({ "error": message.to_string() }).to_string()
+}
+
+/// Reads the `precision` and `iterations` solver settings
+/// from a request's query string, e.g. `?precision=1e-6`.
+fn solver_settings(query: &str) -> Result<(f64, usize), String>
+{
+    let mut precision = DEFAULT_PRECISION;
+    let mut iterations = DEFAULT_ITERATIONS;
+
+    for pair in query.split('&').filter(|x| !x.is_empty())
+    {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key
+        {
+            "precision" => precision = value.parse()
+                .map_err(|_| format!("failed to parse precision '{value}'"))?,
+            "iterations" => iterations = value.parse()
+                .map_err(|_| format!("failed to parse iteration limit '{value}'"))?,
+            _ => return Err(format!("unknown solver setting '{key}'")),
+        }
+    }
+
+    Ok((precision, iterations))
+}
+
+/// Handles a single request to the study server, returning the HTTP status
+/// code and JSON body of the response. This does not touch the network, so
+/// it can also be used to embed the service in another server.
+///
+/// The service has a single endpoint, `POST /solve`, which takes a model in the
+/// same JSON format as the `neapolitan` executable and returns its solution. The
+/// optional `precision` and `iterations` query parameters set the solver's margin
+/// of error and iteration limit.
+///
+/// # Example
+/// ```
+/// use neapolitan::server::handle_request;
+///
+/// let model = r#"{
+///     "model_type": "dc_circuit",
+///     "nodes": 3,
+///     "configuration": { "0": { "potential": [0.0], "is_locked": true, "metadata": null } },
+///     "elements": [
+///         { "element_type": "voltage_source", "input": 0, "output": 1, "gain": [3.0] },
+///         { "element_type": "resistor",       "input": 1, "output": 2, "gain": [1.0] },
+///         { "element_type": "resistor",       "input": 2, "output": 0, "gain": [2.0] }
+///     ]
+/// }"#;
+///
+/// let (status, body) = handle_request("POST", "/solve?precision=1e-9", model);
+/// assert_eq!(status, 200);
+///
+/// let soln: serde_json::Value = serde_json::from_str(&body).unwrap();
+/// assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 2.0).abs() < 1E-6);
+///
+/// let (status, _) = handle_request("POST", "/solve", "not a model");
+/// assert_eq!(status, 400);
+/// ```
+pub fn handle_request(method: &str, url: &str, body: &str) -> (u16, String)
+{
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    if path != "/solve"
+    {
+        return (404, error_body(format!("no such endpoint '{path}'")));
+    }
+
+    if method != "POST"
+    {
+        return (405, error_body("models must be submitted with a POST request"));
+    }
+
+    let (margin, limit) = match solver_settings(query)
+    {
+        Ok(settings) => settings,
+        Err(e) => return (400, error_body(e)),
+    };
+
+    let model = match NodalAnalysisModel::from_json_strict(body)
+    {
+        Ok(model) => model,
+        Err(e) => return (400, error_body(format!("failed to read model: {e}"))),
+    };
+
+    // Malformed models (e.g. unknown element types) may panic while being built
+    let solution = catch_unwind(|| {
+        NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+            .run_study(margin, limit)
+            .and_then(|soln| Ok(to_string_pretty(&soln)?))
+    });
+
+    match solution
+    {
+        Ok(Ok(json)) => (200, json),
+        Ok(Err(e)) => (422, error_body(format!("failed to solve model: {e}"))),
+        Err(_) => (422, error_body("failed to build model")),
+    }
+}
+
+/// Reads a request from the network, solves it, and sends the response.
+fn respond_to(mut request: Request)
+{
+    let mut body = String::new();
+    let (status, json) = match request.as_reader().read_to_string(&mut body)
+    {
+        Ok(_) => handle_request(request.method().as_str(), request.url(), &body),
+        Err(e) => (400, error_body(format!("failed to read request body: {e}"))),
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .expect("content type header is always valid");
+
+    let response = Response::from_string(json)
+        .with_status_code(status)
+        .with_header(content_type);
+
+    // The client may have hung up while the model was solving. There is nobody to tell.
+    let _ = request.respond(response);
+}
+
+/// Starts serving studies over HTTP on `addr` (e.g. `"0.0.0.0:8080"`), blocking
+/// the current thread. See `handle_request` for the endpoints that are available.
+///
+/// Each request is solved on its own thread, so long-running studies do not
+/// hold up other clients. Models are only ever built on the thread that solves
+/// them, so no nodes or elements are shared between requests.
+pub fn serve(addr: &str) -> anyhow::Result<()>
+{
+    let server = match Server::http(addr)
+    {
+        Ok(server) => server,
+        Err(e) => return Err(StudyServerError::BindFailed(e.to_string()).into()),
+    };
+
+    for request in server.incoming_requests()
+    {
+        thread::spawn(move || respond_to(request));
+    }
+
+    Ok(())
+}
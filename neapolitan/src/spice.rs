@@ -0,0 +1,219 @@
+// Std modules
+use std::collections::{HashMap, HashSet};
+
+// Local modules
+use crate::dc_circuits::{CURRENT_SOURCE, DC_CIRCUIT, RESISTOR, VOLTAGE_SOURCE};
+use crate::errors::SpiceImportError;
+use crate::modelling::NodalAnalysisModel;
+use crate::NodalAnalysisStudyBuilder;
+
+impl NodalAnalysisModel
+{
+    /// Reads a `dc_circuit` model from a SPICE netlist (e.g. a `.cir` file), so that existing
+    /// netlists can be solved and compared against SPICE simulators such as ngspice.
+    /// 
+    /// Only resistor (`R`), independent voltage source (`V`) and independent current source 
+    /// (`I`) cards are supported. As in SPICE, the first line is the title of the netlist, lines 
+    /// starting with `*` are comments, lines starting with `+` continue the previous line and
+    /// anything after `.end` is ignored, as are `.title` and `.op` cards. Cards are not case
+    /// sensitive, and values may use SPICE's scale suffixes (e.g. `4.7k`, `10meg` or `100u`),
+    /// optionally followed by a unit (e.g. `5V`).
+    /// 
+    /// The ground node (`0` or `gnd`) becomes node 0, which is locked at zero potential, and 
+    /// every other node is named after its SPICE node in lower case, since SPICE node names are 
+    /// not case sensitive. Numbered nodes are prefixed with `n` (e.g. node `1` is named `n1`) so 
+    /// that they are not mistaken for node indices, and also suffixed with `_` if the netlist 
+    /// already uses that name (e.g. node `1` is named `n1_` in a netlist that also has a node `n1`).
+    /// Elements are labelled with their SPICE names.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::modelling::NodalAnalysisModel;
+    /// 
+    /// let netlist = "voltage divider
+    /// * a 10 V supply across two resistors
+    /// V1 in 0 DC 10
+    /// R1 in out 1k
+    /// R2 out 0 4k
+    /// .op
+    /// .end";
+    /// 
+    /// let model = NodalAnalysisModel::from_spice(netlist).unwrap();
+    /// let soln = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["nodes"]["out"][0].as_f64().unwrap() - 8.0).abs() < 1E-9);
+    /// assert!((soln["elements"]["R1"][0].as_f64().unwrap() - 2E-3).abs() < 1E-9);
+    /// ```
+    pub fn from_spice(netlist: &str) -> anyhow::Result<NodalAnalysisModel>
+    {
+        let mut builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)?
+            .add_nodes(1)
+            .configure_node(0, vec![0.0], true, None);
+        let mut nodes: HashMap<String, usize> = HashMap::new();
+        let mut is_grounded = false;
+
+        // Every name in the netlist, which the names given to numbered nodes must avoid
+        let taken: HashSet<String> = netlist.split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+
+        for (line, card) in cards(netlist)
+        {
+            let fields: Vec<&str> = card.split_whitespace().collect();
+            let name = fields[0];
+
+            let element = match name.chars().next().map(|c| c.to_ascii_uppercase())
+            {
+                Some('R') => RESISTOR,
+                Some('V') => VOLTAGE_SOURCE,
+                Some('I') => CURRENT_SOURCE,
+                Some('.') => match name.to_lowercase().as_str()
+                {
+                    ".end" => break,
+                    ".title" | ".op" => continue,
+                    _ => return Err(SpiceImportError::UnsupportedCard(line, name.to_owned()).into()),
+                },
+                _ => return Err(SpiceImportError::UnsupportedCard(line, name.to_owned()).into()),
+            };
+
+            // Sources may mark their value as `DC`, which is the only kind of source supported
+            let args: Vec<&str> = fields[1..].iter()
+                .copied()
+                .filter(|x| !x.eq_ignore_ascii_case("dc"))
+                .collect();
+
+            let (plus, minus, value) = match args.as_slice()
+            {
+                [plus, minus, value] => (*plus, *minus, *value),
+                _ => return Err(SpiceImportError::MissingField(line, name.to_owned()).into()),
+            };
+
+            let value = match spice_value(value)
+            {
+                Some(x) => x,
+                None => return Err(SpiceImportError::UnreadableValue(line, value.to_owned()).into()),
+            };
+
+            let mut terminals = [0; 2];
+            for (terminal, node) in terminals.iter_mut().zip([plus, minus])
+            {
+                let node = node.to_lowercase();
+                if node == "0" || node == "gnd"
+                {
+                    is_grounded = true;
+                    continue;
+                }
+
+                *terminal = match nodes.get(&node)
+                {
+                    Some(&idx) => idx,
+                    None =>
+                    {
+                        let idx = builder.get_model().nodes;
+                        let name = match node.parse::<usize>()
+                        {
+                            Ok(_) =>
+                            {
+                                let mut name = format!("n{node}");
+                                while taken.contains(&name)
+                                {
+                                    name.push('_');
+                                }
+                                name
+                            },
+                            Err(_) => node.clone(),
+                        };
+                        builder = builder.add_named_node(&name)?;
+                        nodes.insert(node, idx);
+                        idx
+                    },
+                };
+            }
+
+            // A voltage source raises its `+` node above its `-` node, whereas neapolitan's raises 
+            // its output above its input. Current flows from `+` to `-` through the other elements.
+            let (input, output) = match element
+            {
+                VOLTAGE_SOURCE => (terminals[1], terminals[0]),
+                _ => (terminals[0], terminals[1]),
+            };
+
+            builder = builder.add_labeled_element(name, element, input, output, vec![value])?;
+        }
+
+        if !is_grounded
+        {
+            return Err(SpiceImportError::NoGroundNode.into());
+        }
+
+        Ok(builder.get_model().clone())
+    }
+}
+
+/// Splits a netlist into its cards, each with the line number that it starts on, 
+/// skipping the title line and comments and joining continued lines.
+fn cards(netlist: &str) -> Vec<(usize, String)>
+{
+    let mut cards: Vec<(usize, String)> = vec![];
+
+    for (idx, line) in netlist.lines().enumerate().skip(1)
+    {
+        // Both `;` and `$` start inline comments in common SPICE dialects
+        let line = line.split([';', '$']).next().unwrap_or_default().trim();
+
+        if line.is_empty() || line.starts_with('*')
+        {
+            continue;
+        }
+
+        match (line.strip_prefix('+'), cards.last_mut())
+        {
+            (Some(rest), Some((_, card))) =>
+            {
+                card.push(' ');
+                card.push_str(rest);
+            },
+            _ => cards.push((idx + 1, line.to_owned())),
+        }
+    }
+
+    cards
+}
+
+/// Reads a SPICE value, e.g. `4.7k` or `100uF`, where any letters after 
+/// the number are a scale suffix, optionally followed by a unit.
+fn spice_value(value: &str) -> Option<f64>
+{
+    let value = value.to_lowercase();
+
+    // The number is the longest prefix that can be read as one, so that e.g. `1e3` keeps its exponent
+    let (number, suffix) = (1..=value.len()).rev()
+        .filter(|&end| value.is_char_boundary(end))
+        .find_map(|end| value[..end].parse::<f64>().ok().map(|x| (x, &value[end..])))?;
+
+    let scale = match suffix
+    {
+        s if s.starts_with("meg") => 1E6,
+        s if s.starts_with("mil") => 25.4E-6,
+        s => match s.chars().next()
+        {
+            Some('t') => 1E12,
+            Some('g') => 1E9,
+            Some('k') => 1E3,
+            Some('m') => 1E-3,
+            Some('u') => 1E-6,
+            Some('n') => 1E-9,
+            Some('p') => 1E-12,
+            Some('f') => 1E-15,
+            Some(c) if c.is_alphabetic() => 1.0,
+            None => 1.0,
+            Some(_) => return None,
+        },
+    };
+
+    Some(number * scale)
+}
@@ -0,0 +1,163 @@
+// Std modules
+use std::ptr::fn_addr_eq;
+
+// Local modules
+use crate::errors::SubcircuitError;
+use crate::modelling::{ElementControl, NodalAnalysisElement};
+use crate::NodalAnalysisStudyBuilder;
+
+/// Builds the contents of a subcircuit onto a builder whose first nodes are the subcircuit's
+/// ports, given the parameters of the instance being added. Nodes added beyond the ports are
+/// internal to the subcircuit.
+pub type SubcircuitBuilder = fn(NodalAnalysisStudyBuilder, &[f64]) -> anyhow::Result<NodalAnalysisStudyBuilder>;
+
+/// A reusable subnetwork registered with `NodalAnalysisStudyBuilder::define_subcircuit`.
+#[derive(Clone, Debug)]
+pub struct SubcircuitDefinition
+{
+    ports: usize,
+    build: SubcircuitBuilder,
+    instances: usize,
+}
+impl PartialEq for SubcircuitDefinition
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.ports == other.ports && self.instances == other.instances && fn_addr_eq(self.build, other.build)
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Defines a reusable subnetwork (e.g. a voltage divider or RC filter) with `ports`
+    /// external nodes that can then be added to the model any number of times with
+    /// `add_subcircuit`. Subcircuits may themselves contain subcircuits defined before them.
+    ///
+    /// `build` is given a builder for the same model type whose nodes `0..ports` are the
+    /// subcircuit's ports, along with the parameters of the instance being added.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    ///
+    /// // A divider from port 0 to port 1 whose midpoint is an internal node
+    /// let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .define_subcircuit("divider", 2, |builder, r| builder
+    ///         .add_nodes(1)
+    ///         .add_element(RESISTOR, 0, 2, vec![r[0]])?
+    ///         .add_element(RESISTOR, 2, 1, vec![r[1]])
+    ///     ).unwrap()
+    ///     .add_nodes(2)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_subcircuit("divider", &[1, 0], &[1.0, 3.0]).unwrap()
+    ///     .run_study(1E-9, 100)
+    ///     .unwrap();
+    ///
+    /// let soln = serde_json::to_value(soln).unwrap();
+    /// assert!((soln["nodes"]["divider.0.2"][0].as_f64().unwrap() - 7.5).abs() < 1E-6);
+    /// ```
+    pub fn define_subcircuit(mut self, name: &str, ports: usize, build: SubcircuitBuilder) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        if self.subcircuits.contains_key(name)
+        {
+            return Err(SubcircuitError::NameCollision(name.to_owned()).into());
+        }
+
+        self.subcircuits.insert(name.to_owned(), SubcircuitDefinition { ports, build, instances: 0 });
+        Ok(self)
+    }
+
+    /// Adds an instance of a subcircuit defined with `define_subcircuit`, connecting its ports
+    /// to the nodes in `ports` in order and building it with the parameters in `params`.
+    ///
+    /// The subcircuit's internal nodes and elements are added after those already in the
+    /// model. Instances are named `<name>.<n>`, numbered in the order they are added, and
+    /// each internal node is named `<name>.<n>.<local node>` in the model and its results, where
+    /// `<local node>` is the node's index within the subcircuit. Named nodes, labelled elements
+    /// and the nodes of subcircuits nested within it are prefixed with `<name>.<n>.` in the same way.
+    pub fn add_subcircuit(mut self, name: &str, ports: &[usize], params: &[f64]) -> anyhow::Result<NodalAnalysisStudyBuilder>
+    {
+        let definition = match self.subcircuits.get_mut(name)
+        {
+            Some(definition) => definition,
+            None => return Err(SubcircuitError::NotFound(name.to_owned()).into()),
+        };
+
+        if ports.len() != definition.ports
+        {
+            return Err(SubcircuitError::PortCountMismatch(name.to_owned(), definition.ports).into());
+        }
+        if ports.iter().any(|&node| node >= self.model.nodes)
+        {
+            return Err(SubcircuitError::PortDoesNotExist.into());
+        }
+
+        let instance = format!("{name}.{}", definition.instances);
+        definition.instances += 1;
+        let build = definition.build;
+
+        // The subcircuit is built on its own so that it can use every builder method
+        // with local node and element indices, then merged into this model.
+        // Any subcircuits inside it are numbered within this instance.
+        let mut local = NodalAnalysisStudyBuilder::new(self.model.model_type.clone(), Some(self.configurator.clone()))?;
+        local.subcircuits = self.subcircuits.clone();
+        for definition in local.subcircuits.values_mut()
+        {
+            definition.instances = 0;
+        }
+        let local = build(local.add_nodes(ports.len()), params)?.model;
+
+        let first_internal = self.model.nodes;
+        let node_map = |node: usize| match node < ports.len()
+        {
+            true => ports[node],
+            false => first_internal + node - ports.len(),
+        };
+        let element_offset = self.model.elements.len();
+
+        for node in ports.len()..local.nodes
+        {
+            let name = match local.node_names.get(&node)
+            {
+                Some(name) => format!("{instance}.{name}"),
+                None => format!("{instance}.{node}"),
+            };
+            self.model.node_names.insert(node_map(node), name);
+        }
+        self.model.nodes += local.nodes - ports.len();
+
+        for (node, node_data) in local.configuration
+        {
+            self.model.configuration.insert(node_map(node), node_data);
+        }
+
+        for (element, label) in local.element_labels
+        {
+            self.model.element_labels.insert(element_offset + element, format!("{instance}.{label}"));
+        }
+
+        for element in local.elements
+        {
+            let control = element.control.map(|control| match control
+            {
+                ElementControl::Potential { input, output } => ElementControl::Potential { input: node_map(input), output: node_map(output) },
+                ElementControl::Flux { element } => ElementControl::Flux { element: element_offset + element },
+            });
+
+            self.model.elements.push(NodalAnalysisElement
+            {
+                element_type: element.element_type,
+                input: node_map(element.input),
+                output: node_map(element.output),
+                gain: element.gain,
+                control,
+                terminals: element.terminals.into_iter().map(node_map).collect(),
+            });
+        }
+
+        Ok(self)
+    }
+}
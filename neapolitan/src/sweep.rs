@@ -0,0 +1,173 @@
+// 3rd party modules
+use serde::Serialize;
+
+// Local modules
+use crate::errors::SweepError;
+use crate::options::SolverOptions;
+use crate::{NodalAnalysisStudyBuilder, NodalAnalysisStudyResult};
+
+/// A single value in a model that a sweep varies.
+///
+/// # Variants:
+/// - `Gain` - a component of the gain of the element at index `element` (in the order elements were added)
+/// - `Potential` - a component of the potential of a node that was locked with `configure_node`
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum SweepParameter
+{
+    Gain { element: usize, component: usize },
+    Potential { node: usize, component: usize },
+}
+
+/// The results of a parameter sweep.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SweepResult
+{
+    parameters: Vec<SweepParameter>,
+    points: Vec<Vec<f64>>,
+    results: Vec<NodalAnalysisStudyResult>,
+}
+impl SweepResult
+{
+    /// Returns the parameters that were swept.
+    pub fn get_parameters(&self) -> &Vec<SweepParameter>
+    {
+        &self.parameters
+    }
+
+    /// Returns the value of each parameter (in the order of `get_parameters`) at each point solved.
+    pub fn get_points(&self) -> &Vec<Vec<f64>>
+    {
+        &self.points
+    }
+
+    /// Returns the result of the study at each point in `get_points`.
+    pub fn get_results(&self) -> &Vec<NodalAnalysisStudyResult>
+    {
+        &self.results
+    }
+}
+
+/// Returns `points` values evenly spaced from `start` to `end`, inclusive.
+///
+/// # Example
+/// ```
+/// use neapolitan::sweep::linspace;
+///
+/// assert_eq!(linspace(1.0, 2.0, 5), vec![1.0, 1.25, 1.5, 1.75, 2.0]);
+/// ```
+pub fn linspace(start: f64, end: f64, points: usize) -> Vec<f64>
+{
+    match points
+    {
+        0 => vec![],
+        1 => vec![start],
+        _ => (0..points).map(|i| start + (end - start) * i as f64 / (points - 1) as f64).collect(),
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Solves the model at each of a series of points, where `values[i]` lists the value
+    /// that `parameters[i]` takes on at each point. Every parameter changes together, so
+    /// each list must be the same length (see `linspace` for building evenly spaced lists).
+    ///
+    /// The model is only built once, and each point starts from the solution at the point
+    /// before it, so sweeping in small steps keeps nonlinear models near their solution.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::sweep::{linspace, SweepParameter};
+    ///
+    /// let result = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+    ///     .run_sweep(
+    ///         &[SweepParameter::Gain { element: 2, component: 0 }],
+    ///         &[linspace(1.0, 9.0, 5)],
+    ///         1E-9, 100
+    ///     )
+    ///     .unwrap();
+    ///
+    /// for (point, soln) in result.get_points().iter().zip(result.get_results())
+    /// {
+    ///     let r = point[0];
+    ///     let v2 = serde_json::to_value(soln).unwrap()["nodes"]["2"][0].as_f64().unwrap();
+    ///     assert!((v2 - 10.0 * r / (1.0 + r)).abs() < 1E-6);
+    /// }
+    /// ```
+    pub fn run_sweep(self, parameters: &[SweepParameter], values: &[Vec<f64>], margin: f64, limit: usize) -> anyhow::Result<SweepResult>
+    {
+        self.run_sweep_with_options(parameters, values, &SolverOptions::new(margin, limit))
+    }
+
+    /// Runs the sweep exactly as `run_sweep` does, but solves each point with the solver settings in `options`.
+    pub fn run_sweep_with_options(self, parameters: &[SweepParameter], values: &[Vec<f64>], options: &SolverOptions) -> anyhow::Result<SweepResult>
+    {
+        if parameters.len() != values.len() || values.iter().any(|v| v.len() != values[0].len())
+        {
+            return Err(SweepError::ValueCountMismatch.into());
+        }
+
+        for parameter in parameters
+        {
+            match *parameter
+            {
+                SweepParameter::Gain { element, component } => match self.model.elements.get(element)
+                {
+                    None => return Err(SweepError::ElementDoesNotExist.into()),
+                    Some(element) if component >= element.gain.len() => return Err(SweepError::GainComponentDoesNotExist.into()),
+                    Some(_) => (),
+                },
+                SweepParameter::Potential { node, component } if component >= self.get_node_dimension(node) => return Err(SweepError::PotentialComponentDoesNotExist.into()),
+                SweepParameter::Potential { .. } => (),
+            }
+        }
+
+        let points: Vec<Vec<f64>> = match values.first()
+        {
+            Some(first) => (0..first.len()).map(|i| values.iter().map(|v| v[i]).collect()).collect(),
+            None => vec![],
+        };
+
+        // Only the swept values change between points, so the model is only built once
+        let mut study = self.compile()?;
+        let mut results = vec![];
+
+        for point in &points
+        {
+            for (&parameter, &value) in parameters.iter().zip(point)
+            {
+                match parameter
+                {
+                    SweepParameter::Gain { element, component } =>
+                    {
+                        let mut gain = study.get_model().elements[element].gain.clone();
+                        gain[component] = value;
+                        study.set_gain(element, gain)?;
+                    },
+                    SweepParameter::Potential { node, component } =>
+                    {
+                        let mut potential = study.get_model().configuration.get(&node)
+                            .map(|node_data| node_data.potential.clone())
+                            .unwrap_or_default();
+                        potential.resize(study.get_node_dimension(node), 0.0);
+                        potential[component] = value;
+                        study.set_locked_potential(node, potential)?;
+                    },
+                }
+            }
+
+            let soln = study.solve_with_options(options)?;
+            study.set_initial_state(&soln);
+            results.push(soln);
+        }
+
+        Ok(SweepResult { parameters: parameters.to_vec(), points, results })
+    }
+}
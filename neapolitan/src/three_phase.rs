@@ -0,0 +1,347 @@
+// Std modules
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use gmatlib::Matrix;
+use thiserror::Error;
+
+// Local modules
+use crate::dc_circuits::{resistor, RESISTOR};
+use crate::modelling::element::{ElementConstructor, TerminalElementConstructor};
+use crate::{flux_formulas::*, get_node_potential, is_locked, lock_node, set_node_potential};
+use crate::{GenericElement, GenericNode, NodalAnalysisStudyConfigurator};
+
+#[derive(Debug, Error)]
+pub enum ThreePhaseElementError
+{
+    #[error("you must specify an amplitude and optionally a phase angle in radians (in that order) to create a three-phase source")]
+    SourceParameters,
+    #[error("a three-phase source must drive an unlocked bus from a locked neutral node (in that order)")]
+    SourceNeutral,
+    #[error("you must specify a single, positive per-phase resistance to create a balanced load")]
+    BalancedLoadResistance,
+    #[error("you must specify three positive resistances (one per phase, in order) to create an unbalanced load")]
+    UnbalancedLoadResistances,
+    #[error("you must specify a single, positive resistance to create a neutral connection")]
+    NeutralResistance,
+    #[error("a three-phase load or neutral connection must be connected to exactly two nodes")]
+    Terminals,
+}
+
+pub const THREE_PHASE: &str = "three_phase";
+pub const THREE_PHASE_SOURCE: &str = "three_phase_source";
+pub const BALANCED_LOAD: &str = "balanced_load";
+pub const UNBALANCED_LOAD: &str = "unbalanced_load";
+pub const NEUTRAL_CONNECTION: &str = "neutral_connection";
+
+/// The number of phases, and so the dimension of every nodal potential, in a three-phase study.
+pub const PHASES: usize = 3;
+
+/// Returns the configurator for a three-phase study, in which every node is a three-phase bus
+/// whose potential is `[a, b, c]`, the instantaneous potential of each of its phases.
+///
+/// A node used as the neutral (i.e. the star point) of a source or load is a single conductor
+/// rather than three. The elements that connect to a neutral node (`three_phase_source`,
+/// `balanced_load`, `unbalanced_load` and `neutral_connection`) take its potential from phase `a`
+/// and return their whole neutral current through phase `a`, while holding phases `b` and `c` at
+/// the potential of phase `a`. Elements that act on each phase separately (e.g. a `resistor` for
+/// the impedance of a line) should only connect buses.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::three_phase::*;
+///
+/// // A balanced wye load with a floating star point, fed by a grounded source
+/// let soln = NodalAnalysisStudyBuilder::new(THREE_PHASE.to_string(), None)
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(THREE_PHASE_SOURCE, 0, 1, vec![170.0]).unwrap()
+///     .add_terminal_element(BALANCED_LOAD, vec![1, 2], vec![10.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+///
+/// // Balanced phase currents sum to zero, so the star point does not move
+/// let json = serde_json::to_value(soln).unwrap();
+/// assert!(json["nodes"]["2"][0].as_f64().unwrap().abs() < 1E-6);
+/// assert!((json["nodes"]["1"][1].as_f64().unwrap() + 85.0).abs() < 1E-9);
+/// ```
+pub fn three_phase_config() -> NodalAnalysisStudyConfigurator
+{
+    NodalAnalysisStudyConfigurator
+    {
+        dimension: PHASES,
+        elements: HashMap::from([
+            (RESISTOR,                resistor                as ElementConstructor),
+            (THREE_PHASE_SOURCE,      three_phase_source      as ElementConstructor),
+        ]),
+        terminal_elements: HashMap::from([
+            (BALANCED_LOAD,           balanced_load           as TerminalElementConstructor),
+            (UNBALANCED_LOAD,         unbalanced_load         as TerminalElementConstructor),
+            (NEUTRAL_CONNECTION,      neutral_connection      as TerminalElementConstructor),
+        ]),
+        aliases: HashMap::new(),
+        flux_formulas: builtin_flux_formulas(),
+        flux_elements: HashMap::new(),
+    }
+}
+
+/// Returns the instantaneous potential of each phase of a balanced three-phase set with the
+/// given amplitude at the given phase angle, i.e. `amplitude * cos(angle - k * 2pi/3)` for
+/// phases `k = 0, 1, 2`.
+///
+/// # Example
+/// ```
+/// use neapolitan::three_phase::balanced_phases;
+///
+/// let v = balanced_phases(2.0, 0.0);
+///
+/// assert!((v[0] - 2.0).abs() < 1E-12);
+/// assert!((v[1] + 1.0).abs() < 1E-12);
+/// assert!((v.iter().sum::<f64>()).abs() < 1E-12);
+/// ```
+pub fn balanced_phases(amplitude: f64, angle: f64) -> Vec<f64>
+{
+    (0..PHASES)
+        .map(|k| amplitude * (angle - TAU * k as f64 / PHASES as f64).cos())
+        .collect()
+}
+
+/// Returns the flux into an element from a neutral node that carries `current` out
+/// through phase `a`, holding the other phases at the potential of phase `a` through
+/// the conductance `hold`.
+fn neutral_flux(neutral: &Matrix<f64>, current: f64, hold: f64) -> Matrix<f64>
+{
+    let mut flux = Matrix::new(neutral.get_rows(), 1);
+    flux[(0, 0)] = current;
+    for k in 1..neutral.get_rows()
+    {
+        flux[(k, 0)] = hold * (neutral[(k, 0)] - neutral[(0, 0)]);
+    }
+    flux
+}
+
+/// A flux calculation for wye-connected loads between a bus and a neutral node (in that
+/// order), where the gain is the conductance of each phase. Each phase draws
+/// `gain[k] * (bus[k] - neutral[a])` from the bus, and their sum flows into the neutral.
+pub fn wye_load_flux(nodes: &[Rc<RefCell<GenericNode>>], gain: &Matrix<f64>) -> anyhow::Result<Vec<Matrix<f64>>>
+{
+    let (bus, neutral) = match nodes
+    {
+        [bus, neutral] => (bus.try_borrow()?, neutral.try_borrow()?),
+        _ => return Err(ThreePhaseElementError::Terminals.into()),
+    };
+
+    let star = neutral.potential[(0, 0)];
+    let mut phases = Matrix::new(bus.potential.get_rows(), 1);
+    for k in 0..phases.get_rows()
+    {
+        phases[(k, 0)] = gain[(k, 0)] * (bus.potential[(k, 0)] - star);
+    }
+
+    let current: f64 = phases.as_slice().iter().sum();
+    let hold: f64 = gain.as_slice().iter().sum();
+    Ok(vec![phases, neutral_flux(&neutral.potential, -current, hold)])
+}
+
+/// A flux calculation for a conductor joining two neutral nodes, where the gain
+/// is its conductance. The current `gain[0] * (input[a] - output[a])` flows from the
+/// input to the output through phase `a` of each.
+pub fn neutral_conductor_flux(nodes: &[Rc<RefCell<GenericNode>>], gain: &Matrix<f64>) -> anyhow::Result<Vec<Matrix<f64>>>
+{
+    let (input, output) = match nodes
+    {
+        [input, output] => (input.try_borrow()?, output.try_borrow()?),
+        _ => return Err(ThreePhaseElementError::Terminals.into()),
+    };
+
+    let conductance = gain[(0, 0)];
+    let current = conductance * (input.potential[(0, 0)] - output.potential[(0, 0)]);
+    Ok(vec![
+        neutral_flux(&input.potential, current, conductance),
+        neutral_flux(&output.potential, -current, conductance),
+    ])
+}
+
+/// A flux calculation for sources that drive a bus (the output) to `gain` above a neutral
+/// node (the input) on every phase, i.e. phase `k` of the bus is `neutral[a] + gain[k]`. Like
+/// `observe_flux`, the source carries the flux that each phase of the bus is missing.
+///
+/// The returned flux is the current delivered to each phase of the bus. The neutral must be
+/// locked (e.g. grounded), so its flux balance is not needed to solve the study.
+pub fn neutral_observe_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    delta: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let mut bus = onode_ref.try_borrow_mut()?;
+    let neutral = inode_ref.try_borrow()?;
+
+    if !neutral.is_locked
+    {
+        return Err(ThreePhaseElementError::SourceNeutral.into());
+    }
+
+    for k in 0..bus.potential.get_rows()
+    {
+        bus.potential[(k, 0)] = neutral.potential[(0, 0)] + delta[(k, 0)];
+    }
+    drop(bus);
+
+    let mut discrepancy = onode_ref.try_borrow()?
+        .get_flux_discrepancy()?;
+    discrepancy.inplace_scale(-1.0);
+
+    Ok(discrepancy)
+}
+
+/// Represents a balanced three-phase voltage source with its star point at a locked neutral
+/// node (the input), driving the phases of a bus (the output). The gain is given as the
+/// amplitude of the phase voltages and optionally the phase angle of phase `a` in radians,
+/// and phase `k` is driven to `amplitude * cos(angle - k * 2pi/3)` above the neutral.
+pub fn three_phase_source(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    amplitude_and_angle: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let voltage = match *amplitude_and_angle.as_slice()
+    {
+        [amplitude] => balanced_phases(amplitude, 0.0),
+        [amplitude, angle] => balanced_phases(amplitude, angle),
+        _ => return Err(ThreePhaseElementError::SourceParameters.into()),
+    };
+
+    if is_locked(&output_node)?
+    {
+        return Err(ThreePhaseElementError::SourceNeutral.into());
+    }
+
+    let neutral = get_node_potential(&input_node)?[(0, 0)];
+    lock_node(&output_node)?;
+    set_node_potential(&output_node, voltage.iter().map(|v| neutral + v).collect())?;
+
+    let elem = GenericElement::try_new(
+        voltage,
+        input_node, output_node,
+        neutral_observe_flux,
+        true,
+        true, false,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents a wye-connected load with the same resistance on every phase, connected to
+/// a bus and the load's star point (a neutral node), in that order, with
+/// `NodalAnalysisStudyBuilder::add_terminal_element`. The gain is given as the resistance
+/// of each phase.
+///
+/// Its flux is the current drawn from each phase of the bus, followed by the current
+/// into the load from its star point.
+pub fn balanced_load(
+    nodes: Vec<Weak<RefCell<GenericNode>>>,
+    resistance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    match *resistance.as_slice()
+    {
+        [r] if r > 0.0 => wye_load(nodes, vec![1.0 / r; PHASES]),
+        _ => Err(ThreePhaseElementError::BalancedLoadResistance.into()),
+    }
+}
+
+/// Represents a wye-connected load with a different resistance on each phase, connected
+/// to a bus and the load's star point (a neutral node), in that order, with
+/// `NodalAnalysisStudyBuilder::add_terminal_element`. The gain is given as the resistance
+/// of phases `a`, `b` and `c`, in that order.
+///
+/// Its flux is the current drawn from each phase of the bus, followed by the current
+/// into the load from its star point.
+///
+/// # Example
+/// ```
+/// use neapolitan::NodalAnalysisStudyBuilder;
+/// use neapolitan::three_phase::*;
+///
+/// // An unbalanced load whose star point is grounded through a 5 ohm neutral
+/// let soln = NodalAnalysisStudyBuilder::new(THREE_PHASE.to_string(), None)
+///     .unwrap()
+///     .add_nodes(3)
+///     .configure_node(0, vec![0.0], true, None)
+///     .add_element(THREE_PHASE_SOURCE, 0, 1, vec![100.0]).unwrap()
+///     .add_terminal_element(UNBALANCED_LOAD, vec![1, 2], vec![10.0, 20.0, 20.0]).unwrap()
+///     .add_terminal_element(NEUTRAL_CONNECTION, vec![2, 0], vec![5.0]).unwrap()
+///     .run_study(1E-9, 100)
+///     .unwrap();
+///
+/// // The star point settles where the phase and neutral currents balance
+/// let star = (100.0 / 10.0 - 50.0 / 20.0 - 50.0 / 20.0) / (1.0 / 10.0 + 2.0 / 20.0 + 1.0 / 5.0);
+/// let json = serde_json::to_value(soln).unwrap();
+/// assert!((json["nodes"]["2"][0].as_f64().unwrap() - star).abs() < 1E-6);
+/// ```
+pub fn unbalanced_load(
+    nodes: Vec<Weak<RefCell<GenericNode>>>,
+    resistances: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if resistances.len() != PHASES || resistances.iter().any(|&r| r <= 0.0)
+    {
+        return Err(ThreePhaseElementError::UnbalancedLoadResistances.into());
+    }
+    wye_load(nodes, resistances.iter().map(|r| 1.0 / r).collect())
+}
+
+/// Builds a wye-connected load from the conductance of each phase.
+fn wye_load(nodes: Vec<Weak<RefCell<GenericNode>>>, conductances: Vec<f64>) -> anyhow::Result<Rc<GenericElement>>
+{
+    if nodes.len() != 2
+    {
+        return Err(ThreePhaseElementError::Terminals.into());
+    }
+
+    let elem = GenericElement::try_new_with_terminals(
+        conductances,
+        nodes,
+        wye_load_flux,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
+
+/// Represents the neutral conductor joining two neutral nodes, e.g. the star point of a
+/// load and the neutral of its source, connected in that order with
+/// `NodalAnalysisStudyBuilder::add_terminal_element`. The gain is given as its resistance.
+///
+/// Its flux is the current into the conductor from each node, the first component of
+/// which is the current carried from the first node to the second.
+pub fn neutral_connection(
+    nodes: Vec<Weak<RefCell<GenericNode>>>,
+    resistance: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    let conductance = match *resistance.as_slice()
+    {
+        [r] if r > 0.0 => 1.0 / r,
+        _ => return Err(ThreePhaseElementError::NeutralResistance.into()),
+    };
+
+    if nodes.len() != 2
+    {
+        return Err(ThreePhaseElementError::Terminals.into());
+    }
+
+    let elem = GenericElement::try_new_with_terminals(
+        vec![conductance],
+        nodes,
+        neutral_conductor_flux,
+    )?;
+    elem.set_post_process(absorbed_power)?;
+    Ok(elem)
+}
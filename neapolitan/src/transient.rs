@@ -0,0 +1,356 @@
+// Std modules
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+use std::cell::RefCell;
+
+// 3rd party modules
+use gmatlib::Matrix;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Local modules
+use crate::errors::TransientStudyError;
+use crate::dc_circuits::*;
+use crate::flux_formulas::builtin_flux_formulas;
+use crate::options::SolverOptions;
+use crate::modelling::element::{ElementConstructor, TerminalElementConstructor};
+use crate::{csv_columns, write_csv, GenericElement, GenericNode, NodalAnalysisStudyBuilder, NodalAnalysisStudyConfigurator, NodalAnalysisStudyResult};
+
+#[derive(Debug, Error)]
+pub enum TransientElementError
+{
+    #[error("you must specify a capacitance and optionally an initial voltage (in that order) to create a capacitor element")]
+    CapacitorParameters,
+    #[error("you must specify an inductance and optionally an initial current (in that order) to create an inductor element")]
+    InductorParameters,
+    #[error("stateful elements must be given a time step and their previous state by `run_transient_study`")]
+    MissingState,
+}
+
+pub const TRANSIENT_CIRCUIT: &str = "transient_circuit";
+pub const CAPACITOR: &str = "capacitor";
+pub const INDUCTOR: &str = "inductor";
+
+/// Returns a configurator for a time-domain circuit study, which
+/// supports every DC circuit element as well as capacitors and inductors.
+pub fn transient_circuit_config() -> NodalAnalysisStudyConfigurator
+{
+    NodalAnalysisStudyConfigurator
+    {
+        dimension: 1,
+        elements: HashMap::from([
+            (RESISTOR,                resistor                as ElementConstructor),
+            (VOLTAGE_SOURCE,          voltage_source          as ElementConstructor),
+            (CURRENT_SOURCE,          current_source          as ElementConstructor),
+            (DIODE,                   diode                   as ElementConstructor),
+            (CHECK_VALVE,             check_valve             as ElementConstructor),
+            (BATTERY,                 battery                 as ElementConstructor),
+            (PUMP,                    pump                    as ElementConstructor),
+            (VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, voltage_controlled_voltage_source as ElementConstructor),
+            (VOLTAGE_CONTROLLED_CURRENT_SOURCE, voltage_controlled_current_source as ElementConstructor),
+            (CURRENT_CONTROLLED_VOLTAGE_SOURCE, current_controlled_voltage_source as ElementConstructor),
+            (CURRENT_CONTROLLED_CURRENT_SOURCE, current_controlled_current_source as ElementConstructor),
+            (CAPACITOR,               capacitor               as ElementConstructor),
+            (INDUCTOR,                inductor                as ElementConstructor),
+        ]),
+        terminal_elements: HashMap::from([
+            (NPN_TRANSISTOR,          npn_transistor          as TerminalElementConstructor),
+        ]),
+        aliases: HashMap::new(),
+        flux_formulas: builtin_flux_formulas(),
+        flux_elements: HashMap::new(),
+    }
+}
+
+/// A flux calculation for elements whose flux is proportional to the rate of
+/// change of the potential difference across them, discretized with a backward
+/// euler step, i.e. `gain[0] * (v - gain[2]) / gain[1]` where `gain[1]` is the
+/// time step and `gain[2]` is the potential difference at the previous step.
+pub fn backward_euler_derivative_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let delta = inode.potential[(0, 0)] - onode.potential[(0, 0)];
+    Ok(Matrix::from_col_vec(vec![gain[(0, 0)] * (delta - gain[(2, 0)]) / gain[(1, 0)]]))
+}
+
+/// A flux calculation for elements whose flux changes at a rate proportional to the
+/// potential difference across them, discretized with a backward euler step, i.e.
+/// `gain[2] + gain[1] * v / gain[0]` where `gain[1]` is the time step and `gain[2]`
+/// is the flux at the previous step.
+pub fn backward_euler_integral_flux(
+    inode_ref: Rc<RefCell<GenericNode>>,
+    onode_ref: Rc<RefCell<GenericNode>>,
+    gain: &Matrix<f64>,
+    _drives_output: bool
+) -> anyhow::Result<Matrix<f64>>
+{
+    let onode = onode_ref.try_borrow()?;
+    let inode = inode_ref.try_borrow()?;
+
+    let delta = inode.potential[(0, 0)] - onode.potential[(0, 0)];
+    Ok(Matrix::from_col_vec(vec![gain[(2, 0)] + gain[(1, 0)] * delta / gain[(0, 0)]]))
+}
+
+/// Represents a linear capacitor. In a model, the gain is given as the capacitance
+/// and optionally the initial voltage across it. During a transient study, each
+/// step is solved with a gain of `[capacitance, time step, previous voltage]`.
+pub fn capacitor(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    capacitance_step_and_voltage: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if capacitance_step_and_voltage.len() != 3
+    {
+        return Err(TransientElementError::MissingState.into());
+    }
+
+    GenericElement::try_new(
+        capacitance_step_and_voltage,
+        input_node, output_node,
+        backward_euler_derivative_flux,
+        false,
+        true, true,
+    )
+}
+
+/// Represents a linear inductor. In a model, the gain is given as the inductance
+/// and optionally the initial current through it. During a transient study, each
+/// step is solved with a gain of `[inductance, time step, previous current]`.
+pub fn inductor(
+    input_node: Weak<RefCell<GenericNode>>,
+    output_node: Weak<RefCell<GenericNode>>,
+    inductance_step_and_current: Vec<f64>,
+) -> anyhow::Result<Rc<GenericElement>>
+{
+    if inductance_step_and_current.len() != 3
+    {
+        return Err(TransientElementError::MissingState.into());
+    }
+
+    GenericElement::try_new(
+        inductance_step_and_current,
+        input_node, output_node,
+        backward_euler_integral_flux,
+        false,
+        true, true,
+    )
+}
+
+/// The time series of every nodal potential and elemental flux in a transient study.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TransientStudyResult
+{
+    times: Vec<f64>,
+    nodes: HashMap<String, Vec<Vec<f64>>>,
+    elements: HashMap<String, Vec<Vec<f64>>>,
+}
+impl TransientStudyResult
+{
+    /// Returns the time at the end of each step that was solved.
+    pub fn get_times(&self) -> &Vec<f64>
+    {
+        &self.times
+    }
+
+    /// Returns the potential of the given node at each time in `get_times`. Nodes are
+    /// named the same way as in a study's results, i.e. by their name or else their index.
+    pub fn get_node_history(&self, node: &str) -> Option<&Vec<Vec<f64>>>
+    {
+        self.nodes.get(node)
+    }
+
+    /// Returns the flux of the given element at each time in `get_times`. Elements are named the
+    /// same way as in a study's results, i.e. by their label or else `"<element_type>.<element_index>"`.
+    pub fn get_element_history(&self, element: &str) -> Option<&Vec<Vec<f64>>>
+    {
+        self.elements.get(element)
+    }
+
+    /// Formats the result as csv, with a `time` column followed by the same columns as 
+    /// `NodalAnalysisStudyResult::to_csv` (not including derived quantities), and a row for 
+    /// each time in `get_times`.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::transient::*;
+    /// 
+    /// let result = NodalAnalysisStudyBuilder::new(TRANSIENT_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_labeled_element("C1", CAPACITOR, 2, 0, vec![1.0]).unwrap()
+    ///     .run_transient_study(0.25, 1.0, 1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let csv = result.to_csv();
+    /// let lines: Vec<&str> = csv.lines().collect();
+    /// assert_eq!(lines[0], "time,node:0,node:1,node:2,element:C1,element:resistor.1,element:voltage_source.0");
+    /// assert_eq!(lines.len(), 5);
+    /// assert!(lines[4].starts_with("1,0,1,"));
+    /// ```
+    pub fn to_csv(&self) -> String
+    {
+        let mut columns = csv_columns("node", &self.nodes);
+        columns.extend(csv_columns("element", &self.elements));
+
+        let mut header = vec!["time".to_string()];
+        header.extend(columns.iter().map(|(name, _)| name.clone()));
+
+        let rows: Vec<Vec<Vec<f64>>> = self.times.iter()
+            .enumerate()
+            .map(|(step, &time)| 
+            {
+                let mut row = vec![vec![time]];
+                row.extend(columns.iter().map(|(_, history)| history[step].clone()));
+                row
+            })
+            .collect();
+
+        write_csv(&header, &rows)
+    }
+
+    fn add_step(&mut self, time: f64, study: NodalAnalysisStudyResult)
+    {
+        self.times.push(time);
+
+        for (node, potential) in study.nodes
+        {
+            self.nodes.entry(node).or_default().push(potential);
+        }
+
+        for (name, flux) in study.elements
+        {
+            self.elements.entry(name).or_default().push(flux);
+        }
+    }
+}
+
+impl NodalAnalysisStudyBuilder
+{
+    /// Steps the model through time from `t = 0` to `t_end` in increments of `dt`,
+    /// solving for every nodal potential and elemental flux at the end of each step.
+    ///
+    /// Capacitors and inductors carry their voltage and current, respectively, from
+    /// one step to the next. Their initial state is taken from the second component
+    /// of their gain, or is zero if it is not given.
+    ///
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::dc_circuits::*;
+    /// use neapolitan::transient::*;
+    ///
+    /// // An RC circuit charging from a 1 V source
+    /// let result = NodalAnalysisStudyBuilder::new(TRANSIENT_CIRCUIT.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .configure_node(0, vec![0.0], true, None)
+    ///     .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0]).unwrap()
+    ///     .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+    ///     .add_element(CAPACITOR,      2, 0, vec![1.0]).unwrap()
+    ///     .run_transient_study(0.001, 1.0, 1E-9, 100)
+    ///     .unwrap();
+    ///
+    /// // After one time constant, the capacitor has charged to about 1 - 1/e
+    /// let v = result.get_node_history("2").unwrap().last().unwrap()[0];
+    /// assert!((v - (1.0 - (-1.0_f64).exp())).abs() < 1E-3);
+    /// ```
+    pub fn run_transient_study(self, dt: f64, t_end: f64, margin: f64, limit: usize) -> anyhow::Result<TransientStudyResult>
+    {
+        self.run_transient_study_with_options(dt, t_end, &SolverOptions::new(margin, limit))
+    }
+
+    /// Steps the model through time exactly as `run_transient_study` does, but solves 
+    /// each step with the solver settings in `options`.
+    pub fn run_transient_study_with_options(self, dt: f64, t_end: f64, options: &SolverOptions) -> anyhow::Result<TransientStudyResult>
+    {
+        if dt <= 0.0 || t_end < dt || !dt.is_finite() || !t_end.is_finite()
+        {
+            return Err(TransientStudyError::InvalidTimeStep.into());
+        }
+
+        // Find the initial state of every stateful element, however its type was written
+        let mut states = HashMap::new();
+        let mut capacitors = HashSet::new();
+        for (idx, element) in self.model.elements.iter().enumerate()
+        {
+            let error = match self.resolve_element_type(&element.element_type, element.input).as_str()
+            {
+                CAPACITOR =>
+                {
+                    capacitors.insert(idx);
+                    TransientElementError::CapacitorParameters
+                },
+                INDUCTOR => TransientElementError::InductorParameters,
+                _ => continue,
+            };
+
+            match element.gain.len()
+            {
+                1 => states.insert(idx, 0.0),
+                2 => states.insert(idx, element.gain[1]),
+                _ => return Err(error.into()),
+            };
+        }
+
+        let steps = (t_end / dt).round() as usize;
+        let mut result = TransientStudyResult
+        {
+            times: vec![],
+            nodes: HashMap::new(),
+            elements: HashMap::new(),
+        };
+
+        // Give every stateful element its initial state so that the model can be built
+        let mut builder = self;
+        for (&idx, &state) in &states
+        {
+            let gain = &mut builder.model.elements[idx].gain;
+            gain.truncate(1);
+            gain.extend([dt, state]);
+        }
+
+        // Only the states of the stateful elements change between steps, 
+        // so the model is only built once
+        let mut study = builder.compile()?;
+
+        for step in 1..=steps
+        {
+            for (&idx, &state) in &states
+            {
+                let mut gain = study.get_model().elements[idx].gain.clone();
+                gain[2] = state;
+                study.set_gain(idx, gain)?;
+            }
+
+            let soln = study.solve_with_options(options)?;
+
+            for (&idx, state) in states.iter_mut()
+            {
+                let model = study.get_model();
+                let element = &model.elements[idx];
+                *state = match capacitors.contains(&idx)
+                {
+                    true => soln.nodes[&model.get_node_name(element.input)][0] - soln.nodes[&model.get_node_name(element.output)][0],
+                    false => soln.elements[&model.get_element_name(idx)][0],
+                };
+            }
+
+            result.add_step(step as f64 * dt, soln);
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,149 @@
+// Std modules
+use std::fmt::Write;
+
+// Local modules
+use crate::errors::VtkExportError;
+use crate::modelling::NodalAnalysisModel;
+use crate::NodalAnalysisStudyResult;
+
+/// The VTK cell type of a line between two points.
+const VTK_LINE: u8 = 3;
+/// The VTK cell type of a chain of lines through several points.
+const VTK_POLY_LINE: u8 = 4;
+
+/// Writes the values of a point or cell array to `vtk`, one tuple per line. Every tuple 
+/// is padded with zeros to the length of the longest, as VTK arrays have a fixed number
+/// of components.
+fn write_array(vtk: &mut String, name: &str, tuples: &[&[f64]]) -> std::fmt::Result
+{
+    let components = tuples.iter().map(|tuple| tuple.len()).max().unwrap_or(0).max(1);
+    writeln!(vtk, "{name} {components} {} double", tuples.len())?;
+    for tuple in tuples
+    {
+        let values: Vec<String> = (0..components)
+            .map(|idx| tuple.get(idx).copied().unwrap_or_default().to_string())
+            .collect();
+        writeln!(vtk, "{}", values.join(" "))?;
+    }
+    Ok(())
+}
+
+impl NodalAnalysisStudyResult
+{
+    /// Writes these results as a legacy ASCII VTK unstructured grid (a `.vtk` file), so that
+    /// the potentials and fluxes of spatial networks such as heat-transfer or hydraulic models
+    /// can be visualized in ParaView or other VTK-based tools. `model` must be the model that
+    /// these results are a solution of.
+    /// 
+    /// Each node becomes a point at its coordinates, so every node must have been placed with
+    /// `NodalAnalysisStudyBuilder::place_node` (or read from a model file that placed it with 
+    /// `x`, `y` and `z` metadata). Each element becomes a line between its input 
+    /// and output nodes, or a chain of lines through its terminals. 
+    /// Potentials are written as the `potential` point array and fluxes as the `flux` cell 
+    /// array, with one component per component of the potentials and fluxes.
+    /// 
+    /// # Example
+    /// ```
+    /// use neapolitan::NodalAnalysisStudyBuilder;
+    /// use neapolitan::heat_transfer::*;
+    /// 
+    /// let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+    ///     .unwrap()
+    ///     .add_nodes(3)
+    ///     .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+    ///     .place_node(1, [1.0, 0.0, 0.0]).unwrap()
+    ///     .place_node(2, [2.0, 0.0, 0.0]).unwrap()
+    ///     .configure_node(0, vec![100.0], true, None)
+    ///     .configure_node(1, vec![50.0], false, None)
+    ///     .configure_node(2, vec![0.0], true, None)
+    ///     .add_element(CONDUCTOR, 0, 1, vec![2.0]).unwrap()
+    ///     .add_element(CONDUCTOR, 1, 2, vec![2.0]).unwrap();
+    /// 
+    /// let model = builder.get_model().clone();
+    /// let vtk = builder.run_study(1E-9, 100).unwrap().to_vtk(&model).unwrap();
+    /// 
+    /// assert!(vtk.contains("POINTS 3 double\n0 0 0\n1 0 0\n2 0 0\n"));
+    /// assert!(vtk.contains("CELLS 2 6\n2 0 1\n2 1 2\n"));
+    /// assert!(vtk.contains("flux 1 2 double\n100\n100\n"));
+    /// ```
+    pub fn to_vtk(&self, model: &NodalAnalysisModel) -> anyhow::Result<String>
+    {
+        let mut points = vec![];
+        let mut potentials = vec![];
+        for node in 0..model.nodes
+        {
+            let name = model.get_node_name(node);
+            match model.get_node_coordinates(node)
+            {
+                Some(position) => points.push(position),
+                None => return Err(VtkExportError::MissingCoordinates(name).into()),
+            }
+
+            match self.get_node_potential(&name)
+            {
+                Some(potential) => potentials.push(potential),
+                None => return Err(VtkExportError::MissingPotential(name).into()),
+            }
+        }
+
+        let mut cells = vec![];
+        let mut fluxes = vec![];
+        for (idx, element) in model.elements.iter().enumerate()
+        {
+            cells.push(match element.terminals.is_empty()
+            {
+                true => vec![element.input, element.output],
+                false => element.terminals.clone(),
+            });
+
+            let name = model.get_element_name(idx);
+            match self.get_element_flux(&name)
+            {
+                Some(flux) => fluxes.push(flux),
+                None => return Err(VtkExportError::MissingFlux(name).into()),
+            }
+        }
+
+        let mut vtk = String::new();
+        writeln!(vtk, "# vtk DataFile Version 3.0")?;
+        writeln!(vtk, "neapolitan {} results", model.model_type)?;
+        writeln!(vtk, "ASCII")?;
+        writeln!(vtk, "DATASET UNSTRUCTURED_GRID")?;
+
+        writeln!(vtk, "POINTS {} double", points.len())?;
+        for [x, y, z] in &points
+        {
+            writeln!(vtk, "{x} {y} {z}")?;
+        }
+
+        // Each cell is listed as its number of points followed by the points' indices
+        let size: usize = cells.iter().map(|cell| cell.len() + 1).sum();
+        writeln!(vtk, "CELLS {} {size}", cells.len())?;
+        for cell in &cells
+        {
+            let nodes: Vec<String> = cell.iter().map(|node| node.to_string()).collect();
+            writeln!(vtk, "{} {}", cell.len(), nodes.join(" "))?;
+        }
+
+        writeln!(vtk, "CELL_TYPES {}", cells.len())?;
+        for cell in &cells
+        {
+            let cell_type = match cell.len()
+            {
+                2 => VTK_LINE,
+                _ => VTK_POLY_LINE,
+            };
+            writeln!(vtk, "{cell_type}")?;
+        }
+
+        writeln!(vtk, "POINT_DATA {}", points.len())?;
+        writeln!(vtk, "FIELD potentials 1")?;
+        write_array(&mut vtk, "potential", &potentials)?;
+
+        writeln!(vtk, "CELL_DATA {}", cells.len())?;
+        writeln!(vtk, "FIELD fluxes 1")?;
+        write_array(&mut vtk, "flux", &fluxes)?;
+
+        Ok(vtk)
+    }
+}
@@ -0,0 +1,206 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::dc_circuits::{RESISTOR, DC_CIRCUIT, VOLTAGE_SOURCE};
+
+#[test]
+fn ensure_that_corner_analysis_reports_extremes_of_selected_corners()
+{
+    use std::collections::{BTreeMap, HashMap};
+    use neapolitan::corner_analysis::{Corner, GainBounds};
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap();
+
+    let bounds = HashMap::from([
+        (1, GainBounds { min: vec![0.5], max: vec![2.0] }),
+        (2, GainBounds { min: vec![0.5], max: vec![2.0] }),
+    ]);
+
+    // Bad bounds are rejected before anything is solved
+    assert!(builder.clone()
+        .run_corner_analysis(HashMap::from([(7, GainBounds { min: vec![1.0], max: vec![1.0] })]), None, 1E-9, 100)
+        .is_err());
+
+    let corners = vec![
+        BTreeMap::from([(1, Corner::Typ), (2, Corner::Max)]),
+        BTreeMap::from([(1, Corner::Max), (2, Corner::Typ)]),
+    ];
+    let result = builder
+        .run_corner_analysis(bounds, Some(corners), 1E-9, 100)
+        .expect("failed to run corner analysis");
+
+    let v2 = result.get_node_extremes("2").unwrap()[0];
+    assert!((v2.max - 20.0 / 3.0).abs() < 1E-6);
+    assert_eq!(v2.max_corner, 0);
+    assert!((v2.min - 10.0 / 3.0).abs() < 1E-6);
+    assert_eq!(v2.min_corner, 1);
+
+    let i = result.get_element_extremes("resistor.1").unwrap()[0];
+    assert!((i.max - 10.0 / 3.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_goal_seek_finds_source_voltage_and_rejects_bad_parameters()
+{
+    use neapolitan::goal_seek::ModelParameter;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       3, 0, vec![1.0]).unwrap();
+
+    assert!(builder.clone()
+        .solve_for_parameter(3, 1.0, ModelParameter { element: 1, component: 1 }, 1.0, 1E-9, 100)
+        .is_err());
+    assert!(builder.clone()
+        .solve_for_parameter(9, 1.0, ModelParameter { element: 1, component: 0 }, 1.0, 1E-9, 100)
+        .is_err());
+
+    // Node 3 sits at a quarter of the source voltage
+    let (v, soln) = builder
+        .solve_for_parameter(3, 2.5, ModelParameter { element: 0, component: 0 }, 1.0, 1E-9, 100)
+        .expect("failed to goal-seek source voltage");
+
+    assert!((v - 10.0).abs() < 1E-6);
+    let soln = serde_json::to_value(soln).unwrap();
+    assert!((soln["nodes"]["3"][0].as_f64().unwrap() - 2.5).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_sweeps_vary_gains_and_locked_potentials_together()
+{
+    use neapolitan::dc_circuits::DIODE;
+    use neapolitan::sweep::{linspace, SweepParameter};
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+
+    // Raising the ground and the supply together leaves 5 V across the resistor and diode
+    let parameters = [
+        SweepParameter::Potential { node: 0, component: 0 },
+        SweepParameter::Gain { element: 1, component: 0 },
+    ];
+    let result = builder.clone()
+        .run_sweep(&parameters, &[linspace(0.0, 2.0, 5), linspace(1000.0, 2000.0, 5)], 1E-9, 100)
+        .unwrap();
+
+    assert_eq!(result.get_results().len(), 5);
+    let mut last_drop = f64::INFINITY;
+    for (point, soln) in result.get_points().iter().zip(result.get_results())
+    {
+        let soln = serde_json::to_value(soln).unwrap();
+        let ground = soln["nodes"]["0"][0].as_f64().unwrap();
+        let anode = soln["nodes"]["2"][0].as_f64().unwrap();
+
+        assert_eq!(ground, point[0]);
+        assert!((soln["nodes"]["1"][0].as_f64().unwrap() - (point[0] + 5.0)).abs() < 1E-9);
+
+        // A larger resistor leaves less current, and so a smaller drop, across the diode
+        assert!(anode - ground < last_drop);
+        last_drop = anode - ground;
+    }
+
+    assert!(builder.clone().run_sweep(&parameters, &[vec![0.0]], 1E-9, 100).is_err());
+    assert!(builder.clone().run_sweep(&[SweepParameter::Gain { element: 1, component: 1 }], &[vec![1.0]], 1E-9, 100).is_err());
+    assert!(builder.run_sweep(&[SweepParameter::Potential { node: 2, component: 0 }], &[vec![1.0]], 1E-9, 100).is_err());
+}
+
+#[test]
+fn ensure_that_monte_carlo_statistics_are_reproducible_and_match_the_distribution()
+{
+    use std::collections::HashMap;
+    use neapolitan::monte_carlo::GainDistribution;
+
+    // The voltage across a resistor fed by a current source is directly proportional to its resistance
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(neapolitan::dc_circuits::CURRENT_SOURCE, 0, 1, vec![1.0]).unwrap()
+        .add_labeled_element("R", RESISTOR, 1, 0, vec![100.0]).unwrap();
+
+    let run = |seed| builder.clone()
+        .run_monte_carlo(HashMap::from([(1, GainDistribution::Normal { sigma: 0.01 })]), 2000, seed, 1E-9, 100)
+        .unwrap();
+
+    let result = run(7);
+    assert_eq!(result, run(7));
+    assert_ne!(result, run(8));
+    assert_eq!(result.get_samples(), 2000);
+
+    let v1 = result.get_node_statistics("1").unwrap()[0];
+    assert!((v1.mean - 100.0).abs() < 0.1);
+    assert!((v1.std_dev - 1.0).abs() < 0.1);
+    assert!(v1.min < 98.0 && v1.max > 102.0);
+
+    // The source's current is not toleranced
+    let i = result.get_element_statistics("R").unwrap()[0];
+    assert!((i.mean - 1.0).abs() < 1E-9 && i.std_dev < 1E-9);
+
+    assert!(builder.clone().run_monte_carlo(HashMap::from([(2, GainDistribution::Uniform { tolerance: 0.05 })]), 10, 0, 1E-9, 100).is_err());
+    assert!(builder.clone().run_monte_carlo(HashMap::from([(1, GainDistribution::Uniform { tolerance: -0.05 })]), 10, 0, 1E-9, 100).is_err());
+    assert!(builder.run_monte_carlo(HashMap::new(), 0, 0, 1E-9, 100).is_err());
+}
+
+#[test]
+fn ensure_that_batches_of_models_solve_in_order_on_multiple_threads()
+{
+    use neapolitan::batch::{solve_models_parallel, solve_studies_parallel};
+    use neapolitan::dc_circuits::DIODE;
+    use neapolitan::options::SolverOptions;
+
+    let options = SolverOptions::new(1E-12, 100);
+    let builders: Vec<_> = (0..24)
+        .map(|k| NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .unwrap()
+            .add_nodes(3)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0 + k as f64]).unwrap()
+            .add_element(RESISTOR,       1, 2, vec![100.0]).unwrap()
+            .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap())
+        .collect();
+
+    // A model that cannot be built fails in its place without stopping the rest
+    let mut models: Vec<_> = builders.iter().map(|builder| builder.get_model().clone()).collect();
+    models[7] = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .add_element(RESISTOR, 0, 1, vec![1.0]).unwrap()
+        .get_model()
+        .clone();
+
+    let results = solve_models_parallel(models, &options);
+    assert_eq!(results.len(), 24);
+    for (k, (builder, result)) in builders.iter().zip(&results).enumerate()
+    {
+        if k == 7
+        {
+            assert!(result.is_err());
+            continue;
+        }
+
+        let serial = builder.clone().run_study_with_options(&options).unwrap();
+        assert_eq!(result.as_ref().unwrap().get_node_potential(2), serial.get_node_potential(2));
+    }
+
+    let studies = builders.into_iter().map(|builder| builder.compile().unwrap()).collect();
+    let results = solve_studies_parallel(studies, &options);
+    for (k, result) in results.iter().enumerate()
+    {
+        let current = result.as_ref().unwrap().get_element_flux(1).unwrap()[0];
+        assert!(current > 0.0 && current < (1.0 + k as f64) / 100.0);
+    }
+}
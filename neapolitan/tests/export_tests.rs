@@ -0,0 +1,297 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::dc_circuits::{resistor, RESISTOR, DC_CIRCUIT, VOLTAGE_SOURCE};
+
+#[test]
+fn ensure_that_exported_equation_deck_matches_study_solution()
+{
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       3, 0, vec![1.0]).unwrap();
+
+    let deck = builder.get_model().to_equation_system().expect("failed to export model");
+    let (_log, deck_soln) = nexsys::solve_with_preprocessors(&deck, 1E-9, 100).expect("failed to solve deck");
+
+    let study_soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    for node in 0..4
+    {
+        let expected = study_soln["nodes"][node.to_string()][0].as_f64().unwrap();
+        assert!((deck_soln[&format!("node_{node}")] - expected).abs() < 1E-6);
+    }
+    assert!((deck_soln["resistor_1"] - 0.75).abs() < 1E-6);
+    assert!((deck_soln["voltage_source_0"] - 0.75).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_voltage_source_driving_its_input_node_matches_exported_deck()
+{
+    // The source's output is grounded, so it drives the potential of its input node
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 1, 0, vec![3.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap();
+
+    let deck = builder.get_model().to_equation_system().expect("failed to export model");
+    let (_log, deck_soln) = nexsys::solve_with_preprocessors(&deck, 1E-9, 100).expect("failed to solve deck");
+
+    let study_soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    for node in 1..3
+    {
+        let expected = study_soln["nodes"][node.to_string()][0].as_f64().unwrap();
+        assert!((deck_soln[&format!("node_{node}")] - expected).abs() < 1E-6);
+    }
+    
+    let source_flux = study_soln["elements"]["voltage_source.0"][0].as_f64().unwrap();
+    assert!((deck_soln["voltage_source_0"] - source_flux).abs() < 1E-6);
+    assert!((source_flux - 1.5).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_anonymized_models_keep_topology_and_numerics()
+{
+    use std::collections::HashMap;
+    use neapolitan::NodalAnalysisStudyConfigurator;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, Some(HashMap::from([("acme_supply_rail".to_string(), 3.3)])))
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap();
+
+    let anonymized = builder.get_model().anonymize();
+    assert!(!serde_json::to_string(&anonymized).unwrap().contains("acme_supply_rail"));
+
+    let original = builder.run_study(1E-9, 100).unwrap();
+    let copy = NodalAnalysisStudyBuilder::from_model_with_default_config(anonymized).run_study(1E-9, 100).unwrap();
+    assert_eq!(original, copy);
+
+    // Element and model types that are not built in are renamed consistently
+    let config = HashMap::from([(
+        "acme_hydraulics".to_string(), 
+        NodalAnalysisStudyConfigurator::new(1)
+            .add_element_type("acme_valve", resistor).unwrap()
+            .add_element_type("acme_pump", neapolitan::dc_circuits::voltage_source).unwrap()
+    )]);
+    let model = NodalAnalysisStudyBuilder::new("acme_hydraulics".to_string(), Some(config))
+        .unwrap()
+        .add_nodes(3)
+        .add_element("acme_pump",  0, 1, vec![3.0]).unwrap()
+        .add_element("acme_valve", 1, 2, vec![2.0]).unwrap()
+        .add_element("acme_valve", 2, 0, vec![1.0]).unwrap()
+        .get_model()
+        .anonymize();
+
+    let json = serde_json::to_value(&model).unwrap();
+    assert_eq!(json["model_type"], "custom_model");
+    assert_eq!(json["elements"][0]["element_type"], "custom_element_0");
+    assert_eq!(json["elements"][1]["element_type"], "custom_element_1");
+    assert_eq!(json["elements"][2]["element_type"], "custom_element_1");
+    assert_eq!(json["elements"][2]["gain"][0], 1.0);
+}
+
+#[test]
+fn ensure_that_strict_model_validation_reports_the_path_to_mistakes()
+{
+    use neapolitan::modelling::NodalAnalysisModel;
+    use neapolitan::schema::validate_model;
+    use serde_json::json;
+
+    let example = std::fs::read_to_string("neapolitan_json/circuit.json").unwrap();
+    assert!(NodalAnalysisModel::from_json_strict(&example).is_ok());
+
+    let model = |element: serde_json::Value| json!({
+        "model_type": "ssdc_circuit",
+        "nodes": 2,
+        "configuration": { "0": { "potential": [0.0], "is_locked": true } },
+        "elements": [
+            { "element_type": "voltage_source", "input": 0, "output": 1, "gain": [1.0] },
+            element,
+        ]
+    });
+    let error = |element| validate_model(&model(element)).unwrap_err().to_string();
+
+    assert!(validate_model(&model(json!({ "element_type": "resistor", "input": 1, "output": 0, "gain": [1.0] }))).is_ok());
+    assert_eq!(error(json!({ "element_type": "resistor", "input": 1, "output": 0, "gain": 1.0 })), "elements[1].gain: expected array of numbers");
+    assert_eq!(error(json!({ "element_type": "resistor", "input": -1, "output": 0, "gain": [1.0] })), "elements[1].input: expected non-negative integer");
+    assert_eq!(error(json!({ "element_type": "resistor", "input": 1, "gain": [1.0] })), "elements[1].output: missing required property");
+    assert_eq!(error(json!({ "element_type": "resistor", "input": 1, "output": 0, "gain": [1.0], "gian": [] })), "elements[1].gian: unknown property");
+    assert_eq!(
+        error(json!({ "element_type": "current_controlled_current_source", "input": 1, "output": 0, "gain": [1.0], "control": { "flux": { "element": "0" } } })), 
+        "elements[1].control.flux.element: expected non-negative integer"
+    );
+
+    let mut bad_config = model(json!({ "element_type": "resistor", "input": 1, "output": 0, "gain": [1.0] }));
+    bad_config["configuration"]["first"] = bad_config["configuration"]["0"].clone();
+    assert_eq!(validate_model(&bad_config).unwrap_err().to_string(), "configuration.first: unknown property");
+}
+
+#[test]
+fn ensure_that_models_round_trip_through_every_file_format()
+{
+    use neapolitan::formats::FileFormat;
+    use neapolitan::modelling::NodalAnalysisModel;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_named_node("gnd").unwrap()
+        .add_named_node("vcc").unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_labeled_element("R1", RESISTOR,       1, 0, vec![2.0]).unwrap();
+
+    let expected = serde_json::to_value(builder.clone().run_study(1E-9, 100).unwrap()).unwrap();
+
+    for format in [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml]
+    {
+        let text = format.write(builder.get_model()).unwrap();
+        let model = NodalAnalysisModel::from_str_strict(&text, format).unwrap();
+        assert_eq!(&model, builder.get_model());
+
+        let soln = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+            .run_study(1E-9, 100)
+            .unwrap();
+        let soln: serde_json::Value = format.read(&format.write(&soln).unwrap()).unwrap();
+        assert_eq!(soln["nodes"], expected["nodes"]);
+        assert_eq!(soln["elements"], expected["elements"]);
+    }
+
+    // Models in any format are checked against the schema
+    let toml = "model_type = \"dc_circuit\"\nnodes = 2\nconfiguration = {}\n\n[[elements]]\nelement_type = \"resistor\"\ninput = 0\noutput = 1\ngain = [\"1k\"]";
+    let err = NodalAnalysisModel::from_str_strict(toml, FileFormat::Toml).unwrap_err();
+    assert_eq!(err.to_string(), "elements[0].gain: expected array of numbers");
+    assert_eq!(FileFormat::from_path("models/divider.yml"), Some(FileFormat::Yaml));
+    assert!(FileFormat::from_name("xml").is_err());
+}
+
+#[test]
+fn ensure_that_element_type_aliases_resolve_when_models_are_loaded()
+{
+    use neapolitan::modelling::NodalAnalysisModel;
+
+    let json = r#"{
+        "model_type": "dc_circuit",
+        "nodes": 2,
+        "configuration": { "0": { "potential": [0.0], "is_locked": true, "metadata": null } },
+        "elements": [
+            { "element_type": "VSRC",     "input": 0, "output": 1, "gain": [5.0] },
+            { "element_type": "res",      "input": 1, "output": 0, "gain": [2.0] },
+            { "element_type": "Resistor", "input": 1, "output": 0, "gain": [2.0] }
+        ]
+    }"#;
+
+    let model = NodalAnalysisModel::from_json_strict(json).unwrap();
+    let builder = NodalAnalysisStudyBuilder::from_model_with_default_config(model);
+
+    // Only the element types' own names appear in the model and its results
+    let deck = builder.get_model().to_equation_system().unwrap();
+    assert!(deck.contains("node_1 - node_0 = 5"));
+    assert!(deck.contains("resistor_2 = (node_1 - node_0) / 2"));
+
+    let soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    assert!((soln["elements"]["resistor.1"][0].as_f64().unwrap() - 2.5).abs() < 1E-9);
+    assert!((soln["elements"]["voltage_source.0"][0].as_f64().unwrap() - 5.0).abs() < 1E-9);
+
+    // Unknown names are still reported as they were written
+    let err = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element("resistr", 1, 0, vec![2.0]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap_err();
+    assert!(err.to_string().contains("'resistr'"));
+}
+
+#[test]
+fn ensure_that_configurators_round_trip_through_their_registered_names()
+{
+    use serde::de::DeserializeSeed;
+    use neapolitan::{default_study_builder_config, NodalAnalysisStudyConfigurator};
+    use neapolitan::flux_formulas::fourth_power_flux;
+    use std::collections::HashMap;
+
+    let registry = default_study_builder_config()[DC_CIRCUIT].clone()
+        .add_flux_formula("radiation", fourth_power_flux).unwrap();
+    let config = registry.clone()
+        .add_flux_element_type("radiator", "radiation").unwrap()
+        .add_alias("rad", "radiator").unwrap();
+
+    let json = serde_json::to_string(&config).unwrap();
+    let read = (&registry).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    assert_eq!(read, config);
+
+    // Element types read from a file can be used by models like any other
+    let soln = NodalAnalysisStudyBuilder::new("custom".to_string(), Some(HashMap::from([("custom".to_string(), read)])))
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_labeled_element("V", "vsrc", 0, 1, vec![2.0]).unwrap()
+        .add_labeled_element("X", "rad",  1, 0, vec![0.5]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+    let soln = serde_json::to_value(soln).unwrap();
+    assert!((soln["elements"]["X"][0].as_f64().unwrap() - 8.0).abs() < 1E-9);
+
+    // Names that the registry does not know cannot be read
+    let base = NodalAnalysisStudyConfigurator::new(1);
+    let err = (&base).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap_err();
+    assert!(err.to_string().contains("no element type named"));
+    let err = (&base).deserialize(&mut serde_json::Deserializer::from_str(r#"{"dimension": 1, "flux_elements": {"x": "radiation"}}"#)).unwrap_err();
+    assert!(err.to_string().contains("'radiation'"));
+}
+
+#[test]
+fn ensure_that_vtk_exports_place_every_node_and_element()
+{
+    use neapolitan::errors::VtkExportError;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(4)
+        .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+        .place_node(1, [0.0, 1.0, 0.0]).unwrap()
+        .place_node(2, [1.0, 1.0, 0.0]).unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       3, 0, vec![2.0]).unwrap();
+
+    // Node 3 has not been placed yet
+    let model = builder.get_model().clone();
+    let soln = builder.clone().run_study(1E-9, 100).unwrap();
+    let err = soln.to_vtk(&model).unwrap_err();
+    assert!(matches!(err.downcast_ref::<VtkExportError>(), Some(VtkExportError::MissingCoordinates(node)) if node == "3"));
+
+    let builder = builder.place_node(3, [1.0, 0.0, 0.0]).unwrap();
+    let model = builder.get_model().clone();
+    let soln = builder.run_study(1E-9, 100).unwrap();
+    let vtk = soln.to_vtk(&model).unwrap();
+
+    let lines: Vec<&str> = vtk.lines().collect();
+    assert_eq!(lines[3], "DATASET UNSTRUCTURED_GRID");
+    assert_eq!(&lines[4..9], &["POINTS 4 double", "0 0 0", "0 1 0", "1 1 0", "1 0 0"]);
+    assert_eq!(&lines[9..14], &["CELLS 4 12", "2 0 1", "2 1 2", "2 2 3", "2 3 0"]);
+    assert_eq!(&lines[14..19], &["CELL_TYPES 4", "3", "3", "3", "3"]);
+
+    // Each point carries its potential and each cell its flux, in model order
+    let potential_idx = lines.iter().position(|&line| line == "potential 1 4 double").unwrap();
+    let potentials: Vec<f64> = lines[potential_idx + 1..potential_idx + 5].iter().map(|x| x.parse().unwrap()).collect();
+    for (node, potential) in potentials.iter().enumerate()
+    {
+        assert_eq!(*potential, soln.get_node_potential(node).unwrap()[0]);
+    }
+
+    let flux_idx = lines.iter().position(|&line| line == "flux 1 4 double").unwrap();
+    let current: f64 = lines[flux_idx + 2].parse().unwrap();
+    assert!((current - 2.0).abs() < 1E-9);
+}
@@ -0,0 +1,146 @@
+use std::rc::Rc;
+
+use neapolitan::{set_node_potential, NodalAnalysisStudyBuilder};
+use neapolitan::dc_circuits::RESISTOR;
+use neapolitan::harmonic_balance::*;
+use neapolitan::modelling::node::GenericNode;
+
+#[test]
+fn ensure_that_cubic_conductor_generates_third_harmonic()
+{
+    let node1 = GenericNode::new();
+    let node2 = GenericNode::new();
+
+    // v = cos(wt) across the element
+    set_node_potential(&Rc::downgrade(&node1), vec![0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+    set_node_potential(&Rc::downgrade(&node2), vec![0.0; 7]).unwrap();
+
+    // i = v^3 = 3/4 cos(wt) + 1/4 cos(3wt)
+    let elem = nonlinear_conductor(Rc::downgrade(&node1), Rc::downgrade(&node2), vec![0.0, 0.0, 1.0]).unwrap();
+    let flux: Vec<f64> = elem.get_flux().unwrap().into();
+
+    let expected = [0.0, 0.75, 0.0, 0.0, 0.0, 0.25, 0.0];
+    for (x, y) in flux.iter().zip(expected)
+    {
+        assert!((x - y).abs() < 1E-10);
+    }
+}
+
+#[test]
+fn ensure_that_harmonic_balance_study_satisfies_kcl_over_a_period()
+{
+    let soln = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        //           Element                  Input   Output  Gain
+        .add_element(PERIODIC_VOLTAGE_SOURCE, 0,      1,      vec![0.5, 2.0, 1.0]).unwrap()
+        .add_element(RESISTOR,                1,      2,      vec![2.0]).unwrap()
+        .add_element(NONLINEAR_CONDUCTOR,     2,      0,      vec![1.0, 0.0, 1.0]).unwrap()
+        .run_study(1E-10, 100)
+        .expect("failed to solve model");
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let potential = |node: &str| -> Vec<f64> {
+        serde_json::from_value(soln["nodes"][node].clone()).unwrap()
+    };
+
+    let v1 = sample_waveform(&potential("1"), 32);
+    let v2 = sample_waveform(&potential("2"), 32);
+
+    // The fundamental drives a third harmonic through the cubic term
+    assert!(potential("2")[5].abs() > 1E-3);
+
+    // Truncating to 3 harmonics leaves a small residual from the 5th and above
+    for (a, b) in v1.iter().zip(v2)
+    {
+        let resistor_current = (a - b) / 2.0;
+        let conductor_current = b + b.powi(3);
+        assert!((resistor_current - conductor_current).abs() < 5E-2);
+    }
+}
+
+#[test]
+fn ensure_that_multi_winding_transformer_conserves_power()
+{
+    let soln = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(6)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(PERIODIC_VOLTAGE_SOURCE, 0, 1, vec![1.0, 12.0, 3.0]).unwrap()
+        .add_element(RESISTOR,                1, 2, vec![0.5]).unwrap()
+        .add_transformer(2, 0, &[(3, 0, 2.0), (4, 5, -4.0)]).unwrap()
+        .add_element(RESISTOR,                3, 0, vec![6.0]).unwrap()
+        .add_element(RESISTOR,                4, 5, vec![2.0]).unwrap()
+        .add_element(RESISTOR,                5, 0, vec![1.0]).unwrap()
+        .run_study(1E-10, 100)
+        .expect("failed to solve model");
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let vector = |kind: &str, name: &str| -> Vec<f64> {
+        serde_json::from_value(soln[kind][name].clone()).unwrap()
+    };
+
+    let primary = vector("nodes", "2");
+    let first = vector("nodes", "3");
+    let second: Vec<f64> = vector("nodes", "4").iter().zip(vector("nodes", "5")).map(|(a, b)| a - b).collect();
+    
+    // Each secondary's voltage is the primary's voltage scaled by its turns ratio
+    for i in 0..primary.len()
+    {
+        assert!((first[i] - primary[i] / 2.0).abs() < 1E-9);
+        assert!((second[i] + primary[i] / 4.0).abs() < 1E-9);
+    }
+
+    // Every component of the power drawn by the primary is delivered to the loads
+    let primary_current: Vec<f64> = vector("elements", "transformer_primary.3").iter()
+        .zip(vector("elements", "transformer_primary.5"))
+        .map(|(a, b)| a + b)
+        .collect();
+    let load_current = vector("elements", "resistor.6");
+    let divider_current = vector("elements", "resistor.7");
+    for i in 0..primary.len()
+    {
+        let drawn = primary[i] * primary_current[i];
+        let delivered = first[i] * load_current[i] + second[i] * divider_current[i];
+        assert!((drawn - delivered).abs() < 1E-9);
+    }
+
+    // The winding must have a single, nonzero turns ratio
+    let builder = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(PERIODIC_VOLTAGE_SOURCE, 0, 1, vec![0.0, 1.0]).unwrap()
+        .add_element(RESISTOR,                2, 0, vec![1.0]).unwrap();
+    assert!(builder.clone().add_transformer(1, 0, &[(2, 0, 0.0)]).unwrap().run_study(1E-9, 100).is_err());
+    assert!(builder.add_element(TRANSFORMER_SECONDARY, 2, 0, vec![1.0, 2.0]).unwrap().run_study(1E-9, 100).is_err());
+}
+
+#[test]
+fn ensure_that_csv_results_have_a_column_per_harmonic()
+{
+    let soln = NodalAnalysisStudyBuilder::new(HARMONIC_BALANCE.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(PERIODIC_VOLTAGE_SOURCE,         0, 1, vec![0.5, 2.0, 1.0]).unwrap()
+        .add_labeled_element("R1, \"load\"", RESISTOR, 1, 2, vec![2.0]).unwrap()
+        .add_element(NONLINEAR_CONDUCTOR,             2, 0, vec![1.0, 0.0, 1.0]).unwrap()
+        .run_study(1E-10, 100)
+        .expect("failed to solve model");
+
+    let csv = soln.to_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    // Labels that would break the csv are quoted
+    let header = lines[0];
+    assert!(header.starts_with("node:0,node:0[1],node:0[2],node:0[3],node:0[4],node:0[5],node:0[6],node:1,"));
+    assert!(header.contains(",\"element:R1, \"\"load\"\"\",\"element:R1, \"\"load\"\"[1]\","));
+
+    // Every column has a value, which matches the result
+    let values: Vec<f64> = lines[1].split(',').map(|x| x.parse().unwrap()).collect();
+    let potential: Vec<f64> = serde_json::from_value(serde_json::to_value(&soln).unwrap()["nodes"]["2"].clone()).unwrap();
+    assert_eq!(&values[14..21], &potential[..]);
+}
@@ -0,0 +1,216 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::heat_transfer::*;
+
+#[test]
+fn ensure_that_radiation_only_models_solve_from_the_default_guess()
+{
+    // The radiated heat rate's slope is zero at 0 K, so a cold start from zero cannot take a newton step
+    let soln = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![300.0], true, None)
+        .add_element(HEAT_FLUX,           0, 1, vec![100.0]).unwrap()
+        .add_element(RADIATION_INTERFACE, 1, 0, vec![0.8, 1.0]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    // The node radiates away exactly the heat put into it
+    let t = soln.get_node_potential(1).unwrap()[0];
+    assert!((0.8 * STEFAN_BOLTZMANN * (t.powi(4) - 300f64.powi(4)) - 100.0).abs() < 1E-6);
+    assert!((t - 318.6).abs() < 0.1);
+}
+
+#[test]
+fn ensure_that_geometric_conductors_export_their_length()
+{
+    use std::collections::HashMap;
+    use neapolitan::errors::EquationExportError;
+    use neapolitan::modelling::NodalAnalysisModel;
+
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![100.0], true, None)
+        .configure_node(1, vec![0.0], true, None)
+        .add_element(GEOMETRIC_CONDUCTOR, 0, 1, vec![2.0]).unwrap();
+
+    // Neither node has been placed yet
+    let err = builder.get_model().to_equation_system().unwrap_err();
+    assert!(matches!(err.downcast_ref::<EquationExportError>(), Some(EquationExportError::MissingCoordinates(0))));
+
+    let builder = builder
+        .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+        .place_node(1, [3.0, 4.0, 0.0]).unwrap();
+    let deck = builder.get_model().to_equation_system().unwrap();
+    assert!(deck.contains("geometric_conductor_0 = 2 / 5 * (node_0 - node_1)"));
+
+    // Models saved before `coordinates` existed placed their nodes with metadata
+    let legacy = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![100.0], true, Some(HashMap::from([("x".to_string(), 0.0), ("y".to_string(), 0.0)])))
+        .configure_node(1, vec![0.0], true, Some(HashMap::from([("x".to_string(), 3.0), ("y".to_string(), 4.0)])))
+        .add_element(GEOMETRIC_CONDUCTOR, 0, 1, vec![2.0]).unwrap();
+    let json = serde_json::to_string(legacy.get_model()).unwrap();
+    assert!(!json.contains("coordinates"));
+
+    let model: NodalAnalysisModel = serde_json::from_str(&json).unwrap();
+    assert_eq!(model.get_node_coordinates(1), Some([3.0, 4.0, 0.0]));
+    assert_eq!(model.to_equation_system().unwrap(), deck);
+}
+
+#[test]
+fn ensure_that_radiation_and_convection_balance_heat_input()
+{
+
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![300.0], true, None)
+        //           Element              Input   Output  Gain
+        .add_element(HEAT_FLUX,           0,      1,      vec![100.0]).unwrap()
+        .add_element(CONVECTION_INTERFACE, 1,     0,      vec![5.0]).unwrap()
+        .add_element(RADIATION_INTERFACE, 1,      0,      vec![0.8, 1.0]).unwrap();
+
+    let deck = builder.get_model().to_equation_system().expect("failed to export model");
+    assert!(deck.contains("radiation_interface_2 = 0.8 * 0.00000005670374419 * 1 * (node_1^4 - node_0^4)"));
+
+    let soln = builder.run_study(1E-9, 100).unwrap();
+    let convected = soln.get_derived_quantities("convection_interface.1").unwrap()["heat_rate"];
+    let radiated = soln.get_derived_quantities("radiation_interface.2").unwrap()["heat_rate"];
+    assert!((convected + radiated - 100.0).abs() < 1E-6);
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let t = soln["nodes"]["1"][0].as_f64().unwrap();
+
+    let lost = 5.0 * (t - 300.0) + 0.8 * STEFAN_BOLTZMANN * (t.powi(4) - 300.0_f64.powi(4));
+    assert!(t > 300.0);
+    assert!((lost - 100.0).abs() < 1E-6);
+    assert!((convected - 5.0 * (t - 300.0)).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_convection_radiation_interface_matches_parallel_elements()
+{
+
+    let base = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![300.0], true, None)
+        .add_element(HEAT_SOURCE, 1, 1, vec![250.0]).unwrap();
+
+    let combined = base.clone()
+        .add_element(CONVECTION_RADIATION_INTERFACE, 1, 0, vec![4.0, 0.9, 2.0]).unwrap();
+
+    let parallel = base
+        .add_element(CONVECTION_INTERFACE, 1, 0, vec![8.0]).unwrap()
+        .add_element(RADIATION_INTERFACE,  1, 0, vec![0.9, 2.0]).unwrap();
+
+    // Generated heat is only added to node 1, so the deck balances too
+    let deck = combined.get_model().to_equation_system().expect("failed to export model");
+    assert!(deck.contains("heat_source_0 = 250"));
+    assert!(deck.contains("heat_source_0 = convection_radiation_interface_1"));
+
+    let t = |builder: NodalAnalysisStudyBuilder| {
+        serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap()["nodes"]["1"][0].as_f64().unwrap()
+    };
+    assert!((t(combined) - t(parallel)).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_thermal_interface_matches_series_conductors()
+{
+
+    // A 0.5 m^2 stack of two layers with a contact resistance after each
+    let stack = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![300.0], true, None)
+        .add_element(HEAT_SOURCE,       1, 1, vec![100.0]).unwrap()
+        .add_element(THERMAL_INTERFACE, 1, 0, vec![0.5, 0.02, 4.0, 0.01, 0.1, 50.0, 0.03]).unwrap();
+
+    // The same stack as conductors in series, with a node at each boundary
+    let series = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(5)
+        .configure_node(0, vec![300.0], true, None)
+        .add_element(HEAT_SOURCE, 1, 1, vec![100.0]).unwrap()
+        .add_element(CONDUCTOR,   1, 2, vec![0.5 * 4.0 / 0.02]).unwrap()
+        .add_element(CONDUCTOR,   2, 3, vec![0.5 / 0.01]).unwrap()
+        .add_element(CONDUCTOR,   3, 4, vec![0.5 * 50.0 / 0.1]).unwrap()
+        .add_element(CONDUCTOR,   4, 0, vec![0.5 / 0.03]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    let deck = stack.get_model().to_equation_system().expect("failed to export model");
+    assert!(deck.contains("thermal_interface_1 = 0.5 / (0.02 / 4 + 0.01 + 0.1 / 50 + 0.03) * (node_1 - node_0)"));
+
+    let soln = stack.run_study(1E-9, 100).unwrap();
+    let derived = soln.get_derived_quantities("thermal_interface.1").unwrap().clone();
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let series = serde_json::to_value(series).unwrap();
+    let t = |soln: &serde_json::Value, node: &str| soln["nodes"][node][0].as_f64().unwrap();
+    assert!((t(&soln, "1") - t(&series, "1")).abs() < 1E-6);
+
+    let drops = [
+        ("layer_1_drop",   "1", "2"), 
+        ("contact_1_drop", "2", "3"), 
+        ("layer_2_drop",   "3", "4"), 
+        ("contact_2_drop", "4", "0"),
+    ];
+    for (drop, hot, cold) in drops
+    {
+        assert!((derived[drop] - (t(&series, hot) - t(&series, cold))).abs() < 1E-6);
+    }
+    assert!((derived["heat_rate"] - 100.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_placed_nodes_give_geometric_conductors_their_length()
+{
+    use neapolitan::modelling::NodalAnalysisModel;
+    use neapolitan::options::SolverOptions;
+
+    // A bar bent around three sides of a unit square, held at 90 degrees at one end and 0 at the other
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(4)
+        .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+        .place_node(1, [0.0, 1.0, 0.0]).unwrap()
+        .place_node(2, [1.0, 1.0, 0.0]).unwrap()
+        .place_node(3, [1.0, 0.0, 0.0]).unwrap()
+        .configure_node(0, vec![90.0], true, None)
+        .configure_node(3, vec![0.0], true, None)
+        .add_element(GEOMETRIC_CONDUCTOR, 0, 1, vec![1.0]).unwrap()
+        .add_element(GEOMETRIC_CONDUCTOR, 1, 2, vec![1.0]).unwrap()
+        .add_element(GEOMETRIC_CONDUCTOR, 2, 3, vec![1.0]).unwrap();
+
+    // Reconfiguring a node keeps its place
+    assert_eq!(builder.get_model().get_node_coordinates(0), Some([0.0, 0.0, 0.0]));
+    let soln = builder.clone().run_study_with_options(&SolverOptions::new(1E-9, 100)).unwrap();
+    assert!((soln.get_node_potential(1).unwrap()[0] - 60.0).abs() < 1E-9);
+    assert!((soln.get_element_flux(1).unwrap()[0] - 30.0).abs() < 1E-9);
+
+    // Coordinates survive a round trip through a model file
+    let json = serde_json::to_string(builder.get_model()).unwrap();
+    assert!(json.contains("\"coordinates\":[1.0,1.0,0.0]"));
+    let model: NodalAnalysisModel = serde_json::from_str(&json).unwrap();
+    assert_eq!(&model, builder.get_model());
+
+    // Changing a compiled conductor's conductivity still uses its length
+    let mut study = builder.clone().compile().unwrap();
+    study.set_gain(1, vec![2.0]).unwrap();
+    let soln = study.solve(1E-9, 100).unwrap();
+    assert!((soln.get_node_potential(1).unwrap()[0] - 90.0 * 0.6).abs() < 1E-9);
+
+    // Both nodes must be placed, at different points
+    let unplaced = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .place_node(0, [0.0, 0.0, 0.0]).unwrap()
+        .place_node(1, [0.0, 0.0, 0.0]).unwrap()
+        .configure_node(0, vec![10.0], true, None);
+    assert!(unplaced.clone().add_element(GEOMETRIC_CONDUCTOR, 0, 1, vec![1.0]).unwrap().run_study(1E-9, 100).is_err());
+    assert!(unplaced.add_element(GEOMETRIC_CONDUCTOR, 0, 2, vec![1.0]).unwrap().run_study(1E-9, 100).is_err());
+}
@@ -0,0 +1,162 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::dc_circuits::{RESISTOR, DC_CIRCUIT, VOLTAGE_SOURCE};
+
+#[test]
+fn ensure_that_inherited_configurations_resolve_collisions_by_rule()
+{
+    use std::collections::HashMap;
+
+    use neapolitan::{default_study_builder_config, CollisionRule, NodalAnalysisStudyConfigurator};
+    use neapolitan::heat_transfer::{conductor, HEAT_TRANSFER};
+    use neapolitan::harmonic_balance::HARMONIC_BALANCE;
+
+    let defaults = default_study_builder_config();
+
+    // A pack that (mis)defines resistors by their conductance, layered on the built-in DC elements
+    let pack = NodalAnalysisStudyConfigurator::new(1)
+        .add_element_type(RESISTOR, conductor).unwrap();
+
+    assert!(pack.clone().inherit(&defaults[DC_CIRCUIT], CollisionRule::Reject).is_err());
+    assert!(pack.clone().inherit(&defaults[HARMONIC_BALANCE], CollisionRule::Override).is_err());
+
+    let divider = |rule: CollisionRule| {
+        let config = pack.clone()
+            .inherit(&defaults[DC_CIRCUIT], rule).unwrap()
+            .inherit(&defaults[HEAT_TRANSFER], CollisionRule::Reject).unwrap();
+
+        let soln = NodalAnalysisStudyBuilder::new("acme_circuit".to_string(), Some(HashMap::from([("acme_circuit".to_string(), config)])))
+            .unwrap()
+            .add_nodes(3)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+            .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+            .add_element(RESISTOR,       2, 0, vec![4.0]).unwrap()
+            .run_study(1E-9, 100)
+            .unwrap();
+
+        serde_json::to_value(soln).unwrap()["nodes"]["2"][0].as_f64().unwrap()
+    };
+
+    assert!((divider(CollisionRule::Override) - 2.0).abs() < 1E-6);
+    assert!((divider(CollisionRule::Inherit) - 8.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_named_nodes_and_labelled_elements_key_study_results()
+{
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_named_node("gnd").unwrap()
+        .add_named_node("vcc").unwrap()
+        .add_nodes(1)
+        .configure_node(0, vec![0.0], true, None)
+        .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![9.0]).unwrap()
+        .add_labeled_element("R1", RESISTOR,       1, 2, vec![2.0]).unwrap()
+        .add_element(RESISTOR, 2, 0, vec![1.0]).unwrap()
+        .define_subcircuit("load", 2, |builder, r| builder.add_labeled_element("R", RESISTOR, 0, 1, vec![r[0]])).unwrap()
+        .add_subcircuit("load", &[1, 0], &[3.0]).unwrap();
+
+    assert!(builder.clone().add_named_node("vcc").is_err());
+    assert!(builder.clone().add_named_node("7").is_err());
+    assert!(builder.clone().add_labeled_element("R1", RESISTOR, 1, 0, vec![1.0]).is_err());
+    assert!(builder.clone().add_labeled_element("resistor.5", RESISTOR, 1, 0, vec![1.0]).is_err());
+
+    let model = builder.get_model();
+    assert_eq!(model.get_node_index("vcc"), Some(1));
+    assert_eq!(model.get_element_index("R1"), Some(1));
+    assert_eq!(model.get_element_index("load.0.R"), Some(3));
+    assert_eq!(model.get_node_name(2), "2");
+    assert_eq!(model.get_element_name(2), "resistor.2");
+
+    let soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+
+    assert!((soln["nodes"]["vcc"][0].as_f64().unwrap() - 9.0).abs() < 1E-6);
+    assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 3.0).abs() < 1E-6);
+    assert!((soln["elements"]["R1"][0].as_f64().unwrap() - 3.0).abs() < 1E-6);
+    assert!((soln["elements"]["resistor.2"][0].as_f64().unwrap() - 3.0).abs() < 1E-6);
+    assert!((soln["elements"]["load.0.R"][0].as_f64().unwrap() - 3.0).abs() < 1E-6);
+    assert!(soln["nodes"].get("1").is_none());
+}
+
+#[test]
+fn ensure_that_edited_models_reindex_their_nodes_elements_and_controls()
+{
+    use neapolitan::dc_circuits::CURRENT_CONTROLLED_CURRENT_SOURCE;
+    use neapolitan::modelling::ElementControl;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .add_named_node("unused").unwrap()
+        .add_named_node("out").unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(RESISTOR, 0, 1, vec![5.0]).unwrap()
+        .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![2.0]).unwrap()
+        .add_labeled_element("Rsense", RESISTOR, 1, 0, vec![1.0]).unwrap()
+        .add_controlled_element(CURRENT_CONTROLLED_CURRENT_SOURCE, 0, 3, vec![3.0], ElementControl::Flux { element: 2 }).unwrap()
+        .add_labeled_element("Rload", RESISTOR, 3, 0, vec![1.0]).unwrap();
+
+    assert!(builder.clone().remove_element(2).is_err());
+    assert!(builder.clone().remove_element(9).is_err());
+    assert!(builder.clone().remove_node(1).is_err());
+    assert!(builder.clone().update_gain(9, vec![1.0]).is_err());
+    assert!(builder.clone().replace_element(0, RESISTOR, 0, 9, vec![1.0]).is_err());
+
+    let builder = builder
+        .remove_element(0).unwrap()
+        .remove_node(2).unwrap()
+        .update_gain(3, vec![2.0]).unwrap();
+
+    let model = builder.get_model();
+    assert_eq!(model.get_element_index("Rsense"), Some(1));
+    assert_eq!(model.get_element_index("Rload"), Some(3));
+    assert_eq!(model.get_node_index("out"), Some(2));
+    assert_eq!(model.get_node_index("unused"), None);
+
+    // The sensed current of 2 A is amplified to 6 A through the 2 ohm load
+    let soln = serde_json::to_value(builder.clone().run_study(1E-9, 100).unwrap()).unwrap();
+    assert!((soln["nodes"]["out"][0].as_f64().unwrap().abs() - 12.0).abs() < 1E-6);
+
+    // The replaced element keeps its label
+    let soln = builder
+        .replace_element(1, RESISTOR, 1, 0, vec![2.0]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+    let soln = serde_json::to_value(soln).unwrap();
+    assert!((soln["elements"]["Rsense"][0].as_f64().unwrap() - 1.0).abs() < 1E-6);
+    assert!((soln["nodes"]["out"][0].as_f64().unwrap().abs() - 6.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_results_can_be_queried_by_index_or_name()
+{
+    use neapolitan::{NodalAnalysisStudyResult, ResultKey};
+
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .add_named_node("out").unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_labeled_element("R1", RESISTOR, 1, 2, vec![3.0]).unwrap()
+        .add_element(RESISTOR, 2, 0, vec![2.0]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    assert!((soln.get_node_potential(ResultKey::Index(2)).unwrap()[0] - 4.0).abs() < 1E-9);
+    assert_eq!(soln.get_node_potential(2), soln.get_node_potential("out"));
+    assert_eq!(soln.get_element_flux(1), soln.get_element_flux(&"R1".to_string()));
+    assert_eq!(soln.get_element_flux(2), soln.get_element_flux("resistor.2"));
+    assert!(soln.get_node_potential(3).is_none());
+
+    let names: Vec<&str> = soln.iter_elements().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["R1", "resistor.2", "voltage_source.0"]);
+    assert_eq!(soln.iter_nodes().count(), 3);
+
+    // Results read from a file only know the indices of unnamed nodes
+    let read: NodalAnalysisStudyResult = serde_json::from_str(&serde_json::to_string(&soln).unwrap()).unwrap();
+    assert!((read.get_node_potential(1).unwrap()[0] - 10.0).abs() < 1E-9);
+    assert!(read.get_node_potential(2).is_none());
+    assert!(read.get_element_flux(1).is_none());
+    assert!((read.get_element_flux("R1").unwrap()[0] - 2.0).abs() < 1E-9);
+}
@@ -0,0 +1,272 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::dc_circuits::{resistor, RESISTOR, DC_CIRCUIT, VOLTAGE_SOURCE};
+
+#[test]
+fn ensure_that_magnetic_circuits_split_flux_between_parallel_limbs()
+{
+    use neapolitan::magnetic_circuit::*;
+
+    // A shell core: the coil's limb returns its flux through two outer limbs, one of which is gapped
+    let soln = NodalAnalysisStudyBuilder::new(MAGNETIC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(MMF_SOURCE, 0, 1, vec![200.0, 1.5]).unwrap()
+        .add_element(RELUCTANCE, 1, 2, vec![0.1, 2E-4, 1500.0]).unwrap()
+        .add_element(RELUCTANCE, 2, 0, vec![0.2, 1E-4, 1500.0]).unwrap()
+        .add_element(RELUCTANCE, 2, 3, vec![0.2, 1E-4, 1500.0]).unwrap()
+        .add_element(AIR_GAP,    3, 0, vec![5E-4, 1E-4]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    let limb = 0.2 / (VACUUM_PERMEABILITY * 1500.0 * 1E-4);
+    let gapped = limb + 5E-4 / (VACUUM_PERMEABILITY * 1E-4);
+    let center = 0.1 / (VACUUM_PERMEABILITY * 1500.0 * 2E-4);
+    let total = 300.0 / (center + limb * gapped / (limb + gapped));
+
+    let flux = |element: usize| soln.get_element_flux(element).unwrap()[0];
+    assert!((flux(1) - total).abs() < 1E-12);
+    assert!((flux(2) / flux(3) - gapped / limb).abs() < 1E-6);
+    assert!((flux(2) + flux(3) - flux(1)).abs() < 1E-12);
+
+    // Elements given by their geometry report their flux density, but not those given a reluctance
+    let density = soln.get_derived_quantities("reluctance.1").unwrap()["flux_density"];
+    assert!((density - total / 2E-4).abs() < 1E-9);
+    assert!((soln.get_derived_quantities("air_gap.4").unwrap()["flux_density"] - flux(4) / 1E-4).abs() < 1E-9);
+
+    let lumped = NodalAnalysisStudyBuilder::new(MAGNETIC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(MMF_SOURCE, 0, 1, vec![300.0]).unwrap()
+        .add_element(RELUCTANCE, 1, 0, vec![1E6]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    assert!((lumped.get_element_flux(1).unwrap()[0] - 3E-4).abs() < 1E-15);
+    assert!(lumped.get_derived_quantities("reluctance.1").is_none());
+
+    assert!(NodalAnalysisStudyBuilder::new(MAGNETIC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(AIR_GAP, 1, 0, vec![1E-3]).unwrap()
+        .run_study(1E-9, 100)
+        .is_err());
+}
+
+#[test]
+fn ensure_that_species_diffuse_through_layers_in_series()
+{
+    use neapolitan::mass_diffusion::*;
+
+    // A species generated in a sealed cell leaks through a gel layer and a membrane to a clean bath
+    let soln = NodalAnalysisStudyBuilder::new(MASS_DIFFUSION.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(SPECIES_SOURCE,     0, 2, vec![3E-9]).unwrap()
+        .add_element(DIFFUSION,          2, 1, vec![2E-9, 1E-2, 1E-3]).unwrap()
+        .add_element(MEMBRANE_INTERFACE, 1, 0, vec![1E-10, 2.0, 1E-4, 1E-2]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    let gel = 2E-9 * 1E-2 / 1E-3;
+    let membrane = 1E-10 * 2.0 * 1E-2 / 1E-4;
+    let potential = |node: usize| soln.get_node_potential(node).unwrap()[0];
+    assert!((potential(1) - 3E-9 / membrane).abs() < 1E-9);
+    assert!((potential(2) - 3E-9 * (1.0 / gel + 1.0 / membrane)).abs() < 1E-9);
+    assert!((soln.get_element_flux(2).unwrap()[0] - 3E-9).abs() < 1E-18);
+
+    // Sources may also hold a concentration, and lumped diffusances are taken as given
+    let held = NodalAnalysisStudyBuilder::new(MASS_DIFFUSION.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(CONCENTRATION_SOURCE, 0, 1, vec![4.0]).unwrap()
+        .add_element(DIFFUSION,            1, 0, vec![gel]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    assert!((held.get_element_flux(1).unwrap()[0] - 4.0 * gel).abs() < 1E-18);
+
+    assert!(NodalAnalysisStudyBuilder::new(MASS_DIFFUSION.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(MEMBRANE_INTERFACE, 1, 0, vec![1E-10, 2.0, 1E-4]).unwrap()
+        .run_study(1E-9, 100)
+        .is_err());
+}
+
+#[test]
+fn ensure_that_check_valves_conduct_in_one_direction_across_a_sweep()
+{
+    use std::collections::HashMap;
+    use neapolitan::NodalAnalysisStudyConfigurator;
+    use neapolitan::dc_circuits::{voltage_source, CHECK_VALVE};
+    use neapolitan::sweep::{linspace, SweepParameter};
+
+    // A leaky valve feeding a load as its source passes through zero
+    let sweep = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![-10.0]).unwrap()
+        .add_element(CHECK_VALVE,    1, 2, vec![1.0, 1E-4, 1000.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![4.0]).unwrap()
+        .run_sweep(&[SweepParameter::Gain { element: 0, component: 0 }], &[linspace(-10.0, 10.0, 21)], 1E-12, 100)
+        .unwrap();
+
+    for (point, soln) in sweep.get_points().iter().zip(sweep.get_results())
+    {
+        let v = point[0];
+        let current = soln.get_element_flux(2).unwrap()[0];
+        let expected = match v > 0.0
+        {
+            true => v / 5.0,
+            false => v / 1004.0,
+        };
+        assert!((current - expected).abs() < 1E-4, "{v}: {current} != {expected}");
+    }
+
+    // The same element can be defined from its flux formula alone
+    let config = NodalAnalysisStudyConfigurator::new(1)
+        .add_element_type(VOLTAGE_SOURCE, voltage_source).unwrap()
+        .add_flux_element_type("ideal_valve", "one_way_flux").unwrap();
+    let soln = NodalAnalysisStudyBuilder::new("valves".to_string(), Some(HashMap::from([("valves".to_string(), config)])))
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element("ideal_valve",  1, 2, vec![0.5, 0.0, 1E-6]).unwrap()
+        .add_element("ideal_valve",  2, 0, vec![0.5, 0.0, 1E-6]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    assert!((soln.get_node_potential(2).unwrap()[0] - 1.5).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_coupled_domains_are_solved_self_consistently()
+{
+    use neapolitan::heat_transfer::{CONDUCTOR, HEAT_TRANSFER};
+    use neapolitan::modelling::ElementControl;
+    use neapolitan::multiphysics::{JOULE_HEAT_SOURCE, TEMPERATURE_DEPENDENT_RESISTOR};
+    use neapolitan::three_phase::{THREE_PHASE, THREE_PHASE_SOURCE};
+
+    // A self-heating resistor, solved in one study rather than by iterating between two
+    let r = vec![2.0, 0.005, 25.0];
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .add_domain_nodes(HEAT_TRANSFER, 3).unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .configure_node(2, vec![0.0], true, None)
+        .configure_node(3, vec![25.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![6.0]).unwrap()
+        .add_controlled_element(TEMPERATURE_DEPENDENT_RESISTOR, 1, 0, r.clone(), ElementControl::Potential { input: 4, output: 2 }).unwrap()
+        .add_controlled_element(JOULE_HEAT_SOURCE,              2, 4, r.clone(), ElementControl::Flux { element: 1 }).unwrap()
+        .add_element(CONDUCTOR,                                 4, 3, vec![0.1]).unwrap();
+
+    let mut expected = 25.0;
+    for _ in 0..200
+    {
+        expected = 25.0 + 36.0 / (2.0 * (1.0 + 0.005 * (expected - 25.0))) / 0.1;
+    }
+
+    for sparse in [false, true]
+    {
+        let soln = builder.clone().with_sparse_jacobian(sparse).run_study(1E-12, 100).unwrap();
+        let t = soln.get_node_potential(4).unwrap();
+        assert_eq!(t.len(), 1);
+        assert!((t[0] - expected).abs() < 1E-6, "{} != {expected}", t[0]);
+
+        let current = soln.get_element_flux(1).unwrap()[0];
+        let power = soln.get_derived_quantities("temperature_dependent_resistor.1").unwrap()["power"];
+        assert!((power - 6.0 * current).abs() < 1E-9);
+        assert!((soln.get_element_flux(2).unwrap()[0] - power).abs() < 1E-6);
+    }
+
+    // Domains may have different dimensions
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .add_domain_nodes(THREE_PHASE, 2).unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .configure_node(2, vec![0.0; 3], true, None)
+        .add_element(VOLTAGE_SOURCE,     0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,           1, 0, vec![1.0]).unwrap()
+        .add_element(THREE_PHASE_SOURCE, 2, 3, vec![10.0]).unwrap()
+        .add_element(RESISTOR,           3, 2, vec![5.0]).unwrap()
+        .with_sparse_jacobian(true)
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    assert_eq!(soln.get_node_potential(1).unwrap(), &[5.0]);
+    assert_eq!(soln.get_node_potential(3).unwrap().len(), 3);
+    assert!((soln.get_element_flux(3).unwrap()[0] - 2.0).abs() < 1E-9);
+
+    // Every domain needs its own reference, even when another domain is locked
+    use neapolitan::errors::NodalAnalysisModellingError;
+
+    let floating = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .add_domain_nodes(HEAT_TRANSFER, 2).unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 0, vec![1.0]).unwrap()
+        .add_element(CONDUCTOR,      2, 3, vec![0.1]).unwrap();
+    let err = floating.clone().run_study(1E-12, 100).unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(NodalAnalysisModellingError::FloatingDomain(domain)) if domain == HEAT_TRANSFER));
+
+    let soln = floating.with_auto_ground(true).run_study(1E-12, 100).unwrap();
+    assert_eq!(soln.get_node_potential(2).unwrap(), &[0.0]);
+    assert!(soln.get_warnings().iter().any(|w| w.contains("'heat_transfer' domain")));
+}
+
+#[test]
+fn ensure_that_pumps_follow_their_performance_curves()
+{
+    use std::collections::HashMap;
+    use neapolitan::NodalAnalysisStudyConfigurator;
+    use neapolitan::dc_circuits::PUMP;
+
+    let curve = vec![0.0, 10.0, 2.0, 8.0, 4.0, 0.0];
+
+    // Identical pumps in series each deliver half of the pipe's pressure drop
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(PUMP,     0, 1, curve.clone()).unwrap()
+        .add_element(PUMP,     1, 2, curve.clone()).unwrap()
+        .add_element(RESISTOR, 2, 0, vec![2.0]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    assert!((soln.get_element_flux(2).unwrap()[0] - 3.2).abs() < 1E-9);
+    assert!((soln.get_node_potential(1).unwrap()[0] - 3.2).abs() < 1E-9);
+
+    // Pushed back past its shutoff head, the pump's curve is extended into reverse flow
+    let config = NodalAnalysisStudyConfigurator::new(1)
+        .add_element_type(RESISTOR, resistor).unwrap()
+        .add_flux_element_type("fan", "performance_curve_flux").unwrap();
+    let soln = NodalAnalysisStudyBuilder::new("air_flow".to_string(), Some(HashMap::from([("air_flow".to_string(), config)])))
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .configure_node(2, vec![12.0], true, None)
+        .add_element("fan",    0, 1, curve).unwrap()
+        .add_element(RESISTOR, 1, 2, vec![1.0]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    assert!((soln.get_element_flux(0).unwrap()[0] + 1.0).abs() < 1E-9);
+    assert!((soln.get_node_potential(1).unwrap()[0] - 11.0).abs() < 1E-9);
+
+    assert!(NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(PUMP,     0, 1, vec![0.0, 10.0, 2.0, 12.0]).unwrap()
+        .add_element(RESISTOR, 1, 0, vec![2.0]).unwrap()
+        .run_study(1E-12, 100)
+        .is_err());
+}
@@ -0,0 +1,602 @@
+use neapolitan::NodalAnalysisStudyBuilder;
+use neapolitan::dc_circuits::{RESISTOR, DC_CIRCUIT, VOLTAGE_SOURCE};
+
+#[test]
+fn ensure_that_compiled_study_matches_fresh_study_after_changing_gains()
+{
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       3, 0, vec![1.0]).unwrap();
+
+    let mut study = builder.clone().compile().expect("failed to compile model");
+    assert!(study.set_gain(4, vec![1.0]).is_err());
+
+    // Change both a driving element and a passive one
+    study.set_gain(0, vec![8.0]).unwrap();
+    study.set_gain(2, vec![5.0]).unwrap();
+    let reused = study.solve(1E-9, 100).unwrap();
+
+    let fresh = NodalAnalysisStudyBuilder::from_model_with_default_config(study.get_model().clone())
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    let (reused, fresh) = (serde_json::to_value(reused).unwrap(), serde_json::to_value(fresh).unwrap());
+    for node in ["1", "2", "3"]
+    {
+        let (a, b) = (reused["nodes"][node][0].as_f64().unwrap(), fresh["nodes"][node][0].as_f64().unwrap());
+        assert!((a - b).abs() < 1E-6);
+    }
+    assert!((reused["nodes"]["1"][0].as_f64().unwrap() - 8.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_models_without_a_locked_node_are_rejected_or_grounded()
+{
+    use neapolitan::dc_circuits::CURRENT_SOURCE;
+    use neapolitan::errors::NodalAnalysisModellingError;
+
+    // Node 0 is configured, but not locked
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![5.0], false, None)
+        .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![2.0]).unwrap();
+
+    let err = builder.clone().run_study(1E-9, 100).unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(NodalAnalysisModellingError::NoReferenceNode)));
+
+    let soln = serde_json::to_value(builder.with_auto_ground(true).run_study(1E-9, 100).unwrap()).unwrap();
+
+    assert_eq!(soln["nodes"]["0"][0].as_f64().unwrap(), 0.0);
+    assert!((soln["nodes"]["1"][0].as_f64().unwrap() - 6.0).abs() < 1E-6);
+    assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 4.0).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_initial_guesses_are_where_the_solver_starts()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+
+    assert!(builder.clone().set_initial_guess(0, vec![1.0]).is_err());
+    assert!(builder.clone().set_initial_guess(3, vec![1.0]).is_err());
+
+    // Starting far up the diode's exponential takes many more iterations than starting near its knee
+    let soln = builder.clone().set_initial_guess(2, vec![0.55]).unwrap().run_study(1E-9, 8).unwrap();
+    let v_diode = serde_json::to_value(soln).unwrap()["nodes"]["2"][0].as_f64().unwrap();
+    assert!(v_diode > 0.5 && v_diode < 0.7);
+    
+    assert!(builder.run_study(1E-9, 8).is_err());
+}
+
+#[test]
+fn ensure_that_warm_started_studies_reach_the_same_solution()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_named_node("gnd").unwrap()
+        .add_named_node("supply").unwrap()
+        .add_named_node("anode").unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+
+    let cold = builder.clone().update_gain(0, vec![4.0]).unwrap().run_study(1E-9, 100).unwrap();
+    let first = builder.clone().run_study(1E-9, 100).unwrap();
+    let warm = builder.update_gain(0, vec![4.0]).unwrap().with_initial_state(&first);
+
+    let warm = serde_json::to_value(warm.run_study(1E-9, 100).unwrap()).unwrap();
+    let cold = serde_json::to_value(cold).unwrap();
+
+    // The locked ground keeps its potential
+    assert_eq!(warm["nodes"]["gnd"][0].as_f64().unwrap(), 0.0);
+    assert!((warm["nodes"]["anode"][0].as_f64().unwrap() - cold["nodes"]["anode"][0].as_f64().unwrap()).abs() < 1E-9);
+}
+
+#[test]
+fn ensure_that_sources_driving_locked_nodes_are_reported_as_conflicts()
+{
+    use neapolitan::errors::NodalAnalysisModellingError;
+    use neapolitan::heat_transfer::{HEAT_TRANSFER, TEMPERATURE_DELTA, CONDUCTOR};
+
+    let conflict = |builder: NodalAnalysisStudyBuilder| match builder.run_study(1E-9, 100).unwrap_err().downcast()
+    {
+        Ok(e @ NodalAnalysisModellingError::LockedNodeConflict { .. }) => e.to_string(),
+        other => panic!("expected a locked node conflict, found {other:?}"),
+    };
+
+    // Both ends of the source are locked by the configuration
+    let err = conflict(NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![20.0], true, None)
+        .configure_node(1, vec![80.0], true, None)
+        .add_element(CONDUCTOR, 0, 1, vec![1.0]).unwrap()
+        .add_element(TEMPERATURE_DELTA, 0, 1, vec![60.0]).unwrap());
+    assert!(err.contains("'temperature_delta.1'") && err.contains("'1' by configure_node"));
+
+    // The second source drives a node that the first already drives
+    let err = conflict(NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_labeled_element("V2", VOLTAGE_SOURCE, 0, 1, vec![3.0]).unwrap()
+        .add_element(RESISTOR, 1, 0, vec![1.0]).unwrap());
+    assert!(err.contains("'V2'") && err.contains("'0' by configure_node") && err.contains("'1' by element 'V1'"));
+}
+
+#[test]
+fn ensure_that_results_report_how_the_solver_converged()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    let stats = soln.get_statistics();
+    assert!(stats.iterations > 1);
+    assert_eq!(stats.residual_history.len(), stats.iterations);
+    assert_eq!(stats.residual_norm, *stats.residual_history.last().unwrap());
+    assert!(stats.residual_norm < stats.residual_history[0]);
+    assert!(stats.wall_time > 0.0);
+
+    // Statistics are included when results are written out
+    let json = serde_json::to_value(&soln).unwrap();
+    assert_eq!(json["statistics"]["iterations"].as_u64().unwrap() as usize, stats.iterations);
+}
+
+#[test]
+fn ensure_that_progress_callbacks_see_every_iteration_and_can_cancel()
+{
+    use std::ops::ControlFlow;
+    use neapolitan::dc_circuits::DIODE;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap();
+
+    let mut seen = vec![];
+    let soln = builder.clone()
+        .run_study_with_progress(1E-9, 100, |iteration, residual| {
+            seen.push((iteration, residual));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+
+    let history: Vec<(usize, f64)> = soln.get_statistics().residual_history.iter().copied().enumerate().collect();
+    assert_eq!(seen, history);
+
+    let mut calls = 0;
+    let cancelled = builder.run_study_with_progress(1E-9, 100, |_, _| {
+        calls += 1;
+        ControlFlow::Break(())
+    });
+    assert!(cancelled.unwrap_err().to_string().contains("cancelled"));
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn ensure_that_failed_solves_report_where_the_flux_balance_is_blowing_up()
+{
+    use neapolitan::dc_circuits::DIODE;
+    use neapolitan::errors::SolveFailedError;
+
+    let err = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_named_node("gnd").unwrap()
+        .add_named_node("supply").unwrap()
+        .add_named_node("anode").unwrap()
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap()
+        .run_study(1E-9, 3)
+        .unwrap_err();
+
+    let failure = err.downcast_ref::<SolveFailedError>().expect("expected the solver's last state");
+    assert!(failure.reason.contains("maximum number of iterations"));
+    assert_eq!(failure.guess.len(), 1);
+    assert_eq!(failure.potentials.len(), 3);
+    assert_eq!(failure.potentials["anode"][0], failure.guess[0]);
+
+    // Only the unlocked node has a flux balance to solve
+    assert_eq!(failure.discrepancies.keys().collect::<Vec<_>>(), vec!["anode"]);
+    assert!(failure.discrepancies["anode"][0].abs() > 1E-9);
+    assert_eq!(failure.worst_node.as_deref(), Some("anode"));
+    assert!(err.to_string().ends_with("at node 'anode'"));
+}
+
+#[test]
+fn ensure_that_elements_driving_the_final_iterations_are_reported()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_labeled_element("d1",    DIODE,    2, 0, vec![1E-12, 0.02585]).unwrap()
+        .add_labeled_element("bleed", RESISTOR, 2, 0, vec![1E6]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    let convergence = &soln.get_statistics().element_convergence;
+    assert_eq!(convergence.len(), 4);
+
+    let total: f64 = convergence.values().map(|e| e.residual_contribution).sum();
+    assert!((total - 1.0).abs() < 1E-9);
+
+    // The diode's current swings while the bleed resistor's barely moves
+    assert!(convergence["d1"].residual_contribution > 100.0 * convergence["bleed"].residual_contribution);
+
+    let i_diode = serde_json::to_value(&soln).unwrap()["elements"]["d1"][0].as_f64().unwrap();
+    assert!((convergence["d1"].flux_magnitude - i_diode.abs()).abs() < 1E-12);
+}
+
+#[test]
+fn ensure_that_compiled_studies_can_be_solved_on_another_thread()
+{
+    use neapolitan::CompiledStudy;
+    use neapolitan::modelling::arena::NodeId;
+
+    fn assert_send<T: Send>() {}
+    assert_send::<CompiledStudy>();
+
+    let mut study = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       2, 0, vec![1.0]).unwrap()
+        .compile()
+        .unwrap();
+    study.set_gain(2, vec![3.0]).unwrap();
+
+    let arena = study.get_arena();
+    assert_eq!(arena.get_neighbours(NodeId(2)), vec![NodeId(0), NodeId(1)]);
+
+    let handles: Vec<_> = [1.0, 9.0].into_iter()
+        .map(|r| {
+            let mut study = study.clone();
+            std::thread::spawn(move || {
+                study.set_gain(2, vec![r]).unwrap();
+                let soln = serde_json::to_value(study.solve(1E-9, 100).unwrap()).unwrap();
+                (r, soln["nodes"]["2"][0].as_f64().unwrap(), study)
+            })
+        })
+        .collect();
+
+    for handle in handles
+    {
+        let (r, v2, study) = handle.join().unwrap();
+        assert!((v2 - 10.0 * r / (1.0 + r)).abs() < 1E-6);
+        assert_eq!(study.get_arena().get_potential(NodeId(2))[(0, 0)], v2);
+    }
+
+    // The original study is untouched by the solves on other threads
+    let soln = serde_json::to_value(study.solve(1E-9, 100).unwrap()).unwrap();
+    assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 7.5).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_auto_grounded_results_warn_that_the_model_was_changed()
+{
+    use neapolitan::dc_circuits::CURRENT_SOURCE;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       1, 0, vec![3.0]).unwrap()
+        .with_auto_ground(true);
+
+    let soln = builder.clone().run_study(1E-9, 100).unwrap();
+    assert_eq!(soln.get_warnings(), &vec!["no node was locked, so node '0' was locked at zero potential".to_owned()]);
+    assert_eq!(serde_json::to_value(&soln).unwrap()["warnings"].as_array().unwrap().len(), 1);
+
+    // Models that already have a reference are solved as they are, without a warning
+    let soln = builder
+        .configure_node(1, vec![0.0], true, None)
+        .run_study(1E-9, 100)
+        .unwrap();
+    assert!(soln.get_warnings().is_empty());
+    assert!(serde_json::to_value(&soln).unwrap().get("warnings").is_none());
+}
+
+#[test]
+fn ensure_that_models_with_hundreds_of_nodes_solve()
+{
+    // A 10V source across a chain of 200 equal resistors, so each node drops an equal share.
+    // With the `parallel` feature, this exercises evaluating the model on several threads.
+    let n = 200;
+    let mut builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(n + 1)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap();
+
+    for node in 1..n
+    {
+        builder = builder.add_element(RESISTOR, node, node + 1, vec![1.0]).unwrap();
+    }
+    let soln = builder
+        .add_element(RESISTOR, n, 0, vec![1.0]).unwrap()
+        .run_study(1E-9, 100)
+        .unwrap();
+
+    let soln = serde_json::to_value(soln).unwrap();
+    for node in 1..=n
+    {
+        let expected = 10.0 * (n + 1 - node) as f64 / n as f64;
+        assert!((soln["nodes"][node.to_string()][0].as_f64().unwrap() - expected).abs() < 1E-6);
+    }
+}
+
+#[test]
+fn ensure_that_sparse_jacobians_match_dense_jacobians()
+{
+    use neapolitan::dc_circuits::*;
+    use neapolitan::modelling::ElementControl;
+
+    let models = vec![
+        // An inverting amplifier, where a controlled source drives a node from the node it senses
+        NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .expect("failed to create model builder object")
+            .add_nodes(4)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0]).unwrap()
+            .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+            .add_element(RESISTOR,       2, 3, vec![10000.0]).unwrap()
+            .add_controlled_element(VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, 0, 3, vec![-1E5], ElementControl::Potential { input: 2, output: 0 }).unwrap(),
+        // A controlled source that senses the current through a resistor elsewhere in the model
+        NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .expect("failed to create model builder object")
+            .add_nodes(5)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+            .add_element(RESISTOR, 1, 0, vec![3.0]).unwrap()
+            .add_controlled_element(VOLTAGE_CONTROLLED_CURRENT_SOURCE, 0, 2, vec![0.5], ElementControl::Potential { input: 1, output: 0 }).unwrap()
+            .add_element(RESISTOR, 2, 0, vec![2.0]).unwrap()
+            .add_controlled_element(CURRENT_CONTROLLED_CURRENT_SOURCE, 0, 3, vec![2.0], ElementControl::Flux { element: 3 }).unwrap()
+            .add_element(RESISTOR, 3, 4, vec![1.0]).unwrap()
+            .add_element(RESISTOR, 4, 0, vec![1.0]).unwrap(),
+        // A nonlinear element
+        NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .expect("failed to create model builder object")
+            .add_nodes(3)
+            .configure_node(0, vec![0.0], true, None)
+            .configure_node(2, vec![0.6], false, None)
+            .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+            .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+            .add_element(DIODE,          2, 0, vec![1E-12, 0.025]).unwrap(),
+    ];
+
+    for model in models
+    {
+        let dense = model.clone().run_study(1E-12, 100).unwrap();
+        let sparse = model.with_sparse_jacobian(true).run_study(1E-12, 100).unwrap();
+
+        let (dense, sparse) = (serde_json::to_value(dense).unwrap(), serde_json::to_value(sparse).unwrap());
+        for (node, potential) in dense["nodes"].as_object().unwrap()
+        {
+            let expected = potential[0].as_f64().unwrap();
+            assert!((sparse["nodes"][node][0].as_f64().unwrap() - expected).abs() < 1E-9 * expected.abs().max(1.0));
+        }
+    }
+}
+
+#[test]
+fn ensure_that_models_with_thousands_of_nodes_solve_with_sparse_jacobians()
+{
+    // A 10V source across a chain of 2000 equal resistors, each with a resistor to ground,
+    // which would need a dense jacobian with millions of elements
+    let n = 2000;
+    let mut builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(n + 1)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap();
+
+    for node in 1..n
+    {
+        builder = builder
+            .add_element(RESISTOR, node, node + 1, vec![1.0]).unwrap()
+            .add_element(RESISTOR, node + 1, 0, vec![1E6]).unwrap();
+    }
+    let soln = builder
+        .with_sparse_jacobian(true)
+        .run_study(1E-9, 100)
+        .unwrap();
+    assert!(soln.get_statistics().iterations < 5);
+
+    // Every node's flux balances, and the potential falls along the chain
+    let soln = serde_json::to_value(soln).unwrap();
+    let mut last = 10.0;
+    for node in 2..=n
+    {
+        let potential = soln["nodes"][node.to_string()][0].as_f64().unwrap();
+        assert!(potential < last && potential > 0.0);
+        last = potential;
+    }
+}
+
+#[test]
+fn ensure_that_continuation_converges_models_that_diverge_from_a_cold_start()
+{
+    use neapolitan::continuation::Continuation;
+    use neapolitan::heat_transfer::*;
+    use neapolitan::sweep::SweepParameter;
+
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![2000.0], true, None)
+        .configure_node(2, vec![300.0], true, None)
+        .add_element(RADIATION_INTERFACE, 0, 1, vec![1.0, 0.9]).unwrap()
+        .add_element(CONDUCTOR,           1, 2, vec![0.5, 50.0]).unwrap();
+    let expected = serde_json::to_value(builder.clone().run_study(1E-6, 100).unwrap()).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+
+    // A cold start needs more iterations than are allowed...
+    assert!(builder.clone().run_study(1E-6, 8).is_err());
+
+    // ...but each step of a ramp from a warm start does not
+    let soln = builder.clone()
+        .with_continuation(Continuation::SourceStepping { steps: 10 })
+        .run_study(1E-6, 8)
+        .unwrap();
+    let t1 = serde_json::to_value(soln).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+    assert!((t1 - expected).abs() < 1E-6);
+
+    let soln = builder.clone()
+        .with_continuation(Continuation::Parameter { parameter: SweepParameter::Potential { node: 0, component: 0 }, start: 300.0, steps: 10 })
+        .run_study(1E-6, 8)
+        .unwrap();
+    let t1 = serde_json::to_value(soln).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+    assert!((t1 - expected).abs() < 1E-6);
+
+    // Failures say which step of the ramp could not be solved, and leave the study at its full values
+    let mut study = builder.clone().compile().unwrap();
+    let err = study.solve_with_continuation(1E-6, 3, Continuation::SourceStepping { steps: 2 }).unwrap_err();
+    assert!(err.to_string().contains("of 2 of the continuation"));
+    assert_eq!(study.get_model(), builder.get_model());
+
+    assert!(builder.with_continuation(Continuation::SourceStepping { steps: 0 }).run_study(1E-6, 100).is_err());
+}
+
+#[test]
+fn ensure_that_nodes_outside_the_conservation_tolerance_are_listed_in_warnings()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let mut study = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(DIODE,          2, 0, vec![1E-12, 0.02585]).unwrap()
+        .compile()
+        .unwrap();
+
+    // Results are only classified when a tolerance is set
+    let soln = serde_json::to_value(study.clone().solve(1E-3, 100).unwrap()).unwrap();
+    assert!(soln.get("warnings").is_none());
+
+    study.set_conservation_tolerance(Some(1E-6));
+    let soln = serde_json::to_value(study.clone().solve(1E-3, 100).unwrap()).unwrap();
+    let warnings = soln["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].as_str().unwrap().contains("node '2'"));
+
+    // The tolerance does not change how the study is solved
+    let soln = study.solve(1E-9, 100).unwrap();
+    assert!(soln.get_warnings().is_empty());
+}
+
+#[test]
+fn ensure_that_a_line_search_converges_where_full_newton_steps_overshoot()
+{
+    use neapolitan::heat_transfer::*;
+    use neapolitan::options::{Damping, SolverOptions};
+
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![2000.0], true, None)
+        .configure_node(2, vec![300.0], true, None)
+        .add_element(RADIATION_INTERFACE, 0, 1, vec![1.0, 0.9]).unwrap()
+        .add_element(CONDUCTOR,           1, 2, vec![0.5, 50.0]).unwrap();
+    let expected = serde_json::to_value(builder.clone().run_study(1E-6, 100).unwrap()).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+
+    // Full steps overshoot the fourth-power flux and need more iterations than are allowed
+    let options = SolverOptions::new(1E-6, 10);
+    assert!(builder.clone().run_study_with_options(&options).is_err());
+
+    let options = options.with_damping(Damping::Backtracking { min_step: 1E-3 });
+    let soln = builder.clone().run_study_with_options(&options).unwrap();
+    assert!(soln.get_statistics().iterations < 10);
+    let t1 = serde_json::to_value(soln).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+    assert!((t1 - expected).abs() < 1E-6);
+
+    // Steps can only be damped by a fraction of themselves
+    let options = options.with_damping(Damping::Fixed(0.0));
+    assert!(builder.run_study_with_options(&options).is_err());
+}
+
+#[test]
+fn ensure_that_solver_options_apply_across_study_apis()
+{
+    use neapolitan::heat_transfer::*;
+    use neapolitan::options::SolverOptions;
+    use neapolitan::sweep::{linspace, SweepParameter};
+
+    let builder = NodalAnalysisStudyBuilder::new(HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![2000.0], true, None)
+        .configure_node(2, vec![300.0], true, None)
+        .add_element(RADIATION_INTERFACE, 0, 1, vec![1.0, 0.9]).unwrap()
+        .add_element(CONDUCTOR,           1, 2, vec![0.5, 50.0]).unwrap();
+
+    let t1 = |soln: &neapolitan::NodalAnalysisStudyResult| serde_json::to_value(soln).unwrap()["nodes"]["1"][0].as_f64().unwrap();
+
+    // Starting near the solution takes fewer iterations than starting from zero
+    let options = SolverOptions::default().with_margin(1E-6);
+    let cold = builder.clone().run_study_with_options(&options).unwrap();
+    let warm = builder.clone().run_study_with_options(&options.with_initial_guess(1800.0)).unwrap();
+    assert!(warm.get_statistics().iterations < cold.get_statistics().iterations);
+    assert!((t1(&warm) - t1(&cold)).abs() < 1E-6);
+
+    // Nodes that were given a guess keep it
+    let guessed = builder.clone()
+        .set_initial_guess(1, vec![1800.0]).unwrap()
+        .run_study_with_options(&options.with_initial_guess(-1E6))
+        .unwrap();
+    assert_eq!(guessed.get_statistics().iterations, warm.get_statistics().iterations);
+
+    // A finer jacobian step still finds the same solution
+    let fine = builder.clone().run_study_with_options(&options.with_step(1E-6)).unwrap();
+    assert!((t1(&fine) - t1(&cold)).abs() < 1E-6);
+    assert!(builder.clone().run_study_with_options(&options.with_step(0.0)).is_err());
+
+    // Audited studies solve with every setting, too
+    let audited = builder.clone().run_audited_study_with_options(&options.with_initial_guess(1800.0)).unwrap();
+    assert_eq!(audited.get_result().get_statistics().iterations, warm.get_statistics().iterations);
+
+    let sweep = builder
+        .run_sweep_with_options(&[SweepParameter::Potential { node: 0, component: 0 }], &[linspace(1000.0, 2000.0, 3)], &options.with_initial_guess(1000.0))
+        .unwrap();
+    assert!((t1(&sweep.get_results()[2]) - t1(&cold)).abs() < 1E-6);
+}
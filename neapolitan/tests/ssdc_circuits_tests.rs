@@ -54,4 +54,432 @@ fn fuzz_resistor_flux_calcs()
 
         assert!(expected - res.get_flux().unwrap()[(0, 0)] < 1E-10);
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn ensure_that_rl_circuit_current_rises_to_steady_state()
+{
+    use neapolitan::transient::*;
+
+    let result = NodalAnalysisStudyBuilder::new(TRANSIENT_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        //           Element         Input   Output  Gain
+        .add_element(VOLTAGE_SOURCE, 0,      1,      vec![2.0]).unwrap()
+        .add_element(RESISTOR,       1,      2,      vec![4.0]).unwrap()
+        .add_element(INDUCTOR,       2,      0,      vec![2.0]).unwrap()
+        .run_transient_study(0.01, 5.0, 1E-10, 100)
+        .expect("failed to solve model");
+
+    let current = result.get_element_history("inductor.2").unwrap();
+    assert_eq!(current.len(), result.get_times().len());
+
+    // Current rises monotonically with a time constant of L/R = 0.5 s toward V/R = 0.5 A
+    assert!(current.windows(2).all(|w| w[1][0] >= w[0][0]));
+    let at_tau = current[49][0];
+    assert!((at_tau - 0.5 * (1.0 - (-1.0_f64).exp())).abs() < 5E-3);
+    assert!((current.last().unwrap()[0] - 0.5).abs() < 1E-3);
+
+    // Element types written in another case still keep their state between steps
+    let result = NodalAnalysisStudyBuilder::new(TRANSIENT_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![2.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![4.0]).unwrap()
+        .add_element("Inductor",     2, 0, vec![2.0]).unwrap()
+        .run_transient_study(0.01, 5.0, 1E-10, 100)
+        .unwrap();
+
+    let current = result.get_element_history("inductor.2").unwrap();
+    assert!((current[49][0] - at_tau).abs() < 1E-9);
+}
+
+#[test]
+fn ensure_that_diodes_conduct_forward_and_block_reverse_current()
+{
+    use neapolitan::dc_circuits::DIODE;
+
+    let circuit = |source: f64, diode: Vec<f64>| {
+        NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .expect("failed to create model builder object")
+            .add_nodes(3)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(VOLTAGE_SOURCE, 0, 1, vec![source]).unwrap()
+            .add_element(RESISTOR,       1, 2, vec![100.0]).unwrap()
+            .add_element(DIODE,          2, 0, diode).unwrap()
+    };
+
+    // Forward biased, the current satisfies both the resistor and the shockley equation
+    let soln = serde_json::to_value(circuit(10.0, vec![1E-12, 0.05]).run_study(1E-12, 100).unwrap()).unwrap();
+    let v = soln["nodes"]["2"][0].as_f64().unwrap();
+    let i = soln["elements"]["diode.2"][0].as_f64().unwrap();
+    assert!((i - (10.0 - v) / 100.0).abs() < 1E-9);
+    assert!((i - 1E-12 * ((v / 0.05).exp() - 1.0)).abs() < 1E-6);
+
+    // Reverse biased, only the saturation current flows
+    let soln = serde_json::to_value(circuit(-10.0, vec![1E-12, 0.05]).run_study(1E-15, 100).unwrap()).unwrap();
+    assert!((soln["elements"]["diode.2"][0].as_f64().unwrap() + 1E-12).abs() < 1E-15);
+
+    // Beyond its maximum current, the diode is linear
+    let soln = serde_json::to_value(circuit(10.0, vec![1E-12, 0.05, 1E-6]).run_study(1E-12, 100).unwrap()).unwrap();
+    let v = soln["nodes"]["2"][0].as_f64().unwrap();
+    assert!(v > 0.05 * (1E6_f64 + 1.0).ln());
+
+    // Exported decks use the exact shockley equation
+    let deck = circuit(1.0, vec![1E-12, 0.05]).get_model().to_equation_system().unwrap();
+    assert!(deck.contains("diode_2 = 0.000000000001 * (e^((node_2 - node_0) / 0.05) - 1)"));
+
+    // Bad parameters are rejected
+    assert!(circuit(1.0, vec![1E-12]).run_study(1E-9, 100).is_err());
+    assert!(circuit(1.0, vec![-1E-12, 0.05]).run_study(1E-9, 100).is_err());
+}
+
+#[test]
+fn ensure_that_controlled_sources_model_feedback_amplifiers()
+{
+    use neapolitan::dc_circuits::*;
+    use neapolitan::modelling::ElementControl;
+
+    // An inverting amplifier built from an op-amp with a finite open loop gain
+    let amplifier = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![1.0]).unwrap()
+        .add_element(RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![10000.0]).unwrap()
+        .add_controlled_element(VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, 0, 3, vec![-1E5], ElementControl::Potential { input: 2, output: 0 }).unwrap();
+
+    // Controls survive saving and loading the model
+    let mut json = String::new();
+    let amplifier = amplifier.save_model(&mut json).unwrap();
+    assert!(json.contains("\"potential\""));
+    let loaded = NodalAnalysisStudyBuilder::from_model_with_default_config(serde_json::from_str(&json).unwrap());
+    assert_eq!(loaded.get_model(), amplifier.get_model());
+
+    let deck = amplifier.get_model().to_equation_system().unwrap();
+    assert!(deck.contains("node_3 - node_0 = -100000 * (node_2 - node_0)"));
+
+    let soln = serde_json::to_value(loaded.run_study(1E-12, 100).unwrap()).unwrap();
+    let open_loop = 1E5;
+    let expected = -10.0 * open_loop / (open_loop + 11.0);
+    assert!((soln["nodes"]["3"][0].as_f64().unwrap() - expected).abs() < 1E-6);
+
+    // A current-controlled voltage source senses the current through a resistor, even one added after it
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+        .add_controlled_element(CURRENT_CONTROLLED_VOLTAGE_SOURCE, 0, 2, vec![5.0], ElementControl::Flux { element: 3 }).unwrap()
+        .add_element(RESISTOR, 2, 3, vec![1.0]).unwrap()
+        .add_element(RESISTOR, 1, 0, vec![3.0]).unwrap()
+        .add_element(RESISTOR, 3, 0, vec![1.0]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    let soln = serde_json::to_value(soln).unwrap();
+    assert!((soln["nodes"]["2"][0].as_f64().unwrap() - 5.0 * 2.0).abs() < 1E-9);
+    assert!((soln["nodes"]["3"][0].as_f64().unwrap() - 5.0).abs() < 1E-9);
+
+    // Controlled sources need something to sense, and cannot sense themselves
+    let base = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(RESISTOR, 1, 0, vec![1.0]).unwrap();
+    assert!(base.clone().add_element(CURRENT_CONTROLLED_CURRENT_SOURCE, 0, 1, vec![1.0]).unwrap().run_study(1E-9, 100).is_err());
+    assert!(base.clone().add_controlled_element(CURRENT_CONTROLLED_CURRENT_SOURCE, 0, 1, vec![1.0], ElementControl::Flux { element: 1 }).unwrap().run_study(1E-9, 100).is_err());
+    assert!(base.add_controlled_element(VOLTAGE_CONTROLLED_CURRENT_SOURCE, 0, 1, vec![1.0], ElementControl::Potential { input: 0, output: 7 }).is_err());
+}
+
+#[test]
+fn ensure_that_transistor_terminal_currents_balance_at_every_node()
+{
+    use neapolitan::dc_circuits::*;
+
+    // The emitter is held below ground by a supply, so every terminal's current enters a flux balance
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(6)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_element(VOLTAGE_SOURCE, 0, 2, vec![0.0]).unwrap()
+        .add_element(RESISTOR,       2, 3, vec![10E3]).unwrap()
+        .add_element(RESISTOR,       1, 4, vec![1E3]).unwrap()
+        .add_element(VOLTAGE_SOURCE, 0, 5, vec![-1.0]).unwrap()
+        .add_terminal_element(NPN_TRANSISTOR, vec![4, 3, 5], vec![1E-14, 0.025, 50.0]).unwrap();
+
+    // Terminals survive saving and loading the model
+    let mut json = String::new();
+    let builder = builder.save_model(&mut json).unwrap();
+    assert!(json.contains("\"terminals\""));
+    let loaded = NodalAnalysisStudyBuilder::from_model_with_default_config(serde_json::from_str(&json).unwrap());
+    assert_eq!(loaded.get_model(), builder.get_model());
+
+    let mut study = builder.compile().unwrap();
+    for beta in [50.0, 200.0]
+    {
+        study.set_gain(5, vec![1E-14, 0.025, beta]).unwrap();
+        let soln = serde_json::to_value(study.solve(1E-9, 100).unwrap()).unwrap();
+        let flux = |name: &str, i: usize| soln["elements"][name][i].as_f64().unwrap();
+
+        let (i_c, i_b, i_e) = (flux("npn_transistor.5", 0), flux("npn_transistor.5", 1), flux("npn_transistor.5", 2));
+        assert!((i_c - flux("resistor.3", 0)).abs() < 1E-9);
+        assert!((i_b - flux("resistor.2", 0)).abs() < 1E-9);
+        assert!((i_e - flux("voltage_source.4", 0)).abs() < 1E-9);
+        assert!((i_c / i_b - beta).abs() < 1E-3 * beta);
+    }
+
+    // Transistors need exactly three terminals, and are only available in circuit models
+    let base = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None);
+    assert!(base.clone().add_terminal_element(NPN_TRANSISTOR, vec![1, 2], vec![1E-14, 0.025, 50.0]).unwrap().run_study(1E-9, 100).is_err());
+    assert!(base.add_terminal_element(NPN_TRANSISTOR, vec![], vec![1E-14, 0.025, 50.0]).is_err());
+
+    let heat = NodalAnalysisStudyBuilder::new(neapolitan::heat_transfer::HEAT_TRANSFER.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .add_terminal_element(NPN_TRANSISTOR, vec![0, 1, 2], vec![1E-14, 0.025, 50.0]).unwrap();
+    assert!(heat.run_study(1E-9, 100).is_err());
+}
+
+#[test]
+fn ensure_that_subcircuit_instances_get_their_own_namespaced_internal_nodes()
+{
+    use neapolitan::dc_circuits::VOLTAGE_CONTROLLED_VOLTAGE_SOURCE;
+    use neapolitan::modelling::ElementControl;
+
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        // A divider from port 0 to port 1 with its midpoint at internal node 2
+        .define_subcircuit("divider", 2, |builder, r| builder
+            .add_nodes(1)
+            .add_element(RESISTOR, 0, 2, vec![r[0]])?
+            .add_element(RESISTOR, 2, 1, vec![r[1]])
+        ).unwrap()
+        // An amplifier from port 0 (input) to port 2 (output) referenced to port 1, loaded by a divider
+        .define_subcircuit("loaded_amplifier", 3, |builder, gain| builder
+            .add_controlled_element(VOLTAGE_CONTROLLED_VOLTAGE_SOURCE, 1, 2, vec![gain[0]], ElementControl::Potential { input: 0, output: 1 })?
+            .add_subcircuit("divider", &[2, 1], &[1.0, 1.0])
+        ).unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(VOLTAGE_SOURCE, 0, 1, vec![10.0]).unwrap()
+        .add_subcircuit("divider", &[1, 0], &[1.0, 1.0]).unwrap()
+        .add_subcircuit("divider", &[1, 0], &[1.0, 3.0]).unwrap()
+        .add_subcircuit("loaded_amplifier", &[1, 0, 2], &[0.5]).unwrap();
+
+    assert!(builder.clone().define_subcircuit("divider", 2, |builder, _| Ok(builder)).is_err());
+    assert!(builder.clone().add_subcircuit("divider", &[1], &[1.0, 1.0]).is_err());
+    assert!(builder.clone().add_subcircuit("divider", &[1, 9], &[1.0, 1.0]).is_err());
+    assert!(builder.clone().add_subcircuit("filter", &[1, 0], &[]).is_err());
+
+    assert_eq!(builder.get_model().get_node_index("divider.0.2"), Some(3));
+    assert_eq!(builder.get_model().get_node_index("divider.1.2"), Some(4));
+    assert_eq!(builder.get_model().get_node_index("loaded_amplifier.0.divider.0.2"), Some(5));
+
+    let soln = serde_json::to_value(builder.run_study(1E-9, 100).unwrap()).unwrap();
+    let potential = |node: &str| soln["nodes"][node][0].as_f64().unwrap();
+
+    assert!((potential("divider.0.2") - 5.0).abs() < 1E-6);
+    assert!((potential("divider.1.2") - 7.5).abs() < 1E-6);
+    assert!((potential("2") - 5.0).abs() < 1E-6);
+    assert!((potential("loaded_amplifier.0.divider.0.2") - 2.5).abs() < 1E-6);
+}
+
+#[test]
+fn ensure_that_derived_powers_balance_across_a_circuit()
+{
+    use neapolitan::dc_circuits::*;
+
+    // A source driving a diode through a resistor, with a current source in parallel
+    let soln = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .expect("failed to create model builder object")
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .configure_node(2, vec![0.6], false, None)
+        .add_labeled_element("V1", VOLTAGE_SOURCE, 0, 1, vec![5.0]).unwrap()
+        .add_labeled_element("R1", RESISTOR,       1, 2, vec![1000.0]).unwrap()
+        .add_labeled_element("D1", DIODE,          2, 0, vec![1E-12, 0.025]).unwrap()
+        .add_labeled_element("I1", CURRENT_SOURCE, 0, 2, vec![1E-3]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    let power = |element: &str| soln.get_derived_quantities(element).unwrap()["power"];
+    let v2 = serde_json::to_value(&soln).unwrap()["nodes"]["2"][0].as_f64().unwrap();
+
+    assert!((power("R1") - (5.0 - v2).powi(2) / 1000.0).abs() < 1E-9);
+    assert!(power("D1") > 0.0);
+    assert!(power("V1") < 0.0 && power("I1") < 0.0);
+
+    // Every watt delivered by the sources is absorbed by the other elements
+    let total: f64 = ["V1", "R1", "D1", "I1"].iter().map(|&x| power(x)).sum();
+    assert!(total.abs() < 1E-9);
+
+    // Derived quantities are only serialized for the elements that have them
+    let json = serde_json::to_value(&soln).unwrap();
+    assert_eq!(json["derived"].as_object().unwrap().len(), 4);
+}
+
+#[test]
+fn ensure_that_spice_netlists_solve_like_spice()
+{
+    use neapolitan::modelling::NodalAnalysisModel;
+
+    let netlist = "bridge with a floating source
+* solved by hand, v(1) = 34/23, v(2) = 5/23 and v(3) = 120/23
+I1 0 1 DC 2mA
+R1 1 2 1e3
+r2 2 gnd 1K     ; lower case cards are fine
+R3 1 0 2k
+V1 3 2 dc 5V
+R4 3
++ 0 5k
+.op
+.END
+C1 1 0 1u";
+
+    let model = NodalAnalysisModel::from_spice(netlist).unwrap();
+    let soln = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let v = |node: &str| soln["nodes"][node][0].as_f64().unwrap();
+    assert!((v("n1") - 34.0 / 23.0).abs() < 1E-9);
+    assert!((v("n2") - 5.0 / 23.0).abs() < 1E-9);
+    assert!((v("n3") - 120.0 / 23.0).abs() < 1E-9);
+    assert!((soln["elements"]["R4"][0].as_f64().unwrap() - 24.0 / 23.0 * 1E-3).abs() < 1E-12);
+
+    let unsupported = NodalAnalysisModel::from_spice("title\nC1 1 0 1u\n.end");
+    assert_eq!(unsupported.unwrap_err().to_string(), "line 2: 'C1' cards are not supported. only resistors (R), voltage sources (V), current sources (I) and .end are");
+    assert!(NodalAnalysisModel::from_spice("title\nR1 1 2 1k\n").is_err());
+
+    // Node names are not case sensitive, and numbered nodes never take another node's name
+    let netlist = "title\nV1 N1 0 4\nR1 n1 1 1k\nR2 1 0 1k\n.end";
+    let model = NodalAnalysisModel::from_spice(netlist).unwrap();
+    let soln = NodalAnalysisStudyBuilder::from_model_with_default_config(model)
+        .run_study(1E-12, 100)
+        .unwrap();
+
+    let soln = serde_json::to_value(soln).unwrap();
+    let v = |node: &str| soln["nodes"][node][0].as_f64().unwrap();
+    assert!((v("n1") - 4.0).abs() < 1E-9);
+    assert!((v("n1_") - 2.0).abs() < 1E-9);
+}
+
+#[test]
+fn ensure_that_three_phase_loads_shift_their_star_point_per_phase()
+{
+    use neapolitan::three_phase::*;
+
+    let v = balanced_phases(100.0, 0.3);
+    let r = [11.0, 21.0, 41.0];
+    let g: Vec<f64> = r.iter().map(|r| 1.0 / r).collect();
+
+    let build = || NodalAnalysisStudyBuilder::new(THREE_PHASE.to_string(), None)
+        .unwrap()
+        .add_nodes(4)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(THREE_PHASE_SOURCE, 0, 1, vec![100.0, 0.3]).unwrap()
+        .add_element(RESISTOR, 1, 2, vec![1.0]).unwrap()
+        .add_terminal_element(UNBALANCED_LOAD, vec![2, 3], vec![10.0, 20.0, 40.0]).unwrap();
+
+    // Without a neutral, the phase currents must sum to zero
+    let floating = build().run_study(1E-12, 100).unwrap();
+    let star = (0..3).map(|k| g[k] * v[k]).sum::<f64>() / g.iter().sum::<f64>();
+    let potentials = floating.get_node_potential(3).unwrap();
+    assert!(potentials.iter().all(|s| (s - star).abs() < 1E-9));
+
+    let load = floating.get_element_flux(2).unwrap();
+    assert_eq!(load.len(), 6);
+    for k in 0..3
+    {
+        assert!((load[k] - g[k] * (v[k] - star)).abs() < 1E-9);
+        let bus = floating.get_node_potential(2).unwrap()[k];
+        assert!((bus - (v[k] - load[k])).abs() < 1E-9);
+    }
+    assert!(load[3].abs() < 1E-9);
+
+    // A neutral connection returns the unbalanced current to the source
+    let grounded = build()
+        .add_terminal_element(NEUTRAL_CONNECTION, vec![3, 0], vec![2.0]).unwrap()
+        .run_study(1E-12, 100)
+        .unwrap();
+    let star = (0..3).map(|k| g[k] * v[k]).sum::<f64>() / (g.iter().sum::<f64>() + 0.5);
+    assert!((grounded.get_node_potential(3).unwrap()[0] - star).abs() < 1E-9);
+
+    let load = grounded.get_element_flux(2).unwrap();
+    let neutral = grounded.get_element_flux(3).unwrap();
+    assert!((neutral[0] - star / 2.0).abs() < 1E-9);
+    assert!((load[0] + load[1] + load[2] - neutral[0]).abs() < 1E-9);
+
+    // Sources can only drive an unlocked bus from a locked neutral
+    assert!(NodalAnalysisStudyBuilder::new(THREE_PHASE.to_string(), None)
+        .unwrap()
+        .add_nodes(3)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(THREE_PHASE_SOURCE, 1, 2, vec![100.0]).unwrap()
+        .add_terminal_element(BALANCED_LOAD, vec![2, 1], vec![10.0]).unwrap()
+        .add_terminal_element(NEUTRAL_CONNECTION, vec![1, 0], vec![1.0]).unwrap()
+        .run_study(1E-9, 100)
+        .is_err());
+    assert!(NodalAnalysisStudyBuilder::new(THREE_PHASE.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_terminal_element(BALANCED_LOAD, vec![1, 0], vec![10.0, 20.0]).unwrap()
+        .run_study(1E-9, 100)
+        .is_err());
+}
+
+#[test]
+fn ensure_that_batteries_match_a_source_behind_their_internal_resistance()
+{
+    use neapolitan::dc_circuits::{BATTERY, CURRENT_SOURCE};
+
+    let table = [0.0, 3.0, 0.2, 3.6, 0.8, 3.9, 1.0, 4.2];
+    for (state, ocv) in [(-0.5, 3.0), (0.1, 3.3), (0.5, 3.75), (0.9, 4.05), (1.5, 4.2)]
+    {
+        let mut gain = vec![0.1, state];
+        gain.extend(table);
+
+        // Charging the battery raises its terminal voltage above its open-circuit voltage
+        let battery = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .unwrap()
+            .add_nodes(2)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(BATTERY,        0, 1, gain).unwrap()
+            .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+            .run_study(1E-12, 100)
+            .unwrap();
+
+        let composed = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+            .unwrap()
+            .add_nodes(3)
+            .configure_node(0, vec![0.0], true, None)
+            .add_element(VOLTAGE_SOURCE, 0, 2, vec![ocv]).unwrap()
+            .add_element(RESISTOR,       2, 1, vec![0.1]).unwrap()
+            .add_element(CURRENT_SOURCE, 0, 1, vec![2.0]).unwrap()
+            .run_study(1E-12, 100)
+            .unwrap();
+
+        let v = battery.get_node_potential(1).unwrap()[0];
+        assert!((v - composed.get_node_potential(1).unwrap()[0]).abs() < 1E-9);
+        assert!((v - (ocv + 0.2)).abs() < 1E-9);
+        assert!((battery.get_derived_quantities("battery.0").unwrap()["power"] - 2.0 * v).abs() < 1E-9);
+    }
+
+    // The table must be in order of increasing state of charge
+    let builder = NodalAnalysisStudyBuilder::new(DC_CIRCUIT.to_string(), None)
+        .unwrap()
+        .add_nodes(2)
+        .configure_node(0, vec![0.0], true, None)
+        .add_element(BATTERY, 0, 1, vec![0.1, 0.5, 1.0, 4.2, 0.0, 3.0]).unwrap()
+        .add_element(RESISTOR, 1, 0, vec![1.0]).unwrap();
+    assert!(builder.run_study(1E-12, 100).is_err());
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use geqslib::shunting::{eval_str_with_context, new_context, ContextLike};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::parsing::{comments, consts, conversions, legal_name, namespaces};
+
+/// The results to show beside a single line of a system, e.g. in an equation editor.
+///
+/// # Fields:
+/// - `line` - the (1-based) line number that the annotation belongs to
+/// - `values` - the solved value of each variable that first appears on this line
+/// - `residual` - the left hand side minus the right hand side of the line's equation at the
+///   solution, if the line is an equation that could be evaluated
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineAnnotation
+{
+    pub line: usize,
+    pub values: Vec<(String, f64)>,
+    pub residual: Option<f64>,
+}
+
+/// Evaluates the residual of an equation given the solved value of every variable in it.
+fn residual(equation: &str, solution: &HashMap<String, f64>) -> Option<f64>
+{
+    let mut ctx = new_context();
+    for (name, &val) in solution
+    {
+        ctx.add_const_to_ctx(&legal_name(name), val);
+    }
+
+    let (equation, _) = namespaces(&conversions(&consts(equation).ok()?).ok()?);
+    let (lhs, rhs) = equation.split_once('=')?;
+
+    eval_str_with_context(&format!("({lhs}) - ({rhs})"), &ctx).ok()
+}
+
+/// Annotates each line of a system with the solution found for it, so that results can be
+/// shown beside the lines that produced them. A variable's value is given on the first line
+/// that defines it (in an equation or `const`), and each equation is given its residual.
+/// Lines with nothing to show are omitted.
+///
+/// Equations inside conditional statements are given values but no residual,
+/// since only one of their branches holds at the solution.
+///
+/// # Example
+/// ```
+/// use nexsys::solve_with_preprocessors;
+/// use nexsys::annotations::annotate;
+///
+/// let system = "const a = 9\nguess 2 for y\nx + y = a // first\nx - y = 4";
+/// let (_log, soln) = solve_with_preprocessors(system, 1E-9, 100).unwrap();
+///
+/// let annotations = annotate(system, &soln);
+///
+/// assert_eq!(annotations[0].line, 1);
+/// assert_eq!(annotations[0].values, vec![("a".to_string(), 9.0)]);
+/// assert_eq!(annotations[1].line, 3);
+/// assert_eq!(annotations[1].values.len(), 2);
+/// assert!(annotations[1].residual.unwrap().abs() < 1E-6);
+/// assert!(annotations[2].values.is_empty());
+/// ```
+pub fn annotate(system: &str, solution: &HashMap<String, f64>) -> Vec<LineAnnotation>
+{
+    lazy_static!
+    {
+        static ref VAR: Regex = Regex::new(r"(?i)\b[a-z][a-z0-9_]*(\.[a-z][a-z0-9_]*)*").unwrap();
+        static ref DIRECTIVE: Regex = Regex::new(r"(?i)^(alias|settings|keep|guess|unit|load|piecewise) ").unwrap();
+    }
+    let builtins = new_context();
+
+    let mut annotations = vec![];
+    let mut defined: Vec<&str> = vec![];
+    let mut depth = 0;
+
+    // Comments are removed without removing their lines, so line numbers are unchanged
+    let text = comments(system);
+    for (idx, line) in text.lines().enumerate()
+    {
+        let line = line.trim();
+
+        if (line.starts_with("if ") || line.starts_with("smooth")) && line.ends_with(':')
+        {
+            depth += 1;
+            continue;
+        }
+        if line == "end"
+        {
+            depth -= 1;
+            continue;
+        }
+        if !line.contains('=') || DIRECTIVE.is_match(line)
+        {
+            continue;
+        }
+
+        let is_const = line.to_lowercase().starts_with("const ");
+        let equation = if is_const { &line[6..] } else { line };
+
+        let mut values = vec![];
+        for var in VAR.find_iter(equation).map(|m| m.as_str())
+        {
+            if defined.contains(&var) || builtins.contains_key(var)
+            {
+                continue;
+            }
+
+            if let Some((name, &val)) = solution.get_key_value(var)
+            {
+                defined.push(name);
+                values.push((name.to_owned(), val));
+            }
+        }
+
+        let residual = match is_const || depth > 0
+        {
+            true => None,
+            false => residual(equation, solution),
+        };
+
+        if !values.is_empty() || residual.is_some()
+        {
+            annotations.push(LineAnnotation { line: idx + 1, values, residual });
+        }
+    }
+
+    annotations
+}
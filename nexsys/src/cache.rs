@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::Path;
+
+use geqslib::shunting::{new_context, ContextHashMap, Token};
+use serde_json::{json, Value};
+
+use crate::{compile_with_preprocessors, solve_compiled_system, CompiledSystem};
+
+/// Writes a canonical description of everything that affects the solution of a
+/// compiled system: the compiled equations, the solver settings, every declared
+/// guess and domain, every known constant, every piecewise function declaration 
+/// and the names used to report results.
+fn canonical_key(system: &CompiledSystem, ctx: &ContextHashMap) -> String
+{
+    fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)>
+    {
+        let mut entries: Vec<_> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    let mut key = format!(
+        "nexsys {}\nmethod {:?}\nmargin {:?}\nlimit {}\n",
+        env!("CARGO_PKG_VERSION"), system.method, system.margin, system.limit
+    );
+
+    for (var, [guess, min, max]) in sorted(&system.declared)
+    {
+        let _ = writeln!(key, "declare {var} {guess:?} {min:?} {max:?}");
+    }
+
+    let consts: HashMap<String, f64> = ctx.iter()
+        .filter_map(|(name, tok)| match tok
+        {
+            Token::Num(n) => Some((name.to_owned(), *n)),
+            _ => None,
+        })
+        .collect();
+    for (name, val) in sorted(&consts)
+    {
+        let _ = writeln!(key, "const {name} {val:?}");
+    }
+
+    for (alias, target) in sorted(&system.aliased)
+    {
+        let _ = writeln!(key, "alias {alias} {target}");
+    }
+
+    for (legal_name, name) in sorted(&system.namespaced)
+    {
+        let _ = writeln!(key, "namespace {legal_name} {name}");
+    }
+
+    // Piecewise functions are removed from the compiled text, so their bodies are keyed as declared
+    for declaration in &system.functions
+    {
+        let _ = writeln!(key, "{declaration}");
+    }
+
+    // Blank lines and surrounding whitespace do not affect the solution
+    for line in system.compiled.lines().map(str::trim).filter(|x| !x.is_empty())
+    {
+        let _ = writeln!(key, "{line}");
+    }
+
+    key
+}
+
+/// Calculates a 64-bit FNV-1a hash of the given text.
+fn hash(text: &str) -> u64
+{
+    text.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Reads a cached solution, returning `None` if there is no usable entry for `key`.
+fn read_entry(path: &Path, key: &str) -> Option<(Vec<String>, HashMap<String, f64>)>
+{
+    let entry: Value = serde_json::from_str(&read_to_string(path).ok()?).ok()?;
+
+    // Guard against hash collisions by checking the full key
+    if entry.get("key")?.as_str()? != key
+    {
+        return None;
+    }
+
+    let log = serde_json::from_value(entry.get("log")?.clone()).ok()?;
+    let soln = serde_json::from_value(entry.get("solution")?.clone()).ok()?;
+    Some((log, soln))
+}
+
+/// Solves a system of equations exactly as `solve_with_preprocessors` does, but
+/// first looks for a stored solution in `cache_dir` and stores any new solution
+/// there. Solutions are keyed by a hash of the compiled system and its settings,
+/// so editing comments or formatting does not invalidate them, but changing any
+/// equation, constant, guess, domain, piecewise function or setting (including the 
+/// values loaded by a `load constants from` directive) does.
+///
+/// The cache directory is created if it does not already exist. Unreadable or
+/// corrupted entries are treated as missing and are overwritten.
+///
+/// # Example
+/// ```
+/// use nexsys::cache::solve_with_cache;
+///
+/// let cache_dir = std::env::temp_dir().join("nexsys_doc_cache");
+///
+/// let (_log, first) = solve_with_cache("x^2 = 16\nguess 3 for x", 0.0001, 100, &cache_dir)
+///     .expect("failed to solve system!");
+///
+/// // Only the comment differs, so this is answered from the cache.
+/// let (_log, second) = solve_with_cache("x^2 = 16 // the same\nguess 3 for x", 0.0001, 100, &cache_dir)
+///     .expect("failed to solve system!");
+///
+/// assert_eq!(first, second);
+/// assert!((second["x"] - 4.0).abs() < 0.001);
+/// ```
+pub fn solve_with_cache(system: &str, margin: f64, limit: usize, cache_dir: impl AsRef<Path>) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
+{
+    let mut ctx = new_context();
+    let compiled = compile_with_preprocessors(system, &mut ctx, margin, limit)?;
+
+    let key = canonical_key(&compiled, &ctx);
+    let path = cache_dir.as_ref().join(format!("{:016x}.json", hash(&key)));
+
+    if let Some(cached) = read_entry(&path, &key)
+    {
+        return Ok(cached);
+    }
+
+    let (log, soln) = solve_compiled_system(compiled, &mut ctx)?;
+
+    create_dir_all(cache_dir.as_ref())?;
+    write(&path, json!({ "key": key, "log": log, "solution": soln }).to_string())?;
+
+    Ok((log, soln))
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+/// How a single variable differs between two solutions.
+///
+/// # Fields:
+/// - `name` - the variable's name
+/// - `expected` - the variable's value in the first (baseline) solution, if it has one
+/// - `actual` - the variable's value in the second solution, if it has one
+/// - `delta` - `actual - expected`, if the variable is in both solutions
+/// - `passed` - whether the variable is in both solutions and agrees to within the tolerance
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableDelta
+{
+    pub name: String,
+    pub expected: Option<f64>,
+    pub actual: Option<f64>,
+    pub delta: Option<f64>,
+    pub passed: bool,
+}
+
+impl VariableDelta
+{
+    /// Returns `delta` as a percentage of the variable's baseline value, or `None` if the
+    /// variable is not in both solutions or its baseline value is zero.
+    pub fn percent_change(&self) -> Option<f64>
+    {
+        match (self.expected, self.delta)
+        {
+            (Some(e), Some(delta)) if e != 0.0 => Some(100.0 * delta / e.abs()),
+            _ => None,
+        }
+    }
+}
+
+/// The result of comparing two solutions variable by variable.
+///
+/// # Fields:
+/// - `deltas` - how each variable in either solution differs, sorted by name
+/// - `tolerance` - the tolerance that the solutions were compared with
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionComparison
+{
+    pub deltas: Vec<VariableDelta>,
+    pub tolerance: f64,
+}
+impl SolutionComparison
+{
+    /// Whether every variable agrees between the two solutions.
+    pub fn passed(&self) -> bool
+    {
+        self.deltas.iter().all(|d| d.passed)
+    }
+
+    /// Returns the variables that do not agree between the two solutions.
+    pub fn failures(&self) -> Vec<&VariableDelta>
+    {
+        self.deltas.iter().filter(|d| !d.passed).collect()
+    }
+}
+impl fmt::Display for SolutionComparison
+{
+    /// Lists each variable that does not agree, followed by a one line pass/fail summary.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let failures = self.failures();
+        for d in &failures
+        {
+            match (d.expected, d.actual, d.delta)
+            {
+                (Some(e), Some(a), Some(delta)) => writeln!(f, "{}: expected {e}, got {a} (delta {delta})", d.name)?,
+                (Some(e), None, _) => writeln!(f, "{}: expected {e}, but it is missing", d.name)?,
+                (_, Some(a), _) => writeln!(f, "{}: got {a}, but it was not expected", d.name)?,
+                _ => writeln!(f, "{}: missing from both solutions", d.name)?,
+            }
+        }
+
+        match failures.is_empty()
+        {
+            true => write!(f, "PASS: all {} variables agree to within {}", self.deltas.len(), self.tolerance),
+            false => write!(f, "FAIL: {} of {} variables differ by more than {}", failures.len(), self.deltas.len(), self.tolerance),
+        }
+    }
+}
+
+/// Compares solution `b` against a baseline solution `a`, reporting how each variable
+/// differs. A variable agrees if it is in both solutions and its values differ by no more
+/// than `tol` times the larger of 1 and its baseline magnitude, i.e. `tol` is an absolute
+/// tolerance for small values and a relative tolerance for large ones. Variables that are
+/// only in one of the solutions never agree.
+///
+/// This is intended for regression testing, e.g. re-solving a system after its
+/// correlations or the solver have been updated and comparing against saved results.
+///
+/// # Example
+/// ```
+/// use nexsys::solve_with_preprocessors;
+/// use nexsys::comparison::compare_solutions;
+///
+/// let (_, a) = solve_with_preprocessors("x + y = 9\nx - y = 4", 1E-9, 100).unwrap();
+/// let (_, b) = solve_with_preprocessors("x + y = 9\nx - y = 4.5", 1E-9, 100).unwrap();
+///
+/// let comparison = compare_solutions(&a, &b, 1E-6);
+/// assert!(!comparison.passed());
+///
+/// let failed: Vec<&str> = comparison.failures().iter().map(|d| d.name.as_str()).collect();
+/// assert_eq!(failed, vec!["x", "y"]);
+/// assert!((comparison.failures()[0].delta.unwrap() - 0.25).abs() < 1E-6);
+/// ```
+pub fn compare_solutions(a: &HashMap<String, f64>, b: &HashMap<String, f64>, tol: f64) -> SolutionComparison
+{
+    let mut names: Vec<&String> = a.keys().chain(b.keys().filter(|x| !a.contains_key(*x))).collect();
+    names.sort();
+
+    let deltas = names.into_iter()
+        .map(|name| {
+            let expected = a.get(name).copied();
+            let actual = b.get(name).copied();
+            let delta = expected.zip(actual).map(|(e, a)| a - e);
+            let passed = match (expected, delta)
+            {
+                (Some(e), Some(delta)) => delta.abs() <= tol * e.abs().max(1.0),
+                _ => false,
+            };
+            VariableDelta { name: name.to_owned(), expected, actual, delta, passed }
+        })
+        .collect();
+
+    SolutionComparison { deltas, tolerance: tol }
+}
+
+/// Reads the solution back out of the output that `nxc` writes with `--output-file`,
+/// i.e. each `<name> = <value>` line before the `Procedure:` section.
+pub fn read_solution(text: &str) -> HashMap<String, f64>
+{
+    lazy_static!
+    {
+        static ref RE: Regex = Regex::new(r"(?i)^([a-z][a-z0-9_.]*) = (\S+)$").unwrap();
+    }
+
+    text.lines()
+        .take_while(|line| line.trim() != "Procedure:")
+        .filter_map(|line| RE.captures(line.trim()))
+        .filter_map(|c| Some((c[1].to_owned(), c[2].parse().ok()?)))
+        .collect()
+}
+
+/// Reads a solution out of a JSON document, e.g. a nexsys solution or a neapolitan 
+/// `.soln.json` file, so that it can be compared with `compare_solutions`. Every number in
+/// the document is named by the path to it, with object keys separated by `.` and array 
+/// indices in brackets, except that arrays of one number are named as if they were that
+/// number. For example, `{ "nodes": { "1": [5.0] } }` gives `nodes.1 = 5`.
+///
+/// # Example
+/// ```
+/// use nexsys::comparison::read_json_solution;
+///
+/// let soln = read_json_solution(r#"{ "nodes": { "vcc": [5.0], "ac": [1.0, 0.5] }, "warnings": [] }"#).unwrap();
+///
+/// assert_eq!(soln["nodes.vcc"], 5.0);
+/// assert_eq!(soln["nodes.ac[1]"], 0.5);
+/// assert_eq!(soln.len(), 3);
+/// ```
+pub fn read_json_solution(text: &str) -> anyhow::Result<HashMap<String, f64>>
+{
+    fn flatten(value: &Value, path: String, soln: &mut HashMap<String, f64>)
+    {
+        match value
+        {
+            Value::Number(x) => 
+            {
+                soln.insert(path, x.as_f64().unwrap_or(f64::NAN));
+            },
+            Value::Array(xs) if xs.len() == 1 => flatten(&xs[0], path, soln),
+            Value::Array(xs) => for (idx, x) in xs.iter().enumerate()
+            {
+                flatten(x, format!("{path}[{idx}]"), soln);
+            },
+            Value::Object(map) => for (key, x) in map
+            {
+                let path = match path.is_empty()
+                {
+                    true => key.to_owned(),
+                    false => format!("{path}.{key}"),
+                };
+                flatten(x, path, soln);
+            },
+            _ => (),
+        }
+    }
+
+    let mut soln = HashMap::new();
+    flatten(&serde_json::from_str(text)?, String::new(), &mut soln);
+    Ok(soln)
+}
@@ -74,12 +74,14 @@ impl_err!(
 #[derive(Debug)]
 pub enum ConditionFormatError {
     ConditionalSyntax,
-    Comparator
+    Comparator,
+    SmoothEquality
 }
 impl_err!(
     ConditionFormatError,
     ConditionFormatError::ConditionalSyntax,    "conditional statement failed to compile",
-    ConditionFormatError::Comparator,           "invalid comparison operator. valid operators are: <, >, <=, >=, ==, !="
+    ConditionFormatError::Comparator,           "invalid comparison operator. valid operators are: <, >, <=, >=, ==, !=",
+    ConditionFormatError::SmoothEquality,       "smooth if statements can only compare with <, >, <= or >="
 );
 
 #[derive(Debug)]
@@ -103,9 +105,144 @@ impl_err!(
     "failed to identify conversion factors"
 );
 
+#[derive(Debug)]
+pub struct UnitFormatError;
+impl_err!(
+    UnitFormatError,
+    "unit declaration failed to compile because the unit is not recognized"
+);
+
 #[derive(Debug)]
 pub struct SolverConvergenceError;
 impl_err!(
     SolverConvergenceError,
     "solver algorithm did not converge. consider allowing non-convergent solutions, or try to remove discontinuities from your system"
-);
\ No newline at end of file
+);
+
+/// Error type for issues with `settings` directives in `nexsys::parsing`
+#[derive(Debug)]
+pub enum SettingsFormatError {
+    UnknownSetting,
+    InvalidValue,
+    UnknownMethod
+}
+impl_err!(
+    SettingsFormatError,
+    SettingsFormatError::UnknownSetting,    "unknown solver setting. valid settings are: margin, iterations, method, relaxation, suffixes, locale",
+    SettingsFormatError::InvalidValue,      "solver setting value failed to compile",
+    SettingsFormatError::UnknownMethod,     "unknown solver method. valid methods are: newton, broyden, fixed_point"
+);
+
+#[derive(Debug)]
+pub struct AliasFormatError;
+impl_err!(
+    AliasFormatError,
+    "alias failed to compile because it refers back to itself"
+);
+
+#[derive(Debug)]
+pub struct ReservedNameError;
+impl_err!(
+    ReservedNameError,
+    "variable names may not contain '__', which is reserved for namespaced names (write 'hx1.ua' rather than 'hx1__ua')"
+);
+
+#[derive(Debug)]
+pub struct ServerBindError;
+impl_err!(
+    ServerBindError,
+    "the server could not listen on the given address"
+);
+
+/// Error type for issues with `load constants from` directives in `nexsys::parsing`
+#[derive(Debug)]
+pub enum CsvFormatError {
+    UnreadableFile,
+    MissingRow,
+    ColumnCountMismatch,
+    InvalidColumnName,
+    InvalidValue
+}
+impl_err!(
+    CsvFormatError,
+    CsvFormatError::UnreadableFile,         "the csv file named in a 'load constants from' directive could not be read",
+    CsvFormatError::MissingRow,             "the csv file does not contain the requested row of values",
+    CsvFormatError::ColumnCountMismatch,    "a row of the csv file does not have the same number of values as its header",
+    CsvFormatError::InvalidColumnName,      "a column name in the csv file is not a legal nexsys variable name",
+    CsvFormatError::InvalidValue,           "a value in the csv file is not a number"
+);
+
+/// Error type for saved editor sessions that are missing a field or have a field of the wrong type.
+#[derive(Debug)]
+pub enum SessionFormatError {
+    Documents,
+    OpenTabs,
+    ActiveTab
+}
+impl_err!(
+    SessionFormatError,
+    SessionFormatError::Documents,  "a session's 'documents' must map each document's path to its text",
+    SessionFormatError::OpenTabs,   "a session's 'open_tabs' must be a list of document paths",
+    SessionFormatError::ActiveTab,  "a session's 'active_tab' must be a document path or null"
+);
+
+/// Error type for `piecewise` function declarations that could not be read.
+#[derive(Debug)]
+pub enum PiecewiseFormatError {
+    BranchSyntax,
+    Comparator,
+    MissingElse,
+    Smoothing
+}
+impl_err!(
+    PiecewiseFormatError,
+    PiecewiseFormatError::BranchSyntax, "each branch of a piecewise function must be written as 'condition -> value', and no branch may follow 'else'",
+    PiecewiseFormatError::Comparator,   "piecewise conditions must compare two expressions with one of '<', '<=', '>' or '>='",
+    PiecewiseFormatError::MissingElse,  "the last branch of a piecewise function must be 'else -> value'",
+    PiecewiseFormatError::Smoothing,    "the smoothing width of a piecewise function must be a positive number"
+);
+
+/// Error type for equations and constants in a system that conflict with one another. 
+/// Each variant holds the line numbers of the first and second conflicting lines.
+#[derive(Debug)]
+pub enum ConflictError {
+    DuplicateEquation(usize, usize),
+    ConstRedefined(usize, usize)
+}
+impl Error for ConflictError {}
+impl Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConflictError::DuplicateEquation(a, b) => write!(f, "the equation on line {b} duplicates the equation on line {a}, which over-constrains the system"),
+            ConflictError::ConstRedefined(a, b) => write!(f, "the constant defined on line {b} was already defined with a different value on line {a}"),
+        }
+    }
+}
+
+/// Error type for compiled systems that are exported with no equations to solve.
+#[derive(Debug)]
+pub struct EmptySystemError;
+impl_err!(
+    EmptySystemError,
+    "the compiled system has no equations to build a system of equations from"
+);
+
+/// Error type for numbers that are not written the way the system's locale writes them.
+/// 
+/// # Variants:
+/// - `UnknownLocale` - holds the locale tag that was given
+/// - `UnreadableNumber` - holds the line number and the number as it was written
+#[derive(Debug)]
+pub enum LocaleFormatError {
+    UnknownLocale(String),
+    UnreadableNumber(usize, String)
+}
+impl Error for LocaleFormatError {}
+impl Display for LocaleFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocaleFormatError::UnknownLocale(tag) => write!(f, "'{tag}' is not a known locale. try a language tag like en, de, fr or de-ch"),
+            LocaleFormatError::UnreadableNumber(line, number) => write!(f, "the number '{number}' on line {line} is not written the way the system's locale writes numbers"),
+        }
+    }
+}
@@ -1,82 +1,89 @@
+/// Provides per-line annotations of a solved system for display in an editor.
+pub mod annotations;
+/// Provides an on-disk cache of solutions to previously-solved systems.
+pub mod cache;
+/// Provides tools for comparing solutions, e.g. to regression test a system.
+pub mod comparison;
 /// Different errors specific to Nexsys implementations of algorithms.
 pub mod errors;
 /// Provides `extern "C"` functions for use in other programming languages. Not 
 /// intended for use in other Rust projects.
+#[allow(clippy::missing_safety_doc, clippy::not_unsafe_ptr_arg_deref)]
 pub mod ffi;
 /// Provides tools for parsing text prior to passing to the equation solving engine.
 pub mod parsing;
+/// Provides crash-safe storage of unsaved editor documents and open tabs.
+pub mod session;
+/// Provides a JSON-RPC service for solving systems over HTTP.
+#[cfg(feature = "server")]
+pub mod server;
 /// Provides data sets of common units and functions for converting between them.
 pub mod units;
 
 use std::collections::HashMap;
 
-use geqslib::solve_equation_with_context;
-use geqslib::shunting::{new_context, ContextHashMap, ContextLike, Token};
-use geqslib::system::{ConstrainResult, get_equation_unknowns, SystemBuilder};
+use geqslib::{solve_equation_with_fallback, RootMethod};
+use geqslib::shunting::{get_legal_variables_iter, new_context, ContextHashMap, ContextLike, Token};
+use geqslib::system::{get_equation_unknowns, SolverMethod, SystemBuilder};
 
-use parsing::compile;
+use errors::{EmptySystemError, SettingsFormatError};
+use lazy_static::lazy_static;
+use regex::Regex;
+use parsing::{aliases, comments, compile, conflicts_at_lines, csv_constants, guess_values, legal_name, literals, localized_numbers, namespaces, reserved_names, solver_settings, NumberLocale};
 
 /// Solves a single equation for a single unknown value, returning a `bool` indicating if the solution attempt was successful 
 fn try_solve_single_unknown_eqn(eqn_pool: &mut Vec<String>, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, log_step: &mut String, margin: f64, limit: usize) -> anyhow::Result<bool>
 {
     for (i, equation) in eqn_pool.iter().enumerate()
     {
-        let unknowns: Vec<&str> = get_equation_unknowns(&equation, ctx).collect();
+        let unknowns: Vec<&str> = get_equation_unknowns(equation, ctx).collect();
         if unknowns.len() != 1
         {
-            return Ok(false);
+            continue;
         }
 
-        let var_info: [f64; 3];
-        if declared.contains_key(unknowns[0])
-        {
-            var_info = declared[unknowns[0]];
-        }
-        else
+        let var_info = match declared.get(unknowns[0])
         {
-            var_info = [1.0, f64::NEG_INFINITY, f64::INFINITY];
-        }
+            Some(info) => *info,
+            None => [1.0, f64::NEG_INFINITY, f64::INFINITY],
+        };
 
-        let soln = solve_equation_with_context(equation, ctx, var_info[0], var_info[1], var_info[2], margin, limit)?;
-        ctx.add_var_with_domain_to_ctx(&soln.0, soln.1, var_info[1], var_info[2]);
+        let soln = solve_equation_with_fallback(equation, ctx, var_info[0], var_info[1], var_info[2], margin, limit)?;
+        // The unknown is known from here on. Left as a variable, it would be counted as another
+        // unknown when the equations that use it are compiled
+        ctx.add_const_to_ctx(&soln.0, soln.1);
         *log_step = format!(
             "Var: {:#?} \nEquation: {}", 
             soln.0, equation
         );
+
+        // Note when newton-raphson left the declared domain and the root had to be bracketed
+        if soln.2 == RootMethod::Brent
+        {
+            log_step.push_str(" \nMethod: Brent (bracketed on the declared domain)");
+        }
         eqn_pool.remove(i);
         return Ok(true);
     }
     Ok(false)
 }
 
-fn try_solve_subsystem_of_equations(eqn_pool: &mut Vec<String>, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, log_step: &mut String, margin: f64, limit: usize) -> anyhow::Result<bool>
+fn try_solve_subsystem_of_equations(eqn_pool: &mut Vec<String>, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, log_step: &mut String, method: SolverMethod, margin: f64, limit: usize) -> anyhow::Result<bool>
 {
     for (i, equation) in eqn_pool.iter().enumerate()
     {
         let mut builder = SystemBuilder::new(equation, ctx.clone())?;
-        let mut eqn_strings = vec![equation.to_owned()];
-
-        for (j, equation) in eqn_pool.iter().enumerate()
-        {
-            // Skip the current equation we're building a system with 
-            // OR an equation that we've already added to the system
-            if j == i || eqn_strings.contains(equation)
-            {
-                continue;
-            }
 
-            // println!("Constraint status: {}", builder.is_fully_constrained());
+        // Let the builder pick which of the other equations keep 
+        // the subsystem smallest and simplest
+        let candidates = eqn_pool.iter()
+            .enumerate()
+            .filter(|(j, x)| *j != i && *x != equation)
+            .map(|(_, x)| x.as_str())
+            .collect();
+        builder.try_fully_constrain_with(candidates)?;
 
-            match builder.try_constrain_with(equation)?
-            {
-                ConstrainResult::WillConstrain => {
-                    eqn_strings.push(equation.to_owned());
-                    // println!("constrained with: {}", equation);
-                },
-                ConstrainResult::WillOverConstrain => break,
-                _ => {}
-            }
-        }
+        let eqn_strings = builder.get_equations().clone();
             
         *log_step = format!("{:#?}", eqn_strings);
 
@@ -88,7 +95,7 @@ fn try_solve_subsystem_of_equations(eqn_pool: &mut Vec<String>, ctx: &mut Contex
                 system.specify_variable(var, var_info[0], var_info[1], var_info[2]);
             }
 
-            let soln = system.solve(margin, limit)?;
+            let soln = system.solve_with_method(method, margin, limit)?;
             for (var, val) in soln 
             {
                 ctx.add_const_to_ctx(&var, val);
@@ -118,6 +125,13 @@ fn try_solve_subsystem_of_equations(eqn_pool: &mut Vec<String>, ctx: &mut Contex
 /// ```
 /// ```
 pub fn basic_solve(system: &str, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, margin: f64, limit: usize) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
+{
+    solve_with_method(system, ctx, declared, SolverMethod::NewtonRaphson, margin, limit)
+}
+
+/// Solves a system of equations in plain-text format, using the given 
+/// `SolverMethod` for any subsystems of equations that must be solved simultaneously.
+fn solve_with_method(system: &str, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, method: SolverMethod, margin: f64, limit: usize) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
 {
     let mut log = vec![];
     let mut eqn_pool = system.split('\n')
@@ -136,7 +150,7 @@ pub fn basic_solve(system: &str, ctx: &mut ContextHashMap, declared: &mut HashMa
         }
 
         // Dig in and solve a more expensive subsystem:
-        if try_solve_subsystem_of_equations(&mut eqn_pool, ctx, declared, &mut log_step, margin, limit)?
+        if try_solve_subsystem_of_equations(&mut eqn_pool, ctx, declared, &mut log_step, method, margin, limit)?
         {
             log.push(log_step);
             continue;
@@ -166,17 +180,54 @@ pub fn basic_solve(system: &str, ctx: &mut ContextHashMap, declared: &mut HashMa
 /// Solves a system of equations with additional syntax used to indicate 
 /// unit conversions, constant known values, nicer if statements, and more.
 /// 
+/// Any `settings margin = ...`, `settings iterations = ...`, or 
+/// `settings method = ...` directives in the system override the given 
+/// `margin` and `limit` and the default `newton` solver method, respectively.
+/// The `fixed_point` method solves subsystems whose equations are written as 
+/// `x = g(x)` by successive substitution, damped by `settings relaxation = ...`.
+/// 
+/// With `settings suffixes = on`, numbers may be written with engineering suffixes 
+/// or as percentages (e.g. `3.3k`, `10u` or `5%`). See `parsing::literals` for details.
+/// 
+/// With `settings locale = <tag>` (e.g. `de` or `fr`), numbers are read the way that 
+/// locale writes them, e.g. `3,14` or `1.234,5`. See `parsing::localized_numbers` for details.
+/// 
+/// Variables may be namespaced with dots (e.g. `hx1.ua`) and given 
+/// additional names with `alias <name> = <variable>`. Both are reported 
+/// by their written names in the solution. Names may not contain `__`, 
+/// which is reserved for namespaced variables.
+/// 
+/// Variables measured in an absolute unit (e.g. `unit K for t` or 
+/// `unit psia for p`) are kept nonnegative while solving, unless they are 
+/// given a different domain with `keep <variable> on [<min>, <max>]`.
+/// 
+/// Systems that repeat an equation (even rearranged or rescaled) or give a 
+/// `const` two different values fail to compile with an error naming the 
+/// conflicting lines, rather than leaving the solver an over-constrained system.
+/// 
+/// Unknowns that are not given a guess with `guess <value> for <variable>` 
+/// are given one automatically (see `guess_unknowns`), which is recorded as 
+/// the first entry in the solve log.
+/// 
+/// Known values can be loaded from a csv file with 
+/// `load constants from "<file>.csv"`, which binds each column name to 
+/// the value below it. See `parsing::csv_constants` for details.
+/// 
 /// # Example
 /// ```
 /// use nexsys::solve_with_preprocessors;
 /// 
 /// let system = r#"
+/// settings method = broyden
 /// keep x on [0, 100]
 /// guess 3 for y
+/// unit K for y
 /// const nine = 9
+/// alias sum = nine
 /// 
-/// x + y = nine
-/// x - y = 4
+/// x + y = sum
+/// pair.diff = 4
+/// x - y = pair.diff
 /// 
 /// if x > y:
 ///     i = 1
@@ -191,12 +242,413 @@ pub fn basic_solve(system: &str, ctx: &mut ContextHashMap, declared: &mut HashMa
 /// assert!((f64::from(soln["x"]) - 6.5).abs() < 0.001);
 /// assert!((f64::from(soln["y"]) - 2.5).abs() < 0.001);
 /// assert!((f64::from(soln["i"]) - 1.0).abs() < 0.001);
+/// assert!((f64::from(soln["pair.diff"]) - 4.0).abs() < 0.001);
 /// ```
 pub fn solve_with_preprocessors(system: &str, margin: f64, limit: usize) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
 {
     let mut ctx = new_context(); 
+    solve_with_preprocessors_and_context(system, &mut ctx, margin, limit)
+}
+
+/// Solves a system of equations with the same additional syntax as 
+/// `solve_with_preprocessors`, but uses the given `ctx` rather than
+/// a new default context. This allows functions registered with 
+/// `register_fn` to be called from the system's equations.
+/// 
+/// # Example
+/// ```
+/// use geqslib::shunting::new_context;
+/// use nexsys::{register_fn, solve_with_preprocessors_and_context};
+/// 
+/// let mut ctx = new_context();
+/// register_fn(&mut ctx, "double", 1, |args| 2.0 * args[0]);
+/// 
+/// let (_log, soln) = solve_with_preprocessors_and_context("y = double(3)", &mut ctx, 0.0001, 100)
+///     .expect("failed to solve system!");
+/// 
+/// assert!((soln["y"] - 6.0).abs() < 0.001);
+/// ```
+pub fn solve_with_preprocessors_and_context(system: &str, ctx: &mut ContextHashMap, margin: f64, limit: usize) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
+{
+    let compiled = compile_with_preprocessors(system, ctx, margin, limit)?;
+    solve_compiled_system(compiled, ctx)
+}
+
+/// Solves a system once for each of the given `values` of the known variable `var`, 
+/// which should be left undefined in the system. Returns the solution in columns, 
+/// mapping each variable (including `var`) to its value at each point in turn, 
+/// which is the layout expected by dataframe libraries. A variable that is not 
+/// solved at some of the points (e.g. in one branch of a conditional) is `NaN` 
+/// at those points, so that every column has a value for each point.
+/// 
+/// # Example
+/// ```
+/// use nexsys::solve_parametric;
+/// 
+/// let columns = solve_parametric("x + y = a\nx - y = 4", "a", &[8.0, 10.0, 12.0], 1E-9, 100)
+///     .expect("failed to solve system!");
+/// 
+/// assert_eq!(columns["a"], vec![8.0, 10.0, 12.0]);
+/// for (x, expected) in columns["x"].iter().zip([6.0, 7.0, 8.0])
+/// {
+///     assert!((x - expected).abs() < 0.001);
+/// }
+/// ```
+pub fn solve_parametric(system: &str, var: &str, values: &[f64], margin: f64, limit: usize) -> anyhow::Result<HashMap<String, Vec<f64>>>
+{
+    let mut columns: HashMap<String, Vec<f64>> = HashMap::new();
+
+    for (i, &value) in values.iter().enumerate()
+    {
+        let mut ctx = new_context();
+        ctx.add_const_to_ctx(&legal_name(var), value);
+
+        let (_log, soln) = solve_with_preprocessors_and_context(system, &mut ctx, margin, limit)?;
+        for (name, val) in soln
+        {
+            columns.entry(name).or_insert_with(|| vec![f64::NAN; i]).push(val);
+        }
+
+        // Pad the columns of variables that were not solved at this point
+        for column in columns.values_mut()
+        {
+            column.resize(i + 1, f64::NAN);
+        }
+    }
+    columns.insert(var.to_owned(), values.to_vec());
+
+    Ok(columns)
+}
+
+/// A system of equations that has been preprocessed and compiled, 
+/// along with everything needed to solve it and report its solution.
+/// Created with `compile_with_preprocessors`.
+/// 
+/// Compiled equations refer to variables by their legal names, where 
+/// namespaced variables like `hx1.ua` are written as `hx1__ua`. Use 
+/// `get_written_name` to report a variable by the name it was written with.
+#[derive(Clone, Debug)]
+pub struct CompiledSystem
+{
+    pub (in crate) compiled: String,
+    pub (in crate) declared: HashMap<String, [f64; 3]>,
+    pub (in crate) namespaced: HashMap<String, String>,
+    pub (in crate) aliased: HashMap<String, String>,
+    pub (in crate) method: SolverMethod,
+    pub (in crate) margin: f64,
+    pub (in crate) limit: usize,
+    pub (in crate) guessed: Option<String>,
+    pub (in crate) functions: Vec<String>,
+}
+
+impl CompiledSystem
+{
+    /// Returns the compiled equations of the system, in the order they were written.
+    pub fn get_equations(&self) -> Vec<&str>
+    {
+        self.compiled.lines()
+            .filter(|x| x.contains('='))
+            .collect()
+    }
+
+    /// Returns the guess, minimum and maximum value (in that order) of every variable 
+    /// that was declared with `guess` or `keep`, or was given a guess automatically.
+    pub fn get_domains(&self) -> &HashMap<String, [f64; 3]>
+    {
+        &self.declared
+    }
+
+    /// Returns the solver method chosen with `settings method = ...`.
+    pub fn get_method(&self) -> SolverMethod
+    {
+        self.method
+    }
+
+    /// Returns the margin and iteration limit (in that order) that the system is solved with.
+    pub fn get_settings(&self) -> (f64, usize)
+    {
+        (self.margin, self.limit)
+    }
+
+    /// Returns the name that a variable was written with, given its legal name in the compiled 
+    /// equations. Variables that were not namespaced are written with their legal name.
+    pub fn get_written_name<'a>(&'a self, legal_name: &'a str) -> &'a str
+    {
+        match self.namespaced.get(legal_name)
+        {
+            Some(name) => name,
+            None => legal_name,
+        }
+    }
+
+    /// Starts a `SystemBuilder` with the system's equations, so that it can be solved with 
+    /// geqslib directly (e.g. with a different method or with a callback on each iteration) 
+    /// while still using nexsys's syntax and preprocessing. The builder is constrained with 
+    /// every equation that it will take, and can be built once `is_fully_constrained` says so.
+    /// 
+    /// Equations that can be solved one at a time are left in the builder, so the built 
+    /// `System` solves every unknown at once. Declared domains are not applied to the 
+    /// builder, so apply them from `get_domains` once the system is built.
+    /// 
+    /// # Example
+    /// ```
+    /// use geqslib::shunting::new_context;
+    /// use geqslib::system::SolverMethod;
+    /// use nexsys::compile_with_preprocessors;
+    /// 
+    /// let mut ctx = new_context();
+    /// let compiled = compile_with_preprocessors("keep x on [0, 10]\nx^2 + y = 11\nx + y = 5", &mut ctx, 1E-9, 100)
+    ///     .unwrap();
+    /// 
+    /// let builder = compiled.to_system_builder(&ctx).unwrap();
+    /// assert!(builder.is_fully_constrained());
+    /// 
+    /// let mut system = builder.build_system().unwrap();
+    /// for (var, [guess, min, max]) in compiled.get_domains()
+    /// {
+    ///     system.specify_variable(var, *guess, *min, *max);
+    /// }
+    /// 
+    /// let soln = system.solve_with_method(SolverMethod::Broyden, 1E-9, 100).unwrap();
+    /// assert!((soln["x"] - 3.0).abs() < 1E-6);
+    /// assert!((soln["y"] - 2.0).abs() < 1E-6);
+    /// ```
+    pub fn to_system_builder(&self, ctx: &ContextHashMap) -> anyhow::Result<SystemBuilder>
+    {
+        let equations = self.get_equations();
+        let mut builder = match equations.first()
+        {
+            Some(first) => SystemBuilder::new(first, ctx.clone())?,
+            None => return Err(EmptySystemError.into()),
+        };
+
+        builder.try_fully_constrain_with(equations[1..].to_vec())?;
+        Ok(builder)
+    }
+}
+
+/// Runs every preprocessor on the given system and compiles it, applying any
+/// `settings` directives on top of the given `margin` and `limit`. The returned 
+/// `CompiledSystem` is solved exactly as `solve_with_preprocessors_and_context` 
+/// would solve the system, or can be exported to geqslib (see `CompiledSystem::to_system_builder`).
+pub fn compile_with_preprocessors(system: &str, ctx: &mut ContextHashMap, margin: f64, limit: usize) -> anyhow::Result<CompiledSystem>
+{
+    let system = comments(system);
+
+    // Localized numbers are rewritten before anything else reads them, including other settings
+    let system = match solver_settings(&system).1.get("locale")
+    {
+        Some(tag) => localized_numbers(&system, NumberLocale::from_tag(tag)?)?,
+        None => system,
+    };
+
+    // Load csv values first so that namespacing does not mangle file names. Each line is loaded 
+    // on its own so that a loaded value that conflicts is reported at its directive's line
+    let mut loaded = vec![];
+    let mut line_numbers = vec![];
+    for (idx, line) in system.split('\n').enumerate()
+    {
+        for line in csv_constants(line)?.split('\n')
+        {
+            loaded.push(line.to_owned());
+            line_numbers.push(idx + 1);
+        }
+    }
+    let system = loaded.join("\n");
+    reserved_names(&system)?;
+    conflicts_at_lines(&namespaces(&system).0, &line_numbers)?;
+    let (system, settings) = solver_settings(&system);
+
+    let mut margin = margin;
+    let mut limit = limit;
+    let mut method = SolverMethod::NewtonRaphson;
+    let mut relaxation = 1.0;
+    let mut suffixes = false;
+    for (setting, value) in settings
+    {
+        match setting.as_str()
+        {
+            "margin" => margin = value.parse()
+                .map_err(|_| SettingsFormatError::InvalidValue)?,
+            "iterations" => limit = value.parse()
+                .map_err(|_| SettingsFormatError::InvalidValue)?,
+            "method" => method = match value.to_lowercase().as_str()
+            {
+                "newton" => SolverMethod::NewtonRaphson,
+                "broyden" => SolverMethod::Broyden,
+                "fixed_point" => SolverMethod::FixedPoint { relaxation: 1.0 },
+                _ => return Err(SettingsFormatError::UnknownMethod.into()),
+            },
+            "relaxation" => relaxation = value.parse()
+                .map_err(|_| SettingsFormatError::InvalidValue)?,
+            "suffixes" => suffixes = match value.to_lowercase().as_str()
+            {
+                "on" => true,
+                "off" => false,
+                _ => return Err(SettingsFormatError::InvalidValue.into()),
+            },
+            "locale" => (),
+            _ => return Err(SettingsFormatError::UnknownSetting.into()),
+        }
+    }
+    if let SolverMethod::FixedPoint { relaxation: r } = &mut method
+    {
+        *r = relaxation;
+    }
+
+    // Suffixed numbers are expanded before variables are renamed, while they are still as written
+    let system = match suffixes
+    {
+        true => literals(&system),
+        false => system,
+    };
+    let (system, namespaced) = namespaces(&system);
+    let (system, aliased) = aliases(&system)?;
+
+    // Piecewise functions are registered as closures while compiling, so keep their declarations as written
+    let functions = system.lines()
+        .map(str::trim)
+        .filter(|line| line.to_lowercase().starts_with("piecewise "))
+        .map(str::to_owned)
+        .collect();
+
+    let (_, explicit) = guess_values(&system);
     let mut declared = HashMap::new();
-    let compiled = compile(system, &mut ctx, &mut declared)?;
+    let compiled = compile(&system, ctx, &mut declared)?;
+    let guessed = guess_unknowns(&compiled, ctx, &mut declared, &explicit);
 
-    basic_solve(&compiled, &mut ctx, &mut declared, margin, limit)
+    Ok(CompiledSystem { compiled, declared, namespaced, aliased, method, margin, limit, guessed, functions })
+}
+
+/// Chooses an initial guess for every unknown in a compiled system that was not given one 
+/// with `guess`, since the default guess of 1.0 is often far from the solution. An unknown's
+/// guess is the midpoint of its domain if the domain is bounded on both sides, or else the 
+/// largest magnitude of any number or constant in the equations that it appears in. Guesses 
+/// are always kept within the unknown's domain.
+/// 
+/// Returns a log entry describing the chosen guesses, if any were chosen.
+fn guess_unknowns(system: &str, ctx: &ContextHashMap, declared: &mut HashMap<String, [f64; 3]>, explicit: &HashMap<String, f64>) -> Option<String>
+{
+    lazy_static!
+    {
+        static ref NUM: Regex = Regex::new(r"(?i)\b[0-9]+\.?[0-9]*(e[-+]?[0-9]+)?").unwrap();
+    }
+
+    // The largest magnitude of any number or constant in the equations that each unknown appears in.
+    // Conditionals are skipped since they contain the codes of their comparison operators.
+    let mut scales: HashMap<&str, f64> = HashMap::new();
+    for equation in system.lines().filter(|x| x.contains('=') && !x.contains("if("))
+    {
+        let numbers = NUM.find_iter(equation).filter_map(|x| x.as_str().parse::<f64>().ok());
+        let consts = get_legal_variables_iter(equation).filter_map(|x| match ctx.get(x)
+        {
+            Some(Token::Num(n)) => Some(*n),
+            _ => None,
+        });
+        let scale = numbers.chain(consts).map(f64::abs).filter(|x| x.is_finite()).fold(0.0, f64::max);
+
+        for unknown in get_equation_unknowns(equation, ctx)
+        {
+            let prev = scales.entry(unknown).or_insert(0.0);
+            *prev = prev.max(scale);
+        }
+    }
+
+    let mut unknowns: Vec<&str> = scales.keys().copied().filter(|x| !explicit.contains_key(*x)).collect();
+    unknowns.sort();
+
+    let mut chosen = vec![];
+    for unknown in unknowns
+    {
+        let var_info = declared.entry(unknown.to_owned()).or_insert([1.0, f64::NEG_INFINITY, f64::INFINITY]);
+        let [_, min, max] = *var_info;
+
+        let (guess, source) = if min.is_finite() && max.is_finite()
+        {
+            ((min + max) / 2.0, "domain midpoint")
+        }
+        else if scales[unknown] > 0.0
+        {
+            (scales[unknown], "constants")
+        }
+        else
+        {
+            continue;
+        };
+
+        var_info[0] = guess.clamp(min, max);
+        chosen.push(format!("{unknown} = {} ({source})", var_info[0]));
+    }
+
+    match chosen.is_empty()
+    {
+        true => None,
+        false => Some(format!("Initial guesses: {}", chosen.join(", "))),
+    }
+}
+
+/// Solves a system produced by `compile_with_preprocessors`, reporting 
+/// variables by the names they were written with.
+pub (in crate) fn solve_compiled_system(system: CompiledSystem, ctx: &mut ContextHashMap) -> anyhow::Result<(Vec<String>, HashMap<String, f64>)>
+{
+    let CompiledSystem { compiled, mut declared, namespaced, aliased, method, margin, limit, guessed, .. } = system;
+
+    let (mut log, mut soln) = solve_with_method(&compiled, ctx, &mut declared, method, margin, limit)?;
+    if let Some(guessed) = guessed
+    {
+        log.insert(0, guessed);
+    }
+
+    // Report aliased and namespaced variables by the names they were written with
+    for (alias, target) in aliased
+    {
+        if let Some(&val) = soln.get(&target)
+        {
+            soln.insert(alias, val);
+        }
+    }
+    for (legal_name, name) in namespaced
+    {
+        if let Some(val) = soln.remove(&legal_name)
+        {
+            soln.insert(name, val);
+        }
+    }
+
+    Ok((log, soln))
+}
+
+/// Registers a named Rust closure in the given context so that it can be 
+/// called from equation text like any built-in function. This allows models
+/// that cannot be written as closed-form expressions (e.g. proprietary or 
+/// tabulated component models) to participate in a system of equations.
+/// 
+/// The closure receives its `num_args` arguments in the order they are 
+/// written in the equation.
+/// 
+/// # Example
+/// ```
+/// use geqslib::shunting::new_context;
+/// use nexsys::{register_fn, solve_with_preprocessors_and_context};
+/// 
+/// let mut ctx = new_context();
+/// register_fn(&mut ctx, "pump_head", 1, |args| 50.0 - 0.5 * args[0].powi(2));
+/// 
+/// let system = r#"
+/// keep q on [0, 100]
+/// h = pump_head(q)
+/// h = 2 * q^2
+/// "#;
+/// 
+/// let (_log, soln) = solve_with_preprocessors_and_context(system, &mut ctx, 0.0001, 100)
+///     .expect("failed to solve system!");
+/// 
+/// assert!((soln["q"] - 20.0_f64.sqrt()).abs() < 0.001);
+/// assert!((soln["h"] - 40.0).abs() < 0.001);
+/// ```
+pub fn register_fn<F>(ctx: &mut ContextHashMap, name: &str, num_args: usize, func: F)
+where
+    F: Fn(&[f64]) -> f64 + 'static
+{
+    ctx.add_closure_to_ctx(name, func, num_args);
 }
\ No newline at end of file
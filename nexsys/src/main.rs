@@ -1,6 +1,33 @@
 use std::{env, process};
+use std::collections::HashMap;
 use std::fs::{read_to_string, write};
-use nexsys::{solve_with_preprocessors, parsing::{conditionals, conversions, consts}};
+use nexsys::{cache::solve_with_cache, solve_with_preprocessors, parsing::{conditionals, conversions, consts}};
+use nexsys::comparison::{compare_solutions, read_solution};
+
+/// Reads a solution for `nxc compare`, either from the output of `--output-file` 
+/// or by solving a system file.
+fn load_solution(path: &str) -> HashMap<String, f64> {
+    let text = match read_to_string(path) {
+        Ok(o) => o,
+        Err(_) => {
+            println!("[nxc].....ERR: could not find {path}");
+            process::exit(1);
+        }
+    };
+
+    if path.ends_with(".txt") {
+        return read_solution(&text);
+    }
+
+    match solve_with_preprocessors(&text, 0.0001, 100) {
+        Ok((_, soln)) => soln,
+        Err(e) => {
+            println!("[nxc].....ERR: nxc could not solve {path}");
+            println!("[nxc].....{e}");
+            process::exit(1);
+        }
+    }
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -17,11 +44,52 @@ OPTIONS:
 --max-iterations, -max <int>           The maximum number of iterations that the solver can take to converge
 --output-file, -o                      Sends the results to a .txt file rather than printing them in the terminal
 --verbose -v                           Prints compiled nexsys code in the terminal for debugging
+--cache <dir>                          Reuses solutions stored in <dir> when the system has not changed
+--locale <tag>                         Reads numbers the way the given locale writes them (e.g. de for 3,14)
+
+COMPARING SOLUTIONS:
+nxc compare <BASELINE> <FILEPATH> [--tol <float>]
+
+Solves both files (or reads them, if they are .txt files written with --output-file) and 
+lists every variable that differs by more than the tolerance (1e-6 by default). Exits with 
+an error code if any variable differs.
 "#);
         process::exit(0);
     }
 
-    let system = match read_to_string(&args[1]) {
+    #[cfg(feature = "server")]
+    if args[1] == *"serve" && args.len() > 2 {
+        println!("[nxc].....serving JSON-RPC requests on {}", args[2]);
+        if let Err(e) = nexsys::server::serve(&args[2], nexsys::session::default_session_dir()) {
+            println!("[nxc].....ERR: {e}");
+            process::exit(1);
+        }
+        process::exit(0);
+    }
+
+    if args[1] == *"compare" {
+        if args.len() < 4 {
+            println!("[nxc].....ERR: compare needs a baseline and a file to compare against it");
+            process::exit(1);
+        }
+
+        let mut tol = 1E-6;
+        if let Some(i) = args.iter().position(|x| x == "--tol" || x == "-tol") {
+            match args.get(i+1).map(|x| x.parse::<f64>()) {
+                Some(Ok(o)) => tol = o,
+                _ => {
+                    println!("[nxc].....ERR: tolerance is not a valid float value");
+                    process::exit(1);
+                }
+            }
+        }
+
+        let comparison = compare_solutions(&load_solution(&args[2]), &load_solution(&args[3]), tol);
+        println!("{comparison}");
+        process::exit(if comparison.passed() { 0 } else { 1 });
+    }
+
+    let mut system = match read_to_string(&args[1]) {
         Ok(o) => o,
         Err(_) => {
             println!("[nxc].....ERR: could not find the specified filepath");
@@ -32,6 +100,7 @@ OPTIONS:
     let mut margin = 0.0001;
     let mut limit = 100; 
     let mut output_file = false; // todo: make this produce different file types
+    let mut cache_dir: Option<String> = None;
 
     for i in 0..args.len() {
         if args[i] == *"--margin" || args[i] == *"-tol" {
@@ -67,13 +136,43 @@ OPTIONS:
             
             println!("\n{preprocess}\n");
         }
+        if args[i] == *"--cache" {
+            match args.get(i+1) {
+                Some(dir) => {
+                    println!("[nxc].....caching solutions in {dir}");
+                    cache_dir = Some(dir.to_owned());
+                },
+                None => {
+                    println!("[nxc].....ERR: no cache directory was given");
+                    process::exit(1);
+                }
+            }
+        }
+        if args[i] == *"--locale" {
+            match args.get(i+1) {
+                Some(tag) => {
+                    println!("[nxc].....reading numbers in the {tag} locale");
+                    // Added at the end so that line numbers in errors still match the file
+                    system = format!("{system}\nsettings locale = {tag}");
+                },
+                None => {
+                    println!("[nxc].....ERR: no locale was given");
+                    process::exit(1);
+                }
+            }
+        }
         if args[i] == *"--to-file" || args[i] == *"-o" {
             println!("[nxc].....Writing to file...");
             output_file = true;
         }
     }
 
-    let (log, soln) = match solve_with_preprocessors(&system, margin, limit) {
+    let res = match &cache_dir {
+        Some(dir) => solve_with_cache(&system, margin, limit, dir),
+        None => solve_with_preprocessors(&system, margin, limit),
+    };
+
+    let (log, soln) = match res {
         Ok(o) => o,
         Err(e) => {
             println!("[nxc].....ERR: nxc could not solve the system");
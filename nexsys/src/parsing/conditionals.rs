@@ -2,6 +2,9 @@ use crate::{errors::ConditionFormatError, parsing::nexsys_regex};
 use lazy_static::lazy_static;
 use regex::Regex;
 
+/// The sharpness of a `smooth if` statement that does not give one, in inverse units of the compared quantities.
+pub const DEFAULT_SHARPNESS: f64 = 10.0;
+
 /// Evaluates if the first expression contains any of the later expressions
 macro_rules! contains_any {
     ($s:expr, $ch1:expr, $( $ch:tt ),* ) => {{
@@ -57,10 +60,25 @@ pub (in crate) fn format_conditional(cndl: &str) -> anyhow::Result<String> {
 }
 
 /// Identifies and returns conditional statements found in a Nexsys-legal string.
+///
+/// Statements that start with `smooth if` (or `smooth(<sharpness>) if`) blend their two
+/// branches with a sigmoid of the difference between the compared quantities rather than 
+/// switching between them, which keeps the equation differentiable so that the Newton solver
+/// is not thrown off when a solved variable crosses the switch. Larger sharpnesses switch more 
+/// abruptly, and the default is `DEFAULT_SHARPNESS`. Only `<`, `>`, `<=` and `>=` can be smoothed.
+///
+/// # Example
+/// ```
+/// use nexsys::parsing::conditionals;
+///
+/// let smoothed = conditionals("smooth(50) if x < 2:\n    y = 1\nelse:\n    y = 2\nend").unwrap();
+/// assert_eq!(smoothed, "smooth_if(x,4.0,2,y-(1),y-(2),50) = 0");
+/// ```
 pub fn conditionals(text: &str) -> anyhow::Result<String> {
     lazy_static!{
+        static ref SMOOTH: Regex = Regex::new(r"^[ \t]*smooth(\(([^()]*)\))? if ").unwrap();
         static ref RE: Regex = nexsys_regex(            
-r#"(?m)^[ \t]*if [^<>=]+[<>=]{1,2}[^<>=]+:$
+r#"(?m)^[ \t]*(smooth(\([^()\n]*\))? )?if [^<>=]+[<>=]{1,2}[^<>=]+:$
 ^.*$
 ^[ \t]*else:$
 ^.*$
@@ -83,6 +101,22 @@ r#"(?m)^[ \t]*if [^<>=]+[<>=]{1,2}[^<>=]+:$
                 .collect::<Vec<String>>();
     
             // println!("{rows:#?}");
+
+            // Smoothed statements are formatted as regular ones and then given their sharpness
+            let sharpness = match SMOOTH.captures(&rows[0]) {
+                Some(caps) => {
+                    let sharpness = match caps.get(2) {
+                        Some(x) => x.as_str().replace(' ', ""),
+                        None => DEFAULT_SHARPNESS.to_string(),
+                    };
+                    if rows[0].contains("==") || rows[0].contains("!=") {
+                        return Err(ConditionFormatError::SmoothEquality.into())
+                    }
+                    rows[0] = rows[0].replacen(&caps[0], "if ", 1);
+                    Some(sharpness)
+                },
+                None => None,
+            };
     
             for r in [1,3] {
                 if rows[r].contains('=') {
@@ -99,9 +133,13 @@ r#"(?m)^[ \t]*if [^<>=]+[<>=]{1,2}[^<>=]+:$
     
             // println!("{}", fmt_eqns);
     
-            let fmtd = &format_conditional(&fmt_eqns)?;
+            let mut fmtd = format_conditional(&fmt_eqns)?;
+            if let Some(sharpness) = sharpness {
+                let args = fmtd.trim_start_matches("if(").trim_end_matches(") = 0");
+                fmtd = format!("smooth_if({args},{sharpness}) = 0");
+            }
     
-            output = output.replace(raw, fmtd);
+            output = output.replace(raw, &fmtd);
         }
 
         if cdls.is_empty() { break } // keep going until there are no if statement matches left
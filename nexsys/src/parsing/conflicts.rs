@@ -0,0 +1,149 @@
+use geqslib::shunting::{eval_str_with_context, new_context, ContextHashMap, ContextLike};
+use geqslib::system::get_equation_unknowns;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::{errors::ConflictError, parsing::{consts, conversions, nexsys_regex}};
+
+/// The number of points at which two equations are compared when checking if one is a rescaled copy of the other.
+const SAMPLE_POINTS: usize = 3;
+
+/// Returns the value given to the `var_idx`th variable at the `point`th sample point.
+/// These are deterministic, positive, and unlikely to coincide with anything meaningful.
+fn sample_value(var_idx: usize, point: usize) -> f64 {
+    let x = (var_idx + 1) as f64 * 0.618_033_988_7 + (point + 1) as f64 * 0.414_213_562_3;
+    0.5 + 2.0 * x.fract()
+}
+
+/// An equation found in a Nexsys-legal string, along with what is needed to compare it to others.
+struct Equation {
+    line: usize,
+    text: String,
+    unknowns: Vec<String>,
+    residuals: Option<Vec<f64>>,
+}
+impl Equation {
+    fn new(line: usize, equation: &str, ctx: &ContextHashMap) -> Equation {
+        // Whitespace never changes the meaning of an equation
+        let text: String = equation.chars().filter(|c| !c.is_whitespace()).collect();
+
+        let mut unknowns: Vec<String> = get_equation_unknowns(&text, ctx)
+            .map(|x| x.to_owned())
+            .collect();
+        unknowns.sort();
+
+        let residuals = Equation::residuals(&text, &unknowns);
+
+        Equation { line, text, unknowns, residuals }
+    }
+
+    /// Evaluates `lhs - rhs` at each sample point, or returns `None` if
+    /// the equation cannot be evaluated (e.g. it calls a registered function).
+    fn residuals(text: &str, unknowns: &[String]) -> Option<Vec<f64>> {
+        let expanded = conversions(&consts(text).ok()?).ok()?;
+        let (lhs, rhs) = expanded.split_once('=')?;
+        let residual = format!("({lhs}) - ({rhs})");
+
+        let mut residuals = vec![];
+        for point in 0..SAMPLE_POINTS {
+            let mut ctx = new_context();
+            for (i, var) in unknowns.iter().enumerate() {
+                ctx.add_const_to_ctx(var, sample_value(i, point));
+            }
+
+            match eval_str_with_context(&residual, &ctx) {
+                Ok(r) if r.is_finite() && r != 0.0 => residuals.push(r),
+                _ => return None,
+            }
+        }
+        Some(residuals)
+    }
+
+    /// Checks if the two equations are the same, or one is a rescaled copy of the other.
+    fn duplicates(&self, other: &Equation) -> bool {
+        if self.text == other.text {
+            return true
+        }
+
+        if self.unknowns != other.unknowns {
+            return false
+        }
+
+        match (&self.residuals, &other.residuals) {
+            (Some(a), Some(b)) => {
+                let ratio = a[0] / b[0];
+                a.iter().zip(b).all(|(x, y)| (x / y - ratio).abs() <= 1E-9 * ratio.abs())
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Identifies equations that appear more than once in a Nexsys-legal string (including
+/// copies that are rearranged or multiplied by a constant) and constants that are
+/// defined more than once with different values. Either would leave the system
+/// over-constrained, so an `Err` naming the (1-based) conflicting lines is returned.
+///
+/// Equations inside conditional statements are not checked, since the
+/// same equation may legitimately appear in more than one branch.
+pub fn conflicts(text: &str) -> anyhow::Result<()> {
+    let line_numbers: Vec<usize> = (1..=text.lines().count()).collect();
+    conflicts_at_lines(text, &line_numbers)
+}
+
+/// Works like `conflicts`, but reports the `n`th line of `text` as line `line_numbers[n]`,
+/// for text that was expanded from a system by a preprocessor (e.g. `csv_constants`).
+pub fn conflicts_at_lines(text: &str, line_numbers: &[usize]) -> anyhow::Result<()> {
+    lazy_static! {
+        static ref CONST: Regex = nexsys_regex(r"(?i)^const +(@V) *= *(@N)$");
+        static ref DIRECTIVE: Regex = Regex::new(r"(?i)^(alias|settings|keep|guess|unit|load|piecewise) ").unwrap();
+    }
+    let ctx = new_context();
+
+    let mut constants: HashMap<String, (usize, f64)> = HashMap::new();
+    let mut equations: Vec<Equation> = vec![];
+    let mut depth = 0;
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_num = line_numbers.get(idx).copied().unwrap_or(idx + 1);
+        let line = line.trim();
+
+        if (line.starts_with("if ") || line.starts_with("smooth")) && line.ends_with(':') {
+            depth += 1;
+            continue;
+        }
+        if line == "end" {
+            depth -= 1;
+            continue;
+        }
+        if depth > 0 || !line.contains('=') || DIRECTIVE.is_match(line) {
+            continue;
+        }
+
+        if let Some(c) = CONST.captures(line) {
+            let name = c.get(1).unwrap().as_str();
+            let value: f64 = match c.get(2).unwrap().as_str().replace(' ', "").parse() {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            match constants.get(name) {
+                Some(&(first, prev)) if prev != value => {
+                    return Err(ConflictError::ConstRedefined(first, line_num).into())
+                },
+                Some(_) => {},
+                None => { constants.insert(name.to_owned(), (line_num, value)); },
+            }
+            continue;
+        }
+
+        let equation = Equation::new(line_num, line, &ctx);
+        if let Some(first) = equations.iter().find(|x| x.duplicates(&equation)) {
+            return Err(ConflictError::DuplicateEquation(first.line, line_num).into())
+        }
+        equations.push(equation);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use crate::errors::LocaleFormatError;
+
+/// The characters that a locale writes numbers with, e.g. `1.234,5` in german.
+///
+/// # Fields:
+/// - `decimal` - the character that separates the whole and fractional parts of a number
+/// - `grouping` - the characters that may separate groups of three digits in the whole part
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NumberLocale {
+    pub decimal: char,
+    pub grouping: &'static [char],
+}
+impl NumberLocale {
+    /// `1234.5`, as nexsys reads numbers by default. Commas separate function
+    /// arguments, so digits are not grouped.
+    pub const POINT: NumberLocale = NumberLocale { decimal: '.', grouping: &[] };
+
+    /// `1.234,5`, e.g. in german, spanish, italian or dutch.
+    pub const COMMA_DOT_GROUPED: NumberLocale = NumberLocale { decimal: ',', grouping: &['.'] };
+
+    /// `1 234,5`, e.g. in french, russian, polish or swedish. Groups may be separated
+    /// by a space or by the no-break spaces that spreadsheets tend to copy.
+    pub const COMMA_SPACE_GROUPED: NumberLocale = NumberLocale { decimal: ',', grouping: &[' ', '\u{a0}', '\u{202f}'] };
+
+    /// `1'234.5`, e.g. in switzerland.
+    pub const POINT_APOSTROPHE_GROUPED: NumberLocale = NumberLocale { decimal: '.', grouping: &['\'', '\u{2019}'] };
+
+    /// Looks up how numbers are written in a locale, given a language tag like `de`, `fr-FR`
+    /// or `de_CH`. A region is only used where it changes how numbers are written.
+    pub fn from_tag(tag: &str) -> anyhow::Result<NumberLocale> {
+        let tag = tag.to_lowercase().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+
+        match (tag.as_str(), language) {
+            ("de-ch" | "fr-ch" | "it-ch" | "de-li", _) | (_, "rm") => Ok(NumberLocale::POINT_APOSTROPHE_GROUPED),
+            ("es-mx" | "es-us" | "pt-mo", _) => Ok(NumberLocale::POINT),
+            (_, "en" | "ja" | "ko" | "zh" | "hi" | "he" | "th") => Ok(NumberLocale::POINT),
+            (_, "de" | "es" | "it" | "nl" | "pt" | "da" | "el" | "id" | "ro" | "tr" | "hr" | "sl" | "sr") => Ok(NumberLocale::COMMA_DOT_GROUPED),
+            (_, "fr" | "ru" | "pl" | "sv" | "nb" | "no" | "fi" | "cs" | "sk" | "uk" | "hu" | "bg" | "et" | "lt" | "lv") => Ok(NumberLocale::COMMA_SPACE_GROUPED),
+            _ => Err(LocaleFormatError::UnknownLocale(tag).into()),
+        }
+    }
+
+    /// Whether a character may appear between the digits of a number, including a
+    /// decimal point that this locale does not use (so that it can be reported).
+    fn is_separator(&self, c: char) -> bool {
+        c == '.' || c == self.decimal || self.grouping.contains(&c)
+    }
+
+    /// Rewrites a number written in this locale in plain decimal notation,
+    /// or returns `None` if it is not written the way this locale writes numbers.
+    fn delocalize(&self, number: &str) -> Option<String> {
+        let (whole, frac) = match number.split_once(self.decimal) {
+            Some((whole, frac)) => (whole, Some(frac)),
+            None => (number, None),
+        };
+
+        if frac.is_some_and(|x| !x.chars().all(|c| c.is_ascii_digit())) {
+            return None
+        }
+
+        // Groups must all use the same separator, with every group after the first three digits long
+        let separator = whole.chars().find(|c| !c.is_ascii_digit());
+        let digits: String = match separator {
+            None => whole.to_owned(),
+            Some(sep) if self.grouping.contains(&sep) => {
+                let groups: Vec<&str> = whole.split(sep).collect();
+                let first_is_valid = (1..=3).contains(&groups[0].len());
+                let rest_are_valid = groups[1..].iter().all(|g| g.len() == 3 && g.chars().all(|c| c.is_ascii_digit()));
+                if !(first_is_valid && groups[0].chars().all(|c| c.is_ascii_digit()) && rest_are_valid) {
+                    return None
+                }
+                groups.concat()
+            },
+            Some(_) => return None,
+        };
+
+        match frac {
+            Some(frac) => Some(format!("{digits}.{frac}")),
+            None => Some(digits),
+        }
+    }
+}
+
+/// Rewrites every number in a Nexsys-legal string that is written in the given locale
+/// (e.g. `3,14` or `1.234,5` in german) in plain decimal notation, so that values pasted
+/// from a spreadsheet or document in that locale can be used as they were written.
+///
+/// A separator is only part of a number when it is directly between two digits, so in a
+/// locale that writes decimal commas, function arguments must be separated by a comma and a
+/// space (e.g. `max(1,5, 2)` is the larger of `1.5` and `2`). Numbers inside of quotes (e.g.
+/// file names) and digits in variable names are left as they are.
+///
+/// Numbers that do not match the locale, e.g. `1.5` when the locale writes `1,5` or digits
+/// grouped in the wrong places, are reported as an error rather than read as a different value.
+///
+/// This is applied to systems that set their locale with `settings locale = <tag>`
+/// (see `NumberLocale::from_tag`), and may be applied to any text before it is solved.
+///
+/// # Example
+/// ```
+/// use nexsys::parsing::{localized_numbers, NumberLocale};
+///
+/// let de = NumberLocale::from_tag("de-DE").unwrap();
+/// assert_eq!(localized_numbers("x = 1.234,5 * max(y, 3,14)", de).unwrap(), "x = 1234.5 * max(y, 3.14)");
+/// assert!(localized_numbers("x = 3.14", de).is_err());
+///
+/// let fr = NumberLocale::from_tag("fr").unwrap();
+/// assert_eq!(localized_numbers("x = 1 234,5", fr).unwrap(), "x = 1234.5");
+/// ```
+pub fn localized_numbers(text: &str, locale: NumberLocale) -> anyhow::Result<String> {
+    if locale == NumberLocale::POINT {
+        return Ok(text.to_owned())
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut quoted = false;
+    let mut line = 1;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let follows_name = i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_' || chars[i - 1] == '.');
+
+        if quoted || !c.is_ascii_digit() || follows_name {
+            match c {
+                '"' => quoted = !quoted,
+                '\n' => line += 1,
+                _ => (),
+            }
+            output.push(c);
+            i += 1;
+            continue;
+        }
+
+        // Take digits, and any separators that sit between two digits
+        let mut end = i + 1;
+        while end < chars.len() {
+            let is_joined = locale.is_separator(chars[end]) && chars.get(end + 1).is_some_and(char::is_ascii_digit);
+            match chars[end].is_ascii_digit() || is_joined {
+                true => end += 1,
+                false => break,
+            }
+        }
+
+        let number: String = chars[i..end].iter().collect();
+        match locale.delocalize(&number) {
+            Some(plain) => output.push_str(&plain),
+            None => return Err(LocaleFormatError::UnreadableNumber(line, number).into()),
+        }
+        i = end;
+    }
+
+    Ok(output)
+}
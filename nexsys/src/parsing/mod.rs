@@ -1,17 +1,29 @@
 mod conditionals;
+mod conflicts;
+mod locale;
+mod piecewise;
 // mod duplicate; TODO: need to polish this up.
 
 use geqslib::shunting::{eval_str, ContextHashMap, ContextLike};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
-use crate::{units::{convert, const_data}, errors::ConstFormatError};
+use std::fs::read_to_string;
+use crate::{units::{convert, const_data, lower_bound}, errors::{AliasFormatError, ConstFormatError, CsvFormatError, ReservedNameError}};
 
 pub use conditionals::*;
+pub use conflicts::*;
+pub use locale::*;
+pub use piecewise::*;
 
 const LEGAL_VAR_PATTERN: &str = r"[a-z][a-z0-9_]*";
 const LEGAL_NUM_PATTERN: &str = r"-? ?[0-9]+\.?[0-9]*";
 
+lazy_static!
+{
+    static ref LOAD_CONSTANTS: Regex = Regex::new(r#"(?i)load +constants +from +"([^"]+)"( +row +([0-9]+))?"#).unwrap();
+}
+
 /// Replaces `"@N"` and `"@V"` literals with the nexsys-legal number and variable patterns, respectively.
 fn nexsys_regex(pattern: &str) -> Regex
 {
@@ -67,6 +79,306 @@ pub fn domains(text: &str) -> (String, HashMap<String, [f64; 2]>)
     res
 }
 
+/// Identifies and returns the units of variables found in a Nexsys-legal string,
+/// declared as `unit <unit> for <variable>`. Units are returned as they were written.
+pub fn units(text: &str) -> (String, HashMap<String, String>)
+{
+    lazy_static!
+    {
+        static ref RE: Regex = nexsys_regex(r"\b(?i:unit) +([^\s]+) +(?i:for) +(?i:(@V))");
+    }
+    let mut res = (text.to_owned(), HashMap::new());
+    let units = RE.captures_iter(text);
+
+    for u in units
+    {
+        res.0 = res.0.replace(u.get(0).unwrap().as_str(), "");
+        res.1.insert(
+            u.get(2).unwrap().as_str().to_owned(),
+            u.get(1).unwrap().as_str().to_owned()
+        );
+    }
+    res
+}
+
+/// Identifies and returns solver settings found in a Nexsys-legal string.
+/// Settings are given as `settings <name> = <value>` at the start of a line, and 
+/// values are returned as they were written so that the caller can interpret them.
+pub fn solver_settings(text: &str) -> (String, HashMap<String, String>)
+{
+    lazy_static!
+    {
+        static ref RE: Regex = nexsys_regex(r"(?im)^[ \t]*settings +(@V) *= *([a-z0-9_.+-]+)");
+    }
+    let mut res = (String::with_capacity(text.len()), HashMap::new());
+    let mut last = 0;
+
+    // Only remove each directive itself, since the same text may appear elsewhere
+    for s in RE.captures_iter(text)
+    {
+        let directive = s.get(0).unwrap();
+        res.0.push_str(&text[last..directive.start()]);
+        last = directive.end();
+
+        res.1.insert(
+            s.get(1).unwrap().as_str().to_lowercase(),
+            s.get(2).unwrap().as_str().to_owned()
+        );
+    }
+    res.0.push_str(&text[last..]);
+    res
+}
+
+/// Replaces numbers written with an engineering suffix (`T`, `G`, `M`, `k`, `m`, `u`, `µ`, 
+/// `n`, `p` or `f`) or a percent sign in a Nexsys-legal string with the same number in 
+/// plain decimal notation, e.g. `3.3k` becomes `3300` and `5%` becomes `0.05`. Suffixes are 
+/// case-sensitive, so `2M` is two million while `2m` is two thousandths.
+/// 
+/// Since a suffix is otherwise read as an implicitly multiplied variable (e.g. `2m` as 
+/// `2 * m`), this is only applied to systems that enable it with `settings suffixes = on`.
+pub fn literals(text: &str) -> String
+{
+    lazy_static!
+    {
+        static ref RE: Regex = Regex::new(r"(^|[^A-Za-z0-9_.])([0-9]+\.?[0-9]*|\.[0-9]+)(T|G|M|k|m|u|µ|n|p|f|%)($|[^A-Za-z0-9_])").unwrap();
+    }
+    let mut output = text.to_owned();
+
+    // Neighbouring literals share the character between them, so replace until none are left
+    while RE.is_match(&output)
+    {
+        output = RE.replace_all(&output, |c: &regex::Captures| {
+            let exponent = match &c[3]
+            {
+                "T" => 12,
+                "G" => 9,
+                "M" => 6,
+                "k" => 3,
+                "m" => -3,
+                "u" | "µ" => -6,
+                "n" => -9,
+                "p" => -12,
+                "f" => -15,
+                _ => -2,
+            };
+            format!("{}{}{}", &c[1], shift_decimal(&c[2], exponent), &c[4])
+        }).into_owned();
+    }
+
+    output
+}
+
+/// Moves the decimal point of a number written as digits `exponent` places to the right 
+/// (or left, if negative), so that no precision is lost to floating point arithmetic.
+fn shift_decimal(number: &str, exponent: i32) -> String
+{
+    let (whole, frac) = number.split_once('.').unwrap_or((number, ""));
+    let digits = format!("{whole}{frac}");
+    let point = whole.len() as i32 + exponent;
+
+    let shifted = if point <= 0
+    {
+        format!("0.{}{}", "0".repeat(-point as usize), digits)
+    }
+    else if point as usize >= digits.len()
+    {
+        format!("{}{}", digits, "0".repeat(point as usize - digits.len()))
+    }
+    else
+    {
+        format!("{}.{}", &digits[..point as usize], &digits[point as usize..])
+    };
+
+    match shifted.trim_start_matches('0')
+    {
+        "" => "0".to_owned(),
+        s if s.starts_with('.') => format!("0{s}"),
+        s => s.to_owned(),
+    }
+}
+
+/// Splits a line of a csv file into its trimmed and unquoted fields.
+fn csv_fields(line: &str) -> Vec<&str>
+{
+    line.split(',')
+        .map(|x| x.trim().trim_matches('"').trim())
+        .collect()
+}
+
+/// Identifies `load constants from "<file>.csv"` directives in a Nexsys-legal string
+/// and replaces each with `const` declarations binding the csv file's column names 
+/// to the values in its first row of data. A specific row of data can be chosen by 
+/// adding `row <n>` to the directive, where the first row after the header is row 1.
+/// 
+/// Relative file paths are resolved from the current working directory. Column names 
+/// may be namespaced (e.g. `pump.flow`) but must otherwise be legal variable names.
+/// Systems from untrusted sources should be checked with `csv_files` first, since
+/// any file that the process can read may be named.
+pub fn csv_constants(text: &str) -> anyhow::Result<String>
+{
+    lazy_static!
+    {
+        static ref NAME: Regex = nexsys_regex(r"(?i)^@V(\.@V)*$");
+    }
+    let mut output = text.to_owned();
+
+    for c in LOAD_CONSTANTS.captures_iter(text)
+    {
+        let contents = read_to_string(c.get(1).unwrap().as_str())
+            .map_err(|_| CsvFormatError::UnreadableFile)?;
+
+        let row: usize = match c.get(3)
+        {
+            Some(n) => n.as_str().parse().map_err(|_| CsvFormatError::MissingRow)?,
+            None => 1,
+        };
+
+        // Skip blank lines and a leading byte order mark (e.g. from spreadsheet exports)
+        let mut lines = contents.trim_start_matches('\u{feff}')
+            .lines()
+            .filter(|x| !x.trim().is_empty());
+
+        let header = csv_fields(lines.next().ok_or(CsvFormatError::MissingRow)?);
+        let values = match row
+        {
+            0 => return Err(CsvFormatError::MissingRow.into()),
+            n => csv_fields(lines.nth(n - 1).ok_or(CsvFormatError::MissingRow)?),
+        };
+
+        if header.len() != values.len()
+        {
+            return Err(CsvFormatError::ColumnCountMismatch.into());
+        }
+
+        let mut declarations = vec![];
+        for (name, value) in header.iter().zip(values)
+        {
+            if !NAME.is_match(name)
+            {
+                return Err(CsvFormatError::InvalidColumnName.into());
+            }
+
+            let value: f64 = value.parse().map_err(|_| CsvFormatError::InvalidValue)?;
+            declarations.push(format!("const {name} = {value}"));
+        }
+
+        output = output.replace(c.get(0).unwrap().as_str(), &declarations.join("\n"));
+    }
+
+    Ok(output)
+}
+
+/// Returns the files named in the `load constants from` directives of a Nexsys-legal
+/// string, which `csv_constants` would read.
+/// 
+/// # Example
+/// ```
+/// use nexsys::parsing::csv_files;
+/// 
+/// let files = csv_files("load constants from \"pump.csv\" row 2\nx = pump.flow");
+/// assert_eq!(files, vec!["pump.csv"]);
+/// ```
+pub fn csv_files(text: &str) -> Vec<String>
+{
+    LOAD_CONSTANTS.captures_iter(text)
+        .map(|c| c[1].to_owned())
+        .collect()
+}
+
+/// Returns the solver-legal name of a dotted (namespaced) variable name, e.g. 
+/// `hx1__ua` for `hx1.ua`. Names without a namespace are returned unchanged.
+pub fn legal_name(name: &str) -> String
+{
+    name.replace('.', "__")
+}
+
+/// Returns an `Err` if any variable in a Nexsys-legal string is written with `__`, 
+/// which `legal_name` reserves for namespaced names. Otherwise, a variable like 
+/// `hx1__ua` would silently be the same variable as `hx1.ua`.
+pub fn reserved_names(text: &str) -> anyhow::Result<()>
+{
+    lazy_static!
+    {
+        static ref RE: Regex = nexsys_regex(r"(?i)\b@V(\.@V)*");
+    }
+
+    match RE.find_iter(text).any(|n| n.as_str().contains("__"))
+    {
+        true => Err(ReservedNameError.into()),
+        false => Ok(()),
+    }
+}
+
+/// Identifies dotted (namespaced) variable names like `hx1.ua` in a Nexsys-legal 
+/// string and replaces them with solver-legal names (see `legal_name`). The returned 
+/// `HashMap` maps each replacement name back to the original dotted name.
+pub fn namespaces(text: &str) -> (String, HashMap<String, String>)
+{
+    lazy_static!
+    {
+        static ref RE: Regex = nexsys_regex(r"(?i)\b@V(\.@V)+");
+    }
+    let mut res = (text.to_owned(), HashMap::new());
+
+    for n in RE.find_iter(text).map(|i| i.as_str())
+    {
+        let legal_name = legal_name(n);
+        res.0 = res.0.replace(n, &legal_name);
+        res.1.insert(legal_name, n.to_owned());
+    }
+    res
+}
+
+/// Identifies and returns aliases found in a Nexsys-legal string, replacing 
+/// every use of an alias with the variable that it refers to. The returned
+/// `HashMap` maps each alias to the variable that it refers to.
+pub fn aliases(text: &str) -> anyhow::Result<(String, HashMap<String, String>)>
+{
+    lazy_static!
+    {
+        static ref RE: Regex = nexsys_regex(r"(?i)alias +(@V) *= *(@V)");
+    }
+    let mut output = text.to_owned();
+    let mut found: HashMap<String, String> = HashMap::new();
+
+    for a in RE.captures_iter(text)
+    {
+        output = output.replace(a.get(0).unwrap().as_str(), "");
+        found.insert(
+            a.get(1).unwrap().as_str().to_owned(),
+            a.get(2).unwrap().as_str().to_owned()
+        );
+    }
+
+    // Follow chains of aliases to the variable they eventually refer to
+    let mut resolved = HashMap::new();
+    for alias in found.keys()
+    {
+        let mut target = &found[alias];
+        for _ in 0..found.len()
+        {
+            match found.get(target)
+            {
+                Some(next) => target = next,
+                None => break,
+            }
+        }
+        if found.contains_key(target)
+        {
+            return Err(AliasFormatError.into());
+        }
+        resolved.insert(alias.to_owned(), target.to_owned());
+    }
+
+    for (alias, target) in &resolved
+    {
+        let re = Regex::new(&format!(r"\b{}\b", regex::escape(alias))).unwrap();
+        output = re.replace_all(&output, target.as_str()).into_owned();
+    }
+
+    Ok((output, resolved))
+}
+
 /// Identifies and removes comments found in a Nexsys-legal string.
 pub fn comments(text: &str) -> String 
 {
@@ -161,12 +473,16 @@ pub fn const_values(text: &str) -> anyhow::Result<(String, HashMap<String, f64>)
 /// This also mutates the given `ctx` and `declared` arguments, adding any found constant or 
 pub fn compile(code: &str, ctx: &mut ContextHashMap, declared: &mut HashMap<String, [f64; 3]>) -> anyhow::Result<String> 
 {    
+    let sys_units:   HashMap<String, String>;
     let sys_domains: HashMap<String, [f64; 2]>;
     let sys_guesses: HashMap<String, f64>;
     let sys_consts:  HashMap<String, f64>;
     
     let mut nil = comments(code); 
 
+    // Bind any values loaded from csv files as constants
+    nil = csv_constants(&nil)?;
+
     // Copy-paste all common engineering constants (this happens first so users can rename constants)
     nil = consts(&nil)?;
 
@@ -180,6 +496,26 @@ pub fn compile(code: &str, ctx: &mut ContextHashMap, declared: &mut HashMap<Stri
         ctx.add_const_to_ctx(&var, val);
     }
 
+    // Register any piecewise functions (this happens after constants are set so they can be used in function bodies)
+    nil = piecewise(&nil, ctx)?;
+
+    // Infer domains from the units of variables (this happens before domains are set so they can be overridden)
+    (nil, sys_units) = units(&nil);
+    for (var, unit) in sys_units
+    {
+        if let Some(min) = lower_bound(&unit)?
+        {
+            if let Some(var_info) = declared.get_mut(&var)
+            {
+                var_info[1] = min;
+            }
+            else 
+            {
+                declared.insert(var, [1.0, min, f64::INFINITY]);
+            }
+        }
+    }
+
     // Set all domains for variables in the solution
     (nil, sys_domains) = domains(&nil);
     for (var, bounds) in sys_domains
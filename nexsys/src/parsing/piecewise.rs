@@ -0,0 +1,148 @@
+use geqslib::shunting::{compile_to_fn_of_hashmap, ContextHashMap, ContextLike};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::{errors::PiecewiseFormatError, parsing::nexsys_regex};
+
+/// A piece of a function's body, compiled so that it can be evaluated for any arguments.
+type Expression = Box<dyn Fn(&HashMap<String, f64>) -> anyhow::Result<f64>>;
+
+/// A single `condition -> value` branch of a piecewise function.
+struct Branch {
+    /// How far the arguments are inside the region where the condition holds (negative when outside)
+    margin: Expression,
+    /// Whether the condition also holds when `margin` is exactly zero (i.e. for `<=` and `>=`)
+    inclusive: bool,
+    value: Expression,
+}
+
+/// A function defined by a `piecewise` declaration.
+struct Piecewise {
+    params: Vec<String>,
+    branches: Vec<Branch>,
+    otherwise: Expression,
+    smoothing: Option<f64>,
+}
+impl Piecewise {
+    /// Evaluates the function. Without smoothing, the value of the first branch whose condition
+    /// holds is returned. With smoothing, each condition instead weights its branch's value against
+    /// the branches after it by a logistic curve of its margin that is `smoothing` wide.
+    fn evaluate(&self, args: &[f64]) -> anyhow::Result<f64> {
+        let args: HashMap<String, f64> = self.params.iter().cloned().zip(args.iter().copied()).collect();
+
+        match self.smoothing {
+            None => {
+                for branch in &self.branches {
+                    let margin = (branch.margin)(&args)?;
+                    if margin > 0.0 || (branch.inclusive && margin == 0.0) {
+                        return (branch.value)(&args)
+                    }
+                }
+                (self.otherwise)(&args)
+            },
+            Some(width) => {
+                let mut value = (self.otherwise)(&args)?;
+                for branch in self.branches.iter().rev() {
+                    let weight = 1.0 / (1.0 + (-(branch.margin)(&args)? / width).exp());
+                    value = weight * (branch.value)(&args)? + (1.0 - weight) * value;
+                }
+                Ok(value)
+            },
+        }
+    }
+}
+
+/// Compiles an expression in the body of a piecewise function, which may use its parameters.
+fn expression(expr: &str, ctx: &ContextHashMap) -> anyhow::Result<Expression> {
+    let func = compile_to_fn_of_hashmap(expr.trim(), ctx)?;
+    Ok(Box::new(func))
+}
+
+/// Splits a branch's condition into the expression for its margin and whether it is inclusive.
+fn condition(cond: &str) -> anyhow::Result<(String, bool)> {
+    if cond.contains("==") || cond.contains("!=") || cond.contains("=<") || cond.contains("=>") {
+        return Err(PiecewiseFormatError::Comparator.into())
+    }
+
+    for (op, inclusive, flip) in [("<=", true, false), (">=", true, true), ("<", false, false), (">", false, true)] {
+        if let Some((lhs, rhs)) = cond.split_once(op) {
+            let margin = match flip {
+                false => format!("({rhs}) - ({lhs})"),
+                true => format!("({lhs}) - ({rhs})"),
+            };
+            return Ok((margin, inclusive))
+        }
+    }
+    Err(PiecewiseFormatError::Comparator.into())
+}
+
+/// Identifies piecewise function declarations in a Nexsys-legal string, e.g.
+/// `piecewise f(x): x < 0 -> 0; x < 1 -> x; else -> 1 end`, removing them and registering
+/// each function in `ctx` so that it can be called from equations.
+///
+/// Branches are separated by `;` and checked in order, and the last branch must be `else`.
+/// Each condition compares two expressions with `<`, `<=`, `>` or `>=`. Declarations may
+/// be given a smoothing width, e.g. `piecewise f(x) smooth 0.01: ...`, which blends
+/// neighbouring branches over about that distance from each breakpoint so that the function
+/// stays differentiable for Newton iterations.
+///
+/// A declaration must fit on one line, and may use constants and previously declared functions.
+pub fn piecewise(text: &str, ctx: &mut ContextHashMap) -> anyhow::Result<String> {
+    lazy_static! {
+        static ref RE: Regex = nexsys_regex(r"(?im)^[ \t]*piecewise +(@V) *\(([^)]*)\)(?: +smooth +(@N))? *:(.*)\bend[ \t]*$");
+    }
+    let mut output = text.to_owned();
+
+    for c in RE.captures_iter(text) {
+        let name = c.get(1).unwrap().as_str();
+        let params: Vec<String> = c.get(2).unwrap().as_str()
+            .split(',')
+            .map(|p| p.trim().to_owned())
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        let smoothing = match c.get(3).map(|w| w.as_str().replace(' ', "").parse::<f64>()) {
+            None => None,
+            Some(Ok(w)) if w > 0.0 => Some(w),
+            Some(_) => return Err(PiecewiseFormatError::Smoothing.into()),
+        };
+
+        // The body may use the function's parameters as well as anything already in the context
+        let mut local = ctx.clone();
+        for param in &params {
+            local.add_var_to_ctx(param, 0.0);
+        }
+
+        let mut branches = vec![];
+        let mut otherwise = None;
+        for branch in c.get(4).unwrap().as_str().split(';').filter(|b| !b.trim().is_empty()) {
+            let (cond, value) = match branch.split_once("->") {
+                Some(parts) if otherwise.is_none() => parts,
+                _ => return Err(PiecewiseFormatError::BranchSyntax.into()),
+            };
+
+            let value = expression(value, &local)?;
+            if cond.trim() == "else" {
+                otherwise = Some(value);
+                continue;
+            }
+
+            let (margin, inclusive) = condition(cond)?;
+            branches.push(Branch { margin: expression(&margin, &local)?, inclusive, value });
+        }
+
+        let otherwise = match otherwise {
+            Some(otherwise) => otherwise,
+            None => return Err(PiecewiseFormatError::MissingElse.into()),
+        };
+
+        let func = Piecewise { params, branches, otherwise, smoothing };
+        let num_args = func.params.len();
+        ctx.add_closure_to_ctx(name, move |args| func.evaluate(args).unwrap_or(f64::NAN), num_args);
+
+        output = output.replace(c.get(0).unwrap().as_str(), "");
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use std::panic::catch_unwind;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::annotations::annotate;
+use crate::comparison::compare_solutions;
+use crate::errors::ServerBindError;
+use crate::parsing::{comments, csv_files, legal_name, namespaces, units};
+use crate::units::alternative_units;
+use crate::session::{restore_session, save_session, Session};
+use crate::solve_with_preprocessors;
+
+/// The margin of error used when a request does not specify a `margin`.
+pub const DEFAULT_MARGIN: f64 = 0.0001;
+
+/// The iteration limit used when a request does not specify a `limit`.
+pub const DEFAULT_LIMIT: usize = 100;
+
+// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SOLVER_ERROR: i64 = -32000;
+const SESSION_ERROR: i64 = -32001;
+
+/// Formats a JSON-RPC error response.
+fn rpc_error(id: &Value, code: i64, message: impl std::fmt::Display) -> Value
+{
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message.to_string() },
+        "id": id,
+    })
+}
+
+/// Solves the system given in the `params` of a `solve` call.
+fn solve(params: &Value) -> Result<Value, (i64, String)>
+{
+    let system = match params.get("system").and_then(Value::as_str)
+    {
+        Some(s) => s.to_owned(),
+        None => return Err((INVALID_PARAMS, "params must contain the system text as 'system'".to_owned())),
+    };
+
+    let margin = match params.get("margin")
+    {
+        None => DEFAULT_MARGIN,
+        Some(m) => m.as_f64()
+            .ok_or((INVALID_PARAMS, "'margin' must be a number".to_owned()))?,
+    };
+
+    let limit = match params.get("limit")
+    {
+        None => DEFAULT_LIMIT,
+        Some(l) => l.as_u64()
+            .ok_or((INVALID_PARAMS, "'limit' must be a non-negative integer".to_owned()))? as usize,
+    };
+
+    // Loading constants would let any client read the server's files
+    if !csv_files(&comments(&system)).is_empty()
+    {
+        return Err((INVALID_PARAMS, "the server does not load files for clients, so constants must be written in the system".to_owned()));
+    }
+
+    // The solver may panic on some malformed systems
+    let res = catch_unwind(|| solve_with_preprocessors(&system, margin, limit));
+
+    match res
+    {
+        Ok(Ok((log, soln))) => Ok(json!({ "log": log, "solution": soln })),
+        Ok(Err(e)) => Err((SOLVER_ERROR, e.to_string())),
+        Err(_) => Err((SOLVER_ERROR, "the solver failed unexpectedly".to_owned())),
+    }
+}
+
+/// Annotates the `system` given in the `params` of an `annotate` call with its `solution`.
+fn annotations(params: &Value) -> Result<Value, (i64, String)>
+{
+    let system = match params.get("system").and_then(Value::as_str)
+    {
+        Some(s) => s,
+        None => return Err((INVALID_PARAMS, "params must contain the system text as 'system'".to_owned())),
+    };
+
+    let solution = match params.get("solution").map(|s| serde_json::from_value(s.clone()))
+    {
+        Some(Ok(s)) => s,
+        _ => return Err((INVALID_PARAMS, "params must contain the solved values as 'solution'".to_owned())),
+    };
+
+    let annotations: Vec<Value> = annotate(system, &solution).into_iter()
+        .map(|a| json!({
+            "line": a.line,
+            "values": a.values.into_iter().map(|(name, val)| (name, json!(val))).collect::<serde_json::Map<_, _>>(),
+            "residual": a.residual,
+        }))
+        .collect();
+
+    Ok(json!({ "annotations": annotations }))
+}
+
+/// Converts the solved value of the `variable` given in the `params` of a `tooltip` call
+/// into familiar alternatives to the unit it is given in the `system`.
+fn tooltip(params: &Value) -> Result<Value, (i64, String)>
+{
+    let (system, variable) = match (params.get("system").and_then(Value::as_str), params.get("variable").and_then(Value::as_str))
+    {
+        (Some(s), Some(v)) => (s, v),
+        _ => return Err((INVALID_PARAMS, "params must contain the system text as 'system' and a 'variable'".to_owned())),
+    };
+
+    let value = match params.get("solution").and_then(|s| s.get(variable)).and_then(Value::as_f64)
+    {
+        Some(v) => v,
+        None => return Err((INVALID_PARAMS, format!("'{variable}' must be given a value in 'solution'"))),
+    };
+
+    let (_, declared) = units(&namespaces(&comments(system)).0);
+    let unit = match declared.get(&legal_name(variable))
+    {
+        Some(u) => u,
+        None => return Err((INVALID_PARAMS, format!("'{variable}' is not given a unit in the system"))),
+    };
+
+    let alternatives: Vec<Value> = match alternative_units(value, unit)
+    {
+        Ok(alternatives) => alternatives.into_iter()
+            .map(|(unit, value)| json!({ "unit": unit, "value": value }))
+            .collect(),
+        Err(e) => return Err((INVALID_PARAMS, e.to_string())),
+    };
+
+    Ok(json!({ "value": value, "unit": unit, "alternatives": alternatives }))
+}
+
+/// Reads one of the two solutions given in the `params` of a `compare` call.
+fn comparison_side(params: &Value, side: &str) -> Result<HashMap<String, f64>, (i64, String)>
+{
+    let invalid = || (INVALID_PARAMS, format!("'{side}' must be given a 'solution' or a 'system'"));
+    let params = params.get(side).ok_or_else(invalid)?;
+
+    // Only inline text is accepted. Reading files here would let any client read the server's files
+    match (params.get("solution"), params.get("system"))
+    {
+        (Some(soln), _) => serde_json::from_value(soln.clone())
+            .map_err(|_| (INVALID_PARAMS, format!("'{side}.solution' must map names to numbers"))),
+        (None, Some(_)) =>
+        {
+            let result = solve(params)?;
+            serde_json::from_value(result["solution"].clone()).map_err(|e| (SOLVER_ERROR, e.to_string()))
+        },
+        (None, None) => Err(invalid()),
+    }
+}
+
+/// Compares the `baseline` and `other` solutions given in the `params` of a `compare` call.
+fn comparison(params: &Value) -> Result<Value, (i64, String)>
+{
+    let tolerance = match params.get("tolerance")
+    {
+        None => DEFAULT_MARGIN,
+        Some(t) => t.as_f64()
+            .ok_or((INVALID_PARAMS, "'tolerance' must be a number".to_owned()))?,
+    };
+
+    let baseline = comparison_side(params, "baseline")?;
+    let other = comparison_side(params, "other")?;
+    let comparison = compare_solutions(&baseline, &other, tolerance);
+
+    let rows: Vec<Value> = comparison.deltas.iter()
+        .map(|d| json!({
+            "name": d.name,
+            "baseline": d.expected,
+            "other": d.actual,
+            "delta": d.delta,
+            "percent_change": d.percent_change(),
+            "changed": !d.passed,
+        }))
+        .collect();
+
+    Ok(json!({ "rows": rows, "changed": comparison.failures().len(), "tolerance": tolerance }))
+}
+
+/// Returns the file in `session_dir` that holds the session of the `client` named in `params`.
+fn session_path(params: &Value, session_dir: &Path) -> Result<PathBuf, (i64, String)>
+{
+    // The name becomes a file name, so it must not be able to reach outside `session_dir`
+    match params.get("client").and_then(Value::as_str)
+    {
+        Some(client) if !client.is_empty()
+            && client.len() <= 64
+            && client.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Ok(session_dir.join(format!("{client}.json")))
+        },
+        _ => Err((INVALID_PARAMS, "params must name the 'client' with up to 64 letters, digits, '-' or '_'".to_owned())),
+    }
+}
+
+/// Saves the editor session given as the `params` of an `autosave` call.
+fn autosave(params: &Value, session_dir: &Path) -> Result<Value, (i64, String)>
+{
+    let path = session_path(params, session_dir)?;
+    let session = Session::from_json(params)
+        .map_err(|e| (INVALID_PARAMS, e.to_string()))?;
+
+    match save_session(&path, &session)
+    {
+        Ok(()) => Ok(json!({ "path": path })),
+        Err(e) => Err((SESSION_ERROR, e.to_string())),
+    }
+}
+
+/// Reads the editor session last saved by an `autosave` call from the same client.
+fn restore(params: &Value, session_dir: &Path) -> Result<Value, (i64, String)>
+{
+    match restore_session(session_path(params, session_dir)?)
+    {
+        Ok(Some(session)) => Ok(session.to_json()),
+        Ok(None) => Ok(Value::Null),
+        Err(e) => Err((SESSION_ERROR, e.to_string())),
+    }
+}
+
+/// Handles a single JSON-RPC 2.0 request body, returning the response body.
+/// This does not touch the network, so it can also be used to embed the
+/// solver in another server or transport.
+///
+/// The `solve` method's params are the `system` text (with the same syntax as 
+/// `solve_with_preprocessors`, except that `load constants from` is refused) and an
+/// optional `margin` and `limit`. Its result holds the solver's `log` and the `solution`.
+/// 
+/// The `annotate` method's params are the `system` text and a `solution` to it. Its
+/// result holds a list of `annotations`, each with a `line` number, the `values` of the
+/// variables first defined on that line, and the `residual` of its equation (see 
+/// `annotations::annotate`), so that an editor can show results beside each line.
+/// 
+/// The `tooltip` method's params are the `system` text, a `solution` to it and the name of
+/// a `variable` that is given a unit in the system (with `unit <unit> for <variable>`). Its
+/// result holds the variable's `value` and `unit`, and a list of `alternatives`, each with
+/// a `unit` and the `value` in that unit (see `units::alternative_units`), so that an editor
+/// can show e.g. `101.3 kPa = 14.7 psi` when hovering over a result.
+/// 
+/// The `autosave` method's params are an editor session (see `session::Session::to_json`)
+/// and the name of the `client` it belongs to, which is saved to `<client>.json` in 
+/// `session_dir`. Editors should call it periodically and before closing, so that unsaved 
+/// edits survive a crash (e.g. during a long solve). The `restore_session` method's params
+/// are the name of the `client`, and it returns the last session saved by that client, or 
+/// `null` if there is none. Client names may only hold letters, digits, `-` and `_`.
+/// 
+/// The `compare` method's params are a `baseline` and an `other` solution and an optional 
+/// `tolerance`. Each solution is given either as a `solution` object or as a `system` (with an 
+/// optional `margin` and `limit`) to solve, so that two solutions or two runs of the same model 
+/// can be compared. The server never reads files for a client, so solution files must be read 
+/// (e.g. with `comparison::read_solution` or `comparison::read_json_solution`) by the caller.
+/// Its result holds a row for each variable in either solution, sorted by name, with the 
+/// variable's `name`, its `baseline` and `other` values, the `delta` between them, the 
+/// `percent_change` from the baseline and whether it has `changed` by more than the tolerance 
+/// (see `comparison::compare_solutions`), and the number of variables that have `changed`.
+///
+/// # Example
+/// ```
+/// use nexsys::server::handle_rpc;
+///
+/// let sessions = std::env::temp_dir().join("nexsys_doc_rpc_sessions");
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "solve",
+///     "params": { "system": "x + y = 9\nx - y = 4", "margin": 1e-9 },
+///     "id": 1
+/// }"#;
+///
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+///
+/// assert_eq!(response["id"], 1);
+/// assert!((response["result"]["solution"]["x"].as_f64().unwrap() - 6.5).abs() < 1E-6);
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "annotate",
+///     "params": { "system": "x + y = 9\nx - y = 4", "solution": { "x": 6.5, "y": 2.5 } },
+///     "id": 2
+/// }"#;
+/// 
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// 
+/// assert_eq!(response["result"]["annotations"][0]["values"]["y"], 2.5);
+/// assert_eq!(response["result"]["annotations"][1]["residual"], 0.0);
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "tooltip",
+///     "params": { "system": "unit kPa for p\np = 101.325", "solution": { "p": 101.325 }, "variable": "p" },
+///     "id": 5
+/// }"#;
+/// 
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// let alternatives = response["result"]["alternatives"].as_array().unwrap();
+/// let psi = alternatives.iter().find(|a| a["unit"] == "psi").unwrap();
+/// 
+/// assert!((psi["value"].as_f64().unwrap() - 14.696).abs() < 0.001);
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "autosave",
+///     "params": { 
+///         "client": "editor-1", 
+///         "documents": { "model.nxs": "x + y = 9" }, "open_tabs": ["model.nxs"], "active_tab": "model.nxs" 
+///     },
+///     "id": 3
+/// }"#;
+/// handle_rpc(request, &sessions);
+/// 
+/// let request = r#"{ "jsonrpc": "2.0", "method": "restore_session", "params": { "client": "editor-1" }, "id": 4 }"#;
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// 
+/// assert_eq!(response["result"]["documents"]["model.nxs"], "x + y = 9");
+///
+/// // Each client only sees its own session, and cannot name a file outside `sessions`
+/// let request = r#"{ "jsonrpc": "2.0", "method": "restore_session", "params": { "client": "editor-2" }, "id": 8 }"#;
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// 
+/// assert_eq!(response["result"], serde_json::Value::Null);
+///
+/// let request = r#"{ "jsonrpc": "2.0", "method": "restore_session", "params": { "client": "../editor-1" }, "id": 9 }"#;
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// 
+/// assert_eq!(response["error"]["code"], -32602);
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "compare",
+///     "params": { 
+///         "baseline": { "system": "x + y = 9\nx - y = 4", "margin": 1e-9 }, 
+///         "other": { "system": "x + y = 9\nx - y = 5", "margin": 1e-9 } 
+///     },
+///     "id": 6
+/// }"#;
+/// 
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// let rows = response["result"]["rows"].as_array().unwrap();
+/// let x = rows.iter().find(|r| r["name"] == "x").unwrap();
+/// 
+/// assert_eq!(response["result"]["changed"], 2);
+/// assert!((x["delta"].as_f64().unwrap() - 0.5).abs() < 1E-6);
+/// assert!((x["percent_change"].as_f64().unwrap() - 100.0 / 13.0).abs() < 1E-6);
+///
+/// // Clients cannot ask the server to read its files
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "solve",
+///     "params": { "system": "load constants from \"/etc/passwd\"\nx = 1" },
+///     "id": 10
+/// }"#;
+/// 
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// assert_eq!(response["error"]["code"], -32602);
+///
+/// let request = r#"{
+///     "jsonrpc": "2.0",
+///     "method": "compare",
+///     "params": { "baseline": { "file": "/etc/passwd" }, "other": { "solution": {} } },
+///     "id": 7
+/// }"#;
+/// 
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc(request, &sessions)).unwrap();
+/// assert_eq!(response["error"]["code"], -32602);
+/// assert!(!response.to_string().contains("root"));
+///
+/// let response: serde_json::Value = serde_json::from_str(&handle_rpc("{", &sessions)).unwrap();
+/// assert_eq!(response["error"]["code"], -32700);
+/// ```
+pub fn handle_rpc(body: &str, session_dir: &Path) -> String
+{
+    let request: Value = match serde_json::from_str(body)
+    {
+        Ok(v) => v,
+        Err(e) => return rpc_error(&Value::Null, PARSE_ERROR, e).to_string(),
+    };
+
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+    if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0")
+    {
+        return rpc_error(&id, INVALID_REQUEST, "only JSON-RPC 2.0 requests are supported").to_string();
+    }
+
+    let response = match request.get("method").and_then(Value::as_str)
+    {
+        Some("solve") => match solve(request.get("params").unwrap_or(&Value::Null))
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some("annotate") => match annotations(request.get("params").unwrap_or(&Value::Null))
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some("tooltip") => match tooltip(request.get("params").unwrap_or(&Value::Null))
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some("autosave") => match autosave(request.get("params").unwrap_or(&Value::Null), session_dir)
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some("compare") => match comparison(request.get("params").unwrap_or(&Value::Null))
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some("restore_session") => match restore(request.get("params").unwrap_or(&Value::Null), session_dir)
+        {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err((code, message)) => rpc_error(&id, code, message),
+        },
+        Some(method) => rpc_error(&id, METHOD_NOT_FOUND, format!("no such method '{method}'")),
+        None => rpc_error(&id, INVALID_REQUEST, "request must name a 'method'"),
+    };
+
+    response.to_string()
+}
+
+/// Reads a request from the network, solves it, and sends the response.
+fn respond_to(mut request: Request, session_dir: &Path)
+{
+    let mut body = String::new();
+    let (status, json) = if request.method().as_str() != "POST"
+    {
+        (405, rpc_error(&Value::Null, INVALID_REQUEST, "requests must be sent with POST").to_string())
+    }
+    else
+    {
+        match request.as_reader().read_to_string(&mut body)
+        {
+            Ok(_) => (200, handle_rpc(&body, session_dir)),
+            Err(e) => (400, rpc_error(&Value::Null, PARSE_ERROR, e).to_string()),
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .expect("content type header is always valid");
+
+    let response = Response::from_string(json)
+        .with_status_code(status)
+        .with_header(content_type);
+
+    // The client may have hung up while the system was solving. There is nobody to tell.
+    let _ = request.respond(response);
+}
+
+/// Starts serving JSON-RPC requests over HTTP on `addr` (e.g. `"0.0.0.0:8080"`),
+/// blocking the current thread. See `handle_rpc` for the methods that are available.
+/// Editor sessions are saved in `session_dir`.
+///
+/// Each request is solved on its own thread, so long-running systems do not
+/// hold up other clients.
+pub fn serve(addr: &str, session_dir: impl Into<PathBuf>) -> anyhow::Result<()>
+{
+    let server = match Server::http(addr)
+    {
+        Ok(server) => server,
+        Err(_) => return Err(ServerBindError.into()),
+    };
+
+    let session_dir = session_dir.into();
+    for request in server.incoming_requests()
+    {
+        let session_dir = session_dir.clone();
+        thread::spawn(move || respond_to(request, &session_dir));
+    }
+
+    Ok(())
+}
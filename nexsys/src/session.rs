@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fs::{create_dir_all, read_to_string, rename, write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::errors::SessionFormatError;
+
+/// The editor state that is needed to pick up where a user left off after a crash:
+/// the unsaved text of every open document and the tabs they were open in.
+///
+/// # Fields:
+/// - `documents` - the unsaved text of each open document, keyed by its path
+/// - `open_tabs` - the paths of the open documents, in the order their tabs are shown
+/// - `active_tab` - the path of the document in the focused tab, if any
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session
+{
+    pub documents: HashMap<String, String>,
+    pub open_tabs: Vec<String>,
+    pub active_tab: Option<String>,
+}
+impl Session
+{
+    /// Reads a session from its JSON representation (see `Session::to_json`).
+    pub fn from_json(value: &Value) -> anyhow::Result<Session>
+    {
+        let documents = match value.get("documents").map(|d| serde_json::from_value(d.clone()))
+        {
+            Some(Ok(d)) => d,
+            _ => return Err(SessionFormatError::Documents.into()),
+        };
+
+        let open_tabs = match value.get("open_tabs").map(|t| serde_json::from_value(t.clone()))
+        {
+            Some(Ok(t)) => t,
+            _ => return Err(SessionFormatError::OpenTabs.into()),
+        };
+
+        let active_tab = match value.get("active_tab")
+        {
+            None | Some(Value::Null) => None,
+            Some(Value::String(tab)) => Some(tab.to_owned()),
+            Some(_) => return Err(SessionFormatError::ActiveTab.into()),
+        };
+
+        Ok(Session { documents, open_tabs, active_tab })
+    }
+
+    /// Returns the JSON representation of the session, which is an object
+    /// with the same fields as the `Session` struct.
+    pub fn to_json(&self) -> Value
+    {
+        json!({
+            "documents": self.documents,
+            "open_tabs": self.open_tabs,
+            "active_tab": self.active_tab,
+        })
+    }
+}
+
+/// Returns the file that sessions are saved to when no other file is given,
+/// which is kept in the system's temporary directory.
+pub fn default_session_path() -> PathBuf
+{
+    std::env::temp_dir().join("nexsys").join("session.json")
+}
+
+/// Returns the directory that `nxc serve` saves each client's session in,
+/// which is kept in the system's temporary directory.
+pub fn default_session_dir() -> PathBuf
+{
+    std::env::temp_dir().join("nexsys").join("sessions")
+}
+
+/// Saves a session to `path`, creating its directory if needed. The session is
+/// written to a temporary file that then replaces `path`, so a crash partway
+/// through saving leaves the previously saved session intact.
+///
+/// # Example
+/// ```
+/// use nexsys::session::{restore_session, save_session, Session};
+///
+/// let path = std::env::temp_dir().join("nexsys_doc_session").join("session.json");
+///
+/// let mut session = Session::default();
+/// session.documents.insert("model.nxs".to_string(), "x + y = 9\nx - y = 4".to_string());
+/// session.open_tabs.push("model.nxs".to_string());
+/// session.active_tab = Some("model.nxs".to_string());
+///
+/// save_session(&path, &session).unwrap();
+/// assert_eq!(restore_session(&path).unwrap(), Some(session));
+/// ```
+pub fn save_session(path: impl AsRef<Path>, session: &Session) -> anyhow::Result<()>
+{
+    let path = path.as_ref();
+    if let Some(dir) = path.parent()
+    {
+        create_dir_all(dir)?;
+    }
+
+    let partial = path.with_extension("partial");
+    write(&partial, session.to_json().to_string())?;
+    rename(&partial, path)?;
+
+    Ok(())
+}
+
+/// Reads the session last saved to `path`, or returns `None` if no session has been saved.
+/// An `Err` is returned if the file exists but does not hold a session.
+pub fn restore_session(path: impl AsRef<Path>) -> anyhow::Result<Option<Session>>
+{
+    let path = path.as_ref();
+    if !path.exists()
+    {
+        return Ok(None);
+    }
+
+    let value: Value = serde_json::from_str(&read_to_string(path)?)?;
+    Ok(Some(Session::from_json(&value)?))
+}
@@ -2,7 +2,7 @@ use lazy_static::lazy_static;
 use serde_json::{Value, from_str};
 use std::collections::HashMap;
 
-use crate::errors::UnitConversionError;
+use crate::errors::{UnitConversionError, UnitFormatError};
 
 /// Handles generating additional units for various combinations of other units
 fn generate_num_denom_units(mut data:HashMap<String, HashMap<String, f64>>, qty: &str, num: &str, denom: &str) -> HashMap<String, HashMap<String, f64>> {
@@ -137,6 +137,73 @@ pub fn convert(fro: &str, to: &str) -> anyhow::Result<f64> {
     Ok(cf[0])
 }
 
+/// Units that measure an absolute quantity (i.e. an absolute temperature or 
+/// pressure), for which a negative value is never physical.
+const ABSOLUTE_UNITS: [&str; 12] = [
+    "K", "R", 
+    "Pa", "kPa", "MPa", "GPa", "bar", "atm", "psia", "torr", "mmHg", "inHg"
+];
+
+/// Returns the lowest physical value of a quantity measured in the given `unit`, 
+/// or `None` if it may take any value (e.g. a temperature in `C` or a gauge 
+/// pressure in `psig`). Fails if the unit is not in `unit_data()`.
+pub fn lower_bound(unit: &str) -> anyhow::Result<Option<f64>> {
+    lazy_static! {
+        static ref UD: HashMap<String, HashMap<String, f64>> = unit_data();
+    }
+
+    if !UD.values().any(|qty| qty.contains_key(unit)) {
+        return Err(UnitFormatError.into())
+    }
+
+    if ABSOLUTE_UNITS.contains(&unit) {
+        Ok(Some(0.0))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Familiar units of each quantity, which are offered as alternatives to one another. 
+/// Absolute temperatures are left out since they cannot be converted by a factor alone.
+const COMMON_UNITS: [&str; 61] = [
+    "m", "mm", "cm", "km", "in", "ft", "yd", "mi",
+    "kg", "g", "lbm", "slug",
+    "s", "min", "hr", "day",
+    "m/s", "km/h", "ft/s", "mph", "knot",
+    "sqm", "sqcm", "sqft", "sqin",
+    "m^3", "L", "ml", "gal", "ft^3",
+    "m^3/s", "L/s", "gpm", "cfm",
+    "N", "kN", "lbf", "kgf",
+    "Pa", "kPa", "MPa", "bar", "atm", "psi",
+    "J", "kJ", "MJ", "kWh", "Btu", "kcal",
+    "W", "kW", "MW", "hp", "Btu/hr",
+    "DELTAK", "DELTAC", "DELTAF", "DELTAR",
+    "rad", "deg",
+];
+
+/// Converts a `value` measured in `unit` into each of the familiar units that measure
+/// the same quantity (other than `unit` itself), e.g. so that an editor can show 
+/// `101.3 kPa` as `14.69 psi` when hovering over it. Fails if the unit is not in `unit_data()`.
+/// 
+/// # Example
+/// ```
+/// use nexsys::units::alternative_units;
+/// 
+/// let alternatives = alternative_units(101.325, "kPa").unwrap();
+/// let (_, psi) = alternatives.iter().find(|(unit, _)| unit == "psi").unwrap();
+/// 
+/// assert!((psi - 14.696).abs() < 0.001);
+/// assert!(alternatives.iter().all(|(unit, _)| unit != "kPa" && unit != "m"));
+/// ```
+pub fn alternative_units(value: f64, unit: &str) -> anyhow::Result<Vec<(String, f64)>> {
+    lower_bound(unit)?;
+
+    Ok(COMMON_UNITS.iter()
+        .filter(|&&alt| alt != unit)
+        .filter_map(|&alt| convert(unit, alt).ok().map(|cf| (alt.to_owned(), value * cf)))
+        .collect())
+}
+
 /// Returns the data contained in consts.json as a `HashMap`, allowing for easier access to this data in Rust.
 pub fn const_data() -> HashMap<String, f64> {
 
@@ -1,4 +1,4 @@
-use nexsys::{parsing::{conditionals, conversions}, units::unit_data};
+use nexsys::{parsing::{aliases, compile, conditionals, conflicts, conversions, csv_constants, namespaces, piecewise, reserved_names, solver_settings, units}, units::unit_data};
 
 #[test]
 fn test_conditional_parser() {
@@ -57,6 +57,36 @@ end
     assert!(res.contains("if(a,4.0,b,b-a-(1),if(a,1.0,b,b-(a),a-b-(1))) = 0"));
 }
 
+#[test]
+fn test_smooth_conditional_formatting() {
+    let my_code = 
+r#"
+smooth if a < b:
+    b - a = 1
+else:
+    smooth(2 * k) if a >= b:
+        b = a
+    else:
+        a - b = 1
+    end
+end
+"#;
+
+    let res = conditionals(my_code).unwrap();
+    assert!(res.contains("smooth_if(a,4.0,b,b-a-(1),smooth_if(a,3.0,b,b-(a),a-b-(1),2*k),10) = 0"));
+
+    match conditionals("smooth if a == b:\n    a = 1\nelse:\n    b = 1\nend") {
+        Err(e) => assert_eq!(e.to_string(), "smooth if statements can only compare with <, >, <= or >="),
+        _ => panic!()
+    }
+
+    // A saturating curve, solved near its knee where a hard switch would be discontinuous
+    let system = "y = 3 - x\nsmooth(100) if x < 2:\n    y = x\nelse:\n    y = 2\nend";
+    let (_log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+    assert!((soln["x"] - 1.5).abs() < 1E-6);
+    assert!((soln["y"] - 1.5).abs() < 1E-6);
+}
+
 #[test]
 fn test_conversion_parser() {
     let my_sys = "[in->cm]\n[in/s->cm/s]\n[gpm->m^3/s]";
@@ -77,4 +107,326 @@ fn test_unit_data() {
     // println!("{}",_ud["VOLUMETRIC FLOW"]["m^3/s"]);
     // println!("{}",_ud["VOLUMETRIC FLOW"]["gpm"]);
     // println!("{}",_ud["VELOCITY"]["mph"]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_solver_settings_parser() {
+    let my_code = 
+r#"
+settings margin = 1e-6
+settings iterations = 500
+settings method = broyden
+x = 3
+"#;
+
+    let (res, settings) = solver_settings(my_code);
+
+    assert!(!res.contains("settings"));
+    assert_eq!(settings["margin"], "1e-6");
+    assert_eq!(settings["iterations"], "500");
+    assert_eq!(settings["method"], "broyden");
+
+    // Settings are only read from the start of a line
+    let (res, settings) = solver_settings("mysettings margin = 1\nx = 2 * settings margin = 3");
+    assert!(settings.is_empty());
+    assert_eq!(res, "mysettings margin = 1\nx = 2 * settings margin = 3");
+}
+
+#[test]
+fn test_namespace_and_alias_parsers() {
+    let my_code = 
+r#"
+alias thot = hx1.t_1
+thot - hx1.ua = 1.5
+"#;
+
+    let (res, namespaced) = namespaces(my_code);
+    assert_eq!(namespaced["hx1__t_1"], "hx1.t_1");
+    assert_eq!(namespaced["hx1__ua"], "hx1.ua");
+
+    let (res, aliased) = aliases(&res).unwrap();
+    assert_eq!(aliased["thot"], "hx1__t_1");
+    assert!(res.contains("hx1__t_1 - hx1__ua = 1.5"));
+
+    // Written names cannot collide with the names that namespaced variables are given
+    assert!(reserved_names(my_code).is_ok());
+    assert!(reserved_names("hx1__ua = 2").is_err());
+    assert!(nexsys::solve_with_preprocessors("hx1.ua = 2\nhx1__ua + 1 = x", 0.0001, 100).is_err());
+}
+
+#[test]
+fn test_circular_alias_parser() {
+    let my_code = "alias a = b\nalias b = a\na = 1";
+
+    assert!(aliases(my_code).is_err());
+}
+
+#[test]
+fn test_csv_constants_parser() {
+    let path = std::env::temp_dir().join("nexsys_test_csv_constants.csv");
+    std::fs::write(&path, "\u{feff}flow, \"pump.head\", eff\r\n2.5, 10, 0.8\r\n3.0, 12, 1.5E-1\r\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let res = csv_constants(&format!("load constants from \"{path}\"\nx = flow")).unwrap();
+    assert!(res.contains("const flow = 2.5"));
+    assert!(res.contains("const pump.head = 10"));
+    assert!(res.contains("x = flow"));
+
+    let res = csv_constants(&format!("load constants from \"{path}\" row 2")).unwrap();
+    assert!(res.contains("const eff = 0.15"));
+
+    assert!(csv_constants(&format!("load constants from \"{path}\" row 3")).is_err());
+    assert!(csv_constants("load constants from \"no_such_file.csv\"").is_err());
+
+    let (_log, soln) = nexsys::solve_with_preprocessors(
+        &format!("load constants from \"{path}\"\nx = flow * pump.head"), 0.0001, 100
+    ).unwrap();
+    assert!((soln["x"] - 25.0).abs() < 0.001);
+
+    // Loaded values that conflict with a constant are reported at the directive's line
+    let err = nexsys::solve_with_preprocessors(
+        &format!("const flow = 3\nload constants from \"{path}\"\nx = flow"), 0.0001, 100
+    ).unwrap_err();
+    assert!(err.to_string().contains("line 2"));
+    assert!(err.to_string().contains("line 1"));
+}
+
+#[test]
+fn test_unit_bounds_parser() {
+    let (res, found) = units("unit K for t_hot\nunit psig for p\nt_hot = 300");
+    assert_eq!(found["t_hot"], "K");
+    assert_eq!(found["p"], "psig");
+    assert!(!res.contains("unit"));
+
+    let mut ctx = geqslib::shunting::new_context();
+    let mut declared = std::collections::HashMap::new();
+    compile("unit K for t\nunit kPa for p\nunit C for c\nkeep p on [-10, 10]", &mut ctx, &mut declared).unwrap();
+    assert_eq!(declared["t"], [1.0, 0.0, f64::INFINITY]);
+    assert_eq!(declared["p"], [1.0, -10.0, 10.0]);
+    assert!(!declared.contains_key("c"));
+
+    assert!(compile("unit parsecs for x", &mut ctx, &mut declared).is_err());
+}
+
+#[test]
+fn test_conflict_detection() {
+    assert!(conflicts("x + y = 9\nx - y = 4\nconst a = 2\nconst a = 2").is_ok());
+
+    // Equations in different branches of a conditional may repeat
+    assert!(conflicts("x = 1\nif x > 0:\n    y = 1\nelse:\n    y = 1\nend").is_ok());
+
+    let err = conflicts("x + y = 9\n\n2*y + 2*x = 18").unwrap_err();
+    assert!(err.to_string().contains("line 3"));
+    assert!(err.to_string().contains("line 1"));
+
+    assert!(conflicts("x^2 = y\ny = x ^ 2").is_err());
+    assert!(conflicts("const a = 2\nx = a\nconst a = 3").unwrap_err().to_string().contains("line 3"));
+
+    assert!(nexsys::solve_with_preprocessors("x + y = 9\nx - y = 4\ny - x = -4", 0.0001, 100).is_err());
+}
+
+#[test]
+fn test_piecewise_parser() {
+    use geqslib::shunting::eval_str_with_context;
+
+    let mut ctx = geqslib::shunting::new_context();
+    let res = piecewise("piecewise clamp(x): x < 0 -> 0; x <= 1 -> x; else -> 1 end\ny = clamp(z)", &mut ctx).unwrap();
+    assert_eq!(res.trim(), "y = clamp(z)");
+
+    assert_eq!(eval_str_with_context("clamp(-2)", &ctx).unwrap(), 0.0);
+    assert_eq!(eval_str_with_context("clamp(0.25)", &ctx).unwrap(), 0.25);
+    assert_eq!(eval_str_with_context("clamp(1)", &ctx).unwrap(), 1.0);
+    assert_eq!(eval_str_with_context("clamp(3)", &ctx).unwrap(), 1.0);
+
+    // Smoothing blends the branches on either side of a breakpoint
+    piecewise("piecewise step(x, a) smooth 0.1: x > a -> 1; else -> 0 end", &mut ctx).unwrap();
+    assert!((eval_str_with_context("step(2, 2)", &ctx).unwrap() - 0.5).abs() < 1E-12);
+    assert!(eval_str_with_context("step(3, 2)", &ctx).unwrap() > 0.99);
+    assert!(eval_str_with_context("step(1, 2)", &ctx).unwrap() < 0.01);
+
+    assert!(piecewise("piecewise f(x): x < 0 -> 0 end", &mut ctx).is_err());
+    assert!(piecewise("piecewise f(x): x == 0 -> 0; else -> 1 end", &mut ctx).is_err());
+    assert!(piecewise("piecewise f(x): else -> 1; x < 0 -> 0 end", &mut ctx).is_err());
+    assert!(piecewise("piecewise f(x) smooth 0: x < 0 -> 0; else -> 1 end", &mut ctx).is_err());
+
+    let system = "piecewise ramp(x) smooth 0.001: x < 1 -> 0; else -> x - 1 end\ny = ramp(x)\ny = 2";
+    let (_log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+    assert!((soln["x"] - 3.0).abs() < 1E-6);
+}
+
+#[test]
+fn test_automatic_initial_guesses() {
+    let system = "keep x on [0, 10]\nx^2 = 16\n1000 * y = 3000\nguess 2 for z\nz^3 = 27\nw = x + y";
+    let (log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+
+    // `w` appears only alongside other unknowns, so it keeps the default guess
+    assert_eq!(log[0], "Initial guesses: x = 5 (domain midpoint), y = 3000 (constants)");
+    assert!((soln["x"] - 4.0).abs() < 1E-6);
+    assert!((soln["y"] - 3.0).abs() < 1E-6);
+    assert!((soln["z"] - 3.0).abs() < 1E-6);
+}
+
+#[test]
+fn test_fixed_point_solver_method() {
+    let system = "settings method = fixed_point\nsettings relaxation = 0.8\nx = cos(y)\ny = x / 2";
+    let (_log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 200).unwrap();
+
+    assert!((soln["x"] - soln["y"].cos()).abs() < 1E-6);
+    assert!((soln["y"] - soln["x"] / 2.0).abs() < 1E-6);
+}
+
+#[test]
+fn test_engineering_suffix_literals() {
+    let system = "settings suffixes = on\nr = 3.3k\nc = 10u\ntol = 5%\ntau = r*c*(1 + tol)\nm = 2M+1m";
+    let (_log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+
+    assert!((soln["r"] / 3300.0 - 1.0).abs() < 1E-6);
+    assert!((soln["c"] / 1E-5 - 1.0).abs() < 1E-6);
+    assert!((soln["tol"] / 0.05 - 1.0).abs() < 1E-6);
+    assert!((soln["tau"] / 0.03465 - 1.0).abs() < 1E-6);
+    assert!((soln["m"] / 2000000.001 - 1.0).abs() < 1E-6);
+
+    // Without the setting, suffixes are implicitly multiplied variables
+    let (_log, soln) = nexsys::solve_with_preprocessors("r = 3.3k\nk = 2", 1E-9, 100).unwrap();
+    assert!((soln["r"] / 6.6 - 1.0).abs() < 1E-6);
+    assert!(nexsys::solve_with_preprocessors("settings suffixes = maybe\nr = 3.3k", 1E-9, 100).is_err());
+}
+
+#[test]
+fn test_localized_numbers() {
+    let system = "settings locale = de\nsettings margin = 0,000001\nconst rho = 1.000,5\nkeep x on [0, 10]\nx * 2 = 3,5\ny = x + rho / 1.000";
+    let (_log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+
+    assert!((soln["x"] - 1.75).abs() < 1E-6);
+    assert!((soln["y"] - 2.7505).abs() < 1E-6);
+
+    // Spreadsheets group digits with no-break spaces in french
+    let (_log, soln) = nexsys::solve_with_preprocessors("settings locale = fr-FR\nx = 1\u{a0}234,5", 1E-9, 100).unwrap();
+    assert!((soln["x"] - 1234.5).abs() < 1E-9);
+
+    // Numbers that the locale would not write are reported rather than misread
+    let err = nexsys::solve_with_preprocessors("settings locale = de\n\nx = 3.14", 1E-9, 100).unwrap_err();
+    assert!(err.to_string().contains("'3.14' on line 3"));
+    assert!(nexsys::solve_with_preprocessors("settings locale = de\nx = 12.34,5", 1E-9, 100).is_err());
+    assert!(nexsys::solve_with_preprocessors("settings locale = xx\nx = 3", 1E-9, 100).is_err());
+}
+
+#[test]
+fn test_solution_comparison() {
+    use nexsys::comparison::{compare_solutions, read_solution};
+
+    let baseline = read_solution("Solution:\n+=======+\nx = 6.5\nhx.ua = 2.5\n\nProcedure:\n+========+\nVar: \"x\" \nEquation: x = 1");
+    assert_eq!(baseline.len(), 2);
+
+    let (_log, soln) = nexsys::solve_with_preprocessors("x + hx.ua = 9\nx - hx.ua = 4", 1E-9, 100).unwrap();
+    let soln = soln.into_iter().filter(|(name, _)| baseline.contains_key(name)).collect();
+    assert!(compare_solutions(&baseline, &soln, 1E-6).passed());
+
+    let mut drifted = soln.clone();
+    drifted.insert("hx.ua".to_string(), 2.6);
+    drifted.insert("extra".to_string(), 1.0);
+    let comparison = compare_solutions(&baseline, &drifted, 1E-6);
+
+    let failed: Vec<&str> = comparison.failures().iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(failed, vec!["extra", "hx.ua"]);
+    assert!(comparison.to_string().ends_with("FAIL: 2 of 3 variables differ by more than 0.000001"));
+}
+
+#[test]
+fn test_json_solution_comparison() {
+    use nexsys::comparison::{compare_solutions, read_json_solution};
+
+    let before = read_json_solution(r#"{ "nodes": { "0": [0.0], "vcc": [5.0] }, "elements": { "R1": [2.5] } }"#).unwrap();
+    let after = read_json_solution(r#"{ "nodes": { "0": [0.0], "vcc": [5.5] }, "elements": { "R1": [2.5] } }"#).unwrap();
+    let comparison = compare_solutions(&before, &after, 1E-6);
+
+    let names: Vec<&str> = comparison.deltas.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["elements.R1", "nodes.0", "nodes.vcc"]);
+
+    // Percentages are relative to the baseline, which must not be zero
+    assert_eq!(comparison.deltas[0].percent_change(), Some(0.0));
+    assert_eq!(comparison.deltas[1].percent_change(), None);
+    assert!((comparison.deltas[2].percent_change().unwrap() - 10.0).abs() < 1E-9);
+    assert!(read_json_solution("nodes: 1").is_err());
+}
+
+#[test]
+fn test_bracketed_fallback_on_declared_domain() {
+    // Newton's method finds x = 2 from its default guess, which is outside of the domain
+    let system = "keep x on [-10, 0]\nx^2 = 4";
+    let (log, soln) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+
+    assert!((soln["x"] + 2.0).abs() < 1E-6);
+    assert!(log.iter().any(|step| step.contains("Method: Brent")));
+}
+
+#[test]
+fn test_compiled_system_export() {
+    use geqslib::shunting::new_context;
+    use geqslib::system::SolverMethod;
+    use nexsys::compile_with_preprocessors;
+
+    let system = "settings method = broyden\nguess 4 for hx.a\nhx.a * b = 12\nhx.a + b = 7";
+    let mut ctx = new_context();
+    let compiled = compile_with_preprocessors(system, &mut ctx, 1E-9, 100).unwrap();
+
+    assert_eq!(compiled.get_equations().len(), 2);
+    assert_eq!(compiled.get_method(), SolverMethod::Broyden);
+    assert_eq!(compiled.get_settings(), (1E-9, 100));
+    assert_eq!(compiled.get_domains()["hx__a"][0], 4.0);
+    assert_eq!(compiled.get_written_name("hx__a"), "hx.a");
+    assert_eq!(compiled.get_written_name("b"), "b");
+
+    // The exported system solves to the same values that nexsys finds
+    let mut exported = compiled.to_system_builder(&ctx).unwrap().build_system().unwrap();
+    for (var, [guess, min, max]) in compiled.get_domains() {
+        exported.specify_variable(var, *guess, *min, *max);
+    }
+    let soln = exported.solve_with_method(compiled.get_method(), 1E-9, 100).unwrap();
+    let (_log, expected) = nexsys::solve_with_preprocessors(system, 1E-9, 100).unwrap();
+    assert!((soln["hx__a"] - expected["hx.a"]).abs() < 1E-6);
+    assert!((soln["b"] - expected["b"]).abs() < 1E-6);
+
+    assert!(compile_with_preprocessors("const k = 1", &mut new_context(), 1E-9, 100).unwrap().to_system_builder(&new_context()).is_err());
+}
+
+#[test]
+fn test_single_unknown_equations_past_the_first() {
+    use std::collections::HashMap;
+    use geqslib::shunting::new_context;
+
+    // Only the second equation has a single unknown, but it should still be solved on its own first
+    let (log, soln) = nexsys::basic_solve("x + y = 3\ny = 2", &mut new_context(), &mut HashMap::new(), 1E-9, 100).unwrap();
+
+    assert_eq!(log, vec!["Var: \"y\" \nEquation: y = 2", "Var: \"x\" \nEquation: x + y = 3"]);
+    assert!((soln["x"] - 1.0).abs() < 1E-6);
+}
+
+#[test]
+fn test_solved_unknowns_are_known_to_later_equations() {
+    use std::collections::HashMap;
+    use geqslib::shunting::{new_context, Token};
+
+    let mut ctx = new_context();
+    let (_log, soln) = nexsys::basic_solve("y = 2\nx + y = 3\nz = x * y", &mut ctx, &mut HashMap::new(), 1E-9, 100).unwrap();
+
+    assert!((soln["z"] - 2.0).abs() < 1E-6);
+    assert!(matches!(ctx["y"], Token::Num(_)));
+}
+
+#[test]
+fn test_cached_solutions_follow_piecewise_bodies() {
+    use nexsys::cache::solve_with_cache;
+
+    let cache_dir = std::env::temp_dir().join("nexsys_piecewise_cache_test");
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let (_log, first) = solve_with_cache("piecewise f(x): x < 0 -> 0; else -> 2 * x end\ny = f(3)", 1E-9, 100, &cache_dir).unwrap();
+    assert!((first["y"] - 6.0).abs() < 1E-6);
+
+    // Only the body of the function changed, which must not be answered from the cache
+    let (_log, second) = solve_with_cache("piecewise f(x): x < 0 -> 0; else -> 3 * x end\ny = f(3)", 1E-9, 100, &cache_dir).unwrap();
+    assert!((second["y"] - 9.0).abs() < 1E-6);
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}